@@ -1,13 +1,19 @@
 use clap::Parser;
-use env_logger::Env;
-use jgnes_core::TimingMode;
+use jgnes_core::{EmulatorConfig, RamInit, RamInitPattern, TimingMode};
 use jgnes_native_driver::{
-    InputConfig, JgnesDynamicConfig, JgnesNativeConfig, JgnesSharedConfig, NativeRenderer,
+    CaptureFormat, DataPaths, FrameDiffConfig, FrameDiffMode, FramePacing, HeadlessConfig,
+    HeadlessTransport, HiDpiScaling, InputConfig, JgnesDynamicConfig, JgnesNativeConfig,
+    JgnesSharedConfig, LowPowerMode, NativeRenderer, WindowGeometryMap,
 };
 use jgnes_proc_macros::{EnumDisplay, EnumFromStr};
+use jgnes_renderer::GpuAdapterSelector;
+use jgnes_renderer::colors::Palette;
 use jgnes_renderer::config::{
-    AspectRatio, GpuFilterMode, Overscan, RenderScale, Scanlines, Shader, VSyncMode, WgpuBackend,
+    AspectRatio, ColorBlindFilter, CrtGeometry, GpuFilterMode, Overscan, OverscanPreset,
+    RenderScale, Scanlines, Shader, VSyncMode, WgpuBackend,
 };
+use std::fs;
+use std::path::PathBuf;
 use std::time::Duration;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, EnumDisplay, EnumFromStr)]
@@ -43,6 +49,110 @@ struct CliArgs {
     #[arg(short = 'f', long)]
     nes_file_path: String,
 
+    /// Path to an IPS / BPS patch to apply to the ROM; if not set, a patch file next to the ROM
+    /// with the same name will be applied if one exists
+    #[arg(short = 'p', long)]
+    patch_file_path: Option<String>,
+
+    /// Additional ROM files to switch between at runtime using the next/previous game hotkeys
+    #[arg(long, num_args = 1..)]
+    playlist: Vec<String>,
+
+    /// Store save files and save states in a jgnes directory next to the executable instead of
+    /// next to the ROM; also enabled by a portable.txt file next to the executable
+    #[arg(long, default_value_t)]
+    portable: bool,
+
+    /// Listen for JSON remote control commands on this localhost TCP port; clients must first send
+    /// the token from the jgnes-remote-control-<port>.token file that is written on startup
+    #[arg(long)]
+    remote_control_port: Option<u16>,
+
+    /// Reload the ROM whenever the ROM file changes on disk
+    #[arg(long, default_value_t)]
+    watch_rom_file: bool,
+
+    /// Do not warn when a game runs in a different timing mode than its header specifies
+    #[arg(long = "no-timing-mode-warning", default_value_t = true, action = clap::ArgAction::SetFalse)]
+    warn_on_timing_mode_mismatch: bool,
+
+    /// Write all audio output to this WAV file, starting at launch
+    #[arg(long)]
+    wav_capture_path: Option<PathBuf>,
+
+    /// Log per-frame present times, audio queue depth, and tick counts to this CSV file for the
+    /// first 10 seconds after launch, for diagnosing stutter
+    #[arg(long)]
+    frame_timing_log_path: Option<PathBuf>,
+
+    /// Restart the speedrun timer whenever the console is reset
+    #[arg(long, default_value_t)]
+    speedrun_timer_auto_start: bool,
+
+    /// Save each speedrun timer run to this JSON file when it ends
+    #[arg(long)]
+    last_run_path: Option<PathBuf>,
+
+    /// Send speedrun timer start/split/reset events to LiveSplit Server on this localhost TCP port
+    /// (LiveSplit's default is 16834)
+    #[arg(long)]
+    livesplit_port: Option<u16>,
+
+    /// Run without a window or audio device, streaming raw frames and audio to stdout and reading
+    /// joypad input from stdin
+    #[arg(long, default_value_t)]
+    headless: bool,
+
+    /// In headless mode, stream over a connection to this localhost TCP port instead of
+    /// stdin/stdout
+    #[arg(long, requires = "headless")]
+    headless_port: Option<u16>,
+
+    /// In headless mode, exit after this many frames
+    #[arg(long, requires = "headless")]
+    headless_frame_limit: Option<u64>,
+
+    /// In headless mode, throttle to the NES's native frame rate instead of running as fast as
+    /// output is consumed
+    #[arg(long, default_value_t, requires = "headless")]
+    headless_realtime: bool,
+
+    /// Run without a window and write a hash of each frame to this file, for later comparison
+    /// with --frame-diff-check
+    #[arg(long, group = "frame_diff")]
+    frame_diff_record: Option<PathBuf>,
+
+    /// Run without a window and report the first frame whose hash differs from the hashes in this
+    /// file, writing an image of that frame next to the ROM file
+    #[arg(long, group = "frame_diff")]
+    frame_diff_check: Option<PathBuf>,
+
+    /// Run without a window, side by side with a second instance that uses default emulator
+    /// settings, and report the first frame where their output differs
+    #[arg(long, default_value_t, group = "frame_diff")]
+    frame_diff_compare: bool,
+
+    /// Number of frames to run in frame diff mode
+    #[arg(long, default_value_t = 600)]
+    frame_diff_frames: u64,
+
+    /// Joypad inputs to replay in frame diff mode: 2 bytes per frame, one each for P1 and P2, with
+    /// bits 0-7 for A, B, Select, Start, Up, Down, Left, Right
+    #[arg(long)]
+    frame_diff_input: Option<String>,
+
+    /// Write a Chrome trace of emulator and renderer spans to this file, which can be viewed in
+    /// Perfetto or chrome://tracing
+    #[cfg(feature = "tracing")]
+    #[arg(long)]
+    chrome_trace: Option<PathBuf>,
+
+    /// Most verbose span level to include in the Chrome trace; "trace" includes a span for every
+    /// CPU and APU tick, which is very slow
+    #[cfg(feature = "tracing")]
+    #[arg(long, default_value_t = tracing_subscriber::filter::LevelFilter::DEBUG)]
+    chrome_trace_level: tracing_subscriber::filter::LevelFilter,
+
     /// Window width in pixels
     #[arg(short = 'w', long, default_value_t = 878)]
     window_width: u32,
@@ -55,10 +165,19 @@ struct CliArgs {
     #[arg(long, default_value_t)]
     renderer: NativeRenderer,
 
+    /// Scaling on HiDPI displays (Native / Logical)
+    #[arg(long, default_value_t)]
+    hidpi_scaling: HiDpiScaling,
+
     /// Wgpu backend (Auto / Vulkan / Direct3d12 / OpenGl)
     #[arg(long, default_value_t)]
     wgpu_backend: WgpuBackend,
 
+    /// GPU adapter for the wgpu renderer to use, by index or by part of its name; if not set or if
+    /// no adapter matches, wgpu chooses one
+    #[arg(long)]
+    gpu_adapter: Option<GpuAdapterSelector>,
+
     /// GPU filter type (NearestNeighbor / LinearInterpolation)
     #[arg(long, default_value_t = GpuFilterMode::LinearInterpolation)]
     gpu_filter_mode: GpuFilterMode,
@@ -75,10 +194,46 @@ struct CliArgs {
     #[arg(long, default_value_t = 16)]
     blur_radius: u32,
 
+    /// Path to a TOML multi-pass shader preset file; overrides shader type if set (only applicable
+    /// to Wgpu renderer)
+    #[arg(long)]
+    shader_preset: Option<String>,
+
+    /// Path to a .pal palette file containing either 64 or 512 colors; uses the built-in palette
+    /// if not set
+    #[arg(long)]
+    palette_file: Option<String>,
+
     /// Scanlines setting (None / Black / Dim)
     #[arg(long, default_value_t)]
     scanlines: Scanlines,
 
+    /// Scanline intensity (0.0 to 1.0, only applicable to Wgpu renderer)
+    #[arg(long, default_value_t = 1.0)]
+    scanline_intensity: f64,
+
+    /// CRT screen curvature strength (0.0 to 1.0, only applicable to Wgpu renderer)
+    #[arg(long, default_value_t = 0.0)]
+    crt_curvature: f64,
+
+    /// CRT corner rounding strength (0.0 to 1.0, only applicable to Wgpu renderer)
+    #[arg(long, default_value_t = 0.0)]
+    crt_corner_rounding: f64,
+
+    /// CRT vignette strength (0.0 to 1.0, only applicable to Wgpu renderer)
+    #[arg(long, default_value_t = 0.0)]
+    crt_vignette: f64,
+
+    /// Color-blindness assist filter (None / Protanopia / Deuteranopia / Tritanopia, only
+    /// applicable to Wgpu renderer)
+    #[arg(long, default_value_t)]
+    color_blind_filter: ColorBlindFilter,
+
+    /// Blend each frame with the previous frame at this strength (0.0 to 1.0, only applicable to
+    /// Wgpu renderer); smooths out flicker-based transparency effects
+    #[arg(long, default_value_t = 0.0)]
+    frame_blend: f64,
+
     /// Internal resolution prescale factor (1 to 16, only applicable to Wgpu renderer)
     #[arg(long, default_value_t = 3)]
     render_scale: u32,
@@ -91,6 +246,19 @@ struct CliArgs {
     #[arg(long, default_value_t)]
     forced_timing_mode: OptionalTimingMode,
 
+    /// Force a mapper number instead of relying on cartridge header; useful for ROMs with corrupted
+    /// headers
+    #[arg(long)]
+    forced_mapper_number: Option<u16>,
+
+    /// Power-on contents of CPU and PPU RAM (AllZeros / AllOnes / Alternating / Random)
+    #[arg(long, default_value_t)]
+    ram_init_pattern: RamInitPattern,
+
+    /// Seed for the Random RAM init pattern; if not set, a new seed is chosen at every power-on
+    #[arg(long)]
+    ram_init_seed: Option<u64>,
+
     /// Enable forced integer scaling for height
     #[arg(long, default_value_t)]
     forced_integer_height_scaling: bool,
@@ -100,6 +268,11 @@ struct CliArgs {
     #[arg(long, default_value_t)]
     remove_sprite_limit: bool,
 
+    /// With the sprite limit in place, alternate which sprites are dropped from busy scanlines
+    /// every frame so that they flicker instead of disappearing
+    #[arg(long, default_value_t)]
+    reduce_sprite_flicker: bool,
+
     /// Emulate PAL black border
     #[arg(long, default_value_t)]
     pal_black_border: bool,
@@ -126,14 +299,64 @@ struct CliArgs {
     #[arg(long, default_value_t)]
     silence_ultrasonic_triangle_output: bool,
 
+    /// Mix APU channels using a linear approximation instead of the NES's non-linear DAC formula
+    #[arg(long, default_value_t)]
+    linear_apu_mixing: bool,
+
+    /// Fade the triangle wave channel in and out when it is silenced or resumes to avoid clicks
+    #[arg(long, default_value_t)]
+    ramp_triangle_silencing: bool,
+
+    /// Smooth out jumps in the DMC output level from direct $4011 writes to reduce clicking
+    #[arg(long, default_value_t)]
+    dmc_declick_filter: bool,
+
+    /// Log the scanline and dot every time the PPU sets the sprite 0 hit flag
+    #[arg(long, default_value_t)]
+    log_sprite_0_hits: bool,
+
     /// Launch in fullscreen
     #[arg(long = "fullscreen", default_value_t)]
     launch_fullscreen: bool,
 
-    /// VSync mode (Enabled / Disabled / Fast)
+    /// Keep the emulator window above other windows
+    #[arg(long, default_value_t)]
+    always_on_top: bool,
+
+    /// Hide the emulator window's title bar and border
+    #[arg(long, default_value_t)]
+    borderless: bool,
+
+    /// Open a second window that shows the same output without the on-screen display, e.g. as a
+    /// clean feed for capture
+    #[arg(long, default_value_t)]
+    mirror_window: bool,
+
+    /// SDL video driver to use instead of the default, e.g. kmsdrm to run full-screen without X11
+    /// or Wayland
+    #[arg(long)]
+    sdl_video_driver: Option<String>,
+
+    /// Disable all shader effects (shaders, scanlines, CRT geometry, frame blending), for slow GPUs;
+    /// overrides the individual settings. Only affects the wgpu renderer, which is the only renderer
+    /// that applies these effects
+    #[arg(long, default_value_t)]
+    low_spec: bool,
+
+    /// VSync mode (Enabled / Disabled / Fast / Adaptive)
     #[arg(long, default_value_t)]
     vsync_mode: VSyncMode,
 
+    /// Frame pacing (Audio / Vrr); Vrr presents frames immediately and paces emulation by a timer,
+    /// for variable refresh rate displays
+    #[arg(long, default_value_t)]
+    frame_pacing: FramePacing,
+
+    /// Low power mode (Off / OnBattery / Always); prefers the low-power GPU, always uses VSync
+    /// unless frame pacing is Vrr, and polls less often while syncing to audio
+    #[arg(long, default_value_t)]
+    low_power_mode: LowPowerMode,
+
     /// Left overscan in pixels
     #[arg(long, default_value_t)]
     overscan_left: u8,
@@ -146,6 +369,38 @@ struct CliArgs {
     #[arg(long, default_value_t = 10)]
     rewind_buffer_len_secs: u64,
 
+    /// Number of frames between rewind snapshots
+    #[arg(long, default_value_t = 3)]
+    rewind_interval_frames: u8,
+
+    /// Maximum memory used by the rewind buffer in megabytes, or 0 for no limit
+    #[arg(long, default_value_t = 512)]
+    rewind_memory_limit_mb: u32,
+
+    /// Length of clips saved with the save clip hotkey, in seconds (limited by the rewind buffer length)
+    #[arg(long, default_value_t = 30)]
+    clip_len_secs: u64,
+
+    /// Length of GIF/APNG captures saved with the save capture hotkey, in seconds
+    #[arg(long, default_value_t = 5)]
+    capture_len_secs: u64,
+
+    /// Capture file format (Gif / Apng)
+    #[arg(long, default_value_t)]
+    capture_format: CaptureFormat,
+
+    /// Require pressing the quit hotkey twice to quit
+    #[arg(long, default_value_t)]
+    confirm_quit: bool,
+
+    /// Require pressing the save state hotkey twice to overwrite an existing save state
+    #[arg(long, default_value_t)]
+    confirm_overwrite_state: bool,
+
+    /// Require pressing the hard reset hotkey twice to hard reset
+    #[arg(long, default_value_t)]
+    confirm_hard_reset: bool,
+
     /// Right overscan in pixels
     #[arg(long, default_value_t)]
     overscan_right: u8,
@@ -157,6 +412,11 @@ struct CliArgs {
     /// Bottom overscan in pixels
     #[arg(long, default_value_t)]
     overscan_bottom: u8,
+
+    /// Overscan preset (None / TvSafe8px / FullNtscCrop); overrides the individual overscan
+    /// settings if set
+    #[arg(long)]
+    overscan_preset: Option<OverscanPreset>,
 }
 
 impl CliArgs {
@@ -164,6 +424,10 @@ impl CliArgs {
         RenderScale::try_from(self.render_scale).expect("render_scale arg is invalid")
     }
 
+    fn ram_init(&self) -> RamInit {
+        RamInit { pattern: self.ram_init_pattern, seed: self.ram_init_seed }
+    }
+
     fn blur_stdev(&self) -> f64 {
         assert!(
             !self.blur_stdev.is_nan() && !self.blur_stdev.is_sign_negative(),
@@ -172,6 +436,22 @@ impl CliArgs {
         self.blur_stdev
     }
 
+    fn scanline_intensity(&self) -> f64 {
+        assert!(
+            (0.0..=1.0).contains(&self.scanline_intensity),
+            "Scanline intensity must be between 0.0 and 1.0"
+        );
+        self.scanline_intensity
+    }
+
+    fn frame_blend_strength(&self) -> f64 {
+        assert!(
+            (0.0..=1.0).contains(&self.frame_blend),
+            "Frame blend strength must be between 0.0 and 1.0"
+        );
+        self.frame_blend
+    }
+
     fn shader(&self) -> Shader {
         match self.shader_type {
             ShaderType::None => Shader::None,
@@ -184,7 +464,25 @@ impl CliArgs {
         }
     }
 
+    fn crt_geometry(&self) -> CrtGeometry {
+        let crt_geometry = CrtGeometry {
+            curvature: self.crt_curvature,
+            corner_rounding: self.crt_corner_rounding,
+            vignette: self.crt_vignette,
+        };
+        assert_eq!(
+            crt_geometry,
+            crt_geometry.clamped(),
+            "CRT strengths must be between 0.0 and 1.0"
+        );
+        crt_geometry
+    }
+
     fn overscan(&self) -> Overscan {
+        if let Some(overscan_preset) = self.overscan_preset {
+            return overscan_preset.to_overscan();
+        }
+
         Overscan {
             top: self.overscan_top,
             left: self.overscan_left,
@@ -194,39 +492,179 @@ impl CliArgs {
     }
 }
 
+#[cfg(feature = "tracing")]
+fn init_chrome_trace(
+    path: &std::path::Path,
+    level: tracing_subscriber::filter::LevelFilter,
+) -> anyhow::Result<tracing_chrome::FlushGuard> {
+    use tracing_subscriber::Layer;
+    use tracing_subscriber::layer::SubscriberExt;
+    use tracing_subscriber::util::SubscriberInitExt;
+
+    let (chrome_layer, guard) = tracing_chrome::ChromeLayerBuilder::new().file(path).build();
+    tracing_subscriber::registry().with(chrome_layer.with_filter(level)).try_init()?;
+
+    Ok(guard)
+}
+
 fn main() -> anyhow::Result<()> {
-    env_logger::Builder::from_env(Env::default().default_filter_or("info,wgpu_core::device=warn"))
-        .init();
+    jgnes_native_driver::init_logger("info,wgpu_core::device=warn")?;
 
     let args = CliArgs::parse();
 
-    let (shared_config, _) = JgnesSharedConfig::new(JgnesDynamicConfig {
+    // Trace output is flushed when the guard is dropped at the end of main
+    #[cfg(feature = "tracing")]
+    let _chrome_trace_guard = match &args.chrome_trace {
+        Some(chrome_trace) => Some(init_chrome_trace(chrome_trace, args.chrome_trace_level)?),
+        None => None,
+    };
+
+    let palette = match &args.palette_file {
+        Some(palette_file) => Palette::from_pal_bytes(&fs::read(palette_file)?)?,
+        None => Palette::default(),
+    };
+
+    let emulator_config = EmulatorConfig {
+        remove_sprite_limit: args.remove_sprite_limit,
+        reduce_sprite_flicker: args.reduce_sprite_flicker,
+        pal_black_border: args.pal_black_border,
+        silence_ultrasonic_triangle_output: args.silence_ultrasonic_triangle_output,
+        linear_apu_mixing: args.linear_apu_mixing,
+        ramp_triangle_silencing: args.ramp_triangle_silencing,
+        dmc_declick_filter: args.dmc_declick_filter,
+        log_sprite_0_hits: args.log_sprite_0_hits,
+        ..EmulatorConfig::default()
+    };
+
+    let frame_diff_mode = if let Some(hash_file) = &args.frame_diff_record {
+        Some(FrameDiffMode::Record { hash_file: hash_file.clone() })
+    } else if let Some(hash_file) = &args.frame_diff_check {
+        Some(FrameDiffMode::Check { hash_file: hash_file.clone() })
+    } else if args.frame_diff_compare {
+        Some(FrameDiffMode::Compare { other_config: EmulatorConfig::default() })
+    } else {
+        None
+    };
+    if let Some(mode) = frame_diff_mode {
+        return jgnes_native_driver::run_frame_diff(&FrameDiffConfig {
+            nes_file_path: args.nes_file_path.clone(),
+            patch_file_path: args.patch_file_path.clone(),
+            forced_timing_mode: args.forced_timing_mode.to_timing_mode(),
+            forced_mapper_number: args.forced_mapper_number,
+            ram_init: args.ram_init(),
+            emulator_config,
+            input_file: args.frame_diff_input.clone(),
+            frames: args.frame_diff_frames,
+            mode,
+        });
+    }
+
+    if args.headless {
+        return jgnes_native_driver::run_headless(&HeadlessConfig {
+            nes_file_path: args.nes_file_path.clone(),
+            patch_file_path: args.patch_file_path.clone(),
+            forced_timing_mode: args.forced_timing_mode.to_timing_mode(),
+            forced_mapper_number: args.forced_mapper_number,
+            ram_init: args.ram_init(),
+            emulator_config,
+            palette,
+            overscan: args.overscan(),
+            transport: args
+                .headless_port
+                .map_or(HeadlessTransport::Stdio, |port| HeadlessTransport::Tcp { port }),
+            frame_limit: args.headless_frame_limit,
+            realtime: args.headless_realtime,
+        });
+    }
+
+    let mut dynamic_config = JgnesDynamicConfig {
         gpu_filter_mode: args.gpu_filter_mode,
         shader: args.shader(),
+        shader_preset: args
+            .shader_preset
+            .as_ref()
+            .map(jgnes_native_driver::load_shader_preset)
+            .transpose()?,
         scanlines: args.scanlines,
+        scanline_intensity: args.scanline_intensity(),
+        frame_blend_strength: args.frame_blend_strength(),
+        crt_geometry: args.crt_geometry(),
+        color_blind_filter: args.color_blind_filter,
+        palette,
         aspect_ratio: args.aspect_ratio,
         overscan: args.overscan(),
         forced_integer_height_scaling: args.forced_integer_height_scaling,
         vsync_mode: args.vsync_mode,
+        frame_pacing: args.frame_pacing,
+        low_power_mode: args.low_power_mode,
         remove_sprite_limit: args.remove_sprite_limit,
+        reduce_sprite_flicker: args.reduce_sprite_flicker,
         pal_black_border: args.pal_black_border,
         sync_to_audio: args.sync_to_audio,
         internal_audio_buffer_size: args.internal_audio_buffer_size,
         audio_sync_threshold: args.audio_sync_threshold,
         audio_refresh_rate_adjustment: args.audio_refresh_rate_adjustment,
         silence_ultrasonic_triangle_output: args.silence_ultrasonic_triangle_output,
+        linear_apu_mixing: args.linear_apu_mixing,
+        ramp_triangle_silencing: args.ramp_triangle_silencing,
+        dmc_declick_filter: args.dmc_declick_filter,
+        log_sprite_0_hits: args.log_sprite_0_hits,
         fast_forward_multiplier: args.fast_forward_multiplier,
         rewind_buffer_len: Duration::from_secs(args.rewind_buffer_len_secs),
+        rewind_interval_frames: args.rewind_interval_frames,
+        rewind_memory_limit_mb: args.rewind_memory_limit_mb,
+        clip_len: Duration::from_secs(args.clip_len_secs),
+        capture_len: Duration::from_secs(args.capture_len_secs),
+        capture_format: args.capture_format,
+        confirm_quit: args.confirm_quit,
+        confirm_overwrite_state: args.confirm_overwrite_state,
+        confirm_hard_reset: args.confirm_hard_reset,
         input_config: InputConfig::default(),
-    });
+    };
+    if args.low_spec {
+        dynamic_config.apply_low_spec_preset();
+
+        let kmsdrm = args
+            .sdl_video_driver
+            .as_deref()
+            .is_some_and(|video_driver| video_driver.eq_ignore_ascii_case("kmsdrm"));
+        if args.renderer == NativeRenderer::Sdl2 || kmsdrm {
+            log::warn!(
+                "--low-spec has no effect with the SDL2 renderer, which does not apply shader effects"
+            );
+        }
+    }
+    let (shared_config, _) = JgnesSharedConfig::new(dynamic_config);
+    let ram_init = args.ram_init();
+    let data_paths = DataPaths::resolve(args.portable)?;
     let config = JgnesNativeConfig {
         nes_file_path: args.nes_file_path,
         forced_timing_mode: args.forced_timing_mode.to_timing_mode(),
+        forced_mapper_number: args.forced_mapper_number,
+        patch_file_path: args.patch_file_path,
+        ram_init,
+        playlist: args.playlist,
+        save_dir: data_paths.save_dir(),
+        remote_control_port: args.remote_control_port,
+        watch_rom_file: args.watch_rom_file,
+        warn_on_timing_mode_mismatch: args.warn_on_timing_mode_mismatch,
+        wav_capture_path: args.wav_capture_path,
+        frame_timing_log_path: args.frame_timing_log_path,
+        speedrun_timer_auto_start: args.speedrun_timer_auto_start,
+        last_run_path: args.last_run_path,
+        livesplit_port: args.livesplit_port,
         window_width: args.window_width,
         window_height: args.window_height,
+        saved_window_geometry: WindowGeometryMap::new(),
         renderer: args.renderer,
+        hidpi_scaling: args.hidpi_scaling,
         wgpu_backend: args.wgpu_backend,
+        gpu_adapter: args.gpu_adapter,
         launch_fullscreen: args.launch_fullscreen,
+        always_on_top: args.always_on_top,
+        borderless: args.borderless,
+        mirror_window: args.mirror_window,
+        sdl_video_driver: args.sdl_video_driver,
         shared_config,
     };
 
@@ -0,0 +1,248 @@
+//! Headless emulation benchmarks.
+//!
+//! These use small hand-assembled NROM programs rather than commercial ROMs so that they can run
+//! anywhere without any external files.
+
+use criterion::{BatchSize, Criterion, criterion_group, criterion_main};
+use jgnes_core::{
+    AudioPlayer, ColorEmphasis, EmulationError, Emulator, EmulatorConfig, EmulatorCreateArgs,
+    FrameBuffer, InputPoller, JoypadState, RamInit, Renderer, SaveWriter, TickEffect, TimingMode,
+};
+use std::convert::Infallible;
+use std::hint::black_box;
+
+const PRG_ROM_LEN: usize = 32 * 1024;
+const CHR_ROM_LEN: usize = 8 * 1024;
+
+// Tight loop of arithmetic and zero page stores with rendering left disabled
+const CPU_LOOP_PROGRAM: &[u8] = &[
+    0x78, // SEI
+    0xD8, // CLD
+    0xA2, 0x00, // LDX #$00
+    // loop:
+    0xE8, // INX
+    0x69, 0x01, // ADC #$01
+    0x95, 0x00, // STA $00,X
+    0x4C, 0x04, 0x80, // JMP loop
+];
+
+// Fills palette RAM, the first nametable, and OAM with varied data, enables background and sprite
+// rendering, then idles
+const RENDERING_PROGRAM: &[u8] = &[
+    0x78, // SEI
+    0xD8, // CLD
+    0xA2, 0xFF, // LDX #$FF
+    0x9A, // TXS
+    // Wait for the PPU to warm up
+    0x2C, 0x02, 0x20, // BIT $2002
+    0x10, 0xFB, // BPL -5
+    0x2C, 0x02, 0x20, // BIT $2002
+    0x10, 0xFB, // BPL -5
+    // Palette RAM
+    0xA9, 0x3F, // LDA #$3F
+    0x8D, 0x06, 0x20, // STA $2006
+    0xA9, 0x00, // LDA #$00
+    0x8D, 0x06, 0x20, // STA $2006
+    0xA2, 0x00, // LDX #$00
+    0x8A, // TXA
+    0x8D, 0x07, 0x20, // STA $2007
+    0xE8, // INX
+    0xE0, 0x20, // CPX #$20
+    0xD0, 0xF7, // BNE -9
+    // Nametable and attribute table
+    0xA9, 0x20, // LDA #$20
+    0x8D, 0x06, 0x20, // STA $2006
+    0xA9, 0x00, // LDA #$00
+    0x8D, 0x06, 0x20, // STA $2006
+    0xA0, 0x04, // LDY #$04
+    0xA2, 0x00, // LDX #$00
+    0x8A, // TXA
+    0x8D, 0x07, 0x20, // STA $2007
+    0xE8, // INX
+    0xD0, 0xF9, // BNE -7
+    0x88, // DEY
+    0xD0, 0xF6, // BNE -10
+    // OAM via DMA from $0200
+    0xA2, 0x00, // LDX #$00
+    0x8A, // TXA
+    0x9D, 0x00, 0x02, // STA $0200,X
+    0xE8, // INX
+    0xD0, 0xF9, // BNE -7
+    0xA9, 0x02, // LDA #$02
+    0x8D, 0x14, 0x40, // STA $4014
+    // Reset scroll and enable rendering
+    0xA9, 0x00, // LDA #$00
+    0x8D, 0x05, 0x20, // STA $2005
+    0x8D, 0x05, 0x20, // STA $2005
+    0x8D, 0x00, 0x20, // STA $2000
+    0xA9, 0x1E, // LDA #$1E
+    0x8D, 0x01, 0x20, // STA $2001
+    // forever:
+    0x4C, 0x5A, 0x80, // JMP forever
+];
+
+fn nrom_image(program: &[u8]) -> Vec<u8> {
+    let mut rom = vec![b'N', b'E', b'S', 0x1A, 2, 1, 0x00, 0x00];
+    rom.resize(16, 0);
+
+    let mut prg_rom = vec![0xEA; PRG_ROM_LEN];
+    prg_rom[..program.len()].copy_from_slice(program);
+    // NMI, RESET, and IRQ vectors all point to the start of the program
+    for vector in prg_rom[PRG_ROM_LEN - 6..].chunks_exact_mut(2) {
+        vector.copy_from_slice(&[0x00, 0x80]);
+    }
+    rom.extend(prg_rom);
+
+    rom.extend((0..CHR_ROM_LEN).map(|i| (i * 7) as u8));
+
+    rom
+}
+
+struct NullRenderer;
+
+impl Renderer for NullRenderer {
+    type Err = Infallible;
+
+    fn render_frame(
+        &mut self,
+        frame_buffer: &FrameBuffer,
+        _color_emphasis: ColorEmphasis,
+    ) -> Result<(), Self::Err> {
+        black_box(frame_buffer);
+        Ok(())
+    }
+
+    fn set_timing_mode(&mut self, _timing_mode: TimingMode) -> Result<(), Self::Err> {
+        Ok(())
+    }
+}
+
+struct NullAudioPlayer;
+
+impl AudioPlayer for NullAudioPlayer {
+    type Err = Infallible;
+
+    fn push_sample(&mut self, sample: f64) -> Result<(), Self::Err> {
+        black_box(sample);
+        Ok(())
+    }
+
+    fn set_timing_mode(&mut self, _timing_mode: TimingMode) {}
+}
+
+struct NullInputPoller;
+
+impl InputPoller for NullInputPoller {
+    fn poll_p1_input(&self) -> JoypadState {
+        JoypadState::default()
+    }
+
+    fn poll_p2_input(&self) -> JoypadState {
+        JoypadState::default()
+    }
+}
+
+struct NullSaveWriter;
+
+impl SaveWriter for NullSaveWriter {
+    type Err = Infallible;
+
+    fn persist_sram(&mut self, _sram: &[u8]) -> Result<(), Self::Err> {
+        Ok(())
+    }
+}
+
+type BenchEmulator = Emulator<NullRenderer, NullAudioPlayer, NullInputPoller, NullSaveWriter>;
+
+fn create_emulator(program: &[u8]) -> BenchEmulator {
+    Emulator::create(EmulatorCreateArgs {
+        rom_bytes: nrom_image(program),
+        sav_bytes: None,
+        forced_timing_mode: None,
+        forced_mapper_number: None,
+        ram_init: RamInit::default(),
+        renderer: NullRenderer,
+        audio_player: NullAudioPlayer,
+        input_poller: NullInputPoller,
+        save_writer: NullSaveWriter,
+    })
+    .expect("benchmark ROM should always be valid")
+}
+
+fn run_frame(emulator: &mut BenchEmulator, config: &EmulatorConfig) {
+    loop {
+        match emulator.tick(config) {
+            Ok(TickEffect::None) => {}
+            Ok(TickEffect::FrameRendered) => return,
+            Err(err) => match err.error {
+                EmulationError::Render(err)
+                | EmulationError::Audio(err)
+                | EmulationError::Save(err) => match err {},
+            },
+        }
+    }
+}
+
+// Create an emulator and run it long enough for the program to finish initializing
+fn warmed_up_emulator(program: &[u8], config: &EmulatorConfig) -> BenchEmulator {
+    let mut emulator = create_emulator(program);
+    for _ in 0..10 {
+        run_frame(&mut emulator, config);
+    }
+    emulator
+}
+
+fn frame_benchmarks(c: &mut Criterion) {
+    let config = EmulatorConfig::default();
+
+    let mut group = c.benchmark_group("frame");
+
+    let mut emulator = warmed_up_emulator(CPU_LOOP_PROGRAM, &config);
+    group.bench_function("cpu_loop", |b| b.iter(|| run_frame(&mut emulator, &config)));
+
+    let mut emulator = warmed_up_emulator(RENDERING_PROGRAM, &config);
+    group.bench_function("rendering", |b| b.iter(|| run_frame(&mut emulator, &config)));
+
+    let sprite_limit_config = EmulatorConfig { remove_sprite_limit: true, ..config };
+    let mut emulator = warmed_up_emulator(RENDERING_PROGRAM, &sprite_limit_config);
+    group.bench_function("rendering_no_sprite_limit", |b| {
+        b.iter(|| run_frame(&mut emulator, &sprite_limit_config));
+    });
+
+    group.finish();
+}
+
+fn save_state_benchmarks(c: &mut Criterion) {
+    let config = EmulatorConfig::default();
+    let emulator = warmed_up_emulator(RENDERING_PROGRAM, &config);
+
+    let mut group = c.benchmark_group("save_state");
+
+    group.bench_function("serialize", |b| {
+        b.iter(|| {
+            let mut bytes = Vec::new();
+            emulator.save_state(&mut bytes).unwrap();
+            bytes
+        });
+    });
+
+    let mut state_bytes = Vec::new();
+    emulator.save_state(&mut state_bytes).unwrap();
+    group.bench_function("deserialize", |b| {
+        b.iter_batched(
+            || create_emulator(RENDERING_PROGRAM),
+            |mut emulator| {
+                emulator.load_state(state_bytes.as_slice()).unwrap();
+                emulator
+            },
+            BatchSize::LargeInput,
+        );
+    });
+
+    group.bench_function("snapshot", |b| b.iter(|| emulator.snapshot_state()));
+
+    group.finish();
+}
+
+criterion_group!(benches, frame_benchmarks, save_state_benchmarks);
+criterion_main!(benches);
@@ -1,16 +1,21 @@
 use crate::apu::ApuState;
-use crate::bus::cartridge::CartridgeFileError;
-use crate::bus::{Bus, PpuBus, TimingMode, cartridge};
+use crate::bus::cartridge::{CartridgeInfo, Mapper, RomLoadError};
+use crate::bus::{Bus, PpuBus, RamInit, TimingMode, cartridge};
 use crate::cpu::{CpuRegisters, CpuState};
+use crate::events::{RegisterWriteEvent, Sprite0HitEvent};
 use crate::input::JoypadState;
-use crate::ppu::{FrameBuffer, PpuState};
+use crate::ppu::{FrameBuffer, PpuState, ScrollPosition};
+use crate::profiling::ScanlineSpan;
+use crate::rng::CoreRng;
 use crate::serialize::SaveStateError;
 use crate::{apu, cpu, ppu, serialize};
+use std::borrow::Cow;
 use std::cell::RefCell;
+use std::convert::Infallible;
 use std::error::Error;
 use std::fmt::{Debug, Display, Formatter};
-use std::io;
 use std::rc::Rc;
+use std::{io, iter, mem};
 use thiserror::Error;
 
 // The number of master clock ticks to run in one `Emulator::tick` call
@@ -19,6 +24,10 @@ const PAL_MASTER_CLOCK_TICKS: u32 = 80;
 const PAL_CPU_DIVIDER: u32 = 16;
 const PAL_PPU_DIVIDER: u32 = 5;
 
+/// The color emphasis bits from PPUMASK at the time a frame was rendered.
+///
+/// On PAL consoles the red and green bits are swapped in PPUMASK; this struct always stores the
+/// actual color being emphasized.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub struct ColorEmphasis {
     pub red: bool,
@@ -43,7 +52,9 @@ impl Display for ColorEmphasis {
     }
 }
 
+/// Frontend-provided output for completed frames.
 pub trait Renderer {
+    /// The error type returned when rendering fails.
     type Err;
 
     /// Render a completed frame. This will be called once per frame, immediately after the NES PPU
@@ -83,6 +94,14 @@ pub trait Renderer {
     /// This method can return an error if it is unable to initialize some inner state, and the
     /// error will be propagated.
     fn set_timing_mode(&mut self, timing_mode: TimingMode) -> Result<(), Self::Err>;
+
+    /// Notify the renderer that the output window has been resized. Dimensions are in physical
+    /// pixels.
+    ///
+    /// This method will never be called by the emulator itself; frontends should call it whenever
+    /// the window size changes. The default implementation does nothing, which is appropriate for
+    /// renderers whose output surface automatically tracks the window size.
+    fn handle_window_resize(&mut self, _width: u32, _height: u32) {}
 }
 
 impl<R: Renderer> Renderer for Rc<RefCell<R>> {
@@ -99,9 +118,15 @@ impl<R: Renderer> Renderer for Rc<RefCell<R>> {
     fn set_timing_mode(&mut self, timing_mode: TimingMode) -> Result<(), Self::Err> {
         self.borrow_mut().set_timing_mode(timing_mode)
     }
+
+    fn handle_window_resize(&mut self, width: u32, height: u32) {
+        self.borrow_mut().handle_window_resize(width, height);
+    }
 }
 
+/// Frontend-provided output for audio samples.
 pub trait AudioPlayer {
+    /// The error type returned when audio playback fails.
     type Err;
 
     /// Process an audio sample.
@@ -137,6 +162,8 @@ impl<A: AudioPlayer> AudioPlayer for Rc<RefCell<A>> {
     }
 }
 
+/// Frontend-provided source of controller input. The emulator polls input whenever the game
+/// strobes the controllers, which is usually once per frame.
 pub trait InputPoller {
     /// Retrieve the current Player 1 input state.
     fn poll_p1_input(&self) -> JoypadState;
@@ -168,20 +195,41 @@ impl<I: InputPoller> InputPoller for RefCell<I> {
     }
 }
 
+/// Frontend-provided storage for battery-backed cartridge RAM.
 pub trait SaveWriter {
+    /// The error type returned when persisting save data fails.
     type Err;
 
     /// Optionally persist the contents of non-volatile PRG RAM, which generally contains save data.
     ///
-    /// This method will only be called when running games that have battery-backed PRG RAM.
-    /// Additionally, it will only be called when the contents of PRG RAM have changed since the
-    /// last time this method was called.
+    /// For boards with battery-backed CHR RAM, the contents of CHR RAM are appended after PRG RAM.
+    /// The same format is accepted as `sav_bytes` when creating an emulator, as are saves that
+    /// contain only PRG RAM.
+    ///
+    /// This method will only be called when running games that have battery-backed RAM.
+    /// Additionally, it will only be called when the contents of that RAM have changed since the
+    /// last time this method was called, or when the previous call returned an error.
     ///
     /// # Errors
     ///
     /// This method can return an error if it is unable to persist the data to whatever it is
     /// writing to, and the error will be propagated.
     fn persist_sram(&mut self, sram: &[u8]) -> Result<(), Self::Err>;
+
+    /// Report a failure from an earlier [`Self::persist_sram`] call that was only detected after
+    /// that call returned, e.g. because the writer persists data on a background thread.
+    ///
+    /// This method is called at the end of every frame in which [`Self::persist_sram`] was not
+    /// called. The default implementation never returns an error.
+    ///
+    /// # Errors
+    ///
+    /// This method can return the error from a failed background write. The emulator does not
+    /// call [`Self::persist_sram`] again because of this error, so the writer is responsible for
+    /// retrying the write.
+    fn poll_error(&mut self) -> Result<(), Self::Err> {
+        Ok(())
+    }
 }
 
 impl<S: SaveWriter> SaveWriter for Rc<RefCell<S>> {
@@ -190,12 +238,20 @@ impl<S: SaveWriter> SaveWriter for Rc<RefCell<S>> {
     fn persist_sram(&mut self, sram: &[u8]) -> Result<(), Self::Err> {
         self.borrow_mut().persist_sram(sram)
     }
+
+    fn poll_error(&mut self) -> Result<(), Self::Err> {
+        self.borrow_mut().poll_error()
+    }
 }
 
+/// An error returned by one of the frontend implementations while the emulator was running.
 #[derive(Debug)]
 pub enum EmulationError<RenderError, AudioError, SaveError> {
+    /// Returned by [`Renderer::render_frame`]
     Render(RenderError),
+    /// Returned by [`AudioPlayer::push_sample`]
     Audio(AudioError),
+    /// Returned by [`SaveWriter::persist_sram`]
     Save(SaveError),
 }
 
@@ -219,57 +275,212 @@ impl<R: Error + 'static, A: Error + 'static, S: Error + 'static> Error for Emula
     }
 }
 
+/// Whether an error leaves the emulator in a usable state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorSeverity {
+    /// The emulator can keep running; at worst a frame, some audio samples, or an SRAM write was
+    /// lost, and SRAM writes will be retried on the next frame
+    Recoverable,
+    /// The emulator should be stopped
+    Fatal,
+}
+
+/// Classification of renderer, audio, and save errors into recoverable and fatal errors.
+///
+/// Frontends should implement this for their `Renderer::Err`, `AudioPlayer::Err`, and
+/// `SaveWriter::Err` types so that run loops can decide whether to keep running after an error.
+pub trait ClassifyError {
+    /// Whether the emulator can keep running after this error.
+    fn severity(&self) -> ErrorSeverity;
+}
+
+impl ClassifyError for Infallible {
+    fn severity(&self) -> ErrorSeverity {
+        match *self {}
+    }
+}
+
+impl<R: ClassifyError, A: ClassifyError, S: ClassifyError> EmulationError<R, A, S> {
+    /// The severity of the underlying frontend error.
+    pub fn severity(&self) -> ErrorSeverity {
+        match self {
+            Self::Render(err) => err.severity(),
+            Self::Audio(err) => err.severity(),
+            Self::Save(err) => err.severity(),
+        }
+    }
+
+    /// Shorthand for checking whether [`Self::severity`] is [`ErrorSeverity::Recoverable`].
+    pub fn is_recoverable(&self) -> bool {
+        self.severity() == ErrorSeverity::Recoverable
+    }
+}
+
+/// The error returned by [`Emulator::tick`] when one or more frontend calls failed during the tick.
+///
+/// Emulation state is always fully advanced before this is returned, and [`Self::effect`] reports
+/// what the tick did regardless of the errors, so a frame that failed to render or save still
+/// counts as a completed frame.
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct TickError<RenderError, AudioError, SaveError> {
+    /// What happened during the tick
+    pub effect: TickEffect,
+    /// The first error encountered during the tick
+    pub error: EmulationError<RenderError, AudioError, SaveError>,
+    /// Any other errors encountered during the same tick, e.g. a failed SRAM write in the same
+    /// frame as a failed render
+    pub additional_errors: Vec<EmulationError<RenderError, AudioError, SaveError>>,
+}
+
+impl<R, A, S> TickError<R, A, S> {
+    /// All errors encountered during the tick, in the order that they occurred.
+    pub fn into_errors(self) -> impl Iterator<Item = EmulationError<R, A, S>> {
+        iter::once(self.error).chain(self.additional_errors)
+    }
+}
+
+impl<R: ClassifyError, A: ClassifyError, S: ClassifyError> TickError<R, A, S> {
+    /// Whether every error encountered during the tick is recoverable.
+    pub fn is_recoverable(&self) -> bool {
+        self.error.is_recoverable()
+            && self.additional_errors.iter().all(EmulationError::is_recoverable)
+    }
+}
+
+impl<R: Display, A: Display, S: Display> Display for TickError<R, A, S> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.error)?;
+        if !self.additional_errors.is_empty() {
+            write!(f, " (and {} more)", self.additional_errors.len())?;
+        }
+        Ok(())
+    }
+}
+
+impl<R: Error + 'static, A: Error + 'static, S: Error + 'static> Error for TickError<R, A, S> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.error.source()
+    }
+}
+
+/// Settings that can be changed while the emulator is running; these are passed to every
+/// [`Emulator::tick`] call. The default values emulate the hardware as accurately as possible.
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
 pub struct EmulatorConfig {
     /// If true, do not emulate the 8 sprite per scanline limit; this eliminates sprite flickering
     /// but can cause bugs in some games
     pub remove_sprite_limit: bool,
+    /// If true and the sprite limit is not removed, alternate which sprites are dropped from
+    /// scanlines with too many sprites every frame, rather than always dropping the same sprites.
+    /// Sprites that would otherwise be invisible flicker instead. Not hardware-accurate
+    pub reduce_sprite_flicker: bool,
     /// If true, add a black border over the top scanline, the leftmost 2 columns, and the rightmost 2 columns
     pub pal_black_border: bool,
     /// If true, silence the triangle wave channel when it is outputting a wave at ultrasonic frequency
     pub silence_ultrasonic_triangle_output: bool,
+    /// If true, mix the APU channels using a linear approximation of the NES's non-linear DAC
+    /// instead of the full mixer formula. This is less accurate, but some prefer the sound
+    pub linear_apu_mixing: bool,
+    /// If true, fade the triangle wave channel out and back in over about 1ms when it is silenced or
+    /// resumes, which avoids clicks at the cost of accuracy
+    pub ramp_triangle_silencing: bool,
+    /// If true, smooth out jumps in the DMC output level caused by direct writes to $4011, which
+    /// reduces clicking in games that play samples through $4011 at the cost of accuracy
+    pub dmc_declick_filter: bool,
+    /// If true, draw a debug overlay that marks scroll splits and outlines the visible viewport
+    /// within the four nametables
+    pub debug_scroll_overlay: bool,
+    /// If true, draw the current P1 and P2 controller state at the bottom of the frame
+    pub input_display: bool,
+    /// If true, record all CPU writes to memory-mapped registers along with the scanline and dot
+    /// that they occurred on; see [`Emulator::last_frame_register_writes`]
+    pub record_register_writes: bool,
+    /// If true, log the scanline and dot whenever the PPU sets the sprite 0 hit flag; see
+    /// [`Emulator::sprite_0_hit`]
+    pub log_sprite_0_hits: bool,
 }
 
+/// An in-memory snapshot of the emulator's state, created by [`Emulator::snapshot_state`]. This is
+/// cheaper than a serialized save state and is intended for features such as rewind.
+#[derive(Clone)]
 pub struct EmulationState {
     pub(crate) bus: Bus,
     pub(crate) cpu_state: CpuState,
     pub(crate) ppu_state: PpuState,
     pub(crate) apu_state: ApuState,
+    pub(crate) rng: CoreRng,
+}
+
+impl EmulationState {
+    /// Approximate number of bytes of memory used by this snapshot. Snapshots never include the
+    /// ROM, so this is roughly the size of RAM plus internal register state.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the state cannot be encoded, which should never happen.
+    #[must_use]
+    pub fn approximate_size(&self) -> usize {
+        serialize::encoded_len(self).expect("encoding to a byte counter should never fail")
+    }
 }
 
+/// An emulated NES with a cartridge inserted, along with the frontend implementations that it
+/// outputs to.
+///
+/// See the [crate-level documentation](crate) for an example of creating and running an emulator.
 pub struct Emulator<Renderer, AudioPlayer, InputPoller, SaveWriter> {
     bus: Bus,
     cpu_state: CpuState,
     ppu_state: PpuState,
     apu_state: ApuState,
+    rng: CoreRng,
     renderer: Renderer,
     audio_player: AudioPlayer,
     input_poller: InputPoller,
     save_writer: SaveWriter,
+    // Set when the last attempt to persist SRAM failed so that it will be retried
+    sram_persist_pending: bool,
     // Kept around to enable hard reset
     raw_rom_bytes: Vec<u8>,
+    // Identifies the ROM in save states
+    rom_hash: u64,
+    cartridge_info: CartridgeInfo,
     forced_timing_mode: Option<TimingMode>,
+    forced_mapper_number: Option<u16>,
+    ram_init: RamInit,
+    register_writes: Vec<RegisterWriteEvent>,
+    last_frame_register_writes: Vec<RegisterWriteEvent>,
+    scanline_span: ScanlineSpan,
 }
 
+/// What happened during an [`Emulator::tick`] call, other than advancing emulation.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TickEffect {
+    /// Nothing that the caller needs to react to
     None,
+    /// A frame was sent to the renderer; run loops generally sync to video or audio after this
     FrameRendered,
 }
 
+/// The result of an [`Emulator::tick`] call.
 pub type EmulationResult<RenderError, AudioError, SaveError> =
-    Result<TickEffect, EmulationError<RenderError, AudioError, SaveError>>;
+    Result<TickEffect, TickError<RenderError, AudioError, SaveError>>;
 
 type UnitEmulationResult<RenderError, AudioError, SaveError> =
     Result<(), EmulationError<RenderError, AudioError, SaveError>>;
 
+/// An error returned by [`Emulator::create`].
 #[derive(Debug, Error)]
+#[non_exhaustive]
 pub enum InitializationError<RenderError> {
-    #[error("Error loading cartridge ROM: {source}")]
+    /// The ROM could not be loaded
+    #[error("{source}")]
     CartridgeLoad {
         #[from]
-        source: CartridgeFileError,
+        source: RomLoadError,
     },
+    /// [`Renderer::set_timing_mode`] returned an error
     #[error("Error initializing renderer: {source}")]
     RendererInit {
         #[source]
@@ -277,17 +488,36 @@ pub enum InitializationError<RenderError> {
     },
 }
 
+/// Arguments for [`Emulator::create`].
 #[derive(Debug, Clone)]
 pub struct EmulatorCreateArgs<R, A, I, S> {
+    /// Contents of an iNES or NES 2.0 ROM file
     pub rom_bytes: Vec<u8>,
+    /// Previously persisted save data, in the format passed to [`SaveWriter::persist_sram`]
     pub sav_bytes: Option<Vec<u8>>,
+    /// If set, overrides the timing mode from the ROM header
     pub forced_timing_mode: Option<TimingMode>,
+    /// If set, overrides the mapper number from the ROM header
+    pub forced_mapper_number: Option<u16>,
+    /// Power-on contents of CPU and PPU RAM
+    pub ram_init: RamInit,
     pub renderer: R,
     pub audio_player: A,
     pub input_poller: I,
     pub save_writer: S,
 }
 
+/// Arguments for [`Emulator::swap_cartridge`].
+#[derive(Debug, Clone)]
+pub struct CartridgeSwapArgs<S> {
+    pub rom_bytes: Vec<u8>,
+    pub sav_bytes: Option<Vec<u8>>,
+    pub forced_timing_mode: Option<TimingMode>,
+    pub forced_mapper_number: Option<u16>,
+    pub ram_init: RamInit,
+    pub save_writer: S,
+}
+
 impl<R: Renderer, A: AudioPlayer, I: InputPoller, S: SaveWriter> Emulator<R, A, I, S> {
     /// Create a new emulator instance.
     ///
@@ -300,40 +530,51 @@ impl<R: Renderer, A: AudioPlayer, I: InputPoller, S: SaveWriter> Emulator<R, A,
             rom_bytes,
             sav_bytes,
             forced_timing_mode,
+            forced_mapper_number,
+            ram_init,
             mut renderer,
             mut audio_player,
             input_poller,
             save_writer,
         }: EmulatorCreateArgs<R, A, I, S>,
     ) -> Result<Self, InitializationError<R::Err>> {
-        let mapper = cartridge::from_ines_file(&rom_bytes, sav_bytes, forced_timing_mode)?;
+        let mapper = cartridge::from_ines_file(
+            &rom_bytes,
+            sav_bytes,
+            forced_timing_mode,
+            forced_mapper_number,
+        )
+        .map_err(|err| RomLoadError::new(err, &rom_bytes))?;
         let timing_mode = mapper.timing_mode();
+        let cartridge_info = CartridgeInfo::new(&mapper, &rom_bytes, forced_mapper_number);
 
         renderer
             .set_timing_mode(timing_mode)
             .map_err(|err| InitializationError::RendererInit { source: err })?;
         audio_player.set_timing_mode(timing_mode);
 
-        let mut bus = Bus::from_cartridge(mapper);
-
-        let cpu_registers = CpuRegisters::create(&mut bus.cpu());
-        let cpu_state = CpuState::new(cpu_registers);
-        let ppu_state = PpuState::new(timing_mode);
-        let mut apu_state = ApuState::new(timing_mode);
-
-        init_apu(&mut apu_state, &mut bus);
+        let (bus, cpu_state, ppu_state, apu_state, rng) = power_on(mapper, ram_init);
 
         Ok(Self {
             bus,
             cpu_state,
             ppu_state,
             apu_state,
+            rng,
             renderer,
             audio_player,
             input_poller,
             save_writer,
+            sram_persist_pending: false,
+            rom_hash: serialize::fnv1a_hash(&rom_bytes),
             raw_rom_bytes: rom_bytes,
+            cartridge_info,
             forced_timing_mode,
+            forced_mapper_number,
+            ram_init,
+            register_writes: Vec::new(),
+            last_frame_register_writes: Vec::new(),
+            scanline_span: ScanlineSpan::default(),
         })
     }
 
@@ -344,47 +585,91 @@ impl<R: Renderer, A: AudioPlayer, I: InputPoller, S: SaveWriter> Emulator<R, A,
     /// This method will propagate any errors encountered while rendering a frame, pushing
     /// audio samples, or persisting SRAM. It will also return an error if the emulated CPU executes
     /// an invalid opcode.
+    ///
+    /// Emulation state is always fully advanced before an error is returned, and the returned
+    /// [`TickError`] still carries the tick's [`TickEffect`], so it is safe to keep calling this
+    /// method after an error that [`ClassifyError`] reports as recoverable.
     pub fn tick(&mut self, config: &EmulatorConfig) -> EmulationResult<R::Err, A::Err, S::Err> {
         let prev_in_vblank = self.ppu_state.in_vblank();
 
         let timing_mode = self.bus.mapper().timing_mode();
 
-        match timing_mode {
-            TimingMode::Ntsc => {
-                self.ntsc_tick(config)?;
-            }
-            TimingMode::Pal => {
-                self.pal_tick(config)?;
-            }
+        // Audio errors are reported only after the frame has been handled so that a recoverable
+        // audio error does not cause a frame to be skipped
+        let audio_result = match timing_mode {
+            TimingMode::Ntsc => self.ntsc_tick(config),
+            TimingMode::Pal => self.pal_tick(config),
+        };
+        self.scanline_span.update(self.ppu_state.scanline());
+
+        let mut errors = Vec::new();
+        if let Err(err) = audio_result {
+            errors.push(err);
         }
 
-        if !prev_in_vblank && self.ppu_state.in_vblank() {
-            if config.pal_black_border {
-                ppu::render_pal_black_border(&mut self.ppu_state);
+        let tick_effect = if !prev_in_vblank && self.ppu_state.in_vblank() {
+            self.end_frame(config, timing_mode, &mut errors);
+            TickEffect::FrameRendered
+        } else {
+            TickEffect::None
+        };
+
+        let mut errors = errors.into_iter();
+        match errors.next() {
+            None => Ok(tick_effect),
+            Some(error) => {
+                Err(TickError { effect: tick_effect, error, additional_errors: errors.collect() })
             }
+        }
+    }
 
-            let frame_buffer = self.ppu_state.frame_buffer();
-            let color_emphasis = ColorEmphasis::get_current(&self.bus.ppu(), timing_mode);
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+    fn end_frame(
+        &mut self,
+        config: &EmulatorConfig,
+        timing_mode: TimingMode,
+        errors: &mut Vec<EmulationError<R::Err, A::Err, S::Err>>,
+    ) {
+        if config.pal_black_border {
+            ppu::render_pal_black_border(&mut self.ppu_state);
+        }
 
-            self.renderer
-                .render_frame(frame_buffer, color_emphasis)
-                .map_err(EmulationError::Render)?;
+        if config.debug_scroll_overlay {
+            ppu::render_scroll_overlay(&mut self.ppu_state);
+        }
 
-            let p1_joypad_state = self.input_poller.poll_p1_input();
-            self.bus.update_p1_joypad_state(p1_joypad_state);
+        if config.input_display {
+            let (p1_joypad_state, p2_joypad_state) = self.bus.joypad_states();
+            ppu::render_input_display(&mut self.ppu_state, p1_joypad_state, p2_joypad_state);
+        }
 
-            let p2_joypad_state = self.input_poller.poll_p2_input();
-            self.bus.update_p2_joypad_state(p2_joypad_state);
+        mem::swap(&mut self.register_writes, &mut self.last_frame_register_writes);
+        self.register_writes.clear();
 
-            if self.bus.mapper_mut().get_and_clear_ram_dirty_bit() {
-                let sram = self.bus.mapper().get_prg_ram();
-                self.save_writer.persist_sram(sram).map_err(EmulationError::Save)?;
-            }
+        let frame_buffer = self.ppu_state.frame_buffer();
+        let color_emphasis = ColorEmphasis::get_current(&self.bus.ppu(), timing_mode);
 
-            return Ok(TickEffect::FrameRendered);
+        // Input polling and SRAM persistence still happen if rendering fails
+        if let Err(err) = self.renderer.render_frame(frame_buffer, color_emphasis) {
+            errors.push(EmulationError::Render(err));
         }
 
-        Ok(TickEffect::None)
+        let p1_joypad_state = self.input_poller.poll_p1_input();
+        self.bus.update_p1_joypad_state(p1_joypad_state);
+
+        let p2_joypad_state = self.input_poller.poll_p2_input();
+        self.bus.update_p2_joypad_state(p2_joypad_state);
+
+        if self.bus.mapper_mut().get_and_clear_ram_dirty_bit() || self.sram_persist_pending {
+            let sram = self.bus.mapper().save_data();
+            let persist_result = self.save_writer.persist_sram(&sram);
+            self.sram_persist_pending = persist_result.is_err();
+            if let Err(err) = persist_result {
+                errors.push(EmulationError::Save(err));
+            }
+        } else if let Err(err) = self.save_writer.poll_error() {
+            errors.push(EmulationError::Save(err));
+        }
     }
 
     fn ntsc_tick(
@@ -394,7 +679,7 @@ impl<R: Renderer, A: AudioPlayer, I: InputPoller, S: SaveWriter> Emulator<R, A,
         cpu::tick(&mut self.cpu_state, &mut self.bus.cpu(), self.apu_state.is_active_cycle());
         apu::tick(&mut self.apu_state, &mut self.bus.cpu(), config);
         ppu::tick(&mut self.ppu_state, &mut self.bus.ppu(), config);
-        self.bus.tick_cpu();
+        self.apply_cpu_write(config);
         self.bus.tick();
 
         self.bus.poll_interrupt_lines();
@@ -405,7 +690,7 @@ impl<R: Renderer, A: AudioPlayer, I: InputPoller, S: SaveWriter> Emulator<R, A,
         ppu::tick(&mut self.ppu_state, &mut self.bus.ppu(), config);
         self.bus.tick();
 
-        self.push_audio_sample()?;
+        self.push_audio_sample(config)?;
 
         Ok(())
     }
@@ -415,12 +700,14 @@ impl<R: Renderer, A: AudioPlayer, I: InputPoller, S: SaveWriter> Emulator<R, A,
         cpu::tick(&mut self.cpu_state, &mut self.bus.cpu(), self.apu_state.is_active_cycle());
         apu::tick(&mut self.apu_state, &mut self.bus.cpu(), config);
         ppu::tick(&mut self.ppu_state, &mut self.bus.ppu(), config);
-        self.bus.tick_cpu();
+        self.apply_cpu_write(config);
         self.bus.tick();
 
         self.bus.poll_interrupt_lines();
 
-        self.push_audio_sample()?;
+        // Always run every master clock tick so that an audio error cannot leave the CPU and PPU
+        // out of sync; only the first error is returned
+        let mut audio_result = self.push_audio_sample(config);
 
         for i in 1..PAL_MASTER_CLOCK_TICKS {
             if i % PAL_CPU_DIVIDER == 0 {
@@ -430,24 +717,45 @@ impl<R: Renderer, A: AudioPlayer, I: InputPoller, S: SaveWriter> Emulator<R, A,
                     self.apu_state.is_active_cycle(),
                 );
                 apu::tick(&mut self.apu_state, &mut self.bus.cpu(), config);
-                self.bus.tick_cpu();
+                self.apply_cpu_write(config);
                 self.bus.tick();
 
                 self.bus.poll_interrupt_lines();
 
-                self.push_audio_sample()?;
+                audio_result = audio_result.and(self.push_audio_sample(config));
             } else if i % PAL_PPU_DIVIDER == 0 {
                 ppu::tick(&mut self.ppu_state, &mut self.bus.ppu(), config);
                 self.bus.tick();
             }
         }
 
-        Ok(())
+        audio_result
     }
 
-    fn push_audio_sample(&mut self) -> UnitEmulationResult<R::Err, A::Err, S::Err> {
+    fn apply_cpu_write(&mut self, config: &EmulatorConfig) {
+        if config.record_register_writes {
+            if let Some((address, value)) = self.bus.pending_write() {
+                let event = RegisterWriteEvent {
+                    scanline: self.ppu_state.scanline(),
+                    dot: self.ppu_state.dot(),
+                    address,
+                    value,
+                };
+                if event.target().is_some() {
+                    self.register_writes.push(event);
+                }
+            }
+        }
+
+        self.bus.tick_cpu();
+    }
+
+    fn push_audio_sample(
+        &mut self,
+        config: &EmulatorConfig,
+    ) -> UnitEmulationResult<R::Err, A::Err, S::Err> {
         let audio_sample = {
-            let sample = self.apu_state.sample();
+            let sample = self.apu_state.sample(config);
             let sample = self.bus.mapper().sample_audio(sample);
             self.apu_state.high_pass_filter(sample)
         };
@@ -471,18 +779,20 @@ impl<R: Renderer, A: AudioPlayer, I: InputPoller, S: SaveWriter> Emulator<R, A,
 
     /// Completely re-initialize all emulation state.
     ///
-    /// `sav_bytes` will be used if set, otherwise PRG RAM will be moved from the existing Emulator.
+    /// `sav_bytes` will be used if set, otherwise save data will be moved from the existing Emulator.
     #[allow(clippy::missing_panics_doc)]
     #[must_use]
     pub fn hard_reset(self, sav_bytes: Option<Vec<u8>>) -> Self
     where
         R::Err: Debug,
     {
-        let prg_ram = sav_bytes.unwrap_or_else(|| Vec::from(self.bus.mapper().get_prg_ram()));
+        let sav_bytes = sav_bytes.unwrap_or_else(|| self.bus.mapper().save_data().into_owned());
         Self::create(EmulatorCreateArgs {
             rom_bytes: self.raw_rom_bytes,
-            sav_bytes: Some(prg_ram),
+            sav_bytes: Some(sav_bytes),
             forced_timing_mode: self.forced_timing_mode,
+            forced_mapper_number: self.forced_mapper_number,
+            ram_init: self.ram_init,
             renderer: self.renderer,
             audio_player: self.audio_player,
             input_poller: self.input_poller,
@@ -491,6 +801,61 @@ impl<R: Renderer, A: AudioPlayer, I: InputPoller, S: SaveWriter> Emulator<R, A,
         .expect("hard reset should never fail cartridge validation")
     }
 
+    /// Remove the current cartridge and power on with a different one, keeping the existing
+    /// renderer, audio player, and input poller. Returns the save writer for the previous
+    /// cartridge.
+    ///
+    /// # Errors
+    ///
+    /// This method will return an error if it cannot successfully parse NES ROM data out of the
+    /// given ROM bytes, or if the renderer cannot switch to the new cartridge's timing mode. The
+    /// current cartridge remains loaded if this method returns an error.
+    pub fn swap_cartridge(
+        &mut self,
+        CartridgeSwapArgs {
+            rom_bytes,
+            sav_bytes,
+            forced_timing_mode,
+            forced_mapper_number,
+            ram_init,
+            save_writer,
+        }: CartridgeSwapArgs<S>,
+    ) -> Result<S, InitializationError<R::Err>> {
+        let mapper = cartridge::from_ines_file(
+            &rom_bytes,
+            sav_bytes,
+            forced_timing_mode,
+            forced_mapper_number,
+        )
+        .map_err(|err| RomLoadError::new(err, &rom_bytes))?;
+        let timing_mode = mapper.timing_mode();
+        let cartridge_info = CartridgeInfo::new(&mapper, &rom_bytes, forced_mapper_number);
+
+        self.renderer
+            .set_timing_mode(timing_mode)
+            .map_err(|err| InitializationError::RendererInit { source: err })?;
+        self.audio_player.set_timing_mode(timing_mode);
+
+        // Last chance to persist the outgoing cartridge's SRAM if the previous write failed; there
+        // is nowhere to report an error at this point
+        if self.sram_persist_pending {
+            let _ = self.save_writer.persist_sram(&self.bus.mapper().save_data());
+        }
+
+        (self.bus, self.cpu_state, self.ppu_state, self.apu_state, self.rng) =
+            power_on(mapper, ram_init);
+        self.sram_persist_pending = false;
+        self.rom_hash = serialize::fnv1a_hash(&rom_bytes);
+        self.raw_rom_bytes = rom_bytes;
+        self.cartridge_info = cartridge_info;
+        self.forced_timing_mode = forced_timing_mode;
+        self.forced_mapper_number = forced_mapper_number;
+        self.ram_init = ram_init;
+
+        Ok(mem::replace(&mut self.save_writer, save_writer))
+    }
+
+    /// Return whether the loaded cartridge is running in NTSC or PAL mode.
     pub fn timing_mode(&self) -> TimingMode {
         self.bus.mapper().timing_mode()
     }
@@ -515,19 +880,44 @@ impl<R: Renderer, A, I, S> Emulator<R, A, I, S> {
 }
 
 impl<R, A, I, S> Emulator<R, A, I, S> {
+    /// Retrieve the scroll position at the start of each visible scanline, or None for scanlines
+    /// where rendering was disabled. When called between frames, this covers the entire frame that
+    /// was just rendered.
+    pub fn scroll_trace(&self) -> &[Option<ScrollPosition>] {
+        self.ppu_state.scroll_trace()
+    }
+
+    /// Retrieve the register writes recorded between the start of the previous VBlank and the
+    /// start of the most recent VBlank, which covers the writes that affected the most recently
+    /// rendered frame. This is only populated while [`EmulatorConfig::record_register_writes`] is
+    /// enabled.
+    pub fn last_frame_register_writes(&self) -> &[RegisterWriteEvent] {
+        &self.last_frame_register_writes
+    }
+
+    /// Retrieve the scanline and dot where the sprite 0 hit flag was set during the current frame,
+    /// if it was set. When called between frames, this covers the frame that was just rendered.
+    pub fn sprite_0_hit(&self) -> Option<Sprite0HitEvent> {
+        self.ppu_state.sprite_0_hit()
+    }
+
+    /// Return the renderer that was passed to [`Emulator::create`].
     pub fn get_renderer(&self) -> &R {
         &self.renderer
     }
 
+    /// Return the renderer that was passed to [`Emulator::create`].
     pub fn get_renderer_mut(&mut self) -> &mut R {
         &mut self.renderer
     }
 
+    /// Return the audio player that was passed to [`Emulator::create`].
     pub fn get_audio_player_mut(&mut self) -> &mut A {
         &mut self.audio_player
     }
 
-    /// Save current emulation state to the given writer.
+    /// Save current emulation state to the given writer. The format is the same across all
+    /// frontends, so the state can be loaded by any emulator running the same ROM.
     ///
     /// # Errors
     ///
@@ -537,7 +927,15 @@ impl<R, A, I, S> Emulator<R, A, I, S> {
     where
         Writer: io::Write,
     {
-        serialize::save_state(&self.bus, &self.cpu_state, &self.ppu_state, &self.apu_state, writer)
+        serialize::save_state(
+            self.rom_hash,
+            &self.bus,
+            &self.cpu_state,
+            &self.ppu_state,
+            &self.apu_state,
+            &self.rng,
+            writer,
+        )
     }
 
     /// Load emulation state from the specified reader.
@@ -545,7 +943,7 @@ impl<R, A, I, S> Emulator<R, A, I, S> {
     /// # Errors
     ///
     /// This method will return an error if it is unable to completely load or deserialize state
-    /// from the given reader.
+    /// from the given reader, or if the state was saved from a different ROM.
     ///
     /// This should not be considered a fatal error - for example, deserialization might fail if the
     /// internal state format has changed in an incompatible way due to code changes since the state
@@ -554,7 +952,7 @@ impl<R, A, I, S> Emulator<R, A, I, S> {
     where
         Reader: io::Read,
     {
-        let state = serialize::load_state(reader)?;
+        let state = serialize::load_state(reader, self.rom_hash)?;
 
         self.load_state_snapshot(state);
 
@@ -569,6 +967,7 @@ impl<R, A, I, S> Emulator<R, A, I, S> {
             cpu_state: self.cpu_state.clone(),
             ppu_state: self.ppu_state.clone(),
             apu_state: self.apu_state.clone(),
+            rng: self.rng.clone(),
         }
     }
 
@@ -580,12 +979,73 @@ impl<R, A, I, S> Emulator<R, A, I, S> {
         self.cpu_state = state.cpu_state;
         self.ppu_state = state.ppu_state;
         self.apu_state = state.apu_state;
+        self.rng = state.rng;
     }
 
     /// Return whether the loaded cartridge has some sort of persistent RAM (e.g. SRAM or EEPROM).
     pub fn has_persistent_ram(&self) -> bool {
         self.bus.mapper().has_persistent_ram()
     }
+
+    /// Return the current contents of the cartridge's persistent RAM, in the same format that is
+    /// passed to [`SaveWriter::persist_sram`], or None if the cartridge has no persistent RAM.
+    /// This is always up to date, even if SRAM has not been persisted recently.
+    pub fn persistent_ram(&self) -> Option<Cow<'_, [u8]>> {
+        self.has_persistent_ram().then(|| self.bus.mapper().save_data())
+    }
+
+    /// Return a summary of the loaded cartridge (mapper, ROM/RAM sizes, region, etc.).
+    pub fn cartridge_info(&self) -> &CartridgeInfo {
+        &self.cartridge_info
+    }
+
+    /// Return how RAM was initialized when the current state was powered on, including the seed
+    /// that the core RNG was created with. Passing this back to [`Emulator::create`] will reproduce
+    /// the same initial RAM contents.
+    pub fn ram_init(&self) -> RamInit {
+        self.bus.ram_init()
+    }
+
+    /// Return the seed of the RNG behind all randomness in the core, e.g. random RAM contents.
+    /// The RNG is part of save states and snapshots, so emulation from a given state is always
+    /// deterministic.
+    pub fn rng_seed(&self) -> u64 {
+        self.rng.seed()
+    }
+
+    /// Return the number of CPU cycles emulated since power on. Like all other emulation state,
+    /// this is restored when loading a save state or snapshot.
+    pub fn cpu_cycle_count(&self) -> u64 {
+        self.cpu_state.cycle_count()
+    }
+
+    /// Return the number of frames emulated since power on, counted at the start of VBlank, which
+    /// is when [`TickEffect::FrameRendered`] is returned. Like all other emulation state, this is
+    /// restored when loading a save state or snapshot.
+    pub fn frame_count(&self) -> u64 {
+        self.ppu_state.frame_count()
+    }
+
+    /// Change how RAM will be initialized on the next hard reset. This is mainly useful for
+    /// replaying a recorded hard reset with the same RAM contents.
+    pub fn set_ram_init(&mut self, ram_init: RamInit) {
+        self.ram_init = ram_init;
+    }
+}
+
+fn power_on(mapper: Mapper, ram_init: RamInit) -> (Bus, CpuState, PpuState, ApuState, CoreRng) {
+    let timing_mode = mapper.timing_mode();
+    let mut rng = CoreRng::from_seed_or_entropy(ram_init.seed);
+    let mut bus = Bus::from_cartridge(mapper, ram_init, &mut rng);
+
+    let cpu_registers = CpuRegisters::create(&mut bus.cpu());
+    let cpu_state = CpuState::new(cpu_registers);
+    let ppu_state = PpuState::new(timing_mode);
+    let mut apu_state = ApuState::new(timing_mode);
+
+    init_apu(&mut apu_state, &mut bus);
+
+    (bus, cpu_state, ppu_state, apu_state, rng)
 }
 
 fn init_apu(apu_state: &mut ApuState, bus: &mut Bus) {
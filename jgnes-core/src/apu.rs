@@ -24,12 +24,20 @@ use crate::apu::noise::NoiseChannel;
 use crate::apu::pulse::{PulseChannel, SweepStatus};
 use crate::apu::triangle::TriangleChannel;
 use crate::bus::{CpuBus, IoRegister, IrqSource, TimingMode};
+use crate::logging::APU_TARGET;
 use crate::num::GetBit;
 use bincode::{Decode, Encode};
 use std::array;
 use std::ops::Range;
 use std::sync::OnceLock;
 
+// Fading the triangle channel in or out takes 2048 CPU cycles, a little over 1ms
+const TRIANGLE_RAMP_STEP: f64 = 1.0 / 2048.0;
+
+// Jumps from $4011 writes decay with a time constant of 50 CPU cycles, which rounds off the edges
+// of each jump without noticeably muffling samples played back through $4011
+const DMC_DECLICK_DECAY: f64 = 1.0 - 1.0 / 50.0;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Encode, Decode)]
 enum FrameCounterMode {
     FourStep,
@@ -151,6 +159,8 @@ pub struct ApuState {
     dmc: DeltaModulationChannel,
     frame_counter: FrameCounter,
     frame_counter_interrupt_flag: bool,
+    triangle_gain: f64,
+    dmc_raw_write_offset: f64,
     hpf_capacitor: f64,
 }
 
@@ -164,6 +174,8 @@ impl ApuState {
             dmc: DeltaModulationChannel::new(),
             frame_counter: FrameCounter::new(timing_mode),
             frame_counter_interrupt_flag: false,
+            triangle_gain: 1.0,
+            dmc_raw_write_offset: 0.0,
             hpf_capacitor: 0.0,
         }
     }
@@ -220,7 +232,12 @@ impl ApuState {
                 self.dmc.process_dmc_freq_update(value);
             }
             IoRegister::DMC_RAW => {
+                let previous_sample = self.dmc.sample();
                 self.dmc.process_dmc_raw_update(value);
+
+                // Only used if the DMC declick filter is enabled
+                self.dmc_raw_write_offset +=
+                    f64::from(previous_sample) - f64::from(self.dmc.sample());
             }
             IoRegister::DMC_START => {
                 self.dmc.process_dmc_start_update(value);
@@ -246,6 +263,8 @@ impl ApuState {
         self.pulse_channel_1.tick_cpu();
         self.pulse_channel_2.tick_cpu();
         self.triangle_channel.tick_cpu(config.silence_ultrasonic_triangle_output);
+        self.update_triangle_gain(config);
+        self.update_dmc_raw_write_offset(config);
         self.noise_channel.tick_cpu();
         self.dmc.tick_cpu(bus);
         self.frame_counter.tick();
@@ -276,6 +295,30 @@ impl ApuState {
         bus.interrupt_lines().set_irq_low_pull(IrqSource::ApuDmc, self.dmc.interrupt_flag());
     }
 
+    // Fade the triangle channel out when it is silenced and back in when it resumes, rather than
+    // letting its output jump between its held level and its waveform
+    fn update_triangle_gain(&mut self, config: &EmulatorConfig) {
+        if !config.ramp_triangle_silencing {
+            self.triangle_gain = 1.0;
+            return;
+        }
+
+        if self.triangle_channel.silenced(config.silence_ultrasonic_triangle_output) {
+            self.triangle_gain = (self.triangle_gain - TRIANGLE_RAMP_STEP).max(0.0);
+        } else {
+            self.triangle_gain = (self.triangle_gain + TRIANGLE_RAMP_STEP).min(1.0);
+        }
+    }
+
+    // Rather than jumping straight to the level written to $4011, let the DMC output level slide
+    // toward it
+    fn update_dmc_raw_write_offset(&mut self, config: &EmulatorConfig) {
+        self.dmc_raw_write_offset *= DMC_DECLICK_DECAY;
+        if !config.dmc_declick_filter || self.dmc_raw_write_offset.abs() < 0.001 {
+            self.dmc_raw_write_offset = 0.0;
+        }
+    }
+
     fn get_apu_status(&self) -> u8 {
         (u8::from(self.dmc.interrupt_flag()) << 7)
             | (u8::from(self.frame_counter_interrupt_flag) << 6)
@@ -286,15 +329,33 @@ impl ApuState {
             | u8::from(self.pulse_channel_1.length_counter() > 0)
     }
 
-    fn mix_samples(&self) -> f64 {
+    fn mix_samples(&self, linear_mixing: bool) -> f64 {
         let pulse1_sample = self.pulse_channel_1.sample();
         let pulse2_sample = self.pulse_channel_2.sample();
         let triangle_sample = self.triangle_channel.sample();
         let noise_sample = self.noise_channel.sample();
         let dmc_sample = self.dmc.sample();
 
+        let triangle_level = self.triangle_gain * f64::from(triangle_sample);
+        let dmc_level = (f64::from(dmc_sample) + self.dmc_raw_write_offset).clamp(0.0, 127.0);
+
+        if linear_mixing {
+            return mix_samples_linear(
+                pulse1_sample,
+                pulse2_sample,
+                triangle_level,
+                noise_sample,
+                dmc_level,
+            );
+        }
+
         let pulse_mix = mix_pulse_samples(pulse1_sample, pulse2_sample);
-        let tnd_mix = mix_tnd_samples(triangle_sample, noise_sample, dmc_sample);
+        let tnd_mix = if self.triangle_gain < 1.0 || self.dmc_raw_write_offset != 0.0 {
+            // The lookup table only covers whole triangle and DMC levels
+            tnd_formula(triangle_level, f64::from(noise_sample), dmc_level)
+        } else {
+            mix_tnd_samples(triangle_sample, noise_sample, dmc_sample)
+        };
 
         pulse_mix + tnd_mix
     }
@@ -309,8 +370,8 @@ impl ApuState {
     }
 
     /// Retrieve the current audio sample being generated by the APU, in the range 0 to 1.
-    pub fn sample(&self) -> f64 {
-        self.mix_samples()
+    pub fn sample(&self, config: &EmulatorConfig) -> f64 {
+        self.mix_samples(config.linear_apu_mixing)
     }
 }
 
@@ -343,15 +404,8 @@ fn mix_tnd_samples(triangle_sample: u8, noise_sample: u8, dmc_sample: u8) -> f64
         for (dmc_sample, dmc_row) in lookup_table.iter_mut().enumerate() {
             for (triangle_sample, triangle_row) in dmc_row.iter_mut().enumerate() {
                 for (noise_sample, value) in triangle_row.iter_mut().enumerate() {
-                    if triangle_sample > 0 || noise_sample > 0 || dmc_sample > 0 {
-                        // Formula from https://www.nesdev.org/wiki/APU_Mixer
-                        *value = 159.79
-                            / (1.0
-                                / (triangle_sample as f64 / 8227.0
-                                    + noise_sample as f64 / 12241.0
-                                    + dmc_sample as f64 / 22638.0)
-                                + 100.0);
-                    }
+                    *value =
+                        tnd_formula(triangle_sample as f64, noise_sample as f64, dmc_sample as f64);
                 }
             }
         }
@@ -362,15 +416,40 @@ fn mix_tnd_samples(triangle_sample: u8, noise_sample: u8, dmc_sample: u8) -> f64
     lookup_table[dmc_sample as usize][triangle_sample as usize][noise_sample as usize]
 }
 
+fn tnd_formula(triangle_level: f64, noise_level: f64, dmc_level: f64) -> f64 {
+    if triangle_level == 0.0 && noise_level == 0.0 && dmc_level == 0.0 {
+        return 0.0;
+    }
+
+    // Formula from https://www.nesdev.org/wiki/APU_Mixer
+    159.79 / (1.0 / (triangle_level / 8227.0 + noise_level / 12241.0 + dmc_level / 22638.0) + 100.0)
+}
+
+fn mix_samples_linear(
+    pulse1_sample: u8,
+    pulse2_sample: u8,
+    triangle_level: f64,
+    noise_sample: u8,
+    dmc_level: f64,
+) -> f64 {
+    // Linear approximation from https://www.nesdev.org/wiki/APU_Mixer
+    let pulse_mix = 0.00752 * f64::from(pulse1_sample + pulse2_sample);
+    let tnd_mix =
+        0.00851 * triangle_level + 0.00494 * f64::from(noise_sample) + 0.00335 * dmc_level;
+
+    pulse_mix + tnd_mix
+}
+
 /// Tick the APU for one CPU cycle.
 ///
 /// This function only updates internal state. It does not directly output audio samples anywhere.
 /// To retrieve the current audio sample, call `ApuState::sample`.
+#[cfg_attr(feature = "tracing", tracing::instrument(name = "apu_tick", level = "trace", skip_all))]
 pub fn tick(state: &mut ApuState, bus: &mut CpuBus<'_>, config: &EmulatorConfig) {
-    log::trace!("APU: Frame counter state: {:?}", state.frame_counter);
-    log::trace!("APU: Pulse 1 state: {:?}", state.pulse_channel_1);
-    log::trace!("APU: Pulse 2 state: {:?}", state.pulse_channel_2);
-    log::trace!("APU: DMC state: {:?}", state.dmc);
+    log::trace!(target: APU_TARGET, "APU: Frame counter state: {:?}", state.frame_counter);
+    log::trace!(target: APU_TARGET, "APU: Pulse 1 state: {:?}", state.pulse_channel_1);
+    log::trace!(target: APU_TARGET, "APU: Pulse 2 state: {:?}", state.pulse_channel_2);
+    log::trace!(target: APU_TARGET, "APU: DMC state: {:?}", state.dmc);
 
     if bus.get_io_registers_mut().get_and_clear_snd_chn_read() {
         state.frame_counter_interrupt_flag = false;
@@ -383,7 +462,7 @@ pub fn tick(state: &mut ApuState, bus: &mut CpuBus<'_>, config: &EmulatorConfig)
     state.tick_cpu(bus, config);
 
     bus.get_io_registers_mut().set_apu_status(state.get_apu_status());
-    log::trace!("APU: Status set to {:02X}", state.get_apu_status());
+    log::trace!(target: APU_TARGET, "APU: Status set to {:02X}", state.get_apu_status());
 }
 
 /// Reset the APU, as if the console's reset button was pressed.
@@ -400,3 +479,39 @@ pub fn reset(state: &mut ApuState, bus: &mut CpuBus<'_>) {
     state.triangle_channel.reset();
     state.dmc.reset();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_approx_eq(expected: f64, actual: f64) {
+        assert!((expected - actual).abs() < 1e-4, "expected {expected}, got {actual}");
+    }
+
+    #[test]
+    fn non_linear_mixing() {
+        assert_approx_eq(0.0, mix_pulse_samples(0, 0));
+        assert_approx_eq(0.0, mix_tnd_samples(0, 0, 0));
+
+        assert_approx_eq(0.011_65, mix_pulse_samples(1, 0));
+        assert_approx_eq(0.258_48, mix_pulse_samples(15, 15));
+        assert_approx_eq(0.246_41, mix_tnd_samples(15, 0, 0));
+        assert_approx_eq(0.741_52, mix_tnd_samples(15, 15, 127));
+        assert_approx_eq(0.133_50, tnd_formula(7.5, 0.0, 0.0));
+
+        // Two channels at full volume are quieter than twice one channel at full volume
+        assert!(mix_pulse_samples(15, 15) < 2.0 * mix_pulse_samples(15, 0));
+    }
+
+    #[test]
+    fn linear_mixing() {
+        assert_approx_eq(0.0, mix_samples_linear(0, 0, 0.0, 0, 0.0));
+
+        assert_approx_eq(0.225_6, mix_samples_linear(15, 15, 0.0, 0, 0.0));
+        assert_approx_eq(0.852_8, mix_samples_linear(15, 15, 15.0, 15, 127.0));
+        assert_approx_eq(
+            2.0 * mix_samples_linear(15, 0, 0.0, 0, 0.0),
+            mix_samples_linear(15, 15, 0.0, 0, 0.0),
+        );
+    }
+}
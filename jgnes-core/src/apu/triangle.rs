@@ -95,7 +95,7 @@ impl TriangleChannel {
         self.length_counter.clock();
     }
 
-    fn silenced(&self, silence_ultrasonic_output: bool) -> bool {
+    pub fn silenced(&self, silence_ultrasonic_output: bool) -> bool {
         self.linear_counter.counter == 0
             || self.length_counter.counter == 0
             || (silence_ultrasonic_output && self.timer.divider_period < 2)
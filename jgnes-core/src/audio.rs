@@ -4,6 +4,8 @@ use crate::TimingMode;
 use bincode::{Decode, Encode};
 use std::collections::VecDeque;
 
+/// A low-pass FIR filter with a cutoff of 24KHz, to be applied to APU samples before
+/// downsampling them.
 pub struct LowPassFilter {
     samples: VecDeque<f64>,
 }
@@ -52,7 +54,9 @@ impl TimingMode {
         }
     }
 
-    const fn nes_native_display_rate(self) -> f64 {
+    /// The NES's native display refresh rate in frames per second.
+    #[must_use]
+    pub const fn nes_native_display_rate(self) -> f64 {
         match self {
             Self::Ntsc => NTSC_NES_NATIVE_DISPLAY_RATE,
             Self::Pal => PAL_NES_NATIVE_DISPLAY_RATE,
@@ -67,6 +71,7 @@ impl TimingMode {
     }
 }
 
+/// Whether a downsampled output sample should be produced after the current input sample.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum DownsampleAction {
     None,
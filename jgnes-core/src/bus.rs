@@ -34,14 +34,17 @@
 //! $2000-$2FFF (with some ranges mirrored).
 
 pub mod cartridge;
+mod raminit;
 
 use crate::bus::cartridge::Mapper;
 use crate::input::{JoypadState, LatchedJoypadState};
+use crate::logging::PPU_TARGET;
 use crate::num::GetBit;
+use crate::rng::CoreRng;
 use bincode::{Decode, Encode};
-use std::array;
 
 pub use cartridge::TimingMode;
+pub use raminit::{RamInit, RamInitPattern};
 
 pub const CPU_RAM_START: u16 = 0x0000;
 pub const CPU_RAM_END: u16 = 0x1FFF;
@@ -54,6 +57,9 @@ pub const CPU_PPU_REGISTERS_MASK: u16 = 0x0007;
 pub const CPU_IO_REGISTERS_START: u16 = 0x4000;
 pub const CPU_IO_REGISTERS_END: u16 = 0x4017;
 
+const SND_CHN_ADDRESS: u16 =
+    CPU_IO_REGISTERS_START + IoRegister::SND_CHN.to_relative_address() as u16;
+
 pub const CPU_IO_TEST_MODE_START: u16 = 0x4018;
 pub const CPU_IO_TEST_MODE_END: u16 = 0x401F;
 
@@ -64,6 +70,10 @@ pub const CPU_NMI_VECTOR: u16 = 0xFFFA;
 pub const CPU_RESET_VECTOR: u16 = 0xFFFC;
 pub const CPU_IRQ_VECTOR: u16 = 0xFFFE;
 
+// Bits on the PPU's I/O data bus decay to 0 roughly 600ms after they were last driven; this is 36
+// NTSC frames
+const PPU_OPEN_BUS_DECAY_CYCLES: u64 = 36 * 262 * 341;
+
 #[derive(Debug, Clone, Copy, Default, Encode, Decode)]
 struct PendingCpuWrite {
     address: u16,
@@ -145,9 +155,12 @@ pub struct PpuRegisters {
     ppu_data_buffer: u8,
     ppu_status_read: bool,
     ppu_open_bus_value: u8,
+    // PPU cycle at which each open bus bit was last driven, for decay
+    ppu_open_bus_driven_at: [u64; 8],
     oam_open_bus_value: Option<u8>,
     last_accessed_register: Option<PpuTrackedRegister>,
     write_toggle: PpuWriteToggle,
+    ppu_cycles: u64,
 }
 
 impl PpuRegisters {
@@ -160,9 +173,11 @@ impl PpuRegisters {
             ppu_data_buffer: 0,
             ppu_status_read: false,
             ppu_open_bus_value: 0,
+            ppu_open_bus_driven_at: [0; 8],
             oam_open_bus_value: None,
             last_accessed_register: None,
             write_toggle: PpuWriteToggle::First,
+            ppu_cycles: 0,
         }
     }
 
@@ -264,6 +279,26 @@ impl PpuRegisters {
         self.ppu_open_bus_value
     }
 
+    // Put the bits of value selected by mask onto open bus, leaving the other bits as they were
+    fn drive_open_bus(&mut self, value: u8, mask: u8) {
+        self.ppu_open_bus_value = (self.ppu_open_bus_value & !mask) | (value & mask);
+        for bit in 0..8 {
+            if mask.bit(bit) {
+                self.ppu_open_bus_driven_at[bit as usize] = self.ppu_cycles;
+            }
+        }
+    }
+
+    fn decay_open_bus(&mut self) {
+        for bit in 0..8 {
+            if self.ppu_cycles - self.ppu_open_bus_driven_at[bit as usize]
+                >= PPU_OPEN_BUS_DECAY_CYCLES
+            {
+                self.ppu_open_bus_value &= !(1 << bit);
+            }
+        }
+    }
+
     pub fn set_oam_open_bus(&mut self, value: Option<u8>) {
         self.oam_open_bus_value = value;
     }
@@ -273,6 +308,8 @@ impl PpuRegisters {
     }
 
     fn tick(&mut self, interrupt_lines: &mut InterruptLines) {
+        self.ppu_cycles += 1;
+
         if self.ppu_status_read {
             self.ppu_status_read = false;
 
@@ -393,8 +430,10 @@ pub struct IoRegisters {
 }
 
 impl IoRegisters {
-    // All I/O registers are at $40xx, and JOY1/JOY2 leave the highest 3 bits unused
-    const IO_OPEN_BUS_BITS: u8 = 0x40;
+    // Controller reads only drive bits 4-0 of the data bus
+    const JOY_OPEN_BUS_BITS: u8 = 0xE0;
+    // Bit 5 of SND_CHN is unused
+    const SND_CHN_OPEN_BUS_BITS: u8 = 0x20;
 
     fn new() -> Self {
         Self {
@@ -408,38 +447,45 @@ impl IoRegisters {
         }
     }
 
-    fn read_address(&mut self, address: u16) -> u8 {
+    fn read_address(&mut self, address: u16, open_bus: u8) -> u8 {
         let relative_addr = address - CPU_IO_REGISTERS_START;
         let Some(register) = IoRegister::from_relative_address(relative_addr) else {
-            return cpu_open_bus(address);
+            return open_bus;
         };
 
-        self.read_register(register)
+        self.read_register(register, open_bus)
     }
 
-    fn read_register(&mut self, register: IoRegister) -> u8 {
+    fn read_register(&mut self, register: IoRegister, open_bus: u8) -> u8 {
         match register {
             IoRegister::SND_CHN => {
                 self.snd_chn_read = true;
-                self.data[register.to_relative_address()]
+                self.data[register.to_relative_address()] | (open_bus & Self::SND_CHN_OPEN_BUS_BITS)
             }
             IoRegister::JOY1 => {
-                if let Some((p1_joypad_state, p2_joypad_state)) = self.latched_joypad_state {
+                let bit = if let Some((p1_joypad_state, p2_joypad_state)) =
+                    self.latched_joypad_state
+                {
                     self.latched_joypad_state = Some((p1_joypad_state.shift(), p2_joypad_state));
-                    p1_joypad_state.next_bit() | Self::IO_OPEN_BUS_BITS
+                    p1_joypad_state.next_bit()
                 } else {
-                    u8::from(self.p1_joypad_state.a) | Self::IO_OPEN_BUS_BITS
-                }
+                    u8::from(self.p1_joypad_state.a)
+                };
+                bit | (open_bus & Self::JOY_OPEN_BUS_BITS)
             }
             IoRegister::JOY2 => {
-                if let Some((p1_joypad_state, p2_joypad_state)) = self.latched_joypad_state {
+                let bit = if let Some((p1_joypad_state, p2_joypad_state)) =
+                    self.latched_joypad_state
+                {
                     self.latched_joypad_state = Some((p1_joypad_state, p2_joypad_state.shift()));
-                    p2_joypad_state.next_bit() | Self::IO_OPEN_BUS_BITS
+                    p2_joypad_state.next_bit()
                 } else {
-                    u8::from(self.p2_joypad_state.a) | Self::IO_OPEN_BUS_BITS
-                }
+                    u8::from(self.p2_joypad_state.a)
+                };
+                bit | (open_bus & Self::JOY_OPEN_BUS_BITS)
             }
-            _ => Self::IO_OPEN_BUS_BITS,
+            // Write-only registers
+            _ => open_bus,
         }
     }
 
@@ -602,27 +648,42 @@ pub struct Bus {
     ppu_palette_ram: [u8; 32],
     ppu_oam: [u8; 256],
     ppu_bus_address: u16,
+    // The last value on the CPU data bus, returned by reads from unmapped addresses
+    cpu_open_bus_value: u8,
     interrupt_lines: InterruptLines,
     pending_write: Option<PendingCpuWrite>,
+    // Kept so that save states record how RAM was initialized
+    ram_init: RamInit,
 }
 
 impl Bus {
-    pub(crate) fn from_cartridge(mapper: Mapper) -> Self {
+    pub(crate) fn from_cartridge(mapper: Mapper, ram_init: RamInit, rng: &mut CoreRng) -> Self {
+        let ram_init = RamInit { seed: Some(rng.seed()), ..ram_init };
+
+        let mut cpu_internal_ram = [0; 2048];
+        let mut ppu_vram = [0; 2048];
+        ram_init.fill(&mut [&mut cpu_internal_ram, &mut ppu_vram], rng);
+
         Self {
             mapper,
-            // (Somewhat) randomize initial RAM contents
-            cpu_internal_ram: array::from_fn(|_| if rand::random() { 0x00 } else { 0xFF }),
+            cpu_internal_ram,
             ppu_registers: PpuRegisters::new(),
             io_registers: IoRegisters::new(),
-            ppu_vram: [0; 2048],
+            ppu_vram,
             ppu_palette_ram: [0; 32],
             ppu_oam: [0; 256],
             ppu_bus_address: 0,
+            cpu_open_bus_value: 0,
             interrupt_lines: InterruptLines::new(),
             pending_write: None,
+            ram_init,
         }
     }
 
+    pub(crate) fn ram_init(&self) -> RamInit {
+        self.ram_init
+    }
+
     pub fn cpu(&mut self) -> CpuBus<'_> {
         CpuBus(self)
     }
@@ -639,11 +700,21 @@ impl Bus {
         self.io_registers.p2_joypad_state = p2_joypad_state;
     }
 
+    /// The most recently polled P1 and P2 joypad states.
+    pub fn joypad_states(&self) -> (JoypadState, JoypadState) {
+        (self.io_registers.p1_joypad_state, self.io_registers.p2_joypad_state)
+    }
+
     pub fn tick(&mut self) {
         self.ppu_registers.tick(&mut self.interrupt_lines);
         self.mapper.tick(self.ppu_bus_address);
     }
 
+    // The CPU write that will be applied on the next call to tick_cpu, if any
+    pub(crate) fn pending_write(&self) -> Option<(u16, u8)> {
+        self.pending_write.map(|write| (write.address, write.value))
+    }
+
     pub fn tick_cpu(&mut self) {
         if let Some(write) = self.pending_write.take() {
             self.cpu().apply_write(write.address, write.value);
@@ -683,8 +754,10 @@ impl Bus {
             ppu_palette_ram: self.ppu_palette_ram,
             ppu_oam: self.ppu_oam,
             ppu_bus_address: self.ppu_bus_address,
+            cpu_open_bus_value: self.cpu_open_bus_value,
             interrupt_lines: self.interrupt_lines.clone(),
             pending_write: self.pending_write,
+            ram_init: self.ram_init,
         }
     }
 }
@@ -694,7 +767,8 @@ pub struct CpuBus<'a>(&'a mut Bus);
 
 impl CpuBus<'_> {
     pub fn read_address(&mut self, address: u16) -> u8 {
-        match address {
+        let open_bus = self.0.cpu_open_bus_value;
+        let value = match address {
             address @ CPU_RAM_START..=CPU_RAM_END => {
                 let ram_address = address & CPU_RAM_MASK;
                 self.0.cpu_internal_ram[ram_address as usize]
@@ -705,16 +779,25 @@ impl CpuBus<'_> {
                 self.read_ppu_register_address(ppu_register_relative_addr as usize)
             }
             address @ CPU_IO_REGISTERS_START..=CPU_IO_REGISTERS_END => {
-                self.0.io_registers.read_address(address)
+                self.0.io_registers.read_address(address, open_bus)
             }
-            _address @ CPU_IO_TEST_MODE_START..=CPU_IO_TEST_MODE_END => cpu_open_bus(address),
+            _address @ CPU_IO_TEST_MODE_START..=CPU_IO_TEST_MODE_END => open_bus,
             address @ CPU_CARTRIDGE_START..=CPU_CARTRIDGE_END => {
-                self.0.mapper.read_cpu_address(address)
+                self.0.mapper.read_cpu_address(address, open_bus)
             }
+        };
+
+        // SND_CHN is inside the CPU, so reading it does not put the value on the external data bus
+        if address != SND_CHN_ADDRESS {
+            self.0.cpu_open_bus_value = value;
         }
+
+        value
     }
 
     fn apply_write(&mut self, address: u16, value: u8) {
+        self.0.cpu_open_bus_value = value;
+
         match address {
             address @ CPU_RAM_START..=CPU_RAM_END => {
                 let ram_address = address & CPU_RAM_MASK;
@@ -755,6 +838,8 @@ impl CpuBus<'_> {
     }
 
     pub fn read_ppu_register(&mut self, register: PpuRegister) -> u8 {
+        self.0.ppu_registers.decay_open_bus();
+
         match register {
             PpuRegister::PPUCTRL
             | PpuRegister::PPUMASK
@@ -766,10 +851,8 @@ impl CpuBus<'_> {
 
                 // PPUSTATUS reads only affect bits 7-5 of open bus, bits 4-0 remain intact
                 // and are returned as part of the read
-                let ppu_status_high_bits = self.0.ppu_registers.ppu_status & 0xE0;
-                let open_bus_lower_bits = self.0.ppu_registers.ppu_open_bus_value & 0x1F;
-                self.0.ppu_registers.ppu_open_bus_value =
-                    ppu_status_high_bits | open_bus_lower_bits;
+                let ppu_status = self.0.ppu_registers.ppu_status;
+                self.0.ppu_registers.drive_open_bus(ppu_status, 0xE0);
 
                 self.0.ppu_registers.ppu_open_bus_value
             }
@@ -779,19 +862,27 @@ impl CpuBus<'_> {
                     .ppu_registers
                     .oam_open_bus_value
                     .unwrap_or(self.0.ppu_oam[self.0.ppu_registers.oam_addr as usize]);
-                self.0.ppu_registers.ppu_open_bus_value = value;
+                self.0.ppu_registers.drive_open_bus(value, 0xFF);
                 value
             }
             PpuRegister::PPUDATA => {
                 let address = self.0.ppu_bus_address;
                 let (data, buffer_read_address) = if address < 0x3F00 {
-                    (self.0.ppu_registers.ppu_data_buffer, address)
+                    self.0.ppu_registers.drive_open_bus(self.0.ppu_registers.ppu_data_buffer, 0xFF);
+                    (self.0.ppu_registers.ppu_open_bus_value, address)
                 } else {
+                    // Palette RAM is only 6 bits wide, so palette reads only drive bits 5-0 and
+                    // bits 7-6 come from open bus. Greyscale mode also applies to palette reads
                     let palette_address = map_palette_address(address);
-                    let palette_byte = self.0.ppu_palette_ram[palette_address];
+                    let mut palette_byte = self.0.ppu_palette_ram[palette_address] & 0x3F;
+                    if self.0.ppu_registers.greyscale() {
+                        palette_byte &= 0x30;
+                    }
+                    self.0.ppu_registers.drive_open_bus(palette_byte, 0x3F);
+
                     // When PPUDATA is used to read palette RAM, buffer reads mirror the nametable
                     // data located at $2F00-$2FFF
-                    (palette_byte, address - 0x1000)
+                    (self.0.ppu_registers.ppu_open_bus_value, address - 0x1000)
                 };
 
                 self.0.mapper.about_to_access_ppu_data();
@@ -802,8 +893,6 @@ impl CpuBus<'_> {
                 // actual address
                 self.0.ppu_bus_address = address;
 
-                self.0.ppu_registers.ppu_open_bus_value = data;
-
                 self.0.ppu_registers.last_accessed_register = Some(PpuTrackedRegister::PPUDATA);
 
                 data
@@ -817,7 +906,7 @@ impl CpuBus<'_> {
         };
 
         // Writes to any memory-mapped PPU register put the value on open bus
-        self.0.ppu_registers.ppu_open_bus_value = value;
+        self.0.ppu_registers.drive_open_bus(value, 0xFF);
 
         match register {
             PpuRegister::PPUCTRL => {
@@ -826,7 +915,7 @@ impl CpuBus<'_> {
                 self.0.mapper.process_ppu_ctrl_update(value);
             }
             PpuRegister::PPUMASK => {
-                log::trace!("BUS: PPUMASK set to {value:02X}");
+                log::trace!(target: PPU_TARGET, "BUS: PPUMASK set to {value:02X}");
                 self.0.ppu_registers.ppu_mask = value;
                 self.0.mapper.process_ppu_mask_update(value);
             }
@@ -957,18 +1046,113 @@ fn map_palette_address(address: u16) -> usize {
 
 #[cfg(test)]
 mod tests {
-    use crate::bus::{Bus, cartridge};
+    use crate::bus::{Bus, PPU_OPEN_BUS_DECAY_CYCLES, RamInit, RamInitPattern, cartridge};
+    use crate::input::JoypadState;
+    use crate::rng::CoreRng;
+
+    fn new_bus(mapper: cartridge::Mapper, ram_init: RamInit) -> Bus {
+        Bus::from_cartridge(mapper, ram_init, &mut CoreRng::from_seed_or_entropy(ram_init.seed))
+    }
+
+    fn write(bus: &mut Bus, address: u16, value: u8) {
+        bus.cpu().write_address(address, value);
+        bus.tick_cpu();
+    }
 
     #[test]
     fn randomized_ram_on_startup() {
         let mapper = cartridge::new_mmc1(vec![0; 32768]);
-        let bus1 = Bus::from_cartridge(mapper.clone());
-        let bus2 = Bus::from_cartridge(mapper);
+        let bus1 = new_bus(mapper.clone(), RamInit::default());
+        let bus2 = new_bus(mapper, RamInit::default());
 
         assert_ne!(bus1.cpu_internal_ram, bus2.cpu_internal_ram);
+        assert_ne!(bus1.ram_init().seed, None);
     }
-}
 
-pub(crate) fn cpu_open_bus(address: u16) -> u8 {
-    (address >> 8) as u8
+    #[test]
+    fn seeded_ram_is_deterministic() {
+        let mapper = cartridge::new_mmc1(vec![0; 32768]);
+        let ram_init = RamInit { pattern: RamInitPattern::Random, seed: Some(12345) };
+        let bus1 = new_bus(mapper.clone(), ram_init);
+        let bus2 = new_bus(mapper.clone(), ram_init);
+
+        assert_eq!(bus1.cpu_internal_ram, bus2.cpu_internal_ram);
+        assert_eq!(bus1.ppu_vram, bus2.ppu_vram);
+
+        let alternating = RamInit { pattern: RamInitPattern::Alternating, seed: None };
+        let bus = new_bus(mapper, alternating);
+        assert_eq!(bus.cpu_internal_ram[..8], [0x00, 0x00, 0x00, 0x00, 0xFF, 0xFF, 0xFF, 0xFF]);
+    }
+
+    #[test]
+    fn cpu_open_bus() {
+        let mut bus = new_bus(cartridge::new_mmc1(vec![0; 32768]), RamInit::default());
+        bus.cpu_internal_ram[0] = 0xA5;
+
+        // Unmapped and write-only addresses return the last value on the data bus
+        assert_eq!(bus.cpu().read_address(0x0000), 0xA5);
+        assert_eq!(bus.cpu().read_address(0x4018), 0xA5);
+        assert_eq!(bus.cpu().read_address(0x4000), 0xA5);
+        assert_eq!(bus.cpu().read_address(0x5000), 0xA5);
+        write(&mut bus, 0x6000, 0x3C);
+        assert_eq!(bus.cpu().read_address(0x4018), 0x3C);
+
+        // Controller reads only drive bits 4-0
+        bus.update_p1_joypad_state(JoypadState { a: true, ..JoypadState::new() });
+        bus.cpu_open_bus_value = 0xFE;
+        assert_eq!(bus.cpu().read_address(0x4016), 0xE1);
+
+        // SND_CHN reads don't drive bit 5 and don't change the data bus
+        bus.io_registers.set_apu_status(0xC1);
+        assert_eq!(bus.cpu().read_address(0x4015), 0xE1);
+        assert_eq!(bus.cpu().read_address(0x4018), 0xE1);
+        bus.cpu_open_bus_value = 0x00;
+        assert_eq!(bus.cpu().read_address(0x4015), 0xC1);
+        assert_eq!(bus.cpu().read_address(0x4018), 0x00);
+    }
+
+    #[test]
+    fn ppu_open_bus_decay() {
+        let mut bus = new_bus(cartridge::new_mmc1(vec![0; 32768]), RamInit::default());
+
+        write(&mut bus, 0x2003, 0xFF);
+        assert_eq!(bus.cpu().read_address(0x2000), 0xFF);
+
+        // PPUSTATUS reads only refresh bits 7-5
+        bus.ppu_registers.ppu_cycles = PPU_OPEN_BUS_DECAY_CYCLES / 2;
+        assert_eq!(bus.cpu().read_address(0x2002), 0xBF);
+
+        bus.ppu_registers.ppu_cycles = PPU_OPEN_BUS_DECAY_CYCLES;
+        assert_eq!(bus.cpu().read_address(0x2005), 0xA0);
+
+        bus.ppu_registers.ppu_cycles += PPU_OPEN_BUS_DECAY_CYCLES / 2;
+        assert_eq!(bus.cpu().read_address(0x2005), 0x00);
+    }
+
+    #[test]
+    fn ppudata_read_buffer() {
+        let mut bus = new_bus(cartridge::new_mmc1(vec![0; 32768]), RamInit::default());
+        bus.ppu().write_address(0x2005, 0x12);
+        bus.ppu().write_address(0x2F01, 0x34);
+        bus.ppu().write_address(0x3F01, 0xE5);
+
+        // Non-palette reads return the buffer contents from before the read
+        bus.ppu_bus_address = 0x2005;
+        assert_eq!(bus.cpu().read_address(0x2007), 0x00);
+        bus.ppu_bus_address = 0x2006;
+        assert_eq!(bus.cpu().read_address(0x2007), 0x12);
+
+        // Palette reads return immediately with bits 7-6 from open bus, and fill the buffer from
+        // the nametable underneath palette RAM
+        write(&mut bus, 0x2003, 0x80);
+        bus.ppu_bus_address = 0x3F01;
+        assert_eq!(bus.cpu().read_address(0x2007), 0xA5);
+        bus.ppu_bus_address = 0x2000;
+        assert_eq!(bus.cpu().read_address(0x2007), 0x34);
+
+        // Greyscale applies to palette reads
+        write(&mut bus, 0x2001, 0x01);
+        bus.ppu_bus_address = 0x3F01;
+        assert_eq!(bus.cpu().read_address(0x2007), 0x20);
+    }
 }
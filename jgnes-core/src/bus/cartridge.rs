@@ -1,4 +1,8 @@
+mod diagnostics;
+mod info;
 mod mappers;
+#[cfg(test)]
+mod testing;
 
 use crate::bus::cartridge::mappers::{
     Axrom, BandaiFcg, Bnrom, ChrType, Cnrom, Gxrom, Mmc1, Mmc2, Mmc3, Mmc5, Namco163, Namco175,
@@ -10,10 +14,13 @@ use bincode::error::{DecodeError, EncodeError};
 use bincode::{BorrowDecode, Decode, Encode};
 use jgnes_proc_macros::MatchEachVariantMacro;
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 use std::fmt::{Display, Formatter};
 use std::{io, mem};
 use thiserror::Error;
 
+/// The region of the emulated console, which determines CPU/PPU/APU clock rates and the number of
+/// scanlines per frame.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Encode, Decode, Serialize, Deserialize)]
 pub enum TimingMode {
     Ntsc,
@@ -36,7 +43,10 @@ impl Display for TimingMode {
     }
 }
 
+use crate::logging::MAPPER_TARGET;
 use crate::num::GetBit;
+pub use diagnostics::{RomDiagnostics, RomHeaderInfo, RomLoadError, RomProblem};
+pub use info::{CartridgeInfo, HeaderMirroring};
 #[cfg(test)]
 pub(crate) use mappers::new_mmc1;
 
@@ -46,9 +56,10 @@ struct Cartridge {
     prg_rom: Vec<u8>,
     prg_ram: Vec<u8>,
     has_ram_battery: bool,
-    prg_ram_dirty_bit: bool,
+    ram_dirty_bit: bool,
     chr_rom: Vec<u8>,
     chr_ram: Vec<u8>,
+    has_chr_ram_battery: bool,
 }
 
 // Encode and Decode are implemented explicitly instead of using derive in order to avoid
@@ -58,8 +69,9 @@ impl Encode for Cartridge {
         self.timing_mode.encode(encoder)?;
         self.prg_ram.encode(encoder)?;
         self.has_ram_battery.encode(encoder)?;
-        self.prg_ram_dirty_bit.encode(encoder)?;
+        self.ram_dirty_bit.encode(encoder)?;
         self.chr_ram.encode(encoder)?;
+        self.has_chr_ram_battery.encode(encoder)?;
 
         Ok(())
     }
@@ -70,17 +82,19 @@ impl Decode for Cartridge {
         let timing_mode = Decode::decode(decoder)?;
         let prg_ram = Decode::decode(decoder)?;
         let has_ram_battery = Decode::decode(decoder)?;
-        let prg_ram_dirty_bit = Decode::decode(decoder)?;
+        let ram_dirty_bit = Decode::decode(decoder)?;
         let chr_ram = Decode::decode(decoder)?;
+        let has_chr_ram_battery = Decode::decode(decoder)?;
 
         Ok(Self {
             timing_mode,
             prg_rom: vec![],
             prg_ram,
             has_ram_battery,
-            prg_ram_dirty_bit,
+            ram_dirty_bit,
             chr_rom: vec![],
             chr_ram,
+            has_chr_ram_battery,
         })
     }
 }
@@ -90,17 +104,19 @@ impl<'de> BorrowDecode<'de> for Cartridge {
         let timing_mode = BorrowDecode::borrow_decode(decoder)?;
         let prg_ram = BorrowDecode::borrow_decode(decoder)?;
         let has_ram_battery = BorrowDecode::borrow_decode(decoder)?;
-        let prg_ram_dirty_bit = BorrowDecode::borrow_decode(decoder)?;
+        let ram_dirty_bit = BorrowDecode::borrow_decode(decoder)?;
         let chr_ram = BorrowDecode::borrow_decode(decoder)?;
+        let has_chr_ram_battery = BorrowDecode::borrow_decode(decoder)?;
 
         Ok(Self {
             timing_mode,
             prg_rom: vec![],
             prg_ram,
             has_ram_battery,
-            prg_ram_dirty_bit,
+            ram_dirty_bit,
             chr_rom: vec![],
             chr_ram,
+            has_chr_ram_battery,
         })
     }
 }
@@ -123,7 +139,7 @@ impl Cartridge {
             let prg_ram_len = self.prg_ram.len();
             self.prg_ram[(address as usize) & (prg_ram_len - 1)] = value;
             if self.has_ram_battery {
-                self.prg_ram_dirty_bit = true;
+                self.ram_dirty_bit = true;
             }
         }
     }
@@ -139,6 +155,18 @@ impl Cartridge {
     fn set_chr_ram(&mut self, address: u32, value: u8) {
         let chr_ram_len = self.chr_ram.len();
         self.chr_ram[(address as usize) & (chr_ram_len - 1)] = value;
+        if self.has_chr_ram_battery {
+            self.ram_dirty_bit = true;
+        }
+    }
+
+    // PRG RAM followed by CHR RAM if CHR RAM is battery-backed
+    fn save_data(&self) -> Cow<'_, [u8]> {
+        if self.has_chr_ram_battery {
+            Cow::Owned([self.prg_ram.as_slice(), self.chr_ram.as_slice()].concat())
+        } else {
+            Cow::Borrowed(&self.prg_ram)
+        }
     }
 
     fn move_rom_from(&mut self, other: &mut Self) {
@@ -152,9 +180,10 @@ impl Cartridge {
             prg_rom: vec![],
             prg_ram: self.prg_ram.clone(),
             has_ram_battery: self.has_ram_battery,
-            prg_ram_dirty_bit: self.prg_ram_dirty_bit,
+            ram_dirty_bit: self.ram_dirty_bit,
             chr_rom: vec![],
             chr_ram: self.chr_ram.clone(),
+            has_chr_ram_battery: self.has_chr_ram_battery,
         }
     }
 }
@@ -205,7 +234,7 @@ pub(crate) enum Mapper {
 }
 
 impl Mapper {
-    /// Retrieve the mapper's user-readable name. Only used for logging output.
+    /// Retrieve the mapper's user-readable name, for logging and frontend display.
     pub(crate) fn name(&self) -> &'static str {
         match self {
             Self::Axrom(..) => "AxROM",
@@ -228,9 +257,10 @@ impl Mapper {
         }
     }
 
-    /// Read a value from the given address in the CPU address space.
-    pub(crate) fn read_cpu_address(&mut self, address: u16) -> u8 {
-        match_each_variant!(self, mapper => mapper.read_cpu_address(address))
+    /// Read a value from the given address in the CPU address space. `open_bus` is the value
+    /// currently on the CPU data bus, which is returned for addresses that the board does not map.
+    pub(crate) fn read_cpu_address(&mut self, address: u16, open_bus: u8) -> u8 {
+        match_each_variant!(self, mapper => mapper.read_cpu_address(address, open_bus))
     }
 
     /// Write a value to the given address in the CPU address space.
@@ -347,12 +377,22 @@ impl Mapper {
         }
 
         match_each_variant!(self, mapper => {
-            let dirty_bit = mapper.cartridge.prg_ram_dirty_bit;
-            mapper.cartridge.prg_ram_dirty_bit = false;
+            let dirty_bit = mapper.cartridge.ram_dirty_bit;
+            mapper.cartridge.ram_dirty_bit = false;
             dirty_bit
         })
     }
 
+    /// Return the data that should be persisted for the board's battery-backed memory. This is
+    /// PRG RAM followed by CHR RAM if the board has battery-backed CHR RAM, and otherwise the same
+    /// as [`Self::get_prg_ram`].
+    pub(crate) fn save_data(&self) -> Cow<'_, [u8]> {
+        match self {
+            Mapper::BandaiFcg(..) | Mapper::Namco163(..) => Cow::Borrowed(self.get_prg_ram()),
+            _ => match_each_variant!(self, mapper => mapper.cartridge.save_data()),
+        }
+    }
+
     /// Return the board's writable memory as a slice. This will be an empty slice if the board
     /// has no PRG RAM or EEPROM.
     pub(crate) fn get_prg_ram(&self) -> &[u8] {
@@ -388,8 +428,10 @@ impl Mapper {
             _ => {}
         }
 
-        !self.get_prg_ram().is_empty()
-            && match_each_variant!(self, mapper => mapper.cartridge.has_ram_battery)
+        match_each_variant!(self, mapper => {
+            (!mapper.cartridge.prg_ram.is_empty() && mapper.cartridge.has_ram_battery)
+                || mapper.cartridge.has_chr_ram_battery
+        })
     }
 
     /// Retrieve the timing mode of the cartridge (NTSC/PAL).
@@ -430,7 +472,9 @@ impl Mapper {
     }
 }
 
+/// An error encountered while parsing a ROM file.
 #[derive(Debug, Error)]
+#[non_exhaustive]
 pub enum CartridgeFileError {
     #[error("I/O error: {source}")]
     Io {
@@ -445,15 +489,20 @@ pub enum CartridgeFileError {
     MultiplePrgRamTypes,
     #[error("unsupported timing mode byte: {byte}")]
     UnsupportedTimingMode { byte: u8 },
+    #[error(
+        "file is truncated: header specifies {expected_len} bytes but file is {file_len} bytes"
+    )]
+    Truncated { expected_len: usize, file_len: usize },
 }
 
+/// The format of a ROM file's header.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum FileFormat {
+pub enum HeaderFormat {
     INes,
     Nes2Point0,
 }
 
-impl Display for FileFormat {
+impl Display for HeaderFormat {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::INes => write!(f, "iNES"),
@@ -464,13 +513,17 @@ impl Display for FileFormat {
 
 #[derive(Debug, Clone)]
 struct INesHeader {
+    format: HeaderFormat,
     mapper_number: u16,
     sub_mapper_number: u8,
-    timing_mode: TimingMode,
+    // None if the header specifies a timing mode that is not supported
+    timing_mode: Option<TimingMode>,
     prg_rom_size: u32,
     prg_ram_size: u32,
     chr_rom_size: u32,
     chr_ram_size: u32,
+    // The battery-backed portion of CHR RAM; only NES 2.0 headers can specify this
+    chr_nvram_size: u32,
     chr_type: ChrType,
     nametable_mirroring: NametableMirroring,
     has_trainer: bool,
@@ -480,7 +533,10 @@ struct INesHeader {
 
 impl INesHeader {
     fn parse_from_file(file_bytes: &[u8]) -> Result<INesHeader, CartridgeFileError> {
-        let header = &file_bytes[..16];
+        if file_bytes.len() < INES_HEADER_LEN {
+            return Err(CartridgeFileError::Format);
+        }
+        let header = &file_bytes[..INES_HEADER_LEN];
 
         // All iNES headers should begin with this 4-byte sequence, which is "NES" followed by the
         // character that MS-DOS used for EOF
@@ -508,50 +564,45 @@ impl INesHeader {
         let has_battery = header[6].bit(1);
 
         let format =
-            if header[7] & 0x0C == 0x08 { FileFormat::Nes2Point0 } else { FileFormat::INes };
-
-        log::info!("ROM header format: {format}");
+            if header[7] & 0x0C == 0x08 { HeaderFormat::Nes2Point0 } else { HeaderFormat::INes };
 
         let sub_mapper_number = match format {
-            FileFormat::Nes2Point0 => header[8] >> 4,
-            FileFormat::INes => 0,
+            HeaderFormat::Nes2Point0 => header[8] >> 4,
+            HeaderFormat::INes => 0,
         };
 
         let timing_mode = match format {
-            FileFormat::Nes2Point0 => {
+            HeaderFormat::Nes2Point0 => {
                 let timing_mode_byte = header[12] & 0x03;
                 match timing_mode_byte {
-                    0x00 | 0x02 => TimingMode::Ntsc,
-                    0x01 => TimingMode::Pal,
-                    0x03 => {
-                        return Err(CartridgeFileError::UnsupportedTimingMode {
-                            byte: timing_mode_byte,
-                        });
-                    }
+                    0x00 | 0x02 => Some(TimingMode::Ntsc),
+                    0x01 => Some(TimingMode::Pal),
+                    0x03 => None,
                     _ => unreachable!("value & 0x03 should always be 0x00/0x01/0x02/0x03"),
                 }
             }
-            FileFormat::INes => {
+            HeaderFormat::INes => {
                 if header[0].bit(0) {
-                    TimingMode::Pal
+                    Some(TimingMode::Pal)
                 } else {
-                    TimingMode::Ntsc
+                    Some(TimingMode::Ntsc)
                 }
             }
         };
 
         let prg_ram_size = determine_prg_ram_size(header, mapper_number, format);
 
-        let chr_ram_size = match (chr_type, format) {
-            (ChrType::RAM, FileFormat::Nes2Point0) => {
-                let chr_ram_shift = header[11] & 0x0F;
-                if chr_ram_shift > 0 { 64 << chr_ram_shift } else { 0 }
+        let (chr_ram_size, chr_nvram_size) = match (chr_type, format) {
+            (ChrType::RAM, HeaderFormat::Nes2Point0) => {
+                let chr_nvram_size = nes2_ram_size(header[11] >> 4);
+                (total_ram_size(nes2_ram_size(header[11] & 0x0F), chr_nvram_size), chr_nvram_size)
             }
-            (ChrType::RAM, FileFormat::INes) => 8192,
-            (ChrType::ROM, _) => 0,
+            (ChrType::RAM, HeaderFormat::INes) => (8192, 0),
+            (ChrType::ROM, _) => (0, 0),
         };
 
         Ok(Self {
+            format,
             mapper_number,
             sub_mapper_number,
             timing_mode,
@@ -559,6 +610,7 @@ impl INesHeader {
             prg_ram_size,
             chr_rom_size,
             chr_ram_size,
+            chr_nvram_size,
             chr_type,
             nametable_mirroring,
             has_trainer,
@@ -568,21 +620,17 @@ impl INesHeader {
     }
 }
 
-fn determine_prg_ram_size(header: &[u8], mapper_number: u16, format: FileFormat) -> u32 {
+fn determine_prg_ram_size(header: &[u8], mapper_number: u16, format: HeaderFormat) -> u32 {
     let prg_ram_size = match format {
-        FileFormat::Nes2Point0 => {
-            let volatile_shift = header[10] & 0x0F;
-            let non_volatile_shift = header[10] >> 4;
+        HeaderFormat::Nes2Point0 => {
             // TODO separate these? very very few games have both volatile and non-volatile RAM
-            let volatile_ram = if volatile_shift > 0 { 64 << volatile_shift } else { 0 };
-            let non_volatile_ram =
-                if non_volatile_shift > 0 { 64 << non_volatile_shift } else { 0 };
-            let total_ram = volatile_ram + non_volatile_ram;
+            let total_ram =
+                total_ram_size(nes2_ram_size(header[10] & 0x0F), nes2_ram_size(header[10] >> 4));
 
             // Hack to handle MMC5 headers that don't specify PRG RAM size but expect 32KB/64KB of
             // PRG RAM
             if mapper_number == 5 && total_ram == 0 {
-                log::info!(
+                log::info!(target: MAPPER_TARGET,
                     "Ignoring PRG RAM size of 0 in MMC5 NES 2.0 header; setting to 64KB instead"
                 );
                 None
@@ -590,7 +638,7 @@ fn determine_prg_ram_size(header: &[u8], mapper_number: u16, format: FileFormat)
                 Some(total_ram)
             }
         }
-        FileFormat::INes => None,
+        HeaderFormat::INes => None,
     };
 
     // Default to 64KB for MMC5, 8KB for all other mappers
@@ -601,8 +649,27 @@ fn determine_prg_ram_size(header: &[u8], mapper_number: u16, format: FileFormat)
     prg_ram_size.unwrap_or(default_ram_size)
 }
 
+// NES 2.0 RAM sizes are stored as shift counts, with 0 meaning no RAM
+fn nes2_ram_size(shift: u8) -> u32 {
+    if shift > 0 { 64 << shift } else { 0 }
+}
+
+// Volatile and non-volatile RAM are stored in one buffer that is addressed using a mask, so the
+// combined size is rounded up to a power of 2
+fn total_ram_size(volatile_size: u32, non_volatile_size: u32) -> u32 {
+    match volatile_size + non_volatile_size {
+        0 => 0,
+        total => total.next_power_of_two(),
+    }
+}
+
+const INES_HEADER_LEN: usize = 16;
+const TRAINER_LEN: usize = 512;
+
 /// Parse cartridge data out of an iNES file.
 ///
+/// If `forced_mapper_number` is set, it is used in place of the mapper number from the header.
+///
 /// # Errors
 ///
 /// This function will return an error if the given bytes do not appear to represent an iNES file.
@@ -610,43 +677,82 @@ pub(crate) fn from_ines_file(
     file_bytes: &[u8],
     sav_bytes: Option<Vec<u8>>,
     forced_timing_mode: Option<TimingMode>,
+    forced_mapper_number: Option<u16>,
 ) -> Result<Mapper, CartridgeFileError> {
-    let header = INesHeader::parse_from_file(file_bytes)?;
+    let mut header = INesHeader::parse_from_file(file_bytes)?;
+
+    log::info!(target: MAPPER_TARGET, "ROM header format: {}", header.format);
+
+    if let Some(mapper_number) = forced_mapper_number {
+        if mapper_number != header.mapper_number {
+            log::info!(target: MAPPER_TARGET,
+                "Forcing mapper number to {mapper_number}; ignoring mapper number {} from cartridge header",
+                header.mapper_number
+            );
+            header.mapper_number = mapper_number;
+            header.sub_mapper_number = 0;
+        }
+    }
 
     // Header is 16 bytes, trainer is 512 bytes if present
-    let prg_rom_start_address = if header.has_trainer { 16 + 512 } else { 16 } as usize;
+    let prg_rom_start_address =
+        if header.has_trainer { INES_HEADER_LEN + TRAINER_LEN } else { INES_HEADER_LEN };
     let prg_rom_end_address = prg_rom_start_address + header.prg_rom_size as usize;
     let chr_rom_end_address = prg_rom_end_address + header.chr_rom_size as usize;
 
+    if file_bytes.len() < chr_rom_end_address {
+        return Err(CartridgeFileError::Truncated {
+            expected_len: chr_rom_end_address,
+            file_len: file_bytes.len(),
+        });
+    }
+
     let prg_rom = Vec::from(&file_bytes[prg_rom_start_address..prg_rom_end_address]);
     let chr_rom = Vec::from(&file_bytes[prg_rom_end_address..chr_rom_end_address]);
 
-    let prg_ram = if let Some(sav_bytes) = &sav_bytes {
-        if sav_bytes.len() == header.prg_ram_size as usize {
-            sav_bytes.clone()
-        } else {
-            vec![0; header.prg_ram_size as usize]
+    // Save files contain PRG RAM followed by CHR RAM if CHR RAM is battery-backed. Saves from
+    // before battery-backed CHR RAM was supported contain only PRG RAM
+    let prg_ram_size = header.prg_ram_size as usize;
+    let chr_ram_size = header.chr_ram_size as usize;
+    let has_chr_ram_battery = header.chr_nvram_size != 0;
+    let (prg_ram, chr_ram) = match &sav_bytes {
+        Some(sav_bytes)
+            if has_chr_ram_battery && sav_bytes.len() == prg_ram_size + chr_ram_size =>
+        {
+            let (prg_ram, chr_ram) = sav_bytes.split_at(prg_ram_size);
+            (prg_ram.to_vec(), chr_ram.to_vec())
         }
-    } else {
-        vec![0; header.prg_ram_size as usize]
+        Some(sav_bytes) if sav_bytes.len() == prg_ram_size => {
+            (sav_bytes.clone(), vec![0; chr_ram_size])
+        }
+        _ => (vec![0; prg_ram_size], vec![0; chr_ram_size]),
     };
 
-    let timing_mode = forced_timing_mode.unwrap_or(header.timing_mode);
-    if timing_mode != header.timing_mode {
-        log::info!(
-            "Forcing timing mode to {timing_mode}; ignoring timing mode {} from cartridge header",
-            header.timing_mode
-        );
-    }
+    let timing_mode = match (forced_timing_mode, header.timing_mode) {
+        (Some(timing_mode), header_timing_mode) => {
+            if header_timing_mode != Some(timing_mode) {
+                log::info!(target: MAPPER_TARGET,
+                    "Forcing timing mode to {timing_mode}; ignoring timing mode {} from cartridge header",
+                    header_timing_mode.map_or("(unsupported)".into(), |mode| mode.to_string())
+                );
+            }
+            timing_mode
+        }
+        (None, Some(timing_mode)) => timing_mode,
+        (None, None) => {
+            return Err(CartridgeFileError::UnsupportedTimingMode { byte: file_bytes[12] & 0x03 });
+        }
+    };
 
     let cartridge = Cartridge {
         timing_mode,
         prg_rom,
         prg_ram,
         has_ram_battery: header.has_battery,
-        prg_ram_dirty_bit: header.has_battery,
+        ram_dirty_bit: header.has_battery || has_chr_ram_battery,
         chr_rom,
-        chr_ram: vec![0; header.chr_ram_size as usize],
+        chr_ram,
+        has_chr_ram_battery,
     };
 
     let chr_size = match header.chr_type {
@@ -745,19 +851,73 @@ pub(crate) fn from_ines_file(
         }
     };
 
-    log::info!("Timing mode: {timing_mode}");
-    log::info!("Mapper number: {} ({})", header.mapper_number, mapper.name());
-    log::info!("PRG ROM size: {}", header.prg_rom_size);
-    log::info!("PRG RAM size: {}", header.prg_ram_size);
-    log::info!("Cartridge has battery-backed PRG RAM: {}", header.has_battery);
-    log::info!("CHR ROM size: {}", header.chr_rom_size);
-    log::info!("CHR RAM size: {}", header.chr_ram_size);
-    log::info!("CHR memory type: {:?}", header.chr_type);
-    log::info!(
+    log::info!(target: MAPPER_TARGET, "Timing mode: {timing_mode}");
+    log::info!(target: MAPPER_TARGET, "Mapper number: {} ({})", header.mapper_number, mapper.name());
+    log::info!(target: MAPPER_TARGET, "PRG ROM size: {}", header.prg_rom_size);
+    log::info!(target: MAPPER_TARGET, "PRG RAM size: {}", header.prg_ram_size);
+    log::info!(target: MAPPER_TARGET, "Cartridge has battery-backed PRG RAM: {}", header.has_battery);
+    log::info!(target: MAPPER_TARGET, "CHR ROM size: {}", header.chr_rom_size);
+    log::info!(target: MAPPER_TARGET, "CHR RAM size: {}", header.chr_ram_size);
+    log::info!(target: MAPPER_TARGET, "Battery-backed CHR RAM size: {}", header.chr_nvram_size);
+    log::info!(target: MAPPER_TARGET, "CHR memory type: {:?}", header.chr_type);
+    log::info!(target: MAPPER_TARGET,
         "Hardwired nametable mirroring: {:?} (not applicable to all mappers)",
         header.nametable_mirroring
     );
-    log::info!("Has 4-screen nametable VRAM: {}", header.has_four_screen_vram);
+    log::info!(target: MAPPER_TARGET, "Has 4-screen nametable VRAM: {}", header.has_four_screen_vram);
 
     Ok(mapper)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bus::cartridge::testing::MapperHarness;
+
+    // MMC1 NES 2.0 header with 2x16KB PRG ROM, 32KB battery-backed PRG RAM, and 8KB CHR RAM
+    // where the upper 4KB are battery-backed
+    fn sxrom_file() -> Vec<u8> {
+        let mut rom = vec![0x4E, 0x45, 0x53, 0x1A, 2, 0, 0x12, 0x08, 0, 0, 0x90, 0x66];
+        rom.resize(INES_HEADER_LEN + 32 * 1024, 0);
+        rom
+    }
+
+    #[test]
+    fn battery_backed_chr_ram_and_banked_prg_ram() {
+        let rom = sxrom_file();
+
+        let mapper = from_ines_file(&rom, None, None, None).unwrap();
+        assert!(mapper.has_persistent_ram());
+        assert_eq!(mapper.get_prg_ram().len(), 32 * 1024);
+
+        let mut harness = MapperHarness::new(mapper);
+        harness.write_cpu(0x6000, 0x12);
+        // Select PRG RAM bank 3 through CHR bank 0
+        for i in 0..5 {
+            harness.write_cpu(0xA000, (0x0C >> i) & 0x01);
+        }
+        harness.write_cpu(0x6000, 0x34);
+        harness.mapper.write_ppu_address(0x0010, 0x56, &mut harness.vram);
+        assert!(harness.mapper.get_and_clear_ram_dirty_bit());
+
+        harness.mapper.write_ppu_address(0x0011, 0x78, &mut harness.vram);
+        assert!(harness.mapper.get_and_clear_ram_dirty_bit());
+
+        let save_data = harness.mapper.save_data().into_owned();
+        assert_eq!(save_data.len(), (32 + 8) * 1024);
+        assert_eq!(save_data[0], 0x12);
+        assert_eq!(save_data[0x6000], 0x34);
+        assert_eq!(save_data[0x8010], 0x56);
+
+        let mapper = from_ines_file(&rom, Some(save_data.clone()), None, None).unwrap();
+        let mut harness = MapperHarness::new(mapper);
+        assert_eq!(harness.read_cpu(0x6000), 0x12);
+        assert_eq!(harness.mapper.read_ppu_address(0x0011, &harness.vram), 0x78);
+
+        // Saves that only contain PRG RAM should still load
+        let mapper =
+            from_ines_file(&rom, Some(save_data[..32 * 1024].to_vec()), None, None).unwrap();
+        assert_eq!(mapper.get_prg_ram()[0x6000], 0x34);
+        assert_eq!(mapper.save_data()[0x8010], 0);
+    }
+}
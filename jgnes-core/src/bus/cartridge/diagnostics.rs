@@ -0,0 +1,306 @@
+//! Diagnostics for ROM files that fail to load.
+
+use crate::bus::TimingMode;
+use crate::bus::cartridge::mappers::NametableMirroring;
+use crate::bus::cartridge::{
+    CartridgeFileError, HeaderFormat, INES_HEADER_LEN, INesHeader, TRAINER_LEN, from_ines_file,
+};
+use std::fmt::{Display, Formatter};
+use thiserror::Error;
+
+const INES_SIGNATURE: [u8; 4] = [0x4E, 0x45, 0x53, 0x1A];
+
+/// Fields parsed out of an iNES / NES 2.0 header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct RomHeaderInfo {
+    pub format: HeaderFormat,
+    pub mapper_number: u16,
+    pub sub_mapper_number: u8,
+    /// None if the header specifies a timing mode that is not supported
+    pub timing_mode: Option<TimingMode>,
+    pub prg_rom_size: u32,
+    pub prg_ram_size: u32,
+    pub chr_rom_size: u32,
+    pub chr_ram_size: u32,
+    pub chr_nvram_size: u32,
+    pub has_trainer: bool,
+    pub has_battery: bool,
+    pub vertical_mirroring: bool,
+    pub has_four_screen_vram: bool,
+}
+
+impl RomHeaderInfo {
+    fn from_header(header: &INesHeader) -> Self {
+        Self {
+            format: header.format,
+            mapper_number: header.mapper_number,
+            sub_mapper_number: header.sub_mapper_number,
+            timing_mode: header.timing_mode,
+            prg_rom_size: header.prg_rom_size,
+            prg_ram_size: header.prg_ram_size,
+            chr_rom_size: header.chr_rom_size,
+            chr_ram_size: header.chr_ram_size,
+            chr_nvram_size: header.chr_nvram_size,
+            has_trainer: header.has_trainer,
+            has_battery: header.has_battery,
+            vertical_mirroring: header.nametable_mirroring == NametableMirroring::Vertical,
+            has_four_screen_vram: header.has_four_screen_vram,
+        }
+    }
+
+    fn expected_file_len(&self) -> usize {
+        let trainer_len = if self.has_trainer { TRAINER_LEN } else { 0 };
+        INES_HEADER_LEN + trainer_len + self.prg_rom_size as usize + self.chr_rom_size as usize
+    }
+}
+
+/// A problem detected in a ROM file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum RomProblem {
+    /// The file is too short to contain a 16-byte header
+    MissingHeader { file_len: usize },
+    /// The file does not begin with "NES" followed by $1A
+    InvalidSignature,
+    /// The file is shorter than the PRG ROM and CHR ROM sizes in the header require
+    Truncated { expected_len: usize, file_len: usize },
+    /// The file has data past the end of CHR ROM; this is ignored
+    TrailingData { extra_len: usize },
+    /// The header specifies a PRG ROM size of 0
+    EmptyPrgRom,
+    /// The header specifies a NES 2.0 timing mode that is not supported
+    UnsupportedTimingMode { byte: u8 },
+    /// The header specifies a mapper that is not implemented
+    UnsupportedMapper { mapper_number: u16 },
+    /// Unused header bytes are not zero, which usually means that an old ROM tool wrote text into
+    /// the header and that the upper 4 bits of the mapper number are garbage
+    DirtyHeader { low_mapper_number: u16 },
+}
+
+impl RomProblem {
+    /// Whether this problem prevents the ROM from loading at all, as opposed to being a warning.
+    #[must_use]
+    pub fn prevents_loading(&self) -> bool {
+        match self {
+            Self::MissingHeader { .. }
+            | Self::InvalidSignature
+            | Self::Truncated { .. }
+            | Self::UnsupportedTimingMode { .. }
+            | Self::UnsupportedMapper { .. } => true,
+            Self::TrailingData { .. } | Self::EmptyPrgRom | Self::DirtyHeader { .. } => false,
+        }
+    }
+}
+
+impl Display for RomProblem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingHeader { file_len } => {
+                write!(f, "File is only {file_len} bytes, too short to contain a 16-byte header")
+            }
+            Self::InvalidSignature => write!(
+                f,
+                "File does not begin with the iNES signature \"NES\\x1A\"; it may not be an NES ROM"
+            ),
+            Self::Truncated { expected_len, file_len } => write!(
+                f,
+                "File is truncated: header specifies {expected_len} bytes but file is {file_len} bytes"
+            ),
+            Self::TrailingData { extra_len } => {
+                write!(f, "File has {extra_len} extra bytes after CHR ROM which will be ignored")
+            }
+            Self::EmptyPrgRom => write!(f, "Header specifies a PRG ROM size of 0"),
+            Self::UnsupportedTimingMode { byte } => {
+                write!(
+                    f,
+                    "Header specifies unsupported timing mode {byte}; try forcing a timing mode"
+                )
+            }
+            Self::UnsupportedMapper { mapper_number } => {
+                write!(f, "Mapper {mapper_number} is not supported")
+            }
+            Self::DirtyHeader { low_mapper_number } => write!(
+                f,
+                "Unused header bytes 12-15 are not zero, so the mapper number may be corrupted; the intended mapper may be {low_mapper_number}"
+            ),
+        }
+    }
+}
+
+/// Summary of a ROM file's header and any problems detected in it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct RomDiagnostics {
+    pub file_len: usize,
+    /// None if the file does not have a parseable header
+    pub header: Option<RomHeaderInfo>,
+    pub problems: Vec<RomProblem>,
+}
+
+impl RomDiagnostics {
+    /// Analyze the given ROM file contents.
+    #[must_use]
+    pub fn analyze(file_bytes: &[u8]) -> Self {
+        let file_len = file_bytes.len();
+
+        if file_len < INES_HEADER_LEN {
+            return Self {
+                file_len,
+                header: None,
+                problems: vec![RomProblem::MissingHeader { file_len }],
+            };
+        }
+
+        if file_bytes[..4] != INES_SIGNATURE {
+            return Self { file_len, header: None, problems: vec![RomProblem::InvalidSignature] };
+        }
+
+        let header = match INesHeader::parse_from_file(file_bytes) {
+            Ok(header) => header,
+            // Only a bad signature or a short file can fail parsing, and both were checked above
+            Err(err) => unreachable!("header parsing should not fail after validation: {err}"),
+        };
+        let header = RomHeaderInfo::from_header(&header);
+
+        let mut problems = Vec::new();
+
+        if header.format == HeaderFormat::INes && file_bytes[12..16].iter().any(|&b| b != 0) {
+            problems
+                .push(RomProblem::DirtyHeader { low_mapper_number: u16::from(file_bytes[6] >> 4) });
+        }
+
+        if header.prg_rom_size == 0 {
+            problems.push(RomProblem::EmptyPrgRom);
+        }
+
+        let expected_len = header.expected_file_len();
+        if file_len < expected_len {
+            problems.push(RomProblem::Truncated { expected_len, file_len });
+        } else if file_len > expected_len {
+            problems.push(RomProblem::TrailingData { extra_len: file_len - expected_len });
+        }
+
+        if header.timing_mode.is_none() {
+            problems.push(RomProblem::UnsupportedTimingMode { byte: file_bytes[12] & 0x03 });
+        }
+
+        // Actually attempt to create the mapper so that this can never disagree with the loader
+        // about which mappers are supported
+        if let Err(CartridgeFileError::UnsupportedMapper { mapper_number }) =
+            from_ines_file(file_bytes, None, Some(TimingMode::Ntsc), None)
+        {
+            problems.push(RomProblem::UnsupportedMapper { mapper_number });
+        }
+
+        Self { file_len, header: Some(header), problems }
+    }
+
+    /// The mapper number from the header, if the header specifies a mapper that is not supported.
+    #[must_use]
+    pub fn unsupported_mapper(&self) -> Option<u16> {
+        self.problems.iter().find_map(|problem| match problem {
+            &RomProblem::UnsupportedMapper { mapper_number } => Some(mapper_number),
+            _ => None,
+        })
+    }
+}
+
+impl Display for RomDiagnostics {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "File size: {} bytes", self.file_len)?;
+
+        if let Some(header) = &self.header {
+            writeln!(f, "Header format: {}", header.format)?;
+            writeln!(
+                f,
+                "Mapper: {} (submapper {})",
+                header.mapper_number, header.sub_mapper_number
+            )?;
+            match header.timing_mode {
+                Some(timing_mode) => writeln!(f, "Timing mode: {timing_mode}")?,
+                None => writeln!(f, "Timing mode: (unsupported)")?,
+            }
+            writeln!(f, "PRG ROM size: {}", header.prg_rom_size)?;
+            writeln!(f, "PRG RAM size: {}", header.prg_ram_size)?;
+            writeln!(f, "CHR ROM size: {}", header.chr_rom_size)?;
+            writeln!(f, "CHR RAM size: {}", header.chr_ram_size)?;
+            writeln!(f, "Battery-backed CHR RAM size: {}", header.chr_nvram_size)?;
+            writeln!(f, "Battery-backed PRG RAM: {}", header.has_battery)?;
+            writeln!(f, "Trainer: {}", header.has_trainer)?;
+            writeln!(
+                f,
+                "Hardwired nametable mirroring: {}",
+                if header.vertical_mirroring { "Vertical" } else { "Horizontal" }
+            )?;
+            writeln!(f, "4-screen nametable VRAM: {}", header.has_four_screen_vram)?;
+        }
+
+        if self.problems.is_empty() {
+            write!(f, "No problems detected")?;
+        } else {
+            write!(f, "Problems:")?;
+            for problem in &self.problems {
+                write!(f, "\n  {problem}")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Error returned when cartridge data cannot be loaded, along with diagnostics about the ROM file.
+#[derive(Debug, Error)]
+#[error("Error loading cartridge ROM: {source}")]
+#[non_exhaustive]
+pub struct RomLoadError {
+    #[source]
+    pub source: CartridgeFileError,
+    pub diagnostics: Box<RomDiagnostics>,
+}
+
+impl RomLoadError {
+    pub(crate) fn new(source: CartridgeFileError, file_bytes: &[u8]) -> Self {
+        Self { source, diagnostics: Box::new(RomDiagnostics::analyze(file_bytes)) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rom_header(mapper_number: u8, prg_banks: u8, chr_banks: u8) -> Vec<u8> {
+        let mut rom = vec![0x4E, 0x45, 0x53, 0x1A, prg_banks, chr_banks];
+        rom.push(mapper_number << 4);
+        rom.push(mapper_number & 0xF0);
+        rom.resize(INES_HEADER_LEN, 0);
+        rom
+    }
+
+    #[test]
+    fn detects_unsupported_mapper_and_truncation() {
+        let mut rom = rom_header(0xF3, 2, 1);
+        rom.resize(INES_HEADER_LEN + 32 * 1024 + 8 * 1024, 0);
+        let diagnostics = RomDiagnostics::analyze(&rom);
+        assert_eq!(diagnostics.unsupported_mapper(), Some(0xF3));
+        assert_eq!(diagnostics.header.as_ref().map(|header| header.mapper_number), Some(0xF3));
+
+        rom.truncate(INES_HEADER_LEN + 100);
+        let diagnostics = RomDiagnostics::analyze(&rom);
+        assert!(diagnostics.problems.contains(&RomProblem::Truncated {
+            expected_len: INES_HEADER_LEN + 40 * 1024,
+            file_len: INES_HEADER_LEN + 100,
+        }));
+
+        let diagnostics = RomDiagnostics::analyze(&rom[..10]);
+        assert_eq!(diagnostics.problems, vec![RomProblem::MissingHeader { file_len: 10 }]);
+    }
+
+    #[test]
+    fn valid_nrom_has_no_problems() {
+        let mut rom = rom_header(0, 2, 1);
+        rom.resize(INES_HEADER_LEN + 32 * 1024 + 8 * 1024, 0);
+        let diagnostics = RomDiagnostics::analyze(&rom);
+        assert!(diagnostics.problems.is_empty(), "{:?}", diagnostics.problems);
+    }
+}
@@ -0,0 +1,123 @@
+//! Summary of the loaded cartridge for display in frontends.
+
+use crate::bus::TimingMode;
+use crate::bus::cartridge::mappers::NametableMirroring;
+use crate::bus::cartridge::{INesHeader, Mapper};
+use serde::Serialize;
+use std::fmt::{Display, Formatter};
+
+/// Nametable mirroring as specified in the cartridge header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum HeaderMirroring {
+    Horizontal,
+    Vertical,
+    FourScreen,
+}
+
+impl Display for HeaderMirroring {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Horizontal => write!(f, "Horizontal"),
+            Self::Vertical => write!(f, "Vertical"),
+            Self::FourScreen => write!(f, "4-screen"),
+        }
+    }
+}
+
+/// Information about the currently loaded cartridge, after applying any forced mapper number or
+/// timing mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[non_exhaustive]
+pub struct CartridgeInfo {
+    pub mapper_number: u16,
+    pub mapper_name: &'static str,
+    pub prg_rom_size: u32,
+    pub prg_ram_size: u32,
+    pub chr_rom_size: u32,
+    pub chr_ram_size: u32,
+    /// Hardwired mirroring from the header; many boards ignore this and control mirroring
+    /// themselves
+    pub mirroring: HeaderMirroring,
+    /// Whether the cartridge has battery-backed RAM or EEPROM that persists between sessions
+    pub has_battery: bool,
+    pub timing_mode: TimingMode,
+    /// Timing mode from the header, or None if the header specifies a mode that is not supported;
+    /// this differs from `timing_mode` if a timing mode was forced
+    pub header_timing_mode: Option<TimingMode>,
+}
+
+impl CartridgeInfo {
+    pub(crate) fn new(
+        mapper: &Mapper,
+        file_bytes: &[u8],
+        forced_mapper_number: Option<u16>,
+    ) -> Self {
+        let header = INesHeader::parse_from_file(file_bytes)
+            .expect("header should always be valid once the cartridge has loaded");
+
+        let mirroring = if header.has_four_screen_vram {
+            HeaderMirroring::FourScreen
+        } else if header.nametable_mirroring == NametableMirroring::Vertical {
+            HeaderMirroring::Vertical
+        } else {
+            HeaderMirroring::Horizontal
+        };
+
+        Self {
+            mapper_number: forced_mapper_number.unwrap_or(header.mapper_number),
+            mapper_name: mapper.name(),
+            prg_rom_size: header.prg_rom_size,
+            prg_ram_size: mapper.get_prg_ram().len() as u32,
+            chr_rom_size: header.chr_rom_size,
+            chr_ram_size: header.chr_ram_size,
+            mirroring,
+            has_battery: mapper.has_persistent_ram(),
+            timing_mode: mapper.timing_mode(),
+            header_timing_mode: header.timing_mode,
+        }
+    }
+
+    /// Return the header's timing mode if the cartridge is running in a different timing mode,
+    /// which makes the game run at the wrong speed.
+    #[must_use]
+    pub fn timing_mode_mismatch(&self) -> Option<TimingMode> {
+        self.header_timing_mode.filter(|&header_timing_mode| header_timing_mode != self.timing_mode)
+    }
+}
+
+impl Display for CartridgeInfo {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} (mapper {}), {}", self.mapper_name, self.mapper_number, self.timing_mode)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bus::cartridge::{INES_HEADER_LEN, from_ines_file};
+
+    #[test]
+    fn reports_forced_settings_and_header_fields() {
+        // MMC1 header with 2x16KB PRG ROM, CHR RAM, battery, and vertical mirroring
+        let mut rom = vec![0x4E, 0x45, 0x53, 0x1A, 2, 0, 0x13, 0x00];
+        rom.resize(INES_HEADER_LEN + 32 * 1024, 0);
+
+        let mapper = from_ines_file(&rom, None, None, None).unwrap();
+        let info = CartridgeInfo::new(&mapper, &rom, None);
+        assert_eq!(info.mapper_number, 1);
+        assert_eq!(info.prg_rom_size, 32 * 1024);
+        assert_eq!(info.chr_rom_size, 0);
+        assert_eq!(info.chr_ram_size, 8 * 1024);
+        assert_eq!(info.mirroring, HeaderMirroring::Vertical);
+        assert!(info.has_battery);
+        assert_eq!(info.timing_mode, TimingMode::Ntsc);
+        assert_eq!(info.timing_mode_mismatch(), None);
+
+        let mapper = from_ines_file(&rom, None, Some(TimingMode::Pal), Some(2)).unwrap();
+        let info = CartridgeInfo::new(&mapper, &rom, Some(2));
+        assert_eq!(info.mapper_number, 2);
+        assert_eq!(info.mapper_name, mapper.name());
+        assert_eq!(info.timing_mode, TimingMode::Pal);
+        assert_eq!(info.timing_mode_mismatch(), Some(TimingMode::Ntsc));
+    }
+}
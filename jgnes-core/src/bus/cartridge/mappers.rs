@@ -12,7 +12,6 @@ mod sunsoft;
 use crate::bus::cartridge::Cartridge;
 use bincode::{Decode, Encode};
 
-use crate::bus;
 pub(crate) use bandai::BandaiFcg;
 pub(crate) use konami::{Vrc4, Vrc6, Vrc7};
 pub(crate) use mmc1::Mmc1;
@@ -67,15 +66,15 @@ impl NametableMirroring {
 pub(crate) enum CpuMapResult {
     PrgROM(u32),
     PrgRAM(u32),
-    None { original_address: u16 },
+    None,
 }
 
 impl CpuMapResult {
-    fn read(self, cartridge: &Cartridge) -> u8 {
+    fn read(self, cartridge: &Cartridge, open_bus: u8) -> u8 {
         match self {
             Self::PrgROM(address) => cartridge.get_prg_rom(address),
             Self::PrgRAM(address) => cartridge.get_prg_ram(address),
-            Self::None { original_address } => bus::cpu_open_bus(original_address),
+            Self::None => open_bus,
         }
     }
 
@@ -177,9 +176,10 @@ pub(crate) fn new_mmc1(prg_rom: Vec<u8>) -> super::Mapper {
             prg_rom,
             prg_ram: vec![0; 8192],
             has_ram_battery: false,
-            prg_ram_dirty_bit: false,
+            ram_dirty_bit: false,
             chr_rom: vec![0; 8192],
             chr_ram: Vec::new(),
+            has_chr_ram_battery: false,
         },
         data: Mmc1::new(ChrType::ROM),
     })
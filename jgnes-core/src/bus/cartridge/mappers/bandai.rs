@@ -2,10 +2,10 @@
 
 mod eeprom;
 
-use crate::bus;
 use crate::bus::cartridge::mappers::bandai::eeprom::{X24C01Chip, X24C02Chip};
 use crate::bus::cartridge::mappers::{BankSizeKb, ChrType, NametableMirroring, PpuMapResult};
 use crate::bus::cartridge::{HasBasicPpuMapping, MapperImpl};
+use crate::logging::MAPPER_TARGET;
 use crate::num::GetBit;
 use bincode::{Decode, Encode};
 
@@ -125,7 +125,7 @@ impl BandaiFcg {
             _ => None,
         };
 
-        log::info!("Bandai FCG variant: {variant:?}");
+        log::info!(target: MAPPER_TARGET, "Bandai FCG variant: {variant:?}");
 
         Self {
             variant,
@@ -141,29 +141,29 @@ impl BandaiFcg {
     }
 }
 
-fn eeprom_read(address: u16, data: bool) -> u8 {
-    (bus::cpu_open_bus(address) & 0xEF) | (u8::from(data) << 4)
+fn eeprom_read(open_bus: u8, data: bool) -> u8 {
+    (open_bus & 0xEF) | (u8::from(data) << 4)
 }
 
 impl MapperImpl<BandaiFcg> {
-    pub(crate) fn read_cpu_address(&mut self, address: u16) -> u8 {
+    pub(crate) fn read_cpu_address(&mut self, address: u16, open_bus: u8) -> u8 {
         match address {
             0x0000..=0x401F => panic!("invalid CPU map address: {address:04X}"),
-            0x4020..=0x5FFF => bus::cpu_open_bus(address),
+            0x4020..=0x5FFF => open_bus,
             0x6000..=0x7FFF => match self.data.variant {
-                Variant::Fcg | Variant::Lz93D50(MemoryVariant::None) => bus::cpu_open_bus(address),
+                Variant::Fcg | Variant::Lz93D50(MemoryVariant::None) => open_bus,
                 Variant::Lz93D50(MemoryVariant::RAM) => {
                     if self.data.ram_enabled {
                         self.cartridge.get_prg_ram((address & 0x1FFF).into())
                     } else {
-                        bus::cpu_open_bus(address)
+                        open_bus
                     }
                 }
                 Variant::Lz93D50(MemoryVariant::X24C01 | MemoryVariant::X24C02)
                 | Variant::Unknown => match &self.data.eeprom {
-                    Some(Eeprom::X24C01(chip)) => eeprom_read(address, chip.handle_read()),
-                    Some(Eeprom::X24C02(chip)) => eeprom_read(address, chip.handle_read()),
-                    None => bus::cpu_open_bus(address),
+                    Some(Eeprom::X24C01(chip)) => eeprom_read(open_bus, chip.handle_read()),
+                    Some(Eeprom::X24C02(chip)) => eeprom_read(open_bus, chip.handle_read()),
+                    None => open_bus,
                 },
             },
             0x8000..=0xBFFF => {
@@ -190,7 +190,7 @@ impl MapperImpl<BandaiFcg> {
     }
 
     pub(crate) fn write_cpu_address(&mut self, address: u16, value: u8) {
-        log::trace!("Wrote address={address:04X}, value={value:02X}");
+        log::trace!(target: MAPPER_TARGET, "Wrote address={address:04X}, value={value:02X}");
         match (self.data.variant, address) {
             (_, 0x0000..=0x401F) => panic!("invalid CPU map address: {address:04X}"),
             (Variant::Fcg | Variant::Unknown, 0x6000..=0x7FFF)
@@ -309,3 +309,70 @@ impl HasBasicPpuMapping for MapperImpl<BandaiFcg> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::bus::cartridge::mappers::NametableMirroring;
+    use crate::bus::cartridge::testing::{OPEN_BUS, TestRom};
+
+    #[test]
+    fn fcg_registers() {
+        // FCG boards have registers at $6000-$7FFF
+        let mut harness = TestRom::new(16, 256, 256).sub_mapper(4).load();
+        harness.write_cpu(0x8008, 3);
+        assert_eq!(harness.prg_bank(0x8000), 0);
+        harness.write_cpu(0x6008, 3);
+        harness.write_cpu(0x6003, 200);
+        assert_eq!(harness.prg_bank(0x8000), 6);
+        assert_eq!(harness.prg_bank(0xC000), 30);
+        assert_eq!(harness.chr_bank(0x0C00), 200);
+
+        harness.write_cpu(0x6009, 0x02);
+        assert_eq!(harness.nametable_mirroring(), Some(NametableMirroring::SingleScreenBank0));
+
+        // The FCG IRQ counter is written directly
+        harness.write_cpu(0x600B, 3);
+        harness.write_cpu(0x600C, 0);
+        harness.write_cpu(0x600A, 1);
+        harness.clock_cpu(1);
+        assert!(!harness.irq());
+        harness.clock_cpu(1);
+        assert!(harness.irq());
+    }
+
+    #[test]
+    fn lz93d50_registers() {
+        let mut harness = TestRom::new(16, 256, 256).sub_mapper(5).load();
+        harness.write_cpu(0x8008, 3);
+        harness.write_cpu(0x8009, 0x01);
+        assert_eq!(harness.prg_bank(0x8000), 6);
+        assert_eq!(harness.nametable_mirroring(), Some(NametableMirroring::Horizontal));
+
+        // The LZ93D50 IRQ counter is reloaded from a latch when IRQs are enabled
+        harness.write_cpu(0x800B, 5);
+        harness.write_cpu(0x800C, 0);
+        harness.write_cpu(0x800A, 1);
+        harness.clock_cpu(3);
+        assert!(!harness.irq());
+        harness.clock_cpu(1);
+        assert!(harness.irq());
+
+        harness.write_cpu(0x800A, 0);
+        assert!(!harness.irq());
+    }
+
+    #[test]
+    fn mapper_153_outer_bank_and_ram() {
+        let mut harness = TestRom::new(153, 512, 0).load();
+        harness.write_cpu(0x8000, 1);
+        harness.write_cpu(0x8008, 2);
+        assert_eq!(harness.prg_bank(0x8000), 36);
+        assert_eq!(harness.prg_bank(0xC000), 62);
+
+        harness.write_cpu(0x6000, 0x12);
+        assert_eq!(harness.read_cpu(0x6000), OPEN_BUS);
+        harness.write_cpu(0x800D, 0x20);
+        harness.write_cpu(0x6000, 0x12);
+        assert_eq!(harness.read_cpu(0x6000), 0x12);
+    }
+}
@@ -1,5 +1,6 @@
 //! Code for the X24C01 and X24C02 EEPROM chips, which are used in some of Bandai's FCG boards.
 
+use crate::logging::MAPPER_TARGET;
 use crate::num::GetBit;
 use bincode::{Decode, Encode};
 use std::fmt::Debug;
@@ -71,7 +72,7 @@ impl EepromState for X24C01State {
                 } else {
                     let bits_received = (bits_received << 1) | u8::from(data);
                     if bits_remaining == 1 {
-                        log::trace!("Writing {bits_received:02X} to {address:02X}");
+                        log::trace!(target: MAPPER_TARGET, "Writing {bits_received:02X} to {address:02X}");
                         memory[address as usize] = bits_received;
                         *dirty = true;
                     }
@@ -127,7 +128,7 @@ impl Default for X24C02State {
 
 impl EepromState for X24C02State {
     fn start(self) -> Self {
-        log::trace!("transitioning to Start from {self:?}");
+        log::trace!(target: MAPPER_TARGET, "transitioning to Start from {self:?}");
         match self {
             Self::Standby { address }
             | Self::Stopped { address }
@@ -139,7 +140,7 @@ impl EepromState for X24C02State {
     }
 
     fn stop(self) -> Self {
-        log::trace!("transitioning to Stop from {self:?}");
+        log::trace!(target: MAPPER_TARGET, "transitioning to Stop from {self:?}");
         match self {
             Self::Standby { address }
             | Self::Stopped { address }
@@ -257,7 +258,7 @@ impl<State: EepromState + Debug, const N: usize> EepromChip<State, N> {
     }
 
     pub fn handle_read(&self) -> bool {
-        log::trace!("EEPROM read");
+        log::trace!(target: MAPPER_TARGET, "EEPROM read");
         self.state.read(&self.memory).unwrap_or(false)
     }
 
@@ -265,7 +266,7 @@ impl<State: EepromState + Debug, const N: usize> EepromChip<State, N> {
         let data = value.bit(6);
         let clock = value.bit(5);
 
-        log::trace!("EEPROM write: {value:02X}");
+        log::trace!(target: MAPPER_TARGET, "EEPROM write: {value:02X}");
         if self.last_clock && clock && data != self.last_data {
             if data {
                 // Low to high
@@ -277,7 +278,7 @@ impl<State: EepromState + Debug, const N: usize> EepromChip<State, N> {
         } else if !self.last_clock && clock {
             let last_state = self.state;
             self.state = self.state.clock(data, &mut self.memory, &mut self.dirty);
-            log::trace!("transitioned from {last_state:?} to {:?}, data is {data}", self.state);
+            log::trace!(target: MAPPER_TARGET, "transitioned from {last_state:?} to {:?}, data is {data}", self.state);
         }
 
         self.last_data = data;
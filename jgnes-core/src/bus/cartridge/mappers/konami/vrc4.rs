@@ -1,11 +1,11 @@
 //! Code for Konami's VRC2 and VRC4 boards (iNES mappers 21 + 22 + 23 + 25).
 
-use crate::bus;
 use crate::bus::cartridge::mappers::konami::irq::VrcIrqCounter;
 use crate::bus::cartridge::mappers::{
     BankSizeKb, ChrType, NametableMirroring, PpuMapResult, konami,
 };
 use crate::bus::cartridge::{HasBasicPpuMapping, MapperImpl};
+use crate::logging::MAPPER_TARGET;
 use crate::num::GetBit;
 use bincode::{Decode, Encode};
 
@@ -150,7 +150,7 @@ impl Vrc4 {
             }
         };
 
-        log::info!("VRC2/VRC4 variant: {}", variant.name());
+        log::info!(target: MAPPER_TARGET, "VRC2/VRC4 variant: {}", variant.name());
 
         Self {
             variant,
@@ -168,20 +168,17 @@ impl Vrc4 {
 }
 
 impl MapperImpl<Vrc4> {
-    pub(crate) fn read_cpu_address(&self, address: u16) -> u8 {
+    pub(crate) fn read_cpu_address(&self, address: u16, open_bus: u8) -> u8 {
         match (self.data.variant.to_type(), address) {
             (_, 0x0000..=0x401F) => panic!("invalid CPU map address: {address:04X}"),
-            (_, 0x4020..=0x5FFF) => bus::cpu_open_bus(address),
+            (_, 0x4020..=0x5FFF) => open_bus,
             (Type::Vrc2, 0x6000..=0x7FFF) => {
                 if !self.cartridge.prg_ram.is_empty() {
                     self.cartridge.get_prg_ram((address & 0x1FFF).into())
+                } else if address < 0x7000 {
+                    (open_bus & 0xFE) | self.data.vrc2_ram_bit
                 } else {
-                    let open_bus = bus::cpu_open_bus(address);
-                    if address < 0x7000 {
-                        (open_bus & 0xFE) | self.data.vrc2_ram_bit
-                    } else {
-                        open_bus
-                    }
+                    open_bus
                 }
             }
             (Type::Vrc4, 0x6000..=0x7FFF) => {
@@ -191,10 +188,10 @@ impl MapperImpl<Vrc4> {
                         (2048, 0x6000..=0x6FFF) => {
                             self.cartridge.get_prg_ram((address & 0x07FF).into())
                         }
-                        _ => bus::cpu_open_bus(address),
+                        _ => open_bus,
                     }
                 } else {
-                    bus::cpu_open_bus(address)
+                    open_bus
                 }
             }
             (_, 0x8000..=0xFFFF) => match (self.data.prg_mode, address) {
@@ -229,7 +226,7 @@ impl MapperImpl<Vrc4> {
     }
 
     pub(crate) fn write_cpu_address(&mut self, address: u16, value: u8) {
-        log::trace!("CPU write: address={address:04X}, value={value:02X}");
+        log::trace!(target: MAPPER_TARGET, "CPU write: address={address:04X}, value={value:02X}");
         match address {
             0x0000..=0x401F => panic!("invalid CPU map address: {address:04X}"),
             0x4020..=0x5FFF => {}
@@ -382,3 +379,77 @@ impl HasBasicPpuMapping for MapperImpl<Vrc4> {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::bus::cartridge::mappers::NametableMirroring;
+    use crate::bus::cartridge::testing::TestRom;
+
+    #[test]
+    fn bank_switching() {
+        // VRC4f connects A0 and A1 directly to the register select lines
+        let mut harness = TestRom::new(23, 256, 256).sub_mapper(1).load();
+        harness.write_cpu(0x8000, 5);
+        harness.write_cpu(0xA000, 6);
+        assert_eq!(
+            [0x8000, 0xA000, 0xC000, 0xE000].map(|address| harness.prg_bank(address)),
+            [5, 6, 30, 31]
+        );
+        harness.write_cpu(0x9002, 0x02);
+        assert_eq!(
+            [0x8000, 0xA000, 0xC000, 0xE000].map(|address| harness.prg_bank(address)),
+            [30, 6, 5, 31]
+        );
+
+        harness.write_cpu(0xB000, 0x03);
+        harness.write_cpu(0xB001, 0x01);
+        harness.write_cpu(0xE002, 0x04);
+        assert_eq!(harness.chr_bank(0x0000), 0x13);
+        assert_eq!(harness.chr_bank(0x1C00), 4);
+
+        harness.write_cpu(0x9000, 0x01);
+        assert_eq!(harness.nametable_mirroring(), Some(NametableMirroring::Horizontal));
+    }
+
+    #[test]
+    fn address_line_remapping() {
+        // VRC4c uses A6 and A7 as the register select lines
+        let mut harness = TestRom::new(21, 256, 256).sub_mapper(2).load();
+        harness.write_cpu(0x8000, 5);
+        harness.write_cpu(0x9080, 0x02);
+        assert_eq!(harness.prg_bank(0xC000), 5);
+
+        harness.write_cpu(0xB000, 0x01);
+        harness.write_cpu(0xB040, 0x01);
+        assert_eq!(harness.chr_bank(0x0000), 0x11);
+    }
+
+    #[test]
+    fn irq_counter() {
+        let mut harness = TestRom::new(23, 256, 256).sub_mapper(1).load();
+
+        // Cycle mode with a reload value of $FD; the store instruction's final cycle clocks the
+        // counter once
+        harness.write_cpu(0xF000, 0x0D);
+        harness.write_cpu(0xF001, 0x0F);
+        harness.write_cpu(0xF002, 0x06);
+        harness.clock_cpu(1);
+        assert!(!harness.irq());
+        harness.clock_cpu(1);
+        assert!(harness.irq());
+
+        // Acknowledging copies the "enable after acknowledgement" bit, which was not set
+        harness.write_cpu(0xF003, 0);
+        assert!(!harness.irq());
+        harness.clock_cpu(1000);
+        assert!(!harness.irq());
+
+        // Scanline mode divides the CPU clock by ~113.667
+        harness.write_cpu(0xF000, 0x0F);
+        harness.write_cpu(0xF002, 0x02);
+        harness.clock_cpu(112);
+        assert!(!harness.irq());
+        harness.clock_cpu(1);
+        assert!(harness.irq());
+    }
+}
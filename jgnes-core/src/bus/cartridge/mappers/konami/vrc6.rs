@@ -1,12 +1,12 @@
 //! Code for Konami's VRC6 board (iNES mappers 24 + 26).
 
 use crate::apu::units::PhaseTimer;
-use crate::bus;
 use crate::bus::cartridge::mappers::konami::irq::VrcIrqCounter;
 use crate::bus::cartridge::mappers::{
     BankSizeKb, ChrType, NametableMirroring, PpuMapResult, konami,
 };
 use crate::bus::cartridge::{HasBasicPpuMapping, MapperImpl};
+use crate::logging::MAPPER_TARGET;
 use crate::num::GetBit;
 use bincode::{Decode, Encode};
 
@@ -173,7 +173,7 @@ impl Vrc6 {
             _ => panic!("invalid VRC6 mapper number, expected 24/26: {mapper_number}"),
         };
 
-        log::info!("VRC6 variant: {variant:?}");
+        log::info!(target: MAPPER_TARGET, "VRC6 variant: {variant:?}");
 
         Self {
             variant,
@@ -192,15 +192,15 @@ impl Vrc6 {
 }
 
 impl MapperImpl<Vrc6> {
-    pub(crate) fn read_cpu_address(&self, address: u16) -> u8 {
+    pub(crate) fn read_cpu_address(&self, address: u16, open_bus: u8) -> u8 {
         match address {
             0x0000..=0x401F => panic!("invalid CPU map address: {address:04X}"),
-            0x4020..=0x5FFF => bus::cpu_open_bus(address),
+            0x4020..=0x5FFF => open_bus,
             0x6000..=0x7FFF => {
                 if self.data.ram_enabled && !self.cartridge.prg_ram.is_empty() {
                     self.cartridge.get_prg_ram((address & 0x1FFF).into())
                 } else {
-                    bus::cpu_open_bus(address)
+                    open_bus
                 }
             }
             0x8000..=0xBFFF => {
@@ -344,3 +344,69 @@ impl HasBasicPpuMapping for MapperImpl<Vrc6> {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::bus::cartridge::mappers::NametableMirroring;
+    use crate::bus::cartridge::testing::{OPEN_BUS, TestRom};
+
+    #[test]
+    fn bank_switching() {
+        let mut harness = TestRom::new(24, 256, 256).load();
+        harness.write_cpu(0x8000, 3);
+        harness.write_cpu(0xC000, 9);
+        assert_eq!(
+            [0x8000, 0xA000, 0xC000, 0xE000].map(|address| harness.prg_bank(address)),
+            [6, 7, 9, 31]
+        );
+
+        for (address, bank) in
+            [0xD000, 0xD001, 0xD002, 0xD003, 0xE000, 0xE001, 0xE002, 0xE003].into_iter().zip(10..)
+        {
+            harness.write_cpu(address, bank);
+        }
+        assert_eq!(
+            [0x0000, 0x0400, 0x0800, 0x0C00, 0x1000, 0x1400, 0x1800, 0x1C00]
+                .map(|address| harness.chr_bank(address)),
+            [10, 11, 12, 13, 14, 15, 16, 17]
+        );
+
+        harness.write_cpu(0xB003, 0x04);
+        assert_eq!(harness.nametable_mirroring(), Some(NametableMirroring::Horizontal));
+        assert_eq!(harness.read_cpu(0x6000), OPEN_BUS);
+        harness.write_cpu(0xB003, 0x84);
+        harness.write_cpu(0x6000, 0x12);
+        assert_eq!(harness.read_cpu(0x6000), 0x12);
+    }
+
+    #[test]
+    fn swapped_address_lines() {
+        // Mapper 26 swaps A0 and A1
+        let mut harness = TestRom::new(26, 256, 256).load();
+        harness.write_cpu(0xD001, 20);
+        harness.write_cpu(0xD002, 21);
+        assert_eq!(harness.chr_bank(0x0400), 21);
+        assert_eq!(harness.chr_bank(0x0800), 20);
+    }
+
+    #[test]
+    fn irq_counter() {
+        let mut harness = TestRom::new(24, 256, 256).load();
+        // Cycle mode with enable after acknowledgement; the store instruction's final cycle
+        // clocks the counter once
+        harness.write_cpu(0xF000, 0xFE);
+        harness.write_cpu(0xF001, 0x07);
+        assert!(!harness.irq());
+        harness.clock_cpu(1);
+        assert!(harness.irq());
+
+        // The counter was reloaded and is still enabled after acknowledgement. Write directly to
+        // the mapper because the counter overflows every 2 cycles
+        harness.mapper.write_cpu_address(0xF002, 0);
+        assert!(!harness.irq());
+        harness.clock_cpu(1);
+        assert!(!harness.irq());
+        harness.clock_cpu(1);
+        assert!(harness.irq());
+    }
+}
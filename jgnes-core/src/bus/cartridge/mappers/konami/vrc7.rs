@@ -8,12 +8,12 @@
 //! Sega Master System emulator:
 //! <https://github.com/jsgroth/jgenesis/blob/master/smsgg-core/src/ym2413.rs>
 
-use crate::bus;
 use crate::bus::cartridge::mappers::konami::irq::VrcIrqCounter;
 use crate::bus::cartridge::mappers::{
     BankSizeKb, ChrType, NametableMirroring, PpuMapResult, konami,
 };
 use crate::bus::cartridge::{HasBasicPpuMapping, MapperImpl};
+use crate::logging::MAPPER_TARGET;
 use crate::num::GetBit;
 use bincode::{Decode, Encode};
 use std::sync::OnceLock;
@@ -490,7 +490,7 @@ impl Channel {
     fn write_register_1(&mut self, value: u8) {
         self.settings.f_number = (self.settings.f_number & 0xFF00) | u16::from(value);
 
-        log::trace!("F-number: {:03X}", self.settings.f_number);
+        log::trace!(target: MAPPER_TARGET, "F-number: {:03X}", self.settings.f_number);
     }
 
     fn write_register_2(&mut self, value: u8) {
@@ -499,6 +499,7 @@ impl Channel {
         self.settings.sustain = value.bit(5);
 
         log::trace!(
+            target: MAPPER_TARGET,
             "F-number: {:03X}, Block: {}, Channel Sustain: {}",
             self.settings.f_number,
             self.settings.block,
@@ -513,6 +514,7 @@ impl Channel {
         self.settings.instrument = value >> 4;
 
         log::trace!(
+            target: MAPPER_TARGET,
             "Volume: {:02X}, Instrument: {}",
             self.settings.volume,
             self.settings.instrument
@@ -521,7 +523,7 @@ impl Channel {
 
     fn set_key_on(&mut self, key_on: bool) {
         if self.modulator.envelope.key_on != key_on {
-            log::trace!("State at key on ({key_on}): {self:?}");
+            log::trace!(target: MAPPER_TARGET, "State at key on ({key_on}): {self:?}");
         }
 
         self.modulator.set_key_on(key_on);
@@ -710,7 +712,11 @@ impl Vrc7AudioUnit {
     }
 
     pub fn write_data(&mut self, value: u8) {
-        log::trace!("Write to register {:02X}: {value:02X}", self.selected_register);
+        log::trace!(
+            target: MAPPER_TARGET,
+            "Write to register {:02X}: {value:02X}",
+            self.selected_register
+        );
 
         match self.selected_register {
             register @ 0x00..=0x07 => {
@@ -798,7 +804,7 @@ impl Vrc7 {
             _ => panic!("invalid VRC7 sub mapper: {sub_mapper_number}"),
         };
 
-        log::info!("VRC7 variant: {variant:?}");
+        log::info!(target: MAPPER_TARGET, "VRC7 variant: {variant:?}");
 
         Self {
             variant,
@@ -816,15 +822,15 @@ impl Vrc7 {
 }
 
 impl MapperImpl<Vrc7> {
-    pub(crate) fn read_cpu_address(&self, address: u16) -> u8 {
+    pub(crate) fn read_cpu_address(&self, address: u16, open_bus: u8) -> u8 {
         match address {
             0x0000..=0x401F => panic!("invalid CPU map address: {address:04X}"),
-            0x4020..=0x5FFF => bus::cpu_open_bus(address),
+            0x4020..=0x5FFF => open_bus,
             0x6000..=0x7FFF => {
                 if self.data.ram_enabled {
                     self.cartridge.get_prg_ram((address & 0x1FFF).into())
                 } else {
-                    bus::cpu_open_bus(address)
+                    open_bus
                 }
             }
             0x8000..=0x9FFF => {
@@ -954,3 +960,51 @@ impl HasBasicPpuMapping for MapperImpl<Vrc7> {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::bus::cartridge::mappers::NametableMirroring;
+    use crate::bus::cartridge::testing::TestRom;
+
+    #[test]
+    fn vrc7a_registers() {
+        // VRC7a uses A4 as the register select line
+        let mut harness = TestRom::new(85, 256, 256).sub_mapper(2).load();
+        harness.write_cpu(0x8000, 3);
+        harness.write_cpu(0x8010, 4);
+        harness.write_cpu(0x9000, 5);
+        assert_eq!(
+            [0x8000, 0xA000, 0xC000, 0xE000].map(|address| harness.prg_bank(address)),
+            [3, 4, 5, 31]
+        );
+
+        harness.write_cpu(0xA000, 20);
+        harness.write_cpu(0xA010, 21);
+        harness.write_cpu(0xD010, 22);
+        assert_eq!(harness.chr_bank(0x0000), 20);
+        assert_eq!(harness.chr_bank(0x0400), 21);
+        assert_eq!(harness.chr_bank(0x1C00), 22);
+
+        harness.write_cpu(0xE000, 0x03);
+        assert_eq!(harness.nametable_mirroring(), Some(NametableMirroring::SingleScreenBank1));
+    }
+
+    #[test]
+    fn vrc7b_registers() {
+        // VRC7b uses A3 as the register select line
+        let mut harness = TestRom::new(85, 256, 256).sub_mapper(1).load();
+        harness.write_cpu(0x8008, 4);
+        harness.write_cpu(0x8010, 6);
+        assert_eq!(harness.prg_bank(0xA000), 4);
+
+        harness.write_cpu(0xB008, 23);
+        assert_eq!(harness.chr_bank(0x0C00), 23);
+
+        // Cycle mode IRQ; the store instruction's final cycle clocks the counter once
+        harness.write_cpu(0xE008, 0xFE);
+        harness.write_cpu(0xF000, 0x06);
+        assert!(!harness.irq());
+        harness.clock_cpu(1);
+        assert!(harness.irq());
+    }
+}
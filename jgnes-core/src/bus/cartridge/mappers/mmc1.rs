@@ -60,15 +60,25 @@ impl Mmc1 {
 }
 
 impl MapperImpl<Mmc1> {
+    // SOROM (16KB) and SXROM (32KB) select the 8KB PRG RAM bank using the CHR bank 0 register
+    fn prg_ram_address(&self, address: u16) -> u32 {
+        let prg_ram_bank = match self.cartridge.prg_ram.len() {
+            16384 => (self.data.chr_bank_0 >> 3) & 0x01,
+            32768 => (self.data.chr_bank_0 >> 2) & 0x03,
+            _ => 0,
+        };
+        BankSizeKb::Eight.to_absolute_address(prg_ram_bank, address)
+    }
+
     fn map_cpu_address(&self, address: u16) -> CpuMapResult {
         match address {
             0x0000..=0x401F => panic!("invalid CPU map address: 0x{address:04X}"),
-            0x4020..=0x5FFF => CpuMapResult::None { original_address: address },
+            0x4020..=0x5FFF => CpuMapResult::None,
             0x6000..=0x7FFF => {
                 if !self.cartridge.prg_ram.is_empty() {
-                    CpuMapResult::PrgRAM(u32::from(address & 0x1FFF))
+                    CpuMapResult::PrgRAM(self.prg_ram_address(address))
                 } else {
-                    CpuMapResult::None { original_address: address }
+                    CpuMapResult::None
                 }
             }
             0x8000..=0xFFFF => match self.data.prg_banking_mode {
@@ -111,8 +121,8 @@ impl MapperImpl<Mmc1> {
         }
     }
 
-    pub(crate) fn read_cpu_address(&self, address: u16) -> u8 {
-        self.map_cpu_address(address).read(&self.cartridge)
+    pub(crate) fn read_cpu_address(&self, address: u16, open_bus: u8) -> u8 {
+        self.map_cpu_address(address).read(&self.cartridge, open_bus)
     }
 
     pub(crate) fn write_cpu_address(&mut self, address: u16, value: u8) {
@@ -121,7 +131,8 @@ impl MapperImpl<Mmc1> {
             0x4020..=0x5FFF => {}
             0x6000..=0x7FFF => {
                 if !self.cartridge.prg_ram.is_empty() {
-                    self.cartridge.set_prg_ram(u32::from(address & 0x1FFF), value);
+                    let prg_ram_addr = self.prg_ram_address(address);
+                    self.cartridge.set_prg_ram(prg_ram_addr, value);
                 }
             }
             0x8000..=0xFFFF => {
@@ -235,3 +246,78 @@ impl HasBasicPpuMapping for MapperImpl<Mmc1> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::bus::cartridge::mappers::NametableMirroring;
+    use crate::bus::cartridge::testing::{MapperHarness, TestRom};
+
+    fn write_register(harness: &mut MapperHarness, address: u16, value: u8) {
+        for i in 0..5 {
+            harness.write_cpu(address, (value >> i) & 0x01);
+        }
+    }
+
+    #[test]
+    fn prg_banking_modes() {
+        let mut harness = TestRom::new(1, 256, 128).load();
+        // Power-on state fixes the last bank at $C000
+        assert_eq!(harness.prg_bank(0xC000), 30);
+
+        write_register(&mut harness, 0xE000, 5);
+        assert_eq!(harness.prg_bank(0x8000), 10);
+        assert_eq!(harness.prg_bank(0xC000), 30);
+
+        // First bank fixed at $8000
+        write_register(&mut harness, 0x8000, 0x08);
+        assert_eq!(harness.prg_bank(0x8000), 0);
+        assert_eq!(harness.prg_bank(0xC000), 10);
+
+        // 32KB mode ignores the lowest bit
+        write_register(&mut harness, 0x8000, 0x00);
+        assert_eq!(harness.prg_bank(0x8000), 8);
+        assert_eq!(harness.prg_bank(0xE000), 11);
+
+        // Setting bit 7 resets the shift register and restores the power-on PRG banking mode
+        harness.write_cpu(0x8000, 0x01);
+        harness.write_cpu(0x8000, 0x80);
+        assert_eq!(harness.prg_bank(0xC000), 30);
+        write_register(&mut harness, 0xE000, 2);
+        assert_eq!(harness.prg_bank(0x8000), 4);
+    }
+
+    #[test]
+    fn chr_banking_and_mirroring() {
+        let mut harness = TestRom::new(1, 128, 128).load();
+
+        write_register(&mut harness, 0xA000, 3);
+        assert_eq!(harness.chr_bank(0x0000), 8);
+        assert_eq!(harness.chr_bank(0x1C00), 15);
+
+        write_register(&mut harness, 0x8000, 0x1E);
+        write_register(&mut harness, 0xC000, 6);
+        assert_eq!(harness.chr_bank(0x0000), 12);
+        assert_eq!(harness.chr_bank(0x1000), 24);
+        assert_eq!(harness.nametable_mirroring(), Some(NametableMirroring::Vertical));
+
+        write_register(&mut harness, 0x8000, 0x1F);
+        assert_eq!(harness.nametable_mirroring(), Some(NametableMirroring::Horizontal));
+        write_register(&mut harness, 0x8000, 0x1D);
+        assert_eq!(harness.nametable_mirroring(), Some(NametableMirroring::SingleScreenBank1));
+    }
+
+    #[test]
+    fn consecutive_cycle_writes_ignored() {
+        let mut harness = TestRom::new(1, 256, 128).load();
+
+        // Emulates a read-modify-write instruction that writes on two consecutive cycles; only the
+        // first write should be seen
+        for _ in 0..5 {
+            harness.mapper.write_cpu_address(0xE000, 0x01);
+            harness.clock_cpu(1);
+            harness.mapper.write_cpu_address(0xE000, 0x00);
+            harness.clock_cpu(3);
+        }
+        assert_eq!(harness.prg_bank(0x8000), 30);
+    }
+}
@@ -58,12 +58,12 @@ impl MapperImpl<Mmc2> {
     fn map_cpu_address(&self, address: u16) -> CpuMapResult {
         match (self.data.variant, address) {
             (_, 0x0000..=0x401F) => panic!("invalid CPU map address: {address:04X}"),
-            (_, 0x4020..=0x5FFF) => CpuMapResult::None { original_address: address },
+            (_, 0x4020..=0x5FFF) => CpuMapResult::None,
             (_, 0x6000..=0x7FFF) => {
                 if !self.cartridge.prg_ram.is_empty() {
                     CpuMapResult::PrgRAM(u32::from(address & 0x1FFF))
                 } else {
-                    CpuMapResult::None { original_address: address }
+                    CpuMapResult::None
                 }
             }
             (Variant::Mmc2, 0x8000..=0x9FFF) => {
@@ -109,8 +109,8 @@ impl MapperImpl<Mmc2> {
         }
     }
 
-    pub(crate) fn read_cpu_address(&self, address: u16) -> u8 {
-        self.map_cpu_address(address).read(&self.cartridge)
+    pub(crate) fn read_cpu_address(&self, address: u16, open_bus: u8) -> u8 {
+        self.map_cpu_address(address).read(&self.cartridge, open_bus)
     }
 
     pub(crate) fn write_cpu_address(&mut self, address: u16, value: u8) {
@@ -215,3 +215,54 @@ impl MapperImpl<Mmc2> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::bus::cartridge::mappers::NametableMirroring;
+    use crate::bus::cartridge::testing::TestRom;
+
+    #[test]
+    fn mmc2_prg_banking() {
+        let mut harness = TestRom::new(9, 128, 128).load();
+        harness.write_cpu(0xA000, 5);
+        assert_eq!(harness.prg_bank(0x8000), 5);
+        assert_eq!(harness.prg_bank(0xA000), 13);
+        assert_eq!(harness.prg_bank(0xC000), 14);
+        assert_eq!(harness.prg_bank(0xE000), 15);
+
+        harness.write_cpu(0xF000, 0x01);
+        assert_eq!(harness.nametable_mirroring(), Some(NametableMirroring::Horizontal));
+    }
+
+    #[test]
+    fn chr_latches() {
+        let mut harness = TestRom::new(9, 128, 128).load();
+        harness.write_cpu(0xB000, 1);
+        harness.write_cpu(0xC000, 2);
+        harness.write_cpu(0xD000, 3);
+        harness.write_cpu(0xE000, 4);
+        assert_eq!(harness.chr_bank(0x0000), 4);
+        assert_eq!(harness.chr_bank(0x1000), 12);
+
+        // The read that triggers the latch still sees the previous bank
+        assert_eq!(harness.chr_bank(0x0FE8), 7);
+        assert_eq!(harness.chr_bank(0x0000), 8);
+        // MMC2 only checks a single address for the left pattern table
+        harness.chr_bank(0x0FD9);
+        assert_eq!(harness.chr_bank(0x0000), 8);
+        harness.chr_bank(0x0FD8);
+        assert_eq!(harness.chr_bank(0x0000), 4);
+
+        harness.chr_bank(0x1FEF);
+        assert_eq!(harness.chr_bank(0x1000), 16);
+
+        // MMC4 checks an 8-byte range for both pattern tables and has 16KB PRG ROM banks
+        let mut harness = TestRom::new(10, 128, 128).load();
+        harness.write_cpu(0xA000, 2);
+        harness.write_cpu(0xC000, 2);
+        harness.chr_bank(0x0FEB);
+        assert_eq!(harness.chr_bank(0x0000), 8);
+        assert_eq!(harness.prg_bank(0x8000), 4);
+        assert_eq!(harness.prg_bank(0xC000), 14);
+    }
+}
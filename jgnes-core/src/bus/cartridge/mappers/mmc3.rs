@@ -6,9 +6,9 @@
 //! * NAMCOT-3446 (iNES mapper 76)
 //! * NAMCOT-3453 (iNES mapper 154)
 
-use crate::bus;
 use crate::bus::cartridge::MapperImpl;
 use crate::bus::cartridge::mappers::{BankSizeKb, ChrType, NametableMirroring, PpuMapResult};
+use crate::logging::MAPPER_TARGET;
 use crate::num::GetBit;
 use bincode::{Decode, Encode};
 
@@ -257,15 +257,15 @@ impl Mmc3 {
 }
 
 impl MapperImpl<Mmc3> {
-    pub(crate) fn read_cpu_address(&self, address: u16) -> u8 {
+    pub(crate) fn read_cpu_address(&self, address: u16, open_bus: u8) -> u8 {
         match address {
             0x0000..=0x401F => panic!("invalid CPU map address: 0x{address:04X}"),
-            0x4020..=0x5FFF => bus::cpu_open_bus(address),
+            0x4020..=0x5FFF => open_bus,
             0x6000..=0x7FFF => {
                 if self.data.ram_mode.reads_enabled(address) && !self.cartridge.prg_ram.is_empty() {
                     self.cartridge.get_prg_ram(u32::from(address & 0x1FFF))
                 } else {
-                    bus::cpu_open_bus(address)
+                    open_bus
                 }
             }
             0x8000..=0xFFFF => {
@@ -405,7 +405,7 @@ impl MapperImpl<Mmc3> {
     }
 
     fn clock_irq(&mut self) {
-        log::trace!(
+        log::trace!(target: MAPPER_TARGET,
             "IRQ clocked; counter={}, reload_flag={}, reload_value={}",
             self.data.irq_counter,
             self.data.irq_reload_flag,
@@ -425,7 +425,7 @@ impl MapperImpl<Mmc3> {
     }
 
     fn process_ppu_address(&mut self, address: u16) {
-        log::trace!("PPU bus address: {address:04X}");
+        log::trace!(target: MAPPER_TARGET, "PPU bus address: {address:04X}");
 
         let a12 = address.bit(12);
 
@@ -547,3 +547,67 @@ impl MapperImpl<Mmc3> {
         self.data.variant.name()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::bus::cartridge::mappers::NametableMirroring;
+    use crate::bus::cartridge::testing::TestRom;
+
+    #[test]
+    fn bank_switching() {
+        let mut harness = TestRom::new(4, 256, 256).load();
+        for (register, bank) in [0, 2, 4, 5, 6, 7, 3, 9].into_iter().enumerate() {
+            harness.write_cpu(0x8000, register as u8);
+            harness.write_cpu(0x8001, bank);
+        }
+        assert_eq!(
+            [0x8000, 0xA000, 0xC000, 0xE000].map(|address| harness.prg_bank(address)),
+            [3, 9, 30, 31]
+        );
+        assert_eq!(
+            [0x0000, 0x0400, 0x0800, 0x0C00, 0x1000, 0x1400, 0x1800, 0x1C00]
+                .map(|address| harness.chr_bank(address)),
+            [0, 1, 2, 3, 4, 5, 6, 7]
+        );
+
+        // Swap the $8000 and $C000 PRG ROM banks and invert the CHR ROM A12 ranges
+        harness.write_cpu(0x8000, 0xC0);
+        assert_eq!(
+            [0x8000, 0xA000, 0xC000, 0xE000].map(|address| harness.prg_bank(address)),
+            [30, 9, 3, 31]
+        );
+        assert_eq!(harness.chr_bank(0x0000), 4);
+        assert_eq!(harness.chr_bank(0x1000), 0);
+
+        harness.write_cpu(0xA000, 0x01);
+        assert_eq!(harness.nametable_mirroring(), Some(NametableMirroring::Horizontal));
+        harness.write_cpu(0xA000, 0x00);
+        assert_eq!(harness.nametable_mirroring(), Some(NametableMirroring::Vertical));
+    }
+
+    #[test]
+    fn scanline_irq() {
+        let mut harness = TestRom::new(4, 256, 256).load();
+        // BG at $0000 and sprites at $1000, so A12 rises once per scanline
+        harness.set_ppu_ctrl(0x08);
+        harness.write_cpu(0xC000, 3);
+        harness.write_cpu(0xC001, 0);
+        harness.write_cpu(0xE001, 0);
+
+        // The first clock reloads the counter
+        harness.render_scanlines(3);
+        assert!(!harness.irq());
+        harness.render_scanline();
+        assert!(harness.irq());
+
+        harness.write_cpu(0xE000, 0);
+        assert!(!harness.irq());
+
+        // Counter reloads after hitting 0
+        harness.write_cpu(0xE001, 0);
+        harness.render_scanlines(3);
+        assert!(!harness.irq());
+        harness.render_scanline();
+        assert!(harness.irq());
+    }
+}
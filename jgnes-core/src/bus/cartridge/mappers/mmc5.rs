@@ -4,8 +4,9 @@ use crate::apu::FrameCounter;
 use crate::apu::pulse::{PulseChannel, SweepStatus};
 use crate::bus::cartridge::mappers::{BankSizeKb, CpuMapResult};
 use crate::bus::cartridge::{Cartridge, MapperImpl};
+use crate::logging::MAPPER_TARGET;
 use crate::num::GetBit;
-use crate::{TimingMode, apu, bus};
+use crate::{TimingMode, apu};
 use bincode::{Decode, Encode};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Encode, Decode)]
@@ -200,7 +201,7 @@ impl ChrMapper {
 
     fn process_ppu_ctrl_update(&mut self, ppu_ctrl_value: u8) {
         self.double_height_sprites = ppu_ctrl_value.bit(5);
-        log::trace!("Double height sprites update detected: {}", self.double_height_sprites);
+        log::trace!(target: MAPPER_TARGET, "Double height sprites update detected: {}", self.double_height_sprites);
     }
 
     fn process_bank_register_update(&mut self, address: u16, value: u8) {
@@ -322,7 +323,7 @@ impl ScanlineCounter {
             self.same_nametable_addr_fetch_count = 0;
 
             if self.in_frame {
-                log::trace!(
+                log::trace!(target: MAPPER_TARGET,
                     "Detected new scanline; nametable address = {:04X}",
                     self.last_nametable_address
                 );
@@ -333,16 +334,16 @@ impl ScanlineCounter {
                 self.scanline_tile_byte_fetches = 4;
 
                 if self.scanline == 241 {
-                    log::trace!("Reached VBlank scanline, resetting state");
+                    log::trace!(target: MAPPER_TARGET, "Reached VBlank scanline, resetting state");
                     self.scanline = 0;
                     self.irq_pending = false;
                     self.in_frame = false;
                 } else if self.compare_value != 0 && self.scanline == self.compare_value {
-                    log::trace!("Setting IRQ pending flag");
+                    log::trace!(target: MAPPER_TARGET, "Setting IRQ pending flag");
                     self.irq_pending = true;
                 }
             } else {
-                log::trace!("Detected new frame");
+                log::trace!(target: MAPPER_TARGET, "Detected new frame");
                 self.scanline = 0;
                 self.in_frame = true;
             }
@@ -352,7 +353,7 @@ impl ScanlineCounter {
     // This should be called *after* mapping the tile address in case the increment changes the
     // current tile type
     fn increment_tile_bytes_fetched(&mut self) {
-        log::trace!("Tile byte fetched, current fetches={}", self.scanline_tile_byte_fetches);
+        log::trace!(target: MAPPER_TARGET, "Tile byte fetched, current fetches={}", self.scanline_tile_byte_fetches);
 
         self.cpu_ticks_no_read = 0;
 
@@ -391,7 +392,7 @@ impl ScanlineCounter {
             TileType::Sprite
         };
 
-        log::trace!("current tile type is {tile_type:?}");
+        log::trace!(target: MAPPER_TARGET, "current tile type is {tile_type:?}");
 
         tile_type
     }
@@ -406,7 +407,7 @@ impl ScanlineCounter {
 
     fn tick_cpu(&mut self) {
         if self.cpu_ticks_no_read == 3 {
-            log::trace!("Went 3 CPU cycles with no PPU reads, clearing in frame flag");
+            log::trace!(target: MAPPER_TARGET, "Went 3 CPU cycles with no PPU reads, clearing in frame flag");
             self.in_frame = false;
             // Set to 4 so that the counter increments correctly starting from the pre-render scanline
             // 2 tiles * 2 bytes per tile
@@ -588,7 +589,7 @@ impl MapperImpl<Mmc5> {
         self.data.chr_mapper.next_access_from_ppu_data = true;
     }
 
-    fn read_internal_register(&mut self, address: u16) -> u8 {
+    fn read_internal_register(&mut self, address: u16, open_bus: u8) -> u8 {
         match address {
             0x5010 => self.data.pcm_channel.read_control(),
             0x5015 => {
@@ -596,7 +597,7 @@ impl MapperImpl<Mmc5> {
                     | u8::from(self.data.pulse_channel_1.length_counter() != 0)
             }
             0x5204 => {
-                log::trace!("Scanline IRQ status register read, clearing IRQ pending flag");
+                log::trace!(target: MAPPER_TARGET, "Scanline IRQ status register read, clearing IRQ pending flag");
 
                 let result = (u8::from(self.data.scanline_counter.irq_pending) << 7)
                     | (u8::from(self.data.scanline_counter.in_frame) << 6);
@@ -605,7 +606,7 @@ impl MapperImpl<Mmc5> {
             }
             0x5205 => (self.data.multiplier.output() & 0x00FF) as u8,
             0x5206 => (self.data.multiplier.output() >> 8) as u8,
-            _ => bus::cpu_open_bus(address),
+            _ => open_bus,
         }
     }
 
@@ -647,7 +648,7 @@ impl MapperImpl<Mmc5> {
                     0x03 => PrgBankingMode::Mode3,
                     _ => unreachable!("value & 0x03 should always be 0x00/0x01/0x02/0x03"),
                 };
-                log::trace!("PRG banking mode set to {:?}", self.data.prg_banking_mode);
+                log::trace!(target: MAPPER_TARGET, "PRG banking mode set to {:?}", self.data.prg_banking_mode);
             }
             0x5101 => {
                 self.data.chr_mapper.bank_size = match value & 0x03 {
@@ -657,7 +658,7 @@ impl MapperImpl<Mmc5> {
                     0x03 => BankSizeKb::One,
                     _ => unreachable!("value & 0x03 should always be 0x00/0x01/0x02/0x03"),
                 };
-                log::trace!("CHR bank size set to {:?}", self.data.chr_mapper.bank_size);
+                log::trace!(target: MAPPER_TARGET, "CHR bank size set to {:?}", self.data.chr_mapper.bank_size);
             }
             0x5102 => {
                 self.data.ram_writes_enabled_1 = value & 0x03 == 0x02;
@@ -673,18 +674,18 @@ impl MapperImpl<Mmc5> {
                     0x03 => ExtendedRamMode::ReadOnly,
                     _ => unreachable!("value & 0x03 should be 0x00/0x01/0x02/0x03"),
                 };
-                log::trace!("Extended RAM mode set to {:?}", self.data.extended_ram_mode);
+                log::trace!(target: MAPPER_TARGET, "Extended RAM mode set to {:?}", self.data.extended_ram_mode);
             }
             0x5105 => {
                 self.data.nametable_mappings[0] = NametableMapping::from_bits(value & 0x03);
                 self.data.nametable_mappings[1] = NametableMapping::from_bits((value >> 2) & 0x03);
                 self.data.nametable_mappings[2] = NametableMapping::from_bits((value >> 4) & 0x03);
                 self.data.nametable_mappings[3] = NametableMapping::from_bits((value >> 6) & 0x03);
-                log::trace!("Nametable mappings set to {:?}", self.data.nametable_mappings);
+                log::trace!(target: MAPPER_TARGET, "Nametable mappings set to {:?}", self.data.nametable_mappings);
             }
             0x5106 => {
                 self.data.fill_mode_tile_data = value;
-                log::trace!("Fill mode tile set to {value:02X}");
+                log::trace!(target: MAPPER_TARGET, "Fill mode tile set to {value:02X}");
             }
             0x5107 => {
                 let palette_index = value & 0x03;
@@ -692,41 +693,41 @@ impl MapperImpl<Mmc5> {
                     | (palette_index << 2)
                     | (palette_index << 4)
                     | (palette_index << 6);
-                log::trace!("Fill mode palette index set to {value:02X}");
+                log::trace!(target: MAPPER_TARGET, "Fill mode palette index set to {value:02X}");
             }
             0x5113..=0x5117 => {
                 self.data.prg_bank_registers[(address - 0x5113) as usize] = value;
-                log::trace!("PRG bank {:02X} set to {value:02X}", address - 0x5113);
+                log::trace!(target: MAPPER_TARGET, "PRG bank {:02X} set to {value:02X}", address - 0x5113);
             }
             0x5120..=0x512B => {
                 self.data.chr_mapper.process_bank_register_update(address, value);
-                log::trace!("CHR bank {:02X} set to {value:02X}", address - 0x5120);
+                log::trace!(target: MAPPER_TARGET, "CHR bank {:02X} set to {value:02X}", address - 0x5120);
             }
             0x5200 => {
                 self.data.vertical_split.enabled = value.bit(7);
                 self.data.vertical_split.mode =
                     if value.bit(6) { VerticalSplitMode::Right } else { VerticalSplitMode::Left };
                 self.data.vertical_split.split_tile_index = value & 0x1F;
-                log::trace!(
+                log::trace!(target: MAPPER_TARGET,
                     "Vertical split enabled/mode/index set: {:?}",
                     self.data.vertical_split
                 );
             }
             0x5201 => {
                 self.data.vertical_split.y_scroll = value;
-                log::trace!("Vertical split Y scroll set to {value}");
+                log::trace!(target: MAPPER_TARGET, "Vertical split Y scroll set to {value}");
             }
             0x5202 => {
                 self.data.vertical_split.chr_bank = value;
-                log::trace!("Vertical split CHR bank set to {value:02X}");
+                log::trace!(target: MAPPER_TARGET, "Vertical split CHR bank set to {value:02X}");
             }
             0x5203 => {
                 self.data.scanline_counter.compare_value = value;
-                log::trace!("Scanline counter compare value set to {value}");
+                log::trace!(target: MAPPER_TARGET, "Scanline counter compare value set to {value}");
             }
             0x5204 => {
                 self.data.scanline_counter.irq_enabled = value.bit(7);
-                log::trace!(
+                log::trace!(target: MAPPER_TARGET,
                     "Scanline IRQ enabled set to {}",
                     self.data.scanline_counter.irq_enabled
                 );
@@ -741,21 +742,21 @@ impl MapperImpl<Mmc5> {
         }
     }
 
-    pub(crate) fn read_cpu_address(&mut self, address: u16) -> u8 {
+    pub(crate) fn read_cpu_address(&mut self, address: u16, open_bus: u8) -> u8 {
         if address == 0xFFFA || address == 0xFFFB {
             self.data.scanline_counter.nmi_vector_fetched();
         }
 
         match address {
             0x0000..=0x401F => panic!("invalid CPU map address: {address:04X}"),
-            0x4020..=0x4FFF => bus::cpu_open_bus(address),
-            0x5000..=0x5BFF => self.read_internal_register(address),
+            0x4020..=0x4FFF => open_bus,
+            0x5000..=0x5BFF => self.read_internal_register(address, open_bus),
             0x5C00..=0x5FFF => match self.data.extended_ram_mode {
                 ExtendedRamMode::ReadWrite | ExtendedRamMode::ReadOnly => {
                     self.data.extended_ram[(address - 0x5C00) as usize]
                 }
                 ExtendedRamMode::Nametable | ExtendedRamMode::NametableExtendedAttributes => {
-                    bus::cpu_open_bus(address)
+                    open_bus
                 }
             },
             0x6000..=0xFFFF => {
@@ -763,7 +764,7 @@ impl MapperImpl<Mmc5> {
                     .data
                     .prg_banking_mode
                     .map_prg_address(self.data.prg_bank_registers, address)
-                    .read(&self.cartridge);
+                    .read(&self.cartridge, open_bus);
 
                 self.data.pcm_channel.process_cpu_read(address, value);
 
@@ -886,9 +887,9 @@ impl MapperImpl<Mmc5> {
                         | ExtendedRamMode::NametableExtendedAttributes => {
                             self.data.extended_ram[(relative_addr & 0x03FF) as usize]
                         }
-                        ExtendedRamMode::ReadWrite | ExtendedRamMode::ReadOnly => {
-                            bus::cpu_open_bus(address)
-                        }
+                        // The PPU's data bus is shared with the low byte of the address, so open
+                        // bus reads return the low address byte
+                        ExtendedRamMode::ReadWrite | ExtendedRamMode::ReadOnly => address as u8,
                     },
                     NametableMapping::FillMode => {
                         if relative_addr & 0x03FF < 0x03C0 {
@@ -971,3 +972,97 @@ impl MapperImpl<Mmc5> {
         mixed_apu_sample - mmc5_pulse_mix - scaled_pcm_sample
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::bus::cartridge::mappers::NametableMirroring;
+    use crate::bus::cartridge::testing::TestRom;
+
+    #[test]
+    fn prg_banking() {
+        let mut harness = TestRom::new(5, 256, 256).load();
+
+        harness.write_cpu(0x5100, 0x03);
+        for (address, bank) in (0x5114..=0x5117).zip([0x81, 0x83, 0x85, 0x87]) {
+            harness.write_cpu(address, bank);
+        }
+        assert_eq!(
+            [0x8000, 0xA000, 0xC000, 0xE000].map(|address| harness.prg_bank(address)),
+            [1, 3, 5, 7]
+        );
+
+        // 2x16KB mode ignores the lowest bit and always maps ROM at $C000-$FFFF
+        harness.write_cpu(0x5100, 0x01);
+        harness.write_cpu(0x5115, 0x85);
+        harness.write_cpu(0x5117, 0x07);
+        assert_eq!(
+            [0x8000, 0xA000, 0xC000, 0xE000].map(|address| harness.prg_bank(address)),
+            [4, 5, 6, 7]
+        );
+
+        // Bank numbers with bit 7 clear map PRG RAM, which is only writable once both write
+        // protect registers are set
+        harness.write_cpu(0x5100, 0x03);
+        harness.write_cpu(0x5114, 0x00);
+        harness.write_cpu(0x8000, 0x5A);
+        assert_eq!(harness.read_cpu(0x8000), 0x00);
+        harness.write_cpu(0x5102, 0x02);
+        harness.write_cpu(0x5103, 0x01);
+        harness.write_cpu(0x8000, 0x5A);
+        assert_eq!(harness.read_cpu(0x8000), 0x5A);
+        harness.write_cpu(0x5113, 0x00);
+        assert_eq!(harness.read_cpu(0x6000), 0x5A);
+    }
+
+    #[test]
+    fn chr_banking_and_nametables() {
+        let mut harness = TestRom::new(5, 256, 256).load();
+
+        harness.write_cpu(0x5101, 0x03);
+        for (address, bank) in (0x5120..=0x5127).zip([9, 8, 7, 6, 5, 4, 3, 2]) {
+            harness.write_cpu(address, bank);
+        }
+        assert_eq!(
+            [0x0000, 0x0400, 0x0800, 0x0C00, 0x1000, 0x1400, 0x1800, 0x1C00]
+                .map(|address| harness.chr_bank(address)),
+            [9, 8, 7, 6, 5, 4, 3, 2]
+        );
+
+        harness.write_cpu(0x5101, 0x01);
+        assert_eq!(harness.chr_bank(0x0C00), 27);
+        assert_eq!(harness.chr_bank(0x1000), 8);
+
+        harness.write_cpu(0x5105, 0x44);
+        assert_eq!(harness.nametable_mirroring(), Some(NametableMirroring::Vertical));
+        harness.write_cpu(0x5105, 0x50);
+        assert_eq!(harness.nametable_mirroring(), Some(NametableMirroring::Horizontal));
+
+        // Fill mode nametables
+        harness.write_cpu(0x5105, 0xFF);
+        harness.write_cpu(0x5106, 0x42);
+        assert_eq!(harness.nametable_mirroring(), None);
+        assert_eq!(harness.chr_bank(0x2000), 0x42);
+    }
+
+    #[test]
+    fn scanline_irq() {
+        let mut harness = TestRom::new(5, 256, 256).load();
+        harness.write_cpu(0x5203, 3);
+        harness.write_cpu(0x5204, 0x80);
+        harness.set_ppu_mask(0x18);
+
+        // The first scanline rendered acts as the pre-render scanline
+        harness.render_scanlines(4);
+        assert!(!harness.irq());
+        harness.render_scanline();
+        assert!(harness.irq());
+
+        // Reading the status register acknowledges the IRQ and reports that the PPU is in frame
+        assert_eq!(harness.mapper.read_cpu_address(0x5204, 0), 0xC0);
+        assert!(!harness.irq());
+
+        // Going a few CPU cycles without PPU reads means rendering has stopped
+        harness.clock_cpu(4);
+        assert_eq!(harness.mapper.read_cpu_address(0x5204, 0), 0x00);
+    }
+}
@@ -1,8 +1,8 @@
 //! Code for the Namco 129 and Namco 163 boards (iNES mapper 19).
 
-use crate::bus;
 use crate::bus::cartridge::mappers::{BankSizeKb, ChrType, PpuMapResult};
 use crate::bus::cartridge::{HasBasicPpuMapping, MapperImpl};
+use crate::logging::MAPPER_TARGET;
 use crate::num::GetBit;
 use bincode::{Decode, Encode};
 use std::array;
@@ -261,7 +261,7 @@ impl Namco163 {
             }
         }
 
-        log::info!("Namco 163 volume variant: {volume_variant:?}");
+        log::info!(target: MAPPER_TARGET, "Namco 163 volume variant: {volume_variant:?}");
 
         Self {
             chr_type,
@@ -283,10 +283,10 @@ impl Namco163 {
 }
 
 impl MapperImpl<Namco163> {
-    pub(crate) fn read_cpu_address(&mut self, address: u16) -> u8 {
+    pub(crate) fn read_cpu_address(&mut self, address: u16, open_bus: u8) -> u8 {
         match address {
             0x0000..=0x401F => panic!("invalid CPU map address: {address:04X}"),
-            0x4020..=0x47FF => bus::cpu_open_bus(address),
+            0x4020..=0x47FF => open_bus,
             0x4800..=0x4FFF => {
                 let byte = self.data.internal_ram[self.data.internal_ram_addr as usize];
                 if self.data.internal_ram_auto_increment {
@@ -300,7 +300,7 @@ impl MapperImpl<Namco163> {
                 if !self.cartridge.prg_ram.is_empty() {
                     self.cartridge.get_prg_ram((address & 0x1FFF).into())
                 } else {
-                    bus::cpu_open_bus(address)
+                    open_bus
                 }
             }
             0x8000..=0xDFFF => {
@@ -453,3 +453,72 @@ impl HasBasicPpuMapping for MapperImpl<Namco163> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::bus::cartridge::mappers::NametableMirroring;
+    use crate::bus::cartridge::testing::TestRom;
+
+    #[test]
+    fn bank_switching() {
+        let mut harness = TestRom::new(19, 256, 256).load();
+        harness.write_cpu(0xE000, 4);
+        harness.write_cpu(0xE800, 5);
+        harness.write_cpu(0xF000, 6);
+        assert_eq!(
+            [0x8000, 0xA000, 0xC000, 0xE000].map(|address| harness.prg_bank(address)),
+            [4, 5, 6, 31]
+        );
+
+        for (i, address) in (0x8000..0xC000).step_by(0x0800).enumerate() {
+            harness.write_cpu(address, 20 + i as u8);
+        }
+        assert_eq!(
+            [0x0000, 0x0400, 0x0800, 0x0C00, 0x1000, 0x1400, 0x1800, 0x1C00]
+                .map(|address| harness.chr_bank(address)),
+            [20, 21, 22, 23, 24, 25, 26, 27]
+        );
+
+        // Nametable banks $E0-$FF select CIRAM pages
+        for (address, bank) in [(0xC000, 0xE0), (0xC800, 0xE1), (0xD000, 0xE0), (0xD800, 0xE1)] {
+            harness.write_cpu(address, bank);
+        }
+        assert_eq!(harness.nametable_mirroring(), Some(NametableMirroring::Vertical));
+
+        // Nametables can also be mapped to CHR ROM
+        harness.write_cpu(0xC000, 0x10);
+        assert_eq!(harness.nametable_mirroring(), None);
+        assert_eq!(harness.chr_bank(0x2000), 0x10);
+    }
+
+    #[test]
+    fn internal_ram() {
+        let mut harness = TestRom::new(19, 256, 256).load();
+        harness.write_cpu(0xF800, 0x80 | 0x10);
+        harness.write_cpu(0x4800, 0x12);
+        harness.write_cpu(0x4800, 0x34);
+
+        harness.write_cpu(0xF800, 0x80 | 0x10);
+        assert_eq!(harness.read_cpu(0x4800), 0x12);
+        assert_eq!(harness.read_cpu(0x4800), 0x34);
+    }
+
+    #[test]
+    fn irq_counter() {
+        let mut harness = TestRom::new(19, 256, 256).load();
+        harness.write_cpu(0x5000, 0xFD);
+        // The store instruction's final cycle clocks the counter once
+        harness.write_cpu(0x5800, 0xFF);
+        assert!(!harness.irq());
+        harness.clock_cpu(1);
+        assert!(harness.irq());
+
+        // The counter stops at $7FFF
+        harness.clock_cpu(10);
+        assert_eq!(harness.mapper.read_cpu_address(0x5000, 0), 0xFF);
+        assert_eq!(harness.mapper.read_cpu_address(0x5800, 0), 0xFF);
+
+        harness.write_cpu(0x5800, 0x7F);
+        assert!(!harness.irq());
+    }
+}
@@ -1,8 +1,8 @@
 //! Code for the Namco 175 and Namco 340 boards (iNES mapper 210).
 
-use crate::bus;
 use crate::bus::cartridge::mappers::{BankSizeKb, ChrType, NametableMirroring, PpuMapResult};
 use crate::bus::cartridge::{HasBasicPpuMapping, MapperImpl};
+use crate::logging::MAPPER_TARGET;
 use crate::num::GetBit;
 use bincode::{Decode, Encode};
 
@@ -35,7 +35,7 @@ impl Namco175 {
             _ => Variant::Unknown,
         };
 
-        log::info!("Namco 175 variant: {variant:?}");
+        log::info!(target: MAPPER_TARGET, "Namco 175 variant: {variant:?}");
 
         Self {
             variant,
@@ -49,15 +49,15 @@ impl Namco175 {
 }
 
 impl MapperImpl<Namco175> {
-    pub(crate) fn read_cpu_address(&self, address: u16) -> u8 {
+    pub(crate) fn read_cpu_address(&self, address: u16, open_bus: u8) -> u8 {
         match address {
             0x0000..=0x401F => panic!("invalid CPU map address: {address:04X}"),
-            0x4020..=0x5FFF => bus::cpu_open_bus(address),
+            0x4020..=0x5FFF => open_bus,
             0x6000..=0x7FFF => {
                 if self.data.ram_enabled && !self.cartridge.prg_ram.is_empty() {
                     self.cartridge.get_prg_ram((address & 0x1FFF).into())
                 } else {
-                    bus::cpu_open_bus(address)
+                    open_bus
                 }
             }
             0x8000..=0xDFFF => {
@@ -131,3 +131,46 @@ impl HasBasicPpuMapping for MapperImpl<Namco175> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::bus::cartridge::mappers::NametableMirroring;
+    use crate::bus::cartridge::testing::{OPEN_BUS, TestRom};
+
+    #[test]
+    fn namco_175() {
+        let mut harness = TestRom::new(210, 128, 128).sub_mapper(1).vertical_mirroring().load();
+        harness.write_cpu(0xE000, 0xC3);
+        harness.write_cpu(0xE800, 4);
+        harness.write_cpu(0xF000, 5);
+        assert_eq!(
+            [0x8000, 0xA000, 0xC000, 0xE000].map(|address| harness.prg_bank(address)),
+            [3, 4, 5, 15]
+        );
+        harness.write_cpu(0x9800, 30);
+        assert_eq!(harness.chr_bank(0x0C00), 30);
+
+        // Mirroring is hardwired
+        assert_eq!(harness.nametable_mirroring(), Some(NametableMirroring::Vertical));
+
+        harness.write_cpu(0x6000, 0x12);
+        assert_eq!(harness.read_cpu(0x6000), OPEN_BUS);
+        harness.write_cpu(0xC000, 0x01);
+        harness.write_cpu(0x6000, 0x12);
+        assert_eq!(harness.read_cpu(0x6000), 0x12);
+    }
+
+    #[test]
+    fn namco_340() {
+        let mut harness = TestRom::new(210, 128, 128).sub_mapper(2).load();
+        harness.write_cpu(0xE000, 0x43);
+        assert_eq!(harness.prg_bank(0x8000), 3);
+        assert_eq!(harness.nametable_mirroring(), Some(NametableMirroring::Vertical));
+        harness.write_cpu(0xE000, 0x80);
+        assert_eq!(harness.nametable_mirroring(), Some(NametableMirroring::SingleScreenBank1));
+
+        // No PRG RAM enable
+        harness.write_cpu(0xC000, 0x01);
+        assert_eq!(harness.read_cpu(0x6000), OPEN_BUS);
+    }
+}
@@ -11,16 +11,15 @@
 //! * Codemasters unlicensed board (iNES mapper 71, very similar to UxROM)
 //! * Jaleco JF-11 / JF-14 (iNES mapper 140, very similar to GxROM)
 
-use crate::bus;
 use crate::bus::cartridge::mappers::{BankSizeKb, ChrType, NametableMirroring, PpuMapResult};
 use crate::bus::cartridge::{Cartridge, HasBasicPpuMapping, MapperImpl};
 use crate::num::GetBit;
 use bincode::{Decode, Encode};
 
-fn basic_read_cpu_address(address: u16, cartridge: &Cartridge) -> u8 {
+fn basic_read_cpu_address(address: u16, cartridge: &Cartridge, open_bus: u8) -> u8 {
     match address {
         0x0000..=0x401F => panic!("invalid CPU map address: 0x{address:04X}"),
-        0x4020..=0x7FFF => bus::cpu_open_bus(address),
+        0x4020..=0x7FFF => open_bus,
         0x8000..=0xFFFF => cartridge.get_prg_rom(u32::from(address & 0x7FFF)),
     }
 }
@@ -50,8 +49,8 @@ impl Nrom {
 }
 
 impl MapperImpl<Nrom> {
-    pub(crate) fn read_cpu_address(&self, address: u16) -> u8 {
-        basic_read_cpu_address(address, &self.cartridge)
+    pub(crate) fn read_cpu_address(&self, address: u16, open_bus: u8) -> u8 {
+        basic_read_cpu_address(address, &self.cartridge, open_bus)
     }
 
     // Intentionally blank implementation that is present because every MapperImpl must have a
@@ -106,10 +105,10 @@ impl Uxrom {
 }
 
 impl MapperImpl<Uxrom> {
-    pub(crate) fn read_cpu_address(&self, address: u16) -> u8 {
+    pub(crate) fn read_cpu_address(&self, address: u16, open_bus: u8) -> u8 {
         match address {
             0x0000..=0x401F => panic!("invalid CPU map address: 0x{address:04X}"),
-            0x4020..=0x7FFF => bus::cpu_open_bus(address),
+            0x4020..=0x7FFF => open_bus,
             0x8000..=0xBFFF => {
                 let prg_rom_addr =
                     BankSizeKb::Sixteen.to_absolute_address(self.data.prg_bank, address);
@@ -172,8 +171,8 @@ impl Cnrom {
 }
 
 impl MapperImpl<Cnrom> {
-    pub(crate) fn read_cpu_address(&self, address: u16) -> u8 {
-        basic_read_cpu_address(address, &self.cartridge)
+    pub(crate) fn read_cpu_address(&self, address: u16, open_bus: u8) -> u8 {
+        basic_read_cpu_address(address, &self.cartridge, open_bus)
     }
 
     pub(crate) fn write_cpu_address(&mut self, address: u16, value: u8) {
@@ -216,9 +215,9 @@ impl Axrom {
 }
 
 impl MapperImpl<Axrom> {
-    pub(crate) fn read_cpu_address(&self, address: u16) -> u8 {
+    pub(crate) fn read_cpu_address(&self, address: u16, open_bus: u8) -> u8 {
         if address < 0x8000 {
-            return bus::cpu_open_bus(address);
+            return open_bus;
         }
 
         let prg_rom_addr = BankSizeKb::ThirtyTwo.to_absolute_address(self.data.prg_bank, address);
@@ -274,10 +273,10 @@ impl Gxrom {
 }
 
 impl MapperImpl<Gxrom> {
-    pub(crate) fn read_cpu_address(&self, address: u16) -> u8 {
+    pub(crate) fn read_cpu_address(&self, address: u16, open_bus: u8) -> u8 {
         match address {
             0x0000..=0x401F => panic!("invalid CPU map address: {address:04X}"),
-            0x4020..=0x7FFF => bus::cpu_open_bus(address),
+            0x4020..=0x7FFF => open_bus,
             0x8000..=0xFFFF => {
                 let prg_rom_addr =
                     BankSizeKb::ThirtyTwo.to_absolute_address(self.data.prg_bank, address);
@@ -352,15 +351,15 @@ impl Bnrom {
 }
 
 impl MapperImpl<Bnrom> {
-    pub(crate) fn read_cpu_address(&self, address: u16) -> u8 {
+    pub(crate) fn read_cpu_address(&self, address: u16, open_bus: u8) -> u8 {
         match address {
             0x0000..=0x401F => panic!("invalid CPU map address: {address:04X}"),
-            0x4020..=0x5FFF => bus::cpu_open_bus(address),
+            0x4020..=0x5FFF => open_bus,
             0x6000..=0x7FFF => {
                 if !self.cartridge.prg_ram.is_empty() {
                     self.cartridge.get_prg_ram((address & 0x1FFF).into())
                 } else {
-                    bus::cpu_open_bus(address)
+                    open_bus
                 }
             }
             0x8000..=0xFFFF => {
@@ -411,3 +410,96 @@ impl HasBasicPpuMapping for MapperImpl<Bnrom> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::bus::cartridge::mappers::NametableMirroring;
+    use crate::bus::cartridge::testing::TestRom;
+
+    #[test]
+    fn nrom() {
+        let mut harness = TestRom::new(0, 16, 8).vertical_mirroring().load();
+        // 16KB PRG ROM is mirrored
+        assert_eq!(harness.prg_bank(0x8000), 0);
+        assert_eq!(harness.prg_bank(0xA000), 1);
+        assert_eq!(harness.prg_bank(0xC000), 0);
+        assert_eq!(harness.prg_bank(0xE000), 1);
+        assert_eq!(harness.chr_bank(0x1C00), 7);
+        assert_eq!(harness.nametable_mirroring(), Some(NametableMirroring::Vertical));
+    }
+
+    #[test]
+    fn uxrom() {
+        let mut harness = TestRom::new(2, 128, 0).load();
+        harness.write_cpu(0x8000, 3);
+        assert_eq!(harness.prg_bank(0x8000), 6);
+        assert_eq!(harness.prg_bank(0xBFFF), 7);
+        assert_eq!(harness.prg_bank(0xC000), 14);
+        assert_eq!(harness.prg_bank(0xFFFF), 15);
+        assert_eq!(harness.nametable_mirroring(), Some(NametableMirroring::Horizontal));
+
+        // Fire Hawk variant of the Codemasters board controls single-screen mirroring
+        let mut harness = TestRom::new(71, 128, 0).sub_mapper(1).load();
+        harness.write_cpu(0x8000, 2);
+        harness.write_cpu(0xC000, 5);
+        assert_eq!(harness.prg_bank(0x8000), 10);
+        harness.write_cpu(0x9000, 0x10);
+        assert_eq!(harness.nametable_mirroring(), Some(NametableMirroring::SingleScreenBank1));
+    }
+
+    #[test]
+    fn cnrom() {
+        let mut harness = TestRom::new(3, 32, 32).load();
+        harness.write_cpu(0x8000, 2);
+        assert_eq!(harness.chr_bank(0x0000), 16);
+        assert_eq!(harness.chr_bank(0x1FFF), 23);
+    }
+
+    #[test]
+    fn axrom() {
+        let mut harness = TestRom::new(7, 256, 0).load();
+        assert_eq!(harness.nametable_mirroring(), Some(NametableMirroring::SingleScreenBank0));
+
+        harness.write_cpu(0x8000, 0x12);
+        assert_eq!(harness.prg_bank(0x8000), 8);
+        assert_eq!(harness.prg_bank(0xE000), 11);
+        assert_eq!(harness.nametable_mirroring(), Some(NametableMirroring::SingleScreenBank1));
+    }
+
+    #[test]
+    fn gxrom() {
+        let mut harness = TestRom::new(66, 128, 32).load();
+        harness.write_cpu(0x8000, 0x32);
+        assert_eq!(harness.prg_bank(0x8000), 12);
+        assert_eq!(harness.chr_bank(0x0000), 16);
+
+        // Color Dreams swaps the nibbles
+        let mut harness = TestRom::new(11, 128, 32).load();
+        harness.write_cpu(0x8000, 0x32);
+        assert_eq!(harness.prg_bank(0x8000), 8);
+        assert_eq!(harness.chr_bank(0x0000), 24);
+
+        // Jaleco boards put the register at $6000-$7FFF
+        let mut harness = TestRom::new(140, 128, 32).load();
+        harness.write_cpu(0x8000, 0x32);
+        assert_eq!(harness.prg_bank(0x8000), 0);
+        harness.write_cpu(0x6000, 0x32);
+        assert_eq!(harness.prg_bank(0x8000), 12);
+    }
+
+    #[test]
+    fn bnrom() {
+        let mut harness = TestRom::new(34, 128, 64).load();
+        assert_eq!(harness.chr_bank(0x1000), 4);
+
+        harness.write_cpu(0x7FFD, 2);
+        harness.write_cpu(0x7FFE, 3);
+        harness.write_cpu(0x7FFF, 5);
+        assert_eq!(harness.prg_bank(0x8000), 8);
+        assert_eq!(harness.chr_bank(0x0000), 12);
+        assert_eq!(harness.chr_bank(0x1C00), 23);
+
+        harness.write_cpu(0x6000, 0xAB);
+        assert_eq!(harness.read_cpu(0x6000), 0xAB);
+    }
+}
@@ -242,7 +242,7 @@ impl MapperImpl<Sunsoft> {
     fn map_cpu_address(&self, address: u16) -> CpuMapResult {
         match address {
             0x0000..=0x401F => panic!("invalid CPU map address: {address:04X}"),
-            0x4020..=0x5FFF => CpuMapResult::None { original_address: address },
+            0x4020..=0x5FFF => CpuMapResult::None,
             0x6000..=0x7FFF => match self.data.prg_bank_0_type {
                 PrgType::ROM => {
                     let prg_rom_addr =
@@ -255,7 +255,7 @@ impl MapperImpl<Sunsoft> {
                             BankSizeKb::Eight.to_absolute_address(self.data.prg_banks[0], address);
                         CpuMapResult::PrgRAM(prg_ram_addr)
                     } else {
-                        CpuMapResult::None { original_address: address }
+                        CpuMapResult::None
                     }
                 }
             },
@@ -277,8 +277,8 @@ impl MapperImpl<Sunsoft> {
         }
     }
 
-    pub(crate) fn read_cpu_address(&self, address: u16) -> u8 {
-        self.map_cpu_address(address).read(&self.cartridge)
+    pub(crate) fn read_cpu_address(&self, address: u16, open_bus: u8) -> u8 {
+        self.map_cpu_address(address).read(&self.cartridge, open_bus)
     }
 
     pub(crate) fn write_cpu_address(&mut self, address: u16, value: u8) {
@@ -382,3 +382,65 @@ impl HasBasicPpuMapping for MapperImpl<Sunsoft> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::bus::cartridge::mappers::NametableMirroring;
+    use crate::bus::cartridge::testing::{MapperHarness, OPEN_BUS, TestRom};
+
+    fn write_command(harness: &mut MapperHarness, command: u8, value: u8) {
+        harness.write_cpu(0x8000, command);
+        harness.write_cpu(0xA000, value);
+    }
+
+    #[test]
+    fn bank_switching() {
+        let mut harness = TestRom::new(69, 256, 256).load();
+        for (command, bank) in (0x00..=0x07).zip([7, 6, 5, 4, 3, 2, 1, 0]) {
+            write_command(&mut harness, command, bank);
+        }
+        assert_eq!(
+            [0x0000, 0x0400, 0x0800, 0x0C00, 0x1000, 0x1400, 0x1800, 0x1C00]
+                .map(|address| harness.chr_bank(address)),
+            [7, 6, 5, 4, 3, 2, 1, 0]
+        );
+
+        for (command, bank) in (0x08..=0x0B).zip([8, 9, 10, 11]) {
+            write_command(&mut harness, command, bank);
+        }
+        assert_eq!(
+            [0x6000, 0x8000, 0xA000, 0xC000, 0xE000].map(|address| harness.prg_bank(address)),
+            [8, 9, 10, 11, 31]
+        );
+
+        // $6000-$7FFF can map PRG RAM instead, which has a separate enable bit
+        write_command(&mut harness, 0x08, 0x40);
+        assert_eq!(harness.read_cpu(0x6000), OPEN_BUS);
+        write_command(&mut harness, 0x08, 0xC0);
+        harness.write_cpu(0x6000, 0x12);
+        assert_eq!(harness.read_cpu(0x6000), 0x12);
+
+        write_command(&mut harness, 0x0C, 0x01);
+        assert_eq!(harness.nametable_mirroring(), Some(NametableMirroring::Horizontal));
+        write_command(&mut harness, 0x0C, 0x03);
+        assert_eq!(harness.nametable_mirroring(), Some(NametableMirroring::SingleScreenBank1));
+    }
+
+    #[test]
+    fn irq_counter() {
+        let mut harness = TestRom::new(69, 256, 256).load();
+        write_command(&mut harness, 0x0E, 10);
+        write_command(&mut harness, 0x0F, 0);
+        // The store instruction's final cycle decrements the counter to 9
+        write_command(&mut harness, 0x0D, 0x81);
+
+        harness.clock_cpu(9);
+        assert!(!harness.irq());
+        harness.clock_cpu(1);
+        assert!(harness.irq());
+
+        // Any write to the IRQ control register acknowledges the IRQ
+        write_command(&mut harness, 0x0D, 0x81);
+        assert!(!harness.irq());
+    }
+}
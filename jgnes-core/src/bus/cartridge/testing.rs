@@ -0,0 +1,207 @@
+//! Test harness for exercising mapper implementations without running a ROM.
+//!
+//! [`TestRom`] builds an NES 2.0 ROM image where every byte of each 8KB PRG ROM bank contains the
+//! bank number and every byte of each 1KB CHR ROM bank contains the bank number, so that the
+//! currently mapped banks can be read back directly. [`MapperHarness`] then drives the mapper with
+//! scripted CPU accesses and PPU rendering fetches.
+
+use crate::bus::cartridge::mappers::NametableMirroring;
+use crate::bus::cartridge::{INES_HEADER_LEN, Mapper, from_ines_file};
+
+const PRG_BANK_LEN: usize = 8 * 1024;
+const CHR_BANK_LEN: usize = 1024;
+
+// Value returned for unmapped CPU reads
+pub(crate) const OPEN_BUS: u8 = 0xFF;
+
+#[derive(Debug, Clone)]
+pub(crate) struct TestRom {
+    mapper_number: u8,
+    sub_mapper_number: u8,
+    prg_rom_len: usize,
+    chr_rom_len: usize,
+    vertical_mirroring: bool,
+}
+
+impl TestRom {
+    /// A ROM with the given PRG ROM and CHR ROM sizes in KB. If `chr_rom_kb` is 0, the board
+    /// has 8KB of CHR RAM instead.
+    pub(crate) fn new(mapper_number: u8, prg_rom_kb: usize, chr_rom_kb: usize) -> Self {
+        Self {
+            mapper_number,
+            sub_mapper_number: 0,
+            prg_rom_len: prg_rom_kb * 1024,
+            chr_rom_len: chr_rom_kb * 1024,
+            vertical_mirroring: false,
+        }
+    }
+
+    pub(crate) fn sub_mapper(mut self, sub_mapper_number: u8) -> Self {
+        self.sub_mapper_number = sub_mapper_number;
+        self
+    }
+
+    pub(crate) fn vertical_mirroring(mut self) -> Self {
+        self.vertical_mirroring = true;
+        self
+    }
+
+    pub(crate) fn to_file_bytes(&self) -> Vec<u8> {
+        let mut header = [0; INES_HEADER_LEN];
+        header[..4].copy_from_slice(&[0x4E, 0x45, 0x53, 0x1A]);
+        header[4] = (self.prg_rom_len / (16 * 1024)) as u8;
+        header[5] = (self.chr_rom_len / (8 * 1024)) as u8;
+        header[6] = (self.mapper_number << 4) | u8::from(self.vertical_mirroring);
+        header[7] = (self.mapper_number & 0xF0) | 0x08;
+        header[8] = self.sub_mapper_number << 4;
+        // 8KB of PRG RAM, and 8KB of CHR RAM if there is no CHR ROM
+        header[10] = 0x07;
+        header[11] = if self.chr_rom_len == 0 { 0x07 } else { 0x00 };
+
+        let prg_rom = (0..self.prg_rom_len).map(|i| (i / PRG_BANK_LEN) as u8);
+        let chr_rom = (0..self.chr_rom_len).map(|i| (i / CHR_BANK_LEN) as u8);
+        header.into_iter().chain(prg_rom).chain(chr_rom).collect()
+    }
+
+    pub(crate) fn load(&self) -> MapperHarness {
+        let mapper = from_ines_file(&self.to_file_bytes(), None, None, None)
+            .expect("test ROM should always be valid");
+        MapperHarness::new(mapper)
+    }
+}
+
+pub(crate) struct MapperHarness {
+    pub(crate) mapper: Mapper,
+    pub(crate) vram: [u8; 2048],
+    ppu_ctrl: u8,
+}
+
+impl MapperHarness {
+    pub(crate) fn new(mapper: Mapper) -> Self {
+        Self { mapper, vram: [0; 2048], ppu_ctrl: 0 }
+    }
+
+    /// Clock the mapper as if the CPU ran for the given number of cycles without accessing it.
+    pub(crate) fn clock_cpu(&mut self, cycles: u32) {
+        for _ in 0..cycles {
+            self.mapper.tick_cpu();
+        }
+    }
+
+    /// Write to the CPU address space as the final cycle of a 4-cycle absolute store instruction.
+    pub(crate) fn write_cpu(&mut self, address: u16, value: u8) {
+        self.clock_cpu(3);
+        self.mapper.write_cpu_address(address, value);
+        self.clock_cpu(1);
+    }
+
+    /// Read from the CPU address space as the final cycle of a 4-cycle absolute load instruction.
+    pub(crate) fn read_cpu(&mut self, address: u16) -> u8 {
+        self.clock_cpu(3);
+        let value = self.mapper.read_cpu_address(address, OPEN_BUS);
+        self.clock_cpu(1);
+        value
+    }
+
+    /// Return the number of the 8KB PRG ROM bank mapped at the given CPU address, without
+    /// clocking the mapper.
+    pub(crate) fn prg_bank(&mut self, address: u16) -> u8 {
+        self.mapper.read_cpu_address(address, OPEN_BUS)
+    }
+
+    /// Return the number of the 1KB CHR ROM bank mapped at the given PPU address, without
+    /// clocking the mapper.
+    pub(crate) fn chr_bank(&mut self, address: u16) -> u8 {
+        self.mapper.read_ppu_address(address, &self.vram)
+    }
+
+    /// Return the nametable arrangement that the mapper currently maps to CIRAM, or None if the
+    /// nametables are not mapped to CIRAM using one of the standard arrangements.
+    pub(crate) fn nametable_mirroring(&mut self) -> Option<NametableMirroring> {
+        self.vram[0x000] = 0x00;
+        self.vram[0x400] = 0x01;
+        let pages = [0x2000, 0x2400, 0x2800, 0x2C00]
+            .map(|address| self.mapper.read_ppu_address(address, &self.vram));
+        match pages {
+            [0, 0, 1, 1] => Some(NametableMirroring::Horizontal),
+            [0, 1, 0, 1] => Some(NametableMirroring::Vertical),
+            [0, 0, 0, 0] => Some(NametableMirroring::SingleScreenBank0),
+            [1, 1, 1, 1] => Some(NametableMirroring::SingleScreenBank1),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn irq(&self) -> bool {
+        self.mapper.interrupt_flag()
+    }
+
+    pub(crate) fn set_ppu_ctrl(&mut self, value: u8) {
+        self.ppu_ctrl = value;
+        self.mapper.process_ppu_ctrl_update(value);
+    }
+
+    pub(crate) fn set_ppu_mask(&mut self, value: u8) {
+        self.mapper.process_ppu_mask_update(value);
+    }
+
+    fn ppu_fetch(&mut self, address: u16) {
+        self.mapper.read_ppu_address(address, &self.vram);
+    }
+
+    /// Perform the PPU memory fetches for one rendered scanline using the pattern tables selected
+    /// by PPUCTRL, clocking the mapper once per PPU cycle and once per CPU cycle.
+    ///
+    /// This follows the same fetch order as the PPU, including the two spurious nametable fetches
+    /// at the end of the scanline that MMC5 uses to detect scanlines. 8x8 sprites are assumed.
+    pub(crate) fn render_scanline(&mut self) {
+        let bg_pattern_table = if self.ppu_ctrl & 0x10 != 0 { 0x1000 } else { 0x0000 };
+        let sprite_pattern_table = if self.ppu_ctrl & 0x08 != 0 { 0x1000 } else { 0x0000 };
+
+        let mut bus_address = 0x2000;
+        for dot in 0..341_u16 {
+            let tile_cycle_offset = dot.wrapping_sub(1) & 0x07;
+            let fetch_address = match dot {
+                1..=256 | 321..=336 => {
+                    // The first two tiles of each scanline are fetched at the end of the previous
+                    // scanline
+                    let tile = if dot <= 256 { (dot - 1) / 8 + 2 } else { (dot - 321) / 8 };
+                    match tile_cycle_offset {
+                        0 => Some(0x2000 + tile),
+                        1 => Some(0x23C0),
+                        2 => Some(bg_pattern_table),
+                        4 => Some(bg_pattern_table | 0x0008),
+                        _ => None,
+                    }
+                }
+                257..=320 => {
+                    let sprite = (dot - 257) / 8;
+                    match tile_cycle_offset {
+                        0 | 1 => Some(0x2000 + sprite + 1),
+                        2 => Some(sprite_pattern_table),
+                        4 => Some(sprite_pattern_table | 0x0008),
+                        _ => None,
+                    }
+                }
+                // Same address as the first nametable fetch of the next scanline
+                337 | 339 => Some(0x2002),
+                _ => None,
+            };
+
+            if let Some(address) = fetch_address {
+                self.ppu_fetch(address);
+                bus_address = address;
+            }
+            self.mapper.tick(bus_address);
+
+            if dot % 3 == 2 {
+                self.mapper.tick_cpu();
+            }
+        }
+    }
+
+    pub(crate) fn render_scanlines(&mut self, scanlines: u32) {
+        for _ in 0..scanlines {
+            self.render_scanline();
+        }
+    }
+}
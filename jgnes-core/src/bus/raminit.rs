@@ -0,0 +1,79 @@
+//! Power-on contents of CPU internal RAM and PPU VRAM.
+//!
+//! Real hardware powers on with RAM in an unpredictable state, and a handful of games (knowingly
+//! or not) depend on what that state looks like. The random pattern draws from the core RNG, so a
+//! given seed always produces the same RAM contents.
+
+use crate::rng::CoreRng;
+use bincode::{Decode, Encode};
+use jgnes_proc_macros::{EnumDisplay, EnumFromStr};
+use serde::{Deserialize, Serialize};
+
+/// What CPU and PPU RAM contain when the console powers on.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Default,
+    Encode,
+    Decode,
+    Serialize,
+    Deserialize,
+    EnumDisplay,
+    EnumFromStr,
+)]
+pub enum RamInitPattern {
+    /// Every byte is $00
+    AllZeros,
+    /// Every byte is $FF
+    AllOnes,
+    /// Repeating blocks of four $00 bytes followed by four $FF bytes
+    Alternating,
+    /// Every byte is randomly either $00 or $FF
+    #[default]
+    Random,
+}
+
+impl RamInitPattern {
+    pub const ALL: [Self; 4] = [Self::AllZeros, Self::AllOnes, Self::Alternating, Self::Random];
+}
+
+/// How to initialize RAM when the console powers on or is hard reset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Encode, Decode)]
+pub struct RamInit {
+    pub pattern: RamInitPattern,
+    /// Seed for the core RNG, which generates the random pattern. If not set, a new seed is chosen
+    /// every time the console powers on; [`Emulator::ram_init`](crate::Emulator::ram_init) reports
+    /// the seed that was actually used.
+    pub seed: Option<u64>,
+}
+
+impl RamInit {
+    /// Fill the given RAM arrays, in order, according to this pattern.
+    pub(crate) fn fill(self, rams: &mut [&mut [u8]], rng: &mut CoreRng) {
+        for ram in rams {
+            for (i, byte) in ram.iter_mut().enumerate() {
+                *byte = match self.pattern {
+                    RamInitPattern::AllZeros => 0x00,
+                    RamInitPattern::AllOnes => 0xFF,
+                    RamInitPattern::Alternating => {
+                        if i & 0x04 == 0 {
+                            0x00
+                        } else {
+                            0xFF
+                        }
+                    }
+                    RamInitPattern::Random => {
+                        if rng.next_bool() {
+                            0xFF
+                        } else {
+                            0x00
+                        }
+                    }
+                };
+            }
+        }
+    }
+}
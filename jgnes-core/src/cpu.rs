@@ -3,6 +3,7 @@
 use crate::bus;
 use crate::bus::{CpuBus, PpuRegister};
 use crate::cpu::instructions::InstructionState;
+use crate::logging::CPU_TARGET;
 use crate::num::GetBit;
 use bincode::{Decode, Encode};
 
@@ -135,11 +136,17 @@ pub struct CpuState {
     registers: CpuRegisters,
     state: State,
     terminated: bool,
+    // Total CPU cycles since power on, including cycles where the CPU was halted
+    cycle_count: u64,
 }
 
 impl CpuState {
     pub fn new(registers: CpuRegisters) -> Self {
-        Self { registers, state: State::INITIAL, terminated: false }
+        Self { registers, state: State::INITIAL, terminated: false, cycle_count: 0 }
+    }
+
+    pub fn cycle_count(&self) -> u64 {
+        self.cycle_count
     }
 
     #[cfg(test)]
@@ -149,7 +156,10 @@ impl CpuState {
 }
 
 /// Run the CPU for 1 CPU cycle.
+#[cfg_attr(feature = "tracing", tracing::instrument(name = "cpu_tick", level = "trace", skip_all))]
 pub fn tick(state: &mut CpuState, bus: &mut CpuBus<'_>, is_apu_active_cycle: bool) {
+    state.cycle_count += 1;
+
     if state.terminated {
         return;
     }
@@ -163,8 +173,15 @@ pub fn tick(state: &mut CpuState, bus: &mut CpuBus<'_>, is_apu_active_cycle: boo
                 bus.clear_oamdma_dirty();
 
                 let source_high_byte = bus.read_oamdma_for_transfer();
-                log::trace!("OAM: Initiating OAM DMA transfer from {source_high_byte:02X}");
-
+                log::trace!(target: CPU_TARGET, "OAM: Initiating OAM DMA transfer from {source_high_byte:02X}");
+
+                // OAM DMA halts the CPU for 513 cycles: this cycle, then 256 read/write pairs. The
+                // reads must line up with the APU's read cycles, which takes 1 extra cycle half of
+                // the time.
+                //
+                // The interrupt poll for this instruction boundary has already happened, so an
+                // interrupt that is pending now is serviced after the DMA, while an interrupt that
+                // becomes pending during the DMA is serviced after the next instruction
                 let oam_dma_state = OamDmaState {
                     cycles_remaining: 512,
                     source_high_byte,
@@ -177,7 +194,7 @@ pub fn tick(state: &mut CpuState, bus: &mut CpuBus<'_>, is_apu_active_cycle: boo
                     State::OamDma(oam_dma_state)
                 }
             } else if pending_interrupt {
-                log::trace!("INTERRUPT: Handling hardware NMI/IRQ interrupt");
+                log::trace!(target: CPU_TARGET, "INTERRUPT: Handling hardware NMI/IRQ interrupt");
 
                 let interrupt_state = InstructionState::interrupt_service();
 
@@ -251,3 +268,64 @@ pub fn reset(cpu_state: &mut CpuState, bus: &mut CpuBus<'_>) {
 
     cpu_state.terminated = false;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bus::{Bus, IrqSource, RamInit, cartridge};
+    use crate::rng::CoreRng;
+
+    // CLI; LDA #$02; STA $4014; NOP
+    const OAM_DMA_PROGRAM: [u8; 7] = [0x58, 0xA9, 0x02, 0x8D, 0x14, 0x40, 0xEA];
+
+    // Returns the number of cycles until the NOP after the OAM DMA is fetched
+    fn run_oam_dma_program(apu_parity: u64, irq_cycle: Option<u64>) -> (u64, Bus) {
+        let mut prg_rom = vec![0xEA; 32768];
+        prg_rom[..OAM_DMA_PROGRAM.len()].copy_from_slice(&OAM_DMA_PROGRAM);
+        // RESET vector at $8000, IRQ vector at $9000
+        prg_rom[0x7FFC..].copy_from_slice(&[0x00, 0x80, 0x00, 0x90]);
+
+        let mapper = cartridge::new_mmc1(prg_rom);
+        let mut bus = Bus::from_cartridge(mapper, RamInit::default(), &mut CoreRng::new(0));
+        let mut cpu_state = CpuState::new(CpuRegisters::create(&mut bus.cpu()));
+
+        let mut nop_fetch_cycle = None;
+        while cpu_state.registers.pc < 0x9000 {
+            if irq_cycle == Some(cpu_state.cycle_count) {
+                bus.cpu().interrupt_lines().set_irq_low_pull(IrqSource::ApuFrameCounter, true);
+            }
+
+            let is_apu_active_cycle = cpu_state.cycle_count % 2 == apu_parity;
+            tick(&mut cpu_state, &mut bus.cpu(), is_apu_active_cycle);
+            bus.tick_cpu();
+            bus.tick();
+            bus.poll_interrupt_lines();
+
+            if cpu_state.registers.pc == 0x8007 && nop_fetch_cycle.is_none() {
+                nop_fetch_cycle = Some(cpu_state.cycle_count);
+            }
+            if irq_cycle.is_none() && nop_fetch_cycle.is_some() {
+                break;
+            }
+        }
+
+        (nop_fetch_cycle.unwrap(), bus)
+    }
+
+    #[test]
+    fn oam_dma_cycles() {
+        // 8 cycles for CLI/LDA/STA and 1 cycle for the NOP fetch, plus 513 or 514 for the DMA
+        let mut cycles = [0, 1].map(|parity| run_oam_dma_program(parity, None).0);
+        cycles.sort_unstable();
+        assert_eq!(cycles, [522, 523]);
+    }
+
+    #[test]
+    fn irq_during_oam_dma() {
+        let (_, mut bus) = run_oam_dma_program(0, Some(20));
+
+        // The NOP after the DMA executes before the IRQ handler
+        assert_eq!(bus.cpu().read_address(0x01FD), 0x80);
+        assert_eq!(bus.cpu().read_address(0x01FC), 0x07);
+    }
+}
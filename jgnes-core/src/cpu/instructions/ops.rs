@@ -1900,6 +1900,12 @@ impl_multi_byte_noop!(nop_zero_page_x, zero_page_x);
 impl_multi_byte_noop!(nop_absolute, absolute);
 impl_multi_byte_noop!(nop_absolute_x, absolute_x);
 
+/// Execute a single cycle of the current instruction.
+///
+/// Dispatch is a single exhaustive match on the opcode that the compiler lowers to a jump table,
+/// and each instruction function switches on `state.cycle` directly. There is no decode step or
+/// per-instruction list of cycle ops to precompute, so a separate opcode lookup table would not
+/// remove any work from the hot path.
 pub fn execute_cycle(
     state: &mut InstructionState,
     registers: &mut CpuRegisters,
@@ -1,8 +1,9 @@
 mod load;
 
-use crate::bus::{Bus, cartridge};
+use crate::bus::{Bus, RamInit, cartridge};
 use crate::cpu;
 use crate::cpu::{CpuRegisters, CpuState, StatusReadContext};
+use crate::rng::CoreRng;
 use std::collections::HashMap;
 
 #[derive(Default)]
@@ -74,7 +75,7 @@ fn run_test(program: &str, expected_state: ExpectedState) {
 
     let mapper = cartridge::new_mmc1(prg_rom);
 
-    let mut bus = Bus::from_cartridge(mapper);
+    let mut bus = Bus::from_cartridge(mapper, RamInit::default(), &mut CoreRng::new(0));
 
     let mut cpu_state = CpuState::new(CpuRegisters::create(&mut bus.cpu()));
 
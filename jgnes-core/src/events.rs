@@ -0,0 +1,122 @@
+//! Recording of CPU writes to PPU, APU/IO, and mapper registers and of sprite 0 hits, for debugging
+//! timing-sensitive effects such as mid-frame scroll splits.
+
+use bincode::{Decode, Encode};
+use std::fmt::{Display, Formatter};
+
+/// Which part of the system a register write goes to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegisterWriteTarget {
+    /// $2000-$3FFF
+    Ppu,
+    /// $4000-$4017, which includes OAM DMA and the joypad strobe in addition to the APU
+    ApuIo,
+    /// $4020-$FFFF
+    Cartridge,
+}
+
+impl Display for RegisterWriteTarget {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Ppu => write!(f, "PPU"),
+            Self::ApuIo => write!(f, "APU/IO"),
+            Self::Cartridge => write!(f, "Cartridge"),
+        }
+    }
+}
+
+/// A CPU write to a memory-mapped register, along with the PPU position when it was applied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct RegisterWriteEvent {
+    pub scanline: u16,
+    pub dot: u16,
+    pub address: u16,
+    pub value: u8,
+}
+
+impl RegisterWriteEvent {
+    /// Which part of the system the written address belongs to, or None for unmapped addresses.
+    #[must_use]
+    pub fn target(&self) -> Option<RegisterWriteTarget> {
+        match self.address {
+            0x2000..=0x3FFF => Some(RegisterWriteTarget::Ppu),
+            0x4000..=0x4017 => Some(RegisterWriteTarget::ApuIo),
+            0x4020..=0xFFFF => Some(RegisterWriteTarget::Cartridge),
+            _ => None,
+        }
+    }
+
+    /// The conventional name of the register that was written to, if it has one. Mapper registers
+    /// are not named because their meaning depends on the cartridge board.
+    #[must_use]
+    pub fn register_name(&self) -> Option<&'static str> {
+        let name = match self.address {
+            0x2000..=0x3FFF => match self.address & 0x0007 {
+                0x00 => "PPUCTRL",
+                0x01 => "PPUMASK",
+                0x02 => "PPUSTATUS",
+                0x03 => "OAMADDR",
+                0x04 => "OAMDATA",
+                0x05 => "PPUSCROLL",
+                0x06 => "PPUADDR",
+                0x07 => "PPUDATA",
+                _ => unreachable!("value & 0x07 should always be 0x00-0x07"),
+            },
+            0x4000 => "SQ1_VOL",
+            0x4001 => "SQ1_SWEEP",
+            0x4002 => "SQ1_LO",
+            0x4003 => "SQ1_HI",
+            0x4004 => "SQ2_VOL",
+            0x4005 => "SQ2_SWEEP",
+            0x4006 => "SQ2_LO",
+            0x4007 => "SQ2_HI",
+            0x4008 => "TRI_LINEAR",
+            0x400A => "TRI_LO",
+            0x400B => "TRI_HI",
+            0x400C => "NOISE_VOL",
+            0x400E => "NOISE_LO",
+            0x400F => "NOISE_HI",
+            0x4010 => "DMC_FREQ",
+            0x4011 => "DMC_RAW",
+            0x4012 => "DMC_START",
+            0x4013 => "DMC_LEN",
+            0x4014 => "OAMDMA",
+            0x4015 => "SND_CHN",
+            0x4016 => "JOY1",
+            0x4017 => "JOY2/FRAME_CNT",
+            _ => return None,
+        };
+        Some(name)
+    }
+}
+
+impl Display for RegisterWriteEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:>3} {:>3}  ${:04X} = ${:02X}",
+            self.scanline, self.dot, self.address, self.value
+        )?;
+
+        match (self.target(), self.register_name()) {
+            (Some(target), Some(name)) => write!(f, "  {target} {name}"),
+            (Some(target), None) => write!(f, "  {target}"),
+            (None, _) => Ok(()),
+        }
+    }
+}
+
+/// The PPU position where the sprite 0 hit flag was set in PPUSTATUS.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Encode, Decode)]
+#[non_exhaustive]
+pub struct Sprite0HitEvent {
+    pub scanline: u16,
+    pub dot: u16,
+}
+
+impl Display for Sprite0HitEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "scanline {} dot {}", self.scanline, self.dot)
+    }
+}
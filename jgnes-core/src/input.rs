@@ -1,6 +1,7 @@
 use bincode::{Decode, Encode};
 
-#[derive(Debug, Clone, Copy, Default, Encode, Decode)]
+/// Which buttons on a standard NES controller are currently pressed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Encode, Decode)]
 pub struct JoypadState {
     pub up: bool,
     pub down: bool,
@@ -1,3 +1,108 @@
+//! NES emulation core for jgnes, with no dependencies on any particular frontend.
+//!
+//! The core does not do any I/O itself. Instead, an [`Emulator`] is created with four frontend
+//! implementations: a [`Renderer`] that displays completed frames, an [`AudioPlayer`] that receives
+//! raw APU samples, an [`InputPoller`] that reports controller state, and a [`SaveWriter`] that
+//! persists battery-backed cartridge RAM. Frontends then call [`Emulator::tick`] in a loop, which
+//! calls into those implementations as needed.
+//!
+//! Everything outside of this facade is an implementation detail. The `audio`, `logging`, and
+//! `patch` modules contain helpers that most frontends need, but they are not required in order
+//! to embed the core.
+//!
+//! # Example
+//!
+//! ```
+//! use jgnes_core::{
+//!     AudioPlayer, ColorEmphasis, Emulator, EmulatorConfig, EmulatorCreateArgs, FrameBuffer,
+//!     InputPoller, JoypadState, RamInit, Renderer, SaveWriter, TickEffect, TimingMode,
+//! };
+//! use std::convert::Infallible;
+//!
+//! struct NullRenderer;
+//!
+//! impl Renderer for NullRenderer {
+//!     type Err = Infallible;
+//!
+//!     fn render_frame(&mut self, _: &FrameBuffer, _: ColorEmphasis) -> Result<(), Self::Err> {
+//!         Ok(())
+//!     }
+//!
+//!     fn set_timing_mode(&mut self, _: TimingMode) -> Result<(), Self::Err> {
+//!         Ok(())
+//!     }
+//! }
+//!
+//! struct NullAudioPlayer;
+//!
+//! impl AudioPlayer for NullAudioPlayer {
+//!     type Err = Infallible;
+//!
+//!     fn push_sample(&mut self, _: f64) -> Result<(), Self::Err> {
+//!         Ok(())
+//!     }
+//!
+//!     fn set_timing_mode(&mut self, _: TimingMode) {}
+//! }
+//!
+//! struct NoInput;
+//!
+//! impl InputPoller for NoInput {
+//!     fn poll_p1_input(&self) -> JoypadState {
+//!         JoypadState::default()
+//!     }
+//!
+//!     fn poll_p2_input(&self) -> JoypadState {
+//!         JoypadState::default()
+//!     }
+//! }
+//!
+//! struct NullSaveWriter;
+//!
+//! impl SaveWriter for NullSaveWriter {
+//!     type Err = Infallible;
+//!
+//!     fn persist_sram(&mut self, _: &[u8]) -> Result<(), Self::Err> {
+//!         Ok(())
+//!     }
+//! }
+//!
+//! // An NROM cartridge with 16KB of PRG ROM and 8KB of CHR ROM, where the program is an
+//! // infinite loop at $8000
+//! let mut rom_bytes = vec![0x4E, 0x45, 0x53, 0x1A, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+//! let mut prg_rom = vec![0; 16 * 1024];
+//! prg_rom[..3].copy_from_slice(&[0x4C, 0x00, 0x80]);
+//! prg_rom[0x3FFC..0x3FFE].copy_from_slice(&[0x00, 0x80]);
+//! rom_bytes.extend(prg_rom);
+//! rom_bytes.extend([0; 8 * 1024]);
+//!
+//! let mut emulator = Emulator::create(EmulatorCreateArgs {
+//!     rom_bytes,
+//!     sav_bytes: None,
+//!     forced_timing_mode: None,
+//!     forced_mapper_number: None,
+//!     ram_init: RamInit::default(),
+//!     renderer: NullRenderer,
+//!     audio_player: NullAudioPlayer,
+//!     input_poller: NoInput,
+//!     save_writer: NullSaveWriter,
+//! })?;
+//! assert_eq!(emulator.timing_mode(), TimingMode::Ntsc);
+//!
+//! // Run until the first frame has been sent to the renderer
+//! let config = EmulatorConfig::default();
+//! while emulator.tick(&config)? != TickEffect::FrameRendered {}
+//! assert_eq!(emulator.frame_count(), 1);
+//!
+//! // Save states are plain byte streams
+//! let mut save_state = Vec::new();
+//! emulator.save_state(&mut save_state)?;
+//! while emulator.tick(&config)? != TickEffect::FrameRendered {}
+//! emulator.load_state(save_state.as_slice())?;
+//! assert_eq!(emulator.frame_count(), 1);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
 #![forbid(unsafe_code)]
 
 mod api;
@@ -5,15 +110,27 @@ mod apu;
 pub mod audio;
 mod bus;
 mod cpu;
+mod events;
 mod input;
+pub mod logging;
 mod num;
+pub mod patch;
 mod ppu;
+mod profiling;
+mod rng;
 mod serialize;
 
 pub use api::{
-    AudioPlayer, ColorEmphasis, EmulationError, EmulationResult, EmulationState, Emulator,
-    EmulatorConfig, EmulatorCreateArgs, InputPoller, Renderer, SaveWriter, TickEffect,
+    AudioPlayer, CartridgeSwapArgs, ClassifyError, ColorEmphasis, EmulationError, EmulationResult,
+    EmulationState, Emulator, EmulatorConfig, EmulatorCreateArgs, ErrorSeverity,
+    InitializationError, InputPoller, Renderer, SaveWriter, TickEffect, TickError,
+};
+pub use bus::cartridge::{
+    CartridgeFileError, CartridgeInfo, HeaderFormat, HeaderMirroring, RomDiagnostics,
+    RomHeaderInfo, RomLoadError, RomProblem,
 };
-pub use bus::TimingMode;
+pub use bus::{RamInit, RamInitPattern, TimingMode};
+pub use events::{RegisterWriteEvent, RegisterWriteTarget, Sprite0HitEvent};
 pub use input::JoypadState;
-pub use ppu::{FrameBuffer, SCREEN_HEIGHT, SCREEN_WIDTH};
+pub use ppu::{FrameBuffer, SCREEN_HEIGHT, SCREEN_WIDTH, ScrollPosition};
+pub use serialize::{SaveStateError, fnv1a_hash};
@@ -0,0 +1,51 @@
+//! Log targets for each emulator subsystem, which allow log levels to be configured separately for
+//! e.g. the PPU and the mappers.
+//!
+//! Targets share the `jgnes::` prefix, so a filter for `jgnes` applies to every subsystem.
+
+use jgnes_proc_macros::{EnumDisplay, EnumFromStr};
+
+pub const CPU_TARGET: &str = "jgnes::cpu";
+pub const PPU_TARGET: &str = "jgnes::ppu";
+pub const APU_TARGET: &str = "jgnes::apu";
+pub const MAPPER_TARGET: &str = "jgnes::mapper";
+pub const RENDERER_TARGET: &str = "jgnes::renderer";
+pub const AUDIO_TARGET: &str = "jgnes::audio";
+pub const INPUT_TARGET: &str = "jgnes::input";
+
+/// An emulator subsystem with its own log target.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, EnumDisplay, EnumFromStr)]
+pub enum LogSubsystem {
+    Cpu,
+    Ppu,
+    Apu,
+    Mapper,
+    Renderer,
+    Audio,
+    Input,
+}
+
+impl LogSubsystem {
+    pub const ALL: [Self; 7] =
+        [Self::Cpu, Self::Ppu, Self::Apu, Self::Mapper, Self::Renderer, Self::Audio, Self::Input];
+
+    /// The log target that this subsystem logs to.
+    #[must_use]
+    pub const fn target(self) -> &'static str {
+        match self {
+            Self::Cpu => CPU_TARGET,
+            Self::Ppu => PPU_TARGET,
+            Self::Apu => APU_TARGET,
+            Self::Mapper => MAPPER_TARGET,
+            Self::Renderer => RENDERER_TARGET,
+            Self::Audio => AUDIO_TARGET,
+            Self::Input => INPUT_TARGET,
+        }
+    }
+
+    /// The subsystem that a log target belongs to, if any.
+    #[must_use]
+    pub fn from_target(target: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|subsystem| subsystem.target() == target)
+    }
+}
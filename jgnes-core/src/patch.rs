@@ -0,0 +1,493 @@
+//! In-memory soft patching of ROM files using IPS or BPS patches, and creation of IPS patches from
+//! modified ROM data.
+
+use thiserror::Error;
+
+const IPS_HEADER: &[u8] = b"PATCH";
+const IPS_EOF: &[u8] = b"EOF";
+const BPS_HEADER: &[u8] = b"BPS1";
+// Source CRC32, target CRC32, patch CRC32
+const BPS_FOOTER_LEN: usize = 12;
+
+/// A supported patch file format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PatchFormat {
+    Ips,
+    Bps,
+}
+
+impl PatchFormat {
+    /// Determine a patch's format from its header bytes.
+    #[must_use]
+    pub fn detect(patch: &[u8]) -> Option<Self> {
+        if patch.starts_with(IPS_HEADER) {
+            Some(Self::Ips)
+        } else if patch.starts_with(BPS_HEADER) {
+            Some(Self::Bps)
+        } else {
+            None
+        }
+    }
+
+    /// All file extensions that patches are recognized by, in the order that they are searched.
+    #[must_use]
+    pub const fn extensions() -> &'static [&'static str] {
+        &["bps", "ips"]
+    }
+}
+
+/// An error encountered while applying or creating a patch.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum PatchError {
+    #[error("file is not an IPS or BPS patch")]
+    UnknownFormat,
+    #[error("patch ended unexpectedly")]
+    UnexpectedEof,
+    #[error("patch reads or writes out of bounds")]
+    OutOfBounds,
+    #[error("ROM is {actual} bytes, but patch expects a {expected}-byte ROM")]
+    SourceSizeMismatch { expected: usize, actual: usize },
+    #[error("{kind} checksum mismatch: expected {expected:08X}, was {actual:08X}")]
+    ChecksumMismatch { kind: &'static str, expected: u32, actual: u32 },
+    #[error("patch output would be {size} bytes, which is too large for an NES ROM")]
+    TargetTooLarge { size: usize },
+}
+
+/// Apply an IPS or BPS patch to the given ROM, detecting the patch format from its header.
+///
+/// # Errors
+///
+/// This function will return an error if the patch is not a valid IPS or BPS patch, or if it is a
+/// BPS patch for a different ROM.
+pub fn apply(rom: &[u8], patch: &[u8]) -> Result<Vec<u8>, PatchError> {
+    match PatchFormat::detect(patch) {
+        Some(PatchFormat::Ips) => apply_ips(rom, patch),
+        Some(PatchFormat::Bps) => apply_bps(rom, patch),
+        None => Err(PatchError::UnknownFormat),
+    }
+}
+
+struct PatchReader<'a> {
+    patch: &'a [u8],
+    position: usize,
+}
+
+impl<'a> PatchReader<'a> {
+    fn new(patch: &'a [u8], position: usize) -> Self {
+        Self { patch, position }
+    }
+
+    fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], PatchError> {
+        let end = self.position.checked_add(len).ok_or(PatchError::UnexpectedEof)?;
+        let bytes = self.patch.get(self.position..end).ok_or(PatchError::UnexpectedEof)?;
+        self.position = end;
+        Ok(bytes)
+    }
+
+    fn read_u8(&mut self) -> Result<u8, PatchError> {
+        Ok(self.read_bytes(1)?[0])
+    }
+
+    fn read_be(&mut self, len: usize) -> Result<usize, PatchError> {
+        Ok(self.read_bytes(len)?.iter().fold(0, |acc, &b| (acc << 8) | usize::from(b)))
+    }
+
+    // BPS variable-length integer: 7 bits per byte, with the high bit marking the last byte and
+    // an implicit +1 per continuation byte so that every value has exactly one encoding
+    fn read_varint(&mut self) -> Result<usize, PatchError> {
+        let mut value = 0_usize;
+        let mut shift = 1_usize;
+        loop {
+            let byte = self.read_u8()?;
+            value = value
+                .checked_add(usize::from(byte & 0x7F) * shift)
+                .ok_or(PatchError::OutOfBounds)?;
+            if byte & 0x80 != 0 {
+                return Ok(value);
+            }
+            shift = shift.checked_mul(128).ok_or(PatchError::OutOfBounds)?;
+            value = value.checked_add(shift).ok_or(PatchError::OutOfBounds)?;
+        }
+    }
+}
+
+/// Apply an IPS patch to the given ROM.
+///
+/// # Errors
+///
+/// This function will return an error if the patch is not a valid IPS patch.
+pub fn apply_ips(rom: &[u8], patch: &[u8]) -> Result<Vec<u8>, PatchError> {
+    if !patch.starts_with(IPS_HEADER) {
+        return Err(PatchError::UnknownFormat);
+    }
+
+    let mut target = rom.to_vec();
+    let mut reader = PatchReader::new(patch, IPS_HEADER.len());
+    loop {
+        let offset_bytes = reader.read_bytes(3)?;
+        if offset_bytes == IPS_EOF {
+            break;
+        }
+        let offset = offset_bytes.iter().fold(0, |acc, &b| (acc << 8) | usize::from(b));
+
+        let (len, rle_value) = match reader.read_be(2)? {
+            // Size of 0 indicates an RLE record
+            0 => {
+                let len = reader.read_be(2)?;
+                (len, Some(reader.read_u8()?))
+            }
+            len => (len, None),
+        };
+
+        if target.len() < offset + len {
+            target.resize(offset + len, 0);
+        }
+
+        match rle_value {
+            Some(value) => target[offset..offset + len].fill(value),
+            None => target[offset..offset + len].copy_from_slice(reader.read_bytes(len)?),
+        }
+    }
+
+    // Some IPS patches truncate the file after patching
+    if let Ok(truncated_len) = reader.read_be(3) {
+        target.truncate(truncated_len);
+    }
+
+    Ok(target)
+}
+
+/// Apply a BPS patch to the given ROM.
+///
+/// # Errors
+///
+/// This function will return an error if the patch is not a valid BPS patch, or if the source ROM
+/// or patch checksum does not match.
+pub fn apply_bps(rom: &[u8], patch: &[u8]) -> Result<Vec<u8>, PatchError> {
+    if !patch.starts_with(BPS_HEADER) || patch.len() < BPS_HEADER.len() + BPS_FOOTER_LEN {
+        return Err(PatchError::UnknownFormat);
+    }
+
+    let footer_start = patch.len() - BPS_FOOTER_LEN;
+    let footer_crc = |i: usize| {
+        let start = footer_start + 4 * i;
+        u32::from_le_bytes([patch[start], patch[start + 1], patch[start + 2], patch[start + 3]])
+    };
+    let (source_crc, target_crc, patch_crc) = (footer_crc(0), footer_crc(1), footer_crc(2));
+
+    verify_crc32("Patch", patch_crc, &patch[..patch.len() - 4])?;
+    verify_crc32("Source ROM", source_crc, rom)?;
+
+    let mut reader = PatchReader::new(&patch[..footer_start], BPS_HEADER.len());
+
+    let source_size = reader.read_varint()?;
+    if source_size != rom.len() {
+        return Err(PatchError::SourceSizeMismatch { expected: source_size, actual: rom.len() });
+    }
+
+    let target_size = reader.read_varint()?;
+    if target_size > BPS_MAX_TARGET_SIZE {
+        return Err(PatchError::TargetTooLarge { size: target_size });
+    }
+
+    let metadata_size = reader.read_varint()?;
+    reader.read_bytes(metadata_size)?;
+
+    let mut target = vec![0; target_size];
+    let mut output_offset = 0_usize;
+    let mut source_relative_offset = 0;
+    let mut target_relative_offset = 0;
+    while reader.position < footer_start {
+        let action = reader.read_varint()?;
+        let len = (action >> 2) + 1;
+        let output_end = output_offset
+            .checked_add(len)
+            .filter(|&output_end| output_end <= target.len())
+            .ok_or(PatchError::OutOfBounds)?;
+
+        match action & 0x03 {
+            // SourceRead
+            0 => {
+                let source = rom.get(output_offset..output_end).ok_or(PatchError::OutOfBounds)?;
+                target[output_offset..output_end].copy_from_slice(source);
+            }
+            // TargetRead
+            1 => {
+                target[output_offset..output_end].copy_from_slice(reader.read_bytes(len)?);
+            }
+            // SourceCopy
+            2 => {
+                source_relative_offset =
+                    apply_relative_offset(source_relative_offset, reader.read_varint()?)?;
+                let source_end =
+                    source_relative_offset.checked_add(len).ok_or(PatchError::OutOfBounds)?;
+                let source =
+                    rom.get(source_relative_offset..source_end).ok_or(PatchError::OutOfBounds)?;
+                target[output_offset..output_end].copy_from_slice(source);
+                source_relative_offset = source_end;
+            }
+            // TargetCopy
+            3 => {
+                target_relative_offset =
+                    apply_relative_offset(target_relative_offset, reader.read_varint()?)?;
+                if target_relative_offset >= output_offset {
+                    return Err(PatchError::OutOfBounds);
+                }
+                // Copy byte by byte; the source and destination ranges are allowed to overlap, which
+                // is how BPS encodes runs
+                for i in 0..len {
+                    target[output_offset + i] = target[target_relative_offset + i];
+                }
+                target_relative_offset += len;
+            }
+            _ => unreachable!("value & 0x03 should always be 0x00/0x01/0x02/0x03"),
+        }
+
+        output_offset = output_end;
+    }
+
+    verify_crc32("Patched ROM", target_crc, &target)?;
+
+    Ok(target)
+}
+
+// Several times larger than any real NES ROM; patches that claim a larger output are malformed, and
+// allocating the output buffer up front could otherwise exhaust memory
+const BPS_MAX_TARGET_SIZE: usize = 64 * 1024 * 1024;
+
+// IPS offsets are 24-bit and record lengths are 16-bit
+const IPS_MAX_OFFSET: usize = 0xFF_FFFF;
+const IPS_MAX_RECORD_LEN: usize = 0xFFFF;
+// Offset whose big-endian encoding is "EOF"
+const IPS_EOF_OFFSET: usize = 0x45_4F46;
+// Starting a new record costs 5 bytes (offset + length), so runs of fewer unchanged bytes than this
+// are cheaper to include in the surrounding record
+const IPS_MIN_RECORD_GAP: usize = 6;
+
+/// Create an IPS patch that transforms `original` into `modified`, e.g. to export edits made to a
+/// ROM in memory.
+///
+/// If `modified` is shorter than `original`, the patch uses the truncation extension that is
+/// supported by most IPS patchers including [`apply_ips`].
+///
+/// # Errors
+///
+/// This function will return an error if a difference is located past the 16MB limit of the IPS
+/// format.
+pub fn create_ips(original: &[u8], modified: &[u8]) -> Result<Vec<u8>, PatchError> {
+    let differs = |i: usize| original.get(i) != modified.get(i);
+
+    let mut patch = IPS_HEADER.to_vec();
+    let mut i = 0;
+    while i < modified.len() {
+        if !differs(i) {
+            i += 1;
+            continue;
+        }
+
+        // An offset that spells "EOF" would be read as the end of the patch, so start the record
+        // one byte earlier instead
+        let start = if i == IPS_EOF_OFFSET { i - 1 } else { i };
+        if start > IPS_MAX_OFFSET {
+            return Err(PatchError::OutOfBounds);
+        }
+
+        let mut end = i + 1;
+        while end < modified.len() && end - start < IPS_MAX_RECORD_LEN {
+            if differs(end) {
+                end += 1;
+                continue;
+            }
+
+            let gap_len = (end..modified.len().min(end + IPS_MIN_RECORD_GAP))
+                .take_while(|&j| !differs(j))
+                .count();
+            if gap_len >= IPS_MIN_RECORD_GAP || end + gap_len == modified.len() {
+                break;
+            }
+            end = (end + gap_len).min(start + IPS_MAX_RECORD_LEN);
+        }
+
+        push_be(&mut patch, start, 3);
+        push_be(&mut patch, end - start, 2);
+        patch.extend(&modified[start..end]);
+
+        i = end;
+    }
+
+    patch.extend(IPS_EOF);
+
+    if modified.len() < original.len() {
+        if modified.len() > IPS_MAX_OFFSET {
+            return Err(PatchError::OutOfBounds);
+        }
+        push_be(&mut patch, modified.len(), 3);
+    }
+
+    Ok(patch)
+}
+
+fn push_be(patch: &mut Vec<u8>, value: usize, len: usize) {
+    patch.extend((0..len).rev().map(|i| (value >> (8 * i)) as u8));
+}
+
+// BPS relative offsets store the sign in the lowest bit
+fn apply_relative_offset(offset: usize, encoded: usize) -> Result<usize, PatchError> {
+    let magnitude = encoded >> 1;
+    if encoded & 1 != 0 {
+        offset.checked_sub(magnitude).ok_or(PatchError::OutOfBounds)
+    } else {
+        offset.checked_add(magnitude).ok_or(PatchError::OutOfBounds)
+    }
+}
+
+fn verify_crc32(kind: &'static str, expected: u32, bytes: &[u8]) -> Result<(), PatchError> {
+    let actual = crc32(bytes);
+    if actual != expected {
+        return Err(PatchError::ChecksumMismatch { kind, expected, actual });
+    }
+    Ok(())
+}
+
+// Standard CRC-32 (IEEE 802.3), as used by BPS
+fn crc32(bytes: &[u8]) -> u32 {
+    let crc = bytes.iter().fold(!0_u32, |crc, &byte| {
+        (0..8).fold(crc ^ u32::from(byte), |crc, _| {
+            if crc & 1 != 0 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 }
+        })
+    });
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crc32_check_value() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn ips_records() {
+        let rom = [0_u8; 8];
+        let mut patch = IPS_HEADER.to_vec();
+        // Normal record: 2 bytes at offset 1
+        patch.extend([0x00, 0x00, 0x01, 0x00, 0x02, 0xAA, 0xBB]);
+        // RLE record: 3 copies of 0xCC at offset 6, extending the file
+        patch.extend([0x00, 0x00, 0x06, 0x00, 0x00, 0x00, 0x03, 0xCC]);
+        patch.extend(IPS_EOF);
+
+        let patched = apply(&rom, &patch).unwrap();
+        assert_eq!(patched, [0x00, 0xAA, 0xBB, 0x00, 0x00, 0x00, 0xCC, 0xCC, 0xCC]);
+    }
+
+    #[test]
+    fn create_ips_round_trip() {
+        let original: Vec<u8> = (0..0x50_0000_u32).map(|i| i as u8).collect();
+
+        let mut modified = original.clone();
+        modified[0x10] = 0xFF;
+        modified[0x14] ^= 0xFF;
+        // Offset that spells "EOF"
+        modified[0x45_4F46] ^= 0xFF;
+        modified[0x2_0000..0x3_0000].fill(0);
+        modified.truncate(0x4F_FFF0);
+        assert_eq!(
+            apply_ips(&original, &create_ips(&original, &modified).unwrap()).unwrap(),
+            modified
+        );
+
+        modified.extend([1, 2, 3]);
+        modified.resize(0x50_0010, 0);
+        assert_eq!(
+            apply_ips(&original, &create_ips(&original, &modified).unwrap()).unwrap(),
+            modified
+        );
+
+        assert_eq!(create_ips(&original, &original).unwrap(), [IPS_HEADER, IPS_EOF].concat());
+    }
+
+    #[test]
+    fn bps_actions() {
+        let rom = b"ABCDEFGH".to_vec();
+        let expected = b"ABCDxyzxyzGHAB".to_vec();
+
+        let mut patch = BPS_HEADER.to_vec();
+        // Source size 8, target size 14, no metadata
+        patch.extend([0x88, 0x8E, 0x80]);
+        // SourceRead 4
+        patch.push(0x80 | (3 << 2));
+        // TargetRead 3 "xyz"
+        patch.push(0x80 | (2 << 2) | 1);
+        patch.extend(b"xyz");
+        // TargetCopy 3 from target offset +4
+        patch.extend([0x80 | (2 << 2) | 3, 0x80 | (4 << 1)]);
+        // SourceCopy 2 from source offset +6
+        patch.extend([0x80 | (1 << 2) | 2, 0x80 | (6 << 1)]);
+        // SourceCopy 2 from source offset -8
+        patch.extend([0x80 | (1 << 2) | 2, 0x80 | (8 << 1) | 1]);
+
+        patch.extend(crc32(&rom).to_le_bytes());
+        patch.extend(crc32(&expected).to_le_bytes());
+        patch.extend(crc32(&patch).to_le_bytes());
+
+        assert_eq!(apply(&rom, &patch).unwrap(), expected);
+
+        let wrong_rom = b"ABCDEFGX".to_vec();
+        assert!(matches!(
+            apply(&wrong_rom, &patch),
+            Err(PatchError::ChecksumMismatch { kind: "Source ROM", .. })
+        ));
+    }
+
+    fn encode_varint(mut value: usize) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        loop {
+            let low_bits = (value & 0x7F) as u8;
+            value >>= 7;
+            if value == 0 {
+                bytes.push(0x80 | low_bits);
+                return bytes;
+            }
+            bytes.push(low_bits);
+            value -= 1;
+        }
+    }
+
+    // Patches with valid checksums, so that only the malformed sizes and offsets are rejected
+    fn bps_patch(rom: &[u8], sizes_and_actions: &[u8]) -> Vec<u8> {
+        let mut patch = BPS_HEADER.to_vec();
+        patch.extend(sizes_and_actions);
+        patch.extend(crc32(rom).to_le_bytes());
+        patch.extend(0_u32.to_le_bytes());
+        patch.extend(crc32(&patch).to_le_bytes());
+        patch
+    }
+
+    #[test]
+    fn bps_huge_sizes_and_offsets() {
+        let rom = b"ABCDEFGH".to_vec();
+
+        assert_eq!(encode_varint(14), [0x8E]);
+        assert_eq!(encode_varint(usize::MAX).len(), 10);
+
+        // Metadata size that overflows the patch position
+        let patch =
+            bps_patch(&rom, &[[0x88, 0x8E].as_slice(), &encode_varint(usize::MAX)].concat());
+        assert!(matches!(apply(&rom, &patch), Err(PatchError::UnexpectedEof)));
+
+        let patch =
+            bps_patch(&rom, &[[0x88].as_slice(), &encode_varint(usize::MAX), &[0x80]].concat());
+        assert!(matches!(apply(&rom, &patch), Err(PatchError::TargetTooLarge { .. })));
+
+        // SourceCopy from an offset far past the end of the source ROM
+        let patch = bps_patch(
+            &rom,
+            &[[0x88, 0x8E, 0x80, 0x80 | (1 << 2) | 2].as_slice(), &encode_varint(usize::MAX - 1)]
+                .concat(),
+        );
+        assert!(matches!(apply(&rom, &patch), Err(PatchError::OutOfBounds)));
+    }
+}
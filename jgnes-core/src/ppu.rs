@@ -9,14 +9,21 @@
 
 use crate::EmulatorConfig;
 use crate::bus::{PpuBus, PpuRegisters, PpuTrackedRegister, PpuWriteToggle, TimingMode};
+use crate::events::Sprite0HitEvent;
+use crate::input::JoypadState;
+use crate::logging::PPU_TARGET;
 use crate::num::GetBit;
 use bincode::{Decode, Encode};
 use std::array;
 use std::ops::RangeInclusive;
 
+/// Width of the frame buffer in pixels.
 pub const SCREEN_WIDTH: u16 = 256;
+/// Height of the frame buffer in pixels, including the rows that NTSC TVs do not display.
 pub const SCREEN_HEIGHT: u16 = 240;
 const NTSC_VISIBLE_SCREEN_HEIGHT: u16 = 224;
+const NAMETABLES_WIDTH: u16 = 2 * SCREEN_WIDTH;
+const NAMETABLES_HEIGHT: u16 = 2 * SCREEN_HEIGHT;
 
 const DOTS_PER_SCANLINE: u16 = 341;
 // Set/reset flags on dot 2 instead of 1 to resolve some CPU/PPU alignment issues that affect NMI
@@ -41,9 +48,11 @@ const PAL_PRE_RENDER_SCANLINE: u16 = 311;
 
 const BLACK_NES_COLOR: u8 = 0x0F;
 
+/// A completed frame, stored as rows of 6-bit NES color indices.
 pub type FrameBuffer = [[u8; SCREEN_WIDTH as usize]; SCREEN_HEIGHT as usize];
 
 impl TimingMode {
+    /// The number of rows of the frame buffer that should be displayed in this timing mode.
     #[must_use]
     pub const fn visible_screen_height(self) -> u16 {
         match self {
@@ -105,6 +114,29 @@ impl InternalRegisters {
     fn nametable_bits(&self) -> u16 {
         self.vram_address & 0x0C00
     }
+
+    // Only meaningful at the start of a visible scanline, after the 2 BG tile pre-fetches from the
+    // end of the previous scanline have incremented coarse X twice
+    fn scroll_position(&self) -> ScrollPosition {
+        let x =
+            ((self.vram_address & 0x0400) >> 2) | (self.coarse_x() << 3) | u16::from(self.fine_x());
+        let y = ((self.vram_address & 0x0800) >> 11) * SCREEN_HEIGHT
+            + (self.coarse_y() << 3)
+            + self.fine_y();
+        ScrollPosition {
+            x: (x + NAMETABLES_WIDTH - 16) % NAMETABLES_WIDTH,
+            y: y % NAMETABLES_HEIGHT,
+        }
+    }
+}
+
+/// The position of the top-left visible pixel of a scanline within the 512x480 space formed by the
+/// four nametables.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Encode, Decode)]
+#[non_exhaustive]
+pub struct ScrollPosition {
+    pub x: u16,
+    pub y: u16,
 }
 
 #[derive(Debug, Clone, Encode, Decode)]
@@ -149,23 +181,6 @@ impl BgBuffers {
     }
 }
 
-#[derive(Debug, Clone, Encode, Decode)]
-struct BitSet256([u64; 4]);
-
-impl BitSet256 {
-    fn new() -> Self {
-        Self([0; 4])
-    }
-
-    fn get(&self, bit: u8) -> bool {
-        self.0[(bit >> 6) as usize] & (1_u64 << (bit & 0x3F)) != 0
-    }
-
-    fn set(&mut self, bit: u8) {
-        self.0[(bit >> 6) as usize] |= 1_u64 << (bit & 0x3F);
-    }
-}
-
 #[derive(Debug, Clone, Copy, Encode, Decode)]
 struct SpriteBufferData {
     y_position: u8,
@@ -189,8 +204,10 @@ struct SpriteBuffers {
     pattern_table_high: [u8; SPRITE_BUFFER_LEN],
     buffer_len: u8,
     sprite_0_buffered: bool,
-    // Stores whether there is any sprite at the given X coordinate
-    sprite_x_bit_set: BitSet256,
+    // The highest-priority opaque sprite pixel at each X coordinate, computed for the whole
+    // scanline at once the first time a sprite pixel is needed after the buffers change
+    line: [SpriteData; SCREEN_WIDTH as usize],
+    line_dirty: bool,
 }
 
 impl SpriteBuffers {
@@ -201,7 +218,49 @@ impl SpriteBuffers {
             pattern_table_high: [0; SPRITE_BUFFER_LEN],
             buffer_len: 0,
             sprite_0_buffered: false,
-            sprite_x_bit_set: BitSet256::new(),
+            line: [SpriteData::TRANSPARENT; SCREEN_WIDTH as usize],
+            line_dirty: false,
+        }
+    }
+
+    fn sprite_pixel(&mut self, pixel: u8) -> SpriteData {
+        if self.line_dirty {
+            self.render_line();
+            self.line_dirty = false;
+        }
+
+        self.line[pixel as usize]
+    }
+
+    fn render_line(&mut self) {
+        self.line = [SpriteData::TRANSPARENT; SCREEN_WIDTH as usize];
+
+        for i in 0..self.buffer_len as usize {
+            let SpriteBufferData { x_position, attributes, .. } = self.sprites[i];
+            let sprite_flip_x = attributes.bit(6);
+
+            for sprite_fine_x in 0..8 {
+                let Some(x) = x_position.checked_add(sprite_fine_x) else { break };
+
+                // Sprites earlier in OAM take priority, even when they are behind the background
+                if self.line[x as usize].color_id != 0 {
+                    continue;
+                }
+
+                let pattern_fine_x = if sprite_flip_x { 7 - sprite_fine_x } else { sprite_fine_x };
+                let color_id = get_color_id(
+                    self.pattern_table_low[i],
+                    self.pattern_table_high[i],
+                    pattern_fine_x,
+                );
+                if color_id != 0 {
+                    self.line[x as usize] = SpriteData {
+                        color_id,
+                        is_sprite_0: i == 0 && self.sprite_0_buffered,
+                        attributes,
+                    };
+                }
+            }
         }
     }
 }
@@ -234,7 +293,6 @@ impl SpriteEvaluationData {
 
     fn to_sprite_buffers(&self) -> SpriteBuffers {
         let mut sprites = [SpriteBufferData::default(); SPRITE_BUFFER_LEN];
-        let mut sprite_x_bit_set = BitSet256::new();
 
         for (i, chunk) in
             self.secondary_oam.chunks_exact(4).take(self.sprites_found as usize).enumerate()
@@ -249,30 +307,29 @@ impl SpriteEvaluationData {
                 attributes: attributes_byte,
                 tile_index,
             };
-
-            for x in x_position..=x_position.saturating_add(7) {
-                sprite_x_bit_set.set(x);
-            }
         }
 
         SpriteBuffers {
             sprites,
-            pattern_table_low: [0; SPRITE_BUFFER_LEN],
-            pattern_table_high: [0; SPRITE_BUFFER_LEN],
             buffer_len: self.sprites_found,
             sprite_0_buffered: self.sprite_0_found,
-            sprite_x_bit_set,
+            line_dirty: true,
+            ..SpriteBuffers::new()
         }
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Encode, Decode)]
 struct SpriteData {
     color_id: u8,
     is_sprite_0: bool,
     attributes: u8,
 }
 
+impl SpriteData {
+    const TRANSPARENT: Self = Self { color_id: 0, is_sprite_0: false, attributes: 0x00 };
+}
+
 #[derive(Debug, Clone, Encode, Decode)]
 pub struct PpuState {
     timing_mode: TimingMode,
@@ -284,8 +341,16 @@ pub struct PpuState {
     scanline: u16,
     dot: u16,
     odd_frame: bool,
+    // Advances every frame; see `rotate_dropped_sprites`
+    sprite_rotation: u32,
+    // Frames completed since power on, counted at the start of VBlank
+    frame_count: u64,
     rendering_disabled_backdrop_color: Option<u8>,
     pending_sprite_0_hit: bool,
+    // Where sprite 0 hit was set this frame, if it was
+    sprite_0_hit: Option<Sprite0HitEvent>,
+    // Scroll position at the start of each visible scanline, or None if rendering was disabled
+    scroll_trace: Box<[Option<ScrollPosition>; SCREEN_HEIGHT as usize]>,
 }
 
 impl PpuState {
@@ -300,8 +365,12 @@ impl PpuState {
             scanline: timing_mode.pre_render_scanline(),
             dot: 0,
             odd_frame: false,
+            sprite_rotation: 0,
+            frame_count: 0,
             rendering_disabled_backdrop_color: Some(BLACK_NES_COLOR),
             pending_sprite_0_hit: false,
+            sprite_0_hit: None,
+            scroll_trace: Box::new([None; SCREEN_HEIGHT as usize]),
         }
     }
 
@@ -313,6 +382,18 @@ impl PpuState {
         self.timing_mode.vblank_scanlines().contains(&self.scanline)
     }
 
+    pub fn scanline(&self) -> u16 {
+        self.scanline
+    }
+
+    pub fn dot(&self) -> u16 {
+        self.dot
+    }
+
+    pub fn frame_count(&self) -> u64 {
+        self.frame_count
+    }
+
     /// Retrieve a reference the PPU's frame buffer.
     ///
     /// The frame buffer is a 256x240 grid storing 6-bit NES colors. These colors
@@ -321,6 +402,19 @@ impl PpuState {
     pub fn frame_buffer(&self) -> &FrameBuffer {
         &self.frame_buffer
     }
+
+    /// Retrieve the scroll position at the start of each visible scanline, or None for scanlines
+    /// where rendering was disabled. When called between frames, this covers the entire frame that
+    /// was just rendered.
+    pub fn scroll_trace(&self) -> &[Option<ScrollPosition>; SCREEN_HEIGHT as usize] {
+        &self.scroll_trace
+    }
+
+    /// Retrieve the scanline and dot where the sprite 0 hit flag was set during the current frame,
+    /// if it was set.
+    pub fn sprite_0_hit(&self) -> Option<Sprite0HitEvent> {
+        self.sprite_0_hit
+    }
 }
 
 pub fn render_pal_black_border(state: &mut PpuState) {
@@ -337,6 +431,135 @@ pub fn render_pal_black_border(state: &mut PpuState) {
     }
 }
 
+// NES colors used by the scroll overlay; white, light gray, red, black
+const OVERLAY_BORDER_COLOR: u8 = 0x30;
+const OVERLAY_DIVIDER_COLOR: u8 = 0x10;
+const OVERLAY_VIEWPORT_COLOR: u8 = 0x16;
+const OVERLAY_BACKGROUND_COLOR: u8 = BLACK_NES_COLOR;
+// The minimap of the four nametables is drawn at 1/8 scale in the top-right corner, below the
+// rows that are cropped in NTSC
+const MINIMAP_SCALE: u16 = 8;
+const MINIMAP_WIDTH: u16 = NAMETABLES_WIDTH / MINIMAP_SCALE;
+const MINIMAP_HEIGHT: u16 = NAMETABLES_HEIGHT / MINIMAP_SCALE;
+const MINIMAP_LEFT: u16 = SCREEN_WIDTH - MINIMAP_WIDTH - 10;
+const MINIMAP_TOP: u16 = 10;
+
+/// Draw a scroll debugging overlay on top of the frame: scanlines where the scroll position jumps
+/// (i.e. raster splits) are marked with a dashed line, and a minimap of the four nametables
+/// outlines the viewport of each split region.
+pub fn render_scroll_overlay(state: &mut PpuState) {
+    let frame_buffer = &mut state.frame_buffer;
+
+    // Regions of consecutive scanlines that continue scrolling from the previous scanline
+    let mut regions: Vec<(usize, usize, ScrollPosition)> = Vec::new();
+    for (scanline, position) in state.scroll_trace.iter().enumerate() {
+        let Some(position) = *position else { continue };
+
+        let continues = regions.last().is_some_and(|&(start, end, start_position)| {
+            let expected_y = (start_position.y + (scanline - start) as u16) % NAMETABLES_HEIGHT;
+            end == scanline && position.x == start_position.x && position.y == expected_y
+        });
+        match regions.last_mut() {
+            Some((_, end, _)) if continues => *end = scanline + 1,
+            _ => regions.push((scanline, scanline + 1, position)),
+        }
+    }
+
+    // Split markers; skip the first region since the top of the frame is not a split
+    for &(start, _, _) in regions.iter().skip(1) {
+        for x in (0..SCREEN_WIDTH as usize).step_by(4) {
+            frame_buffer[start][x] = OVERLAY_VIEWPORT_COLOR;
+            frame_buffer[start][x + 1] = OVERLAY_VIEWPORT_COLOR;
+        }
+    }
+
+    // Minimap background, border, and nametable dividers
+    let left = usize::from(MINIMAP_LEFT);
+    let top = usize::from(MINIMAP_TOP);
+    let (width, height) = (usize::from(MINIMAP_WIDTH), usize::from(MINIMAP_HEIGHT));
+    for row in top - 1..=top + height {
+        for col in left - 1..=left + width {
+            let is_border =
+                row == top - 1 || row == top + height || col == left - 1 || col == left + width;
+            let is_divider = row == top + height / 2 || col == left + width / 2;
+            frame_buffer[row][col] = if is_border {
+                OVERLAY_BORDER_COLOR
+            } else if is_divider {
+                OVERLAY_DIVIDER_COLOR
+            } else {
+                OVERLAY_BACKGROUND_COLOR
+            };
+        }
+    }
+
+    // Viewport outlines, wrapping around the edges of the nametable space
+    let mut plot = |x: u16, y: u16| {
+        let col = left + usize::from((x % NAMETABLES_WIDTH) / MINIMAP_SCALE);
+        let row = top + usize::from((y % NAMETABLES_HEIGHT) / MINIMAP_SCALE);
+        frame_buffer[row][col] = OVERLAY_VIEWPORT_COLOR;
+    };
+    for &(start, end, position) in &regions {
+        let height = (end - start) as u16;
+        for dy in 0..height {
+            plot(position.x, position.y + dy);
+            plot(position.x + SCREEN_WIDTH - 1, position.y + dy);
+        }
+        for dx in 0..SCREEN_WIDTH {
+            plot(position.x + dx, position.y);
+            plot(position.x + dx, position.y + height - 1);
+        }
+    }
+}
+
+// NES colors used by the input display; white for pressed buttons, dark gray for released buttons
+const INPUT_DISPLAY_PRESSED_COLOR: u8 = 0x30;
+const INPUT_DISPLAY_RELEASED_COLOR: u8 = 0x00;
+const INPUT_DISPLAY_WIDTH: usize = 42;
+const INPUT_DISPLAY_HEIGHT: usize = 13;
+const INPUT_DISPLAY_MARGIN: usize = 10;
+// Keep the display above the bottom rows that are cropped in NTSC
+const INPUT_DISPLAY_TOP: usize = 210;
+
+/// Draw both controllers' button states on top of the frame, P1 in the bottom-left corner and P2 in
+/// the bottom-right corner.
+pub fn render_input_display(
+    state: &mut PpuState,
+    p1_joypad_state: JoypadState,
+    p2_joypad_state: JoypadState,
+) {
+    let p2_left = SCREEN_WIDTH as usize - INPUT_DISPLAY_WIDTH - INPUT_DISPLAY_MARGIN;
+    for (left, joypad_state) in
+        [(INPUT_DISPLAY_MARGIN, p1_joypad_state), (p2_left, p2_joypad_state)]
+    {
+        render_controller(&mut state.frame_buffer, left, INPUT_DISPLAY_TOP, joypad_state);
+    }
+}
+
+fn render_controller(frame_buffer: &mut FrameBuffer, left: usize, top: usize, joypad: JoypadState) {
+    for row in &mut frame_buffer[top..top + INPUT_DISPLAY_HEIGHT] {
+        row[left..left + INPUT_DISPLAY_WIDTH].fill(BLACK_NES_COLOR);
+    }
+
+    // (pressed, x, y, width, height) relative to the top-left corner of the controller
+    let buttons = [
+        (joypad.up, 5, 2, 3, 3),
+        (joypad.left, 2, 5, 3, 3),
+        (joypad.right, 8, 5, 3, 3),
+        (joypad.down, 5, 8, 3, 3),
+        (joypad.select, 14, 6, 5, 2),
+        (joypad.start, 21, 6, 5, 2),
+        (joypad.b, 29, 5, 4, 4),
+        (joypad.a, 35, 5, 4, 4),
+    ];
+    for (pressed, x, y, width, height) in buttons {
+        let color =
+            if pressed { INPUT_DISPLAY_PRESSED_COLOR } else { INPUT_DISPLAY_RELEASED_COLOR };
+        for row in &mut frame_buffer[top + y..top + y + height] {
+            row[left + x..left + x + width].fill(color);
+        }
+    }
+}
+
 /// Run the PPU for one PPU cycle. Pixels will be written to `PpuState`'s frame buffer as appropriate.
 pub fn tick(state: &mut PpuState, bus: &mut PpuBus<'_>, config: &EmulatorConfig) {
     let rendering_enabled =
@@ -351,14 +574,20 @@ pub fn tick(state: &mut PpuState, bus: &mut PpuBus<'_>, config: &EmulatorConfig)
         ppu_registers.set_vblank_flag(false);
         ppu_registers.set_sprite_0_hit(false);
         ppu_registers.set_sprite_overflow(false);
+        state.sprite_0_hit = None;
     } else if state.scanline == FIRST_VBLANK_SCANLINE && state.dot == VBLANK_FLAG_SET_DOT {
         bus.get_ppu_registers_mut().set_vblank_flag(true);
     }
 
+    if VISIBLE_SCANLINES.contains(&state.scanline) && state.dot == 1 {
+        state.scroll_trace[state.scanline as usize] =
+            rendering_enabled.then(|| state.registers.scroll_position());
+    }
+
     let color_mask = get_color_mask(bus.get_ppu_registers());
     if rendering_enabled {
         state.rendering_disabled_backdrop_color = None;
-        process_scanline(state, bus, config.remove_sprite_limit);
+        process_scanline(state, bus, config);
     } else {
         bus.get_ppu_registers_mut().set_oam_open_bus(None);
 
@@ -397,6 +626,10 @@ pub fn tick(state: &mut PpuState, bus: &mut PpuBus<'_>, config: &EmulatorConfig)
         state.scanline += 1;
         state.dot = 0;
 
+        if state.scanline == FIRST_VBLANK_SCANLINE {
+            state.frame_count += 1;
+        }
+
         if state.scanline == state.timing_mode.pre_render_scanline() + 1 {
             state.scanline = 0;
 
@@ -405,6 +638,7 @@ pub fn tick(state: &mut PpuState, bus: &mut PpuBus<'_>, config: &EmulatorConfig)
                 state.dot = 1;
             }
             state.odd_frame = !state.odd_frame;
+            state.sprite_rotation = state.sprite_rotation.wrapping_add(1);
         }
     }
 }
@@ -416,27 +650,35 @@ fn get_color_mask(registers: &PpuRegisters) -> u8 {
 
 /// Reset the PPU, as if the console's reset button was pressed.
 ///
-/// This resets all PPU state except for the internal v register, and also clears most of the
-/// memory-mapped PPU regsiters.
+/// This resets all PPU state except for the internal v register and the frame count, and also
+/// clears most of the memory-mapped PPU regsiters.
 pub fn reset(state: &mut PpuState, bus: &mut PpuBus<'_>) {
     let vram_address = state.registers.vram_address;
+    let frame_count = state.frame_count;
     *state = PpuState::new(state.timing_mode);
     state.registers.vram_address = vram_address;
+    state.frame_count = frame_count;
 
     bus.reset();
 }
 
-fn process_scanline(state: &mut PpuState, bus: &mut PpuBus<'_>, remove_sprite_limit: bool) {
+fn process_scanline(state: &mut PpuState, bus: &mut PpuBus<'_>, config: &EmulatorConfig) {
+    let remove_sprite_limit = config.remove_sprite_limit;
     let scanline = state.scanline;
     let dot = state.dot;
     let timing_mode = state.timing_mode;
 
-    log::trace!("Rendering at scanline {} dot {}", scanline, dot);
+    log::trace!(target: PPU_TARGET, "Rendering at scanline {scanline} dot {dot}");
 
     if state.pending_sprite_0_hit {
         // If sprite 0 hit triggered on the last cycle, set the flag in PPUSTATUS
         state.pending_sprite_0_hit = false;
         bus.get_ppu_registers_mut().set_sprite_0_hit(true);
+
+        state.sprite_0_hit = Some(Sprite0HitEvent { scanline, dot });
+        if config.log_sprite_0_hits {
+            log::info!(target: PPU_TARGET, "Sprite 0 hit at scanline {scanline} dot {dot}");
+        }
     }
 
     match (timing_mode, scanline) {
@@ -487,6 +729,8 @@ fn process_scanline(state: &mut PpuState, bus: &mut PpuBus<'_>, remove_sprite_li
 
                         if remove_sprite_limit && dot == 255 {
                             finish_sprite_evaluation_no_limit(state, bus);
+                        } else if config.reduce_sprite_flicker && dot == 255 {
+                            rotate_dropped_sprites(state, bus);
                         }
                     }
 
@@ -563,7 +807,7 @@ fn process_register_updates(state: &mut PpuState, bus: &mut PpuBus<'_>, renderin
     match bus.get_ppu_registers_mut().take_last_accessed_register() {
         Some(PpuTrackedRegister::PPUCTRL) => {
             let ppu_ctrl = bus.get_ppu_registers().ppu_ctrl();
-            log::trace!(
+            log::trace!(target: PPU_TARGET,
                 "PPU: {ppu_ctrl:02X} written to PPUCTRL on scanline {}, dot {}",
                 state.scanline,
                 state.dot
@@ -575,7 +819,7 @@ fn process_register_updates(state: &mut PpuState, bus: &mut PpuBus<'_>, renderin
         }
         Some(PpuTrackedRegister::PPUSCROLL) => {
             let value = bus.get_ppu_registers().get_ppu_open_bus_value();
-            log::trace!(
+            log::trace!(target: PPU_TARGET,
                 "PPU: {value:02X} written to PPUSCROLL, write_toggle={:?} on scanline {}, dot {}",
                 bus.get_ppu_registers().get_write_toggle(),
                 state.scanline,
@@ -600,7 +844,7 @@ fn process_register_updates(state: &mut PpuState, bus: &mut PpuBus<'_>, renderin
         }
         Some(PpuTrackedRegister::PPUADDR) => {
             let value = bus.get_ppu_registers().get_ppu_open_bus_value();
-            log::trace!(
+            log::trace!(target: PPU_TARGET,
                 "PPU: {value:02X} written to PPUADDR, write_toggle={:?} on scanline {}, dot {}",
                 bus.get_ppu_registers().get_write_toggle(),
                 state.scanline,
@@ -628,7 +872,7 @@ fn process_register_updates(state: &mut PpuState, bus: &mut PpuBus<'_>, renderin
                     || state.scanline == state.timing_mode.pre_render_scanline())
             {
                 // Accessing PPUDATA during rendering causes a coarse X increment + Y increment
-                log::trace!(
+                log::trace!(target: PPU_TARGET,
                     "PPU: PPUDATA was accessed during rendering (scanline {} / dot {}), incrementing coarse X and Y in v register",
                     state.scanline,
                     state.dot
@@ -637,7 +881,7 @@ fn process_register_updates(state: &mut PpuState, bus: &mut PpuBus<'_>, renderin
                 increment_horizontal_pos(&mut state.registers);
                 increment_vertical_pos(&mut state.registers);
             } else {
-                log::trace!(
+                log::trace!(target: PPU_TARGET,
                     "PPU: PPUDATA was accessed on scanline {} / dot {}, incrementing internal v register by {}",
                     state.scanline,
                     state.dot,
@@ -734,14 +978,15 @@ fn render_pixel(state: &mut PpuState, bus: &PpuBus<'_>) {
     state.bg_buffers.shift();
 
     // Find the first overlapping sprite by OAM index, if any; use transparent if none found
-    let sprite = (state.scanline != 0
+    let sprite = if state.scanline != 0
         && state.scanline != state.timing_mode.pre_render_scanline()
         && sprites_enabled
         && (pixel >= 8 || left_edge_sprites_enabled)
-        && state.sprite_buffers.sprite_x_bit_set.get(pixel))
-    .then(|| find_first_overlapping_sprite(pixel, &state.sprite_buffers))
-    .flatten()
-    .unwrap_or(SpriteData { color_id: 0, is_sprite_0: false, attributes: 0x00 });
+    {
+        state.sprite_buffers.sprite_pixel(pixel)
+    } else {
+        SpriteData::TRANSPARENT
+    };
 
     if sprite.is_sprite_0 && bg_color_id != 0 && sprite.color_id != 0 && pixel < 255 {
         // Set sprite 0 hit when a non-transparent sprite pixel overlaps a non-transparent BG pixel
@@ -788,9 +1033,8 @@ fn fetch_bg_tile_data(state: &mut PpuState, bus: &mut PpuBus<'_>) {
             state.bg_buffers.next_nametable_byte = fetch_nametable_byte(&state.registers, bus);
         }
         1 => {
-            let next_palette_index = u16::from(fetch_palette_index(&state.registers, bus));
-            state.bg_buffers.next_palette_indices =
-                (0..8).map(|i| next_palette_index << (2 * i)).reduce(|a, b| a | b).unwrap();
+            let next_palette_index = fetch_palette_index(&state.registers, bus);
+            state.bg_buffers.next_palette_indices = repeat_palette_index(next_palette_index);
         }
         2 => {
             state.bg_buffers.next_pattern_table_low = fetch_bg_pattern_table_byte(
@@ -865,6 +1109,7 @@ fn fetch_sprite_tile_data(
                     bus,
                 );
                 sprite_buffers.pattern_table_low[sprite_index as usize] = pattern_table_low;
+                sprite_buffers.line_dirty = true;
             } else {
                 // Spurious read
                 fetch_sprite_pattern_table_byte(
@@ -892,6 +1137,7 @@ fn fetch_sprite_tile_data(
                     bus,
                 );
                 sprite_buffers.pattern_table_high[sprite_index as usize] = pattern_table_high;
+                sprite_buffers.line_dirty = true;
             } else {
                 // Spurious read
                 fetch_sprite_pattern_table_byte(
@@ -1056,30 +1302,70 @@ fn finish_sprite_evaluation_no_limit(state: &mut PpuState, bus: &mut PpuBus<'_>)
     }
 }
 
-fn find_first_overlapping_sprite(pixel: u8, sprites: &SpriteBuffers) -> Option<SpriteData> {
-    (0..sprites.buffer_len as usize).find_map(|i| {
-        let SpriteBufferData { x_position: x_pos, attributes, .. } = sprites.sprites[i];
+// With the sprite limit in place, the PPU always drops the highest-indexed sprites on a busy
+// scanline, which can leave them permanently invisible in games that do not cycle OAM themselves.
+// Instead, pick which sprites to drop based on a rotation that advances every frame so that dropped
+// sprites flicker. The sprite overflow flag has already been set by sprite evaluation.
+fn rotate_dropped_sprites(state: &mut PpuState, bus: &PpuBus<'_>) {
+    let sprite_height = if bus.get_ppu_registers().double_height_sprites() { 16 } else { 8 };
+    let oam = bus.get_oam();
 
-        if !(x_pos..=x_pos.saturating_add(7)).contains(&pixel) {
-            return None;
-        }
+    let Some(kept_sprites) =
+        select_rotated_sprites(oam, state.scanline as u8, sprite_height, state.sprite_rotation)
+    else {
+        return;
+    };
 
-        let sprite_flip_x = attributes.bit(6);
+    let evaluation_data = &mut state.sprite_evaluation_data;
+    let mut sprites_found = 0;
+    for oam_index in (0..64).filter(|&oam_index| kept_sprites[oam_index]) {
+        let slot = sprites_found << 2;
+        evaluation_data.secondary_oam[slot..slot + 4]
+            .copy_from_slice(&oam[oam_index << 2..(oam_index << 2) + 4]);
+        sprites_found += 1;
+    }
+    evaluation_data.sprites_found = sprites_found as u8;
+    evaluation_data.sprite_0_found = kept_sprites[0];
+}
 
-        // Determine sprite pixel color ID
-        let sprite_fine_x = if sprite_flip_x { 7 - (pixel - x_pos) } else { pixel - x_pos };
-        let sprite_color_id = get_color_id(
-            sprites.pattern_table_low[i],
-            sprites.pattern_table_high[i],
-            sprite_fine_x,
-        );
+// Returns which OAM indices to keep on the given scanline, or None if the sprite limit is not hit.
+// Sprite 0 is always kept because games depend on it for sprite 0 hit timing, and the kept sprites
+// are returned in OAM order so that sprite priority does not change.
+fn select_rotated_sprites(
+    oam: &[u8; 256],
+    scanline: u8,
+    sprite_height: u8,
+    rotation: u32,
+) -> Option<[bool; 64]> {
+    let in_range = |oam_index: usize| {
+        let y_position = oam[oam_index << 2];
+        (y_position..y_position.saturating_add(sprite_height)).contains(&scanline)
+    };
 
-        (sprite_color_id != 0).then_some(SpriteData {
-            color_id: sprite_color_id,
-            attributes,
-            is_sprite_0: i == 0 && sprites.sprite_0_buffered,
-        })
-    })
+    let mut candidates = [0; 64];
+    let mut num_candidates = 0;
+    for oam_index in (1..64).filter(|&oam_index| in_range(oam_index)) {
+        candidates[num_candidates] = oam_index;
+        num_candidates += 1;
+    }
+
+    let sprite_0_in_range = in_range(0);
+    let limit = usize::from(SPRITE_PER_SCANLINE_LIMIT) - usize::from(sprite_0_in_range);
+    if num_candidates <= limit {
+        return None;
+    }
+
+    let mut kept_sprites = [false; 64];
+    kept_sprites[0] = sprite_0_in_range;
+
+    // Advance by a full set of sprites each frame so that, like games that cycle OAM themselves,
+    // up to twice the limit can be shown on alternating frames
+    let start = (rotation as usize * limit) % num_candidates;
+    for i in 0..limit {
+        kept_sprites[candidates[(start + i) % num_candidates]] = true;
+    }
+
+    Some(kept_sprites)
 }
 
 fn fetch_nametable_byte(registers: &InternalRegisters, bus: &mut PpuBus<'_>) -> u8 {
@@ -1180,9 +1466,18 @@ fn get_color_id(pattern_table_low: u8, pattern_table_high: u8, fine_x: u8) -> u8
     ((pattern_table_low & mask) >> shift) | (((pattern_table_high & mask) >> shift) << 1)
 }
 
+// Repeat a 2-bit palette index once for each of a tile's 8 pixels. This runs on every background
+// tile fetch, and multiplying by 0x5555 (0b01 repeated 8 times) avoids building the value with an
+// iterator each time
+fn repeat_palette_index(palette_index: u8) -> u16 {
+    u16::from(palette_index & 0x03) * 0x5555
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::bus::{Bus, RamInit, cartridge};
+    use crate::rng::CoreRng;
 
     #[test]
     fn color_id() {
@@ -1198,4 +1493,140 @@ mod tests {
 
         assert_eq!(3, get_color_id(0x01, 0x01, 7));
     }
+
+    #[test]
+    fn repeated_palette_index() {
+        for palette_index in 0..4 {
+            let expected =
+                (0..8).map(|i| u16::from(palette_index) << (2 * i)).reduce(|a, b| a | b).unwrap();
+            assert_eq!(expected, repeat_palette_index(palette_index));
+        }
+
+        assert_eq!(0x0000, repeat_palette_index(0));
+        assert_eq!(0xFFFF, repeat_palette_index(3));
+    }
+
+    #[test]
+    fn rotated_sprites_alternate_and_keep_sprite_0() {
+        // Sprite 0 plus 10 more sprites on scanline 20, and one sprite that is out of range
+        let mut oam = [0xFF; 256];
+        for oam_index in 0..11 {
+            oam[oam_index << 2] = 15;
+        }
+        oam[11 << 2] = 100;
+
+        let kept_indices = |rotation| {
+            let kept_sprites = select_rotated_sprites(&oam, 20, 8, rotation).unwrap();
+            (0..64).filter(|&i| kept_sprites[i]).collect::<Vec<_>>()
+        };
+
+        assert_eq!(kept_indices(0), vec![0, 1, 2, 3, 4, 5, 6, 7]);
+        assert_eq!(kept_indices(1), vec![0, 1, 2, 3, 4, 8, 9, 10]);
+
+        // No sprites need to be dropped on a scanline with only sprite 0
+        assert_eq!(select_rotated_sprites(&oam, 105, 8, 0), None);
+    }
+
+    #[test]
+    fn frame_count_advances_at_vblank_and_survives_reset() {
+        let mapper = cartridge::new_mmc1(vec![0; 32768]);
+        let mut bus = Bus::from_cartridge(mapper, RamInit::default(), &mut CoreRng::new(0));
+        let mut state = PpuState::new(TimingMode::Ntsc);
+        let config = EmulatorConfig::default();
+
+        // Power on starts at the beginning of the pre-render scanline
+        let dots_until_vblank = 242 * u32::from(DOTS_PER_SCANLINE);
+        for _ in 1..dots_until_vblank {
+            tick(&mut state, &mut bus.ppu(), &config);
+        }
+        assert_eq!(state.frame_count(), 0);
+
+        tick(&mut state, &mut bus.ppu(), &config);
+        assert_eq!(state.frame_count(), 1);
+        assert!(state.in_vblank());
+
+        reset(&mut state, &mut bus.ppu());
+        assert_eq!(state.frame_count(), 1);
+    }
+
+    #[test]
+    fn input_display_lights_pressed_buttons() {
+        let mut state = PpuState::new(TimingMode::Ntsc);
+        let p1 = JoypadState { a: true, ..JoypadState::default() };
+        let p2 = JoypadState { up: true, ..JoypadState::default() };
+        render_input_display(&mut state, p1, p2);
+
+        let p1_left = INPUT_DISPLAY_MARGIN;
+        let p2_left = SCREEN_WIDTH as usize - INPUT_DISPLAY_WIDTH - INPUT_DISPLAY_MARGIN;
+        let a_row = INPUT_DISPLAY_TOP + 5;
+        let up_row = INPUT_DISPLAY_TOP + 2;
+        assert_eq!(state.frame_buffer[a_row][p1_left + 35], INPUT_DISPLAY_PRESSED_COLOR);
+        assert_eq!(state.frame_buffer[up_row][p1_left + 5], INPUT_DISPLAY_RELEASED_COLOR);
+        assert_eq!(state.frame_buffer[a_row][p2_left + 35], INPUT_DISPLAY_RELEASED_COLOR);
+        assert_eq!(state.frame_buffer[up_row][p2_left + 5], INPUT_DISPLAY_PRESSED_COLOR);
+    }
+
+    #[test]
+    fn scroll_position_accounts_for_prefetch() {
+        // Coarse X 2 after the pre-fetch increments means the scanline starts at X=0
+        let registers =
+            InternalRegisters { vram_address: 0x0002, temp_vram_address: 0, fine_x_scroll: 0 };
+        assert_eq!(registers.scroll_position(), ScrollPosition { x: 0, y: 0 });
+
+        // Wraps around from the left edge; fine Y 3, coarse Y 4, bottom nametable
+        let registers = InternalRegisters {
+            vram_address: 0x3000 | 0x0800 | (4 << 5) | 0x0001,
+            temp_vram_address: 0,
+            fine_x_scroll: 5,
+        };
+        assert_eq!(registers.scroll_position(), ScrollPosition { x: 509, y: 275 });
+    }
+
+    #[test]
+    fn sprite_line_priority() {
+        let mut sprite_buffers = SpriteBuffers::new();
+        sprite_buffers.buffer_len = 3;
+        sprite_buffers.sprite_0_buffered = true;
+
+        // Sprite 0 is transparent except for its leftmost pixel
+        sprite_buffers.sprites[0] =
+            SpriteBufferData { y_position: 0, x_position: 10, attributes: 0x00, tile_index: 0 };
+        sprite_buffers.pattern_table_low[0] = 0x80;
+
+        // Sprite 1 is fully opaque, flipped horizontally, and overlaps sprite 0
+        sprite_buffers.sprites[1] =
+            SpriteBufferData { y_position: 0, x_position: 8, attributes: 0x41, tile_index: 0 };
+        sprite_buffers.pattern_table_low[1] = 0xFF;
+        sprite_buffers.pattern_table_high[1] = 0x0F;
+
+        // Sprite 2 is partially off the right edge of the screen
+        sprite_buffers.sprites[2] =
+            SpriteBufferData { y_position: 0, x_position: 252, attributes: 0x02, tile_index: 0 };
+        sprite_buffers.pattern_table_high[2] = 0xFF;
+
+        sprite_buffers.line_dirty = true;
+
+        assert_eq!(SpriteData::TRANSPARENT, sprite_buffers.sprite_pixel(7));
+        assert_eq!(
+            SpriteData { color_id: 3, is_sprite_0: false, attributes: 0x41 },
+            sprite_buffers.sprite_pixel(8)
+        );
+        assert_eq!(
+            SpriteData { color_id: 1, is_sprite_0: true, attributes: 0x00 },
+            sprite_buffers.sprite_pixel(10)
+        );
+        assert_eq!(
+            SpriteData { color_id: 3, is_sprite_0: false, attributes: 0x41 },
+            sprite_buffers.sprite_pixel(11)
+        );
+        assert_eq!(
+            SpriteData { color_id: 1, is_sprite_0: false, attributes: 0x41 },
+            sprite_buffers.sprite_pixel(15)
+        );
+        assert_eq!(SpriteData::TRANSPARENT, sprite_buffers.sprite_pixel(16));
+        assert_eq!(
+            SpriteData { color_id: 2, is_sprite_0: false, attributes: 0x02 },
+            sprite_buffers.sprite_pixel(255)
+        );
+    }
 }
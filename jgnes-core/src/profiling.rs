@@ -0,0 +1,49 @@
+//! Profiling hooks using `tracing` spans, compiled in only when the `tracing` feature is enabled.
+//!
+//! Frames and PPU scanlines are recorded at DEBUG level. CPU and APU ticks are recorded at TRACE
+//! level because there is one span per CPU cycle, which adds significant overhead once a
+//! subscriber is listening.
+
+/// Tracks a span that covers the PPU's current scanline. Scanlines do not line up with any single
+/// function call, so the span is entered and exited manually whenever the scanline changes.
+#[derive(Debug, Default)]
+pub(crate) struct ScanlineSpan {
+    #[cfg(feature = "tracing")]
+    current: Option<(u16, tracing::Span)>,
+}
+
+impl ScanlineSpan {
+    #[inline]
+    #[cfg_attr(not(feature = "tracing"), allow(clippy::unused_self))]
+    pub(crate) fn update(&mut self, scanline: u16) {
+        #[cfg(feature = "tracing")]
+        {
+            if self.current.as_ref().is_some_and(|&(current, _)| current == scanline) {
+                return;
+            }
+
+            self.exit();
+
+            let span = tracing::debug_span!("scanline", scanline);
+            span.with_subscriber(|(id, dispatch)| dispatch.enter(id));
+            self.current = Some((scanline, span));
+        }
+
+        #[cfg(not(feature = "tracing"))]
+        let _ = scanline;
+    }
+
+    #[cfg(feature = "tracing")]
+    fn exit(&mut self) {
+        if let Some((_, span)) = self.current.take() {
+            span.with_subscriber(|(id, dispatch)| dispatch.exit(id));
+        }
+    }
+}
+
+#[cfg(feature = "tracing")]
+impl Drop for ScanlineSpan {
+    fn drop(&mut self) {
+        self.exit();
+    }
+}
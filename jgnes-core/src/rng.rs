@@ -0,0 +1,42 @@
+//! Seedable random number generator shared by all core features that need randomness.
+//!
+//! The generator is part of the emulation state, so save states, rewind snapshots, and replays
+//! that start from the same seed always see the same sequence of random values. The only source
+//! of nondeterminism is choosing a seed when none is configured.
+
+use bincode::{Decode, Encode};
+
+// https://prng.di.unimi.it/splitmix64.c
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub(crate) struct CoreRng {
+    seed: u64,
+    state: u64,
+}
+
+impl CoreRng {
+    pub(crate) fn new(seed: u64) -> Self {
+        Self { seed, state: seed }
+    }
+
+    /// Create a generator using the given seed, or a newly chosen seed if `None`.
+    pub(crate) fn from_seed_or_entropy(seed: Option<u64>) -> Self {
+        Self::new(seed.unwrap_or_else(rand::random))
+    }
+
+    /// The seed this generator was created with.
+    pub(crate) fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    pub(crate) fn next_bool(&mut self) -> bool {
+        self.next_u64() & 0x01 != 0
+    }
+}
@@ -1,15 +1,28 @@
+//! Save state serialization.
+//!
+//! Save states are stored in a small container so that the same file can be moved between
+//! frontends (e.g. from the web frontend to the native GUI): an 8-byte signature, a format
+//! version, and a hash of the ROM that the state was saved from, followed by the bincode-encoded
+//! emulation state.
+
 use crate::api::EmulationState;
 use crate::apu::ApuState;
 use crate::bus::Bus;
 use crate::cpu::CpuState;
 use crate::ppu::PpuState;
+use crate::rng::CoreRng;
+use bincode::Encode;
 use bincode::config::{Fixint, LittleEndian};
+use bincode::enc::EncoderImpl;
+use bincode::enc::write::SizeWriter;
 use bincode::error::{DecodeError, EncodeError};
 use std::io;
-use std::io::{BufReader, BufWriter};
+use std::io::{BufReader, BufWriter, Read, Write};
 use thiserror::Error;
 
+/// An error returned when saving or loading a save state.
 #[derive(Debug, Error)]
+#[non_exhaustive]
 pub enum SaveStateError {
     #[error("error saving state: {source}")]
     Serialization {
@@ -21,16 +34,49 @@ pub enum SaveStateError {
         #[from]
         source: DecodeError,
     },
+    #[error("I/O error reading or writing state: {source}")]
+    Io {
+        #[from]
+        source: io::Error,
+    },
+    #[error("file is not a jgnes save state")]
+    InvalidSignature,
+    #[error(
+        "save state is from an incompatible version of jgnes (format version {version}, expected {SAVE_STATE_VERSION})"
+    )]
+    UnsupportedVersion { version: u32 },
+    #[error("save state was created with a different ROM")]
+    RomMismatch,
+}
+
+const SAVE_STATE_SIGNATURE: [u8; 8] = *b"JGNESST\x1A";
+
+/// Incremented whenever the encoding of emulation state changes in a way that makes older states
+/// unreadable.
+const SAVE_STATE_VERSION: u32 = 8;
+
+/// Compute the 64-bit FNV-1a hash of the given bytes.
+///
+/// Unlike std's default hasher, this is guaranteed to be stable across Rust versions, platforms,
+/// and jgnes builds. Save states use it to identify the ROM image they were created with, and
+/// frontends can use it as a persistent key for ROMs or to compare rendered frames between builds.
+#[must_use]
+pub fn fnv1a_hash(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xCBF2_9CE4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01B3)
+    })
 }
 
 const BINCODE_CONFIG: bincode::config::Configuration<LittleEndian, Fixint> =
     bincode::config::standard().with_little_endian().with_fixed_int_encoding();
 
 pub fn save_state<W>(
+    rom_hash: u64,
     bus: &Bus,
     cpu_state: &CpuState,
     ppu_state: &PpuState,
     apu_state: &ApuState,
+    rng: &CoreRng,
     writer: W,
 ) -> Result<(), SaveStateError>
 where
@@ -38,24 +84,95 @@ where
 {
     let mut writer = BufWriter::new(writer);
 
+    writer.write_all(&SAVE_STATE_SIGNATURE)?;
+    writer.write_all(&SAVE_STATE_VERSION.to_le_bytes())?;
+    writer.write_all(&rom_hash.to_le_bytes())?;
+
     bincode::encode_into_std_write(bus, &mut writer, BINCODE_CONFIG)?;
     bincode::encode_into_std_write(cpu_state, &mut writer, BINCODE_CONFIG)?;
     bincode::encode_into_std_write(ppu_state, &mut writer, BINCODE_CONFIG)?;
     bincode::encode_into_std_write(apu_state, &mut writer, BINCODE_CONFIG)?;
+    bincode::encode_into_std_write(rng, &mut writer, BINCODE_CONFIG)?;
+
+    writer.flush()?;
 
     Ok(())
 }
 
-pub fn load_state<R>(reader: R) -> Result<EmulationState, SaveStateError>
+/// The number of bytes that the given state occupies when encoded, not including the save state
+/// header.
+pub(crate) fn encoded_len(state: &EmulationState) -> Result<usize, EncodeError> {
+    let mut encoder = EncoderImpl::new(SizeWriter::default(), BINCODE_CONFIG);
+
+    state.bus.encode(&mut encoder)?;
+    state.cpu_state.encode(&mut encoder)?;
+    state.ppu_state.encode(&mut encoder)?;
+    state.apu_state.encode(&mut encoder)?;
+    state.rng.encode(&mut encoder)?;
+
+    Ok(encoder.into_writer().bytes_written)
+}
+
+pub fn load_state<R>(reader: R, expected_rom_hash: u64) -> Result<EmulationState, SaveStateError>
 where
     R: io::Read,
 {
     let mut reader = BufReader::new(reader);
 
+    let mut signature = [0; 8];
+    reader.read_exact(&mut signature).map_err(|_| SaveStateError::InvalidSignature)?;
+    if signature != SAVE_STATE_SIGNATURE {
+        return Err(SaveStateError::InvalidSignature);
+    }
+
+    let mut version = [0; 4];
+    reader.read_exact(&mut version)?;
+    let version = u32::from_le_bytes(version);
+    if version != SAVE_STATE_VERSION {
+        return Err(SaveStateError::UnsupportedVersion { version });
+    }
+
+    let mut rom_hash = [0; 8];
+    reader.read_exact(&mut rom_hash)?;
+    if u64::from_le_bytes(rom_hash) != expected_rom_hash {
+        return Err(SaveStateError::RomMismatch);
+    }
+
     let bus = bincode::decode_from_std_read(&mut reader, BINCODE_CONFIG)?;
     let cpu_state = bincode::decode_from_std_read(&mut reader, BINCODE_CONFIG)?;
     let ppu_state = bincode::decode_from_std_read(&mut reader, BINCODE_CONFIG)?;
     let apu_state = bincode::decode_from_std_read(&mut reader, BINCODE_CONFIG)?;
+    let rng = bincode::decode_from_std_read(&mut reader, BINCODE_CONFIG)?;
+
+    Ok(EmulationState { bus, cpu_state, ppu_state, apu_state, rng })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fnv1a_known_values() {
+        assert_eq!(fnv1a_hash(b""), 0xCBF2_9CE4_8422_2325);
+        assert_eq!(fnv1a_hash(b"a"), 0xAF63_DC4C_8601_EC8C);
+    }
+
+    #[test]
+    fn rejects_invalid_headers() {
+        let mut header = Vec::from(SAVE_STATE_SIGNATURE);
+        header.extend(SAVE_STATE_VERSION.to_le_bytes());
+        header.extend(5_u64.to_le_bytes());
+
+        assert!(matches!(
+            load_state(&b"not a state"[..], 5),
+            Err(SaveStateError::InvalidSignature)
+        ));
+        assert!(matches!(load_state(header.as_slice(), 6), Err(SaveStateError::RomMismatch)));
 
-    Ok(EmulationState { bus, cpu_state, ppu_state, apu_state })
+        header[8] = 0xFF;
+        assert!(matches!(
+            load_state(header.as_slice(), 5),
+            Err(SaveStateError::UnsupportedVersion { version: 0xFF })
+        ));
+    }
 }
@@ -1,23 +1,38 @@
-use crate::emuthread::EmuThreadTask;
+use crate::covers::CoverArt;
+use crate::emuthread::{EmuThreadTask, LauncherInput};
+use crate::handoff::HandoffReceiver;
+use crate::instances::InstancePool;
 use crate::romlist::RomMetadata;
-use crate::{emuthread, romlist};
+use crate::{emuthread, instances, romlist};
 use eframe::Frame;
 use egui::panel::TopBottomSide;
 use egui::{
-    Align, Button, CentralPanel, Color32, Context, Grid, Key, KeyboardShortcut, Layout, Modifiers,
-    TextEdit, TopBottomPanel, Ui, Vec2, ViewportCommand, Widget, Window, menu,
+    Align, Align2, Button, CentralPanel, CollapsingHeader, Color32, ComboBox, Context, Grid, Id,
+    Image, Key, KeyboardShortcut, Layout, Modifiers, Response, RichText, ScrollArea, SidePanel,
+    Slider, Stroke, Style, TextEdit, TextureHandle, Theme, TopBottomPanel, Ui, Vec2,
+    ViewportCommand, Visuals, Widget, WidgetInfo, WidgetType, Window, menu,
 };
 use egui_extras::{Column, TableBuilder};
-use jgnes_core::TimingMode;
+use jgnes_core::logging::LogSubsystem;
+use jgnes_core::{CartridgeInfo, RamInit, RamInitPattern, RomLoadError, RomProblem, TimingMode};
 use jgnes_native_driver::{
-    HotkeyConfig, InputCollectResult, InputConfig, InputConfigBase, InputType, JgnesDynamicConfig,
-    JgnesNativeConfig, JgnesSharedConfig, JoystickInput, KeyboardInput, NativeRenderer,
+    AnalogStickConfig, CaptureFormat, CrashReport, DataPaths, DpadRotation, EmulatorCommand,
+    EmulatorStatus, FRAME_TIMING_LOG_DURATION, FramePacing, HiDpiScaling, HotkeyConfig,
+    InputCollectResult, InputConfig, InputConfigBase, InputProfile, InputTransforms, InputType,
+    JgnesDynamicConfig, JgnesNativeConfig, JgnesSharedConfig, JoystickInput, KeyboardInput,
+    LowPowerMode, NativeRenderer, RomLoadRequest, WindowGeometry, WindowGeometryMap,
+    load_shader_preset,
 };
+use jgnes_renderer::colors::Palette;
 use jgnes_renderer::config::{
-    AspectRatio, GpuFilterMode, Overscan, RenderScale, Scanlines, Shader, VSyncMode, WgpuBackend,
+    AspectRatio, ColorBlindFilter, CrtGeometry, GpuFilterMode, Overscan, OverscanPreset,
+    RenderScale, Scanlines, Shader, VSyncMode, WgpuBackend,
 };
+use jgnes_renderer::{GpuAdapterInfo, GpuAdapterSelector};
+use log::{Level, LevelFilter};
 use rfd::FileDialog;
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
 use std::fs;
 use std::ops::RangeInclusive;
 use std::path::{Path, PathBuf};
@@ -47,6 +62,10 @@ fn default_blur_radius() -> u32 {
     16
 }
 
+fn default_scanline_intensity() -> f64 {
+    1.0
+}
+
 fn default_audio_buffer_size() -> u32 {
     800
 }
@@ -63,10 +82,143 @@ fn default_rewind_buffer_len_secs() -> u64 {
     10
 }
 
+fn default_rewind_interval_frames() -> u8 {
+    3
+}
+
+fn default_rewind_memory_limit_mb() -> u32 {
+    512
+}
+
+fn default_clip_len_secs() -> u64 {
+    30
+}
+
+fn default_capture_len_secs() -> u64 {
+    5
+}
+
+fn default_remote_control_port() -> u16 {
+    7787
+}
+
+fn default_livesplit_port() -> u16 {
+    16834
+}
+
+fn default_ui_scale() -> f32 {
+    1.0
+}
+
 fn true_fn() -> bool {
     true
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+enum UiTheme {
+    // Follow the OS dark/light mode setting
+    System,
+    #[default]
+    Dark,
+    Light,
+    HighContrast,
+}
+
+impl UiTheme {
+    fn visuals(self, system_theme: Option<Theme>) -> Visuals {
+        match self {
+            Self::System => match system_theme {
+                Some(Theme::Light) => Visuals::light(),
+                Some(Theme::Dark) | None => Visuals::dark(),
+            },
+            Self::Dark => Visuals::dark(),
+            Self::Light => Visuals::light(),
+            Self::HighContrast => high_contrast_visuals(),
+        }
+    }
+}
+
+// Use the accent color for selections, links, and the outlines of hovered and clicked widgets
+fn apply_accent_color(visuals: &mut Visuals, [r, g, b]: [u8; 3]) {
+    let accent = Color32::from_rgb(r, g, b);
+    // Text on top of the accent color needs to contrast with it
+    let luminance = 0.299 * f32::from(r) + 0.587 * f32::from(g) + 0.114 * f32::from(b);
+    let selected_text = if luminance > 150.0 { Color32::BLACK } else { Color32::WHITE };
+
+    visuals.selection.bg_fill = accent;
+    visuals.selection.stroke.color = selected_text;
+    visuals.hyperlink_color = accent;
+    visuals.widgets.hovered.bg_stroke.color = accent;
+    visuals.widgets.active.bg_stroke.color = accent;
+}
+
+// egui's default selection color
+const DEFAULT_ACCENT_COLOR: [u8; 3] = [0, 92, 128];
+
+fn high_contrast_visuals() -> Visuals {
+    let mut visuals = Visuals::dark();
+
+    visuals.override_text_color = Some(Color32::WHITE);
+    visuals.panel_fill = Color32::BLACK;
+    visuals.window_fill = Color32::BLACK;
+    visuals.extreme_bg_color = Color32::BLACK;
+    visuals.faint_bg_color = Color32::from_gray(30);
+    visuals.window_stroke = Stroke::new(2.0, Color32::WHITE);
+    visuals.hyperlink_color = Color32::from_rgb(0x00, 0xFF, 0xFF);
+    visuals.selection.bg_fill = Color32::from_rgb(0x00, 0x40, 0xC0);
+    visuals.selection.stroke = Stroke::new(2.0, Color32::YELLOW);
+
+    for widget_visuals in [
+        &mut visuals.widgets.noninteractive,
+        &mut visuals.widgets.inactive,
+        &mut visuals.widgets.hovered,
+        &mut visuals.widgets.active,
+        &mut visuals.widgets.open,
+    ] {
+        widget_visuals.fg_stroke = Stroke::new(2.0, Color32::WHITE);
+    }
+    visuals.widgets.noninteractive.bg_stroke = Stroke::new(1.0, Color32::WHITE);
+    visuals.widgets.inactive.bg_fill = Color32::BLACK;
+    visuals.widgets.inactive.weak_bg_fill = Color32::BLACK;
+    visuals.widgets.inactive.bg_stroke = Stroke::new(2.0, Color32::WHITE);
+    visuals.widgets.hovered.bg_stroke = Stroke::new(3.0, Color32::YELLOW);
+    visuals.widgets.active.bg_stroke = Stroke::new(3.0, Color32::YELLOW);
+
+    visuals
+}
+
+/// GUI appearance settings, used to detect when the egui style needs to be rebuilt.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Appearance {
+    ui_scale: f32,
+    ui_theme: UiTheme,
+    accent_color: Option<[u8; 3]>,
+    // Only used by the system theme, but tracked so that OS theme changes are applied
+    system_theme: Option<Theme>,
+    large_hit_targets: bool,
+}
+
+impl Appearance {
+    fn apply(self, ctx: &Context) {
+        let mut visuals = self.ui_theme.visuals(self.system_theme);
+        if let Some(accent_color) = self.accent_color {
+            apply_accent_color(&mut visuals, accent_color);
+        }
+
+        let mut style = Style { visuals, ..Style::default() };
+        if self.large_hit_targets {
+            style.spacing.interact_size = Vec2::new(56.0, 32.0);
+            style.spacing.button_padding = Vec2::new(10.0, 6.0);
+            style.spacing.item_spacing = Vec2::new(10.0, 8.0);
+            style.spacing.icon_width = 24.0;
+            style.spacing.icon_width_inner = 14.0;
+        }
+
+        ctx.set_style(style);
+        ctx.set_zoom_factor(self.ui_scale);
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
 enum ShaderType {
     None,
@@ -81,10 +233,20 @@ struct AppConfig {
     window_width: u32,
     #[serde(default = "default_window_height")]
     window_height: u32,
+    // Keyed by display configuration
+    #[serde(default)]
+    emulator_window_geometry: WindowGeometryMap,
+    // Keyed by the size of the monitor that the GUI window was on
+    #[serde(default)]
+    launcher_window_geometry: WindowGeometryMap,
     #[serde(default)]
     renderer: NativeRenderer,
     #[serde(default)]
+    hidpi_scaling: HiDpiScaling,
+    #[serde(default)]
     wgpu_backend: WgpuBackend,
+    #[serde(default)]
+    gpu_adapter: Option<GpuAdapterSelector>,
     #[serde(default = "default_gpu_filter_mode")]
     gpu_filter_mode: GpuFilterMode,
     #[serde(default)]
@@ -95,18 +257,62 @@ struct AppConfig {
     blur_stdev: f64,
     #[serde(default = "default_blur_radius")]
     blur_radius: u32,
+    // Overrides the shader type if set
+    #[serde(default)]
+    shader_preset_path: Option<String>,
     #[serde(default)]
     scanlines: Scanlines,
+    #[serde(default = "default_scanline_intensity")]
+    scanline_intensity: f64,
+    #[serde(default)]
+    crt_geometry: CrtGeometry,
+    #[serde(default)]
+    color_blind_filter: ColorBlindFilter,
+    #[serde(default)]
+    frame_blend_strength: f64,
+    #[serde(default)]
+    palette_path: Option<String>,
     #[serde(default)]
     aspect_ratio: AspectRatio,
     #[serde(default)]
+    // Default for games without a per-game overscan
     overscan: Overscan,
+    // Last-used overscan for each game, keyed by ROM hash
+    #[serde(default)]
+    per_game_overscan: BTreeMap<String, Overscan>,
     #[serde(default)]
     forced_integer_height_scaling: bool,
     #[serde(default)]
     forced_timing_mode: Option<TimingMode>,
+    #[serde(default = "true_fn")]
+    warn_on_timing_mode_mismatch: bool,
+    #[serde(default)]
+    ram_init_pattern: RamInitPattern,
+    #[serde(default)]
+    ram_init_fixed_seed: bool,
+    #[serde(default)]
+    ram_init_seed: u64,
+    // Default for games without a per-game sprite limit setting
     #[serde(default)]
     remove_sprite_limit: bool,
+    // Last-used sprite limit setting for each game, keyed by ROM hash; some games break without
+    // the sprite limit
+    #[serde(default)]
+    per_game_remove_sprite_limit: BTreeMap<String, bool>,
+    #[serde(default)]
+    reduce_sprite_flicker: bool,
+    #[serde(default)]
+    remote_control_enabled: bool,
+    #[serde(default = "default_remote_control_port")]
+    remote_control_port: u16,
+    #[serde(default)]
+    watch_rom_file: bool,
+    #[serde(default)]
+    speedrun_timer_auto_start: bool,
+    #[serde(default)]
+    livesplit_enabled: bool,
+    #[serde(default = "default_livesplit_port")]
+    livesplit_port: u16,
     #[serde(default)]
     pal_black_border: bool,
     #[serde(default = "true_fn")]
@@ -120,21 +326,106 @@ struct AppConfig {
     #[serde(default)]
     silence_ultrasonic_triangle_output: bool,
     #[serde(default)]
+    linear_apu_mixing: bool,
+    #[serde(default)]
+    ramp_triangle_silencing: bool,
+    #[serde(default)]
+    dmc_declick_filter: bool,
+    #[serde(default)]
+    log_sprite_0_hits: bool,
+    #[serde(default)]
     launch_fullscreen: bool,
     #[serde(default)]
+    always_on_top: bool,
+    #[serde(default)]
+    borderless: bool,
+    #[serde(default)]
+    mirror_window: bool,
+    // Empty to let SDL choose
+    #[serde(default)]
+    sdl_video_driver: String,
+    #[serde(default)]
     vsync_mode: VSyncMode,
+    #[serde(default)]
+    frame_pacing: FramePacing,
+    #[serde(default)]
+    low_power_mode: LowPowerMode,
     #[serde(default = "default_ff_multiplier")]
     fast_forward_multiplier: u8,
     #[serde(default = "default_rewind_buffer_len_secs")]
     rewind_buffer_len_secs: u64,
+    #[serde(default = "default_rewind_interval_frames")]
+    rewind_interval_frames: u8,
+    #[serde(default = "default_rewind_memory_limit_mb")]
+    rewind_memory_limit_mb: u32,
+    #[serde(default = "default_clip_len_secs")]
+    clip_len_secs: u64,
+    #[serde(default = "default_capture_len_secs")]
+    capture_len_secs: u64,
+    #[serde(default)]
+    capture_format: CaptureFormat,
+    #[serde(default)]
+    confirm_quit: bool,
+    #[serde(default)]
+    confirm_overwrite_state: bool,
+    #[serde(default)]
+    confirm_hard_reset: bool,
     #[serde(default)]
     rom_search_dir: Option<String>,
     #[serde(default)]
+    start_in_big_picture: bool,
+    #[serde(default)]
+    last_rom_path: Option<String>,
+    #[serde(default)]
+    show_cover_art: bool,
+    #[serde(default)]
+    download_cover_art: bool,
+    #[serde(default = "default_ui_scale")]
+    ui_scale: f32,
+    #[serde(default)]
+    ui_theme: UiTheme,
+    #[serde(default)]
+    accent_color: Option<[u8; 3]>,
+    #[serde(default)]
+    large_hit_targets: bool,
+    #[serde(default)]
+    pause_while_settings_open: bool,
+    #[serde(default)]
     input: InputConfig,
+    #[serde(default)]
+    input_profiles: Vec<InputProfile>,
 }
 
 impl AppConfig {
-    fn to_jgnes_dynamic_config(&self) -> JgnesDynamicConfig {
+    fn appearance(&self) -> Appearance {
+        Appearance {
+            ui_scale: self.ui_scale,
+            ui_theme: self.ui_theme,
+            accent_color: self.accent_color,
+            system_theme: None,
+            large_hit_targets: self.large_hit_targets,
+        }
+    }
+
+    // Per-game values for the given ROM, falling back to the defaults for settings that the game
+    // has no entry for
+    fn game_settings(&self, rom_hash: String) -> GameSettings {
+        GameSettings {
+            overscan: self.per_game_overscan.get(&rom_hash).copied().unwrap_or(self.overscan),
+            remove_sprite_limit: self
+                .per_game_remove_sprite_limit
+                .get(&rom_hash)
+                .copied()
+                .unwrap_or(self.remove_sprite_limit),
+            rom_hash,
+        }
+    }
+
+    fn to_jgnes_dynamic_config(
+        &self,
+        palette: &Palette,
+        game_settings: Option<&GameSettings>,
+    ) -> JgnesDynamicConfig {
         let shader = match self.shader_type {
             ShaderType::None => Shader::None,
             ShaderType::Prescale => Shader::Prescale(self.render_scale),
@@ -148,20 +439,51 @@ impl AppConfig {
         JgnesDynamicConfig {
             gpu_filter_mode: self.gpu_filter_mode,
             shader,
+            // Read on every config update so that edits to the preset file apply without reloading
+            // it through the GUI
+            shader_preset: self.shader_preset_path.as_ref().and_then(|path| {
+                load_shader_preset(path)
+                    .map_err(|err| {
+                        log::error!("Error loading shader preset from '{path}', ignoring: {err}");
+                    })
+                    .ok()
+            }),
             scanlines: self.scanlines,
+            scanline_intensity: self.scanline_intensity.clamp(0.0, 1.0),
+            crt_geometry: self.crt_geometry.clamped(),
+            color_blind_filter: self.color_blind_filter,
+            frame_blend_strength: self.frame_blend_strength.clamp(0.0, 1.0),
+            palette: palette.clone(),
             aspect_ratio: self.aspect_ratio,
-            overscan: self.overscan,
+            overscan: game_settings.map_or(self.overscan, |game_settings| game_settings.overscan),
             forced_integer_height_scaling: self.forced_integer_height_scaling,
             vsync_mode: self.vsync_mode,
-            remove_sprite_limit: self.remove_sprite_limit,
+            frame_pacing: self.frame_pacing,
+            low_power_mode: self.low_power_mode,
+            remove_sprite_limit: game_settings.map_or(self.remove_sprite_limit, |game_settings| {
+                game_settings.remove_sprite_limit
+            }),
+            reduce_sprite_flicker: self.reduce_sprite_flicker,
             pal_black_border: self.pal_black_border,
             sync_to_audio: self.sync_to_audio,
             internal_audio_buffer_size: self.internal_audio_buffer_size,
             audio_sync_threshold: self.audio_sync_threshold,
             audio_refresh_rate_adjustment: self.audio_refresh_rate_adjustment,
             silence_ultrasonic_triangle_output: self.silence_ultrasonic_triangle_output,
+            linear_apu_mixing: self.linear_apu_mixing,
+            ramp_triangle_silencing: self.ramp_triangle_silencing,
+            dmc_declick_filter: self.dmc_declick_filter,
+            log_sprite_0_hits: self.log_sprite_0_hits,
             fast_forward_multiplier: self.fast_forward_multiplier,
             rewind_buffer_len: Duration::from_secs(self.rewind_buffer_len_secs),
+            rewind_interval_frames: self.rewind_interval_frames,
+            rewind_memory_limit_mb: self.rewind_memory_limit_mb,
+            clip_len: Duration::from_secs(self.clip_len_secs),
+            capture_len: Duration::from_secs(self.capture_len_secs),
+            capture_format: self.capture_format,
+            confirm_quit: self.confirm_quit,
+            confirm_overwrite_state: self.confirm_overwrite_state,
+            confirm_hard_reset: self.confirm_hard_reset,
             input_config: self.input.clone(),
         }
     }
@@ -169,18 +491,44 @@ impl AppConfig {
     fn to_jgnes_native_config(
         &self,
         nes_file_path: String,
+        palette: &Palette,
+        game_settings: Option<&GameSettings>,
     ) -> (JgnesNativeConfig, Receiver<Option<InputCollectResult>>) {
         let (shared_config, input_reconfigure_receiver) =
-            JgnesSharedConfig::new(self.to_jgnes_dynamic_config());
+            JgnesSharedConfig::new(self.to_jgnes_dynamic_config(palette, game_settings));
 
         let native_config = JgnesNativeConfig {
             nes_file_path,
             forced_timing_mode: self.forced_timing_mode,
+            forced_mapper_number: None,
+            patch_file_path: None,
+            ram_init: RamInit {
+                pattern: self.ram_init_pattern,
+                seed: self.ram_init_fixed_seed.then_some(self.ram_init_seed),
+            },
+            playlist: Vec::new(),
+            save_dir: None,
+            remote_control_port: self.remote_control_enabled.then_some(self.remote_control_port),
+            watch_rom_file: self.watch_rom_file,
+            warn_on_timing_mode_mismatch: self.warn_on_timing_mode_mismatch,
+            wav_capture_path: None,
+            frame_timing_log_path: None,
+            speedrun_timer_auto_start: self.speedrun_timer_auto_start,
+            last_run_path: None,
+            livesplit_port: self.livesplit_enabled.then_some(self.livesplit_port),
             window_width: self.window_width,
             window_height: self.window_height,
+            saved_window_geometry: self.emulator_window_geometry.clone(),
             renderer: self.renderer,
+            hidpi_scaling: self.hidpi_scaling,
             wgpu_backend: self.wgpu_backend,
+            gpu_adapter: self.gpu_adapter.clone(),
             launch_fullscreen: self.launch_fullscreen,
+            always_on_top: self.always_on_top,
+            borderless: self.borderless,
+            mirror_window: self.mirror_window,
+            sdl_video_driver: (!self.sdl_video_driver.is_empty())
+                .then(|| self.sdl_video_driver.clone()),
             shared_config,
         };
 
@@ -202,9 +550,56 @@ enum OpenWindow {
     AudioSettings,
     InputSettings,
     HotkeySettings,
+    PaletteEditor,
+    InterfaceSettings,
+    Log,
+    GameProperties,
+    CommandPalette,
+    KeyboardShortcuts,
     About,
 }
 
+impl OpenWindow {
+    // Settings menu entries along with the Ctrl+<key> shortcuts that open them, so that every
+    // window can be reached without a mouse
+    const SETTINGS: [(Self, &'static str, Key); 7] = [
+        (Self::GeneralSettings, "General", Key::Num1),
+        (Self::VideoSettings, "Video", Key::Num2),
+        (Self::AudioSettings, "Audio", Key::Num3),
+        (Self::InputSettings, "Input", Key::Num4),
+        (Self::HotkeySettings, "Hotkeys", Key::Num5),
+        (Self::PaletteEditor, "Palette", Key::Num6),
+        (Self::InterfaceSettings, "Interface", Key::Num7),
+    ];
+}
+
+const COMMAND_PALETTE_SHORTCUT: KeyboardShortcut = KeyboardShortcut::new(Modifiers::CTRL, Key::P);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LauncherCommand {
+    Open,
+    OpenWithPatch,
+    OpenPlaylist,
+    OpenInNewWindow,
+    LaunchLastGame,
+    OpenWindow(OpenWindow),
+    BigPicture,
+    Quit,
+}
+
+#[derive(Default)]
+struct CommandPaletteState {
+    filter: String,
+    selected: usize,
+    focus_requested: bool,
+}
+
+// Every word of the filter must appear somewhere in the command's label, ignoring case
+fn command_matches_filter(label: &str, filter: &str) -> bool {
+    let label = label.to_lowercase();
+    filter.split_whitespace().all(|word| label.contains(&word.to_lowercase()))
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub(crate) enum Player {
     P1,
@@ -215,6 +610,8 @@ pub(crate) enum Player {
 struct InputWindow(Player, InputType);
 
 struct OverscanState {
+    // Hash of the ROM whose per-game overscan is being edited, or None for the default
+    rom_hash: Option<String>,
     top_text: String,
     top_invalid: bool,
     left_text: String,
@@ -226,11 +623,53 @@ struct OverscanState {
 }
 
 impl OverscanState {
+    fn new(overscan: Overscan, rom_hash: Option<String>) -> Self {
+        Self {
+            rom_hash,
+            top_text: overscan.top.to_string(),
+            top_invalid: false,
+            left_text: overscan.left.to_string(),
+            left_invalid: false,
+            right_text: overscan.right.to_string(),
+            right_invalid: false,
+            bottom_text: overscan.bottom.to_string(),
+            bottom_invalid: false,
+        }
+    }
+
     fn invalid(&self) -> bool {
         self.top_invalid || self.left_invalid || self.right_invalid || self.bottom_invalid
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PaletteAction {
+    Load,
+    Save,
+    Reset,
+}
+
+struct PaletteEditorState {
+    // The palette currently in use, which may have unsaved edits
+    palette: Palette,
+    selected_color: usize,
+    modified: bool,
+    error: Option<String>,
+}
+
+impl PaletteEditorState {
+    fn new(palette_path: Option<&str>) -> Self {
+        let palette = palette_path.map_or_else(Palette::default, |palette_path| {
+            load_palette(Path::new(palette_path)).unwrap_or_else(|err| {
+                log::error!("Error loading palette from '{palette_path}', using default: {err}");
+                Palette::default()
+            })
+        });
+
+        Self { palette, selected_color: 0, modified: false, error: None }
+    }
+}
+
 struct InputState {
     axis_deadzone_text: String,
     axis_deadzone_invalid: bool,
@@ -238,6 +677,27 @@ struct InputState {
     ff_multiplier_invalid: bool,
     rewind_buffer_len_text: String,
     rewind_buffer_len_invalid: bool,
+    rewind_interval_text: String,
+    rewind_interval_invalid: bool,
+    rewind_memory_limit_text: String,
+    rewind_memory_limit_invalid: bool,
+    clip_len_text: String,
+    clip_len_invalid: bool,
+    capture_len_text: String,
+    capture_len_invalid: bool,
+    profile_name_text: String,
+    renaming_profile: Option<(usize, String)>,
+    profile_error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum InputProfileAction {
+    Load(usize),
+    StartRename(usize),
+    ConfirmRename(usize),
+    CancelRename,
+    Export(usize),
+    Delete(usize),
 }
 
 struct InputButton<'app, 'button> {
@@ -275,12 +735,14 @@ impl<'app> InputButton<'app, '_> {
         }
     }
 
-    fn ui(self, ui: &mut Ui) {
-        if self.button.ui(ui).clicked() {
+    fn ui(self, ui: &mut Ui) -> Response {
+        let response = self.button.ui(ui);
+        if response.clicked() {
             self.app_state.send_input_configure_request(self.input_type, self.axis_deadzone);
             self.app_state.waiting_for_input =
                 Some(WaitingForInput::NesButton(self.player, self.nes_button));
         }
+        response
     }
 }
 
@@ -303,6 +765,20 @@ impl<'app> HotkeyButton<'app, '_> {
             Hotkey::HardReset => app.config.input.hotkeys.hard_reset.as_ref(),
             Hotkey::FastForward => app.config.input.hotkeys.fast_forward.as_ref(),
             Hotkey::Rewind => app.config.input.hotkeys.rewind.as_ref(),
+            Hotkey::SaveClip => app.config.input.hotkeys.save_clip.as_ref(),
+            Hotkey::SaveCapture => app.config.input.hotkeys.save_capture.as_ref(),
+            Hotkey::NextGame => app.config.input.hotkeys.next_game.as_ref(),
+            Hotkey::PreviousGame => app.config.input.hotkeys.previous_game.as_ref(),
+            Hotkey::ToggleWavCapture => app.config.input.hotkeys.toggle_wav_capture.as_ref(),
+            Hotkey::ToggleScrollOverlay => app.config.input.hotkeys.toggle_scroll_overlay.as_ref(),
+            Hotkey::ToggleInputDisplay => app.config.input.hotkeys.toggle_input_display.as_ref(),
+            Hotkey::TimerStartSplit => app.config.input.hotkeys.timer_start_split.as_ref(),
+            Hotkey::TimerReset => app.config.input.hotkeys.timer_reset.as_ref(),
+            Hotkey::RecordMacro => app.config.input.hotkeys.record_macro.as_ref(),
+            Hotkey::PlayMacro => app.config.input.hotkeys.play_macro.as_ref(),
+            Hotkey::RewindForward => app.config.input.hotkeys.rewind_forward.as_ref(),
+            Hotkey::DumpRegisterWrites => app.config.input.hotkeys.dump_register_writes.as_ref(),
+            Hotkey::UndoLoadState => app.config.input.hotkeys.undo_load_state.as_ref(),
         };
         let button_text = current_value.map_or("<None>", String::as_str);
 
@@ -315,7 +791,7 @@ impl<'app> HotkeyButton<'app, '_> {
         }
     }
 
-    fn ui(self, ui: &mut Ui) {
+    fn ui(self, ui: &mut Ui) -> Response {
         let mut response = self.button.ui(ui);
         if let Some(on_disabled_hover_text) = self.on_disabled_hover_text {
             response = response.on_disabled_hover_text(on_disabled_hover_text);
@@ -324,6 +800,7 @@ impl<'app> HotkeyButton<'app, '_> {
             self.app_state.waiting_for_input = Some(WaitingForInput::Hotkey(self.hotkey));
             self.app_state.send_input_configure_request(InputType::Keyboard, self.axis_deadzone);
         }
+        response
     }
 }
 
@@ -362,6 +839,20 @@ enum Hotkey {
     HardReset,
     FastForward,
     Rewind,
+    SaveClip,
+    SaveCapture,
+    NextGame,
+    PreviousGame,
+    ToggleWavCapture,
+    ToggleScrollOverlay,
+    ToggleInputDisplay,
+    TimerStartSplit,
+    TimerReset,
+    RecordMacro,
+    PlayMacro,
+    RewindForward,
+    DumpRegisterWrites,
+    UndoLoadState,
 }
 
 impl Hotkey {
@@ -374,6 +865,20 @@ impl Hotkey {
         Self::HardReset,
         Self::FastForward,
         Self::Rewind,
+        Self::SaveClip,
+        Self::SaveCapture,
+        Self::NextGame,
+        Self::PreviousGame,
+        Self::ToggleWavCapture,
+        Self::ToggleScrollOverlay,
+        Self::ToggleInputDisplay,
+        Self::TimerStartSplit,
+        Self::TimerReset,
+        Self::RecordMacro,
+        Self::PlayMacro,
+        Self::RewindForward,
+        Self::DumpRegisterWrites,
+        Self::UndoLoadState,
     ];
 
     fn label(self) -> &'static str {
@@ -386,6 +891,20 @@ impl Hotkey {
             Self::HardReset => "Hard Reset",
             Self::FastForward => "Fast Forward",
             Self::Rewind => "Rewind",
+            Self::SaveClip => "Save Clip",
+            Self::SaveCapture => "Save Capture",
+            Self::NextGame => "Next Game",
+            Self::PreviousGame => "Previous Game",
+            Self::ToggleWavCapture => "Toggle WAV Capture",
+            Self::ToggleScrollOverlay => "Toggle Scroll Overlay",
+            Self::ToggleInputDisplay => "Toggle Input Display",
+            Self::TimerStartSplit => "Timer Start/Split",
+            Self::TimerReset => "Timer Reset",
+            Self::RecordMacro => "Record Macro",
+            Self::PlayMacro => "Play Macro",
+            Self::RewindForward => "Rewind Forward",
+            Self::DumpRegisterWrites => "Dump Register Writes",
+            Self::UndoLoadState => "Undo Load State",
         }
     }
 }
@@ -416,6 +935,72 @@ fn get_joystick_field(
     get_input_field(player_config, button)
 }
 
+fn render_analog_stick_settings(
+    ui: &mut Ui,
+    player: &str,
+    analog_stick: &mut AnalogStickConfig,
+    axis_deadzone: u16,
+) {
+    ui.group(|ui| {
+        ui.label(format!("{player} analog stick to D-pad"));
+
+        for (label, deadzone) in [
+            ("Horizontal deadzone", &mut analog_stick.horizontal_deadzone),
+            ("Vertical deadzone", &mut analog_stick.vertical_deadzone),
+        ] {
+            ui.horizontal(|ui| {
+                let mut custom = deadzone.is_some();
+                if ui
+                    .checkbox(&mut custom, label)
+                    .on_hover_text("Override the joystick axis deadzone for this axis")
+                    .changed()
+                {
+                    *deadzone = custom.then_some(axis_deadzone);
+                }
+
+                if let Some(deadzone) = deadzone {
+                    ui.add(Slider::new(deadzone, 0..=i16::MAX as u16));
+                }
+            });
+        }
+
+        ui.horizontal(|ui| {
+            ui.add(Slider::new(&mut analog_stick.diagonal_window, 0..=90).suffix("°"));
+            ui.label("Diagonal window").on_hover_text(
+                "Angle around each diagonal in which both directions are pressed; \
+                 0 treats each axis independently",
+            );
+        });
+    });
+}
+
+fn render_input_transforms(ui: &mut Ui, player: &str, transforms: &mut InputTransforms) {
+    ui.group(|ui| {
+        ui.label(format!("{player} input transforms"));
+
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut transforms.swap_a_b, "Swap A/B");
+            ui.checkbox(&mut transforms.turbo_a, "Turbo A")
+                .on_hover_text("Holding A rapidly presses and releases it");
+            ui.checkbox(&mut transforms.turbo_b, "Turbo B")
+                .on_hover_text("Holding B rapidly presses and releases it");
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("D-pad rotation:");
+            ui.radio_value(&mut transforms.dpad_rotation, DpadRotation::None, "None");
+            ui.radio_value(&mut transforms.dpad_rotation, DpadRotation::Clockwise, "90° clockwise")
+                .on_hover_text("Up becomes Right, Right becomes Down, etc.");
+            ui.radio_value(
+                &mut transforms.dpad_rotation,
+                DpadRotation::Counterclockwise,
+                "90° counterclockwise",
+            )
+            .on_hover_text("Up becomes Left, Left becomes Down, etc.");
+        });
+    });
+}
+
 fn get_hotkey_field(hotkey_config: &mut HotkeyConfig, hotkey: Hotkey) -> &mut Option<String> {
     match hotkey {
         Hotkey::Quit => &mut hotkey_config.quit,
@@ -426,6 +1011,20 @@ fn get_hotkey_field(hotkey_config: &mut HotkeyConfig, hotkey: Hotkey) -> &mut Op
         Hotkey::HardReset => &mut hotkey_config.hard_reset,
         Hotkey::FastForward => &mut hotkey_config.fast_forward,
         Hotkey::Rewind => &mut hotkey_config.rewind,
+        Hotkey::SaveClip => &mut hotkey_config.save_clip,
+        Hotkey::SaveCapture => &mut hotkey_config.save_capture,
+        Hotkey::NextGame => &mut hotkey_config.next_game,
+        Hotkey::PreviousGame => &mut hotkey_config.previous_game,
+        Hotkey::ToggleWavCapture => &mut hotkey_config.toggle_wav_capture,
+        Hotkey::ToggleScrollOverlay => &mut hotkey_config.toggle_scroll_overlay,
+        Hotkey::ToggleInputDisplay => &mut hotkey_config.toggle_input_display,
+        Hotkey::TimerStartSplit => &mut hotkey_config.timer_start_split,
+        Hotkey::TimerReset => &mut hotkey_config.timer_reset,
+        Hotkey::RecordMacro => &mut hotkey_config.record_macro,
+        Hotkey::PlayMacro => &mut hotkey_config.play_macro,
+        Hotkey::RewindForward => &mut hotkey_config.rewind_forward,
+        Hotkey::DumpRegisterWrites => &mut hotkey_config.dump_register_writes,
+        Hotkey::UndoLoadState => &mut hotkey_config.undo_load_state,
     }
 }
 
@@ -448,9 +1047,50 @@ enum WaitingForInput {
     Hotkey(Hotkey),
 }
 
+// Settings that are remembered for each game, applied on top of the defaults in the config
+#[derive(Debug, Clone, PartialEq)]
+struct GameSettings {
+    rom_hash: String,
+    overscan: Overscan,
+    remove_sprite_limit: bool,
+}
+
 struct RunningEmulatorState {
     shared_config: JgnesSharedConfig,
     input_reconfigure_receiver: Receiver<Option<InputCollectResult>>,
+    rom_path: PathBuf,
+    launch_options: LaunchOptions,
+    // None if the ROM could not be read
+    game_settings: Option<GameSettings>,
+    // Whether the emulator was last told to pause because a settings window is open
+    settings_paused: bool,
+}
+
+// A ROM passed on the command line is launched without showing the launcher, which stays minimized
+// until the emulator exits (closing the launcher) or fails (showing the error)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DirectLaunch {
+    // The launcher has not been minimized yet
+    Pending,
+    // Waiting for the emulation thread to start the emulator
+    Starting,
+    Running,
+}
+
+// A running game whose header specifies a different timing mode than the one it is running in
+#[derive(Debug, Clone)]
+struct TimingModeWarning {
+    rom_file_name: String,
+    header_timing_mode: TimingMode,
+    timing_mode: TimingMode,
+}
+
+#[derive(Debug, Clone, Default)]
+struct LaunchOptions {
+    forced_mapper_number: Option<u16>,
+    patch_file_path: Option<PathBuf>,
+    // Additional ROMs that can be switched to at runtime with the next/previous game hotkeys
+    playlist: Vec<PathBuf>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -459,6 +1099,18 @@ enum InputReceiveResult {
     NotReceived,
 }
 
+struct BigPictureState {
+    selected: usize,
+    scroll_to_selected: bool,
+    fullscreen_requested: bool,
+}
+
+impl BigPictureState {
+    fn new() -> Self {
+        Self { selected: 0, scroll_to_selected: true, fullscreen_requested: false }
+    }
+}
+
 struct ShaderState {
     render_scale_text: String,
     render_scale_invalid: bool,
@@ -466,6 +1118,7 @@ struct ShaderState {
     blur_stdev_invalid: bool,
     blur_radius_text: String,
     blur_radius_invalid: bool,
+    preset_error: Option<String>,
 }
 
 struct AppState {
@@ -477,27 +1130,66 @@ struct AppState {
     audio_buffer_size_invalid: bool,
     audio_sync_threshold_text: String,
     audio_sync_threshold_invalid: bool,
+    remote_control_port_text: String,
+    remote_control_port_invalid: bool,
+    livesplit_port_text: String,
+    livesplit_port_invalid: bool,
+    ram_init_seed_text: String,
+    ram_init_seed_invalid: bool,
     shader: ShaderState,
     overscan: OverscanState,
+    palette: PaletteEditorState,
     input: InputState,
     rom_list: Vec<RomMetadata>,
+    big_picture: Option<BigPictureState>,
+    command_palette: CommandPaletteState,
+    // Enumerated when the video settings are shown, and again if the wgpu backend changes
+    gpu_adapters: Option<(WgpuBackend, Vec<GpuAdapterInfo>)>,
+    direct_launch: Option<DirectLaunch>,
+    applied_appearance: Option<Appearance>,
+    applied_window_title: Option<String>,
     open_window: Option<OpenWindow>,
+    log_display_level: Level,
     error_window_open: bool,
+    quit_confirmation_open: bool,
+    quit_confirmed: bool,
+    timing_mode_warning: Option<TimingModeWarning>,
+    // Games that have already been checked for a timing mode mismatch, so that the warning is only
+    // shown once per game
+    timing_mode_checked_roms: HashSet<String>,
+    crash_report: Option<CrashReport>,
+    crash_report_include_state: bool,
+    crash_report_status: Option<String>,
     open_input_window: Option<InputWindow>,
     waiting_for_input: Option<WaitingForInput>,
     emulator_is_running: Arc<AtomicBool>,
     running_emulator_state: Option<RunningEmulatorState>,
+    // Emulators running in separate windows, each in its own process
+    instances: InstancePool,
+    handoff_receiver: Option<HandoffReceiver>,
+    data_paths: DataPaths,
+    // Created the first time cover art is shown
+    cover_art: Option<CoverArt>,
+    // ROM shown in the details pane
+    details_rom: Option<PathBuf>,
+    launcher_geometry_restored: bool,
     emulation_error: Arc<Mutex<Option<anyhow::Error>>>,
     thread_task_sender: Sender<EmuThreadTask>,
     thread_input_receiver: Receiver<Option<InputCollectResult>>,
+    launcher_input_enabled: Arc<AtomicBool>,
+    launcher_input_receiver: Receiver<LauncherInput>,
 }
 
 impl AppState {
     fn new(config: &AppConfig) -> Self {
         let is_running = Arc::new(AtomicBool::new(false));
         let emulation_error = Arc::new(Mutex::new(None));
-        let (thread_task_sender, thread_input_receiver) =
-            emuthread::start(Arc::clone(&is_running), Arc::clone(&emulation_error));
+        let launcher_input_enabled = Arc::new(AtomicBool::new(false));
+        let (thread_task_sender, thread_input_receiver, launcher_input_receiver) = emuthread::start(
+            Arc::clone(&is_running),
+            Arc::clone(&emulation_error),
+            Arc::clone(&launcher_input_enabled),
+        );
         let shader_state = ShaderState {
             render_scale_text: config.render_scale.get().to_string(),
             render_scale_invalid: false,
@@ -505,16 +1197,7 @@ impl AppState {
             blur_stdev_invalid: false,
             blur_radius_text: config.blur_radius.to_string(),
             blur_radius_invalid: false,
-        };
-        let overscan_state = OverscanState {
-            top_text: config.overscan.top.to_string(),
-            top_invalid: false,
-            left_text: config.overscan.left.to_string(),
-            left_invalid: false,
-            right_text: config.overscan.right.to_string(),
-            right_invalid: false,
-            bottom_text: config.overscan.bottom.to_string(),
-            bottom_invalid: false,
+            preset_error: None,
         };
         let input_state = InputState {
             axis_deadzone_text: config.input.axis_deadzone.to_string(),
@@ -523,6 +1206,17 @@ impl AppState {
             ff_multiplier_invalid: false,
             rewind_buffer_len_text: config.rewind_buffer_len_secs.to_string(),
             rewind_buffer_len_invalid: false,
+            rewind_interval_text: config.rewind_interval_frames.to_string(),
+            rewind_interval_invalid: false,
+            rewind_memory_limit_text: config.rewind_memory_limit_mb.to_string(),
+            rewind_memory_limit_invalid: false,
+            clip_len_text: config.clip_len_secs.to_string(),
+            clip_len_invalid: false,
+            capture_len_text: config.capture_len_secs.to_string(),
+            capture_len_invalid: false,
+            profile_name_text: String::new(),
+            renaming_profile: None,
+            profile_error: None,
         };
         Self {
             window_width_text: config.window_width.to_string(),
@@ -533,19 +1227,48 @@ impl AppState {
             audio_buffer_size_invalid: false,
             audio_sync_threshold_text: config.audio_sync_threshold.to_string(),
             audio_sync_threshold_invalid: false,
+            remote_control_port_text: config.remote_control_port.to_string(),
+            remote_control_port_invalid: false,
+            livesplit_port_text: config.livesplit_port.to_string(),
+            livesplit_port_invalid: false,
+            ram_init_seed_text: config.ram_init_seed.to_string(),
+            ram_init_seed_invalid: false,
             shader: shader_state,
-            overscan: overscan_state,
+            overscan: OverscanState::new(config.overscan, None),
+            palette: PaletteEditorState::new(config.palette_path.as_deref()),
             input: input_state,
             rom_list: Vec::new(),
+            big_picture: None,
+            command_palette: CommandPaletteState::default(),
+            gpu_adapters: None,
+            direct_launch: None,
+            applied_appearance: None,
+            applied_window_title: None,
             open_window: None,
+            log_display_level: Level::Trace,
             error_window_open: false,
+            quit_confirmation_open: false,
+            quit_confirmed: false,
+            timing_mode_warning: None,
+            timing_mode_checked_roms: HashSet::new(),
+            crash_report: None,
+            crash_report_include_state: true,
+            crash_report_status: None,
             open_input_window: None,
             waiting_for_input: None,
             emulator_is_running: is_running,
             running_emulator_state: None,
+            instances: InstancePool::default(),
+            handoff_receiver: None,
+            data_paths: DataPaths::default(),
+            cover_art: None,
+            details_rom: None,
+            launcher_geometry_restored: false,
             emulation_error,
             thread_task_sender,
             thread_input_receiver,
+            launcher_input_enabled,
+            launcher_input_receiver,
         }
     }
 
@@ -559,7 +1282,10 @@ impl AppState {
     }
 
     fn is_any_window_open(&self) -> bool {
-        self.open_window.is_some() || self.error_window_open || self.open_input_window.is_some()
+        self.open_window.is_some()
+            || self.error_window_open
+            || self.quit_confirmation_open
+            || self.open_input_window.is_some()
     }
 
     fn send_input_configure_request(&self, input_type: InputType, axis_deadzone: u16) {
@@ -619,7 +1345,7 @@ impl<'a, T: FromStr + PartialOrd> NumericTextInput<'a, T> {
         self
     }
 
-    fn ui(self, ui: &mut Ui) {
+    fn ui(self, ui: &mut Ui) -> Response {
         let mut text_edit = TextEdit::singleline(self.text);
         if let Some(desired_width) = self.desired_width {
             text_edit = text_edit.desired_width(desired_width);
@@ -636,12 +1362,34 @@ impl<'a, T: FromStr + PartialOrd> NumericTextInput<'a, T> {
                 }
             }
         }
+
+        response
+    }
+}
+
+// Per-game settings of the game that is running, if any; see App::running_game_settings
+fn running_game_settings_mut<'a>(
+    emulator_is_running: &AtomicBool,
+    running_emulator_state: &'a mut Option<RunningEmulatorState>,
+) -> Option<&'a mut GameSettings> {
+    if !emulator_is_running.load(Ordering::Relaxed) {
+        return None;
     }
+    running_emulator_state.as_mut()?.game_settings.as_mut()
 }
 
+const UI_SCALE_RANGE: RangeInclusive<f32> = 0.5..=3.0;
+
+const PALETTE_GRID_ROWS: usize = 4;
+const PALETTE_GRID_COLS: usize = 16;
+const PALETTE_SWATCH_SIZE: f32 = 24.0;
+
 const OVERSCAN_VERTICAL_MAX: u8 = 112;
 const OVERSCAN_HORIZONTAL_MAX: u8 = 128;
 
+// Same extension as states exported from the web frontend
+const SAVE_STATE_EXTENSION: &str = "state";
+
 pub struct App {
     config_path: PathBuf,
     config: AppConfig,
@@ -649,8 +1397,15 @@ pub struct App {
 }
 
 impl App {
+    /// Create the launcher, immediately launching `rom_path` if one was given on the command line.
+    /// ROMs handed off by later launchers are received on `handoff_receiver`.
     #[must_use]
-    pub fn new(config_path: PathBuf) -> Self {
+    pub fn new(
+        config_path: PathBuf,
+        data_paths: DataPaths,
+        rom_path: Option<PathBuf>,
+        handoff_receiver: Option<HandoffReceiver>,
+    ) -> Self {
         let config = match load_config(&config_path) {
             Ok(config) => config,
             Err(err) => {
@@ -663,13 +1418,85 @@ impl App {
             }
         };
 
-        let state = AppState::new(&config);
+        let mut state = AppState::new(&config);
+        state.handoff_receiver = handoff_receiver;
+        state.data_paths = data_paths;
 
         let mut app = Self { config_path, config, state };
         app.refresh_rom_list();
+        if app.config.start_in_big_picture {
+            app.enter_big_picture();
+        }
+        if let Some(rom_path) = rom_path {
+            app.launch_emulator(rom_path);
+            app.state.direct_launch = Some(DirectLaunch::Pending);
+        }
         app
     }
 
+    fn update_direct_launch(&mut self, ctx: &Context) {
+        let Some(direct_launch) = self.state.direct_launch else {
+            return;
+        };
+
+        let error = self.state.emulation_error.lock().unwrap().is_some();
+        let minimized = ctx.input(|input| input.viewport().minimized);
+        let running = self.state.emulator_is_running.load(Ordering::Relaxed);
+        self.state.direct_launch = match direct_launch {
+            _ if error => None,
+            DirectLaunch::Pending => {
+                ctx.send_viewport_cmd(ViewportCommand::Minimized(true));
+                Some(DirectLaunch::Starting)
+            }
+            DirectLaunch::Starting if running => Some(DirectLaunch::Running),
+            DirectLaunch::Starting => Some(DirectLaunch::Starting),
+            // The launcher was restored while the game was running, so keep it open
+            DirectLaunch::Running if minimized == Some(false) => None,
+            DirectLaunch::Running if running => Some(DirectLaunch::Running),
+            DirectLaunch::Running => {
+                ctx.send_viewport_cmd(ViewportCommand::Close);
+                None
+            }
+        };
+
+        if error {
+            ctx.send_viewport_cmd(ViewportCommand::Minimized(false));
+            ctx.send_viewport_cmd(ViewportCommand::Focus);
+        } else if self.state.direct_launch.is_some() {
+            // Notice as soon as the emulator exits
+            ctx.request_repaint_after(Duration::from_millis(100));
+        }
+    }
+
+    fn poll_for_handoffs(&mut self, ctx: &Context) {
+        let Some(handoff_receiver) = &self.state.handoff_receiver else {
+            return;
+        };
+        let handoffs: Vec<_> = handoff_receiver.try_iter().collect();
+        if handoffs.is_empty() {
+            return;
+        }
+
+        for rom_path in handoffs.into_iter().flatten() {
+            log::info!("Opening '{}' from another launcher", rom_path.display());
+            self.launch_or_switch_emulator(rom_path);
+        }
+        ctx.send_viewport_cmd(ViewportCommand::Minimized(false));
+        ctx.send_viewport_cmd(ViewportCommand::Focus);
+    }
+
+    fn enter_big_picture(&mut self) {
+        self.state.big_picture = Some(BigPictureState::new());
+        self.state.open_window = None;
+        self.state.launcher_input_enabled.store(true, Ordering::Relaxed);
+    }
+
+    fn exit_big_picture(&mut self, ctx: &Context) {
+        self.state.big_picture = None;
+        self.state.launcher_input_enabled.store(false, Ordering::Relaxed);
+        ctx.send_viewport_cmd(ViewportCommand::Fullscreen(false));
+    }
+
     fn handle_open(&mut self) {
         let file = FileDialog::new().add_filter("nes", &["nes"]).pick_file();
         if let Some(file) = file {
@@ -679,59 +1506,579 @@ impl App {
         }
     }
 
-    fn launch_emulator<P: AsRef<Path>>(&mut self, path: P) {
-        let path = path.as_ref();
+    fn handle_open_in_new_window(&mut self) {
+        let Some(file) = FileDialog::new().add_filter("nes", &["nes"]).pick_file() else {
+            return;
+        };
 
-        let file_path_str = path.to_string_lossy().to_string();
-        let (native_config, input_reconfigure_receiver) =
-            self.config.to_jgnes_native_config(file_path_str);
+        // The new instance reads its settings from the config file
+        self.save_config();
+        let portable = self.state.data_paths.is_portable();
+        if let Err(err) = self.state.instances.spawn(&self.config_path, &file, portable) {
+            log::error!("Unable to launch '{}' in new window: {err}", file.display());
+        }
+    }
 
-        self.state
-            .thread_task_sender
-            .send(EmuThreadTask::RunEmulator(Box::new(native_config.clone())))
-            .unwrap();
+    // Save states are portable, so these can be moved to and from the web frontend
+    fn handle_export_state(&mut self) {
+        let Some(running_emulator_state) = &self.state.running_emulator_state else {
+            return;
+        };
 
-        self.state.running_emulator_state = Some(RunningEmulatorState {
-            shared_config: native_config.shared_config,
-            input_reconfigure_receiver,
-        });
+        let rom_file_name =
+            running_emulator_state.shared_config.status().rom_file_name.unwrap_or_default();
+        let rom_stem = rom_file_name.strip_suffix(".nes").unwrap_or(&rom_file_name);
+        let default_file_name = format!("{rom_stem}.{SAVE_STATE_EXTENSION}");
+        let Some(path) = FileDialog::new()
+            .add_filter("save state", &[SAVE_STATE_EXTENSION])
+            .set_file_name(default_file_name)
+            .save_file()
+        else {
+            return;
+        };
+
+        running_emulator_state.shared_config.send_command(EmulatorCommand::ExportState(path));
     }
 
-    fn save_config(&mut self) {
-        let config_str =
-            toml::to_string(&self.config).expect("Config should always be serializable");
-        fs::write(&self.config_path, config_str).expect("Unable to save config file");
+    fn handle_record_frame_timing(&mut self) {
+        let Some(running_emulator_state) = &self.state.running_emulator_state else {
+            return;
+        };
+
+        let Some(path) = FileDialog::new()
+            .add_filter("csv", &["csv"])
+            .set_file_name("jgnes-frame-timing.csv")
+            .save_file()
+        else {
+            return;
+        };
+
+        running_emulator_state.shared_config.send_command(EmulatorCommand::RecordFrameTiming(path));
     }
 
-    fn update_running_emulator_config(&mut self) {
+    fn handle_import_state(&mut self) {
         let Some(running_emulator_state) = &self.state.running_emulator_state else {
             return;
         };
 
-        let dynamic_config =
-            &mut *running_emulator_state.shared_config.get_dynamic_config().lock().unwrap();
+        let Some(path) =
+            FileDialog::new().add_filter("save state", &[SAVE_STATE_EXTENSION]).pick_file()
+        else {
+            return;
+        };
 
-        *dynamic_config = self.config.to_jgnes_dynamic_config();
+        running_emulator_state.shared_config.send_command(EmulatorCommand::ImportState(path));
+    }
 
-        running_emulator_state.shared_config.request_config_reload();
+    fn handle_open_with_patch(&mut self) {
+        let Some(file) = FileDialog::new().add_filter("nes", &["nes"]).pick_file() else {
+            return;
+        };
+        let Some(patch_file) = FileDialog::new()
+            .set_title("Select patch")
+            .add_filter("patch", jgnes_core::patch::PatchFormat::extensions())
+            .pick_file()
+        else {
+            return;
+        };
+
+        self.state.stop_emulator_if_running();
+
+        self.launch_emulator_with_options(
+            file,
+            LaunchOptions { patch_file_path: Some(patch_file), ..LaunchOptions::default() },
+        );
     }
 
-    fn render_central_panel(&mut self, ctx: &Context) {
-        CentralPanel::default().show(ctx, |ui| {
-            ui.add_enabled_ui(!self.state.is_any_window_open(), |ui| {
-                match &self.config.rom_search_dir {
-                    Some(_) => {
-                        TableBuilder::new(ui)
-                            .auto_shrink([false; 2])
-                            .striped(true)
-                            .cell_layout(Layout::left_to_right(Align::Center))
-                            .column(Column::auto().at_most(300.0))
-                            .columns(Column::auto(), 3)
-                            .column(Column::remainder())
-                            .header(30.0, |mut row| {
-                                row.col(|ui| {
-                                    ui.vertical_centered(|ui| {
-                                        ui.heading("Name");
+    fn handle_open_playlist(&mut self) {
+        let Some(files) = FileDialog::new()
+            .set_title("Select ROMs for playlist")
+            .add_filter("nes", &["nes"])
+            .pick_files()
+        else {
+            return;
+        };
+        let mut files = files.into_iter();
+        let Some(first) = files.next() else {
+            return;
+        };
+
+        self.state.stop_emulator_if_running();
+
+        self.launch_emulator_with_options(
+            first,
+            LaunchOptions { playlist: files.collect(), ..LaunchOptions::default() },
+        );
+    }
+
+    fn launch_emulator<P: AsRef<Path>>(&mut self, path: P) {
+        self.launch_emulator_with_options(path, LaunchOptions::default());
+    }
+
+    // Load the ROM into the existing emulator window if an emulator is already running rather
+    // than closing the window and opening a new one
+    fn launch_or_switch_emulator<P: AsRef<Path>>(&mut self, path: P) {
+        let path = path.as_ref();
+
+        if !self.state.emulator_is_running.load(Ordering::Relaxed)
+            || self.state.running_emulator_state.is_none()
+        {
+            self.launch_emulator(path);
+            return;
+        }
+
+        let game_settings = self.game_settings(path);
+
+        let Some(running_emulator_state) = &mut self.state.running_emulator_state else {
+            return;
+        };
+        running_emulator_state.shared_config.request_rom_load(RomLoadRequest {
+            nes_file_path: path.to_string_lossy().to_string(),
+            patch_file_path: None,
+            forced_mapper_number: None,
+        });
+        running_emulator_state.rom_path = path.to_path_buf();
+        running_emulator_state.launch_options = LaunchOptions::default();
+        running_emulator_state.game_settings = game_settings;
+
+        self.config.last_rom_path = Some(path.to_string_lossy().to_string());
+        self.update_running_emulator_config();
+    }
+
+    // Returns None if the ROM can't be read
+    fn game_settings(&self, path: &Path) -> Option<GameSettings> {
+        // Hashes of ROMs in the ROM list are already known, so those don't need to be read again
+        let rom_hash = self
+            .state
+            .rom_list
+            .iter()
+            .find(|metadata| metadata.full_path == path)
+            .map(|metadata| metadata.rom_hash.clone())
+            .or_else(|| fs::read(path).ok().map(|rom_bytes| romlist::rom_hash(&rom_bytes)));
+        rom_hash.map(|rom_hash| self.config.game_settings(rom_hash))
+    }
+
+    // Per-game settings of the game that is running, which settings changes apply to instead of
+    // the defaults
+    fn running_game_settings(&self) -> Option<&GameSettings> {
+        if !self.state.emulator_is_running.load(Ordering::Relaxed) {
+            return None;
+        }
+        self.state.running_emulator_state.as_ref()?.game_settings.as_ref()
+    }
+
+    fn launch_emulator_with_options<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        launch_options: LaunchOptions,
+    ) {
+        let path = path.as_ref();
+
+        // If the ROM can't be read then the emulator will report the error when it starts
+        let game_settings = self.game_settings(path);
+
+        let file_path_str = path.to_string_lossy().to_string();
+        self.config.last_rom_path = Some(file_path_str.clone());
+        let (mut native_config, input_reconfigure_receiver) = self.config.to_jgnes_native_config(
+            file_path_str,
+            &self.state.palette.palette,
+            game_settings.as_ref(),
+        );
+
+        // Games launched from big picture mode should not require a keyboard to go fullscreen
+        if self.state.big_picture.is_some() {
+            native_config.launch_fullscreen = true;
+        }
+        native_config.forced_mapper_number = launch_options.forced_mapper_number;
+        native_config.patch_file_path =
+            launch_options.patch_file_path.as_ref().map(|path| path.to_string_lossy().to_string());
+        native_config.playlist =
+            launch_options.playlist.iter().map(|path| path.to_string_lossy().to_string()).collect();
+        native_config.last_run_path = Some(last_run_path(&self.config_path));
+        native_config.save_dir = self.state.data_paths.save_dir();
+
+        self.state
+            .thread_task_sender
+            .send(EmuThreadTask::RunEmulator(Box::new(native_config.clone())))
+            .unwrap();
+
+        self.state.running_emulator_state = Some(RunningEmulatorState {
+            shared_config: native_config.shared_config,
+            input_reconfigure_receiver,
+            rom_path: path.to_path_buf(),
+            launch_options,
+            game_settings,
+            settings_paused: false,
+        });
+    }
+
+    fn capture_crash_report(&self) -> Option<CrashReport> {
+        let emulation_error = self.state.emulation_error.lock().unwrap();
+        let err = emulation_error.as_ref()?;
+
+        // ROM problems are already explained in the error window and are not emulator bugs
+        if err.chain().find_map(|source| source.downcast_ref::<RomLoadError>()).is_some() {
+            return None;
+        }
+
+        let running_emulator_state = self.state.running_emulator_state.as_ref();
+        Some(CrashReport {
+            error: format!("{err:#}"),
+            config: toml::to_string(&self.config).unwrap_or_default(),
+            rom_file_name: running_emulator_state.and_then(|running_emulator_state| {
+                running_emulator_state
+                    .rom_path
+                    .file_name()
+                    .map(|file_name| file_name.to_string_lossy().into_owned())
+            }),
+            rom_hash: running_emulator_state
+                .and_then(|running_emulator_state| running_emulator_state.game_settings.as_ref())
+                .map(|game_settings| game_settings.rom_hash.clone()),
+            log_lines: jgnes_native_driver::recent_log_lines(),
+            save_state: running_emulator_state.and_then(|running_emulator_state| {
+                running_emulator_state.shared_config.take_crash_state()
+            }),
+        })
+    }
+
+    fn save_crash_report(&mut self) {
+        let Some(crash_report) = &self.state.crash_report else {
+            return;
+        };
+
+        let Some(path) = FileDialog::new()
+            .add_filter("zip", &["zip"])
+            .set_file_name("jgnes-crash-report.zip")
+            .save_file()
+        else {
+            return;
+        };
+
+        let crash_report = if self.state.crash_report_include_state {
+            crash_report.clone()
+        } else {
+            CrashReport { save_state: None, ..crash_report.clone() }
+        };
+        self.state.crash_report_status =
+            Some(match jgnes_native_driver::write_crash_report(&path, &crash_report) {
+                Ok(()) => format!("Saved crash report to '{}'", path.display()),
+                Err(err) => format!("Error saving crash report: {err}"),
+            });
+    }
+
+    fn render_error_window(&mut self, ctx: &Context) {
+        let (message, diagnostics) = {
+            let emulation_error = self.state.emulation_error.lock().unwrap();
+            let message = emulation_error.as_ref().map_or(String::new(), anyhow::Error::to_string);
+            let diagnostics = emulation_error.as_ref().and_then(|err| {
+                err.chain()
+                    .find_map(|source| source.downcast_ref::<RomLoadError>())
+                    .map(|err| err.diagnostics.clone())
+            });
+            (message, diagnostics)
+        };
+
+        let mut error_open = true;
+        let mut retry_mapper = None;
+        let mut save_crash_report = false;
+        Window::new("Error").resizable(false).open(&mut error_open).show(ctx, |ui| {
+            ui.colored_label(Color32::RED, message);
+
+            if let Some(crash_report) = &self.state.crash_report {
+                ui.add_space(5.0);
+
+                ui.label("If this looks like a bug, you can save a crash report to attach to a bug report. The report contains the error, your settings, the ROM's file name and hash, and recent log messages.");
+
+                if crash_report.save_state.is_some() {
+                    ui.checkbox(
+                        &mut self.state.crash_report_include_state,
+                        "Include a save state from when the error occurred",
+                    );
+                }
+
+                if ui.button("Save crash report...").clicked() {
+                    save_crash_report = true;
+                }
+
+                if let Some(status) = &self.state.crash_report_status {
+                    ui.label(status);
+                }
+            }
+
+            let Some(diagnostics) = diagnostics else {
+                return;
+            };
+
+            ui.add_space(5.0);
+
+            for problem in &diagnostics.problems {
+                let color = if problem.prevents_loading() { Color32::RED } else { Color32::YELLOW };
+                ui.colored_label(color, format!("• {problem}"));
+            }
+
+            if let Some(header) = &diagnostics.header {
+                ui.add_space(5.0);
+
+                CollapsingHeader::new("ROM header").default_open(true).show(ui, |ui| {
+                    Grid::new("rom_header_grid").num_columns(2).striped(true).show(ui, |ui| {
+                        let timing_mode = header
+                            .timing_mode
+                            .map_or_else(|| "Unsupported".into(), |mode| mode.to_string());
+                        let mirroring =
+                            if header.vertical_mirroring { "Vertical" } else { "Horizontal" };
+                        let rows = [
+                            ("File size", diagnostics.file_len.to_string()),
+                            ("Format", header.format.to_string()),
+                            ("Mapper", header.mapper_number.to_string()),
+                            ("Submapper", header.sub_mapper_number.to_string()),
+                            ("Timing mode", timing_mode),
+                            ("PRG ROM size", header.prg_rom_size.to_string()),
+                            ("PRG RAM size", header.prg_ram_size.to_string()),
+                            ("CHR ROM size", header.chr_rom_size.to_string()),
+                            ("CHR RAM size", header.chr_ram_size.to_string()),
+                            ("Battery", header.has_battery.to_string()),
+                            ("Trainer", header.has_trainer.to_string()),
+                            ("Mirroring", mirroring.into()),
+                            ("4-screen VRAM", header.has_four_screen_vram.to_string()),
+                        ];
+                        for (label, value) in rows {
+                            ui.label(label);
+                            ui.label(value);
+                            ui.end_row();
+                        }
+                    });
+                });
+            }
+
+            let can_retry = self.state.running_emulator_state.is_some();
+            if diagnostics.unsupported_mapper().is_some() && can_retry {
+                ui.add_space(5.0);
+
+                ui.horizontal(|ui| {
+                    if ui
+                        .button("Try anyway with mapper 0")
+                        .on_hover_text("Ignore the mapper number in the ROM header and run as NROM")
+                        .clicked()
+                    {
+                        retry_mapper = Some(0);
+                    }
+
+                    for problem in &diagnostics.problems {
+                        if let &RomProblem::DirtyHeader { low_mapper_number } = problem {
+                            if low_mapper_number != 0
+                                && ui
+                                    .button(format!("Try with mapper {low_mapper_number}"))
+                                    .clicked()
+                            {
+                                retry_mapper = Some(low_mapper_number);
+                            }
+                        }
+                    }
+                });
+            }
+        });
+
+        if save_crash_report {
+            self.save_crash_report();
+        }
+
+        if !error_open || retry_mapper.is_some() {
+            self.state.error_window_open = false;
+            self.state.crash_report = None;
+            *self.state.emulation_error.lock().unwrap() = None;
+        }
+
+        if let Some(mapper_number) = retry_mapper {
+            if let Some((rom_path, launch_options)) =
+                self.state.running_emulator_state.as_ref().map(|running_emulator_state| {
+                    (
+                        running_emulator_state.rom_path.clone(),
+                        running_emulator_state.launch_options.clone(),
+                    )
+                })
+            {
+                self.launch_emulator_with_options(
+                    rom_path,
+                    LaunchOptions { forced_mapper_number: Some(mapper_number), ..launch_options },
+                );
+            }
+        }
+    }
+
+    fn restore_launcher_geometry(&mut self, ctx: &Context) {
+        if self.state.launcher_geometry_restored {
+            return;
+        }
+
+        // The monitor size may not be known until the window has been shown
+        let Some(monitor_key) = ctx.input(|input| launcher_monitor_key(input.viewport())) else {
+            return;
+        };
+        self.state.launcher_geometry_restored = true;
+
+        if let Some(geometry) = self.config.launcher_window_geometry.get(&monitor_key) {
+            ctx.send_viewport_cmd(ViewportCommand::OuterPosition(egui::pos2(
+                geometry.x as f32,
+                geometry.y as f32,
+            )));
+            ctx.send_viewport_cmd(ViewportCommand::InnerSize(Vec2::new(
+                geometry.width as f32,
+                geometry.height as f32,
+            )));
+        }
+    }
+
+    fn save_launcher_geometry(&mut self, ctx: &Context) {
+        let geometry = ctx.input(|input| {
+            let viewport = input.viewport();
+            if viewport.fullscreen == Some(true) || viewport.minimized == Some(true) {
+                return None;
+            }
+
+            let monitor_key = launcher_monitor_key(viewport)?;
+            let position = viewport.outer_rect?.min;
+            let size = viewport.inner_rect?.size();
+            Some((
+                monitor_key,
+                WindowGeometry {
+                    x: position.x.round() as i32,
+                    y: position.y.round() as i32,
+                    width: size.x.round() as u32,
+                    height: size.y.round() as u32,
+                },
+            ))
+        });
+
+        if let Some((monitor_key, geometry)) = geometry {
+            self.config.launcher_window_geometry.insert(monitor_key, geometry);
+        }
+    }
+
+    fn save_emulator_geometry(&mut self) {
+        if let Some((display_key, geometry)) =
+            self.state.running_emulator_state.as_ref().and_then(|running_emulator_state| {
+                running_emulator_state.shared_config.take_window_geometry()
+            })
+        {
+            self.config.emulator_window_geometry.insert(display_key, geometry);
+        }
+    }
+
+    fn save_config(&mut self) {
+        let config_str =
+            toml::to_string(&self.config).expect("Config should always be serializable");
+        fs::write(&self.config_path, config_str).expect("Unable to save config file");
+    }
+
+    fn update_running_emulator_config(&mut self) {
+        let Some(running_emulator_state) = &self.state.running_emulator_state else {
+            return;
+        };
+
+        let dynamic_config =
+            &mut *running_emulator_state.shared_config.get_dynamic_config().lock().unwrap();
+
+        *dynamic_config = self.config.to_jgnes_dynamic_config(
+            &self.state.palette.palette,
+            running_emulator_state.game_settings.as_ref(),
+        );
+
+        running_emulator_state.shared_config.request_config_reload();
+        running_emulator_state
+            .shared_config
+            .request_window_flags(self.config.always_on_top, self.config.borderless);
+    }
+
+    fn cover(&mut self, ctx: &Context, metadata: &RomMetadata) -> Option<TextureHandle> {
+        if !self.config.show_cover_art {
+            return None;
+        }
+
+        let cover_art = self
+            .state
+            .cover_art
+            .get_or_insert_with(|| CoverArt::new(cover_art_dir(&self.config_path), ctx.clone()));
+        cover_art.cover(ctx, metadata, self.config.download_cover_art)
+    }
+
+    fn render_rom_details_panel(&mut self, ctx: &Context) {
+        let Some(metadata) = self.state.details_rom.as_ref().and_then(|details_rom| {
+            self.state.rom_list.iter().find(|metadata| &metadata.full_path == details_rom).cloned()
+        }) else {
+            self.state.details_rom = None;
+            return;
+        };
+
+        let cover = self.cover(ctx, &metadata);
+        SidePanel::right("rom_details_panel").resizable(false).exact_width(260.0).show(ctx, |ui| {
+            ui.add_space(5.0);
+            ui.horizontal(|ui| {
+                ui.heading("Details");
+                ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                    if ui.button("Close").clicked() {
+                        self.state.details_rom = None;
+                    }
+                });
+            });
+            ui.separator();
+
+            if let Some(cover) = &cover {
+                ui.add(Image::new(cover).max_size(Vec2::new(250.0, 250.0)));
+            }
+
+            ui.label(RichText::new(&metadata.file_name_no_ext).strong());
+            Grid::new("rom_details_grid").num_columns(2).show(ui, |ui| {
+                let chr_rom = if metadata.chr_rom_len > 0 {
+                    format!("{} KB", metadata.chr_rom_len / 1024)
+                } else {
+                    "None (RAM)".into()
+                };
+                let rows = [
+                    ("Board", metadata.mapper_name.clone()),
+                    ("PRG ROM", format!("{} KB", metadata.prg_rom_len / 1024)),
+                    ("CHR ROM", chr_rom),
+                    ("Hash", metadata.rom_hash.clone()),
+                ];
+                for (label, value) in rows {
+                    ui.label(label);
+                    ui.label(value);
+                    ui.end_row();
+                }
+            });
+            ui.label(metadata.full_path.display().to_string());
+
+            ui.add_space(5.0);
+            if ui.button("Launch").clicked() {
+                self.launch_or_switch_emulator(&metadata.full_path);
+            }
+        });
+    }
+
+    fn render_central_panel(&mut self, ctx: &Context) {
+        CentralPanel::default().show(ctx, |ui| {
+            ui.add_enabled_ui(!self.state.is_any_window_open(), |ui| {
+                match &self.config.rom_search_dir {
+                    Some(_) => {
+                        let show_cover_art = self.config.show_cover_art;
+                        let row_height = if show_cover_art { 64.0 } else { 40.0 };
+                        let mut table = TableBuilder::new(ui)
+                            .auto_shrink([false; 2])
+                            .striped(true)
+                            .cell_layout(Layout::left_to_right(Align::Center));
+                        if show_cover_art {
+                            table = table.column(Column::exact(64.0));
+                        }
+                        table
+                            .column(Column::auto().at_most(300.0))
+                            .columns(Column::auto(), 3)
+                            .column(Column::remainder())
+                            .header(30.0, |mut row| {
+                                if show_cover_art {
+                                    row.col(|_ui| {});
+                                }
+                                row.col(|ui| {
+                                    ui.vertical_centered(|ui| {
+                                        ui.heading("Name");
                                     });
                                 });
                                 row.col(|ui| {
@@ -755,14 +2102,25 @@ impl App {
                             })
                             .body(|mut body| {
                                 for metadata in self.state.rom_list.clone() {
-                                    body.row(40.0, |mut row| {
+                                    body.row(row_height, |mut row| {
+                                        if show_cover_art {
+                                            let cover = self.cover(ctx, &metadata);
+                                            row.col(|ui| {
+                                                if let Some(cover) = &cover {
+                                                    ui.add(
+                                                        Image::new(cover)
+                                                            .max_size(Vec2::new(60.0, 60.0)),
+                                                    );
+                                                }
+                                            });
+                                        }
+
                                         row.col(|ui| {
                                             let button = Button::new(&metadata.file_name_no_ext)
                                                 .min_size(Vec2::new(300.0, 30.0))
                                                 .wrap();
                                             if button.ui(ui).clicked() {
-                                                self.state.stop_emulator_if_running();
-                                                self.launch_emulator(&metadata.full_path);
+                                                self.launch_or_switch_emulator(&metadata.full_path);
                                             }
                                         });
 
@@ -790,8 +2148,13 @@ impl App {
                                             });
                                         });
 
-                                        // Blank column to make the stripes extend to the right
-                                        row.col(|_ui| {});
+                                        // Also extends the stripes to the right
+                                        row.col(|ui| {
+                                            if ui.button("Details").clicked() {
+                                                self.state.details_rom =
+                                                    Some(metadata.full_path.clone());
+                                            }
+                                        });
                                     });
                                 }
                             });
@@ -806,568 +2169,2448 @@ impl App {
         });
     }
 
-    fn render_general_settings_window(&mut self, ctx: &Context) {
-        let mut general_settings_open = true;
-        Window::new("General Settings").resizable(false).open(&mut general_settings_open).show(
-            ctx,
-            |ui| {
-                ui.horizontal(|ui| {
-                    let button_text = self.config.rom_search_dir.as_deref().unwrap_or("<None>");
-                    if ui.button(button_text).clicked() {
-                        if let Some(dir) = FileDialog::new().pick_folder() {
-                            self.config.rom_search_dir = dir.to_str().map(String::from);
+    fn render_main_ui(
+        &mut self,
+        ctx: &Context,
+        open_shortcut: KeyboardShortcut,
+        quit_shortcut: KeyboardShortcut,
+    ) {
+        TopBottomPanel::new(TopBottomSide::Top, "top_bottom_panel").show(ctx, |ui| {
+            ui.add_enabled_ui(!self.state.is_any_window_open(), |ui| {
+                menu::bar(ui, |ui| {
+                    ui.menu_button("File", |ui| {
+                        let open_button = Button::new("Open")
+                            .shortcut_text(ctx.format_shortcut(&open_shortcut))
+                            .ui(ui);
+                        if open_button.clicked() {
+                            self.handle_open();
+                            ui.close_menu();
                         }
-                    }
 
-                    ui.label("ROM search directory");
+                        if ui.button("Open with Patch...").clicked() {
+                            self.handle_open_with_patch();
+                            ui.close_menu();
+                        }
 
-                    if ui.button("Clear").clicked() {
-                        self.config.rom_search_dir = None;
-                    }
-                });
+                        if ui.button("Open Playlist...").clicked() {
+                            self.handle_open_playlist();
+                            ui.close_menu();
+                        }
 
-                ui.checkbox(
-                    &mut self.config.remove_sprite_limit,
-                    "Remove 8 sprite per scanline limit",
-                )
-                .on_hover_text("Eliminates sprite flickering but can cause bugs");
+                        if ui.button("Open in New Window...").clicked() {
+                            self.handle_open_in_new_window();
+                            ui.close_menu();
+                        }
 
-                ui.group(|ui| {
-                    ui.add_enabled_ui(
-                        !self.state.emulator_is_running.load(Ordering::Relaxed),
-                        |ui| {
-                            let disabled_hover_text =
-                                "Cannot change forced timing mode while emulator is running";
+                        ui.add_enabled_ui(self.config.last_rom_path.is_some(), |ui| {
+                            if ui.button("Launch Last Game").clicked() {
+                                self.run_launcher_command(ctx, LauncherCommand::LaunchLastGame);
+                                ui.close_menu();
+                            }
+                        });
 
-                            ui.label("Forced timing mode")
-                                .on_hover_text("If set, ignore timing mode in cartridge header")
-                                .on_disabled_hover_text(disabled_hover_text);
+                        ui.separator();
 
-                            ui.horizontal(|ui| {
-                                ui.radio_value(&mut self.config.forced_timing_mode, None, "None")
-                                    .on_disabled_hover_text(disabled_hover_text);
+                        let emulator_is_running =
+                            self.state.emulator_is_running.load(Ordering::Relaxed);
+                        ui.add_enabled_ui(emulator_is_running, |ui| {
+                            if ui.button("Export State...").clicked() {
+                                self.handle_export_state();
+                                ui.close_menu();
+                            }
 
-                                ui.radio_value(
-                                    &mut self.config.forced_timing_mode,
-                                    Some(TimingMode::Ntsc),
-                                    "NTSC",
-                                )
-                                .on_disabled_hover_text(disabled_hover_text);
+                            if ui.button("Import State...").clicked() {
+                                self.handle_import_state();
+                                ui.close_menu();
+                            }
+                        });
 
-                                ui.radio_value(
-                                    &mut self.config.forced_timing_mode,
-                                    Some(TimingMode::Pal),
-                                    "PAL",
-                                )
-                                .on_disabled_hover_text(disabled_hover_text);
+                        ui.separator();
+
+                        ui.add_enabled_ui(!self.state.instances.is_empty(), |ui| {
+                            ui.menu_button("Close Window", |ui| {
+                                for instance in self.state.instances.instances_mut() {
+                                    if ui.button(instance.rom_file_name()).clicked() {
+                                        instance.request_quit();
+                                        ui.close_menu();
+                                    }
+                                }
+                            });
+                        });
+
+                        let quit_button = Button::new("Quit")
+                            .shortcut_text(ctx.format_shortcut(&quit_shortcut))
+                            .ui(ui);
+                        if quit_button.clicked() {
+                            ctx.send_viewport_cmd(ViewportCommand::Close);
+                        }
+                    });
+
+                    ui.menu_button("View", |ui| {
+                        if ui.button("Big Picture Mode").clicked() {
+                            self.enter_big_picture();
+                            ui.close_menu();
+                        }
+
+                        if ui.button("Log").clicked() {
+                            self.state.open_window = Some(OpenWindow::Log);
+                            ui.close_menu();
+                        }
+
+                        if ui.button("Game Properties").clicked() {
+                            self.state.open_window = Some(OpenWindow::GameProperties);
+                            ui.close_menu();
+                        }
+                    });
+
+                    ui.menu_button("Settings", |ui| {
+                        for (window, label, key) in OpenWindow::SETTINGS {
+                            let shortcut = KeyboardShortcut::new(Modifiers::CTRL, key);
+                            let button = Button::new(label)
+                                .shortcut_text(ctx.format_shortcut(&shortcut))
+                                .ui(ui);
+                            if button.clicked() {
+                                self.state.open_window = Some(window);
+                                ui.close_menu();
+                            }
+                        }
+                    });
+
+                    ui.menu_button("Help", |ui| {
+                        let command_palette_button = Button::new("Command Palette")
+                            .shortcut_text(ctx.format_shortcut(&COMMAND_PALETTE_SHORTCUT))
+                            .ui(ui);
+                        if command_palette_button.clicked() {
+                            self.open_command_palette();
+                            ui.close_menu();
+                        }
+
+                        if ui.button("Keyboard Shortcuts").clicked() {
+                            self.state.open_window = Some(OpenWindow::KeyboardShortcuts);
+                            ui.close_menu();
+                        }
+
+                        ui.separator();
+
+                        let emulator_is_running =
+                            self.state.emulator_is_running.load(Ordering::Relaxed);
+                        ui.add_enabled_ui(emulator_is_running, |ui| {
+                            let button =
+                                ui.button("Record Frame Timing...").on_hover_text(format!(
+                                    "Log when each frame is shown, audio queue depth, and emulator \
+                                 ticks per frame to a CSV file for the next {} seconds; useful \
+                                 to attach to stutter bug reports",
+                                    FRAME_TIMING_LOG_DURATION.as_secs()
+                                ));
+                            if button.clicked() {
+                                self.handle_record_frame_timing();
+                                ui.close_menu();
+                            }
+                        });
+
+                        ui.separator();
+
+                        if ui.button("About").clicked() {
+                            self.state.open_window = Some(OpenWindow::About);
+                            ui.close_menu();
+                        }
+                    });
+                });
+            });
+        });
+
+        self.render_rom_details_panel(ctx);
+        self.render_central_panel(ctx);
+
+        match self.state.open_window {
+            Some(OpenWindow::GeneralSettings) => {
+                self.render_general_settings_window(ctx);
+            }
+            Some(OpenWindow::VideoSettings) => {
+                self.render_video_settings_window(ctx);
+            }
+            Some(OpenWindow::AudioSettings) => {
+                self.render_audio_settings_window(ctx);
+            }
+            Some(OpenWindow::InputSettings) => {
+                self.render_input_settings_window(ctx);
+            }
+            Some(OpenWindow::HotkeySettings) => {
+                self.render_hotkey_settings_window(ctx);
+            }
+            Some(OpenWindow::PaletteEditor) => {
+                self.render_palette_editor_window(ctx);
+            }
+            Some(OpenWindow::InterfaceSettings) => {
+                self.render_interface_settings_window(ctx);
+            }
+            Some(OpenWindow::Log) => {
+                self.render_log_window(ctx);
+            }
+            Some(OpenWindow::GameProperties) => {
+                self.render_game_properties_window(ctx);
+            }
+            Some(OpenWindow::CommandPalette) => {
+                self.render_command_palette_window(ctx);
+            }
+            Some(OpenWindow::KeyboardShortcuts) => {
+                self.render_keyboard_shortcuts_window(ctx);
+            }
+            Some(OpenWindow::About) => {
+                self.render_about_window(ctx);
+            }
+            None => {}
+        }
+    }
+
+    fn handle_window_shortcuts(&mut self, ctx: &Context) {
+        for (window, _, key) in OpenWindow::SETTINGS {
+            let shortcut = KeyboardShortcut::new(Modifiers::CTRL, key);
+            if ctx.input_mut(|input| input.consume_shortcut(&shortcut)) {
+                self.state.open_window = Some(window);
+                self.state.open_input_window = None;
+            }
+        }
+
+        if ctx.input_mut(|input| input.consume_shortcut(&COMMAND_PALETTE_SHORTCUT)) {
+            self.open_command_palette();
+        }
+
+        // Escape closes the topmost window, unless a key is currently being captured for a binding
+        if self.state.waiting_for_input.is_none()
+            && self.state.is_any_window_open()
+            && ctx.input_mut(|input| input.consume_key(Modifiers::NONE, Key::Escape))
+        {
+            if self.state.quit_confirmation_open {
+                self.state.quit_confirmation_open = false;
+            } else if self.state.open_input_window.is_some() {
+                self.state.open_input_window = None;
+            } else if self.state.error_window_open {
+                self.state.error_window_open = false;
+                self.state.crash_report = None;
+                *self.state.emulation_error.lock().unwrap() = None;
+            } else {
+                self.state.open_window = None;
+            }
+        }
+    }
+
+    fn render_big_picture(&mut self, ctx: &Context) {
+        const TILE_SIZE: Vec2 = Vec2::new(280.0, 160.0);
+
+        let covers: Vec<_> = if self.config.show_cover_art {
+            self.state.rom_list.clone().iter().map(|metadata| self.cover(ctx, metadata)).collect()
+        } else {
+            Vec::new()
+        };
+
+        let Some(big_picture) = &mut self.state.big_picture else {
+            return;
+        };
+
+        if !big_picture.fullscreen_requested {
+            ctx.send_viewport_cmd(ViewportCommand::Fullscreen(true));
+            big_picture.fullscreen_requested = true;
+        }
+
+        // Controller input is still delivered while a game is running, so drain it without acting
+        let emulator_is_running = self.state.emulator_is_running.load(Ordering::Relaxed);
+        let mut inputs: Vec<_> = self.state.launcher_input_receiver.try_iter().collect();
+        if emulator_is_running {
+            inputs.clear();
+        }
+
+        ctx.input_mut(|input| {
+            for (key, launcher_input) in [
+                (Key::ArrowUp, LauncherInput::Up),
+                (Key::ArrowDown, LauncherInput::Down),
+                (Key::ArrowLeft, LauncherInput::Left),
+                (Key::ArrowRight, LauncherInput::Right),
+                (Key::Enter, LauncherInput::Confirm),
+                (Key::Escape, LauncherInput::Back),
+            ] {
+                if input.consume_key(Modifiers::NONE, key) {
+                    inputs.push(launcher_input);
+                }
+            }
+        });
+
+        let item_spacing = ctx.style().spacing.item_spacing;
+        let available_width = ctx.available_rect().width() - 2.0 * item_spacing.x;
+        let columns =
+            (((available_width + item_spacing.x) / (TILE_SIZE.x + item_spacing.x)).floor()
+                as usize)
+                .max(1);
+
+        let num_roms = self.state.rom_list.len();
+        let mut launch_selected = false;
+        let mut exit = false;
+        for input in inputs {
+            let prev_selected = big_picture.selected;
+            match input {
+                LauncherInput::Up => {
+                    big_picture.selected = big_picture.selected.saturating_sub(columns);
+                }
+                LauncherInput::Down => {
+                    if big_picture.selected + columns < num_roms {
+                        big_picture.selected += columns;
+                    }
+                }
+                LauncherInput::Left => {
+                    big_picture.selected = big_picture.selected.saturating_sub(1);
+                }
+                LauncherInput::Right => {
+                    if big_picture.selected + 1 < num_roms {
+                        big_picture.selected += 1;
+                    }
+                }
+                LauncherInput::Confirm => {
+                    launch_selected = true;
+                }
+                LauncherInput::Back => {
+                    exit = true;
+                }
+            }
+            big_picture.scroll_to_selected |= big_picture.selected != prev_selected;
+        }
+        big_picture.selected = big_picture.selected.min(num_roms.saturating_sub(1));
+
+        let mut clicked = None;
+        TopBottomPanel::new(TopBottomSide::Bottom, "big_picture_footer").show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.label(
+                    RichText::new(
+                        "D-Pad / Arrows: Select    A / Enter: Launch    B / Escape: Exit",
+                    )
+                    .size(20.0),
+                );
+
+                ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                    if ui.button(RichText::new("Exit Big Picture").size(20.0)).clicked() {
+                        exit = true;
+                    }
+                });
+            });
+        });
+
+        CentralPanel::default().show(ctx, |ui| {
+            if num_roms == 0 {
+                ui.centered_and_justified(|ui| {
+                    ui.heading("Configure a ROM search directory to see ROM list here");
+                });
+                return;
+            }
+
+            ScrollArea::vertical().auto_shrink([false; 2]).show(ui, |ui| {
+                Grid::new("big_picture_grid").spacing(item_spacing).show(ui, |ui| {
+                    for (i, metadata) in self.state.rom_list.iter().enumerate() {
+                        let text = RichText::new(&metadata.file_name_no_ext).size(24.0);
+                        let button = match covers.get(i).cloned().flatten() {
+                            Some(cover) => Button::image_and_text(
+                                Image::new(&cover).max_size(Vec2::new(110.0, 110.0)),
+                                text,
+                            ),
+                            None => Button::new(text),
+                        };
+                        let response = button
+                            .wrap()
+                            .min_size(TILE_SIZE)
+                            .selected(i == big_picture.selected)
+                            .ui(ui);
+                        if response.clicked() {
+                            clicked = Some(i);
+                        }
+                        if i == big_picture.selected && big_picture.scroll_to_selected {
+                            response.scroll_to_me(Some(Align::Center));
+                            big_picture.scroll_to_selected = false;
+                        }
+
+                        if (i + 1) % columns == 0 {
+                            ui.end_row();
+                        }
+                    }
+                });
+            });
+        });
+
+        if let Some(i) = clicked {
+            big_picture.selected = i;
+            launch_selected = true;
+        }
+
+        if launch_selected && num_roms != 0 {
+            let path = self.state.rom_list[big_picture.selected].full_path.clone();
+            self.launch_or_switch_emulator(path);
+        }
+
+        if exit {
+            self.exit_big_picture(ctx);
+        }
+
+        // Controller input arrives outside of egui, so keep repainting to pick it up
+        ctx.request_repaint_after(Duration::from_millis(16));
+    }
+
+    fn render_general_settings_window(&mut self, ctx: &Context) {
+        let mut general_settings_open = true;
+        Window::new("General Settings").resizable(false).open(&mut general_settings_open).show(
+            ctx,
+            |ui| {
+                ui.horizontal(|ui| {
+                    let button_text = self.config.rom_search_dir.as_deref().unwrap_or("<None>");
+                    if ui.button(button_text).clicked() {
+                        if let Some(dir) = FileDialog::new().pick_folder() {
+                            self.config.rom_search_dir = dir.to_str().map(String::from);
+                        }
+                    }
+
+                    ui.label("ROM search directory");
+
+                    if ui.button("Clear").clicked() {
+                        self.config.rom_search_dir = None;
+                    }
+                });
+
+                ui.checkbox(&mut self.config.start_in_big_picture, "Start in big picture mode")
+                    .on_hover_text("Full-screen ROM picker that can be navigated with a gamepad");
+
+                ui.checkbox(&mut self.config.show_cover_art, "Show cover art in ROM list");
+                ui.add_enabled_ui(self.config.show_cover_art, |ui| {
+                    ui.checkbox(
+                        &mut self.config.download_cover_art,
+                        "Download missing cover art from libretro-thumbnails",
+                    )
+                    .on_hover_text(
+                        "Cover art is found by ROM file name, so this works best with No-Intro \
+                         names. When disabled, only cover art that was already downloaded is \
+                         shown and the launcher never accesses the network",
+                    );
+                });
+
+                ui.add_enabled_ui(!self.state.emulator_is_running.load(Ordering::Relaxed), |ui| {
+                    ui.checkbox(&mut self.config.watch_rom_file, "Reload ROM when file changes")
+                        .on_hover_text(
+                            "Hard reset with the new ROM contents whenever the running ROM file \
+                             is modified; useful for homebrew development",
+                        )
+                        .on_disabled_hover_text(
+                            "Cannot change ROM watching while emulator is running",
+                        );
+
+                    ui.checkbox(
+                        &mut self.config.speedrun_timer_auto_start,
+                        "Restart speedrun timer on reset",
+                    )
+                    .on_hover_text(
+                        "Start a new speedrun timer run whenever the console is soft or hard reset",
+                    )
+                    .on_disabled_hover_text(
+                        "Cannot change speedrun timer settings while emulator is running",
+                    );
+                });
+
+                let remove_sprite_limit = match running_game_settings_mut(
+                    &self.state.emulator_is_running,
+                    &mut self.state.running_emulator_state,
+                ) {
+                    Some(game_settings) => &mut game_settings.remove_sprite_limit,
+                    None => &mut self.config.remove_sprite_limit,
+                };
+                ui.checkbox(remove_sprite_limit, "Remove 8 sprite per scanline limit")
+                    .on_hover_text(
+                        "Eliminates sprite flickering but can cause bugs in some games; changing \
+                         this while a game is running only changes the setting for that game",
+                    );
+                let remove_sprite_limit = *remove_sprite_limit;
+
+                ui.add_enabled_ui(!remove_sprite_limit, |ui| {
+                    ui.checkbox(
+                        &mut self.config.reduce_sprite_flicker,
+                        "Alternate dropped sprites",
+                    )
+                    .on_hover_text(
+                        "On scanlines with too many sprites, change which sprites are dropped \
+                         every frame so that they flicker instead of disappearing",
+                    );
+                });
+
+                ui.group(|ui| {
+                    ui.label("Ask for confirmation before");
+
+                    ui.checkbox(&mut self.config.confirm_quit, "Quitting while a game is running")
+                        .on_hover_text(
+                            "The quit hotkey and the emulator window's close button must be used \
+                             twice, and closing the launcher asks first",
+                        );
+
+                    ui.checkbox(
+                        &mut self.config.confirm_overwrite_state,
+                        "Overwriting a save state",
+                    )
+                    .on_hover_text(
+                        "The save state hotkey must be pressed twice if there is already a save \
+                         state",
+                    );
+
+                    ui.checkbox(&mut self.config.confirm_hard_reset, "Hard resetting")
+                        .on_hover_text("The hard reset hotkey must be pressed twice");
+                });
+
+                ui.group(|ui| {
+                    ui.add_enabled_ui(
+                        !self.state.emulator_is_running.load(Ordering::Relaxed),
+                        |ui| {
+                            let disabled_hover_text =
+                                "Cannot change forced timing mode while emulator is running";
+
+                            ui.label("Forced timing mode")
+                                .on_hover_text("If set, ignore timing mode in cartridge header")
+                                .on_disabled_hover_text(disabled_hover_text);
+
+                            ui.horizontal(|ui| {
+                                ui.radio_value(&mut self.config.forced_timing_mode, None, "None")
+                                    .on_disabled_hover_text(disabled_hover_text);
+
+                                ui.radio_value(
+                                    &mut self.config.forced_timing_mode,
+                                    Some(TimingMode::Ntsc),
+                                    "NTSC",
+                                )
+                                .on_disabled_hover_text(disabled_hover_text);
+
+                                ui.radio_value(
+                                    &mut self.config.forced_timing_mode,
+                                    Some(TimingMode::Pal),
+                                    "PAL",
+                                )
+                                .on_disabled_hover_text(disabled_hover_text);
+                            });
+
+                            ui.checkbox(
+                                &mut self.config.warn_on_timing_mode_mismatch,
+                                "Warn when a game runs in the wrong timing mode",
+                            )
+                            .on_hover_text(
+                                "Games run at the wrong speed when the forced timing mode does not \
+                                 match the cartridge header",
+                            )
+                            .on_disabled_hover_text(disabled_hover_text);
+                        },
+                    );
+                });
+
+                ui.group(|ui| {
+                    ui.add_enabled_ui(
+                        !self.state.emulator_is_running.load(Ordering::Relaxed),
+                        |ui| {
+                            let disabled_hover_text =
+                                "Cannot change RAM initialization while emulator is running";
+
+                            ui.label("Power-on RAM contents")
+                                .on_hover_text(
+                                    "Some games behave differently based on initial RAM contents",
+                                )
+                                .on_disabled_hover_text(disabled_hover_text);
+
+                            ui.horizontal(|ui| {
+                                for (pattern, label) in [
+                                    (RamInitPattern::AllZeros, "All $00"),
+                                    (RamInitPattern::AllOnes, "All $FF"),
+                                    (RamInitPattern::Alternating, "Alternating"),
+                                    (RamInitPattern::Random, "Random"),
+                                ] {
+                                    ui.radio_value(
+                                        &mut self.config.ram_init_pattern,
+                                        pattern,
+                                        label,
+                                    )
+                                    .on_disabled_hover_text(disabled_hover_text);
+                                }
                             });
+
+                            ui.add_enabled_ui(
+                                self.config.ram_init_pattern == RamInitPattern::Random,
+                                |ui| {
+                                    ui.horizontal(|ui| {
+                                        ui.checkbox(
+                                            &mut self.config.ram_init_fixed_seed,
+                                            "Fixed seed",
+                                        )
+                                        .on_hover_text(
+                                            "If not checked, a new seed is chosen every power-on",
+                                        );
+
+                                        NumericTextInput::new(
+                                            &mut self.state.ram_init_seed_text,
+                                            &mut self.config.ram_init_seed,
+                                            &mut self.state.ram_init_seed_invalid,
+                                            0..=u64::MAX,
+                                        )
+                                        .desired_width(150.0)
+                                        .ui(ui);
+                                    });
+                                },
+                            );
+                            if self.state.ram_init_seed_invalid {
+                                ui.colored_label(
+                                    Color32::RED,
+                                    "Seed must be a non-negative integer",
+                                );
+                            }
                         },
                     );
                 });
+
+                ui.group(|ui| {
+                    ui.add_enabled_ui(
+                        !self.state.emulator_is_running.load(Ordering::Relaxed),
+                        |ui| {
+                            let disabled_hover_text =
+                                "Cannot change remote control settings while emulator is running";
+
+                            ui.checkbox(
+                                &mut self.config.remote_control_enabled,
+                                "Enable remote control",
+                            )
+                            .on_hover_text(
+                                "Accept newline-delimited JSON commands over a localhost TCP port",
+                            )
+                            .on_disabled_hover_text(disabled_hover_text);
+
+                            ui.horizontal(|ui| {
+                                let text_edit = NumericTextInput::new(
+                                    &mut self.state.remote_control_port_text,
+                                    &mut self.config.remote_control_port,
+                                    &mut self.state.remote_control_port_invalid,
+                                    1..=u16::MAX,
+                                )
+                                .desired_width(60.0)
+                                .ui(ui)
+                                .on_disabled_hover_text(disabled_hover_text);
+                                let label = ui.label("Remote control port");
+                                text_edit.labelled_by(label.id);
+                            });
+                            if self.state.remote_control_port_invalid {
+                                ui.colored_label(
+                                    Color32::RED,
+                                    "Port must be an integer between 1 and 65535",
+                                );
+                            }
+                        },
+                    );
+                });
+
+                ui.group(|ui| {
+                    ui.add_enabled_ui(
+                        !self.state.emulator_is_running.load(Ordering::Relaxed),
+                        |ui| {
+                            let disabled_hover_text =
+                                "Cannot change LiveSplit settings while emulator is running";
+
+                            ui.checkbox(
+                                &mut self.config.livesplit_enabled,
+                                "Send timer events to LiveSplit Server",
+                            )
+                            .on_hover_text(
+                                "Forward speedrun timer start, split, and reset events to the \
+                                 LiveSplit Server component over a localhost TCP port",
+                            )
+                            .on_disabled_hover_text(disabled_hover_text);
+
+                            ui.horizontal(|ui| {
+                                let text_edit = NumericTextInput::new(
+                                    &mut self.state.livesplit_port_text,
+                                    &mut self.config.livesplit_port,
+                                    &mut self.state.livesplit_port_invalid,
+                                    1..=u16::MAX,
+                                )
+                                .desired_width(60.0)
+                                .ui(ui)
+                                .on_disabled_hover_text(disabled_hover_text);
+                                let label = ui.label("LiveSplit Server port");
+                                text_edit.labelled_by(label.id);
+                            });
+                            if self.state.livesplit_port_invalid {
+                                ui.colored_label(
+                                    Color32::RED,
+                                    "Port must be an integer between 1 and 65535",
+                                );
+                            }
+                        },
+                    );
+                });
+            },
+        );
+        if !general_settings_open {
+            self.state.open_window = None;
+        }
+    }
+
+    fn render_video_settings_window(&mut self, ctx: &Context) {
+        let mut video_settings_open = true;
+        Window::new("Video Settings")
+            .resizable(false)
+            .open(&mut video_settings_open)
+            .show(ctx, |ui| {
+                ui.checkbox(&mut self.config.launch_fullscreen, "Launch in fullscreen");
+
+                ui.checkbox(&mut self.config.always_on_top, "Keep window on top");
+
+                ui.checkbox(&mut self.config.borderless, "Borderless window")
+                    .on_hover_text("Hide the emulator window's title bar and border");
+
+                ui.checkbox(&mut self.config.mirror_window, "Mirror to a second window")
+                    .on_hover_text(
+                        "Open a second window that shows the same output without the on-screen \
+                         display, e.g. as a clean feed for capture",
+                    );
+
+                ui.horizontal(|ui| {
+                    let text_edit = NumericTextInput::new(
+                        &mut self.state.window_width_text,
+                        &mut self.config.window_width,
+                        &mut self.state.window_width_invalid,
+                        1..=u32::MAX,
+                    )
+                        .desired_width(60.0)
+                        .ui(ui);
+                    let label = ui.label("Window width in pixels");
+                    text_edit.labelled_by(label.id);
+                });
+                if self.state.window_width_invalid {
+                    ui.colored_label(Color32::RED, "Window width must be a non-negative integer");
+                }
+
+                ui.horizontal(|ui| {
+                    let text_edit = NumericTextInput::new(
+                        &mut self.state.window_height_text,
+                        &mut self.config.window_height,
+                        &mut self.state.window_height_invalid,
+                        1..=u32::MAX
+                    )
+                        .desired_width(60.0)
+                        .ui(ui);
+                    let label = ui.label("Window height in pixels");
+                    text_edit.labelled_by(label.id);
+                });
+                if self.state.window_height_invalid {
+                    ui.colored_label(Color32::RED, "Window height must be a non-negative integer");
+                }
+
+                ui.group(|ui| {
+                    ui.add_enabled_ui(!self.state.emulator_is_running.load(Ordering::Relaxed), |ui| {
+                        ui.label("Renderer");
+                        ui.horizontal(|ui| {
+                            ui.radio_value(&mut self.config.renderer, NativeRenderer::Wgpu, "wgpu")
+                                .on_disabled_hover_text("Cannot change renderer while emulator is running");
+                            ui.radio_value(&mut self.config.renderer, NativeRenderer::Sdl2, "SDL2")
+                                .on_disabled_hover_text("Cannot change renderer while emulator is running");
+                        });
+                    });
+                });
+
+                ui.add_enabled_ui(!self.state.emulator_is_running.load(Ordering::Relaxed), |ui| {
+                    ui.horizontal(|ui| {
+                        TextEdit::singleline(&mut self.config.sdl_video_driver)
+                            .hint_text("Default")
+                            .desired_width(80.0)
+                            .ui(ui)
+                            .on_hover_text("e.g. kmsdrm to run full-screen without X11 or Wayland; the SDL2 renderer is always used with kmsdrm")
+                            .on_disabled_hover_text("Cannot change SDL video driver while emulator is running");
+                        ui.label("SDL video driver");
+                    });
+                });
+
+                ui.group(|ui| {
+                    ui.add_enabled_ui(!self.state.emulator_is_running.load(Ordering::Relaxed), |ui| {
+                        ui.label("HiDPI scaling");
+                        ui.horizontal(|ui| {
+                            ui.radio_value(&mut self.config.hidpi_scaling, HiDpiScaling::Native, "Native pixels")
+                                .on_hover_text("Render at the display's full resolution")
+                                .on_disabled_hover_text("Cannot change HiDPI scaling while emulator is running");
+                            ui.radio_value(&mut self.config.hidpi_scaling, HiDpiScaling::Logical, "Logical size")
+                                .on_hover_text("Render at the window's logical size and let the OS scale it up")
+                                .on_disabled_hover_text("Cannot change HiDPI scaling while emulator is running");
+                        });
+                    });
+                });
+
+                ui.group(|ui| {
+                    ui.add_enabled_ui(!self.state.emulator_is_running.load(Ordering::Relaxed) && self.config.renderer == NativeRenderer::Wgpu, |ui| {
+                        let disabled_text = match self.config.renderer {
+                            NativeRenderer::Sdl2 => "Not applicable to SDL2 renderer",
+                            NativeRenderer::Wgpu => "Cannot change wgpu backend while emulator is running"
+                        };
+
+                        ui.label("wgpu backend");
+                        ui.horizontal(|ui| {
+                            ui.radio_value(&mut self.config.wgpu_backend, WgpuBackend::Auto, "Auto")
+                                .on_disabled_hover_text(disabled_text);
+                            ui.radio_value(&mut self.config.wgpu_backend, WgpuBackend::Vulkan, "Vulkan")
+                                .on_disabled_hover_text(disabled_text);
+                            ui.radio_value(&mut self.config.wgpu_backend, WgpuBackend::Direct3d12, "Direct3D 12")
+                                .on_disabled_hover_text(disabled_text);
+                            ui.radio_value(&mut self.config.wgpu_backend, WgpuBackend::OpenGl, "OpenGL")
+                                .on_disabled_hover_text(disabled_text);
+                        });
+
+                        if self.config.renderer == NativeRenderer::Wgpu
+                            && self.state.gpu_adapters.as_ref().is_none_or(|(backend, _)| *backend != self.config.wgpu_backend)
+                        {
+                            let backend = self.config.wgpu_backend;
+                            self.state.gpu_adapters = Some((backend, jgnes_renderer::enumerate_adapters(backend)));
+                        }
+                        let adapters = self.state.gpu_adapters.as_ref().map_or(&[][..], |(_, adapters)| adapters);
+
+                        let selected_text = match &self.config.gpu_adapter {
+                            None => "Default".into(),
+                            Some(selector) => selector.to_string(),
+                        };
+                        ComboBox::from_label("GPU adapter").selected_text(selected_text).show_ui(ui, |ui| {
+                            ui.selectable_value(&mut self.config.gpu_adapter, None, "Default")
+                                .on_hover_text("Let wgpu choose, preferring a high-performance GPU");
+                            for adapter in adapters {
+                                let selector = GpuAdapterSelector::Name(adapter.name.clone());
+                                ui.selectable_value(&mut self.config.gpu_adapter, Some(selector), adapter.to_string());
+                            }
+                        })
+                        .response
+                        .on_hover_text("Adapters that cannot render to the emulator window fall back to the default")
+                        .on_disabled_hover_text(disabled_text);
+                    });
+                });
+
+                ui.group(|ui| {
+                    ui.label("VSync mode");
+
+                    ui.horizontal(|ui| {
+                        ui.radio_value(&mut self.config.vsync_mode, VSyncMode::Enabled, "Enabled");
+                        ui.radio_value(&mut self.config.vsync_mode, VSyncMode::Disabled, "Disabled");
+
+                        ui.add_enabled_ui(self.config.renderer == NativeRenderer::Wgpu, |ui| {
+                            ui.radio_value(&mut self.config.vsync_mode, VSyncMode::Fast, "Fast")
+                                .on_disabled_hover_text("Fast VSync is only supported with the wgpu renderer");
+                            ui.radio_value(&mut self.config.vsync_mode, VSyncMode::Adaptive, "Adaptive")
+                                .on_hover_text("Present frames immediately; for variable refresh rate displays")
+                                .on_disabled_hover_text("Adaptive VSync is only supported with the wgpu renderer");
+                        });
+                    });
+                });
+
+                ui.group(|ui| {
+                    ui.label("Frame pacing");
+
+                    ui.horizontal(|ui| {
+                        ui.radio_value(&mut self.config.frame_pacing, FramePacing::Audio, "Audio / VSync");
+                        ui.radio_value(&mut self.config.frame_pacing, FramePacing::Vrr, "Variable refresh rate")
+                            .on_hover_text("Present each frame immediately and pace emulation to the NES's native ~60.1Hz with a timer; overrides VSync mode and audio sync");
+                    });
+                });
+
+                ui.group(|ui| {
+                    ui.label("Low power mode").on_hover_text(
+                        "Prefer the low-power GPU, always use VSync unless using variable refresh rate pacing, and check the audio queue less often; the GPU preference applies the next time the emulator starts",
+                    );
+
+                    ui.horizontal(|ui| {
+                        ui.radio_value(&mut self.config.low_power_mode, LowPowerMode::Off, "Off");
+                        ui.radio_value(&mut self.config.low_power_mode, LowPowerMode::OnBattery, "On battery");
+                        ui.radio_value(&mut self.config.low_power_mode, LowPowerMode::Always, "Always");
+                    });
+                });
+
+                if ui.button("Apply low-spec preset")
+                    .on_hover_text("Disable shaders, scanlines, CRT geometry, and frame blending for slow GPUs (wgpu renderer only)")
+                    .clicked()
+                {
+                    self.config.shader_type = ShaderType::None;
+                    self.config.shader_preset_path = None;
+                    self.config.scanlines = Scanlines::None;
+                    self.config.crt_geometry = CrtGeometry::default();
+                    self.config.frame_blend_strength = 0.0;
+                }
+
+                ui.group(|ui| {
+                    ui.label("Image filtering");
+                    ui.horizontal(|ui| {
+                        ui.radio_value(&mut self.config.gpu_filter_mode, GpuFilterMode::NearestNeighbor, "Nearest neighbor");
+                        ui.radio_value(&mut self.config.gpu_filter_mode, GpuFilterMode::LinearInterpolation, "Linear interpolation");
+                    });
+                });
+
+                ui.group(|ui| {
+                    ui.label("Shader");
+                    ui.horizontal(|ui| {
+                        ui.radio_value(&mut self.config.shader_type, ShaderType::None, "None");
+                        ui.radio_value(&mut self.config.shader_type, ShaderType::Prescale, "Prescale");
+                        ui.add_enabled_ui(self.config.renderer == NativeRenderer::Wgpu, |ui| {
+                            ui.radio_value(&mut self.config.shader_type, ShaderType::GaussianBlur, "Gaussian blur")
+                                .on_disabled_hover_text("Gaussian blur is not supported with SDL2 renderer");
+                        });
+                    });
+                });
+
+                ui.horizontal(|ui| {
+                    ui.add_enabled_ui([ShaderType::Prescale, ShaderType::GaussianBlur].contains(&self.config.shader_type), |ui| {
+                        let render_scale_edit = TextEdit::singleline(&mut self.state.shader.render_scale_text).desired_width(30.0).ui(ui);
+                        if !render_scale_edit.has_focus() {
+                            match RenderScale::try_from(self.state.shader.render_scale_text.parse::<u32>().unwrap_or(0)) {
+                                Ok(render_scale) => {
+                                    self.state.shader.render_scale_invalid = false;
+                                    self.config.render_scale = render_scale;
+                                }
+                                Err(_) => {
+                                    self.state.shader.render_scale_invalid = true;
+                                }
+                            }
+                        }
+                        let label = ui.label("Prescale factor")
+                            .on_hover_text("The image will be integer upscaled by this factor before filtering");
+                        render_scale_edit.labelled_by(label.id);
+
+                        ui.add_enabled_ui(self.config.renderer == NativeRenderer::Wgpu && self.config.shader_type == ShaderType::GaussianBlur, |ui| {
+                            let blur_stdev_edit = TextEdit::singleline(&mut self.state.shader.blur_stdev_text).desired_width(30.0).ui(ui);
+                            if !blur_stdev_edit.has_focus() {
+                                match self.state.shader.blur_stdev_text.parse::<f64>() {
+                                    Ok(blur_stdev) if !blur_stdev.is_nan() && !blur_stdev.is_sign_negative() => {
+                                        self.state.shader.blur_stdev_invalid = false;
+                                        self.config.blur_stdev = blur_stdev;
+                                    }
+                                    _ => {
+                                        self.state.shader.blur_stdev_invalid = true;
+                                    }
+                                }
+                            }
+                            let label = ui.label("Blur stdev");
+                            blur_stdev_edit.labelled_by(label.id);
+
+                            let text_edit = NumericTextInput::new(
+                                &mut self.state.shader.blur_radius_text,
+                                &mut self.config.blur_radius,
+                                &mut self.state.shader.blur_radius_invalid,
+                                1..=u32::MAX,
+                            )
+                                .desired_width(30.0)
+                                .ui(ui);
+                            let label = ui.label("Blur radius");
+                            text_edit.labelled_by(label.id);
+                        });
+                    });
+                });
+
+                if self.state.shader.render_scale_invalid {
+                    ui.colored_label(Color32::RED, "Scaling factor must be an integer between 1 and 16");
+                }
+                if self.state.shader.blur_stdev_invalid {
+                    ui.colored_label(Color32::RED, "Blur stdev must be a floating point number that is not negative or NaN");
+                }
+                if self.state.shader.blur_radius_invalid {
+                    ui.colored_label(Color32::RED, "Blur radius must be a non-negative integer");
+                }
+
+                ui.group(|ui| {
+                    ui.add_enabled_ui(self.config.renderer == NativeRenderer::Wgpu, |ui| {
+                        ui.label("Shader preset")
+                            .on_hover_text("Multi-pass shader preset TOML file; overrides the shader selected above")
+                            .on_disabled_hover_text("Shader presets are not supported with SDL2 renderer");
+                        ui.horizontal(|ui| {
+                            if ui.button("Load...").clicked() {
+                                self.load_shader_preset();
+                            }
+                            if ui.add_enabled(self.config.shader_preset_path.is_some(), Button::new("Clear")).clicked() {
+                                self.config.shader_preset_path = None;
+                                self.state.shader.preset_error = None;
+                            }
+                        });
+                        match &self.config.shader_preset_path {
+                            Some(shader_preset_path) => ui.label(format!("Preset file: {shader_preset_path}")),
+                            None => ui.label("No shader preset"),
+                        };
+                    });
+                });
+
+                if let Some(error) = &self.state.shader.preset_error {
+                    ui.colored_label(Color32::RED, error);
+                }
+
+                ui.group(|ui| {
+                    ui.add_enabled_ui(self.config.renderer == NativeRenderer::Wgpu, |ui| {
+                        let scanlines_hover_text = "Works best with integer height scaling";
+                        let disabled_hover_text = "Scanlines are not supported with SDL2 renderer";
+
+                        ui.label("Scanlines").on_disabled_hover_text(disabled_hover_text);
+                        ui.horizontal(|ui| {
+                            ui.radio_value(&mut self.config.scanlines, Scanlines::None, "None")
+                                .on_disabled_hover_text(disabled_hover_text);
+                            ui.radio_value(&mut self.config.scanlines, Scanlines::Dim, "Dim")
+                                .on_hover_text(scanlines_hover_text)
+                                .on_disabled_hover_text(disabled_hover_text);
+                            ui.radio_value(&mut self.config.scanlines, Scanlines::Black, "Black")
+                                .on_hover_text(scanlines_hover_text)
+                                .on_disabled_hover_text(disabled_hover_text);
+                        });
+
+                        ui.add_enabled_ui(self.config.scanlines != Scanlines::None, |ui| {
+                            ui.add(Slider::new(&mut self.config.scanline_intensity, 0.0..=1.0).text("Intensity"));
+                        });
+                    });
+                });
+
+                ui.group(|ui| {
+                    ui.add_enabled_ui(self.config.renderer == NativeRenderer::Wgpu, |ui| {
+                        let disabled_hover_text = "CRT geometry effects are not supported with SDL2 renderer";
+
+                        ui.label("CRT geometry").on_disabled_hover_text(disabled_hover_text);
+                        ui.add(Slider::new(&mut self.config.crt_geometry.curvature, 0.0..=1.0).text("Curvature"))
+                            .on_hover_text("Barrel distortion applied to the image")
+                            .on_disabled_hover_text(disabled_hover_text);
+                        ui.add(Slider::new(&mut self.config.crt_geometry.corner_rounding, 0.0..=1.0).text("Corner rounding"))
+                            .on_disabled_hover_text(disabled_hover_text);
+                        ui.add(Slider::new(&mut self.config.crt_geometry.vignette, 0.0..=1.0).text("Vignette"))
+                            .on_hover_text("Darken the image towards the edges")
+                            .on_disabled_hover_text(disabled_hover_text);
+                    });
+                });
+
+                ui.group(|ui| {
+                    ui.add_enabled_ui(self.config.renderer == NativeRenderer::Wgpu, |ui| {
+                        let disabled_hover_text = "Color-blindness filters are not supported with SDL2 renderer";
+
+                        ui.label("Color-blindness filter")
+                            .on_hover_text("Shift colors so that they are easier to tell apart")
+                            .on_disabled_hover_text(disabled_hover_text);
+                        ui.horizontal(|ui| {
+                            for (filter, label) in [
+                                (ColorBlindFilter::None, "None"),
+                                (ColorBlindFilter::Protanopia, "Protanopia"),
+                                (ColorBlindFilter::Deuteranopia, "Deuteranopia"),
+                                (ColorBlindFilter::Tritanopia, "Tritanopia"),
+                            ] {
+                                ui.radio_value(&mut self.config.color_blind_filter, filter, label)
+                                    .on_disabled_hover_text(disabled_hover_text);
+                            }
+                        });
+                    });
+                });
+
+                ui.group(|ui| {
+                    ui.add_enabled_ui(self.config.renderer == NativeRenderer::Wgpu, |ui| {
+                        let disabled_hover_text = "Frame blending is not supported with SDL2 renderer";
+
+                        ui.label("Frame blending")
+                            .on_hover_text("Blend each frame with the previous frame, like phosphor persistence on a CRT; smooths out flickering transparency effects")
+                            .on_disabled_hover_text(disabled_hover_text);
+                        ui.add(Slider::new(&mut self.config.frame_blend_strength, 0.0..=1.0).text("Strength"))
+                            .on_disabled_hover_text(disabled_hover_text);
+                    });
+                });
+
+                ui.group(|ui| {
+                    ui.label("Aspect ratio");
+                    ui.horizontal(|ui| {
+                        ui.radio_value(&mut self.config.aspect_ratio, AspectRatio::Ntsc, "NTSC")
+                            .on_hover_text("8:7 pixel aspect ratio, 64:49 screen aspect ratio");
+                        ui.radio_value(&mut self.config.aspect_ratio, AspectRatio::Pal, "PAL")
+                            .on_hover_text("11:8 pixel aspect ratio, 22:15 screen aspect ratio");
+                        ui.radio_value(&mut self.config.aspect_ratio, AspectRatio::SquarePixels, "Square pixels")
+                            .on_hover_text("1:1 pixel aspect ratio, 8:7 screen aspect ratio");
+                        ui.radio_value(&mut self.config.aspect_ratio, AspectRatio::FourThree, "4:3")
+                            .on_hover_text("7:6 pixel aspect ratio, 4:3 screen aspect ratio");
+                        ui.radio_value(&mut self.config.aspect_ratio, AspectRatio::Stretched, "Stretched")
+                            .on_hover_text("Image will be stretched to fill the entire display area");
+                    });
+                });
+
+                ui.with_layout(Layout::top_down(Align::LEFT), |ui| {
+                    ui.add_enabled_ui(self.config.aspect_ratio != AspectRatio::Stretched, |ui| {
+                        ui.checkbox(&mut self.config.forced_integer_height_scaling, "Forced integer scaling for height")
+                            .on_hover_text("Image height will always be the highest possible integer multiple of native (224px)")
+                            .on_disabled_hover_text("This option is not available in stretched image mode");
+                    });
+                });
+
+                ui.checkbox(&mut self.config.pal_black_border, "Emulate PAL black border")
+                    .on_hover_text("Removes top scanline plus two columns of pixels in each row");
+
+                ui.group(|ui| {
+                    ui.label("Overscan in pixels")
+                        .on_hover_text("Overscan changes made while a game is running only change the overscan for that game");
+
+                    let (overscan, rom_hash) = match running_game_settings_mut(&self.state.emulator_is_running, &mut self.state.running_emulator_state) {
+                        Some(GameSettings { rom_hash, overscan, .. }) => (overscan, Some(rom_hash.clone())),
+                        None => (&mut self.config.overscan, None),
+                    };
+                    if self.state.overscan.rom_hash != rom_hash {
+                        self.state.overscan = OverscanState::new(*overscan, rom_hash.clone());
+                    }
+
+                    ui.horizontal(|ui| {
+                        let current_preset = overscan.matching_preset();
+                        for (preset, label) in [
+                            (OverscanPreset::None, "None"),
+                            (OverscanPreset::TvSafe8px, "TV Safe 8px"),
+                            (OverscanPreset::FullNtscCrop, "Full NTSC crop"),
+                        ] {
+                            if ui.selectable_label(current_preset == Some(preset), label).clicked() {
+                                *overscan = preset.to_overscan();
+                                self.state.overscan = OverscanState::new(*overscan, rom_hash.clone());
+                            }
+                        }
+                    });
+
+                    ui.with_layout(Layout::top_down(Align::Center), |ui| {
+                        let label = ui.label("Top");
+                        NumericTextInput::new(
+                            &mut self.state.overscan.top_text,
+                            &mut overscan.top,
+                            &mut self.state.overscan.top_invalid,
+                            0..=OVERSCAN_VERTICAL_MAX,
+                        )
+                            .desired_width(40.0)
+                            .ui(ui)
+                            .labelled_by(label.id);
+                    });
+
+                    ui.horizontal(|ui| {
+                        let label = ui.label("Left");
+                        NumericTextInput::new(
+                            &mut self.state.overscan.left_text,
+                            &mut overscan.left,
+                            &mut self.state.overscan.left_invalid,
+                            0..=OVERSCAN_HORIZONTAL_MAX,
+                        )
+                            .desired_width(40.0)
+                            .ui(ui)
+                            .labelled_by(label.id);
+
+                        ui.with_layout(Layout::top_down(Align::RIGHT), |ui| {
+                            ui.horizontal(|ui| {
+                                let label = ui.label("Right");
+                                NumericTextInput::new(
+                                    &mut self.state.overscan.right_text,
+                                    &mut overscan.right,
+                                    &mut self.state.overscan.right_invalid,
+                                    0..=OVERSCAN_HORIZONTAL_MAX,
+                                )
+                                    .desired_width(40.0)
+                                    .ui(ui)
+                            .labelled_by(label.id);
+                            });
+                        });
+                    });
+
+                    ui.with_layout(Layout::top_down(Align::Center), |ui| {
+                        let text_edit = NumericTextInput::new(
+                            &mut self.state.overscan.bottom_text,
+                            &mut overscan.bottom,
+                            &mut self.state.overscan.bottom_invalid,
+                            0..=OVERSCAN_VERTICAL_MAX,
+                        )
+                            .desired_width(40.0)
+                            .ui(ui);
+                        let label = ui.label("Bottom");
+                        text_edit.labelled_by(label.id);
+                    });
+
+                    if self.state.overscan.invalid() {
+                        ui.colored_label(Color32::RED, "Overscan settings invalid; vertical must be 0-112 and horizontal must be 0-128");
+                    }
+                });
+            });
+        if !video_settings_open {
+            self.state.open_window = None;
+        }
+    }
+
+    fn render_audio_settings_window(&mut self, ctx: &Context) {
+        let mut audio_settings_open = true;
+        Window::new("Audio Settings")
+            .resizable(false)
+            .open(&mut audio_settings_open)
+            .show(ctx, |ui| {
+                ui.checkbox(
+                    &mut self.config.sync_to_audio,
+                    "Sync emulation speed to audio",
+                );
+
+                ui.checkbox(
+                    &mut self.config.audio_refresh_rate_adjustment,
+                    "Apply 60Hz refresh rate adjustment",
+                )
+                    .on_hover_text("Adjust audio downsampling rate to time audio to 60FPS instead of ~60.1FPS (NTSC)");
+
+                ui.checkbox(
+                    &mut self.config.silence_ultrasonic_triangle_output,
+                    "Silence triangle wave channel at ultrasonic frequencies",
+                )
+                .on_hover_text("This is less accurate but can reduce audio popping in some games");
+
+                ui.checkbox(
+                    &mut self.config.linear_apu_mixing,
+                    "Use linear APU mixing",
+                )
+                .on_hover_text("Approximate the NES's non-linear audio mixer with a linear one; this is less accurate, but some prefer the sound");
+
+                ui.checkbox(
+                    &mut self.config.ramp_triangle_silencing,
+                    "Ramp triangle wave channel volume",
+                )
+                .on_hover_text("Briefly fade the triangle wave channel in and out when it stops or starts to avoid audible clicks");
+
+                ui.checkbox(
+                    &mut self.config.dmc_declick_filter,
+                    "DMC declick filter",
+                )
+                .on_hover_text("Smooth out abrupt DMC level changes from direct $4011 writes; this reduces clicking in some games' sample playback but is less accurate");
+
+                ui.horizontal(|ui| {
+                    let text_edit = NumericTextInput::new(&mut self.state.audio_buffer_size_text, &mut self.config.internal_audio_buffer_size, &mut self.state.audio_buffer_size_invalid, 0..=u32::MAX)
+                        .desired_width(50.0)
+                        .ui(ui);
+                    let label = ui.label("Internal audio buffer size in samples");
+                    text_edit.labelled_by(label.id);
+                });
+                if self.state.audio_buffer_size_invalid {
+                    ui.colored_label(Color32::RED, "Audio buffer size must be a non-negative integer");
+                }
+
+                ui.horizontal(|ui| {
+                    let text_edit = NumericTextInput::new(&mut self.state.audio_sync_threshold_text, &mut self.config.audio_sync_threshold, &mut self.state.audio_sync_threshold_invalid, 0..=u32::MAX)
+                        .desired_width(50.0)
+                        .ui(ui);
+                    let label = ui.label("Audio sync threshold in bytes");
+                    text_edit.labelled_by(label.id);
+                });
+                if self.state.audio_sync_threshold_invalid {
+                    ui.colored_label(Color32::RED, "Audio sync threshold must be a non-negative integer");
+                }
+            });
+        if !audio_settings_open {
+            self.state.open_window = None;
+        }
+    }
+
+    fn render_input_settings_window(&mut self, ctx: &Context) {
+        let mut input_settings_open = true;
+        Window::new("Input Settings")
+            .resizable(false)
+            .open(&mut input_settings_open)
+            .show(ctx, |ui| {
+                ui.add_enabled_ui(self.state.open_input_window.is_none() && self.state.waiting_for_input.is_none(), |ui| {
+                    ui.horizontal(|ui| {
+                        if ui.button("P1 Keyboard Input").clicked() {
+                            self.state.open_input_window = Some(InputWindow(Player::P1, InputType::Keyboard));
+                        }
+
+                        ui.add_space(20.0);
+
+                        if ui.button("P1 Gamepad Input").clicked() {
+                            self.state.open_input_window = Some(InputWindow(Player::P1, InputType::Gamepad));
+                        }
+                    });
+
+                    ui.add_space(10.0);
+
+                    ui.horizontal(|ui| {
+                        if ui.button("P2 Keyboard Input").clicked() {
+                            self.state.open_input_window = Some(InputWindow(Player::P2, InputType::Keyboard));
+                        }
+
+                        ui.add_space(20.0);
+
+                        if ui.button("P2 Gamepad Input").clicked() {
+                            self.state.open_input_window = Some(InputWindow(Player::P2, InputType::Gamepad));
+                        }
+                    });
+
+                    ui.add_space(20.0);
+
+                    render_input_transforms(ui, "P1", &mut self.config.input.p1.transforms);
+                    render_input_transforms(ui, "P2", &mut self.config.input.p2.transforms);
+
+                    let axis_deadzone = self.config.input.axis_deadzone;
+                    render_analog_stick_settings(ui, "P1", &mut self.config.input.p1.analog_stick, axis_deadzone);
+                    render_analog_stick_settings(ui, "P2", &mut self.config.input.p2.analog_stick, axis_deadzone);
+
+                    ui.add_space(10.0);
+
+                    ui.checkbox(
+                        &mut self.config.input.allow_opposite_directions,
+                        "Allow simultaneous opposing directional inputs (left+right / up+down)",
+                    )
+                        .on_hover_text("Some games exhibit severe glitches when opposing directions are pressed simultaneously");
+
+                    ui.add_enabled_ui(!self.config.input.allow_opposite_directions, |ui| {
+                        ui.checkbox(
+                            &mut self.config.input.prefer_last_pressed_direction,
+                            "Prefer the last pressed direction when opposing directions are pressed",
+                        )
+                            .on_hover_text("Otherwise left and up are always preferred");
+                    });
+
+                    ui.horizontal(|ui| {
+                        let text_edit = NumericTextInput::new(
+                            &mut self.state.input.axis_deadzone_text,
+                            &mut self.config.input.axis_deadzone,
+                            &mut self.state.input.axis_deadzone_invalid,
+                            0..=i16::MAX as u16,
+                        )
+                            .desired_width(55.0)
+                            .ui(ui);
+                        let label = ui.label("Joystick axis deadzone (0-32767)");
+                        text_edit.labelled_by(label.id);
+                    });
+                    if self.state.input.axis_deadzone_invalid {
+                        ui.colored_label(
+                            Color32::RED,
+                            "Axis deadzone must be an integer between 0 and 32767",
+                        );
+                    }
+
+                    ui.add_space(10.0);
+                    ui.separator();
+
+                    self.render_input_profiles(ui);
+                });
+            });
+        if !input_settings_open {
+            self.state.open_window = None;
+            self.state.open_input_window = None;
+        }
+
+        self.render_input_subwindow(ctx);
+    }
+
+    fn render_input_profiles(&mut self, ui: &mut Ui) {
+        ui.heading("Profiles");
+
+        ui.horizontal(|ui| {
+            TextEdit::singleline(&mut self.state.input.profile_name_text)
+                .desired_width(150.0)
+                .hint_text("Profile name")
+                .ui(ui);
+
+            let profile_name = self.state.input.profile_name_text.trim().to_string();
+            if ui
+                .add_enabled(!profile_name.is_empty(), Button::new("Save current as profile"))
+                .clicked()
+            {
+                let profile = InputProfile::from_input_config(profile_name, &self.config.input);
+                self.save_input_profile(profile);
+                self.state.input.profile_name_text.clear();
+            }
+
+            if ui.button("Import").clicked() {
+                self.handle_import_input_profile();
+            }
+        });
+
+        let mut action = None;
+        Grid::new("input_profiles_grid").show(ui, |ui| {
+            for (i, profile) in self.config.input_profiles.iter().enumerate() {
+                match &mut self.state.input.renaming_profile {
+                    Some((renaming_idx, rename_text)) if *renaming_idx == i => {
+                        TextEdit::singleline(rename_text).desired_width(150.0).ui(ui);
+
+                        if ui.button("Confirm").clicked() {
+                            action = Some(InputProfileAction::ConfirmRename(i));
+                        }
+
+                        if ui.button("Cancel").clicked() {
+                            action = Some(InputProfileAction::CancelRename);
+                        }
+                    }
+                    _ => {
+                        ui.label(&profile.name);
+
+                        if ui.button("Load").clicked() {
+                            action = Some(InputProfileAction::Load(i));
+                        }
+
+                        if ui.button("Rename").clicked() {
+                            action = Some(InputProfileAction::StartRename(i));
+                        }
+
+                        if ui.button("Export").clicked() {
+                            action = Some(InputProfileAction::Export(i));
+                        }
+
+                        if ui.button("Delete").clicked() {
+                            action = Some(InputProfileAction::Delete(i));
+                        }
+                    }
+                }
+
+                ui.end_row();
+            }
+        });
+
+        if let Some(action) = action {
+            self.handle_input_profile_action(action);
+        }
+
+        if let Some(profile_error) = &self.state.input.profile_error {
+            ui.colored_label(Color32::RED, profile_error);
+        }
+    }
+
+    fn handle_input_profile_action(&mut self, action: InputProfileAction) {
+        self.state.input.profile_error = None;
+
+        match action {
+            InputProfileAction::Load(i) => {
+                self.config.input_profiles[i].apply_to(&mut self.config.input);
+            }
+            InputProfileAction::StartRename(i) => {
+                let name = self.config.input_profiles[i].name.clone();
+                self.state.input.renaming_profile = Some((i, name));
+            }
+            InputProfileAction::ConfirmRename(i) => {
+                let Some((_, rename_text)) = &self.state.input.renaming_profile else {
+                    return;
+                };
+
+                let new_name = rename_text.trim();
+                if new_name.is_empty() {
+                    self.state.input.profile_error = Some("Profile name cannot be empty".into());
+                } else if self
+                    .config
+                    .input_profiles
+                    .iter()
+                    .enumerate()
+                    .any(|(j, profile)| j != i && profile.name == new_name)
+                {
+                    self.state.input.profile_error =
+                        Some(format!("A profile named '{new_name}' already exists"));
+                } else {
+                    self.config.input_profiles[i].name = new_name.into();
+                    self.state.input.renaming_profile = None;
+                }
+            }
+            InputProfileAction::CancelRename => {
+                self.state.input.renaming_profile = None;
+            }
+            InputProfileAction::Export(i) => {
+                let profile = &self.config.input_profiles[i];
+                let file = FileDialog::new()
+                    .add_filter("toml", &["toml"])
+                    .set_file_name(format!("{}.toml", profile.name))
+                    .save_file();
+                if let Some(file) = file {
+                    if let Err(err) = export_input_profile(profile, &file) {
+                        log::error!("Error exporting input profile to '{}': {err}", file.display());
+                        self.state.input.profile_error =
+                            Some(format!("Error exporting profile: {err}"));
+                    }
+                }
+            }
+            InputProfileAction::Delete(i) => {
+                self.config.input_profiles.remove(i);
+                self.state.input.renaming_profile = None;
+            }
+        }
+    }
+
+    fn handle_import_input_profile(&mut self) {
+        self.state.input.profile_error = None;
+
+        let Some(file) = FileDialog::new().add_filter("toml", &["toml"]).pick_file() else {
+            return;
+        };
+
+        match import_input_profile(&file) {
+            Ok(profile) => {
+                self.save_input_profile(profile);
+            }
+            Err(err) => {
+                log::error!("Error importing input profile from '{}': {err}", file.display());
+                self.state.input.profile_error = Some(format!("Error importing profile: {err}"));
+            }
+        }
+    }
+
+    // Profiles are keyed by name; saving a profile with an existing name overwrites it
+    fn save_input_profile(&mut self, profile: InputProfile) {
+        match self.config.input_profiles.iter_mut().find(|existing| existing.name == profile.name) {
+            Some(existing) => {
+                *existing = profile;
+            }
+            None => {
+                self.config.input_profiles.push(profile);
+            }
+        }
+    }
+
+    fn render_input_subwindow(&mut self, ctx: &Context) {
+        let Some(InputWindow(player, input_type)) = self.state.open_input_window else {
+            return;
+        };
+
+        let window_title = format!("{player:?} {input_type:?} Configuration");
+
+        let mut input_subwindow_open = true;
+        Window::new(&window_title).resizable(false).open(&mut input_subwindow_open).show(
+            ctx,
+            |ui| {
+                ui.add_enabled_ui(self.state.waiting_for_input.is_none(), |ui| {
+                    Grid::new(format!("{player:?}_{input_type:?}")).show(ui, |ui| {
+                        for nes_button in NesButton::ALL {
+                            let label = ui.label(format!("{nes_button:?}:"));
+                            InputButton::new(player, input_type, nes_button, self)
+                                .ui(ui)
+                                .labelled_by(label.id);
+
+                            if ui.button("Clear").clicked() {
+                                match input_type {
+                                    InputType::Keyboard => {
+                                        *get_keyboard_field(
+                                            &mut self.config.input,
+                                            player,
+                                            nes_button,
+                                        ) = None;
+                                    }
+                                    InputType::Gamepad => {
+                                        *get_joystick_field(
+                                            &mut self.config.input,
+                                            player,
+                                            nes_button,
+                                        ) = None;
+                                    }
+                                }
+                            }
+
+                            ui.end_row();
+                        }
+                    });
+                });
             },
         );
-        if !general_settings_open {
-            self.state.open_window = None;
+        if !input_subwindow_open {
+            self.state.open_input_window = None;
         }
     }
 
-    fn render_video_settings_window(&mut self, ctx: &Context) {
-        let mut video_settings_open = true;
-        Window::new("Video Settings")
-            .resizable(false)
-            .open(&mut video_settings_open)
-            .show(ctx, |ui| {
-                ui.checkbox(&mut self.config.launch_fullscreen, "Launch in fullscreen");
+    fn render_hotkey_settings_window(&mut self, ctx: &Context) {
+        let mut hotkey_settings_open = true;
+        Window::new("Hotkey Settings").resizable(false).open(&mut hotkey_settings_open).show(
+            ctx,
+            |ui| {
+                Grid::new("hotkey_settings_grid").show(ui, |ui| {
+                    for &hotkey in Hotkey::ALL {
+                        let label = ui.label(format!("{}:", hotkey.label()));
+
+                        HotkeyButton::new(hotkey, self).ui(ui).labelled_by(label.id);
+
+                        if ui.button("Clear").clicked() {
+                            *get_hotkey_field(&mut self.config.input.hotkeys, hotkey) = None;
+                        }
+
+                        ui.end_row();
+                    }
+                });
 
                 ui.horizontal(|ui| {
-                    NumericTextInput::new(
-                        &mut self.state.window_width_text,
-                        &mut self.config.window_width,
-                        &mut self.state.window_width_invalid,
-                        1..=u32::MAX,
+                    let text_edit = NumericTextInput::new(
+                        &mut self.state.input.ff_multiplier_text,
+                        &mut self.config.fast_forward_multiplier,
+                        &mut self.state.input.ff_multiplier_invalid,
+                        2..=16,
                     )
-                        .desired_width(60.0)
-                        .ui(ui);
-                    ui.label("Window width in pixels");
+                    .desired_width(40.0)
+                    .ui(ui);
+                    let label = ui.label("Fast forward multiplier");
+                    text_edit.labelled_by(label.id);
                 });
-                if self.state.window_width_invalid {
-                    ui.colored_label(Color32::RED, "Window width must be a non-negative integer");
+                if self.state.input.ff_multiplier_invalid {
+                    ui.colored_label(
+                        Color32::RED,
+                        "Fast forward multiplier must be an integer between 2 and 16",
+                    );
                 }
 
                 ui.horizontal(|ui| {
-                    NumericTextInput::new(
-                        &mut self.state.window_height_text,
-                        &mut self.config.window_height,
-                        &mut self.state.window_height_invalid,
-                        1..=u32::MAX
+                    let text_edit = NumericTextInput::new(
+                        &mut self.state.input.rewind_buffer_len_text,
+                        &mut self.config.rewind_buffer_len_secs,
+                        &mut self.state.input.rewind_buffer_len_invalid,
+                        0..=u64::MAX,
                     )
-                        .desired_width(60.0)
-                        .ui(ui);
-                    ui.label("Window height in pixels");
+                    .desired_width(40.0)
+                    .ui(ui);
+                    let label = ui.label("Rewind buffer length in seconds");
+                    text_edit.labelled_by(label.id);
                 });
-                if self.state.window_height_invalid {
-                    ui.colored_label(Color32::RED, "Window height must be a non-negative integer");
+                if self.state.input.rewind_buffer_len_invalid {
+                    ui.colored_label(
+                        Color32::RED,
+                        "Rewind buffer length must be a non-negative integer",
+                    );
                 }
 
-                ui.group(|ui| {
-                    ui.add_enabled_ui(!self.state.emulator_is_running.load(Ordering::Relaxed), |ui| {
-                        ui.label("Renderer");
-                        ui.horizontal(|ui| {
-                            ui.radio_value(&mut self.config.renderer, NativeRenderer::Wgpu, "wgpu")
-                                .on_disabled_hover_text("Cannot change renderer while emulator is running");
-                            ui.radio_value(&mut self.config.renderer, NativeRenderer::Sdl2, "SDL2")
-                                .on_disabled_hover_text("Cannot change renderer while emulator is running");
-                        });
-                    });
+                ui.horizontal(|ui| {
+                    let text_edit = NumericTextInput::new(
+                        &mut self.state.input.rewind_interval_text,
+                        &mut self.config.rewind_interval_frames,
+                        &mut self.state.input.rewind_interval_invalid,
+                        1..=60,
+                    )
+                    .desired_width(40.0)
+                    .ui(ui);
+                    let label = ui
+                        .label("Frames between rewind snapshots")
+                        .on_hover_text("Higher values use less memory but rewind in larger steps");
+                    text_edit.labelled_by(label.id);
                 });
+                if self.state.input.rewind_interval_invalid {
+                    ui.colored_label(
+                        Color32::RED,
+                        "Frames between rewind snapshots must be an integer between 1 and 60",
+                    );
+                }
 
-                ui.group(|ui| {
-                    ui.add_enabled_ui(!self.state.emulator_is_running.load(Ordering::Relaxed) && self.config.renderer == NativeRenderer::Wgpu, |ui| {
-                        let disabled_text = match self.config.renderer {
-                            NativeRenderer::Sdl2 => "Not applicable to SDL2 renderer",
-                            NativeRenderer::Wgpu => "Cannot change wgpu backend while emulator is running"
-                        };
-
-                        ui.label("wgpu backend");
-                        ui.horizontal(|ui| {
-                            ui.radio_value(&mut self.config.wgpu_backend, WgpuBackend::Auto, "Auto")
-                                .on_disabled_hover_text(disabled_text);
-                            ui.radio_value(&mut self.config.wgpu_backend, WgpuBackend::Vulkan, "Vulkan")
-                                .on_disabled_hover_text(disabled_text);
-                            ui.radio_value(&mut self.config.wgpu_backend, WgpuBackend::Direct3d12, "Direct3D 12")
-                                .on_disabled_hover_text(disabled_text);
-                            ui.radio_value(&mut self.config.wgpu_backend, WgpuBackend::OpenGl, "OpenGL")
-                                .on_disabled_hover_text(disabled_text);
-                        });
-                    });
+                ui.horizontal(|ui| {
+                    let text_edit = NumericTextInput::new(
+                        &mut self.state.input.rewind_memory_limit_text,
+                        &mut self.config.rewind_memory_limit_mb,
+                        &mut self.state.input.rewind_memory_limit_invalid,
+                        0..=u32::MAX,
+                    )
+                    .desired_width(40.0)
+                    .ui(ui);
+                    let label = ui.label("Rewind memory limit in MB").on_hover_text(
+                        "The oldest snapshots are dropped past this limit; 0 for no limit",
+                    );
+                    text_edit.labelled_by(label.id);
                 });
+                if self.state.input.rewind_memory_limit_invalid {
+                    ui.colored_label(
+                        Color32::RED,
+                        "Rewind memory limit must be a non-negative integer",
+                    );
+                }
 
-                ui.group(|ui| {
-                    ui.label("VSync mode");
+                if let Some(status) = self.running_emulator_status() {
+                    ui.label(format!(
+                        "Rewind buffer usage: {:.1} MB ({:.1} seconds)",
+                        status.rewind_buffer_size as f64 / (1024.0 * 1024.0),
+                        status.rewind_buffer_secs
+                    ));
+                    // Keep the usage current while the emulator is running
+                    ui.ctx().request_repaint_after(Duration::from_millis(500));
+                }
 
-                    ui.add_enabled_ui(self.config.renderer == NativeRenderer::Wgpu || !self.state.emulator_is_running.load(Ordering::Relaxed), |ui| {
-                        ui.horizontal(|ui| {
-                            ui.radio_value(&mut self.config.vsync_mode, VSyncMode::Enabled, "Enabled")
-                                .on_disabled_hover_text("SDL2 renderer cannot change VSync mode while running");
-                            ui.radio_value(&mut self.config.vsync_mode, VSyncMode::Disabled, "Disabled")
-                                .on_disabled_hover_text("SDL2 renderer cannot change VSync mode while running");
-
-                            ui.add_enabled_ui(self.config.renderer == NativeRenderer::Wgpu, |ui| {
-                                ui.radio_value(&mut self.config.vsync_mode, VSyncMode::Fast, "Fast")
-                                    .on_disabled_hover_text("Fast VSync is only supported with the wgpu renderer");
-                            });
-                        });
-                    });
+                ui.horizontal(|ui| {
+                    let text_edit = NumericTextInput::new(
+                        &mut self.state.input.clip_len_text,
+                        &mut self.config.clip_len_secs,
+                        &mut self.state.input.clip_len_invalid,
+                        1..=u64::MAX,
+                    )
+                    .desired_width(40.0)
+                    .ui(ui);
+                    let label = ui
+                        .label("Clip length in seconds")
+                        .on_hover_text("Clips can be no longer than the rewind buffer");
+                    text_edit.labelled_by(label.id);
                 });
+                if self.state.input.clip_len_invalid {
+                    ui.colored_label(Color32::RED, "Clip length must be a positive integer");
+                }
 
-                ui.group(|ui| {
-                    ui.add_enabled_ui(self.config.renderer == NativeRenderer::Wgpu, |ui| {
-                        let disabled_hover_text = "Only nearest neighbor sampling is supported with SDL2 renderer";
-                        ui.label("Image filtering")
-                            .on_disabled_hover_text(disabled_hover_text);
-                        ui.horizontal(|ui| {
-                            ui.radio_value(&mut self.config.gpu_filter_mode, GpuFilterMode::NearestNeighbor, "Nearest neighbor")
-                                .on_disabled_hover_text(disabled_hover_text);
-                            ui.radio_value(&mut self.config.gpu_filter_mode, GpuFilterMode::LinearInterpolation, "Linear interpolation")
-                                .on_disabled_hover_text(disabled_hover_text);
-                        });
-                    });
+                ui.horizontal(|ui| {
+                    let text_edit = NumericTextInput::new(
+                        &mut self.state.input.capture_len_text,
+                        &mut self.config.capture_len_secs,
+                        &mut self.state.input.capture_len_invalid,
+                        1..=60,
+                    )
+                    .desired_width(40.0)
+                    .ui(ui);
+                    let label = ui.label("Capture length in seconds");
+                    text_edit.labelled_by(label.id);
                 });
+                if self.state.input.capture_len_invalid {
+                    ui.colored_label(
+                        Color32::RED,
+                        "Capture length must be an integer between 1 and 60",
+                    );
+                }
 
-                ui.group(|ui| {
-                    ui.add_enabled_ui(self.config.renderer == NativeRenderer::Wgpu, |ui| {
-                        let disabled_hover_text = "Shaders are not supported with SDL2 renderer";
-                        ui.label("Shader").on_disabled_hover_text(disabled_hover_text);
-                        ui.horizontal(|ui| {
-                            ui.radio_value(&mut self.config.shader_type, ShaderType::None, "None").on_disabled_hover_text(disabled_hover_text);
-                            ui.radio_value(&mut self.config.shader_type, ShaderType::Prescale, "Prescale").on_disabled_hover_text(disabled_hover_text);
-                            ui.radio_value(&mut self.config.shader_type, ShaderType::GaussianBlur, "Gaussian blur").on_disabled_hover_text(disabled_hover_text);
-                        });
-                    });
+                ui.horizontal(|ui| {
+                    ui.label("Capture format:");
+                    ui.radio_value(&mut self.config.capture_format, CaptureFormat::Gif, "GIF");
+                    ui.radio_value(&mut self.config.capture_format, CaptureFormat::Apng, "APNG");
                 });
+            },
+        );
+        if !hotkey_settings_open {
+            self.state.open_window = None;
+        }
+    }
 
-                ui.horizontal(|ui| {
-                    ui.add_enabled_ui(self.config.renderer == NativeRenderer::Wgpu && [ShaderType::Prescale, ShaderType::GaussianBlur].contains(&self.config.shader_type), |ui| {
-                        if !TextEdit::singleline(&mut self.state.shader.render_scale_text).desired_width(30.0).ui(ui).has_focus() {
-                            match RenderScale::try_from(self.state.shader.render_scale_text.parse::<u32>().unwrap_or(0)) {
-                                Ok(render_scale) => {
-                                    self.state.shader.render_scale_invalid = false;
-                                    self.config.render_scale = render_scale;
-                                }
-                                Err(_) => {
-                                    self.state.shader.render_scale_invalid = true;
-                                }
+    fn render_palette_editor_window(&mut self, ctx: &Context) {
+        let mut palette_editor_open = true;
+        let mut palette_changed = false;
+        let mut palette_action = None;
+        Window::new("Palette Editor").resizable(false).open(&mut palette_editor_open).show(
+            ctx,
+            |ui| {
+                let palette_state = &mut self.state.palette;
+
+                Grid::new("palette_grid").spacing([2.0, 2.0]).show(ui, |ui| {
+                    for row in 0..PALETTE_GRID_ROWS {
+                        for col in 0..PALETTE_GRID_COLS {
+                            let index = row * PALETTE_GRID_COLS + col;
+                            let [r, g, b] = palette_state.palette.color(index);
+
+                            let mut swatch = Button::new("")
+                                .fill(Color32::from_rgb(r, g, b))
+                                .min_size(Vec2::new(PALETTE_SWATCH_SIZE, PALETTE_SWATCH_SIZE));
+                            if index == palette_state.selected_color {
+                                swatch = swatch.stroke((2.0, Color32::WHITE));
                             }
-                        }
-                        ui.label("Prescale factor")
-                            .on_hover_text("The image will be integer upscaled by this factor before filtering");
 
-                        ui.add_enabled_ui(self.config.shader_type == ShaderType::GaussianBlur, |ui| {
-                            if !TextEdit::singleline(&mut self.state.shader.blur_stdev_text).desired_width(30.0).ui(ui).has_focus() {
-                                match self.state.shader.blur_stdev_text.parse::<f64>() {
-                                    Ok(blur_stdev) if !blur_stdev.is_nan() && !blur_stdev.is_sign_negative() => {
-                                        self.state.shader.blur_stdev_invalid = false;
-                                        self.config.blur_stdev = blur_stdev;
-                                    }
-                                    _ => {
-                                        self.state.shader.blur_stdev_invalid = true;
-                                    }
-                                }
+                            let response = swatch.ui(ui).on_hover_text(format!("${index:02X}"));
+                            // Swatches have no text, so give screen readers something to announce
+                            response.widget_info(|| {
+                                WidgetInfo::selected(
+                                    WidgetType::Button,
+                                    true,
+                                    index == palette_state.selected_color,
+                                    format!("Palette color ${index:02X}"),
+                                )
+                            });
+                            if response.clicked() {
+                                palette_state.selected_color = index;
                             }
-                            ui.label("Blur stdev");
+                        }
+                        ui.end_row();
+                    }
+                });
 
-                            NumericTextInput::new(
-                                &mut self.state.shader.blur_radius_text,
-                                &mut self.config.blur_radius,
-                                &mut self.state.shader.blur_radius_invalid,
-                                1..=u32::MAX,
-                            )
-                                .desired_width(30.0)
-                                .ui(ui);
-                            ui.label("Blur radius");
-                        });
-                    });
+                ui.add_space(10.0);
+
+                ui.horizontal(|ui| {
+                    let selected_color = palette_state.selected_color;
+                    let mut rgb = palette_state.palette.color(selected_color);
+                    if egui::color_picker::color_edit_button_srgb(ui, &mut rgb).changed() {
+                        palette_state.palette.set_color(selected_color, rgb);
+                        palette_state.modified = true;
+                        palette_changed = true;
+                    }
+
+                    let [r, g, b] = rgb;
+                    ui.label(format!("${selected_color:02X}: #{r:02X}{g:02X}{b:02X}"));
                 });
 
-                if self.state.shader.render_scale_invalid {
-                    ui.colored_label(Color32::RED, "Scaling factor must be an integer between 1 and 16");
-                }
-                if self.state.shader.blur_stdev_invalid {
-                    ui.colored_label(Color32::RED, "Blur stdev must be a floating point number that is not negative or NaN");
+                ui.add_space(10.0);
+
+                ui.horizontal(|ui| {
+                    if ui.button("Load .pal").clicked() {
+                        palette_action = Some(PaletteAction::Load);
+                    }
+
+                    if ui.button("Save .pal").clicked() {
+                        palette_action = Some(PaletteAction::Save);
+                    }
+
+                    if ui.button("Reset to default").clicked() {
+                        palette_action = Some(PaletteAction::Reset);
+                    }
+                });
+
+                match &self.config.palette_path {
+                    Some(palette_path) => ui.label(format!("Palette file: {palette_path}")),
+                    None => ui.label("Using built-in palette"),
+                };
+
+                if palette_state.modified {
+                    ui.label("Palette has unsaved changes, which will be lost on exit");
+                }
+
+                if let Some(error) = &palette_state.error {
+                    ui.colored_label(Color32::RED, error);
+                }
+            },
+        );
+        if !palette_editor_open {
+            self.state.open_window = None;
+        }
+
+        if let Some(palette_action) = palette_action {
+            palette_changed |= self.handle_palette_action(palette_action);
+        }
+
+        // Palette edits are not part of the persisted config, so push them to a running emulator
+        // explicitly for live preview
+        if palette_changed && self.state.emulator_is_running.load(Ordering::Relaxed) {
+            self.update_running_emulator_config();
+        }
+    }
+
+    fn load_shader_preset(&mut self) {
+        let Some(path) = FileDialog::new().add_filter("toml", &["toml"]).pick_file() else {
+            return;
+        };
+
+        // Validate the preset up front so that errors show up here instead of only in the log
+        match load_shader_preset(&path) {
+            Ok(_) => {
+                self.config.shader_preset_path = Some(path.to_string_lossy().to_string());
+                self.state.shader.preset_error = None;
+            }
+            Err(err) => {
+                log::error!("Error loading shader preset from '{}': {err}", path.display());
+                self.state.shader.preset_error =
+                    Some(format!("Error loading shader preset: {err}"));
+            }
+        }
+    }
+
+    // Returns whether the in-use palette changed
+    fn handle_palette_action(&mut self, action: PaletteAction) -> bool {
+        let palette_state = &mut self.state.palette;
+        palette_state.error = None;
+
+        match action {
+            PaletteAction::Load => {
+                let Some(path) = FileDialog::new().add_filter("pal", &["pal"]).pick_file() else {
+                    return false;
+                };
+
+                match load_palette(&path) {
+                    Ok(palette) => {
+                        palette_state.palette = palette;
+                        palette_state.modified = false;
+                        self.config.palette_path = Some(path.to_string_lossy().to_string());
+                        true
+                    }
+                    Err(err) => {
+                        log::error!("Error loading palette from '{}': {err}", path.display());
+                        palette_state.error = Some(format!("Error loading palette: {err}"));
+                        false
+                    }
                 }
-                if self.state.shader.blur_radius_invalid {
-                    ui.colored_label(Color32::RED, "Blur radius must be a non-negative integer");
+            }
+            PaletteAction::Save => {
+                let Some(path) = FileDialog::new().add_filter("pal", &["pal"]).save_file() else {
+                    return false;
+                };
+
+                match fs::write(&path, palette_state.palette.to_pal_bytes()) {
+                    Ok(()) => {
+                        palette_state.modified = false;
+                        self.config.palette_path = Some(path.to_string_lossy().to_string());
+                    }
+                    Err(err) => {
+                        log::error!("Error saving palette to '{}': {err}", path.display());
+                        palette_state.error = Some(format!("Error saving palette: {err}"));
+                    }
                 }
 
-                ui.group(|ui| {
-                    ui.add_enabled_ui(self.config.renderer == NativeRenderer::Wgpu, |ui| {
-                        let scanlines_hover_text = "Works best with integer height scaling";
-                        let disabled_hover_text = "Scanlines are not supported with SDL2 renderer";
+                false
+            }
+            PaletteAction::Reset => {
+                palette_state.palette = Palette::default();
+                palette_state.modified = false;
+                self.config.palette_path = None;
+                true
+            }
+        }
+    }
 
-                        ui.label("Scanlines").on_disabled_hover_text(disabled_hover_text);
-                        ui.horizontal(|ui| {
-                            ui.radio_value(&mut self.config.scanlines, Scanlines::None, "None")
-                                .on_disabled_hover_text(disabled_hover_text);
-                            ui.radio_value(&mut self.config.scanlines, Scanlines::Dim, "Dim")
-                                .on_hover_text(scanlines_hover_text)
-                                .on_disabled_hover_text(disabled_hover_text);
-                            ui.radio_value(&mut self.config.scanlines, Scanlines::Black, "Black")
-                                .on_hover_text(scanlines_hover_text)
-                                .on_disabled_hover_text(disabled_hover_text);
-                        });
-                    });
+    fn render_interface_settings_window(&mut self, ctx: &Context) {
+        let mut interface_settings_open = true;
+        Window::new("Interface Settings").resizable(false).open(&mut interface_settings_open).show(
+            ctx,
+            |ui| {
+                ui.horizontal(|ui| {
+                    let slider = ui.add(
+                        Slider::new(&mut self.config.ui_scale, UI_SCALE_RANGE)
+                            .step_by(0.05)
+                            .fixed_decimals(2),
+                    );
+                    let label = ui.label("UI scale");
+                    slider.labelled_by(label.id);
                 });
 
                 ui.group(|ui| {
-                    ui.label("Aspect ratio");
+                    ui.label("Theme");
+
                     ui.horizontal(|ui| {
-                        ui.radio_value(&mut self.config.aspect_ratio, AspectRatio::Ntsc, "NTSC")
-                            .on_hover_text("8:7 pixel aspect ratio, 64:49 screen aspect ratio");
-                        ui.radio_value(&mut self.config.aspect_ratio, AspectRatio::Pal, "PAL")
-                            .on_hover_text("11:8 pixel aspect ratio, 22:15 screen aspect ratio");
-                        ui.radio_value(&mut self.config.aspect_ratio, AspectRatio::SquarePixels, "Square pixels")
-                            .on_hover_text("1:1 pixel aspect ratio, 8:7 screen aspect ratio");
-                        ui.radio_value(&mut self.config.aspect_ratio, AspectRatio::FourThree, "4:3")
-                            .on_hover_text("7:6 pixel aspect ratio, 4:3 screen aspect ratio");
-                        ui.radio_value(&mut self.config.aspect_ratio, AspectRatio::Stretched, "Stretched")
-                            .on_hover_text("Image will be stretched to fill the entire display area");
+                        ui.radio_value(&mut self.config.ui_theme, UiTheme::System, "System");
+                        ui.radio_value(&mut self.config.ui_theme, UiTheme::Dark, "Dark");
+                        ui.radio_value(&mut self.config.ui_theme, UiTheme::Light, "Light");
+                        ui.radio_value(
+                            &mut self.config.ui_theme,
+                            UiTheme::HighContrast,
+                            "High contrast",
+                        );
                     });
-                });
 
-                ui.with_layout(Layout::top_down(Align::LEFT), |ui| {
-                    ui.add_enabled_ui(self.config.aspect_ratio != AspectRatio::Stretched, |ui| {
-                        ui.checkbox(&mut self.config.forced_integer_height_scaling, "Forced integer scaling for height")
-                            .on_hover_text("Image height will always be the highest possible integer multiple of native (224px)")
-                            .on_disabled_hover_text("This option is not available in stretched image mode");
+                    ui.horizontal(|ui| {
+                        let mut custom_accent = self.config.accent_color.is_some();
+                        if ui.checkbox(&mut custom_accent, "Custom accent color").changed() {
+                            self.config.accent_color =
+                                custom_accent.then_some(DEFAULT_ACCENT_COLOR);
+                        }
+
+                        if let Some(accent_color) = &mut self.config.accent_color {
+                            ui.color_edit_button_srgb(accent_color);
+                        }
                     });
                 });
 
-                ui.checkbox(&mut self.config.pal_black_border, "Emulate PAL black border")
-                    .on_hover_text("Removes top scanline plus two columns of pixels in each row");
+                ui.checkbox(&mut self.config.large_hit_targets, "Larger buttons and spacing");
 
-                ui.group(|ui| {
-                    ui.label("Overscan in pixels");
+                ui.checkbox(
+                    &mut self.config.pause_while_settings_open,
+                    "Pause emulation while a settings window is open",
+                )
+                .on_hover_text(
+                    "Emulation resumes when the settings window is closed or the launcher loses focus",
+                );
 
-                    ui.with_layout(Layout::top_down(Align::Center), |ui| {
-                        ui.label("Top");
-                        NumericTextInput::new(
-                            &mut self.state.overscan.top_text,
-                            &mut self.config.overscan.top,
-                            &mut self.state.overscan.top_invalid,
-                            0..=OVERSCAN_VERTICAL_MAX,
-                        )
-                            .desired_width(40.0)
-                            .ui(ui);
-                    });
+                if ui.button("Reset to defaults").clicked() {
+                    self.config.ui_scale = default_ui_scale();
+                    self.config.ui_theme = UiTheme::default();
+                    self.config.accent_color = None;
+                    self.config.large_hit_targets = false;
+                }
+            },
+        );
+        if !interface_settings_open {
+            self.state.open_window = None;
+        }
+    }
 
-                    ui.horizontal(|ui| {
-                        ui.label("Left");
-                        NumericTextInput::new(
-                            &mut self.state.overscan.left_text,
-                            &mut self.config.overscan.left,
-                            &mut self.state.overscan.left_invalid,
-                            0..=OVERSCAN_HORIZONTAL_MAX,
-                        )
-                            .desired_width(40.0)
-                            .ui(ui);
+    // Settings windows that change how the game plays, where inputs should not reach the game
+    fn settings_window_open(&self) -> bool {
+        self.state.open_input_window.is_some()
+            || self.state.open_window.is_some_and(|open_window| {
+                OpenWindow::SETTINGS.iter().any(|&(window, _, _)| window == open_window)
+            })
+    }
 
-                        ui.with_layout(Layout::top_down(Align::RIGHT), |ui| {
-                            ui.horizontal(|ui| {
-                                ui.label("Right");
-                                NumericTextInput::new(
-                                    &mut self.state.overscan.right_text,
-                                    &mut self.config.overscan.right,
-                                    &mut self.state.overscan.right_invalid,
-                                    0..=OVERSCAN_HORIZONTAL_MAX,
-                                )
-                                    .desired_width(40.0)
-                                    .ui(ui);
-                            });
-                        });
-                    });
+    fn update_settings_pause(&mut self, ctx: &Context) {
+        if !self.state.emulator_is_running.load(Ordering::Relaxed) {
+            return;
+        }
 
-                    ui.with_layout(Layout::top_down(Align::Center), |ui| {
-                        NumericTextInput::new(
-                            &mut self.state.overscan.bottom_text,
-                            &mut self.config.overscan.bottom,
-                            &mut self.state.overscan.bottom_invalid,
-                            0..=OVERSCAN_VERTICAL_MAX,
-                        )
-                            .desired_width(40.0)
-                            .ui(ui);
-                        ui.label("Bottom");
-                    });
+        let focused = ctx.input(|input| input.viewport().focused.unwrap_or(true));
+        let should_pause =
+            self.config.pause_while_settings_open && focused && self.settings_window_open();
 
-                    if self.state.overscan.invalid() {
-                        ui.colored_label(Color32::RED, "Overscan settings invalid; vertical must be 0-112 and horizontal must be 0-128");
-                    }
-                });
-            });
-        if !video_settings_open {
-            self.state.open_window = None;
+        let Some(running_emulator_state) = &mut self.state.running_emulator_state else {
+            return;
+        };
+        if running_emulator_state.settings_paused != should_pause {
+            running_emulator_state.shared_config.set_frontend_paused(should_pause);
+            running_emulator_state.settings_paused = should_pause;
         }
     }
 
-    fn render_audio_settings_window(&mut self, ctx: &Context) {
-        let mut audio_settings_open = true;
-        Window::new("Audio Settings")
-            .resizable(false)
-            .open(&mut audio_settings_open)
-            .show(ctx, |ui| {
-                ui.checkbox(
-                    &mut self.config.sync_to_audio,
-                    "Sync emulation speed to audio",
-                );
+    fn apply_appearance(&mut self, ctx: &Context) {
+        // Wait until the scale slider is released; rescaling mid-drag moves the slider
+        if ctx.is_using_pointer() {
+            return;
+        }
 
-                ui.checkbox(
-                    &mut self.config.audio_refresh_rate_adjustment,
-                    "Apply 60Hz refresh rate adjustment",
-                )
-                    .on_hover_text("Adjust audio downsampling rate to time audio to 60FPS instead of ~60.1FPS (NTSC)");
+        let mut appearance = self.config.appearance();
+        appearance.system_theme = ctx.system_theme();
+        appearance.ui_scale =
+            appearance.ui_scale.clamp(*UI_SCALE_RANGE.start(), *UI_SCALE_RANGE.end());
+        if self.state.applied_appearance != Some(appearance) {
+            appearance.apply(ctx);
+            self.state.applied_appearance = Some(appearance);
+        }
+    }
 
-                ui.checkbox(
-                    &mut self.config.silence_ultrasonic_triangle_output,
-                    "Silence triangle wave channel at ultrasonic frequencies",
-                )
-                .on_hover_text("This is less accurate but can reduce audio popping in some games");
+    fn render_log_window(&mut self, ctx: &Context) {
+        let mut log_open = true;
+        Window::new("Log").default_width(700.0).open(&mut log_open).show(ctx, |ui| {
+            CollapsingHeader::new("Subsystem log levels").show(ui, |ui| {
+                Grid::new("log_subsystem_levels").show(ui, |ui| {
+                    for subsystem in LogSubsystem::ALL {
+                        ui.label(subsystem.to_string());
+
+                        let current_level = jgnes_native_driver::subsystem_log_level(subsystem);
+                        let mut level = current_level;
+                        for option in LevelFilter::iter() {
+                            ui.radio_value(&mut level, option, option.to_string());
+                        }
+                        if level != current_level {
+                            jgnes_native_driver::set_subsystem_log_level(subsystem, level);
+                        }
 
-                ui.horizontal(|ui| {
-                    NumericTextInput::new(&mut self.state.audio_buffer_size_text, &mut self.config.internal_audio_buffer_size, &mut self.state.audio_buffer_size_invalid, 0..=u32::MAX)
-                        .desired_width(50.0)
-                        .ui(ui);
-                    ui.label("Internal audio buffer size in samples");
+                        ui.end_row();
+                    }
                 });
-                if self.state.audio_buffer_size_invalid {
-                    ui.colored_label(Color32::RED, "Audio buffer size must be a non-negative integer");
+            });
+
+            ui.checkbox(&mut self.config.log_sprite_0_hits, "Log sprite 0 hits").on_hover_text(
+                "Log the scanline and dot every time the PPU sets the sprite 0 hit flag; these are \
+                 logged at info level under the PPU subsystem",
+            );
+
+            ui.horizontal(|ui| {
+                ui.label("Show:");
+                for level in Level::iter() {
+                    ui.radio_value(&mut self.state.log_display_level, level, level.to_string());
                 }
+            });
 
-                ui.horizontal(|ui| {
-                    NumericTextInput::new(&mut self.state.audio_sync_threshold_text, &mut self.config.audio_sync_threshold, &mut self.state.audio_sync_threshold_invalid, 0..=u32::MAX)
-                        .desired_width(50.0)
-                        .ui(ui);
-                    ui.label("Audio sync threshold in bytes");
-                });
-                if self.state.audio_sync_threshold_invalid {
-                    ui.colored_label(Color32::RED, "Audio sync threshold must be a non-negative integer");
+            ui.separator();
+
+            ScrollArea::both().stick_to_bottom(true).max_height(400.0).show(ui, |ui| {
+                let log_lines = jgnes_native_driver::recent_log_lines();
+                for line in
+                    log_lines.iter().filter(|line| line.level <= self.state.log_display_level)
+                {
+                    let text = format!("{:<5} {}: {}", line.level, line.target, line.message);
+                    let color = match line.level {
+                        Level::Error => Color32::from_rgb(255, 90, 90),
+                        Level::Warn => Color32::from_rgb(255, 200, 60),
+                        Level::Info | Level::Debug | Level::Trace => ui.visuals().text_color(),
+                    };
+                    ui.label(RichText::new(text).monospace().color(color));
                 }
             });
-        if !audio_settings_open {
+        });
+        if !log_open {
             self.state.open_window = None;
+        } else {
+            // Keep tailing new log lines even when there is no user input
+            ctx.request_repaint_after(Duration::from_millis(250));
         }
     }
 
-    fn render_input_settings_window(&mut self, ctx: &Context) {
-        let mut input_settings_open = true;
-        Window::new("Input Settings")
-            .resizable(false)
-            .open(&mut input_settings_open)
-            .show(ctx, |ui| {
-                ui.add_enabled_ui(self.state.open_input_window.is_none() && self.state.waiting_for_input.is_none(), |ui| {
-                    ui.horizontal(|ui| {
-                        if ui.button("P1 Keyboard Input").clicked() {
-                            self.state.open_input_window = Some(InputWindow(Player::P1, InputType::Keyboard));
-                        }
+    fn running_emulator_status(&self) -> Option<EmulatorStatus> {
+        if !self.state.emulator_is_running.load(Ordering::Relaxed) {
+            return None;
+        }
 
-                        ui.add_space(20.0);
+        Some(self.state.running_emulator_state.as_ref()?.shared_config.status())
+    }
 
-                        if ui.button("P1 Gamepad Input").clicked() {
-                            self.state.open_input_window = Some(InputWindow(Player::P1, InputType::Gamepad));
-                        }
-                    });
+    fn running_cartridge_info(&self) -> Option<(String, CartridgeInfo)> {
+        let status = self.running_emulator_status()?;
+        Some((status.rom_file_name?, status.cartridge_info?))
+    }
 
-                    ui.add_space(10.0);
+    fn check_timing_mode_mismatch(&mut self) {
+        if !self.config.warn_on_timing_mode_mismatch || self.state.timing_mode_warning.is_some() {
+            return;
+        }
 
-                    ui.horizontal(|ui| {
-                        if ui.button("P2 Keyboard Input").clicked() {
-                            self.state.open_input_window = Some(InputWindow(Player::P2, InputType::Keyboard));
-                        }
+        let Some((rom_file_name, cartridge_info)) = self.running_cartridge_info() else {
+            return;
+        };
+        if !self.state.timing_mode_checked_roms.insert(rom_file_name.clone()) {
+            return;
+        }
 
-                        ui.add_space(20.0);
+        if let Some(header_timing_mode) = cartridge_info.timing_mode_mismatch() {
+            self.state.timing_mode_warning = Some(TimingModeWarning {
+                rom_file_name,
+                header_timing_mode,
+                timing_mode: cartridge_info.timing_mode,
+            });
+        }
+    }
 
-                        if ui.button("P2 Gamepad Input").clicked() {
-                            self.state.open_input_window = Some(InputWindow(Player::P2, InputType::Gamepad));
-                        }
-                    });
+    fn render_error_pause_window(&mut self, ctx: &Context, error_pause: &str) {
+        let Some(running_emulator_state) = &self.state.running_emulator_state else {
+            return;
+        };
 
-                    ui.add_space(20.0);
+        let mut stop = false;
+        Window::new("Emulation Paused").collapsible(false).resizable(false).show(ctx, |ui| {
+            ui.label("The emulator paused itself because of an error:");
+            ui.label(RichText::new(error_pause).color(Color32::from_rgb(255, 90, 90)));
 
-                    ui.checkbox(
-                        &mut self.config.input.allow_opposite_directions,
-                        "Allow simultaneous opposing directional inputs (left+right / up+down)",
-                    )
-                        .on_hover_text("Some games exhibit severe glitches when opposing directions are pressed simultaneously");
+            ui.add_space(5.0);
 
-                    ui.horizontal(|ui| {
-                        NumericTextInput::new(
-                            &mut self.state.input.axis_deadzone_text,
-                            &mut self.config.input.axis_deadzone,
-                            &mut self.state.input.axis_deadzone_invalid,
-                            0..=i16::MAX as u16,
-                        )
-                            .desired_width(55.0)
-                            .ui(ui);
-                        ui.label("Joystick axis deadzone (0-32767)");
-                    });
-                    if self.state.input.axis_deadzone_invalid {
-                        ui.colored_label(
-                            Color32::RED,
-                            "Axis deadzone must be an integer between 0 and 32767",
-                        );
+            ui.label(
+                "The game can keep running, but if saving failed then progress may not be saved \
+                 until the problem is fixed.",
+            );
+
+            ui.add_space(5.0);
+
+            ui.horizontal(|ui| {
+                if ui.button("Resume").clicked() {
+                    running_emulator_state.shared_config.request_error_resume();
+                }
+
+                if ui.button("Step Frame").clicked() {
+                    running_emulator_state.shared_config.request_frame_step();
+                }
+
+                if ui.button("Stop Emulator").clicked() {
+                    stop = true;
+                }
+            });
+        });
+
+        if stop {
+            self.state.stop_emulator_if_running();
+        }
+    }
+
+    fn render_quit_confirmation_window(&mut self, ctx: &Context) {
+        let mut confirmation_open = true;
+        let mut quit = false;
+        let mut cancelled = false;
+        Window::new("Quit jgnes?")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(Align2::CENTER_CENTER, Vec2::ZERO)
+            .open(&mut confirmation_open)
+            .show(ctx, |ui| {
+                ui.label("A game is still running. Quitting will close it.");
+
+                ui.add_space(5.0);
+
+                ui.horizontal(|ui| {
+                    let quit_button = ui.button("Quit");
+                    if !quit_button.has_focus() && ui.memory(|memory| memory.focused().is_none()) {
+                        quit_button.request_focus();
+                    }
+                    if quit_button.clicked() {
+                        quit = true;
+                    }
+
+                    if ui.button("Cancel").clicked() {
+                        cancelled = true;
                     }
                 });
             });
-        if !input_settings_open {
-            self.state.open_window = None;
-            self.state.open_input_window = None;
+
+        if quit {
+            self.state.quit_confirmed = true;
+            ctx.send_viewport_cmd(ViewportCommand::Close);
+        }
+        if quit || cancelled || !confirmation_open {
+            self.state.quit_confirmation_open = false;
         }
-
-        self.render_input_subwindow(ctx);
     }
 
-    fn render_input_subwindow(&mut self, ctx: &Context) {
-        let Some(InputWindow(player, input_type)) = self.state.open_input_window else {
+    fn render_timing_mode_warning_window(&mut self, ctx: &Context) {
+        let Some(warning) = self.state.timing_mode_warning.clone() else {
             return;
         };
 
-        let window_title = format!("{player:?} {input_type:?} Configuration");
-
-        let mut input_subwindow_open = true;
-        Window::new(&window_title).resizable(false).open(&mut input_subwindow_open).show(
+        let mut warning_open = true;
+        let mut switch_timing_mode = false;
+        let mut dismissed = false;
+        Window::new("Timing Mode Mismatch").resizable(false).open(&mut warning_open).show(
             ctx,
             |ui| {
-                ui.add_enabled_ui(self.state.waiting_for_input.is_none(), |ui| {
-                    Grid::new(format!("{player:?}_{input_type:?}")).show(ui, |ui| {
-                        for nes_button in NesButton::ALL {
-                            ui.label(format!("{nes_button:?}:"));
-                            InputButton::new(player, input_type, nes_button, self).ui(ui);
+                ui.label(format!(
+                    "'{}' is a {} game but is running in {} mode because of the forced timing \
+                     mode setting, so it will run at the wrong speed.",
+                    warning.rom_file_name, warning.header_timing_mode, warning.timing_mode
+                ));
 
-                            if ui.button("Clear").clicked() {
-                                match input_type {
-                                    InputType::Keyboard => {
-                                        *get_keyboard_field(
-                                            &mut self.config.input,
-                                            player,
-                                            nes_button,
-                                        ) = None;
-                                    }
-                                    InputType::Gamepad => {
-                                        *get_joystick_field(
-                                            &mut self.config.input,
-                                            player,
-                                            nes_button,
-                                        ) = None;
-                                    }
-                                }
-                            }
+                ui.add_space(5.0);
 
-                            ui.end_row();
-                        }
-                    });
+                ui.horizontal(|ui| {
+                    if ui.button(format!("Switch to {}", warning.header_timing_mode)).clicked() {
+                        switch_timing_mode = true;
+                    }
+
+                    if ui.button("Ignore").clicked() {
+                        dismissed = true;
+                    }
                 });
+
+                ui.checkbox(&mut self.config.warn_on_timing_mode_mismatch, "Show this warning");
             },
         );
-        if !input_subwindow_open {
-            self.state.open_input_window = None;
+
+        if switch_timing_mode {
+            // Clearing the forced timing mode switches to the header's timing mode; the emulator
+            // has to be restarted because the timing mode cannot change while it is running
+            self.config.forced_timing_mode = None;
+            if let Some(running_emulator_state) = &self.state.running_emulator_state {
+                let rom_path = running_emulator_state.rom_path.clone();
+                let launch_options = running_emulator_state.launch_options.clone();
+
+                self.state.stop_emulator_if_running();
+                self.launch_emulator_with_options(rom_path, launch_options);
+            }
+        }
+
+        if !warning_open || switch_timing_mode || dismissed {
+            self.state.timing_mode_warning = None;
         }
     }
 
-    fn render_hotkey_settings_window(&mut self, ctx: &Context) {
-        let mut hotkey_settings_open = true;
-        Window::new("Hotkey Settings").resizable(false).open(&mut hotkey_settings_open).show(
+    fn render_game_properties_window(&mut self, ctx: &Context) {
+        let cartridge_info = self.running_cartridge_info();
+
+        let mut properties_open = true;
+        Window::new("Game Properties").resizable(false).open(&mut properties_open).show(
             ctx,
             |ui| {
-                Grid::new("hotkey_settings_grid").show(ui, |ui| {
-                    for &hotkey in Hotkey::ALL {
-                        ui.label(format!("{}:", hotkey.label()));
+                let Some((file_name, info)) = cartridge_info else {
+                    ui.label("No game is running");
+                    return;
+                };
+
+                Grid::new("game_properties_grid").num_columns(2).show(ui, |ui| {
+                    let size_kb = |size: u32| format!("{} KB", size / 1024);
+                    let rows = [
+                        ("File", file_name),
+                        ("Mapper", format!("{} ({})", info.mapper_number, info.mapper_name)),
+                        ("Region", info.timing_mode.to_string()),
+                        ("PRG ROM", size_kb(info.prg_rom_size)),
+                        ("PRG RAM", size_kb(info.prg_ram_size)),
+                        ("CHR ROM", size_kb(info.chr_rom_size)),
+                        ("CHR RAM", size_kb(info.chr_ram_size)),
+                        ("Mirroring", info.mirroring.to_string()),
+                        ("Battery", if info.has_battery { "Yes" } else { "No" }.into()),
+                    ];
+                    for (label, value) in rows {
+                        ui.label(label);
+                        ui.label(value);
+                        ui.end_row();
+                    }
+                });
+            },
+        );
+        if !properties_open {
+            self.state.open_window = None;
+        } else {
+            // The running game can change from the emulator window
+            ctx.request_repaint_after(Duration::from_millis(250));
+        }
+    }
 
-                        HotkeyButton::new(hotkey, self).ui(ui);
+    fn update_window_title(&mut self, ctx: &Context) {
+        let title = match self.running_cartridge_info() {
+            Some((file_name, info)) => format!("jgnes - {file_name} [{info}]"),
+            None => "jgnes".into(),
+        };
+        if self.state.applied_window_title.as_ref() != Some(&title) {
+            ctx.send_viewport_cmd(ViewportCommand::Title(title.clone()));
+            self.state.applied_window_title = Some(title);
+        }
 
-                        if ui.button("Clear").clicked() {
-                            *get_hotkey_field(&mut self.config.input.hotkeys, hotkey) = None;
+        // The emulator only reports the cartridge once it starts running, and the game can change
+        // from the emulator window
+        if self.state.emulator_is_running.load(Ordering::Relaxed) {
+            ctx.request_repaint_after(Duration::from_secs(1));
+        }
+    }
+
+    fn open_command_palette(&mut self) {
+        self.state.command_palette = CommandPaletteState::default();
+        self.state.open_window = Some(OpenWindow::CommandPalette);
+        self.state.open_input_window = None;
+    }
+
+    fn launcher_commands(&self) -> Vec<(LauncherCommand, String, Option<KeyboardShortcut>)> {
+        let open_shortcut = KeyboardShortcut::new(Modifiers::CTRL, Key::O);
+        let quit_shortcut = KeyboardShortcut::new(Modifiers::CTRL, Key::Q);
+
+        let mut commands = vec![
+            (LauncherCommand::Open, "Open ROM...".into(), Some(open_shortcut)),
+            (LauncherCommand::OpenWithPatch, "Open ROM with Patch...".into(), None),
+            (LauncherCommand::OpenPlaylist, "Open Playlist...".into(), None),
+            (LauncherCommand::OpenInNewWindow, "Open ROM in New Window...".into(), None),
+        ];
+
+        if let Some(last_rom_path) = &self.config.last_rom_path {
+            let file_name = Path::new(last_rom_path)
+                .file_stem()
+                .map_or_else(|| last_rom_path.clone(), |stem| stem.to_string_lossy().to_string());
+            commands.push((
+                LauncherCommand::LaunchLastGame,
+                format!("Launch Last Game ({file_name})"),
+                None,
+            ));
+        }
+
+        for (window, label, key) in OpenWindow::SETTINGS {
+            commands.push((
+                LauncherCommand::OpenWindow(window),
+                format!("{label} Settings"),
+                Some(KeyboardShortcut::new(Modifiers::CTRL, key)),
+            ));
+        }
+
+        commands.extend([
+            (LauncherCommand::BigPicture, "Big Picture Mode".into(), None),
+            (LauncherCommand::OpenWindow(OpenWindow::Log), "Log".into(), None),
+            (
+                LauncherCommand::OpenWindow(OpenWindow::GameProperties),
+                "Game Properties".into(),
+                None,
+            ),
+            (
+                LauncherCommand::OpenWindow(OpenWindow::KeyboardShortcuts),
+                "Keyboard Shortcuts".into(),
+                None,
+            ),
+            (LauncherCommand::OpenWindow(OpenWindow::About), "About".into(), None),
+            (LauncherCommand::Quit, "Quit".into(), Some(quit_shortcut)),
+        ]);
+
+        commands
+    }
+
+    fn run_launcher_command(&mut self, ctx: &Context, command: LauncherCommand) {
+        match command {
+            LauncherCommand::Open => self.handle_open(),
+            LauncherCommand::OpenWithPatch => self.handle_open_with_patch(),
+            LauncherCommand::OpenPlaylist => self.handle_open_playlist(),
+            LauncherCommand::OpenInNewWindow => self.handle_open_in_new_window(),
+            LauncherCommand::LaunchLastGame => {
+                if let Some(last_rom_path) = self.config.last_rom_path.clone() {
+                    self.launch_or_switch_emulator(last_rom_path);
+                }
+            }
+            LauncherCommand::OpenWindow(window) => {
+                self.state.open_window = Some(window);
+            }
+            LauncherCommand::BigPicture => self.enter_big_picture(),
+            LauncherCommand::Quit => ctx.send_viewport_cmd(ViewportCommand::Close),
+        }
+    }
+
+    fn render_command_palette_window(&mut self, ctx: &Context) {
+        let filter_id = Id::new("command_palette_filter");
+
+        let commands: Vec<_> = self
+            .launcher_commands()
+            .into_iter()
+            .filter(|(_, label, _)| {
+                command_matches_filter(label, &self.state.command_palette.filter)
+            })
+            .collect();
+
+        let palette = &mut self.state.command_palette;
+        palette.selected = palette.selected.min(commands.len().saturating_sub(1));
+
+        // While typing in the filter, arrow keys move the selection and Enter runs the selected
+        // command; once Tab moves focus to a command button, Enter activates that button instead
+        let filter_focused =
+            ctx.memory(|memory| memory.has_focus(filter_id) || memory.focused().is_none());
+        let (up, down, enter) = if filter_focused {
+            ctx.input_mut(|input| {
+                (
+                    input.consume_key(Modifiers::NONE, Key::ArrowUp),
+                    input.consume_key(Modifiers::NONE, Key::ArrowDown),
+                    input.consume_key(Modifiers::NONE, Key::Enter),
+                )
+            })
+        } else {
+            (false, false, false)
+        };
+        if up {
+            palette.selected = palette.selected.saturating_sub(1);
+        }
+        if down && palette.selected + 1 < commands.len() {
+            palette.selected += 1;
+        }
+
+        let mut chosen =
+            commands.get(palette.selected).filter(|_| enter).map(|&(command, ..)| command);
+
+        let mut palette_open = true;
+        Window::new("Command Palette")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(Align2::CENTER_TOP, Vec2::new(0.0, 50.0))
+            .open(&mut palette_open)
+            .show(ctx, |ui| {
+                let filter_response = TextEdit::singleline(&mut palette.filter)
+                    .id(filter_id)
+                    .hint_text("Type a command")
+                    .desired_width(350.0)
+                    .ui(ui);
+                if !palette.focus_requested {
+                    filter_response.request_focus();
+                    palette.focus_requested = true;
+                }
+                if filter_response.changed() {
+                    palette.selected = 0;
+                }
+
+                ui.separator();
+
+                ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                    if commands.is_empty() {
+                        ui.label("No matching commands");
+                    }
+
+                    for (i, (command, label, shortcut)) in commands.iter().enumerate() {
+                        let mut button = Button::new(label)
+                            .selected(i == palette.selected)
+                            .min_size(Vec2::new(350.0, 0.0));
+                        if let Some(shortcut) = shortcut {
+                            button = button.shortcut_text(ctx.format_shortcut(shortcut));
                         }
 
-                        ui.end_row();
+                        let response = button.ui(ui);
+                        if i == palette.selected && (up || down) {
+                            response.scroll_to_me(None);
+                        }
+                        if response.clicked() {
+                            chosen = Some(*command);
+                        }
                     }
                 });
+            });
 
-                ui.horizontal(|ui| {
-                    NumericTextInput::new(
-                        &mut self.state.input.ff_multiplier_text,
-                        &mut self.config.fast_forward_multiplier,
-                        &mut self.state.input.ff_multiplier_invalid,
-                        2..=16,
-                    )
-                    .desired_width(40.0)
-                    .ui(ui);
-                    ui.label("Fast forward multiplier");
-                });
-                if self.state.input.ff_multiplier_invalid {
-                    ui.colored_label(
-                        Color32::RED,
-                        "Fast forward multiplier must be an integer between 2 and 16",
-                    );
-                }
+        if !palette_open {
+            self.state.open_window = None;
+        }
 
-                ui.horizontal(|ui| {
-                    NumericTextInput::new(
-                        &mut self.state.input.rewind_buffer_len_text,
-                        &mut self.config.rewind_buffer_len_secs,
-                        &mut self.state.input.rewind_buffer_len_invalid,
-                        0..=u64::MAX,
-                    )
-                    .desired_width(40.0)
-                    .ui(ui);
-                    ui.label("Rewind buffer length in seconds");
+        if let Some(command) = chosen {
+            self.state.open_window = None;
+            self.run_launcher_command(ctx, command);
+        }
+    }
+
+    fn render_keyboard_shortcuts_window(&mut self, ctx: &Context) {
+        let mut shortcuts: Vec<(String, String)> = vec![
+            (
+                ctx.format_shortcut(&KeyboardShortcut::new(Modifiers::CTRL, Key::O)),
+                "Open ROM".into(),
+            ),
+            (ctx.format_shortcut(&COMMAND_PALETTE_SHORTCUT), "Command palette".into()),
+        ];
+        for (_, label, key) in OpenWindow::SETTINGS {
+            shortcuts.push((
+                ctx.format_shortcut(&KeyboardShortcut::new(Modifiers::CTRL, key)),
+                format!("{label} settings"),
+            ));
+        }
+        shortcuts.extend([
+            (ctx.format_shortcut(&KeyboardShortcut::new(Modifiers::CTRL, Key::Q)), "Quit".into()),
+            ("Escape".into(), "Close the topmost window".into()),
+            ("Tab / Shift+Tab".into(), "Move to the next / previous control".into()),
+            ("Enter / Space".into(), "Activate the focused control".into()),
+            ("Up / Down".into(), "Select a command in the command palette".into()),
+        ]);
+
+        let mut shortcuts_open = true;
+        Window::new("Keyboard Shortcuts").resizable(false).open(&mut shortcuts_open).show(
+            ctx,
+            |ui| {
+                Grid::new("keyboard_shortcuts_grid").striped(true).show(ui, |ui| {
+                    for (shortcut, description) in shortcuts {
+                        ui.label(RichText::new(shortcut).strong());
+                        ui.label(description);
+                        ui.end_row();
+                    }
                 });
-                if self.state.input.rewind_buffer_len_invalid {
-                    ui.colored_label(
-                        Color32::RED,
-                        "Rewind buffer length must be a non-negative integer",
-                    );
-                }
             },
         );
-        if !hotkey_settings_open {
+        if !shortcuts_open {
             self.state.open_window = None;
         }
     }
@@ -1391,6 +4634,20 @@ impl App {
                 ui.label("Source code:");
                 ui.hyperlink("https://github.com/jsgroth/jgnes");
             });
+
+            ui.add_space(15.0);
+
+            ui.heading("System Info");
+
+            ui.add_space(5.0);
+
+            ui.label(format!("OS: {} ({})", std::env::consts::OS, std::env::consts::ARCH));
+            ui.label(format!("Renderer: {}", self.config.renderer));
+            let gpu_adapter = self.running_emulator_status().and_then(|status| status.gpu_adapter);
+            ui.label(format!(
+                "GPU adapter: {}",
+                gpu_adapter.as_deref().unwrap_or("Not in use; shown while running with wgpu")
+            ));
         });
         if !about_open {
             self.state.open_window = None;
@@ -1438,7 +4695,7 @@ impl App {
             return;
         };
 
-        match romlist::get_rom_list(rom_search_dir) {
+        match romlist::get_rom_list(rom_search_dir, &rom_cache_path(&self.config_path)) {
             Ok(mut rom_list) => {
                 rom_list.sort_by(|a, b| a.file_name_no_ext.cmp(&b.file_name_no_ext));
                 self.state.rom_list = rom_list;
@@ -1450,19 +4707,118 @@ impl App {
     }
 }
 
-fn load_config(path: &PathBuf) -> Result<AppConfig, anyhow::Error> {
+/// Run a single emulator using the settings in the given config file, without the GUI. This is
+/// how additional emulator windows are run, in child processes of the GUI.
+///
+/// # Errors
+///
+/// This function will return an error if the config file cannot be loaded or if the emulator
+/// encounters a fatal error.
+pub fn run_instance(
+    config_path: &Path,
+    rom_path: &Path,
+    data_paths: &DataPaths,
+) -> Result<(), anyhow::Error> {
+    let config = load_config(config_path)?;
+    let palette = PaletteEditorState::new(config.palette_path.as_deref()).palette;
+
+    let game_settings = fs::read(rom_path)
+        .ok()
+        .map(|rom_bytes| config.game_settings(romlist::rom_hash(&rom_bytes)));
+
+    let (mut native_config, _) = config.to_jgnes_native_config(
+        rom_path.to_string_lossy().into_owned(),
+        &palette,
+        game_settings.as_ref(),
+    );
+    // The GUI's own emulator may already be listening on the remote control port
+    native_config.remote_control_port = None;
+    native_config.last_run_path = Some(last_run_path(config_path));
+    native_config.save_dir = data_paths.save_dir();
+
+    instances::quit_on_stdin_close(native_config.shared_config.clone());
+    jgnes_native_driver::run(&native_config)
+}
+
+fn launcher_monitor_key(viewport: &egui::ViewportInfo) -> Option<String> {
+    viewport.monitor_size.map(|size| format!("{}x{}", size.x.round(), size.y.round()))
+}
+
+// Downloaded cover art is cached next to the config file
+fn cover_art_dir(config_path: &Path) -> PathBuf {
+    config_path.with_file_name("jgnes-covers")
+}
+
+// The ROM list metadata cache is saved next to the config file
+fn rom_cache_path(config_path: &Path) -> PathBuf {
+    config_path.with_file_name("jgnes-rom-cache.toml")
+}
+
+// Speedrun timer runs are saved next to the config file
+fn last_run_path(config_path: &Path) -> PathBuf {
+    config_path.with_file_name("jgnes-last-run.json")
+}
+
+fn load_config(path: &Path) -> Result<AppConfig, anyhow::Error> {
     let config_str = fs::read_to_string(path)?;
     Ok(toml::from_str(&config_str)?)
 }
 
+fn export_input_profile(profile: &InputProfile, path: &Path) -> Result<(), anyhow::Error> {
+    let profile_str = toml::to_string(profile)?;
+    fs::write(path, profile_str)?;
+    Ok(())
+}
+
+fn import_input_profile(path: &Path) -> Result<InputProfile, anyhow::Error> {
+    let profile_str = fs::read_to_string(path)?;
+    let profile: InputProfile = toml::from_str(&profile_str)?;
+    profile.validate().map_err(anyhow::Error::msg)?;
+    Ok(profile)
+}
+
+fn load_palette(path: &Path) -> anyhow::Result<Palette> {
+    let palette_bytes = fs::read(path)?;
+    Ok(Palette::from_pal_bytes(&palette_bytes)?)
+}
+
 impl eframe::App for App {
     fn update(&mut self, ctx: &Context, _frame: &mut Frame) {
         let prev_config = self.config.clone();
+        let prev_game_settings = self.running_game_settings().cloned();
+
+        self.apply_appearance(ctx);
+        self.update_window_title(ctx);
 
         self.poll_for_input_thread_result();
+        self.poll_for_handoffs(ctx);
+        self.update_direct_launch(ctx);
+        self.state.instances.reap();
+
+        self.restore_launcher_geometry(ctx);
+        let mut closing = ctx.input(|input| input.viewport().close_requested());
+        if closing
+            && self.config.confirm_quit
+            && !self.state.quit_confirmed
+            && self.state.emulator_is_running.load(Ordering::Relaxed)
+        {
+            ctx.send_viewport_cmd(ViewportCommand::CancelClose);
+            self.state.quit_confirmation_open = true;
+            closing = false;
+        }
+        if closing {
+            self.save_launcher_geometry(ctx);
+        }
+        // Geometry is only saved once the emulator stops, since config changes while it is
+        // running are sent to the emulator
+        if closing || !self.state.emulator_is_running.load(Ordering::Relaxed) {
+            self.save_emulator_geometry();
+        }
 
-        if self.state.emulation_error.lock().unwrap().is_some() {
+        if !self.state.error_window_open && self.state.emulation_error.lock().unwrap().is_some() {
             self.state.error_window_open = true;
+            self.state.crash_report = self.capture_crash_report();
+            self.state.crash_report_status = None;
         }
 
         let open_shortcut = KeyboardShortcut::new(Modifiers::CTRL, Key::O);
@@ -1475,103 +4831,58 @@ impl eframe::App for App {
             ctx.send_viewport_cmd(ViewportCommand::Close);
         }
 
-        TopBottomPanel::new(TopBottomSide::Top, "top_bottom_panel").show(ctx, |ui| {
-            ui.add_enabled_ui(!self.state.is_any_window_open(), |ui| {
-                menu::bar(ui, |ui| {
-                    ui.menu_button("File", |ui| {
-                        let open_button = Button::new("Open")
-                            .shortcut_text(ctx.format_shortcut(&open_shortcut))
-                            .ui(ui);
-                        if open_button.clicked() {
-                            self.handle_open();
-                            ui.close_menu();
-                        }
-
-                        let quit_button = Button::new("Quit")
-                            .shortcut_text(ctx.format_shortcut(&quit_shortcut))
-                            .ui(ui);
-                        if quit_button.clicked() {
-                            ctx.send_viewport_cmd(ViewportCommand::Close);
-                        }
-                    });
-
-                    ui.menu_button("Settings", |ui| {
-                        if ui.button("General").clicked() {
-                            self.state.open_window = Some(OpenWindow::GeneralSettings);
-                            ui.close_menu();
-                        }
+        if self.state.big_picture.is_none() {
+            self.handle_window_shortcuts(ctx);
+        }
 
-                        if ui.button("Video").clicked() {
-                            self.state.open_window = Some(OpenWindow::VideoSettings);
-                            ui.close_menu();
-                        }
+        if self.state.big_picture.is_some() {
+            self.render_big_picture(ctx);
+        } else {
+            self.render_main_ui(ctx, open_shortcut, quit_shortcut);
+        }
 
-                        if ui.button("Audio").clicked() {
-                            self.state.open_window = Some(OpenWindow::AudioSettings);
-                            ui.close_menu();
-                        }
+        if self.state.error_window_open {
+            self.render_error_window(ctx);
+        }
 
-                        if ui.button("Input").clicked() {
-                            self.state.open_window = Some(OpenWindow::InputSettings);
-                            ui.close_menu();
-                        }
+        if self.state.quit_confirmation_open {
+            self.render_quit_confirmation_window(ctx);
+        }
 
-                        if ui.button("Hotkeys").clicked() {
-                            self.state.open_window = Some(OpenWindow::HotkeySettings);
-                            ui.close_menu();
-                        }
-                    });
+        if let Some(error_pause) =
+            self.running_emulator_status().and_then(|status| status.error_pause)
+        {
+            self.render_error_pause_window(ctx, &error_pause);
+        }
 
-                    ui.menu_button("Help", |ui| {
-                        if ui.button("About").clicked() {
-                            self.state.open_window = Some(OpenWindow::About);
-                            ui.close_menu();
-                        }
-                    });
-                });
-            });
-        });
+        self.update_settings_pause(ctx);
 
-        self.render_central_panel(ctx);
+        self.check_timing_mode_mismatch();
+        if self.state.timing_mode_warning.is_some() {
+            self.render_timing_mode_warning_window(ctx);
+        }
 
-        match self.state.open_window {
-            Some(OpenWindow::GeneralSettings) => {
-                self.render_general_settings_window(ctx);
-            }
-            Some(OpenWindow::VideoSettings) => {
-                self.render_video_settings_window(ctx);
-            }
-            Some(OpenWindow::AudioSettings) => {
-                self.render_audio_settings_window(ctx);
-            }
-            Some(OpenWindow::InputSettings) => {
-                self.render_input_settings_window(ctx);
-            }
-            Some(OpenWindow::HotkeySettings) => {
-                self.render_hotkey_settings_window(ctx);
-            }
-            Some(OpenWindow::About) => {
-                self.render_about_window(ctx);
+        // Remember per-game settings changes; saving the config also sends them to the emulator
+        if let (Some(prev_game_settings), Some(game_settings)) =
+            (prev_game_settings, self.running_game_settings().cloned())
+        {
+            if prev_game_settings.rom_hash == game_settings.rom_hash {
+                if prev_game_settings.overscan != game_settings.overscan {
+                    self.config
+                        .per_game_overscan
+                        .insert(game_settings.rom_hash.clone(), game_settings.overscan);
+                }
+                if prev_game_settings.remove_sprite_limit != game_settings.remove_sprite_limit {
+                    self.config
+                        .per_game_remove_sprite_limit
+                        .insert(game_settings.rom_hash, game_settings.remove_sprite_limit);
+                }
             }
-            None => {}
         }
 
-        if self.state.error_window_open {
-            let mut error_open = true;
-            Window::new("Error").resizable(false).open(&mut error_open).show(ctx, |ui| {
-                ui.colored_label(
-                    Color32::RED,
-                    self.state
-                        .emulation_error
-                        .lock()
-                        .unwrap()
-                        .as_ref()
-                        .map_or(String::new(), anyhow::Error::to_string),
-                );
-            });
-            if !error_open {
-                self.state.error_window_open = false;
-                *self.state.emulation_error.lock().unwrap() = None;
+        if self.config.download_cover_art && !prev_config.download_cover_art {
+            if let Some(cover_art) = &mut self.state.cover_art {
+                cover_art.retry_missing();
             }
         }
 
@@ -1594,4 +4905,43 @@ mod tests {
     fn app_config_default_does_not_panic() {
         let _app_config = AppConfig::default();
     }
+
+    #[test]
+    fn command_palette_filter() {
+        assert!(command_matches_filter("Video Settings", ""));
+        assert!(command_matches_filter("Video Settings", "video"));
+        assert!(command_matches_filter("Video Settings", "set vid"));
+        assert!(!command_matches_filter("Video Settings", "audio"));
+        assert!(command_matches_filter("Launch Last Game (Zelda)", "LAST zel"));
+    }
+
+    #[test]
+    fn input_profile_toml_round_trip() {
+        let profile = InputProfile::from_input_config("test".into(), &InputConfig::default());
+        let profile_str = toml::to_string(&profile).unwrap();
+        assert_eq!(profile, toml::from_str::<InputProfile>(&profile_str).unwrap());
+    }
+
+    #[test]
+    fn per_game_settings_overlay_defaults() {
+        let mut config = AppConfig::default();
+        let crop = Overscan { top: 8, bottom: 8, left: 0, right: 0 };
+        config.per_game_overscan.insert("game".into(), crop);
+        config.per_game_remove_sprite_limit.insert("game".into(), true);
+
+        let game_settings = config.game_settings("game".into());
+        assert_eq!(game_settings.overscan, crop);
+        assert!(game_settings.remove_sprite_limit);
+        let dynamic_config =
+            config.to_jgnes_dynamic_config(&Palette::default(), Some(&game_settings));
+        assert_eq!(dynamic_config.overscan, crop);
+        assert!(dynamic_config.remove_sprite_limit);
+
+        // The defaults are unchanged, and games without an entry use them
+        let other_settings = config.game_settings("other".into());
+        assert_eq!(other_settings.overscan, config.overscan);
+        assert_eq!(other_settings.remove_sprite_limit, config.remove_sprite_limit);
+        assert_ne!(config.overscan, crop);
+        assert!(!config.remove_sprite_limit);
+    }
 }
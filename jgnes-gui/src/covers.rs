@@ -0,0 +1,271 @@
+//! Optional box art for the ROM list, downloaded from the libretro-thumbnails project.
+//!
+//! Images are looked up by ROM file name, which works for ROMs named after their No-Intro entries,
+//! and are cached in a directory next to the config file keyed by ROM hash. Lookups that the
+//! server answered with "not found" are also cached so that they are not retried on every launch;
+//! any other failure, including network errors, is retried the next time the launcher starts.
+//! If downloads are disabled, only images that are already in the cache are shown, so the
+//! launcher never touches the network; images can also be copied into the cache manually as
+//! `<ROM hash>.png`.
+//!
+//! Downloads (over HTTPS) and PNG decoding happen on a background thread. Downloaded images are
+//! only cached once they are complete and decode successfully.
+
+use crate::romlist::RomMetadata;
+use egui::{ColorImage, Context, TextureHandle, TextureOptions};
+use std::collections::HashMap;
+use std::fs;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::sync::mpsc::{Receiver, Sender};
+use std::thread;
+use std::time::Duration;
+
+const THUMBNAIL_BASE_URL: &str = "https://thumbnails.libretro.com";
+const THUMBNAIL_DIR: &str = "/Nintendo - Nintendo Entertainment System/Named_Boxarts/";
+const HTTP_TIMEOUT: Duration = Duration::from_secs(10);
+// Box art is typically a few hundred KB; anything much larger is not a thumbnail
+const MAX_IMAGE_LEN: u64 = 8 * 1024 * 1024;
+const MAX_REDIRECTS: u32 = 5;
+
+#[derive(Debug, Clone)]
+struct CoverRequest {
+    rom_hash: String,
+    file_name_no_ext: String,
+    download: bool,
+}
+
+enum CoverState {
+    Loading,
+    Missing,
+    Loaded(TextureHandle),
+}
+
+pub(crate) struct CoverArt {
+    request_sender: Sender<CoverRequest>,
+    result_receiver: Receiver<(String, Option<ColorImage>)>,
+    covers: HashMap<String, CoverState>,
+}
+
+impl CoverArt {
+    pub(crate) fn new(cache_dir: PathBuf, ctx: Context) -> Self {
+        let (request_sender, request_receiver) = mpsc::channel();
+        let (result_sender, result_receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let agent = ureq::AgentBuilder::new()
+                .timeout(HTTP_TIMEOUT)
+                .redirects(MAX_REDIRECTS)
+                .user_agent("jgnes")
+                .build();
+            for request in request_receiver {
+                let image = load_cover(&agent, &cache_dir, &request);
+                if result_sender.send((request.rom_hash, image)).is_err() {
+                    return;
+                }
+                ctx.request_repaint();
+            }
+        });
+
+        Self { request_sender, result_receiver, covers: HashMap::new() }
+    }
+
+    /// The cover for the given ROM, requesting it in the background if it has not been loaded yet.
+    pub(crate) fn cover(
+        &mut self,
+        ctx: &Context,
+        metadata: &RomMetadata,
+        download: bool,
+    ) -> Option<TextureHandle> {
+        for (rom_hash, image) in self.result_receiver.try_iter() {
+            let state = match image {
+                Some(image) => CoverState::Loaded(ctx.load_texture(
+                    format!("cover-{rom_hash}"),
+                    image,
+                    TextureOptions::LINEAR,
+                )),
+                None => CoverState::Missing,
+            };
+            self.covers.insert(rom_hash, state);
+        }
+
+        match self.covers.get(&metadata.rom_hash) {
+            Some(CoverState::Loaded(texture)) => Some(texture.clone()),
+            Some(CoverState::Loading | CoverState::Missing) => None,
+            None => {
+                // The worker thread only exits once this struct is dropped
+                let _ = self.request_sender.send(CoverRequest {
+                    rom_hash: metadata.rom_hash.clone(),
+                    file_name_no_ext: metadata.file_name_no_ext.clone(),
+                    download,
+                });
+                self.covers.insert(metadata.rom_hash.clone(), CoverState::Loading);
+                None
+            }
+        }
+    }
+
+    /// Forget covers that were not found, e.g. so that they are downloaded after enabling
+    /// downloads.
+    pub(crate) fn retry_missing(&mut self) {
+        self.covers.retain(|_, state| !matches!(state, CoverState::Missing));
+    }
+}
+
+fn load_cover(agent: &ureq::Agent, cache_dir: &Path, request: &CoverRequest) -> Option<ColorImage> {
+    let image_path = cache_dir.join(format!("{}.png", request.rom_hash));
+    let missing_path = cache_dir.join(format!("{}.missing", request.rom_hash));
+
+    if !image_path.is_file() {
+        if !request.download || missing_path.is_file() {
+            return None;
+        }
+
+        match download_cover(agent, &request.file_name_no_ext) {
+            Ok(Some(png_bytes)) => {
+                // Checked before caching so that a corrupt download is retried on the next launch
+                // instead of being cached
+                let image = decode_png(png_bytes.as_slice())
+                    .inspect_err(|err| {
+                        log::warn!(
+                            "Downloaded cover art for '{}' is not a valid PNG: {err}",
+                            request.file_name_no_ext
+                        );
+                    })
+                    .ok()?;
+                if let Err(err) =
+                    fs::create_dir_all(cache_dir).and_then(|()| fs::write(&image_path, png_bytes))
+                {
+                    log::error!("Unable to cache cover art at '{}': {err}", image_path.display());
+                }
+                return Some(image);
+            }
+            Ok(None) => {
+                log::info!("No cover art found for '{}'", request.file_name_no_ext);
+                let _ = fs::create_dir_all(cache_dir).and_then(|()| fs::write(&missing_path, ""));
+                return None;
+            }
+            // Not cached, so the download is retried the next time the launcher starts
+            Err(err) => {
+                log::warn!(
+                    "Unable to download cover art for '{}': {err:#}",
+                    request.file_name_no_ext
+                );
+                return None;
+            }
+        }
+    }
+
+    File::open(&image_path)
+        .map_err(anyhow::Error::from)
+        .and_then(|file| decode_png(BufReader::new(file)))
+        .inspect_err(|err| {
+            log::error!("Unable to decode cover art '{}': {err}", image_path.display());
+        })
+        .ok()
+}
+
+fn decode_png<R: Read>(reader: R) -> anyhow::Result<ColorImage> {
+    let mut decoder = png::Decoder::new(reader);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info()?;
+
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buf)?;
+    let size = [info.width as usize, info.height as usize];
+    let pixels = &buf[..info.buffer_size()];
+
+    Ok(match info.color_type {
+        png::ColorType::Rgba => ColorImage::from_rgba_unmultiplied(size, pixels),
+        png::ColorType::Rgb => ColorImage::from_rgb(size, pixels),
+        png::ColorType::GrayscaleAlpha => {
+            let rgba: Vec<_> = pixels
+                .chunks_exact(2)
+                .flat_map(|pixel| [pixel[0], pixel[0], pixel[0], pixel[1]])
+                .collect();
+            ColorImage::from_rgba_unmultiplied(size, &rgba)
+        }
+        png::ColorType::Grayscale => ColorImage::from_gray(size, pixels),
+        png::ColorType::Indexed => anyhow::bail!("indexed color was not expanded"),
+    })
+}
+
+// libretro-thumbnails replaces characters that are not allowed in file names on some platforms
+fn thumbnail_name(file_name_no_ext: &str) -> String {
+    file_name_no_ext.chars().map(|c| if "&*/:`<>?\\|\"".contains(c) { '_' } else { c }).collect()
+}
+
+fn percent_encode(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                char::from(b).to_string()
+            }
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+fn thumbnail_url(file_name_no_ext: &str) -> String {
+    let path = percent_encode(&format!("{THUMBNAIL_DIR}{}.png", thumbnail_name(file_name_no_ext)));
+    format!("{THUMBNAIL_BASE_URL}{path}")
+}
+
+// Returns Ok(None) only if the server says that it does not have an image for this name
+fn download_cover(agent: &ureq::Agent, file_name_no_ext: &str) -> anyhow::Result<Option<Vec<u8>>> {
+    let response = match agent.get(&thumbnail_url(file_name_no_ext)).call() {
+        Ok(response) => response,
+        Err(ureq::Error::Status(404, _)) => return Ok(None),
+        Err(err) => return Err(err.into()),
+    };
+
+    let content_length =
+        response.header("Content-Length").and_then(|content_length| content_length.parse().ok());
+    read_image_body(response.into_reader(), content_length).map(Some)
+}
+
+fn read_image_body<R: Read>(reader: R, content_length: Option<u64>) -> anyhow::Result<Vec<u8>> {
+    // One extra byte to detect responses that are over the limit
+    let mut body = Vec::new();
+    reader.take(MAX_IMAGE_LEN + 1).read_to_end(&mut body)?;
+    if body.len() as u64 > MAX_IMAGE_LEN {
+        anyhow::bail!("response is larger than {MAX_IMAGE_LEN} bytes");
+    }
+    if content_length.is_some_and(|content_length| content_length != body.len() as u64) {
+        anyhow::bail!("response body does not match its Content-Length");
+    }
+
+    Ok(body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn thumbnail_paths() {
+        let path = percent_encode(&format!(
+            "{THUMBNAIL_DIR}{}.png",
+            thumbnail_name("Legend of Zelda, The (USA) (Rev 1)")
+        ));
+        assert_eq!(
+            path,
+            "/Nintendo%20-%20Nintendo%20Entertainment%20System/Named_Boxarts/\
+             Legend%20of%20Zelda%2C%20The%20%28USA%29%20%28Rev%201%29.png"
+        );
+        assert_eq!(thumbnail_name("Q*bert (USA)"), "Q_bert (USA)");
+    }
+
+    #[test]
+    fn image_bodies() {
+        assert_eq!(read_image_body(&b"abc"[..], Some(3)).unwrap(), b"abc");
+        assert_eq!(read_image_body(&b"abc"[..], None).unwrap(), b"abc");
+
+        // Truncated body
+        assert!(read_image_body(&b"abc"[..], Some(10)).is_err());
+
+        let too_large = vec![0; MAX_IMAGE_LEN as usize + 1];
+        assert!(read_image_body(too_large.as_slice(), None).is_err());
+    }
+}
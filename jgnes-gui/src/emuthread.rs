@@ -1,36 +1,193 @@
 use jgnes_native_driver::{
     AxisDirection, HatDirection, InputCollectResult, InputType, JgnesNativeConfig, JoystickInput,
 };
+use sdl2::controller::{Axis, Button, GameController};
 use sdl2::event::Event;
 use sdl2::pixels::{Color, PixelFormatEnum};
 use sdl2::render::WindowCanvas;
+use sdl2::{EventPump, GameControllerSubsystem, Sdl};
 use std::collections::HashMap;
+use std::panic::AssertUnwindSafe;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::mpsc::{Receiver, Sender};
+use std::sync::mpsc::{Receiver, RecvTimeoutError, Sender};
 use std::sync::{Arc, Mutex, mpsc};
-use std::{process, thread};
+use std::time::Duration;
+use std::{panic, thread};
 
 pub(crate) enum EmuThreadTask {
     RunEmulator(Box<JgnesNativeConfig>),
     CollectInput { input_type: InputType, axis_deadzone: u16 },
 }
 
+/// Menu navigation input from a game controller, used by the big picture launcher.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum LauncherInput {
+    Up,
+    Down,
+    Left,
+    Right,
+    Confirm,
+    Back,
+}
+
+// SDL can only be used from the emulation thread, so controller navigation for the launcher is
+// polled there in between tasks
+struct LauncherInputPoller {
+    _sdl_ctx: Sdl,
+    game_controller_subsystem: GameControllerSubsystem,
+    event_pump: EventPump,
+    controllers: HashMap<u32, GameController>,
+    // Current direction of each analog stick axis, so that holding a stick only moves once
+    stick_directions: HashMap<(u32, Axis), i8>,
+}
+
+const LAUNCHER_STICK_THRESHOLD: i16 = 16384;
+
+impl LauncherInputPoller {
+    fn new() -> Result<Self, anyhow::Error> {
+        // The focused window belongs to the GUI rather than SDL
+        sdl2::hint::set("SDL_JOYSTICK_ALLOW_BACKGROUND_EVENTS", "1");
+
+        let sdl_ctx = sdl2::init().map_err(anyhow::Error::msg)?;
+        let game_controller_subsystem = sdl_ctx.game_controller().map_err(anyhow::Error::msg)?;
+        let event_pump = sdl_ctx.event_pump().map_err(anyhow::Error::msg)?;
+
+        Ok(Self {
+            _sdl_ctx: sdl_ctx,
+            game_controller_subsystem,
+            event_pump,
+            controllers: HashMap::new(),
+            stick_directions: HashMap::new(),
+        })
+    }
+
+    fn poll(&mut self, sender: &Sender<LauncherInput>) {
+        for event in self.event_pump.poll_iter() {
+            let launcher_input = match event {
+                Event::ControllerDeviceAdded { which: device_index, .. } => {
+                    match self.game_controller_subsystem.open(device_index) {
+                        Ok(controller) => {
+                            self.controllers.insert(controller.instance_id(), controller);
+                        }
+                        Err(err) => {
+                            log::error!("Unable to open game controller {device_index}: {err}");
+                        }
+                    }
+                    None
+                }
+                Event::ControllerDeviceRemoved { which: instance_id, .. } => {
+                    self.controllers.remove(&instance_id);
+                    None
+                }
+                Event::ControllerButtonDown { button, .. } => match button {
+                    Button::DPadUp => Some(LauncherInput::Up),
+                    Button::DPadDown => Some(LauncherInput::Down),
+                    Button::DPadLeft => Some(LauncherInput::Left),
+                    Button::DPadRight => Some(LauncherInput::Right),
+                    Button::A | Button::Start => Some(LauncherInput::Confirm),
+                    Button::B | Button::Back => Some(LauncherInput::Back),
+                    _ => None,
+                },
+                Event::ControllerAxisMotion {
+                    which: instance_id,
+                    axis: axis @ (Axis::LeftX | Axis::LeftY),
+                    value,
+                    ..
+                } => {
+                    let direction = if value >= LAUNCHER_STICK_THRESHOLD {
+                        1
+                    } else if value <= -LAUNCHER_STICK_THRESHOLD {
+                        -1
+                    } else {
+                        0
+                    };
+                    let prev_direction =
+                        self.stick_directions.insert((instance_id, axis), direction);
+
+                    match (axis, direction) {
+                        _ if prev_direction == Some(direction) => None,
+                        (Axis::LeftX, 1) => Some(LauncherInput::Right),
+                        (Axis::LeftX, -1) => Some(LauncherInput::Left),
+                        (Axis::LeftY, 1) => Some(LauncherInput::Down),
+                        (Axis::LeftY, -1) => Some(LauncherInput::Up),
+                        _ => None,
+                    }
+                }
+                _ => None,
+            };
+
+            if let Some(launcher_input) = launcher_input {
+                // Ignore send errors; the GUI is shutting down if its receiver is gone
+                let _ = sender.send(launcher_input);
+            }
+        }
+    }
+}
+
+// Wait for the next task, polling for launcher input in the meantime if it is enabled
+fn next_task(
+    task_receiver: &Receiver<EmuThreadTask>,
+    launcher_input_enabled: &AtomicBool,
+    launcher_input_sender: &Sender<LauncherInput>,
+) -> Result<EmuThreadTask, mpsc::RecvError> {
+    let mut poller: Option<LauncherInputPoller> = None;
+    let mut poller_init_failed = false;
+
+    loop {
+        if !launcher_input_enabled.load(Ordering::Relaxed) {
+            // Drop the SDL context before blocking so that tasks can initialize their own
+            drop(poller.take());
+
+            match task_receiver.recv_timeout(Duration::from_millis(100)) {
+                Ok(task) => return Ok(task),
+                Err(RecvTimeoutError::Timeout) => continue,
+                Err(RecvTimeoutError::Disconnected) => return Err(mpsc::RecvError),
+            }
+        }
+
+        match task_receiver.recv_timeout(Duration::from_millis(10)) {
+            Ok(task) => return Ok(task),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return Err(mpsc::RecvError),
+        }
+
+        if poller.is_none() && !poller_init_failed {
+            match LauncherInputPoller::new() {
+                Ok(new_poller) => poller = Some(new_poller),
+                Err(err) => {
+                    log::error!("Unable to initialize game controller input for launcher: {err}");
+                    poller_init_failed = true;
+                }
+            }
+        }
+
+        if let Some(poller) = &mut poller {
+            poller.poll(launcher_input_sender);
+        }
+    }
+}
+
 #[must_use]
 pub(crate) fn start(
     is_running: Arc<AtomicBool>,
     emulation_error: Arc<Mutex<Option<anyhow::Error>>>,
-) -> (Sender<EmuThreadTask>, Receiver<Option<InputCollectResult>>) {
+    launcher_input_enabled: Arc<AtomicBool>,
+) -> (Sender<EmuThreadTask>, Receiver<Option<InputCollectResult>>, Receiver<LauncherInput>) {
     let (task_sender, task_receiver) = mpsc::channel();
     let (input_sender, input_receiver) = mpsc::channel();
+    let (launcher_input_sender, launcher_input_receiver) = mpsc::channel();
 
     thread::spawn(move || {
         std::panic::set_hook(Box::new(|panic_info| {
-            log::error!("Emulation thread panicked, killing process: {panic_info}");
-            process::exit(1);
+            log::error!("Panic: {panic_info}");
         }));
 
         loop {
-            let task = match task_receiver.recv() {
+            let task = match next_task(
+                &task_receiver,
+                &launcher_input_enabled,
+                &launcher_input_sender,
+            ) {
                 Ok(task) => task,
                 Err(err) => {
                     log::info!(
@@ -69,7 +226,7 @@ pub(crate) fn start(
         }
     });
 
-    (task_sender, input_receiver)
+    (task_sender, input_receiver, launcher_input_receiver)
 }
 
 fn run_emulator(
@@ -78,7 +235,18 @@ fn run_emulator(
     emulation_error: &Arc<Mutex<Option<anyhow::Error>>>,
 ) {
     is_running.store(true, Ordering::Relaxed);
-    if let Err(err) = jgnes_native_driver::run(&config) {
+
+    // Panics are reported the same way as fatal errors so that the GUI can offer a crash report
+    let result = panic::catch_unwind(AssertUnwindSafe(|| jgnes_native_driver::run(&config)))
+        .unwrap_or_else(|payload| {
+            let message = payload
+                .downcast_ref::<&str>()
+                .map(|message| (*message).to_owned())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "<unknown>".into());
+            Err(anyhow::Error::msg(format!("Emulation thread panicked: {message}")))
+        });
+    if let Err(err) = result {
         *emulation_error.lock().unwrap() = Some(err);
     }
 
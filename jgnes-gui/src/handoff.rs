@@ -0,0 +1,280 @@
+//! Single-instance guard, so that opening a ROM from a file manager with a file association
+//! switches the already-open launcher to that ROM instead of opening a second launcher.
+//!
+//! The first launcher listens on an ephemeral localhost port and writes the port and a random
+//! token to a lock file in a per-user directory, so launchers run by other users never find it.
+//! Later launchers read the lock file, connect, send the token as a single line followed by the raw
+//! bytes of the ROM path from their command line, wait for an `ok` line, and exit. An empty path
+//! only brings the existing launcher to the front. Connections that send the wrong token are closed
+//! without a response. If the lock file is missing or stale, the new launcher takes over the guard.
+
+use egui::Context;
+use jgnes_native_driver::{DataPaths, user_runtime_file, write_private_file};
+use std::ffi::OsString;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{Ipv4Addr, Shutdown, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::sync::mpsc::Receiver;
+use std::time::Duration;
+use std::{fs, io, thread};
+
+const LOCK_FILE_NAME: &str = "jgnes-launcher.lock";
+const HANDOFF_TIMEOUT: Duration = Duration::from_secs(2);
+const HANDOFF_ACK: &str = "ok";
+// Generous limit on the token line plus the ROM path
+const MAX_HANDOFF_BYTES: u64 = 64 * 1024;
+
+/// Listener for ROM paths handed off by later launchers.
+pub struct HandoffListener {
+    listener: TcpListener,
+    token: String,
+}
+
+pub enum SingleInstance {
+    /// This is the only launcher; ROMs handed off by later launchers arrive on this listener
+    Primary(HandoffListener),
+    /// The ROM was handed off to an existing launcher, so this process should exit
+    HandedOff,
+    /// The single-instance guard is unavailable
+    Unguarded,
+}
+
+/// Claim the single-instance lock file, or hand the given ROM off to the launcher that already
+/// holds it.
+#[must_use]
+pub fn claim_or_hand_off(data_paths: &DataPaths, rom_path: Option<&Path>) -> SingleInstance {
+    let Some(lock_path) = lock_file_path(data_paths) else {
+        log::warn!("Unable to determine where to keep the single-instance lock file");
+        return SingleInstance::Unguarded;
+    };
+    claim_or_hand_off_with_lock_file(&lock_path, rom_path)
+}
+
+// In portable mode the lock file is kept with the rest of the portable data. Otherwise config files
+// are in the working directory, which differs between launches, so a per-user directory is used
+fn lock_file_path(data_paths: &DataPaths) -> Option<PathBuf> {
+    if data_paths.is_portable() {
+        return Some(data_paths.config_file(LOCK_FILE_NAME));
+    }
+
+    user_runtime_file(LOCK_FILE_NAME)
+}
+
+fn claim_or_hand_off_with_lock_file(lock_path: &Path, rom_path: Option<&Path>) -> SingleInstance {
+    if let Some((port, token)) = read_lock_file(lock_path) {
+        // The other launcher's working directory may be different
+        let rom_path = rom_path.map(|rom_path| rom_path.canonicalize().unwrap_or(rom_path.into()));
+        match hand_off(port, &token, rom_path.as_deref()) {
+            Ok(()) => {
+                log::info!("Handed off to the jgnes launcher that is already running");
+                return SingleInstance::HandedOff;
+            }
+            // Most likely the launcher that wrote the lock file has exited
+            Err(err) => log::info!("Unable to hand off to an existing jgnes launcher: {err}"),
+        }
+    }
+
+    match claim(lock_path) {
+        Ok(listener) => SingleInstance::Primary(listener),
+        Err(err) => {
+            log::warn!(
+                "Unable to claim single-instance lock file '{}': {err}",
+                lock_path.display()
+            );
+            SingleInstance::Unguarded
+        }
+    }
+}
+
+fn read_lock_file(lock_path: &Path) -> Option<(u16, String)> {
+    let contents = fs::read_to_string(lock_path).ok()?;
+    let mut lines = contents.lines();
+    let port = lines.next()?.parse().ok()?;
+    let token = lines.next()?.to_owned();
+    Some((port, token))
+}
+
+fn claim(lock_path: &Path) -> io::Result<HandoffListener> {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?;
+    let port = listener.local_addr()?.port();
+    let token = format!("{:032x}", rand::random::<u128>());
+    // Only the current user should be able to read the token
+    write_private_file(lock_path, &format!("{port}\n{token}\n"))?;
+
+    Ok(HandoffListener { listener, token })
+}
+
+// Paths are sent as raw bytes so that paths that are not valid UTF-8 survive the handoff
+#[cfg(unix)]
+fn path_to_bytes(path: &Path) -> Vec<u8> {
+    use std::os::unix::ffi::OsStrExt;
+
+    path.as_os_str().as_bytes().to_vec()
+}
+
+#[cfg(unix)]
+fn path_from_bytes(bytes: Vec<u8>) -> PathBuf {
+    use std::os::unix::ffi::OsStringExt;
+
+    OsString::from_vec(bytes).into()
+}
+
+#[cfg(windows)]
+fn path_to_bytes(path: &Path) -> Vec<u8> {
+    use std::os::windows::ffi::OsStrExt;
+
+    path.as_os_str().encode_wide().flat_map(u16::to_le_bytes).collect()
+}
+
+#[cfg(windows)]
+fn path_from_bytes(bytes: Vec<u8>) -> PathBuf {
+    use std::os::windows::ffi::OsStringExt;
+
+    let wide: Vec<_> =
+        bytes.chunks_exact(2).map(|chunk| u16::from_le_bytes([chunk[0], chunk[1]])).collect();
+    OsString::from_wide(&wide).into()
+}
+
+fn hand_off(port: u16, token: &str, rom_path: Option<&Path>) -> io::Result<()> {
+    let addr = (Ipv4Addr::LOCALHOST, port).into();
+    let mut stream = TcpStream::connect_timeout(&addr, HANDOFF_TIMEOUT)?;
+    stream.set_read_timeout(Some(HANDOFF_TIMEOUT))?;
+
+    writeln!(stream, "{token}")?;
+    if let Some(rom_path) = rom_path {
+        stream.write_all(&path_to_bytes(rom_path))?;
+    }
+    stream.shutdown(Shutdown::Write)?;
+
+    let mut response = String::new();
+    BufReader::new(stream).read_line(&mut response)?;
+    if response.trim_end() != HANDOFF_ACK {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "unexpected handoff response"));
+    }
+
+    Ok(())
+}
+
+/// ROM paths handed off by later launchers. `None` means that a launcher was started without a
+/// ROM, and this launcher should only be brought to the front.
+pub type HandoffReceiver = Receiver<Option<PathBuf>>;
+
+/// Accept handoffs in a background thread, waking up the GUI whenever one arrives.
+#[must_use]
+pub fn listen(listener: HandoffListener, ctx: Context) -> HandoffReceiver {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let HandoffListener { listener, token } = listener;
+        for stream in listener.incoming() {
+            let rom_path = match stream.and_then(|stream| receive_handoff(&stream, &token)) {
+                Ok(rom_path) => rom_path,
+                Err(err) => {
+                    log::warn!("Error receiving handoff from another launcher: {err}");
+                    continue;
+                }
+            };
+
+            if sender.send(rom_path).is_err() {
+                // The GUI has exited
+                return;
+            }
+            ctx.request_repaint();
+        }
+    });
+    receiver
+}
+
+fn receive_handoff(stream: &TcpStream, token: &str) -> io::Result<Option<PathBuf>> {
+    stream.set_read_timeout(Some(HANDOFF_TIMEOUT))?;
+
+    let mut reader = BufReader::new(stream.take(MAX_HANDOFF_BYTES));
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    if line.trim_end_matches(['\r', '\n']) != token {
+        return Err(io::Error::new(io::ErrorKind::PermissionDenied, "handoff token mismatch"));
+    }
+
+    let mut path_bytes = Vec::new();
+    reader.read_to_end(&mut path_bytes)?;
+    let mut writer = stream;
+    writeln!(writer, "{HANDOFF_ACK}")?;
+
+    Ok((!path_bytes.is_empty()).then(|| path_from_bytes(path_bytes)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    fn test_lock_path(name: &str) -> PathBuf {
+        env::temp_dir().join(format!("jgnes-handoff-test-{name}-{}.lock", std::process::id()))
+    }
+
+    fn claim_primary(lock_path: &Path) -> HandoffReceiver {
+        let _ = fs::remove_file(lock_path);
+        let SingleInstance::Primary(listener) = claim_or_hand_off_with_lock_file(lock_path, None)
+        else {
+            panic!("first launcher should claim the lock file");
+        };
+        listen(listener, Context::default())
+    }
+
+    #[test]
+    fn second_launcher_hands_off_rom_path() {
+        let lock_path = test_lock_path("rom-path");
+        let receiver = claim_primary(&lock_path);
+
+        let rom_path = env::temp_dir().join("jgnes-handoff-test.nes");
+        assert!(matches!(
+            claim_or_hand_off_with_lock_file(&lock_path, Some(&rom_path)),
+            SingleInstance::HandedOff
+        ));
+        assert_eq!(receiver.recv().unwrap(), Some(rom_path));
+
+        assert!(matches!(
+            claim_or_hand_off_with_lock_file(&lock_path, None),
+            SingleInstance::HandedOff
+        ));
+        assert_eq!(receiver.recv().unwrap(), None);
+
+        let _ = fs::remove_file(&lock_path);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn non_utf8_rom_path_is_preserved() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let lock_path = test_lock_path("non-utf8");
+        let receiver = claim_primary(&lock_path);
+
+        let rom_path = env::temp_dir().join(std::ffi::OsStr::from_bytes(b"jgnes-\xff.nes"));
+        assert!(matches!(
+            claim_or_hand_off_with_lock_file(&lock_path, Some(&rom_path)),
+            SingleInstance::HandedOff
+        ));
+        assert_eq!(receiver.recv().unwrap(), Some(rom_path));
+
+        let _ = fs::remove_file(&lock_path);
+    }
+
+    #[test]
+    fn wrong_token_is_rejected() {
+        let lock_path = test_lock_path("wrong-token");
+        let receiver = claim_primary(&lock_path);
+
+        let (port, _) = read_lock_file(&lock_path).unwrap();
+        fs::write(&lock_path, format!("{port}\nwrong\n")).unwrap();
+
+        // The handoff fails, so the new launcher takes over the guard
+        assert!(matches!(
+            claim_or_hand_off_with_lock_file(&lock_path, None),
+            SingleInstance::Primary(_)
+        ));
+        assert!(receiver.try_recv().is_err());
+
+        let _ = fs::remove_file(&lock_path);
+    }
+}
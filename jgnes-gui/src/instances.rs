@@ -0,0 +1,111 @@
+//! Additional emulator instances, e.g. for comparing two ROM hacks side by side.
+//!
+//! SDL can only be used from one thread per process, so the GUI's emulation thread can only ever
+//! run one emulator. Additional instances instead run in child processes of the GUI executable.
+//! Each child loads its own copy of the config when it starts, so settings changed in the GUI
+//! afterwards only affect the GUI's own emulator. Closing a child's stdin asks it to quit.
+
+use jgnes_native_driver::JgnesSharedConfig;
+use std::io::stdin;
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::{io, thread};
+
+/// Command-line flag that makes the GUI executable run a single emulator instead of the GUI.
+pub const INSTANCE_ARG: &str = "--instance";
+
+/// Command-line flag that enables portable mode, which instances inherit from the GUI.
+pub const PORTABLE_ARG: &str = "--portable";
+
+pub(crate) struct Instance {
+    rom_path: PathBuf,
+    child: Child,
+    // Dropping this closes the child's stdin, which signals it to quit
+    quit_signal: Option<ChildStdin>,
+}
+
+impl Instance {
+    pub(crate) fn rom_file_name(&self) -> String {
+        self.rom_path.file_name().map_or_else(
+            || self.rom_path.to_string_lossy().into_owned(),
+            |file_name| file_name.to_string_lossy().into_owned(),
+        )
+    }
+
+    pub(crate) fn request_quit(&mut self) {
+        self.quit_signal = None;
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct InstancePool {
+    instances: Vec<Instance>,
+}
+
+impl InstancePool {
+    pub(crate) fn spawn(
+        &mut self,
+        config_path: &Path,
+        rom_path: &Path,
+        portable: bool,
+    ) -> io::Result<()> {
+        let mut command = Command::new(std::env::current_exe()?);
+        command.arg(INSTANCE_ARG).arg(rom_path).arg(config_path);
+        if portable {
+            command.arg(PORTABLE_ARG);
+        }
+        let mut child = command.stdin(Stdio::piped()).spawn()?;
+
+        log::info!("Launched '{}' in new window (pid {})", rom_path.display(), child.id());
+
+        let quit_signal = child.stdin.take();
+        self.instances.push(Instance { rom_path: rom_path.to_path_buf(), child, quit_signal });
+
+        Ok(())
+    }
+
+    /// Remove instances whose processes have exited.
+    pub(crate) fn reap(&mut self) {
+        self.instances.retain_mut(|instance| match instance.child.try_wait() {
+            Ok(Some(status)) => {
+                if !status.success() {
+                    log::error!(
+                        "Emulator window for '{}' exited with {status}",
+                        instance.rom_path.display()
+                    );
+                }
+                false
+            }
+            Ok(None) => true,
+            Err(err) => {
+                log::error!("Unable to check status of emulator window process: {err}");
+                false
+            }
+        });
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.instances.is_empty()
+    }
+
+    pub(crate) fn instances_mut(&mut self) -> &mut [Instance] {
+        &mut self.instances
+    }
+}
+
+impl Drop for InstancePool {
+    fn drop(&mut self) {
+        for instance in &mut self.instances {
+            instance.request_quit();
+        }
+    }
+}
+
+/// In a child process, request the emulator to quit once the parent closes stdin (or exits).
+pub(crate) fn quit_on_stdin_close(shared_config: JgnesSharedConfig) {
+    thread::spawn(move || {
+        // The parent never writes anything, so this only returns once stdin is closed
+        let _ = io::copy(&mut stdin().lock(), &mut io::sink());
+        shared_config.request_quit();
+    });
+}
@@ -1,5 +1,10 @@
 mod app;
+mod covers;
 mod emuthread;
+mod handoff;
+mod instances;
 mod romlist;
 
-pub use app::App;
+pub use app::{App, run_instance};
+pub use handoff::{HandoffListener, HandoffReceiver, SingleInstance, claim_or_hand_off, listen};
+pub use instances::{INSTANCE_ARG, PORTABLE_ARG};
@@ -1,9 +1,9 @@
 use eframe::NativeOptions;
 use egui::{Vec2, ViewportBuilder};
-use env_logger::Env;
-use jgnes_gui::App;
-use std::path::PathBuf;
-use std::str::FromStr;
+use jgnes_gui::{App, SingleInstance};
+use jgnes_native_driver::DataPaths;
+use std::path::{Path, PathBuf};
+use std::process;
 
 // Attempt to detect if the application is running on a Steam Deck, and if it is then override
 // the winit scale factor to 1. It defaults to 4.5 on the Steam Deck which results in the GUI
@@ -46,19 +46,69 @@ fn steam_deck_dpi_hack() {
 }
 
 fn main() -> eframe::Result<()> {
-    env_logger::Builder::from_env(Env::default().default_filter_or("info,wgpu_core::device=warn"))
-        .init();
+    jgnes_native_driver::init_logger("info,wgpu_core::device=warn")
+        .expect("Logger should only be initialized once");
 
     #[cfg(target_os = "linux")]
     steam_deck_dpi_hack();
 
+    // Paths in arguments are not necessarily valid UTF-8
+    let mut args: Vec<_> = std::env::args_os().collect();
+    let portable = args.iter().any(|arg| arg == jgnes_gui::PORTABLE_ARG);
+    args.retain(|arg| arg != jgnes_gui::PORTABLE_ARG);
+
+    let data_paths = match DataPaths::resolve(portable) {
+        Ok(data_paths) => data_paths,
+        Err(err) => {
+            log::error!("Unable to create portable mode directory: {err}");
+            process::exit(1);
+        }
+    };
+
+    // Additional emulator windows launched from the GUI run in child processes
+    if let [_, flag, rom_path, config_path] = args.as_slice() {
+        if flag == jgnes_gui::INSTANCE_ARG {
+            if let Err(err) =
+                jgnes_gui::run_instance(Path::new(config_path), Path::new(rom_path), &data_paths)
+            {
+                log::error!("Emulator terminated with error: {err:#}");
+                process::exit(1);
+            }
+            return Ok(());
+        }
+    }
+
+    // A ROM path may be passed as the only argument, e.g. when opened through a file association
+    let rom_path = match args.as_slice() {
+        [_] => None,
+        [_, rom_path] => Some(PathBuf::from(rom_path)),
+        _ => {
+            eprintln!("USAGE: jgnes-gui [--portable] [rom_path]");
+            process::exit(1);
+        }
+    };
+
+    let handoff_listener = match jgnes_gui::claim_or_hand_off(&data_paths, rom_path.as_deref()) {
+        SingleInstance::Primary(listener) => Some(listener),
+        SingleInstance::HandedOff => return Ok(()),
+        SingleInstance::Unguarded => None,
+    };
+
     // TODO configurability
-    let config_path = PathBuf::from_str("jgnes-config.toml").unwrap();
+    let config_path = data_paths.config_file("jgnes-config.toml");
 
     let options = NativeOptions {
         viewport: ViewportBuilder::default().with_inner_size(Vec2::new(800.0, 720.0)),
         ..NativeOptions::default()
     };
 
-    eframe::run_native("jgnes", options, Box::new(|_cc| Ok(Box::new(App::new(config_path)))))
+    eframe::run_native(
+        "jgnes",
+        options,
+        Box::new(|cc| {
+            let handoff_receiver =
+                handoff_listener.map(|listener| jgnes_gui::listen(listener, cc.egui_ctx.clone()));
+            Ok(Box::new(App::new(config_path, data_paths, rom_path, handoff_receiver)))
+        }),
+    )
 }
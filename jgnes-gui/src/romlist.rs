@@ -1,8 +1,16 @@
+//! ROM directory scanning for the launcher's ROM list.
+//!
+//! Reading and hashing every ROM in a large directory is slow, so headers and hashes are cached
+//! in a sidecar file keyed by path, modification time, and size. Only new or changed ROMs are
+//! read, in parallel.
+
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::ffi::OsStr;
 use std::fs;
-use std::fs::File;
-use std::io::{Read, Seek, SeekFrom};
 use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 
 #[derive(Debug, Clone)]
 pub struct RomMetadata {
@@ -11,8 +19,10 @@ pub struct RomMetadata {
     pub prg_rom_len: u32,
     pub chr_rom_len: u32,
     pub mapper_name: String,
+    pub rom_hash: String,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 struct Header {
     prg_rom_len: u32,
     chr_rom_len: u32,
@@ -21,10 +31,10 @@ struct Header {
 }
 
 impl Header {
-    fn parse_from(file: &mut File) -> anyhow::Result<Self> {
-        let mut header = [0; 16];
-        file.seek(SeekFrom::Start(0))?;
-        file.read_exact(&mut header)?;
+    fn parse(rom_bytes: &[u8]) -> anyhow::Result<Self> {
+        let Some(header) = rom_bytes.get(..16) else {
+            anyhow::bail!("file is too short to contain an iNES header");
+        };
 
         let is_nes_2_0_header = header[7] & 0x0C == 0x08;
 
@@ -49,44 +59,104 @@ impl Header {
     }
 }
 
-pub fn get_rom_list(dir: &str) -> anyhow::Result<Vec<RomMetadata>> {
-    let mut rom_list = Vec::new();
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct CachedRom {
+    path: PathBuf,
+    modified: SystemTime,
+    len: u64,
+    rom_hash: String,
+    header: Header,
+}
 
-    for dir_entry in fs::read_dir(Path::new(dir))? {
-        let dir_entry = dir_entry?;
-        if dir_entry.path().extension().and_then(OsStr::to_str) != Some("nes") {
-            continue;
-        }
+impl CachedRom {
+    fn to_metadata(&self) -> Option<RomMetadata> {
+        let file_name_no_ext = self.path.file_stem()?.to_str()?.into();
+        Some(RomMetadata {
+            full_path: self.path.clone(),
+            file_name_no_ext,
+            prg_rom_len: self.header.prg_rom_len,
+            chr_rom_len: self.header.chr_rom_len,
+            mapper_name: mapper_name(self.header.mapper_number, self.header.sub_mapper_number)
+                .into(),
+            rom_hash: self.rom_hash.clone(),
+        })
+    }
+}
 
-        let metadata = dir_entry.metadata()?;
-        if !metadata.is_file() {
-            continue;
-        }
+#[derive(Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+struct RomCache {
+    #[serde(default)]
+    roms: Vec<CachedRom>,
+}
 
-        let header = {
-            let mut file = File::open(dir_entry.path())?;
-            Header::parse_from(&mut file)?
-        };
+fn load_cache(cache_path: &Path) -> RomCache {
+    let Ok(cache_str) = fs::read_to_string(cache_path) else {
+        return RomCache::default();
+    };
 
-        let Some(file_name) = dir_entry.file_name().to_str().map(String::from) else {
-            continue;
-        };
-        let Some(file_name_no_ext) =
-            Path::new(&file_name).with_extension("").to_str().map(String::from)
-        else {
-            continue;
-        };
+    toml::from_str(&cache_str).unwrap_or_else(|err| {
+        log::warn!("Ignoring invalid ROM list cache at '{}': {err}", cache_path.display());
+        RomCache::default()
+    })
+}
 
-        rom_list.push(RomMetadata {
-            full_path: dir_entry.path(),
-            file_name_no_ext,
-            prg_rom_len: header.prg_rom_len,
-            chr_rom_len: header.chr_rom_len,
-            mapper_name: mapper_name(header.mapper_number, header.sub_mapper_number).into(),
-        });
+fn save_cache(cache: &RomCache, cache_path: &Path) -> anyhow::Result<()> {
+    fs::write(cache_path, toml::to_string(cache)?)?;
+    Ok(())
+}
+
+/// List the ROMs in the given directory, using and updating the metadata cache at `cache_path`.
+pub fn get_rom_list(dir: &str, cache_path: &Path) -> anyhow::Result<Vec<RomMetadata>> {
+    let prev_cache = load_cache(cache_path);
+    let cached_roms: HashMap<_, _> =
+        prev_cache.roms.iter().map(|cached_rom| (cached_rom.path.as_path(), cached_rom)).collect();
+
+    let mut paths = Vec::new();
+    for dir_entry in fs::read_dir(Path::new(dir))? {
+        let path = dir_entry?.path();
+        if path.extension().and_then(OsStr::to_str) == Some("nes") {
+            paths.push(path);
+        }
     }
+    // Directory order is unspecified, and the cache should only be rewritten if it changed
+    paths.sort();
+
+    let roms = paths
+        .into_par_iter()
+        .map(|path| {
+            let metadata = fs::metadata(&path)?;
+            if !metadata.is_file() {
+                return Ok(None);
+            }
+
+            let modified = metadata.modified()?;
+            let len = metadata.len();
+            if let Some(&cached_rom) = cached_roms.get(path.as_path()) {
+                if cached_rom.modified == modified && cached_rom.len == len {
+                    return Ok(Some(cached_rom.clone()));
+                }
+            }
+
+            let rom_bytes = fs::read(&path)?;
+            let header = Header::parse(&rom_bytes)?;
+            Ok(Some(CachedRom { path, modified, len, rom_hash: rom_hash(&rom_bytes), header }))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let cache = RomCache { roms: roms.into_iter().flatten().collect() };
+    if cache != prev_cache {
+        if let Err(err) = save_cache(&cache, cache_path) {
+            log::warn!("Unable to save ROM list cache to '{}': {err}", cache_path.display());
+        }
+    }
+
+    Ok(cache.roms.iter().filter_map(CachedRom::to_metadata).collect())
+}
 
-    Ok(rom_list)
+/// Compute a stable identifier for a ROM file's contents, suitable for use as a key in per-game
+/// settings. This is a 64-bit FNV-1a hash formatted as hex.
+pub fn rom_hash(rom_bytes: &[u8]) -> String {
+    format!("{:016x}", jgnes_core::fnv1a_hash(rom_bytes))
 }
 
 fn mapper_name(mapper_number: u16, sub_mapper_number: u8) -> &'static str {
@@ -132,3 +202,44 @@ fn mapper_name(mapper_number: u16, sub_mapper_number: u8) -> &'static str {
         _ => "(Unknown)",
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rom_list_is_cached() {
+        let dir = std::env::temp_dir().join(format!("jgnes-romlist-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let cache_path = dir.join("cache.toml");
+
+        let mut rom = vec![b'N', b'E', b'S', 0x1A, 2, 1, 0x10, 0x00];
+        rom.resize(16 + 2 * 16 * 1024 + 8 * 1024, 0);
+        fs::write(dir.join("game.nes"), &rom).unwrap();
+        fs::write(dir.join("notes.txt"), "not a ROM").unwrap();
+
+        let rom_list = get_rom_list(dir.to_str().unwrap(), &cache_path).unwrap();
+        assert_eq!(rom_list.len(), 1);
+        assert_eq!(rom_list[0].file_name_no_ext, "game");
+        assert_eq!(rom_list[0].mapper_name, "MMC1");
+        assert_eq!(rom_list[0].rom_hash, rom_hash(&rom));
+
+        // A ROM whose size and modification time are unchanged is not read again, so a changed
+        // cache entry is returned as-is
+        let mut cache = load_cache(&cache_path);
+        assert_eq!(cache.roms.len(), 1);
+        cache.roms[0].rom_hash = "cached".into();
+        save_cache(&cache, &cache_path).unwrap();
+        assert_eq!(get_rom_list(dir.to_str().unwrap(), &cache_path).unwrap()[0].rom_hash, "cached");
+
+        // A ROM whose size changed is read again
+        rom.push(0);
+        fs::write(dir.join("game.nes"), &rom).unwrap();
+        assert_eq!(
+            get_rom_list(dir.to_str().unwrap(), &cache_path).unwrap()[0].rom_hash,
+            rom_hash(&rom)
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,318 @@
+//! Lightweight capture of the last few seconds of gameplay to an animated GIF or APNG.
+//!
+//! Unlike clips, captures do not replay anything; the raw frame buffers of recently rendered frames
+//! are kept in a ring buffer and converted to RGBA only when a capture is saved.
+
+use crate::config::CaptureFormat;
+use crate::encode;
+use crate::osd;
+use crate::osd::OsdLine;
+use jgnes_core::{ColorEmphasis, FrameBuffer, Renderer, TimingMode};
+use jgnes_renderer::colors;
+use jgnes_renderer::colors::Palette;
+use jgnes_renderer::config::Overscan;
+use std::array;
+use std::collections::VecDeque;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+use std::thread;
+
+fn file_extension(format: CaptureFormat) -> &'static str {
+    match format {
+        CaptureFormat::Gif => "gif",
+        CaptureFormat::Apng => "png",
+    }
+}
+
+struct CapturedFrame {
+    frame_buffer: Box<FrameBuffer>,
+    color_emphasis: ColorEmphasis,
+}
+
+/// Renderer wrapper that keeps a copy of the most recently rendered frames.
+///
+/// It can also mirror frames to a second renderer, which always receives the frames as the emulator
+/// rendered them, without the OSD.
+pub(crate) struct RecordingRenderer<R> {
+    inner: R,
+    mirror: Option<R>,
+    mirror_failing: bool,
+    frames: VecDeque<CapturedFrame>,
+    max_frames: usize,
+    timing_mode: TimingMode,
+    osd_lines: Vec<OsdLine>,
+    osd_scrub_bar: Option<f64>,
+    osd_frame_buffer: Box<FrameBuffer>,
+}
+
+impl<R> RecordingRenderer<R> {
+    pub(crate) fn new(inner: R, max_frames: usize) -> Self {
+        Self {
+            inner,
+            mirror: None,
+            mirror_failing: false,
+            frames: VecDeque::new(),
+            max_frames,
+            timing_mode: TimingMode::Ntsc,
+            osd_lines: Vec::new(),
+            osd_scrub_bar: None,
+            osd_frame_buffer: Box::new(array::from_fn(|_| array::from_fn(|_| 0))),
+        }
+    }
+
+    /// Set the text to draw on top of subsequent frames. Captured frames never include it.
+    pub(crate) fn set_osd_lines(&mut self, osd_lines: Vec<OsdLine>) {
+        self.osd_lines = osd_lines;
+    }
+
+    /// Set the position of the scrub bar to draw on top of subsequent frames, or hide it.
+    pub(crate) fn set_osd_scrub_bar(&mut self, position: Option<f64>) {
+        self.osd_scrub_bar = position;
+    }
+
+    pub(crate) fn inner(&self) -> &R {
+        &self.inner
+    }
+
+    pub(crate) fn inner_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    pub(crate) fn with_mirror(mut self, mirror: Option<R>) -> Self {
+        self.mirror = mirror;
+        self
+    }
+
+    pub(crate) fn mirror_mut(&mut self) -> Option<&mut R> {
+        self.mirror.as_mut()
+    }
+
+    pub(crate) fn close_mirror(&mut self) {
+        self.mirror = None;
+        self.mirror_failing = false;
+    }
+
+    pub(crate) fn set_max_frames(&mut self, max_frames: usize) {
+        self.max_frames = max_frames;
+        while self.frames.len() > self.retained_frames() {
+            self.frames.pop_front();
+        }
+    }
+
+    // The most recent frame is always kept so that screenshots work even if captures are disabled
+    fn retained_frames(&self) -> usize {
+        self.max_frames.max(1)
+    }
+
+    fn frame_to_rgba(
+        &self,
+        frame: &CapturedFrame,
+        palette: &Palette,
+        overscan: Overscan,
+    ) -> Vec<u8> {
+        let width = usize::from(jgnes_core::SCREEN_WIDTH);
+        let height = usize::from(self.timing_mode.visible_screen_height());
+
+        let mut rgba = vec![0; 4 * width * height];
+        colors::to_rgba(
+            &frame.frame_buffer,
+            frame.color_emphasis,
+            palette,
+            overscan,
+            self.timing_mode,
+            &mut rgba,
+        );
+        rgba
+    }
+
+    /// Convert the recorded frames to RGBA and start writing them to a file in the background.
+    pub(crate) fn spawn_capture_export(
+        &self,
+        nes_file_path: PathBuf,
+        format: CaptureFormat,
+        palette: &Palette,
+        overscan: Overscan,
+    ) {
+        if self.frames.is_empty() || self.max_frames == 0 {
+            log::warn!("No frames recorded, not saving capture");
+            return;
+        }
+
+        let frames: Vec<_> =
+            self.frames.iter().map(|frame| self.frame_to_rgba(frame, palette, overscan)).collect();
+
+        let timing_mode = self.timing_mode;
+        thread::spawn(move || {
+            let path = encode::timestamped_output_path(&nes_file_path, "capture")
+                .with_extension(file_extension(format));
+            match write_capture(&path, format, timing_mode, &frames) {
+                Ok(()) => {
+                    log::info!("Saved capture to '{}'", path.display());
+                }
+                Err(err) => {
+                    log::error!("Error saving capture to '{}': {err}", path.display());
+                }
+            }
+        });
+    }
+
+    /// Convert the most recently rendered frame to RGBA and start writing it to a PNG file in the
+    /// background.
+    pub(crate) fn spawn_screenshot_export(
+        &self,
+        nes_file_path: PathBuf,
+        palette: &Palette,
+        overscan: Overscan,
+    ) {
+        let Some(frame) = self.frames.back() else {
+            log::warn!("No frames rendered yet, not saving screenshot");
+            return;
+        };
+
+        let rgba = self.frame_to_rgba(frame, palette, overscan);
+        let timing_mode = self.timing_mode;
+        thread::spawn(move || {
+            let path =
+                encode::timestamped_output_path(&nes_file_path, "screenshot").with_extension("png");
+            match encode::write_png(&path, timing_mode, &rgba) {
+                Ok(()) => {
+                    log::info!("Saved screenshot to '{}'", path.display());
+                }
+                Err(err) => {
+                    log::error!("Error saving screenshot to '{}': {err}", path.display());
+                }
+            }
+        });
+    }
+}
+
+fn write_capture(
+    path: &Path,
+    format: CaptureFormat,
+    timing_mode: TimingMode,
+    frames: &[Vec<u8>],
+) -> anyhow::Result<()> {
+    match format {
+        CaptureFormat::Gif => encode::write_gif(path, timing_mode, frames),
+        CaptureFormat::Apng => {
+            let mut writer = encode::apng_writer(path, timing_mode, frames.len() as u32)?;
+            for frame in frames {
+                writer.write_image_data(frame)?;
+            }
+            writer.finish()?;
+
+            Ok(())
+        }
+    }
+}
+
+impl<R> RecordingRenderer<R>
+where
+    R: Renderer,
+    R::Err: Display,
+{
+    /// Render the most recently completed frame again, e.g. after frame skip is turned off and the
+    /// last few frames were never shown. Unlike [`jgnes_core::Emulator::force_render`], this never
+    /// shows a partially drawn frame and does not record the frame a second time.
+    pub(crate) fn render_last_frame(&mut self) -> Result<(), R::Err> {
+        let Some(frame) = self.frames.back() else {
+            return Ok(());
+        };
+
+        *self.osd_frame_buffer = *frame.frame_buffer;
+        osd::draw_lines(&mut self.osd_frame_buffer, &self.osd_lines);
+        if let Some(position) = self.osd_scrub_bar {
+            osd::draw_scrub_bar(&mut self.osd_frame_buffer, position);
+        }
+        let result = self.inner.render_frame(&self.osd_frame_buffer, frame.color_emphasis);
+
+        if let Some(mirror) = &mut self.mirror {
+            render_to_mirror(
+                mirror,
+                &mut self.mirror_failing,
+                &frame.frame_buffer,
+                frame.color_emphasis,
+            );
+        }
+
+        result
+    }
+}
+
+// The mirror window is secondary to the main window, so its errors are logged rather than returned.
+// Only the first error in a row is logged since a broken mirror would otherwise log every frame
+fn render_to_mirror<R>(
+    mirror: &mut R,
+    failing: &mut bool,
+    frame_buffer: &FrameBuffer,
+    color_emphasis: ColorEmphasis,
+) where
+    R: Renderer,
+    R::Err: Display,
+{
+    match mirror.render_frame(frame_buffer, color_emphasis) {
+        Ok(()) => *failing = false,
+        Err(err) => {
+            if !*failing {
+                log::warn!("Unable to render to the mirror window: {err}");
+            }
+            *failing = true;
+        }
+    }
+}
+
+impl<R> Renderer for RecordingRenderer<R>
+where
+    R: Renderer,
+    R::Err: Display,
+{
+    type Err = R::Err;
+
+    fn render_frame(
+        &mut self,
+        frame_buffer: &FrameBuffer,
+        color_emphasis: ColorEmphasis,
+    ) -> Result<(), Self::Err> {
+        // Reuse the oldest frame's allocation once the buffer is full
+        let captured = if self.frames.len() >= self.retained_frames() {
+            let mut captured = self.frames.pop_front().unwrap();
+            *captured.frame_buffer = *frame_buffer;
+            captured.color_emphasis = color_emphasis;
+            captured
+        } else {
+            CapturedFrame { frame_buffer: Box::new(*frame_buffer), color_emphasis }
+        };
+        self.frames.push_back(captured);
+
+        let result = if self.osd_lines.is_empty() && self.osd_scrub_bar.is_none() {
+            self.inner.render_frame(frame_buffer, color_emphasis)
+        } else {
+            *self.osd_frame_buffer = *frame_buffer;
+            osd::draw_lines(&mut self.osd_frame_buffer, &self.osd_lines);
+            if let Some(position) = self.osd_scrub_bar {
+                osd::draw_scrub_bar(&mut self.osd_frame_buffer, position);
+            }
+            self.inner.render_frame(&self.osd_frame_buffer, color_emphasis)
+        };
+
+        if let Some(mirror) = &mut self.mirror {
+            render_to_mirror(mirror, &mut self.mirror_failing, frame_buffer, color_emphasis);
+        }
+
+        result
+    }
+
+    fn set_timing_mode(&mut self, timing_mode: TimingMode) -> Result<(), Self::Err> {
+        self.timing_mode = timing_mode;
+        self.frames.clear();
+
+        if let Some(mirror) = &mut self.mirror {
+            mirror.set_timing_mode(timing_mode)?;
+        }
+        self.inner.set_timing_mode(timing_mode)
+    }
+
+    fn handle_window_resize(&mut self, width: u32, height: u32) {
+        self.inner.handle_window_resize(width, height);
+    }
+}
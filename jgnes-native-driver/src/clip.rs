@@ -0,0 +1,296 @@
+//! Export of recent gameplay to a shareable clip.
+//!
+//! The rewind buffer only stores a snapshot every few frames, so clips are produced by loading each
+//! snapshot into a separate headless emulator instance and re-running the frames in between using
+//! the joypad inputs that were recorded alongside the snapshot. Video is written as an animated PNG
+//! and audio is written as a WAV file next to it.
+
+use crate::encode;
+use crate::{AUDIO_OUTPUT_FREQUENCY, DISPLAY_RATE};
+use jgnes_core::audio::{DownsampleAction, DownsampleCounter, LowPassFilter};
+use jgnes_core::{
+    AudioPlayer, ColorEmphasis, EmulationError, EmulationState, Emulator, EmulatorConfig,
+    EmulatorCreateArgs, FrameBuffer, InputPoller, JoypadState, RamInit, Renderer, SaveWriter,
+    TickEffect, TimingMode,
+};
+use jgnes_renderer::colors;
+use jgnes_renderer::colors::Palette;
+use jgnes_renderer::config::Overscan;
+use std::cell::Cell;
+use std::convert::Infallible;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::thread;
+
+/// Joypad inputs and console resets for the frames following a rewind snapshot.
+///
+/// Inputs are recorded as changes keyed by the number of emulator ticks since the snapshot was
+/// taken, which is what makes it possible to deterministically re-run those frames later. Resets
+/// are recorded the same way so that replays do not desync when the player resets mid-game.
+#[derive(Debug, Clone)]
+pub(crate) struct InputLog {
+    initial: (JoypadState, JoypadState),
+    current: (JoypadState, JoypadState),
+    changes: Vec<InputChange>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct InputChange {
+    tick_offset: u32,
+    event: InputEvent,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum InputEvent {
+    Joypad(JoypadState, JoypadState),
+    SoftReset,
+    // Records the RAM init that was actually used so that a random seed is reproduced
+    HardReset { ram_init: RamInit },
+}
+
+impl InputLog {
+    pub(crate) fn new(initial: (JoypadState, JoypadState)) -> Self {
+        Self { initial, current: initial, changes: Vec::new() }
+    }
+
+    pub(crate) fn record(&mut self, tick_offset: u32, inputs: (JoypadState, JoypadState)) {
+        if inputs != self.current {
+            self.current = inputs;
+            self.changes
+                .push(InputChange { tick_offset, event: InputEvent::Joypad(inputs.0, inputs.1) });
+        }
+    }
+
+    pub(crate) fn record_soft_reset(&mut self, tick_offset: u32) {
+        self.changes.push(InputChange { tick_offset, event: InputEvent::SoftReset });
+    }
+
+    pub(crate) fn record_hard_reset(&mut self, tick_offset: u32, ram_init: RamInit) {
+        self.changes.push(InputChange { tick_offset, event: InputEvent::HardReset { ram_init } });
+    }
+}
+
+/// A rewind snapshot plus the inputs needed to replay from it to the next snapshot.
+pub(crate) struct ClipSegment {
+    pub(crate) state: EmulationState,
+    pub(crate) inputs: InputLog,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct ReplayInputPoller {
+    p1: Cell<JoypadState>,
+    p2: Cell<JoypadState>,
+}
+
+impl ReplayInputPoller {
+    pub(crate) fn set(&self, (p1, p2): (JoypadState, JoypadState)) {
+        self.p1.set(p1);
+        self.p2.set(p2);
+    }
+}
+
+impl InputPoller for ReplayInputPoller {
+    fn poll_p1_input(&self) -> JoypadState {
+        self.p1.get()
+    }
+
+    fn poll_p2_input(&self) -> JoypadState {
+        self.p2.get()
+    }
+}
+
+pub(crate) struct CaptureRenderer {
+    palette: Palette,
+    overscan: Overscan,
+    timing_mode: TimingMode,
+    pub(crate) frame: Vec<u8>,
+}
+
+impl CaptureRenderer {
+    pub(crate) fn new(palette: Palette, overscan: Overscan) -> Self {
+        let mut renderer = Self { palette, overscan, timing_mode: TimingMode::Ntsc, frame: vec![] };
+        renderer.resize_frame();
+        renderer
+    }
+
+    fn resize_frame(&mut self) {
+        let width = usize::from(jgnes_core::SCREEN_WIDTH);
+        let height = usize::from(self.timing_mode.visible_screen_height());
+        self.frame = vec![0; 4 * width * height];
+    }
+}
+
+impl Renderer for CaptureRenderer {
+    type Err = Infallible;
+
+    fn render_frame(
+        &mut self,
+        frame_buffer: &FrameBuffer,
+        color_emphasis: ColorEmphasis,
+    ) -> Result<(), Self::Err> {
+        colors::to_rgba(
+            frame_buffer,
+            color_emphasis,
+            &self.palette,
+            self.overscan,
+            self.timing_mode,
+            &mut self.frame,
+        );
+
+        Ok(())
+    }
+
+    fn set_timing_mode(&mut self, timing_mode: TimingMode) -> Result<(), Self::Err> {
+        self.timing_mode = timing_mode;
+        self.resize_frame();
+
+        Ok(())
+    }
+}
+
+pub(crate) struct CaptureAudioPlayer {
+    pub(crate) samples: Vec<f32>,
+    low_pass_filter: LowPassFilter,
+    downsample_counter: DownsampleCounter,
+}
+
+impl CaptureAudioPlayer {
+    pub(crate) fn new() -> Self {
+        Self {
+            samples: Vec::new(),
+            low_pass_filter: LowPassFilter::new(),
+            downsample_counter: DownsampleCounter::new(AUDIO_OUTPUT_FREQUENCY, DISPLAY_RATE, false),
+        }
+    }
+}
+
+impl AudioPlayer for CaptureAudioPlayer {
+    type Err = Infallible;
+
+    fn push_sample(&mut self, sample: f64) -> Result<(), Self::Err> {
+        self.low_pass_filter.collect_sample(sample);
+
+        if self.downsample_counter.increment() == DownsampleAction::OutputSample {
+            self.samples.push(self.low_pass_filter.output_sample() as f32);
+        }
+
+        Ok(())
+    }
+
+    fn set_timing_mode(&mut self, timing_mode: TimingMode) {
+        self.downsample_counter.set_timing_mode(timing_mode);
+    }
+}
+
+pub(crate) struct NullSaveWriter;
+
+impl SaveWriter for NullSaveWriter {
+    type Err = Infallible;
+
+    fn persist_sram(&mut self, _sram: &[u8]) -> Result<(), Self::Err> {
+        Ok(())
+    }
+}
+
+pub(crate) struct ClipExportArgs {
+    pub(crate) nes_file_path: PathBuf,
+    pub(crate) forced_timing_mode: Option<TimingMode>,
+    pub(crate) forced_mapper_number: Option<u16>,
+    pub(crate) patch_file_path: Option<PathBuf>,
+    pub(crate) emulator_config: EmulatorConfig,
+    pub(crate) palette: Palette,
+    pub(crate) overscan: Overscan,
+    pub(crate) frames_per_segment: u32,
+    pub(crate) segments: Vec<ClipSegment>,
+}
+
+/// Export a clip on a background thread so that the emulator does not stall while encoding.
+pub(crate) fn spawn_clip_export(args: ClipExportArgs) {
+    thread::spawn(move || match export_clip(args) {
+        Ok(video_path) => {
+            log::info!("Saved clip to '{}'", video_path.display());
+        }
+        Err(err) => {
+            log::error!("Error saving clip: {err}");
+        }
+    });
+}
+
+fn export_clip(args: ClipExportArgs) -> anyhow::Result<PathBuf> {
+    let ClipExportArgs {
+        nes_file_path,
+        forced_timing_mode,
+        forced_mapper_number,
+        patch_file_path,
+        emulator_config,
+        palette,
+        overscan,
+        frames_per_segment,
+        segments,
+    } = args;
+
+    let rom_bytes = crate::read_rom_file(&nes_file_path, patch_file_path.as_deref())?;
+    let input_poller = Rc::new(ReplayInputPoller::default());
+    let mut emulator = Emulator::create(EmulatorCreateArgs {
+        rom_bytes,
+        sav_bytes: None,
+        forced_timing_mode,
+        forced_mapper_number,
+        // RAM contents come from the snapshots
+        ram_init: RamInit::default(),
+        renderer: CaptureRenderer::new(palette, overscan),
+        audio_player: CaptureAudioPlayer::new(),
+        input_poller: Rc::clone(&input_poller),
+        save_writer: NullSaveWriter,
+    })?;
+
+    let base_path = encode::timestamped_output_path(&nes_file_path, "clip");
+    let video_path = base_path.with_extension("png");
+    let audio_path = base_path.with_extension("wav");
+
+    let timing_mode = emulator.timing_mode();
+    let num_frames = frames_per_segment * segments.len() as u32;
+    let mut png_writer = encode::apng_writer(&video_path, timing_mode, num_frames)?;
+
+    for ClipSegment { state, inputs } in segments {
+        emulator.load_state_snapshot(state);
+        input_poller.set(inputs.initial);
+
+        let mut changes = inputs.changes.iter().peekable();
+        let mut tick_offset = 0;
+        let mut frames_rendered = 0;
+        while frames_rendered < frames_per_segment {
+            while let Some(change) = changes.next_if(|change| change.tick_offset <= tick_offset) {
+                match change.event {
+                    InputEvent::Joypad(p1, p2) => input_poller.set((p1, p2)),
+                    InputEvent::SoftReset => emulator.soft_reset(),
+                    InputEvent::HardReset { ram_init } => {
+                        emulator.set_ram_init(ram_init);
+                        emulator = emulator.hard_reset(None);
+                    }
+                }
+            }
+
+            match emulator.tick(&emulator_config) {
+                Ok(TickEffect::None) => {}
+                Ok(TickEffect::FrameRendered) => {
+                    png_writer.write_image_data(&emulator.get_renderer().frame)?;
+                    frames_rendered += 1;
+                }
+                Err(err) => match err.error {
+                    EmulationError::Render(err)
+                    | EmulationError::Audio(err)
+                    | EmulationError::Save(err) => match err {},
+                },
+            }
+
+            tick_offset += 1;
+        }
+    }
+
+    png_writer.finish()?;
+
+    encode::write_wav(&audio_path, &emulator.get_audio_player_mut().samples)?;
+
+    Ok(video_path)
+}
@@ -1,13 +1,19 @@
-use jgnes_core::{EmulatorConfig, TimingMode};
+use crate::geometry::{WindowGeometry, WindowGeometryMap};
+use crate::power::LowPowerMode;
+use jgnes_core::{CartridgeInfo, EmulatorConfig, RamInit, TimingMode};
 use jgnes_proc_macros::{EnumDisplay, EnumFromStr};
+use jgnes_renderer::GpuAdapterSelector;
+use jgnes_renderer::colors::Palette;
 use jgnes_renderer::config::{
-    AspectRatio, GpuFilterMode, Overscan, RendererConfig, Scanlines, Shader, VSyncMode, WgpuBackend,
+    AspectRatio, ColorBlindFilter, CrtGeometry, GpuFilterMode, Overscan, PowerPreference,
+    RendererConfig, Scanlines, Shader, ShaderPreset, VSyncMode, WgpuBackend,
 };
 use sdl2::joystick::HatState;
 use sdl2::keyboard::Keycode;
 use serde::{Deserialize, Serialize};
 use std::fmt::{Display, Formatter};
-use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::sync::mpsc::{Receiver, Sender};
 use std::sync::{Arc, Mutex, mpsc};
 use std::time::Duration;
@@ -21,6 +27,44 @@ pub enum NativeRenderer {
     Wgpu,
 }
 
+/// How the emulator window is sized on displays with a scale factor other than 100%, e.g. Retina
+/// displays or fractional scaling on Wayland.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, EnumDisplay, EnumFromStr,
+)]
+pub enum HiDpiScaling {
+    /// Render at the display's native pixel resolution
+    #[default]
+    Native,
+    /// Render at the window's logical size and let the OS scale the output up to the display's
+    /// resolution, which is blurrier but cheaper for shaders that run per output pixel
+    Logical,
+}
+
+/// What sets the emulation speed.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, EnumDisplay, EnumFromStr,
+)]
+pub enum FramePacing {
+    /// Pace emulation by audio playback if audio sync is enabled, and by VSync otherwise
+    #[default]
+    Audio,
+    /// Present each frame as soon as it is emulated and pace emulation with a high-resolution timer
+    /// at the NES's native refresh rate (~60.0988Hz for NTSC), for variable refresh rate displays.
+    /// Overrides the VSync mode and audio sync.
+    Vrr,
+}
+
+/// Output format for short gameplay captures.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, EnumDisplay, EnumFromStr,
+)]
+pub enum CaptureFormat {
+    #[default]
+    Gif,
+    Apng,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct InputConfigBase<T> {
     pub up: Option<T>,
@@ -65,6 +109,14 @@ impl<T: Display> Display for InputConfigBase<T> {
     }
 }
 
+impl<T> InputConfigBase<T> {
+    fn iter(&self) -> impl Iterator<Item = &T> {
+        [&self.up, &self.left, &self.right, &self.down, &self.a, &self.b, &self.start, &self.select]
+            .into_iter()
+            .flatten()
+    }
+}
+
 fn fmt_option<T: Display>(option: Option<&T>) -> String {
     option.map_or("<None>".into(), ToString::to_string)
 }
@@ -191,17 +243,83 @@ impl InputConfigBase<KeyboardInput> {
     }
 }
 
+/// Rotation applied to D-pad inputs, e.g. for games played on a vertically rotated screen.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, EnumDisplay, EnumFromStr,
+)]
+pub enum DpadRotation {
+    #[default]
+    None,
+    /// Up becomes Right, Right becomes Down, etc.
+    Clockwise,
+    /// Up becomes Left, Left becomes Down, etc.
+    Counterclockwise,
+}
+
+/// Logical transforms applied to a player's NES inputs after keyboard/joystick mapping.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InputTransforms {
+    pub swap_a_b: bool,
+    /// Held A repeatedly presses and releases A
+    pub turbo_a: bool,
+    /// Held B repeatedly presses and releases B
+    pub turbo_b: bool,
+    pub dpad_rotation: DpadRotation,
+}
+
+impl Display for InputTransforms {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "swap_a_b={}, turbo_a={}, turbo_b={}, dpad_rotation={}",
+            self.swap_a_b, self.turbo_a, self.turbo_b, self.dpad_rotation
+        )
+    }
+}
+
+/// How joystick axes mapped to D-pad directions are converted to digital inputs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AnalogStickConfig {
+    /// Deadzone for the axis mapped to Left/Right; the global axis deadzone is used if not set
+    pub horizontal_deadzone: Option<u16>,
+    /// Deadzone for the axis mapped to Up/Down; the global axis deadzone is used if not set
+    pub vertical_deadzone: Option<u16>,
+    /// Width in degrees (0-90) of the angle window around each diagonal in which both directions
+    /// are pressed. 0 converts each axis independently.
+    pub diagonal_window: u8,
+}
+
+impl Display for AnalogStickConfig {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "horizontal_deadzone={}, vertical_deadzone={}, diagonal_window={}",
+            fmt_option(self.horizontal_deadzone.as_ref()),
+            fmt_option(self.vertical_deadzone.as_ref()),
+            self.diagonal_window
+        )
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct PlayerInputConfig {
     pub keyboard: KeyboardInputConfig,
     pub joystick: JoystickInputConfig,
+    #[serde(default)]
+    pub analog_stick: AnalogStickConfig,
+    #[serde(default)]
+    pub transforms: InputTransforms,
 }
 
 impl Display for PlayerInputConfig {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         writeln!(f)?;
         writeln!(f, "    Keyboard: {}", self.keyboard)?;
-        write!(f, "    Joystick: {}", self.joystick)?;
+        writeln!(f, "    Joystick: {}", self.joystick)?;
+        writeln!(f, "    Analog Stick: {}", self.analog_stick)?;
+        write!(f, "    Transforms: {}", self.transforms)?;
 
         Ok(())
     }
@@ -217,6 +335,105 @@ pub struct HotkeyConfig {
     pub hard_reset: Option<String>,
     pub fast_forward: Option<String>,
     pub rewind: Option<String>,
+    #[serde(default = "default_save_clip_hotkey")]
+    pub save_clip: Option<String>,
+    #[serde(default = "default_save_capture_hotkey")]
+    pub save_capture: Option<String>,
+    #[serde(default = "default_next_game_hotkey")]
+    pub next_game: Option<String>,
+    #[serde(default = "default_previous_game_hotkey")]
+    pub previous_game: Option<String>,
+    #[serde(default = "default_toggle_wav_capture_hotkey")]
+    pub toggle_wav_capture: Option<String>,
+    #[serde(default = "default_toggle_scroll_overlay_hotkey")]
+    pub toggle_scroll_overlay: Option<String>,
+    #[serde(default = "default_toggle_input_display_hotkey")]
+    pub toggle_input_display: Option<String>,
+    #[serde(default = "default_timer_start_split_hotkey")]
+    pub timer_start_split: Option<String>,
+    #[serde(default = "default_timer_reset_hotkey")]
+    pub timer_reset: Option<String>,
+    #[serde(default = "default_record_macro_hotkey")]
+    pub record_macro: Option<String>,
+    #[serde(default = "default_play_macro_hotkey")]
+    pub play_macro: Option<String>,
+    #[serde(default = "default_rewind_forward_hotkey")]
+    pub rewind_forward: Option<String>,
+    #[serde(default = "default_dump_register_writes_hotkey")]
+    pub dump_register_writes: Option<String>,
+    #[serde(default = "default_undo_load_state_hotkey")]
+    pub undo_load_state: Option<String>,
+}
+
+// Configs saved before the save clip hotkey was added should still get the default mapping
+#[allow(clippy::unnecessary_wraps)]
+fn default_save_clip_hotkey() -> Option<String> {
+    Some(Keycode::F8.name())
+}
+
+#[allow(clippy::unnecessary_wraps)]
+fn default_save_capture_hotkey() -> Option<String> {
+    Some(Keycode::F7.name())
+}
+
+#[allow(clippy::unnecessary_wraps)]
+fn default_next_game_hotkey() -> Option<String> {
+    Some(Keycode::PageDown.name())
+}
+
+#[allow(clippy::unnecessary_wraps)]
+fn default_previous_game_hotkey() -> Option<String> {
+    Some(Keycode::PageUp.name())
+}
+
+#[allow(clippy::unnecessary_wraps)]
+fn default_toggle_wav_capture_hotkey() -> Option<String> {
+    Some(Keycode::F12.name())
+}
+
+#[allow(clippy::unnecessary_wraps)]
+fn default_toggle_scroll_overlay_hotkey() -> Option<String> {
+    Some(Keycode::F10.name())
+}
+
+#[allow(clippy::unnecessary_wraps)]
+fn default_toggle_input_display_hotkey() -> Option<String> {
+    Some(Keycode::F2.name())
+}
+
+#[allow(clippy::unnecessary_wraps)]
+fn default_timer_start_split_hotkey() -> Option<String> {
+    Some(Keycode::Home.name())
+}
+
+#[allow(clippy::unnecessary_wraps)]
+fn default_timer_reset_hotkey() -> Option<String> {
+    Some(Keycode::End.name())
+}
+
+#[allow(clippy::unnecessary_wraps)]
+fn default_record_macro_hotkey() -> Option<String> {
+    Some(Keycode::Insert.name())
+}
+
+#[allow(clippy::unnecessary_wraps)]
+fn default_play_macro_hotkey() -> Option<String> {
+    Some(Keycode::Delete.name())
+}
+
+#[allow(clippy::unnecessary_wraps)]
+fn default_rewind_forward_hotkey() -> Option<String> {
+    Some(Keycode::Backslash.name())
+}
+
+#[allow(clippy::unnecessary_wraps)]
+fn default_dump_register_writes_hotkey() -> Option<String> {
+    Some(Keycode::F11.name())
+}
+
+#[allow(clippy::unnecessary_wraps)]
+fn default_undo_load_state_hotkey() -> Option<String> {
+    Some(Keycode::Backspace.name())
 }
 
 impl Default for HotkeyConfig {
@@ -230,6 +447,20 @@ impl Default for HotkeyConfig {
             hard_reset: Some(Keycode::F4.name()),
             fast_forward: Some(Keycode::Tab.name()),
             rewind: Some(Keycode::Backquote.name()),
+            save_clip: default_save_clip_hotkey(),
+            save_capture: default_save_capture_hotkey(),
+            next_game: default_next_game_hotkey(),
+            previous_game: default_previous_game_hotkey(),
+            toggle_wav_capture: default_toggle_wav_capture_hotkey(),
+            toggle_scroll_overlay: default_toggle_scroll_overlay_hotkey(),
+            toggle_input_display: default_toggle_input_display_hotkey(),
+            timer_start_split: default_timer_start_split_hotkey(),
+            timer_reset: default_timer_reset_hotkey(),
+            record_macro: default_record_macro_hotkey(),
+            play_macro: default_play_macro_hotkey(),
+            rewind_forward: default_rewind_forward_hotkey(),
+            dump_register_writes: default_dump_register_writes_hotkey(),
+            undo_load_state: default_undo_load_state_hotkey(),
         }
     }
 }
@@ -244,7 +475,29 @@ impl Display for HotkeyConfig {
         writeln!(f, "    Soft Reset: {}", fmt_option(self.soft_reset.as_ref()))?;
         writeln!(f, "    Hard Reset: {}", fmt_option(self.hard_reset.as_ref()))?;
         writeln!(f, "    Fast Forward: {}", fmt_option(self.fast_forward.as_ref()))?;
-        write!(f, "    Rewind: {}", fmt_option(self.rewind.as_ref()))?;
+        writeln!(f, "    Rewind: {}", fmt_option(self.rewind.as_ref()))?;
+        writeln!(f, "    Save Clip: {}", fmt_option(self.save_clip.as_ref()))?;
+        writeln!(f, "    Save Capture: {}", fmt_option(self.save_capture.as_ref()))?;
+        writeln!(f, "    Next Game: {}", fmt_option(self.next_game.as_ref()))?;
+        writeln!(f, "    Previous Game: {}", fmt_option(self.previous_game.as_ref()))?;
+        writeln!(f, "    Toggle WAV Capture: {}", fmt_option(self.toggle_wav_capture.as_ref()))?;
+        writeln!(
+            f,
+            "    Toggle Scroll Overlay: {}",
+            fmt_option(self.toggle_scroll_overlay.as_ref())
+        )?;
+        writeln!(
+            f,
+            "    Toggle Input Display: {}",
+            fmt_option(self.toggle_input_display.as_ref())
+        )?;
+        writeln!(f, "    Timer Start/Split: {}", fmt_option(self.timer_start_split.as_ref()))?;
+        writeln!(f, "    Timer Reset: {}", fmt_option(self.timer_reset.as_ref()))?;
+        writeln!(f, "    Record Macro: {}", fmt_option(self.record_macro.as_ref()))?;
+        writeln!(f, "    Play Macro: {}", fmt_option(self.play_macro.as_ref()))?;
+        writeln!(f, "    Rewind Forward: {}", fmt_option(self.rewind_forward.as_ref()))?;
+        write!(f, "    Dump Register Writes: {}", fmt_option(self.dump_register_writes.as_ref()))?;
+        writeln!(f, "    Undo Load State: {}", fmt_option(self.undo_load_state.as_ref()))?;
 
         Ok(())
     }
@@ -258,6 +511,10 @@ pub struct InputConfig {
     pub hotkeys: HotkeyConfig,
     pub axis_deadzone: u16,
     pub allow_opposite_directions: bool,
+    /// If opposing directions are pressed simultaneously, use the most recently pressed one
+    /// instead of a fixed preference. No effect if opposing directions are allowed.
+    #[serde(default)]
+    pub prefer_last_pressed_direction: bool,
 }
 
 impl Default for InputConfig {
@@ -276,14 +533,19 @@ impl Default for InputConfig {
             p1: PlayerInputConfig {
                 keyboard: p1_keyboard,
                 joystick: JoystickInputConfig::default(),
+                analog_stick: AnalogStickConfig::default(),
+                transforms: InputTransforms::default(),
             },
             p2: PlayerInputConfig {
                 keyboard: KeyboardInputConfig::default(),
                 joystick: JoystickInputConfig::default(),
+                analog_stick: AnalogStickConfig::default(),
+                transforms: InputTransforms::default(),
             },
             hotkeys: HotkeyConfig::default(),
             axis_deadzone: 5000,
             allow_opposite_directions: false,
+            prefer_last_pressed_direction: false,
         }
     }
 }
@@ -296,6 +558,84 @@ impl Display for InputConfig {
         writeln!(f, "  Hotkeys: {}", self.hotkeys)?;
         writeln!(f, "  axis_deadzone: {}", self.axis_deadzone)?;
         writeln!(f, "  allow_opposite_directions: {}", self.allow_opposite_directions)?;
+        writeln!(f, "  prefer_last_pressed_direction: {}", self.prefer_last_pressed_direction)?;
+
+        Ok(())
+    }
+}
+
+/// A named set of P1/P2/hotkey mappings that can be swapped into the active input config.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InputProfile {
+    pub name: String,
+    pub p1: PlayerInputConfig,
+    pub p2: PlayerInputConfig,
+    #[serde(default)]
+    pub hotkeys: HotkeyConfig,
+}
+
+impl InputProfile {
+    #[must_use]
+    pub fn from_input_config(name: String, input_config: &InputConfig) -> Self {
+        Self {
+            name,
+            p1: input_config.p1.clone(),
+            p2: input_config.p2.clone(),
+            hotkeys: input_config.hotkeys.clone(),
+        }
+    }
+
+    /// Overwrite the mappings in the given input config with this profile's mappings. Settings
+    /// that are not mappings (e.g. axis deadzone) are left unchanged.
+    pub fn apply_to(&self, input_config: &mut InputConfig) {
+        input_config.p1 = self.p1.clone();
+        input_config.p2 = self.p2.clone();
+        input_config.hotkeys = self.hotkeys.clone();
+    }
+
+    /// Check that every keyboard mapping in this profile refers to a valid SDL keycode name.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error containing the first invalid keycode name found.
+    pub fn validate(&self) -> Result<(), String> {
+        let hotkeys = [
+            &self.hotkeys.quit,
+            &self.hotkeys.toggle_fullscreen,
+            &self.hotkeys.save_state,
+            &self.hotkeys.load_state,
+            &self.hotkeys.soft_reset,
+            &self.hotkeys.hard_reset,
+            &self.hotkeys.fast_forward,
+            &self.hotkeys.rewind,
+            &self.hotkeys.save_clip,
+            &self.hotkeys.save_capture,
+            &self.hotkeys.next_game,
+            &self.hotkeys.previous_game,
+            &self.hotkeys.toggle_wav_capture,
+            &self.hotkeys.toggle_scroll_overlay,
+            &self.hotkeys.toggle_input_display,
+            &self.hotkeys.timer_start_split,
+            &self.hotkeys.timer_reset,
+            &self.hotkeys.record_macro,
+            &self.hotkeys.play_macro,
+            &self.hotkeys.rewind_forward,
+            &self.hotkeys.dump_register_writes,
+            &self.hotkeys.undo_load_state,
+        ];
+
+        let keycode_names = self
+            .p1
+            .keyboard
+            .iter()
+            .chain(self.p2.keyboard.iter())
+            .map(|input| &input.0)
+            .chain(hotkeys.into_iter().flatten());
+        for name in keycode_names {
+            if Keycode::from_name(name).is_none() {
+                return Err(format!("invalid keycode name: {name}"));
+            }
+        }
 
         Ok(())
     }
@@ -305,11 +645,55 @@ impl Display for InputConfig {
 pub struct JgnesNativeConfig {
     pub nes_file_path: String,
     pub forced_timing_mode: Option<TimingMode>,
+    pub forced_mapper_number: Option<u16>,
+    /// IPS/BPS patch to apply to the ROM; if not set, a patch file next to the ROM with the same
+    /// name will be applied if one exists
+    pub patch_file_path: Option<String>,
+    /// Power-on contents of CPU and PPU RAM
+    pub ram_init: RamInit,
+    /// Additional ROMs that can be switched to at runtime using the next/previous game hotkeys
+    pub playlist: Vec<String>,
+    /// Directory to store save files and save states in; if not set, they are stored next to the
+    /// ROM
+    pub save_dir: Option<PathBuf>,
+    /// If set, listen for remote control commands on this localhost TCP port
+    pub remote_control_port: Option<u16>,
+    /// If true, hard reset with the new ROM contents whenever the ROM file changes on disk
+    pub watch_rom_file: bool,
+    /// If true, warn when a game runs in a different timing mode than its header specifies
+    pub warn_on_timing_mode_mismatch: bool,
+    /// If set, write all audio output to this WAV file, starting at launch
+    pub wav_capture_path: Option<PathBuf>,
+    /// If set, log per-frame timing to this CSV file for
+    /// [`FRAME_TIMING_LOG_DURATION`](crate::FRAME_TIMING_LOG_DURATION) after launch
+    pub frame_timing_log_path: Option<PathBuf>,
+    /// If true, restart the speedrun timer whenever the console is reset
+    pub speedrun_timer_auto_start: bool,
+    /// File to save each speedrun timer run to when it ends; if not set, runs are not saved
+    pub last_run_path: Option<PathBuf>,
+    /// If set, send speedrun timer events to LiveSplit Server on this localhost TCP port
+    pub livesplit_port: Option<u16>,
     pub window_width: u32,
     pub window_height: u32,
+    /// Window positions and sizes saved from previous runs; if one was saved for the current
+    /// display configuration, it takes precedence over `window_width` and `window_height`
+    pub saved_window_geometry: WindowGeometryMap,
     pub renderer: NativeRenderer,
+    pub hidpi_scaling: HiDpiScaling,
     pub wgpu_backend: WgpuBackend,
+    /// GPU adapter for the wgpu renderer to use, or `None` to let wgpu choose
+    pub gpu_adapter: Option<GpuAdapterSelector>,
     pub launch_fullscreen: bool,
+    /// Keep the emulator window above other windows
+    pub always_on_top: bool,
+    /// Hide the emulator window's title bar and border
+    pub borderless: bool,
+    /// Open a second window that shows the same frames as the main window, without the OSD; e.g.
+    /// as a clean feed for capture
+    pub mirror_window: bool,
+    /// SDL video driver to use instead of SDL's default, e.g. `kmsdrm` to run without a window
+    /// system
+    pub sdl_video_driver: Option<String>,
     pub shared_config: JgnesSharedConfig,
 }
 
@@ -317,46 +701,161 @@ impl Display for JgnesNativeConfig {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         writeln!(f, "nes_file_path: {}", self.nes_file_path)?;
         writeln!(f, "forced_timing_mode: {}", fmt_option(self.forced_timing_mode.as_ref()))?;
+        writeln!(f, "forced_mapper_number: {}", fmt_option(self.forced_mapper_number.as_ref()))?;
+        writeln!(f, "patch_file_path: {}", fmt_option(self.patch_file_path.as_ref()))?;
+        writeln!(f, "ram_init_pattern: {}", self.ram_init.pattern)?;
+        writeln!(f, "ram_init_seed: {}", fmt_option(self.ram_init.seed.as_ref()))?;
+        writeln!(f, "playlist: {:?}", self.playlist)?;
+        writeln!(
+            f,
+            "save_dir: {}",
+            fmt_option(self.save_dir.as_ref().map(|path| path.display()).as_ref())
+        )?;
+        writeln!(f, "remote_control_port: {}", fmt_option(self.remote_control_port.as_ref()))?;
+        writeln!(f, "watch_rom_file: {}", self.watch_rom_file)?;
+        writeln!(f, "warn_on_timing_mode_mismatch: {}", self.warn_on_timing_mode_mismatch)?;
+        writeln!(
+            f,
+            "wav_capture_path: {}",
+            fmt_option(self.wav_capture_path.as_ref().map(|path| path.display()).as_ref())
+        )?;
+        writeln!(
+            f,
+            "frame_timing_log_path: {}",
+            fmt_option(self.frame_timing_log_path.as_ref().map(|path| path.display()).as_ref())
+        )?;
+        writeln!(f, "speedrun_timer_auto_start: {}", self.speedrun_timer_auto_start)?;
+        writeln!(
+            f,
+            "last_run_path: {}",
+            fmt_option(self.last_run_path.as_ref().map(|path| path.display()).as_ref())
+        )?;
+        writeln!(f, "livesplit_port: {}", fmt_option(self.livesplit_port.as_ref()))?;
         writeln!(f, "window_width: {}", self.window_width)?;
         writeln!(f, "window_height: {}", self.window_height)?;
+        writeln!(f, "saved_window_geometry: {:?}", self.saved_window_geometry)?;
         writeln!(f, "renderer: {}", self.renderer)?;
+        writeln!(f, "hidpi_scaling: {}", self.hidpi_scaling)?;
         writeln!(f, "wgpu_backend: {}", self.wgpu_backend)?;
+        writeln!(f, "gpu_adapter: {}", fmt_option(self.gpu_adapter.as_ref()))?;
         writeln!(f, "launch_fullscreen: {}", self.launch_fullscreen)?;
+        writeln!(f, "always_on_top: {}", self.always_on_top)?;
+        writeln!(f, "borderless: {}", self.borderless)?;
+        writeln!(f, "mirror_window: {}", self.mirror_window)?;
+        writeln!(f, "sdl_video_driver: {}", fmt_option(self.sdl_video_driver.as_ref()))?;
 
         Ok(())
     }
 }
 
+/// Load a multi-pass shader preset from a TOML file.
+///
+/// # Errors
+///
+/// This function will return an error if the file cannot be read, if it is not a valid preset
+/// file, or if the preset fails validation.
+pub fn load_shader_preset<P: AsRef<Path>>(path: P) -> anyhow::Result<ShaderPreset> {
+    let path = path.as_ref();
+    let preset_str = fs::read_to_string(path)?;
+    let preset: ShaderPreset = toml::from_str(&preset_str).map_err(|err| {
+        anyhow::Error::msg(format!("invalid shader preset {}: {err}", path.display()))
+    })?;
+    preset.validate().map_err(anyhow::Error::msg)?;
+
+    Ok(preset)
+}
+
 /// Configuration that can be modified while the emulator is running.
 #[derive(Debug, Clone)]
 pub struct JgnesDynamicConfig {
     pub gpu_filter_mode: GpuFilterMode,
     pub shader: Shader,
+    pub shader_preset: Option<ShaderPreset>,
     pub scanlines: Scanlines,
+    pub scanline_intensity: f64,
+    pub crt_geometry: CrtGeometry,
+    pub color_blind_filter: ColorBlindFilter,
+    pub frame_blend_strength: f64,
+    pub palette: Palette,
     pub aspect_ratio: AspectRatio,
     pub overscan: Overscan,
     pub forced_integer_height_scaling: bool,
     pub vsync_mode: VSyncMode,
+    pub frame_pacing: FramePacing,
+    pub low_power_mode: LowPowerMode,
     pub remove_sprite_limit: bool,
+    pub reduce_sprite_flicker: bool,
     pub pal_black_border: bool,
     pub sync_to_audio: bool,
     pub internal_audio_buffer_size: u32,
     pub audio_sync_threshold: u32,
     pub audio_refresh_rate_adjustment: bool,
     pub silence_ultrasonic_triangle_output: bool,
+    pub linear_apu_mixing: bool,
+    pub ramp_triangle_silencing: bool,
+    pub dmc_declick_filter: bool,
+    pub log_sprite_0_hits: bool,
     pub fast_forward_multiplier: u8,
     pub rewind_buffer_len: Duration,
+    /// Frames between rewind snapshots; longer intervals use less memory but rewind in coarser
+    /// steps
+    pub rewind_interval_frames: u8,
+    /// Maximum memory used by rewind snapshots in megabytes, or 0 for no limit
+    pub rewind_memory_limit_mb: u32,
+    /// How much of the rewind buffer to export when saving a clip
+    pub clip_len: Duration,
+    /// How many seconds of recent frames to keep for GIF/APNG captures
+    pub capture_len: Duration,
+    pub capture_format: CaptureFormat,
+    /// Require pressing the quit hotkey twice, or closing the window twice, to quit
+    pub confirm_quit: bool,
+    /// Require pressing the save state hotkey twice if the slot already has a save state
+    pub confirm_overwrite_state: bool,
+    /// Require pressing the hard reset hotkey twice to hard reset
+    pub confirm_hard_reset: bool,
     pub input_config: InputConfig,
 }
 
 impl JgnesDynamicConfig {
+    /// Disable all shader effects, for GPUs that are too slow to run them at full speed (e.g. on a
+    /// Raspberry Pi).
+    pub fn apply_low_spec_preset(&mut self) {
+        self.shader = Shader::None;
+        self.shader_preset = None;
+        self.scanlines = Scanlines::None;
+        self.crt_geometry = CrtGeometry::default();
+        self.frame_blend_strength = 0.0;
+    }
+
     pub(crate) fn to_renderer_config(&self, wgpu_backend: WgpuBackend) -> RendererConfig {
+        let low_power = self.low_power_mode.is_active();
+
+        let vsync_mode = match self.frame_pacing {
+            // Presenting frames faster than the display refreshes only burns power
+            FramePacing::Audio if low_power => VSyncMode::Enabled,
+            FramePacing::Audio => self.vsync_mode,
+            // Waiting for vertical blank would fight the timer
+            FramePacing::Vrr => VSyncMode::Adaptive,
+        };
+
         RendererConfig {
-            vsync_mode: self.vsync_mode,
+            vsync_mode,
             wgpu_backend,
+            gpu_adapter: None,
+            power_preference: if low_power {
+                PowerPreference::LowPower
+            } else {
+                PowerPreference::HighPerformance
+            },
             gpu_filter_mode: self.gpu_filter_mode,
             shader: self.shader,
+            shader_preset: self.shader_preset.clone(),
             scanlines: self.scanlines,
+            scanline_intensity: self.scanline_intensity,
+            crt_geometry: self.crt_geometry,
+            color_blind_filter: self.color_blind_filter,
+            frame_blend_strength: self.frame_blend_strength,
+            palette: self.palette.clone(),
             aspect_ratio: self.aspect_ratio,
             overscan: self.overscan,
             forced_integer_height_scaling: self.forced_integer_height_scaling,
@@ -364,24 +863,50 @@ impl JgnesDynamicConfig {
         }
     }
 
+    pub(crate) fn audio_sync_enabled(&self) -> bool {
+        self.sync_to_audio && self.frame_pacing == FramePacing::Audio
+    }
+
+    // The 60Hz adjustment is only useful when frames are paced to a 60Hz display; timer pacing
+    // always runs at the native rate
+    pub(crate) fn audio_refresh_rate_adjustment_enabled(&self) -> bool {
+        self.audio_refresh_rate_adjustment && self.frame_pacing == FramePacing::Audio
+    }
+
     pub(crate) fn update_emulator_config(&self, emulator_config: &mut EmulatorConfig) {
         emulator_config.remove_sprite_limit = self.remove_sprite_limit;
+        emulator_config.reduce_sprite_flicker = self.reduce_sprite_flicker;
         emulator_config.pal_black_border = self.pal_black_border;
         emulator_config.silence_ultrasonic_triangle_output =
             self.silence_ultrasonic_triangle_output;
+        emulator_config.linear_apu_mixing = self.linear_apu_mixing;
+        emulator_config.ramp_triangle_silencing = self.ramp_triangle_silencing;
+        emulator_config.dmc_declick_filter = self.dmc_declick_filter;
+        emulator_config.log_sprite_0_hits = self.log_sprite_0_hits;
     }
 }
 
 impl Display for JgnesDynamicConfig {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         writeln!(f, "remove_sprite_limit: {}", self.remove_sprite_limit)?;
+        writeln!(f, "reduce_sprite_flicker: {}", self.reduce_sprite_flicker)?;
         writeln!(f, "gpu_filter_mode: {}", self.gpu_filter_mode)?;
         writeln!(f, "shader: {}", self.shader)?;
+        match &self.shader_preset {
+            Some(shader_preset) => writeln!(f, "shader_preset: {shader_preset}")?,
+            None => writeln!(f, "shader_preset: None")?,
+        }
         writeln!(f, "scanlines: {}", self.scanlines)?;
+        writeln!(f, "scanline_intensity: {}", self.scanline_intensity)?;
+        writeln!(f, "crt_geometry: {}", self.crt_geometry)?;
+        writeln!(f, "color_blind_filter: {}", self.color_blind_filter)?;
+        writeln!(f, "frame_blend_strength: {}", self.frame_blend_strength)?;
         writeln!(f, "aspect_ratio: {}", self.aspect_ratio)?;
         writeln!(f, "overscan: {}", self.overscan)?;
         writeln!(f, "forced_integer_height_scaling: {}", self.forced_integer_height_scaling)?;
         writeln!(f, "vsync_mode: {}", self.vsync_mode)?;
+        writeln!(f, "frame_pacing: {}", self.frame_pacing)?;
+        writeln!(f, "low_power_mode: {}", self.low_power_mode)?;
         writeln!(f, "pal_black_border: {}", self.pal_black_border)?;
         writeln!(f, "sync_to_audio: {}", self.sync_to_audio)?;
         writeln!(f, "internal_audio_buffer_size: {}", self.internal_audio_buffer_size)?;
@@ -392,8 +917,20 @@ impl Display for JgnesDynamicConfig {
             "silence_ultrasonic_triangle_output: {}",
             self.silence_ultrasonic_triangle_output
         )?;
+        writeln!(f, "linear_apu_mixing: {}", self.linear_apu_mixing)?;
+        writeln!(f, "ramp_triangle_silencing: {}", self.ramp_triangle_silencing)?;
+        writeln!(f, "dmc_declick_filter: {}", self.dmc_declick_filter)?;
+        writeln!(f, "log_sprite_0_hits: {}", self.log_sprite_0_hits)?;
         writeln!(f, "fast_forward_multiplier: {}", self.fast_forward_multiplier)?;
         writeln!(f, "rewind_buffer_len_seconds: {}", self.rewind_buffer_len.as_secs())?;
+        writeln!(f, "rewind_interval_frames: {}", self.rewind_interval_frames)?;
+        writeln!(f, "rewind_memory_limit_mb: {}", self.rewind_memory_limit_mb)?;
+        writeln!(f, "clip_len_seconds: {}", self.clip_len.as_secs())?;
+        writeln!(f, "capture_len_seconds: {}", self.capture_len.as_secs())?;
+        writeln!(f, "capture_format: {}", self.capture_format)?;
+        writeln!(f, "confirm_quit: {}", self.confirm_quit)?;
+        writeln!(f, "confirm_overwrite_state: {}", self.confirm_overwrite_state)?;
+        writeln!(f, "confirm_hard_reset: {}", self.confirm_hard_reset)?;
         writeln!(f, "input_config: {}", self.input_config)?;
 
         Ok(())
@@ -402,22 +939,8 @@ impl Display for JgnesDynamicConfig {
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum InputType {
-    Keyboard = 0,
-    Gamepad = 1,
-}
-
-impl InputType {
-    fn to_discriminant(self) -> u8 {
-        self as u8
-    }
-
-    pub(crate) fn from_discriminant(discriminant: u8) -> Option<Self> {
-        match discriminant {
-            0 => Some(Self::Keyboard),
-            1 => Some(Self::Gamepad),
-            _ => None,
-        }
-    }
+    Keyboard,
+    Gamepad,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -426,32 +949,121 @@ pub enum InputCollectResult {
     Gamepad(JoystickInput),
 }
 
-/// A wrapper around shared dynamic configuration state and signals that the emulator driver can
-/// send to the emulator.
+/// A request to load a different ROM into an already running emulator, keeping the existing window.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RomLoadRequest {
+    pub nes_file_path: String,
+    pub patch_file_path: Option<String>,
+    pub forced_mapper_number: Option<u16>,
+}
+
+/// A command for a running emulator, processed by the emulator's run loop.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EmulatorCommand {
+    /// Stop the emulator and close its window
+    Quit,
+    /// Re-read the shared dynamic config
+    ReloadConfig,
+    /// Wait for the next keyboard or gamepad input and send it back over the input reconfigure
+    /// channel
+    ConfigureInput(InputType),
+    /// Save state to the given save state slot of the current ROM
+    SaveState {
+        slot: u8,
+    },
+    /// Load state from the given save state slot of the current ROM
+    LoadState {
+        slot: u8,
+    },
+    /// Save state to the given file. Save states are portable between frontends, so the file can
+    /// also be imported into the web frontend
+    ExportState(PathBuf),
+    /// Load state from the given file, which may have been exported by a different frontend
+    ImportState(PathBuf),
+    /// Log per-frame timing to the given CSV file for
+    /// [`FRAME_TIMING_LOG_DURATION`](crate::FRAME_TIMING_LOG_DURATION), replacing any log that is
+    /// already in progress
+    RecordFrameTiming(PathBuf),
+    SoftReset,
+    HardReset,
+    /// Switch to a different ROM without closing the window
+    LoadRom(RomLoadRequest),
+    /// Pause or unpause emulation; the window keeps processing events while paused
+    SetPaused(bool),
+    /// Pause or unpause emulation on behalf of a frontend while one of its own dialogs is open.
+    /// This is tracked separately from [`EmulatorCommand::SetPaused`] so that closing the dialog
+    /// does not resume a game that the user paused
+    SetFrontendPaused(bool),
+    /// Resume emulation after it was automatically paused because of an error; see
+    /// [`EmulatorStatus::error_pause`]
+    ResumeAfterError,
+    /// While paused, emulate until the next frame is rendered and then stay paused
+    StepFrame,
+    /// Save the most recently rendered frame as a PNG next to the ROM file
+    Screenshot,
+    /// Change the window flags that were initially set by [`JgnesNativeConfig::always_on_top`] and
+    /// [`JgnesNativeConfig::borderless`]
+    SetWindowFlags {
+        always_on_top: bool,
+        borderless: bool,
+    },
+}
+
+/// Status of a running emulator, periodically updated by the emulator's run loop.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct EmulatorStatus {
+    pub running: bool,
+    pub rom_file_name: Option<String>,
+    pub paused: bool,
+    /// Frames rendered per second, averaged over the last second
+    pub fps: f64,
+    /// Frames rendered since the current ROM was loaded
+    pub frame_count: u64,
+    /// Frames emulated since power on; unlike `frame_count`, this is restored by loading a state
+    pub emulated_frame_count: u64,
+    /// CPU cycles emulated since power on; this is also restored by loading a state
+    pub cpu_cycle_count: u64,
+    /// Approximate memory used by rewind snapshots, in bytes
+    pub rewind_buffer_size: usize,
+    /// How far back the rewind buffer currently goes
+    pub rewind_buffer_secs: f64,
+    /// Set while emulation is paused because of a recoverable error that the user should decide
+    /// how to handle, such as a failed save write
+    pub error_pause: Option<String>,
+    pub cartridge_info: Option<CartridgeInfo>,
+    /// GPU adapter used by the wgpu renderer, including its backend and driver
+    pub gpu_adapter: Option<String>,
+}
+
+/// A wrapper around shared dynamic configuration state and a channel that the emulator driver can
+/// use to send commands to the emulator.
 #[derive(Debug, Clone)]
 pub struct JgnesSharedConfig {
     pub(crate) dynamic_config: Arc<Mutex<JgnesDynamicConfig>>,
-    pub(crate) config_reload_signal: Arc<AtomicBool>,
-    pub(crate) quit_signal: Arc<AtomicBool>,
+    command_sender: Sender<EmulatorCommand>,
+    pub(crate) command_receiver: Arc<Mutex<Receiver<EmulatorCommand>>>,
     pub(crate) input_reconfigure_sender: Sender<Option<InputCollectResult>>,
-    pub(crate) input_reconfigure_signal: Arc<AtomicU8>,
+    pub(crate) status: Arc<Mutex<EmulatorStatus>>,
+    pub(crate) crash_state: Arc<Mutex<Option<Vec<u8>>>>,
+    pub(crate) window_geometry: Arc<Mutex<Option<(String, WindowGeometry)>>>,
 }
 
 impl JgnesSharedConfig {
-    pub(crate) const NO_INPUT_RECONFIGURE: u8 = u8::MAX;
-
     #[must_use]
     pub fn new(
         initial_dynamic_config: JgnesDynamicConfig,
     ) -> (Self, Receiver<Option<InputCollectResult>>) {
+        let (command_sender, command_receiver) = mpsc::channel();
         let (input_reconfigure_sender, input_reconfigure_recv) = mpsc::channel();
 
         let config = Self {
             dynamic_config: Arc::new(Mutex::new(initial_dynamic_config)),
-            config_reload_signal: Arc::new(AtomicBool::new(false)),
-            quit_signal: Arc::new(AtomicBool::new(false)),
+            command_sender,
+            command_receiver: Arc::new(Mutex::new(command_receiver)),
             input_reconfigure_sender,
-            input_reconfigure_signal: Arc::new(AtomicU8::new(Self::NO_INPUT_RECONFIGURE)),
+            status: Arc::default(),
+            crash_state: Arc::default(),
+            window_geometry: Arc::default(),
         };
 
         (config, input_reconfigure_recv)
@@ -462,25 +1074,80 @@ impl JgnesSharedConfig {
         &self.dynamic_config
     }
 
+    /// The emulator's most recently reported status.
+    ///
+    /// # Panics
+    ///
+    /// This method will panic if the emulator thread panicked while updating the status.
+    #[must_use]
+    pub fn status(&self) -> EmulatorStatus {
+        self.status.lock().unwrap().clone()
+    }
+
+    /// Take the save state that was captured when the emulator last stopped due to a fatal error,
+    /// if any. This is intended for crash reports.
+    ///
+    /// # Panics
+    ///
+    /// This method will panic if the emulator thread panicked while storing the state.
+    #[must_use]
+    pub fn take_crash_state(&self) -> Option<Vec<u8>> {
+        self.crash_state.lock().unwrap().take()
+    }
+
+    /// Take the most recent geometry of the emulator window along with the display configuration
+    /// key that it should be saved under, so that it can be passed back in
+    /// [`JgnesNativeConfig::saved_window_geometry`] next time. This is updated periodically while
+    /// the emulator is running and kept after it stops.
+    ///
+    /// # Panics
+    ///
+    /// This method will panic if the emulator thread panicked while storing the geometry.
+    #[must_use]
+    pub fn take_window_geometry(&self) -> Option<(String, WindowGeometry)> {
+        self.window_geometry.lock().unwrap().take()
+    }
+
+    /// Send a command to the emulator. Commands are processed in the order that they are sent, and
+    /// commands sent after the emulator has stopped are ignored.
+    pub fn send_command(&self, command: EmulatorCommand) {
+        // The receiver is owned by this struct and all of its clones, so this can never fail
+        let _ = self.command_sender.send(command);
+    }
+
     pub fn request_config_reload(&self) {
-        self.config_reload_signal.store(true, Ordering::Relaxed);
+        self.send_command(EmulatorCommand::ReloadConfig);
     }
 
     pub fn request_quit(&self) {
-        self.quit_signal.store(true, Ordering::Relaxed);
+        self.send_command(EmulatorCommand::Quit);
     }
 
     pub fn request_input_configure(&self, input_type: InputType) {
-        self.input_reconfigure_signal.store(input_type.to_discriminant(), Ordering::Relaxed);
+        self.send_command(EmulatorCommand::ConfigureInput(input_type));
+    }
+
+    /// Request that the running emulator switch to a different ROM. The ROM is added to the
+    /// session's playlist so that the previous game can still be switched back to.
+    pub fn request_rom_load(&self, request: RomLoadRequest) {
+        self.send_command(EmulatorCommand::LoadRom(request));
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// Pause emulation while a frontend dialog is open, or resume once it is closed.
+    pub fn set_frontend_paused(&self, paused: bool) {
+        self.send_command(EmulatorCommand::SetFrontendPaused(paused));
+    }
+
+    /// Resume emulation after it was paused because of an error.
+    pub fn request_error_resume(&self) {
+        self.send_command(EmulatorCommand::ResumeAfterError);
+    }
+
+    pub fn request_frame_step(&self) {
+        self.send_command(EmulatorCommand::StepFrame);
+    }
 
-    #[test]
-    fn no_input_reconfigure_returns_none() {
-        assert_eq!(None, InputType::from_discriminant(JgnesSharedConfig::NO_INPUT_RECONFIGURE));
+    pub fn request_window_flags(&self, always_on_top: bool, borderless: bool) {
+        self.send_command(EmulatorCommand::SetWindowFlags { always_on_top, borderless });
     }
 }
@@ -0,0 +1,77 @@
+//! Optional confirmation for hotkeys that would lose progress, such as quitting or hard resetting.
+//!
+//! The first press shows a prompt on the on-screen display, and the action only happens if the
+//! same hotkey is pressed again before the prompt expires.
+
+use crate::osd::{OSD_TEXT_COLOR, OsdLine};
+use std::time::{Duration, Instant};
+
+const PROMPT_DURATION: Duration = Duration::from_secs(3);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ConfirmAction {
+    Quit,
+    OverwriteState,
+    HardReset,
+}
+
+impl ConfirmAction {
+    // Must fit on one line of the on-screen display
+    pub(crate) fn prompt(self) -> &'static str {
+        match self {
+            Self::Quit => "Press again to quit",
+            Self::OverwriteState => "Press again to overwrite",
+            Self::HardReset => "Press again to hard reset",
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct ConfirmPrompt {
+    pending: Option<(ConfirmAction, Instant)>,
+}
+
+impl ConfirmPrompt {
+    /// Returns whether the action was confirmed. Otherwise, the prompt for this action is shown
+    /// until it expires or a different action is requested.
+    pub(crate) fn confirm(&mut self, action: ConfirmAction, now: Instant) -> bool {
+        match self.pending.take() {
+            Some((pending, until)) if pending == action && now < until => true,
+            _ => {
+                log::info!("Waiting for confirmation: {}", action.prompt());
+                self.pending = Some((action, now + PROMPT_DURATION));
+                false
+            }
+        }
+    }
+
+    pub(crate) fn osd_lines(&self, now: Instant) -> Vec<OsdLine> {
+        match self.pending {
+            Some((action, until)) if now < until => {
+                vec![OsdLine { text: action.prompt().into(), color: OSD_TEXT_COLOR }]
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn second_press_confirms() {
+        let mut prompt = ConfirmPrompt::default();
+        let now = Instant::now();
+
+        assert!(!prompt.confirm(ConfirmAction::Quit, now));
+        assert_eq!(prompt.osd_lines(now).len(), 1);
+        assert!(prompt.confirm(ConfirmAction::Quit, now + Duration::from_secs(1)));
+        assert!(prompt.osd_lines(now).is_empty());
+
+        // A different action or an expired prompt starts over
+        assert!(!prompt.confirm(ConfirmAction::HardReset, now));
+        assert!(!prompt.confirm(ConfirmAction::Quit, now));
+        assert!(!prompt.confirm(ConfirmAction::Quit, now + PROMPT_DURATION));
+    }
+}
@@ -0,0 +1,72 @@
+//! Crash report bundles that users can attach to bug reports.
+//!
+//! A bundle is a zip file containing a summary of the error along with version and platform info,
+//! the frontend's config, recent log lines, and optionally a save state from the moment that the
+//! emulator stopped.
+
+use crate::LogLine;
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+#[derive(Debug, Clone)]
+pub struct CrashReport {
+    /// The error or panic message, including any source errors
+    pub error: String,
+    /// Frontend config in whatever text format the frontend stores it in
+    pub config: String,
+    pub rom_file_name: Option<String>,
+    pub rom_hash: Option<String>,
+    pub log_lines: Vec<LogLine>,
+    /// Save state captured when the emulator stopped, if one was available
+    pub save_state: Option<Vec<u8>>,
+}
+
+impl CrashReport {
+    fn summary(&self) -> String {
+        let mut summary = String::new();
+        let _ = writeln!(summary, "jgnes version: {}", env!("CARGO_PKG_VERSION"));
+        let _ = writeln!(summary, "Platform: {} {}", std::env::consts::OS, std::env::consts::ARCH);
+        let _ = writeln!(summary, "ROM: {}", self.rom_file_name.as_deref().unwrap_or("<none>"));
+        let _ = writeln!(summary, "ROM hash: {}", self.rom_hash.as_deref().unwrap_or("<none>"));
+        let _ = writeln!(summary, "Save state included: {}", self.save_state.is_some());
+        let _ = writeln!(summary);
+        let _ = writeln!(summary, "{}", self.error);
+        summary
+    }
+}
+
+/// Write a crash report bundle to a zip file at the given path.
+///
+/// # Errors
+///
+/// This function will return an error if the zip file cannot be written.
+pub fn write_crash_report(path: &Path, report: &CrashReport) -> anyhow::Result<()> {
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    let mut zip = ZipWriter::new(File::create(path)?);
+
+    zip.start_file("report.txt", options)?;
+    zip.write_all(report.summary().as_bytes())?;
+
+    zip.start_file("config.txt", options)?;
+    zip.write_all(report.config.as_bytes())?;
+
+    zip.start_file("log.txt", options)?;
+    for line in &report.log_lines {
+        writeln!(zip, "{:<5} {}: {}", line.level, line.target, line.message)?;
+    }
+
+    if let Some(save_state) = &report.save_state {
+        zip.start_file("state.ss", options)?;
+        zip.write_all(save_state)?;
+    }
+
+    zip.finish()?;
+
+    log::info!("Wrote crash report to '{}'", path.display());
+
+    Ok(())
+}
@@ -0,0 +1,436 @@
+//! Pure Rust image/audio encoders used for clips and captures.
+
+use crate::AUDIO_OUTPUT_FREQUENCY;
+use jgnes_core::TimingMode;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io;
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Build an output path next to the ROM file of the form `<rom name>-<kind>-<unix timestamp>`,
+/// without an extension.
+pub(crate) fn timestamped_output_path(nes_file_path: &Path, kind: &str) -> PathBuf {
+    let timestamp = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default();
+    let file_stem = nes_file_path.file_stem().and_then(|stem| stem.to_str()).unwrap_or("jgnes");
+
+    nes_file_path.with_file_name(format!("{file_stem}-{kind}-{timestamp}"))
+}
+
+fn frame_rate(timing_mode: TimingMode) -> u16 {
+    match timing_mode {
+        TimingMode::Ntsc => 60,
+        TimingMode::Pal => 50,
+    }
+}
+
+/// Create an APNG writer for the given number of full-size RGBA frames. The caller is responsible
+/// for writing exactly `num_frames` frames and then calling `finish()`.
+pub(crate) fn apng_writer(
+    path: &Path,
+    timing_mode: TimingMode,
+    num_frames: u32,
+) -> anyhow::Result<png::Writer<BufWriter<File>>> {
+    let width = jgnes_core::SCREEN_WIDTH.into();
+    let height = timing_mode.visible_screen_height().into();
+
+    let mut encoder = png::Encoder::new(BufWriter::new(File::create(path)?), width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    // 0 plays = loop forever
+    encoder.set_animated(num_frames, 0)?;
+    encoder.set_frame_delay(1, frame_rate(timing_mode))?;
+
+    Ok(encoder.write_header()?)
+}
+
+/// Write a single full-size RGBA frame to a PNG file.
+pub(crate) fn write_png(path: &Path, timing_mode: TimingMode, rgba: &[u8]) -> anyhow::Result<()> {
+    let width = jgnes_core::SCREEN_WIDTH.into();
+    let height = timing_mode.visible_screen_height().into();
+
+    let mut encoder = png::Encoder::new(BufWriter::new(File::create(path)?), width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(rgba)?;
+    writer.finish()?;
+
+    Ok(())
+}
+
+const WAV_BYTES_PER_SAMPLE: u16 = 2;
+
+/// Streaming writer for mono 16-bit PCM WAV files. The length fields in the header are only
+/// filled in once `finish()` is called.
+pub(crate) struct WavWriter {
+    writer: BufWriter<File>,
+    path: PathBuf,
+    data_len: u32,
+}
+
+impl WavWriter {
+    pub(crate) fn create(path: &Path) -> io::Result<Self> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        write_wav_header(&mut writer, 0)?;
+
+        Ok(Self { writer, path: path.to_path_buf(), data_len: 0 })
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub(crate) fn write_sample(&mut self, sample: f32) -> io::Result<()> {
+        let sample = (sample.clamp(-1.0, 1.0) * f32::from(i16::MAX)) as i16;
+        self.writer.write_all(&sample.to_le_bytes())?;
+        self.data_len = self.data_len.saturating_add(WAV_BYTES_PER_SAMPLE.into());
+
+        Ok(())
+    }
+
+    pub(crate) fn finish(mut self) -> io::Result<()> {
+        self.writer.seek(SeekFrom::Start(0))?;
+        write_wav_header(&mut self.writer, self.data_len)?;
+        self.writer.flush()
+    }
+}
+
+fn write_wav_header<W: Write>(writer: &mut W, data_len: u32) -> io::Result<()> {
+    let sample_rate = AUDIO_OUTPUT_FREQUENCY as u32;
+
+    writer.write_all(b"RIFF")?;
+    writer.write_all(&data_len.saturating_add(36).to_le_bytes())?;
+    writer.write_all(b"WAVE")?;
+
+    writer.write_all(b"fmt ")?;
+    writer.write_all(&16_u32.to_le_bytes())?;
+    // Format 1 = uncompressed PCM, 1 channel
+    writer.write_all(&1_u16.to_le_bytes())?;
+    writer.write_all(&1_u16.to_le_bytes())?;
+    writer.write_all(&sample_rate.to_le_bytes())?;
+    writer.write_all(&(sample_rate * u32::from(WAV_BYTES_PER_SAMPLE)).to_le_bytes())?;
+    writer.write_all(&WAV_BYTES_PER_SAMPLE.to_le_bytes())?;
+    writer.write_all(&(8 * WAV_BYTES_PER_SAMPLE).to_le_bytes())?;
+
+    writer.write_all(b"data")?;
+    writer.write_all(&data_len.to_le_bytes())
+}
+
+/// Write mono 16-bit PCM samples to a WAV file.
+pub(crate) fn write_wav(path: &Path, samples: &[f32]) -> io::Result<()> {
+    let mut writer = WavWriter::create(path)?;
+    for &sample in samples {
+        writer.write_sample(sample)?;
+    }
+
+    writer.finish()
+}
+
+// GIF frame delays are in hundredths of a second, and most viewers treat delays below 2cs as
+// 10cs, so frames are dropped as needed to keep every delay at 2cs or more
+const MIN_GIF_FRAME_DELAY: u16 = 2;
+
+/// Write full-size RGBA frames to a looping animated GIF.
+///
+/// Each frame gets its own local color table, which works because a single NES frame can never
+/// contain more than 64 distinct colors (plus black for overscan).
+///
+/// # Errors
+///
+/// This function will return an error if the file cannot be written or if a frame contains more
+/// than 256 distinct colors.
+pub(crate) fn write_gif(
+    path: &Path,
+    timing_mode: TimingMode,
+    frames: &[Vec<u8>],
+) -> anyhow::Result<()> {
+    let width = jgnes_core::SCREEN_WIDTH;
+    let height = timing_mode.visible_screen_height();
+
+    // Pick which frames to keep based on each frame's timestamp in hundredths of a second
+    let frame_rate = u32::from(frame_rate(timing_mode));
+    let timestamp = |i: usize| (i as u32 * 100 + frame_rate / 2) / frame_rate;
+    let mut kept_frames: Vec<(usize, u32)> = Vec::with_capacity(frames.len());
+    for i in 0..frames.len() {
+        let frame_timestamp = timestamp(i);
+        if kept_frames.last().is_none_or(|&(_, last_timestamp)| {
+            frame_timestamp - last_timestamp >= MIN_GIF_FRAME_DELAY.into()
+        }) {
+            kept_frames.push((i, frame_timestamp));
+        }
+    }
+
+    let mut writer = BufWriter::new(File::create(path)?);
+
+    writer.write_all(b"GIF89a")?;
+    writer.write_all(&width.to_le_bytes())?;
+    writer.write_all(&height.to_le_bytes())?;
+    // No global color table, background color 0, default pixel aspect ratio
+    writer.write_all(&[0x00, 0x00, 0x00])?;
+
+    // NETSCAPE2.0 application extension; loop forever
+    writer.write_all(&[0x21, 0xFF, 0x0B])?;
+    writer.write_all(b"NETSCAPE2.0")?;
+    writer.write_all(&[0x03, 0x01, 0x00, 0x00, 0x00])?;
+
+    let mut indexed = Vec::with_capacity(usize::from(width) * usize::from(height));
+    for (i, &(frame_idx, frame_timestamp)) in kept_frames.iter().enumerate() {
+        let delay = kept_frames
+            .get(i + 1)
+            .map_or(u32::from(MIN_GIF_FRAME_DELAY), |&(_, next)| next - frame_timestamp)
+            as u16;
+
+        let color_table = index_colors(&frames[frame_idx], &mut indexed)?;
+
+        // Graphic control extension; no disposal, no transparency
+        writer.write_all(&[0x21, 0xF9, 0x04, 0x04])?;
+        writer.write_all(&delay.to_le_bytes())?;
+        writer.write_all(&[0x00, 0x00])?;
+
+        // Image descriptor with a 256-entry local color table
+        writer.write_all(&[0x2C, 0x00, 0x00, 0x00, 0x00])?;
+        writer.write_all(&width.to_le_bytes())?;
+        writer.write_all(&height.to_le_bytes())?;
+        writer.write_all(&[0x87])?;
+        writer.write_all(&color_table)?;
+
+        writer.write_all(&[GIF_MIN_CODE_SIZE])?;
+        for block in lzw_encode(&indexed).chunks(255) {
+            writer.write_all(&[block.len() as u8])?;
+            writer.write_all(block)?;
+        }
+        writer.write_all(&[0x00])?;
+    }
+
+    // Trailer
+    writer.write_all(&[0x3B])?;
+
+    writer.flush()?;
+
+    Ok(())
+}
+
+// Convert an RGBA frame to color indices, returning a 256-entry RGB color table
+fn index_colors(rgba: &[u8], indexed: &mut Vec<u8>) -> anyhow::Result<[u8; 3 * 256]> {
+    let mut color_table = [0; 3 * 256];
+    let mut color_indices: HashMap<[u8; 3], u8> = HashMap::new();
+
+    indexed.clear();
+    for pixel in rgba.chunks_exact(4) {
+        let color = [pixel[0], pixel[1], pixel[2]];
+        let index = match color_indices.get(&color) {
+            Some(&index) => index,
+            None => {
+                let num_colors = color_indices.len();
+                if num_colors == 256 {
+                    return Err(anyhow::Error::msg("frame contains more than 256 colors"));
+                }
+
+                color_table[3 * num_colors..3 * (num_colors + 1)].copy_from_slice(&color);
+                color_indices.insert(color, num_colors as u8);
+                num_colors as u8
+            }
+        };
+        indexed.push(index);
+    }
+
+    Ok(color_table)
+}
+
+const GIF_MIN_CODE_SIZE: u8 = 8;
+const LZW_MAX_CODE_SIZE: u8 = 12;
+
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u32,
+    buffer_len: u8,
+}
+
+impl BitWriter {
+    fn new() -> Self {
+        Self { bytes: Vec::new(), buffer: 0, buffer_len: 0 }
+    }
+
+    // GIF packs codes least significant bit first
+    fn write(&mut self, code: u16, code_size: u8) {
+        self.buffer |= u32::from(code) << self.buffer_len;
+        self.buffer_len += code_size;
+
+        while self.buffer_len >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.buffer_len -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.buffer_len > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+        self.bytes
+    }
+}
+
+// Variable-length LZW compression as specified by GIF89a, with 8-bit minimum code size
+fn lzw_encode(indices: &[u8]) -> Vec<u8> {
+    let clear_code: u16 = 1 << GIF_MIN_CODE_SIZE;
+    let end_code = clear_code + 1;
+    let max_code: u16 = 1 << LZW_MAX_CODE_SIZE;
+
+    let mut writer = BitWriter::new();
+    let mut dictionary: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut code_size = GIF_MIN_CODE_SIZE + 1;
+    let mut next_code = clear_code + 2;
+
+    writer.write(clear_code, code_size);
+
+    let Some((&first, rest)) = indices.split_first() else {
+        writer.write(end_code, code_size);
+        return writer.finish();
+    };
+
+    let mut prefix = u16::from(first);
+    for &index in rest {
+        if let Some(&code) = dictionary.get(&(prefix, index)) {
+            prefix = code;
+            continue;
+        }
+
+        writer.write(prefix, code_size);
+
+        if next_code < max_code {
+            dictionary.insert((prefix, index), next_code);
+            next_code += 1;
+
+            // The decoder adds its entries one code later than the encoder, so only widen codes
+            // once the table has grown past what the current code size can address
+            if next_code > 1 << code_size && code_size < LZW_MAX_CODE_SIZE {
+                code_size += 1;
+            }
+        } else {
+            writer.write(clear_code, code_size);
+            dictionary.clear();
+            code_size = GIF_MIN_CODE_SIZE + 1;
+            next_code = clear_code + 2;
+        }
+
+        prefix = u16::from(index);
+    }
+
+    writer.write(prefix, code_size);
+
+    // The decoder adds one more entry after reading the last code, which may widen the end code
+    if next_code == 1 << code_size && code_size < LZW_MAX_CODE_SIZE {
+        code_size += 1;
+    }
+    writer.write(end_code, code_size);
+
+    writer.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Minimal GIF LZW decoder used to verify that the encoder round trips
+    fn lzw_decode(bytes: &[u8]) -> Vec<u8> {
+        let clear_code: u16 = 1 << GIF_MIN_CODE_SIZE;
+        let end_code = clear_code + 1;
+
+        let mut table: Vec<Vec<u8>> = Vec::new();
+        let reset_table = |table: &mut Vec<Vec<u8>>| {
+            table.clear();
+            table.extend((0..=255).map(|i| vec![i]));
+            table.push(vec![]);
+            table.push(vec![]);
+        };
+        reset_table(&mut table);
+
+        let mut code_size = GIF_MIN_CODE_SIZE + 1;
+        let mut bit_pos = 0;
+        let mut prev: Option<Vec<u8>> = None;
+        let mut out = Vec::new();
+        loop {
+            let mut code = 0_u16;
+            for bit in 0..code_size {
+                let byte = bytes[bit_pos / 8];
+                code |= u16::from((byte >> (bit_pos % 8)) & 1) << bit;
+                bit_pos += 1;
+            }
+
+            if code == clear_code {
+                reset_table(&mut table);
+                code_size = GIF_MIN_CODE_SIZE + 1;
+                prev = None;
+                continue;
+            }
+            if code == end_code {
+                return out;
+            }
+
+            let entry = match (table.get(usize::from(code)), &prev) {
+                (Some(entry), _) => entry.clone(),
+                (None, Some(prev)) => {
+                    let mut entry = prev.clone();
+                    entry.push(prev[0]);
+                    entry
+                }
+                (None, None) => panic!("invalid first code {code}"),
+            };
+
+            if let Some(prev) = prev {
+                if table.len() < 1 << LZW_MAX_CODE_SIZE {
+                    let mut new_entry = prev;
+                    new_entry.push(entry[0]);
+                    table.push(new_entry);
+                    if table.len() == 1 << code_size && code_size < LZW_MAX_CODE_SIZE {
+                        code_size += 1;
+                    }
+                }
+            }
+
+            out.extend_from_slice(&entry);
+            prev = Some(entry);
+        }
+    }
+
+    #[test]
+    fn lzw_round_trip() {
+        let inputs: Vec<Vec<u8>> = vec![
+            vec![],
+            vec![7],
+            vec![0; 100_000],
+            (0..100_000_u32).map(|i| (i.wrapping_mul(2_654_435_761) >> 24) as u8).collect(),
+            (0..100_000_u32).map(|i| (i / 7 % 5) as u8).collect(),
+        ];
+
+        for input in inputs {
+            assert_eq!(input, lzw_decode(&lzw_encode(&input)));
+        }
+    }
+
+    #[test]
+    fn wav_header_lengths_are_filled_in_on_finish() {
+        let path = std::env::temp_dir().join(format!("jgnes-encode-{}.wav", std::process::id()));
+
+        let mut writer = WavWriter::create(&path).unwrap();
+        for sample in [0.0, 1.0, -1.0] {
+            writer.write_sample(sample).unwrap();
+        }
+        writer.finish().unwrap();
+
+        let bytes = std::fs::read(&path).unwrap();
+        assert_eq!(bytes.len(), 44 + 6);
+        assert_eq!(bytes[4..8], 42_u32.to_le_bytes());
+        assert_eq!(bytes[40..44], 6_u32.to_le_bytes());
+        assert_eq!(bytes[46..48], i16::MAX.to_le_bytes());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
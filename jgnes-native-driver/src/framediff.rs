@@ -0,0 +1,229 @@
+//! Developer tool for finding the first frame where emulator output diverges, e.g. when bisecting
+//! an accuracy regression.
+//!
+//! Output can be compared either against per-frame hashes recorded by a different build, or
+//! against a second emulator instance that runs side by side with different emulator settings.
+//! Either way, the emulator is driven by an optional input file using the same format as the
+//! headless frame server's input stream: 2 bytes per frame, one each for P1 and P2.
+//!
+//! Note that CPU RAM is randomized at power-on, so games that read uninitialized RAM can produce
+//! different output between hash recording runs. Side-by-side comparisons are not affected because
+//! both instances start from the same state.
+
+use crate::clip::{CaptureAudioPlayer, CaptureRenderer, NullSaveWriter, ReplayInputPoller};
+use crate::encode;
+use crate::headless;
+use jgnes_core::{
+    EmulationError, Emulator, EmulatorConfig, EmulatorCreateArgs, JoypadState, RamInit, TickEffect,
+    TimingMode, fnv1a_hash,
+};
+use jgnes_renderer::colors::Palette;
+use jgnes_renderer::config::Overscan;
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+type DiffEmulator =
+    Emulator<CaptureRenderer, CaptureAudioPlayer, Rc<ReplayInputPoller>, NullSaveWriter>;
+
+#[derive(Debug, Clone)]
+pub enum FrameDiffMode {
+    /// Write a hash of every frame to the given file, one hex hash per line
+    Record { hash_file: PathBuf },
+    /// Compare every frame against hashes previously written by `Record`
+    Check { hash_file: PathBuf },
+    /// Run a second emulator instance with these settings alongside the first one
+    Compare { other_config: EmulatorConfig },
+}
+
+#[derive(Debug, Clone)]
+pub struct FrameDiffConfig {
+    pub nes_file_path: String,
+    pub patch_file_path: Option<String>,
+    pub forced_timing_mode: Option<TimingMode>,
+    pub forced_mapper_number: Option<u16>,
+    /// Recorded hashes can only be checked against a run that uses the same RAM init pattern and
+    /// seed
+    pub ram_init: RamInit,
+    pub emulator_config: EmulatorConfig,
+    /// Joypad inputs to replay, 2 bytes per frame; frames past the end of the file have no input
+    pub input_file: Option<String>,
+    pub frames: u64,
+    pub mode: FrameDiffMode,
+}
+
+/// Run the frame diff tool.
+///
+/// Images of the first diverging frame are written next to the ROM file: the frame itself when
+/// checking against hashes, or both frames plus an image highlighting the differing pixels when
+/// comparing side by side.
+///
+/// # Errors
+///
+/// This function will return an error if the ROM, input file, or hash file cannot be read, if the
+/// hash file or images cannot be written, or if output diverges.
+pub fn run_frame_diff(config: &FrameDiffConfig) -> anyhow::Result<()> {
+    let nes_file_path = Path::new(&config.nes_file_path);
+    let rom_bytes =
+        crate::read_rom_file(nes_file_path, config.patch_file_path.as_deref().map(Path::new))?;
+    let inputs = match &config.input_file {
+        Some(input_file) => fs::read(input_file)?,
+        None => vec![],
+    };
+
+    let input_poller = Rc::new(ReplayInputPoller::default());
+    let mut emulator = Emulator::create(EmulatorCreateArgs {
+        rom_bytes,
+        sav_bytes: None,
+        forced_timing_mode: config.forced_timing_mode,
+        forced_mapper_number: config.forced_mapper_number,
+        ram_init: config.ram_init,
+        renderer: CaptureRenderer::new(Palette::default(), Overscan::default()),
+        audio_player: CaptureAudioPlayer::new(),
+        input_poller: Rc::clone(&input_poller),
+        save_writer: NullSaveWriter,
+    })
+    .inspect_err(crate::log_rom_diagnostics)?;
+
+    match &config.mode {
+        FrameDiffMode::Record { hash_file } => {
+            let mut hashes = Vec::new();
+            for frame in 0..config.frames {
+                input_poller.set(frame_inputs(&inputs, frame));
+                run_frame(&mut emulator, &config.emulator_config);
+                hashes.push(format!("{:016x}\n", fnv1a_hash(&emulator.get_renderer().frame)));
+            }
+            fs::write(hash_file, hashes.concat())?;
+
+            log::info!("Wrote {} frame hashes to '{}'", config.frames, hash_file.display());
+        }
+        FrameDiffMode::Check { hash_file } => {
+            let expected_hashes = fs::read_to_string(hash_file)?
+                .lines()
+                .map(|line| u64::from_str_radix(line.trim(), 16))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|err| {
+                    anyhow::Error::msg(format!(
+                        "invalid hash file '{}': {err}",
+                        hash_file.display()
+                    ))
+                })?;
+
+            let frames = config.frames.min(expected_hashes.len() as u64);
+            for (frame, &expected_hash) in (0..frames).zip(&expected_hashes) {
+                input_poller.set(frame_inputs(&inputs, frame));
+                run_frame(&mut emulator, &config.emulator_config);
+
+                let actual_hash = fnv1a_hash(&emulator.get_renderer().frame);
+                if actual_hash != expected_hash {
+                    let image_path = output_path(nes_file_path, frame, "actual.png");
+                    encode::write_png(
+                        &image_path,
+                        emulator.timing_mode(),
+                        &emulator.get_renderer().frame,
+                    )?;
+                    return Err(anyhow::Error::msg(format!(
+                        "frame {frame} hash is {actual_hash:016x}, expected {expected_hash:016x}; wrote frame to '{}'",
+                        image_path.display()
+                    )));
+                }
+            }
+
+            log::info!("All {frames} frames match hashes in '{}'", hash_file.display());
+        }
+        FrameDiffMode::Compare { other_config } => {
+            // Clone the first instance's state so that both start with the same RAM contents
+            let other_input_poller = Rc::new(ReplayInputPoller::default());
+            let mut other_emulator = Emulator::create(EmulatorCreateArgs {
+                rom_bytes: crate::read_rom_file(
+                    nes_file_path,
+                    config.patch_file_path.as_deref().map(Path::new),
+                )?,
+                sav_bytes: None,
+                forced_timing_mode: config.forced_timing_mode,
+                forced_mapper_number: config.forced_mapper_number,
+                ram_init: emulator.ram_init(),
+                renderer: CaptureRenderer::new(Palette::default(), Overscan::default()),
+                audio_player: CaptureAudioPlayer::new(),
+                input_poller: Rc::clone(&other_input_poller),
+                save_writer: NullSaveWriter,
+            })?;
+            other_emulator.load_state_snapshot(emulator.snapshot_state());
+
+            for frame in 0..config.frames {
+                let inputs = frame_inputs(&inputs, frame);
+                input_poller.set(inputs);
+                other_input_poller.set(inputs);
+                run_frame(&mut emulator, &config.emulator_config);
+                run_frame(&mut other_emulator, other_config);
+
+                let frame_a = &emulator.get_renderer().frame;
+                let frame_b = &other_emulator.get_renderer().frame;
+                if frame_a != frame_b {
+                    let timing_mode = emulator.timing_mode();
+                    let paths = ["a.png", "b.png", "diff.png"]
+                        .map(|suffix| output_path(nes_file_path, frame, suffix));
+                    encode::write_png(&paths[0], timing_mode, frame_a)?;
+                    encode::write_png(&paths[1], timing_mode, frame_b)?;
+                    encode::write_png(&paths[2], timing_mode, &highlight_diff(frame_a, frame_b))?;
+                    return Err(anyhow::Error::msg(format!(
+                        "frame {frame} differs; wrote images to '{}', '{}', and '{}'",
+                        paths[0].display(),
+                        paths[1].display(),
+                        paths[2].display()
+                    )));
+                }
+            }
+
+            log::info!("All {} frames match", config.frames);
+        }
+    }
+
+    Ok(())
+}
+
+fn frame_inputs(inputs: &[u8], frame: u64) -> (JoypadState, JoypadState) {
+    let get =
+        |i: u64| usize::try_from(i).ok().and_then(|i| inputs.get(i)).copied().unwrap_or_default();
+    (headless::joypad_from_bits(get(2 * frame)), headless::joypad_from_bits(get(2 * frame + 1)))
+}
+
+fn run_frame(emulator: &mut DiffEmulator, config: &EmulatorConfig) {
+    loop {
+        match emulator.tick(config) {
+            Ok(TickEffect::None) => {}
+            Ok(TickEffect::FrameRendered) => break,
+            Err(err) => match err.error {
+                EmulationError::Render(err)
+                | EmulationError::Audio(err)
+                | EmulationError::Save(err) => match err {},
+            },
+        }
+    }
+
+    // Audio is not compared, so don't let it accumulate
+    emulator.get_audio_player_mut().samples.clear();
+}
+
+fn output_path(nes_file_path: &Path, frame: u64, suffix: &str) -> PathBuf {
+    let mut path = OsString::from(encode::timestamped_output_path(nes_file_path, "frame-diff"));
+    path.push(format!("-frame{frame}-{suffix}"));
+    path.into()
+}
+
+// Differing pixels are red; matching pixels are dimmed so that the differences stand out
+fn highlight_diff(frame_a: &[u8], frame_b: &[u8]) -> Vec<u8> {
+    frame_a
+        .chunks_exact(4)
+        .zip(frame_b.chunks_exact(4))
+        .flat_map(|(a, b)| {
+            if a == b {
+                let gray = ((u16::from(a[0]) + u16::from(a[1]) + u16::from(a[2])) / 12) as u8;
+                [gray, gray, gray, 255]
+            } else {
+                [255, 0, 0, 255]
+            }
+        })
+        .collect()
+}
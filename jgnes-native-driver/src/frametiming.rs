@@ -0,0 +1,121 @@
+//! Per-frame timing logs for diagnosing stutter.
+//!
+//! For a fixed duration, every rendered frame records when it was presented, how much audio was
+//! queued for playback, and how many emulator ticks it took. The log is then written to a CSV file
+//! on a background thread so that it can be attached to bug reports.
+
+use std::fs::File;
+use std::io;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How long frame timing is logged for once logging starts.
+pub const FRAME_TIMING_LOG_DURATION: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy)]
+struct FrameTiming {
+    // Relative to when logging started
+    presented_at: Duration,
+    audio_queue: Duration,
+    ticks: u64,
+}
+
+#[derive(Debug)]
+pub(crate) struct FrameTimingLog {
+    path: PathBuf,
+    start: Instant,
+    last_frame_ticks: u64,
+    frames: Vec<FrameTiming>,
+}
+
+impl FrameTimingLog {
+    pub(crate) fn start(path: PathBuf, ticks: u64) -> Self {
+        log::info!(
+            "Logging frame timing for {} seconds to '{}'",
+            FRAME_TIMING_LOG_DURATION.as_secs(),
+            path.display()
+        );
+
+        Self { path, start: Instant::now(), last_frame_ticks: ticks, frames: Vec::new() }
+    }
+
+    /// Record a frame that was just presented. `ticks` is the run loop's running tick count.
+    pub(crate) fn record_frame(&mut self, now: Instant, ticks: u64, audio_queue: Duration) {
+        self.frames.push(FrameTiming {
+            presented_at: now.saturating_duration_since(self.start),
+            audio_queue,
+            ticks: ticks - self.last_frame_ticks,
+        });
+        self.last_frame_ticks = ticks;
+    }
+
+    pub(crate) fn is_complete(&self) -> bool {
+        self.frames.last().is_some_and(|frame| frame.presented_at >= FRAME_TIMING_LOG_DURATION)
+    }
+
+    pub(crate) fn spawn_export(self) {
+        thread::spawn(move || {
+            let result =
+                File::create(&self.path).and_then(|file| self.write_csv(BufWriter::new(file)));
+            match result {
+                Ok(()) => log::info!(
+                    "Wrote timing for {} frames to '{}'",
+                    self.frames.len(),
+                    self.path.display()
+                ),
+                Err(err) => log::error!(
+                    "Error writing frame timing log to '{}': {err}",
+                    self.path.display()
+                ),
+            }
+        });
+    }
+
+    fn write_csv<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writeln!(writer, "frame,presented_ms,frame_time_ms,audio_queue_ms,ticks")?;
+
+        let mut prev_presented_at = Duration::ZERO;
+        for (i, frame) in self.frames.iter().enumerate() {
+            writeln!(
+                writer,
+                "{},{:.3},{:.3},{:.3},{}",
+                i + 1,
+                frame.presented_at.as_secs_f64() * 1000.0,
+                frame.presented_at.saturating_sub(prev_presented_at).as_secs_f64() * 1000.0,
+                frame.audio_queue.as_secs_f64() * 1000.0,
+                frame.ticks
+            )?;
+            prev_presented_at = frame.presented_at;
+        }
+
+        writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_output() {
+        let mut log = FrameTimingLog::start(PathBuf::from("timing.csv"), 100);
+        let start = log.start;
+        log.record_frame(start + Duration::from_millis(16), 29880, Duration::from_millis(20));
+        log.record_frame(start + Duration::from_millis(50), 59660, Duration::from_micros(2500));
+        assert!(!log.is_complete());
+
+        let mut csv = Vec::new();
+        log.write_csv(&mut csv).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "frame,presented_ms,frame_time_ms,audio_queue_ms,ticks\n\
+             1,16.000,16.000,20.000,29780\n\
+             2,50.000,34.000,2.500,29780\n"
+        );
+
+        log.record_frame(start + FRAME_TIMING_LOG_DURATION, 89440, Duration::ZERO);
+        assert!(log.is_complete());
+    }
+}
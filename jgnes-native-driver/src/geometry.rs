@@ -0,0 +1,48 @@
+//! Emulator window position and size persistence.
+//!
+//! Saved geometry is keyed by the current display configuration, so that e.g. a position saved
+//! while an external monitor was connected is not restored off-screen after it is disconnected.
+
+use sdl2::VideoSubsystem;
+use sdl2::video::{FullscreenType, Window};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WindowGeometry {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Saved window geometry, keyed by display configuration.
+pub type WindowGeometryMap = BTreeMap<String, WindowGeometry>;
+
+/// A string that identifies the current set of displays and their arrangement, or `None` if the
+/// displays cannot be queried.
+pub(crate) fn display_configuration_key(video_subsystem: &VideoSubsystem) -> Option<String> {
+    let num_displays = video_subsystem.num_video_displays().ok()?;
+    let bounds: Vec<_> = (0..num_displays)
+        .map(|display_index| {
+            video_subsystem.display_bounds(display_index).map(|bounds| {
+                format!("{}x{}+{}+{}", bounds.width(), bounds.height(), bounds.x(), bounds.y())
+            })
+        })
+        .collect::<Result<_, _>>()
+        .ok()?;
+
+    Some(bounds.join(","))
+}
+
+/// The window's current geometry, or `None` if it is fullscreen or minimized; restoring those
+/// states as a windowed position would not be useful.
+pub(crate) fn window_geometry(window: &Window) -> Option<WindowGeometry> {
+    if window.fullscreen_state() != FullscreenType::Off || window.is_minimized() {
+        return None;
+    }
+
+    let (x, y) = window.position();
+    let (width, height) = window.size();
+    Some(WindowGeometry { x, y, width, height })
+}
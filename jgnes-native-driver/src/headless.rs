@@ -0,0 +1,248 @@
+//! Headless frame server mode that runs the emulator without a window or audio device.
+//!
+//! Emulator output is streamed over stdout, or over a localhost TCP connection, as a sequence of
+//! packets. Each packet is a 1-byte tag, a little-endian u32 payload length, and then the payload:
+//!
+//! * `H` (sent once at the start): frame width as u16, frame height as u16, and audio sample
+//!   rate as u32, all little-endian
+//! * `V`: one frame of video as tightly packed RGBA8 pixels
+//! * `A`: the audio generated during the preceding frame as little-endian f32 mono samples
+//!
+//! Input is read as 2-byte messages, one byte each for P1 and P2, from stdin (or from the TCP
+//! connection). Bits 0-7 are A, B, Select, Start, Up, Down, Left, Right, which is the same order
+//! that the NES reads them in. Each message replaces the previous joypad state.
+
+use crate::AUDIO_OUTPUT_FREQUENCY;
+use crate::clip::{CaptureAudioPlayer, CaptureRenderer, NullSaveWriter};
+use jgnes_core::{
+    EmulationError, Emulator, EmulatorConfig, EmulatorCreateArgs, InputPoller, JoypadState,
+    RamInit, TickEffect, TimingMode,
+};
+use jgnes_renderer::colors::Palette;
+use jgnes_renderer::config::Overscan;
+use std::io::{BufWriter, Read, Write};
+use std::net::{Ipv4Addr, TcpListener};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU16, Ordering};
+use std::time::{Duration, Instant};
+use std::{io, thread};
+
+const HEADER_TAG: u8 = b'H';
+const VIDEO_TAG: u8 = b'V';
+const AUDIO_TAG: u8 = b'A';
+
+/// Where the headless frame server writes output and reads input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeadlessTransport {
+    /// Write output to stdout and read input from stdin
+    Stdio,
+    /// Wait for a single connection on this localhost TCP port, then use it for both output and
+    /// input
+    Tcp { port: u16 },
+}
+
+#[derive(Debug, Clone)]
+pub struct HeadlessConfig {
+    pub nes_file_path: String,
+    pub patch_file_path: Option<String>,
+    pub forced_timing_mode: Option<TimingMode>,
+    pub forced_mapper_number: Option<u16>,
+    pub ram_init: RamInit,
+    pub emulator_config: EmulatorConfig,
+    pub palette: Palette,
+    pub overscan: Overscan,
+    pub transport: HeadlessTransport,
+    /// Stop after this many frames; if not set, run until the output is closed
+    pub frame_limit: Option<u64>,
+    /// Throttle to the NES's native frame rate instead of running as fast as output is consumed
+    pub realtime: bool,
+}
+
+#[derive(Debug, Default)]
+struct StreamInputPoller {
+    // P1 in the low byte, P2 in the high byte
+    joypad_bits: Arc<AtomicU16>,
+}
+
+impl InputPoller for StreamInputPoller {
+    fn poll_p1_input(&self) -> JoypadState {
+        joypad_from_bits(self.joypad_bits.load(Ordering::Relaxed) as u8)
+    }
+
+    fn poll_p2_input(&self) -> JoypadState {
+        joypad_from_bits((self.joypad_bits.load(Ordering::Relaxed) >> 8) as u8)
+    }
+}
+
+pub(crate) fn joypad_from_bits(bits: u8) -> JoypadState {
+    JoypadState {
+        a: bits & 0x01 != 0,
+        b: bits & 0x02 != 0,
+        select: bits & 0x04 != 0,
+        start: bits & 0x08 != 0,
+        up: bits & 0x10 != 0,
+        down: bits & 0x20 != 0,
+        left: bits & 0x40 != 0,
+        right: bits & 0x80 != 0,
+    }
+}
+
+/// Run the emulator headless, streaming frames and audio to the configured transport until the
+/// frame limit is reached or the receiving end closes the stream.
+///
+/// # Errors
+///
+/// This function will return an error if the ROM cannot be loaded, if the transport cannot be set
+/// up, or if writing output fails for any reason other than the receiving end closing the stream.
+pub fn run_headless(config: &HeadlessConfig) -> anyhow::Result<()> {
+    let nes_file_path = PathBuf::from(&config.nes_file_path);
+    let patch_file_path = config.patch_file_path.as_ref().map(PathBuf::from);
+    let rom_bytes = crate::read_rom_file(&nes_file_path, patch_file_path.as_deref())?;
+
+    let input_poller = StreamInputPoller::default();
+    let joypad_bits = Arc::clone(&input_poller.joypad_bits);
+
+    let mut emulator = Emulator::create(EmulatorCreateArgs {
+        rom_bytes,
+        sav_bytes: None,
+        forced_timing_mode: config.forced_timing_mode,
+        forced_mapper_number: config.forced_mapper_number,
+        ram_init: config.ram_init,
+        renderer: CaptureRenderer::new(config.palette.clone(), config.overscan),
+        audio_player: CaptureAudioPlayer::new(),
+        input_poller,
+        save_writer: NullSaveWriter,
+    })
+    .inspect_err(crate::log_rom_diagnostics)?;
+
+    let (output, input): (Box<dyn Write>, Box<dyn Read + Send>) = match config.transport {
+        HeadlessTransport::Stdio => (Box::new(io::stdout().lock()), Box::new(io::stdin())),
+        HeadlessTransport::Tcp { port } => {
+            let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))?;
+            log::info!("Waiting for a frame server connection on {}", listener.local_addr()?);
+            let (stream, addr) = listener.accept()?;
+            log::info!("Accepted frame server connection from {addr}");
+            stream.set_nodelay(true)?;
+            (Box::new(stream.try_clone()?), Box::new(stream))
+        }
+    };
+
+    // The reader thread is not joined; it exits on its own when the input stream is closed
+    thread::spawn(move || read_input(input, &joypad_bits));
+
+    let timing_mode = emulator.timing_mode();
+    let frame_duration = Duration::from_secs_f64(1.0 / timing_mode.nes_native_display_rate());
+
+    let mut output = BufWriter::new(output);
+    let mut header = Vec::with_capacity(8);
+    header.extend(jgnes_core::SCREEN_WIDTH.to_le_bytes());
+    header.extend(timing_mode.visible_screen_height().to_le_bytes());
+    header.extend((AUDIO_OUTPUT_FREQUENCY as u32).to_le_bytes());
+    if !write_or_closed(write_packet(&mut output, HEADER_TAG, &header))? {
+        return Ok(());
+    }
+
+    let mut frame_count = 0;
+    let mut next_frame_time = Instant::now() + frame_duration;
+    let mut audio_bytes = Vec::new();
+    while config.frame_limit.is_none_or(|frame_limit| frame_count < frame_limit) {
+        match emulator.tick(&config.emulator_config) {
+            Ok(TickEffect::None) => continue,
+            Ok(TickEffect::FrameRendered) => {}
+            Err(err) => match err.error {
+                EmulationError::Render(err)
+                | EmulationError::Audio(err)
+                | EmulationError::Save(err) => match err {},
+            },
+        }
+        frame_count += 1;
+
+        audio_bytes.clear();
+        let audio_player = emulator.get_audio_player_mut();
+        audio_bytes.extend(audio_player.samples.drain(..).flat_map(f32::to_le_bytes));
+
+        let result = write_packet(&mut output, VIDEO_TAG, &emulator.get_renderer().frame)
+            .and_then(|()| write_packet(&mut output, AUDIO_TAG, &audio_bytes))
+            .and_then(|()| output.flush());
+        if !write_or_closed(result)? {
+            log::info!("Frame server output closed after {frame_count} frames");
+            return Ok(());
+        }
+
+        if config.realtime {
+            let now = Instant::now();
+            if now < next_frame_time {
+                crate::sleep(next_frame_time - now);
+                next_frame_time += frame_duration;
+            } else {
+                // Running behind; don't try to catch up by running frames back-to-back
+                next_frame_time = now + frame_duration;
+            }
+        }
+    }
+
+    log::info!("Frame server stopped after reaching frame limit of {frame_count}");
+
+    Ok(())
+}
+
+fn write_packet<W: Write>(writer: &mut W, tag: u8, payload: &[u8]) -> io::Result<()> {
+    let len = u32::try_from(payload.len())
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "packet payload is too large"))?;
+    writer.write_all(&[tag])?;
+    writer.write_all(&len.to_le_bytes())?;
+    writer.write_all(payload)
+}
+
+// Returns Ok(false) if the receiving end closed the stream, which is a normal way to stop
+fn write_or_closed(result: io::Result<()>) -> io::Result<bool> {
+    match result {
+        Ok(()) => Ok(true),
+        Err(err)
+            if matches!(
+                err.kind(),
+                io::ErrorKind::BrokenPipe
+                    | io::ErrorKind::ConnectionReset
+                    | io::ErrorKind::ConnectionAborted
+            ) =>
+        {
+            Ok(false)
+        }
+        Err(err) => Err(err),
+    }
+}
+
+fn read_input(mut input: Box<dyn Read + Send>, joypad_bits: &AtomicU16) {
+    let mut message = [0; 2];
+    loop {
+        match input.read_exact(&mut message) {
+            Ok(()) => joypad_bits.store(u16::from_le_bytes(message), Ordering::Relaxed),
+            Err(err) => {
+                if err.kind() != io::ErrorKind::UnexpectedEof {
+                    log::warn!("Error reading frame server input: {err}");
+                }
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn packet_framing() {
+        let mut buffer = Vec::new();
+        write_packet(&mut buffer, AUDIO_TAG, &[1, 2, 3]).unwrap();
+        assert_eq!(buffer, [b'A', 3, 0, 0, 0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn joypad_bits_match_nes_read_order() {
+        let state = joypad_from_bits(0b1000_1001);
+        assert!(state.a && state.start && state.right);
+        assert!(!state.b && !state.select && !state.up && !state.down && !state.left);
+    }
+}
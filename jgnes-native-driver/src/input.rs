@@ -1,7 +1,9 @@
 use crate::{
-    AxisDirection, HatDirection, HotkeyConfig, InputConfig, InputConfigBase, JoystickInput,
+    AnalogStickConfig, AxisDirection, DpadRotation, HatDirection, HotkeyConfig, InputConfig,
+    InputConfigBase, InputTransforms, JoystickInput, JoystickInputConfig,
 };
 use jgnes_core::JoypadState;
+use jgnes_core::logging::INPUT_TARGET;
 use sdl2::JoystickSubsystem;
 use sdl2::event::Event;
 use sdl2::joystick::{HatState, Joystick};
@@ -40,6 +42,126 @@ pub enum Hotkey {
     HardReset,
     FastForward,
     Rewind,
+    SaveClip,
+    SaveCapture,
+    NextGame,
+    PreviousGame,
+    ToggleWavCapture,
+    ToggleScrollOverlay,
+    ToggleInputDisplay,
+    TimerStartSplit,
+    TimerReset,
+    RecordMacro,
+    PlayMacro,
+    RewindForward,
+    DumpRegisterWrites,
+    UndoLoadState,
+}
+
+/// Turbo buttons alternate between pressed and released every this many frames
+const TURBO_HALF_PERIOD: u64 = 2;
+
+/// Longest input macro that can be recorded, about one minute of NTSC frames
+const MAX_MACRO_FRAMES: usize = 3600;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum MacroStatus {
+    RecordingStarted,
+    Recorded { frames: usize },
+    PlaybackStarted,
+    NothingRecorded,
+    Busy,
+}
+
+#[derive(Debug, Default)]
+enum MacroState {
+    #[default]
+    Idle,
+    RecordPending,
+    Recording(Vec<(JoypadState, JoypadState)>),
+    PlayPending,
+    Playing(usize),
+}
+
+// Recording and playback always start on a frame boundary so that a macro replays exactly the
+// per-frame inputs that were recorded, regardless of when during a frame the hotkey was pressed
+#[derive(Debug, Default)]
+struct InputMacroStore {
+    state: MacroState,
+    recorded: Vec<(JoypadState, JoypadState)>,
+}
+
+impl InputMacroStore {
+    fn toggle_recording(&mut self) -> MacroStatus {
+        match std::mem::take(&mut self.state) {
+            MacroState::Idle => {
+                self.state = MacroState::RecordPending;
+                MacroStatus::RecordingStarted
+            }
+            MacroState::RecordPending => MacroStatus::Recorded { frames: 0 },
+            MacroState::Recording(frames) => {
+                let len = frames.len();
+                if !frames.is_empty() {
+                    self.recorded = frames;
+                }
+                MacroStatus::Recorded { frames: len }
+            }
+            state @ (MacroState::PlayPending | MacroState::Playing(_)) => {
+                self.state = state;
+                MacroStatus::Busy
+            }
+        }
+    }
+
+    fn play(&mut self) -> MacroStatus {
+        match self.state {
+            MacroState::RecordPending | MacroState::Recording(_) => MacroStatus::Busy,
+            MacroState::Idle | MacroState::PlayPending | MacroState::Playing(_) => {
+                if self.recorded.is_empty() {
+                    return MacroStatus::NothingRecorded;
+                }
+
+                self.state = MacroState::PlayPending;
+                MacroStatus::PlaybackStarted
+            }
+        }
+    }
+
+    // Called at the end of every frame with the inputs that the emulator saw during that frame
+    fn advance_frame(&mut self, frame_inputs: (JoypadState, JoypadState)) {
+        self.state = match std::mem::take(&mut self.state) {
+            MacroState::Idle => MacroState::Idle,
+            MacroState::RecordPending => MacroState::Recording(Vec::new()),
+            MacroState::Recording(mut frames) => {
+                frames.push(frame_inputs);
+                if frames.len() < MAX_MACRO_FRAMES {
+                    MacroState::Recording(frames)
+                } else {
+                    log::info!(
+                        target: INPUT_TARGET,
+                        "Input macro reached maximum length of {MAX_MACRO_FRAMES} frames"
+                    );
+                    self.recorded = frames;
+                    MacroState::Idle
+                }
+            }
+            MacroState::PlayPending => MacroState::Playing(0),
+            MacroState::Playing(frame) => {
+                if frame + 1 < self.recorded.len() {
+                    MacroState::Playing(frame + 1)
+                } else {
+                    MacroState::Idle
+                }
+            }
+        };
+    }
+
+    fn playback_inputs(&self) -> Option<(JoypadState, JoypadState)> {
+        match self.state {
+            MacroState::Playing(frame) => Some(self.recorded[frame]),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -48,6 +170,83 @@ enum Input {
     Joystick(JoystickInput),
 }
 
+type AxisId = (u32, u8);
+
+// The joystick axes that a player has mapped to D-pad directions, identified by device ID and axis
+// index
+#[derive(Debug, Clone, Copy, Default)]
+struct AnalogStick {
+    horizontal_axis: Option<AxisId>,
+    vertical_axis: Option<AxisId>,
+    config: AnalogStickConfig,
+}
+
+impl AnalogStick {
+    fn new(joystick_config: &JoystickInputConfig, config: AnalogStickConfig) -> Self {
+        let axis_of = |inputs: [Option<JoystickInput>; 2]| {
+            inputs.into_iter().flatten().find_map(|input| match input {
+                JoystickInput::Axis { device_id, axis_idx, .. } => Some((device_id, axis_idx)),
+                JoystickInput::Button { .. } | JoystickInput::Hat { .. } => None,
+            })
+        };
+
+        Self {
+            horizontal_axis: axis_of([joystick_config.left, joystick_config.right]),
+            vertical_axis: axis_of([joystick_config.up, joystick_config.down]),
+            config,
+        }
+    }
+
+    fn contains(&self, axis: AxisId) -> bool {
+        self.horizontal_axis == Some(axis) || self.vertical_axis == Some(axis)
+    }
+
+    fn other_axis(&self, axis: AxisId) -> Option<AxisId> {
+        if self.horizontal_axis == Some(axis) {
+            self.vertical_axis
+        } else if self.vertical_axis == Some(axis) {
+            self.horizontal_axis
+        } else {
+            None
+        }
+    }
+}
+
+// Which of each pair of opposing directions was pressed most recently
+#[derive(Debug, Clone, Copy, Default)]
+struct LastPressedDirections {
+    left_over_right: bool,
+    up_over_down: bool,
+}
+
+impl LastPressedDirections {
+    fn record_press(&mut self, button: Button) {
+        match button {
+            Button::Left => self.left_over_right = true,
+            Button::Right => self.left_over_right = false,
+            Button::Up => self.up_over_down = true,
+            Button::Down => self.up_over_down = false,
+            Button::A | Button::B | Button::Start | Button::Select => {}
+        }
+    }
+
+    fn resolve(self, state: JoypadState) -> JoypadState {
+        let mut resolved = state;
+
+        if state.left && state.right {
+            resolved.left = self.left_over_right;
+            resolved.right = !self.left_over_right;
+        }
+
+        if state.up && state.down {
+            resolved.up = self.up_over_down;
+            resolved.down = !self.up_over_down;
+        }
+
+        resolved
+    }
+}
+
 pub(crate) struct SdlInputHandler<'a> {
     raw_p1_joypad_state: JoypadState,
     p1_joypad_state: Rc<Cell<JoypadState>>,
@@ -58,9 +257,19 @@ pub(crate) struct SdlInputHandler<'a> {
     hotkey_mapping: HashMap<Keycode, Vec<Hotkey>>,
     axis_deadzone: u16,
     allow_opposite_directions: bool,
+    prefer_last_pressed_direction: bool,
+    p1_last_pressed: LastPressedDirections,
+    p2_last_pressed: LastPressedDirections,
+    p1_analog_stick: AnalogStick,
+    p2_analog_stick: AnalogStick,
+    axis_values: HashMap<AxisId, i16>,
+    p1_transforms: InputTransforms,
+    p2_transforms: InputTransforms,
+    frame_count: u64,
     joystick_subsystem: &'a JoystickSubsystem,
     joysticks: HashMap<u32, Joystick>,
     instance_id_to_device_id: HashMap<u32, u32>,
+    input_macros: InputMacroStore,
 }
 
 const EMPTY_VEC: &Vec<Hotkey> = &Vec::new();
@@ -82,9 +291,19 @@ impl<'a> SdlInputHandler<'a> {
             hotkey_mapping: HashMap::new(),
             axis_deadzone: input_config.axis_deadzone,
             allow_opposite_directions: input_config.allow_opposite_directions,
+            prefer_last_pressed_direction: input_config.prefer_last_pressed_direction,
+            p1_last_pressed: LastPressedDirections::default(),
+            p2_last_pressed: LastPressedDirections::default(),
+            p1_analog_stick: AnalogStick::default(),
+            p2_analog_stick: AnalogStick::default(),
+            axis_values: HashMap::new(),
+            p1_transforms: input_config.p1.transforms,
+            p2_transforms: input_config.p2.transforms,
+            frame_count: 0,
             joystick_subsystem,
             joysticks: HashMap::new(),
             instance_id_to_device_id: HashMap::new(),
+            input_macros: InputMacroStore::default(),
         };
 
         input_handler.reload_input_config(input_config);
@@ -112,6 +331,15 @@ impl<'a> SdlInputHandler<'a> {
         self.hotkey_mapping.clear();
         populate_hotkey_map(&mut self.hotkey_mapping, &input_config.hotkeys);
 
+        self.p1_analog_stick =
+            AnalogStick::new(&input_config.p1.joystick, input_config.p1.analog_stick);
+        self.p2_analog_stick =
+            AnalogStick::new(&input_config.p2.joystick, input_config.p2.analog_stick);
+        self.prefer_last_pressed_direction = input_config.prefer_last_pressed_direction;
+
+        self.p1_transforms = input_config.p1.transforms;
+        self.p2_transforms = input_config.p2.transforms;
+
         // Clear all current joypad states in case there were any lingering pressed inputs
         self.raw_p1_joypad_state = JoypadState::default();
         self.raw_p2_joypad_state = JoypadState::default();
@@ -131,6 +359,7 @@ impl<'a> SdlInputHandler<'a> {
                 let joystick = self.joystick_subsystem.open(device_id)?;
                 let instance_id = joystick.instance_id();
                 log::info!(
+                    target: INPUT_TARGET,
                     "Opened joystick device id {device_id} with instance id {instance_id}: {} ({})",
                     joystick.name(),
                     joystick.guid()
@@ -142,6 +371,7 @@ impl<'a> SdlInputHandler<'a> {
                 if let Some(device_id) = self.instance_id_to_device_id.remove(&instance_id) {
                     if let Some(removed) = self.joysticks.remove(&device_id) {
                         log::info!(
+                            target: INPUT_TARGET,
                             "Joystick {device_id} removed (instance id {instance_id}): {}",
                             removed.name()
                         );
@@ -162,27 +392,16 @@ impl<'a> SdlInputHandler<'a> {
             }
             Event::JoyAxisMotion { which: instance_id, axis_idx, value, .. } => {
                 if let Some(&device_id) = self.instance_id_to_device_id.get(&instance_id) {
-                    let positive = JoystickInput::Axis {
-                        device_id,
-                        axis_idx,
-                        direction: AxisDirection::Positive,
-                    };
-                    let negative = JoystickInput::Axis {
-                        device_id,
-                        axis_idx,
-                        direction: AxisDirection::Negative,
-                    };
-                    if value.saturating_abs() >= self.axis_deadzone as i16 {
-                        if value > 0 {
-                            self.update_joypad_state(Input::Joystick(positive), true);
-                            self.update_joypad_state(Input::Joystick(negative), false);
-                        } else {
-                            self.update_joypad_state(Input::Joystick(positive), false);
-                            self.update_joypad_state(Input::Joystick(negative), true);
+                    let axis = (device_id, axis_idx);
+                    self.axis_values.insert(axis, value);
+                    self.update_axis(axis);
+
+                    // With a diagonal window, moving one axis of a stick can change whether the
+                    // other axis's direction is pressed
+                    for stick in [self.p1_analog_stick, self.p2_analog_stick] {
+                        if let Some(other_axis) = stick.other_axis(axis) {
+                            self.update_axis(other_axis);
                         }
-                    } else {
-                        self.update_joypad_state(Input::Joystick(positive), false);
-                        self.update_joypad_state(Input::Joystick(negative), false);
                     }
                 }
             }
@@ -201,20 +420,109 @@ impl<'a> SdlInputHandler<'a> {
             _ => {}
         }
 
-        self.p1_joypad_state.set(if self.allow_opposite_directions {
-            self.raw_p1_joypad_state
-        } else {
-            self.raw_p1_joypad_state.sanitize_opposing_directions()
-        });
-        self.p2_joypad_state.set(if self.allow_opposite_directions {
-            self.raw_p2_joypad_state
-        } else {
-            self.raw_p2_joypad_state.sanitize_opposing_directions()
-        });
+        self.update_polled_states();
 
         Ok(())
     }
 
+    fn update_axis(&mut self, (device_id, axis_idx): AxisId) {
+        let value = self.axis_values.get(&(device_id, axis_idx)).copied().unwrap_or(0);
+        let pressed = self.axis_pressed((device_id, axis_idx), value);
+
+        let positive =
+            JoystickInput::Axis { device_id, axis_idx, direction: AxisDirection::Positive };
+        let negative =
+            JoystickInput::Axis { device_id, axis_idx, direction: AxisDirection::Negative };
+        self.update_joypad_state(Input::Joystick(positive), pressed && value > 0);
+        self.update_joypad_state(Input::Joystick(negative), pressed && value < 0);
+    }
+
+    fn axis_pressed(&self, axis: AxisId, value: i16) -> bool {
+        let Some(stick) = [self.p1_analog_stick, self.p2_analog_stick]
+            .into_iter()
+            .find(|stick| stick.contains(axis))
+        else {
+            return value.unsigned_abs() >= self.axis_deadzone;
+        };
+
+        let axis_value = |axis: Option<AxisId>| {
+            axis.and_then(|axis| self.axis_values.get(&axis).copied()).unwrap_or(0)
+        };
+        let (horizontal, vertical) = stick_directions(
+            (axis_value(stick.horizontal_axis), axis_value(stick.vertical_axis)),
+            (
+                stick.config.horizontal_deadzone.unwrap_or(self.axis_deadzone),
+                stick.config.vertical_deadzone.unwrap_or(self.axis_deadzone),
+            ),
+            stick.config.diagonal_window,
+        );
+
+        if stick.horizontal_axis == Some(axis) { horizontal } else { vertical }
+    }
+
+    // Inputs from a macro being played back take precedence over live inputs. Macros are recorded
+    // after transforms are applied, so transforms are not applied again during playback.
+    fn update_polled_states(&self) {
+        if let Some((p1, p2)) = self.input_macros.playback_inputs() {
+            self.p1_joypad_state.set(p1);
+            self.p2_joypad_state.set(p2);
+            return;
+        }
+
+        let turbo_pressed = (self.frame_count / TURBO_HALF_PERIOD).is_multiple_of(2);
+        for (joypad_state, raw_joypad_state, last_pressed, transforms) in [
+            (
+                &self.p1_joypad_state,
+                self.raw_p1_joypad_state,
+                self.p1_last_pressed,
+                self.p1_transforms,
+            ),
+            (
+                &self.p2_joypad_state,
+                self.raw_p2_joypad_state,
+                self.p2_last_pressed,
+                self.p2_transforms,
+            ),
+        ] {
+            // Opposing directions are resolved before transforms since the last pressed directions
+            // are tracked before D-pad rotation
+            let raw_joypad_state =
+                if self.prefer_last_pressed_direction && !self.allow_opposite_directions {
+                    last_pressed.resolve(raw_joypad_state)
+                } else {
+                    raw_joypad_state
+                };
+
+            let transformed = apply_transforms(raw_joypad_state, transforms, turbo_pressed);
+            joypad_state.set(if self.allow_opposite_directions {
+                transformed
+            } else {
+                transformed.sanitize_opposing_directions()
+            });
+        }
+    }
+
+    /// Start recording an input macro at the next frame, or stop the current recording.
+    pub(crate) fn toggle_macro_recording(&mut self) -> MacroStatus {
+        self.input_macros.toggle_recording()
+    }
+
+    /// Play back the recorded input macro starting at the next frame.
+    pub(crate) fn play_macro(&mut self) -> MacroStatus {
+        self.input_macros.play()
+    }
+
+    /// Record or play back macro inputs; must be called once at the end of every emulated frame.
+    pub(crate) fn end_frame(&mut self) {
+        self.frame_count += 1;
+        self.input_macros.advance_frame(self.joypad_states());
+        self.update_polled_states();
+    }
+
+    pub(crate) fn joypad_states(&self) -> (JoypadState, JoypadState) {
+        (self.p1_joypad_state.get(), self.p2_joypad_state.get())
+    }
+
     pub(crate) fn check_for_hotkeys(&self, keycode: Keycode) -> &Vec<Hotkey> {
         self.hotkey_mapping.get(&keycode).unwrap_or(EMPTY_VEC)
     }
@@ -226,9 +534,9 @@ impl<'a> SdlInputHandler<'a> {
         };
 
         for (player, button) in input_mapping.unwrap_or(&Vec::new()).iter().copied() {
-            let joypad_state = match player {
-                Player::Player1 => &mut self.raw_p1_joypad_state,
-                Player::Player2 => &mut self.raw_p2_joypad_state,
+            let (joypad_state, last_pressed) = match player {
+                Player::Player1 => (&mut self.raw_p1_joypad_state, &mut self.p1_last_pressed),
+                Player::Player2 => (&mut self.raw_p2_joypad_state, &mut self.p2_last_pressed),
             };
 
             let field = match button {
@@ -242,6 +550,9 @@ impl<'a> SdlInputHandler<'a> {
                 Button::Select => &mut joypad_state.select,
             };
 
+            if value && !*field {
+                last_pressed.record_press(button);
+            }
             *field = value;
         }
     }
@@ -255,6 +566,53 @@ impl<'a> SdlInputHandler<'a> {
     }
 }
 
+// Returns whether the horizontal and vertical directions of a stick are pressed
+fn stick_directions(
+    (x, y): (i16, i16),
+    (horizontal_deadzone, vertical_deadzone): (u16, u16),
+    diagonal_window: u8,
+) -> (bool, bool) {
+    let horizontal = x.unsigned_abs() >= horizontal_deadzone;
+    let vertical = y.unsigned_abs() >= vertical_deadzone;
+    if diagonal_window == 0 || !(horizontal || vertical) {
+        return (horizontal, vertical);
+    }
+
+    // 0 degrees is fully horizontal and 90 degrees is fully vertical
+    let angle = f64::from(y).abs().atan2(f64::from(x).abs()).to_degrees();
+    let half_window = f64::from(diagonal_window.min(90)) / 2.0;
+    if angle < 45.0 - half_window {
+        (true, false)
+    } else if angle > 45.0 + half_window {
+        (false, true)
+    } else {
+        (true, true)
+    }
+}
+
+fn apply_transforms(
+    state: JoypadState,
+    transforms: InputTransforms,
+    turbo_pressed: bool,
+) -> JoypadState {
+    let (a, b) = if transforms.swap_a_b { (state.b, state.a) } else { (state.a, state.b) };
+    let (up, right, down, left) = match transforms.dpad_rotation {
+        DpadRotation::None => (state.up, state.right, state.down, state.left),
+        DpadRotation::Clockwise => (state.left, state.up, state.right, state.down),
+        DpadRotation::Counterclockwise => (state.right, state.down, state.left, state.up),
+    };
+
+    JoypadState {
+        up,
+        down,
+        left,
+        right,
+        a: a && (!transforms.turbo_a || turbo_pressed),
+        b: b && (!transforms.turbo_b || turbo_pressed),
+        ..state
+    }
+}
+
 fn populate_map<K>(
     map: &mut HashMap<K, Vec<(Player, Button)>>,
     config: &InputConfigBase<K>,
@@ -288,6 +646,20 @@ fn populate_hotkey_map(map: &mut HashMap<Keycode, Vec<Hotkey>>, config: &HotkeyC
         (Hotkey::HardReset, config.hard_reset.as_ref()),
         (Hotkey::FastForward, config.fast_forward.as_ref()),
         (Hotkey::Rewind, config.rewind.as_ref()),
+        (Hotkey::SaveClip, config.save_clip.as_ref()),
+        (Hotkey::SaveCapture, config.save_capture.as_ref()),
+        (Hotkey::NextGame, config.next_game.as_ref()),
+        (Hotkey::PreviousGame, config.previous_game.as_ref()),
+        (Hotkey::ToggleWavCapture, config.toggle_wav_capture.as_ref()),
+        (Hotkey::ToggleScrollOverlay, config.toggle_scroll_overlay.as_ref()),
+        (Hotkey::ToggleInputDisplay, config.toggle_input_display.as_ref()),
+        (Hotkey::TimerStartSplit, config.timer_start_split.as_ref()),
+        (Hotkey::TimerReset, config.timer_reset.as_ref()),
+        (Hotkey::RecordMacro, config.record_macro.as_ref()),
+        (Hotkey::PlayMacro, config.play_macro.as_ref()),
+        (Hotkey::RewindForward, config.rewind_forward.as_ref()),
+        (Hotkey::DumpRegisterWrites, config.dump_register_writes.as_ref()),
+        (Hotkey::UndoLoadState, config.undo_load_state.as_ref()),
     ] {
         if let Some(config_input) = config_input {
             add_to_map(map, Keycode::from_name(config_input).unwrap(), hotkey);
@@ -316,3 +688,92 @@ fn hat_directions_for(state: HatState) -> ArrayVec<[HatDirection; 2]> {
         HatState::Centered => [].into_iter().collect(),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn inputs(right: bool) -> (JoypadState, JoypadState) {
+        (JoypadState { right, ..JoypadState::new() }, JoypadState::new())
+    }
+
+    #[test]
+    fn stick_diagonal_window() {
+        let deadzones = (5000, 5000);
+
+        // Each axis independently
+        assert_eq!(stick_directions((10000, 6000), deadzones, 0), (true, true));
+        assert_eq!(stick_directions((10000, 4000), deadzones, 0), (true, false));
+        assert_eq!(stick_directions((1000, 1000), deadzones, 0), (false, false));
+
+        // ~31 degrees, outside a 20 degree window around the diagonal but inside a 45 degree window
+        assert_eq!(stick_directions((10000, 6000), deadzones, 20), (true, false));
+        assert_eq!(stick_directions((10000, 6000), deadzones, 45), (true, true));
+        assert_eq!(stick_directions((-6000, -10000), deadzones, 20), (false, true));
+
+        // Both axes inside their deadzones
+        assert_eq!(stick_directions((3000, 3000), deadzones, 45), (false, false));
+    }
+
+    #[test]
+    fn last_pressed_direction_wins() {
+        let mut last_pressed = LastPressedDirections::default();
+        let state = JoypadState { left: true, right: true, ..JoypadState::new() };
+
+        last_pressed.record_press(Button::Left);
+        assert_eq!(last_pressed.resolve(state), JoypadState { left: true, ..JoypadState::new() });
+
+        last_pressed.record_press(Button::Right);
+        assert_eq!(last_pressed.resolve(state), JoypadState { right: true, ..JoypadState::new() });
+    }
+
+    #[test]
+    fn transforms() {
+        let state = JoypadState { up: true, a: true, ..JoypadState::new() };
+
+        let swapped = InputTransforms { swap_a_b: true, ..InputTransforms::default() };
+        assert_eq!(
+            apply_transforms(state, swapped, true),
+            JoypadState { up: true, b: true, ..JoypadState::new() }
+        );
+
+        let rotated = InputTransforms {
+            dpad_rotation: DpadRotation::Clockwise,
+            ..InputTransforms::default()
+        };
+        assert_eq!(
+            apply_transforms(state, rotated, true),
+            JoypadState { right: true, a: true, ..JoypadState::new() }
+        );
+
+        let turbo = InputTransforms { turbo_a: true, ..InputTransforms::default() };
+        assert_eq!(apply_transforms(state, turbo, true), state);
+        assert_eq!(
+            apply_transforms(state, turbo, false),
+            JoypadState { up: true, ..JoypadState::new() }
+        );
+    }
+
+    #[test]
+    fn macros_replay_recorded_frames() {
+        let mut store = InputMacroStore::default();
+        assert_eq!(store.play(), MacroStatus::NothingRecorded);
+
+        assert_eq!(store.toggle_recording(), MacroStatus::RecordingStarted);
+        // Recording starts at the next frame boundary
+        store.advance_frame(inputs(false));
+        store.advance_frame(inputs(true));
+        store.advance_frame(inputs(false));
+        assert_eq!(store.play(), MacroStatus::Busy);
+        assert_eq!(store.toggle_recording(), MacroStatus::Recorded { frames: 2 });
+
+        assert_eq!(store.play(), MacroStatus::PlaybackStarted);
+        assert_eq!(store.playback_inputs(), None);
+        store.advance_frame(inputs(false));
+        assert_eq!(store.playback_inputs(), Some(inputs(true)));
+        store.advance_frame(inputs(true));
+        assert_eq!(store.playback_inputs(), Some(inputs(false)));
+        store.advance_frame(inputs(false));
+        assert_eq!(store.playback_inputs(), None);
+    }
+}
@@ -1,37 +1,97 @@
+mod capture;
+mod clip;
 mod config;
+mod confirm;
+mod crashreport;
+mod encode;
+mod framediff;
+mod frametiming;
+mod geometry;
+mod headless;
 mod input;
+mod livesplit;
+mod logging;
+mod osd;
+mod pacing;
+mod paths;
+mod playlist;
+mod power;
+mod remote;
+mod savestate;
+mod savewriter;
+mod timer;
+mod title;
+mod watch;
 
 use jgnes_core::audio::{DownsampleAction, DownsampleCounter, LowPassFilter};
+use jgnes_core::logging::{AUDIO_TARGET, INPUT_TARGET};
+use jgnes_core::patch;
+use jgnes_core::patch::PatchFormat;
 use jgnes_core::{
-    AudioPlayer, ColorEmphasis, EmulationError, EmulationState, Emulator, EmulatorConfig,
-    EmulatorCreateArgs, FrameBuffer, InputPoller, JoypadState, Renderer, SaveWriter, TickEffect,
+    AudioPlayer, CartridgeSwapArgs, ClassifyError, ColorEmphasis, EmulationError, EmulationState,
+    Emulator, EmulatorConfig, EmulatorCreateArgs, ErrorSeverity, FrameBuffer, InitializationError,
+    InputPoller, JoypadState, RamInit, RegisterWriteEvent, Renderer, Sprite0HitEvent, TickEffect,
     TimingMode,
 };
-use sdl2::EventPump;
 use sdl2::audio::{AudioQueue, AudioSpecDesired};
 use sdl2::event::{Event, EventType, WindowEvent};
+use sdl2::keyboard::Keycode;
 use sdl2::pixels::{Color, PixelFormatEnum};
 use sdl2::rect::Rect;
 use sdl2::render::{Texture, TextureCreator, TextureValueError, WindowCanvas};
+use sdl2::sys::SDL_RenderSetVSync;
 use sdl2::video::{FullscreenType, Window};
+use sdl2::{EventPump, VideoSubsystem};
 use std::cell::Cell;
 use std::collections::VecDeque;
+use std::convert::Infallible;
 use std::ffi::OsStr;
-use std::fs::File;
+use std::fmt::Display;
+use std::io;
 use std::path::{Path, PathBuf};
 use std::rc::Rc;
-use std::sync::atomic::Ordering;
-use std::time::{Duration, SystemTime};
-use std::{fs, thread};
+use std::time::{Duration, Instant, SystemTime};
+use std::{fs, mem, thread};
 use thiserror::Error;
 
+use crate::capture::RecordingRenderer;
+use crate::clip::{ClipExportArgs, ClipSegment, InputLog};
 pub use crate::config::{
-    AxisDirection, HatDirection, HotkeyConfig, InputCollectResult, InputConfig, InputConfigBase,
-    InputType, JgnesDynamicConfig, JgnesNativeConfig, JgnesSharedConfig, JoystickInput,
-    JoystickInputConfig, KeyboardInput, KeyboardInputConfig, NativeRenderer, PlayerInputConfig,
+    AnalogStickConfig, AxisDirection, CaptureFormat, DpadRotation, EmulatorCommand, EmulatorStatus,
+    FramePacing, HatDirection, HiDpiScaling, HotkeyConfig, InputCollectResult, InputConfig,
+    InputConfigBase, InputProfile, InputTransforms, InputType, JgnesDynamicConfig,
+    JgnesNativeConfig, JgnesSharedConfig, JoystickInput, JoystickInputConfig, KeyboardInput,
+    KeyboardInputConfig, NativeRenderer, PlayerInputConfig, RomLoadRequest, load_shader_preset,
+};
+use crate::confirm::{ConfirmAction, ConfirmPrompt};
+pub use crate::crashreport::{CrashReport, write_crash_report};
+use crate::encode::WavWriter;
+pub use crate::framediff::{FrameDiffConfig, FrameDiffMode, run_frame_diff};
+pub use crate::frametiming::FRAME_TIMING_LOG_DURATION;
+use crate::frametiming::FrameTimingLog;
+pub use crate::geometry::{WindowGeometry, WindowGeometryMap};
+pub use crate::headless::{HeadlessConfig, HeadlessTransport, run_headless};
+use crate::input::{Hotkey, MacroStatus, SdlInputHandler};
+use crate::livesplit::LiveSplitClient;
+pub use crate::logging::{
+    LogLine, init_logger, recent_log_lines, set_subsystem_log_level, subsystem_log_level,
+};
+use crate::osd::{OSD_DIM_TEXT_COLOR, OSD_TEXT_COLOR, OsdLine};
+use crate::pacing::FramePacer;
+pub use crate::paths::{
+    DataPaths, PORTABLE_MARKER_FILE_NAME, user_runtime_file, write_private_file,
+};
+use crate::playlist::{Playlist, PlaylistDirection};
+pub use crate::power::LowPowerMode;
+use crate::remote::RemoteControlServer;
+use crate::savestate::{SaveStateCompletion, SaveStateWorker};
+use crate::savewriter::FsSaveWriter;
+use crate::timer::SpeedrunTimer;
+use crate::title::TitleMessage;
+use crate::watch::RomWatcher;
+use jgnes_renderer::config::{
+    FrameSkip, GpuFilterMode, PowerPreference, RendererConfig, Shader, VSyncMode, WgpuBackend,
 };
-use crate::input::{Hotkey, SdlInputHandler};
-use jgnes_renderer::config::{FrameSkip, RendererConfig, VSyncMode};
 use jgnes_renderer::{WgpuRenderer, colors};
 
 const SDL_PIXEL_FORMAT: PixelFormatEnum = PixelFormatEnum::RGB24;
@@ -53,10 +113,85 @@ impl SdlRendererError {
     }
 }
 
+impl ClassifyError for SdlRendererError {
+    fn severity(&self) -> ErrorSeverity {
+        ErrorSeverity::Fatal
+    }
+}
+
+#[derive(Debug, Error)]
+enum SdlAudioError {
+    #[error("Error queueing audio samples: {msg}")]
+    QueueAudio { msg: String },
+}
+
+impl ClassifyError for SdlAudioError {
+    fn severity(&self) -> ErrorSeverity {
+        // The samples that failed to queue are dropped
+        match self {
+            Self::QueueAudio { .. } => ErrorSeverity::Recoverable,
+        }
+    }
+}
+
+const SDL_SCALE_QUALITY_HINT: &str = "SDL_RENDER_SCALE_QUALITY";
+
+struct SdlTextures<'a> {
+    // Frame-sized texture that the NES frame buffer is copied into
+    frame: Texture<'a>,
+    // Optional render target texture that the frame is integer upscaled into before the final
+    // (potentially non-integer) scale to the window size
+    prescaled: Option<Texture<'a>>,
+}
+
+impl<'a> SdlTextures<'a> {
+    fn new<T>(
+        texture_creator: &'a TextureCreator<T>,
+        config: &RendererConfig,
+        timing_mode: TimingMode,
+    ) -> Result<Self, SdlRendererError> {
+        let width: u32 = jgnes_core::SCREEN_WIDTH.into();
+        let height: u32 = timing_mode.visible_screen_height().into();
+
+        // SDL2 applies the scale quality hint when a texture is created, not when it is rendered
+        let prescale_factor = match config.shader {
+            Shader::None => None,
+            // Gaussian blur is not supported with SDL2, but its prescale factor still applies
+            Shader::Prescale(render_scale)
+            | Shader::GaussianBlur { prescale_factor: render_scale, .. } => {
+                Some(render_scale.get()).filter(|&factor| factor > 1)
+            }
+        };
+        let output_scale_quality = match config.gpu_filter_mode {
+            GpuFilterMode::NearestNeighbor => "nearest",
+            GpuFilterMode::LinearInterpolation => "linear",
+        };
+
+        sdl2::hint::set(
+            SDL_SCALE_QUALITY_HINT,
+            if prescale_factor.is_some() { "nearest" } else { output_scale_quality },
+        );
+        let frame = texture_creator.create_texture_streaming(SDL_PIXEL_FORMAT, width, height)?;
+
+        let prescaled = prescale_factor
+            .map(|factor| {
+                sdl2::hint::set(SDL_SCALE_QUALITY_HINT, output_scale_quality);
+                texture_creator.create_texture_target(
+                    SDL_PIXEL_FORMAT,
+                    factor * width,
+                    factor * height,
+                )
+            })
+            .transpose()?;
+
+        Ok(Self { frame, prescaled })
+    }
+}
+
 struct SdlRenderer<'a, T> {
     canvas: WindowCanvas,
     texture_creator: &'a TextureCreator<T>,
-    texture: Texture<'a>,
+    textures: SdlTextures<'a>,
     config: RendererConfig,
     frame_skip: FrameSkip,
     total_frames: u64,
@@ -69,21 +204,34 @@ impl<'a, T> SdlRenderer<'a, T> {
         texture_creator: &'a TextureCreator<T>,
         config: RendererConfig,
     ) -> anyhow::Result<Self> {
-        let texture = texture_creator.create_texture_streaming(
-            SDL_PIXEL_FORMAT,
-            jgnes_core::SCREEN_WIDTH.into(),
-            TimingMode::Ntsc.visible_screen_height().into(),
-        )?;
+        let textures = SdlTextures::new(texture_creator, &config, TimingMode::Ntsc)?;
         Ok(Self {
             canvas,
             texture_creator,
-            texture,
+            textures,
             config,
             frame_skip: FrameSkip::ZERO,
             total_frames: 0,
             timing_mode: TimingMode::Ntsc,
         })
     }
+
+    fn update_vsync_mode(&mut self, vsync_mode: VSyncMode) -> Result<(), SdlRendererError> {
+        // Fast and adaptive VSync are not supported by SDL2; treat them the same as disabled
+        let vsync = i32::from(vsync_mode == VSyncMode::Enabled);
+
+        // SAFETY: The raw renderer pointer is valid for as long as the canvas is alive, and the
+        // canvas is borrowed for the duration of this call.
+        let result = unsafe { SDL_RenderSetVSync(self.canvas.raw(), vsync) };
+        if result != 0 {
+            return Err(SdlRendererError::msg(format!(
+                "unable to change VSync mode: {}",
+                sdl2::get_error()
+            )));
+        }
+
+        Ok(())
+    }
 }
 
 impl<T> Renderer for SdlRenderer<'_, T> {
@@ -100,19 +248,22 @@ impl<T> Renderer for SdlRenderer<'_, T> {
             return Ok(());
         }
 
-        self.texture
+        self.textures
+            .frame
             .with_lock(
                 None,
                 colors::sdl_texture_updater(
                     frame_buffer,
                     color_emphasis,
+                    &self.config.palette,
                     self.config.overscan,
                     self.timing_mode,
                 ),
             )
             .map_err(SdlRendererError::msg)?;
 
-        let (window_width, window_height) = self.canvas.window().size();
+        let (window_width, window_height) =
+            self.canvas.output_size().map_err(SdlRendererError::msg)?;
         let display_area = jgnes_renderer::determine_display_area(
             window_width,
             window_height,
@@ -128,7 +279,25 @@ impl<T> Renderer for SdlRenderer<'_, T> {
             display_area.width,
             display_area.height,
         );
-        self.canvas.copy(&self.texture, None, dst).map_err(SdlRendererError::msg)?;
+
+        match &mut self.textures.prescaled {
+            Some(prescaled) => {
+                let frame = &self.textures.frame;
+                let mut copy_result = Ok(());
+                self.canvas
+                    .with_texture_canvas(prescaled, |canvas| {
+                        copy_result = canvas.copy(frame, None, None);
+                    })
+                    .map_err(|err| SdlRendererError::msg(err.to_string()))?;
+                copy_result.map_err(SdlRendererError::msg)?;
+
+                self.canvas.copy(prescaled, None, dst).map_err(SdlRendererError::msg)?;
+            }
+            None => {
+                self.canvas.copy(&self.textures.frame, None, dst).map_err(SdlRendererError::msg)?;
+            }
+        }
+
         self.canvas.present();
 
         Ok(())
@@ -137,11 +306,7 @@ impl<T> Renderer for SdlRenderer<'_, T> {
     fn set_timing_mode(&mut self, timing_mode: TimingMode) -> Result<(), Self::Err> {
         self.timing_mode = timing_mode;
 
-        self.texture = self.texture_creator.create_texture_streaming(
-            SDL_PIXEL_FORMAT,
-            jgnes_core::SCREEN_WIDTH.into(),
-            timing_mode.visible_screen_height().into(),
-        )?;
+        self.textures = SdlTextures::new(self.texture_creator, &self.config, timing_mode)?;
 
         Ok(())
     }
@@ -152,11 +317,13 @@ struct SdlAudioPlayer {
     sync_to_audio: bool,
     internal_buffer_size: u32,
     audio_sync_threshold: u32,
+    audio_sync_poll_interval: Duration,
     sample_queue: Vec<f32>,
     low_pass_filter: LowPassFilter,
     downsample_counter: DownsampleCounter,
     frame_skip: FrameSkip,
     total_output_samples: u64,
+    wav_writer: Option<WavWriter>,
 }
 
 impl SdlAudioPlayer {
@@ -165,6 +332,7 @@ impl SdlAudioPlayer {
         sync_to_audio: bool,
         internal_buffer_size: u32,
         audio_sync_threshold: u32,
+        audio_sync_poll_interval: Duration,
         audio_refresh_rate_adjustment: bool,
     ) -> Self {
         Self {
@@ -172,6 +340,7 @@ impl SdlAudioPlayer {
             sync_to_audio,
             internal_buffer_size,
             audio_sync_threshold,
+            audio_sync_poll_interval,
             sample_queue: Vec::new(),
             low_pass_filter: LowPassFilter::new(),
             downsample_counter: DownsampleCounter::new(
@@ -181,16 +350,64 @@ impl SdlAudioPlayer {
             ),
             frame_skip: FrameSkip::ZERO,
             total_output_samples: 0,
+            wav_writer: None,
+        }
+    }
+
+    /// Start writing all audio output to a WAV file, replacing any capture already in progress.
+    fn start_wav_capture(&mut self, path: &Path) -> io::Result<()> {
+        self.stop_wav_capture();
+
+        self.wav_writer = Some(WavWriter::create(path)?);
+        log::info!("Capturing audio to '{}'", path.display());
+
+        Ok(())
+    }
+
+    /// Stop the current audio capture, if any, and return the path of the finished WAV file.
+    fn stop_wav_capture(&mut self) -> Option<PathBuf> {
+        let wav_writer = self.wav_writer.take()?;
+        let path = wav_writer.path().to_path_buf();
+        match wav_writer.finish() {
+            Ok(()) => log::info!("Saved audio capture to '{}'", path.display()),
+            Err(err) => log::error!("Error finishing audio capture '{}': {err}", path.display()),
+        }
+
+        Some(path)
+    }
+
+    // How much audio is waiting in SDL's queue, i.e. how far audio output is ahead of playback
+    fn queued_audio_duration(&self) -> Duration {
+        let queued_samples = self.audio_queue.size() as usize / size_of::<f32>();
+        Duration::from_secs_f64(queued_samples as f64 / AUDIO_OUTPUT_FREQUENCY)
+    }
+
+    // Failing to write a captured sample is not fatal; the capture stops but audio keeps playing
+    fn capture_wav_sample(&mut self, sample: f32) {
+        let Some(wav_writer) = &mut self.wav_writer else {
+            return;
+        };
+
+        if let Err(err) = wav_writer.write_sample(sample) {
+            log::error!("Error writing audio capture '{}': {err}", wav_writer.path().display());
+            self.stop_wav_capture();
         }
     }
 }
 
+// Make sure the WAV header is completed however the emulator exits
+impl Drop for SdlAudioPlayer {
+    fn drop(&mut self) {
+        self.stop_wav_capture();
+    }
+}
+
 const AUDIO_OUTPUT_FREQUENCY: f64 = 48000.0;
 const DEVICE_BUFFER_SIZE: u16 = 64;
 const DISPLAY_RATE: f64 = 60.0;
 
 impl AudioPlayer for SdlAudioPlayer {
-    type Err = anyhow::Error;
+    type Err = SdlAudioError;
 
     fn push_sample(&mut self, sample: f64) -> Result<(), Self::Err> {
         self.low_pass_filter.collect_sample(sample);
@@ -199,21 +416,32 @@ impl AudioPlayer for SdlAudioPlayer {
             self.total_output_samples += 1;
 
             if !self.frame_skip.should_skip(self.total_output_samples) {
-                self.sample_queue.push(self.low_pass_filter.output_sample() as f32);
+                let output_sample = self.low_pass_filter.output_sample() as f32;
+                self.sample_queue.push(output_sample);
+                self.capture_wav_sample(output_sample);
             }
         }
 
         if self.sample_queue.len() >= self.internal_buffer_size as usize {
             // 1024 samples * 4 bytes per sample
             while self.sync_to_audio && self.audio_queue.size() >= self.audio_sync_threshold {
-                sleep(Duration::from_micros(250));
+                sleep(self.audio_sync_poll_interval);
             }
 
-            if self.audio_queue.size() < 4 * self.audio_sync_threshold {
-                self.audio_queue.queue_audio(&self.sample_queue).map_err(anyhow::Error::msg)?;
-            }
-            // If audio sync is disabled, intentionally drop samples while the audio queue is full
+            let queue_result = if self.audio_queue.size() < 4 * self.audio_sync_threshold {
+                self.audio_queue.queue_audio(&self.sample_queue)
+            } else {
+                log::debug!(
+                    target: AUDIO_TARGET,
+                    "Audio queue is full, dropping {} samples",
+                    self.sample_queue.len()
+                );
+                Ok(())
+            };
+            // If audio sync is disabled, intentionally drop samples while the audio queue is full.
+            // Samples are also dropped if they could not be queued
             self.sample_queue.clear();
+            queue_result.map_err(|msg| SdlAudioError::QueueAudio { msg })?;
         }
 
         Ok(())
@@ -241,23 +469,6 @@ impl InputPoller for SdlInputPoller {
     }
 }
 
-struct FsSaveWriter {
-    path: PathBuf,
-}
-
-impl SaveWriter for FsSaveWriter {
-    type Err = anyhow::Error;
-
-    #[inline]
-    fn persist_sram(&mut self, sram: &[u8]) -> Result<(), Self::Err> {
-        let tmp_path = self.path.with_extension("tmp");
-        fs::write(&tmp_path, sram)?;
-        fs::rename(tmp_path, &self.path)?;
-
-        Ok(())
-    }
-}
-
 fn load_sav_file<P: AsRef<Path>>(path: P) -> Option<Vec<u8>> {
     fs::read(path.as_ref()).ok()
 }
@@ -267,9 +478,23 @@ trait SdlWindowRenderer {
 
     fn set_frame_skip(&mut self, frame_skip: FrameSkip);
 
-    fn handle_resize(&mut self);
+    fn reload_config(&mut self, config: &JgnesDynamicConfig) -> Result<(), anyhow::Error> {
+        let renderer_config = config.to_renderer_config(self.wgpu_backend());
+        self.update_renderer_config(renderer_config)
+    }
+
+    fn update_renderer_config(
+        &mut self,
+        renderer_config: RendererConfig,
+    ) -> Result<(), anyhow::Error>;
+
+    fn wgpu_backend(&self) -> WgpuBackend;
+
+    /// Description of the GPU adapter being rendered with, if the renderer reports one
+    fn gpu_adapter(&self) -> Option<String>;
 
-    fn reload_config(&mut self, config: &JgnesDynamicConfig) -> Result<(), anyhow::Error>;
+    /// Take the most recent warning issued by the renderer since the last call, if any
+    fn take_warning(&mut self) -> Option<String>;
 }
 
 impl<T> SdlWindowRenderer for SdlRenderer<'_, T> {
@@ -281,20 +506,92 @@ impl<T> SdlWindowRenderer for SdlRenderer<'_, T> {
         self.frame_skip = frame_skip;
     }
 
-    fn handle_resize(&mut self) {
-        // nothing to do
+    fn update_renderer_config(&mut self, new_config: RendererConfig) -> Result<(), anyhow::Error> {
+        if new_config.vsync_mode != self.config.vsync_mode {
+            self.update_vsync_mode(new_config.vsync_mode)?;
+        }
+
+        let textures_changed = new_config.shader != self.config.shader
+            || new_config.gpu_filter_mode != self.config.gpu_filter_mode;
+
+        // Scanlines and shaders other than prescaling are not supported by the SDL2 renderer
+        self.config = new_config;
+
+        if textures_changed {
+            self.textures = SdlTextures::new(self.texture_creator, &self.config, self.timing_mode)?;
+        }
+
+        Ok(())
+    }
+
+    fn wgpu_backend(&self) -> WgpuBackend {
+        self.config.wgpu_backend
+    }
+
+    fn gpu_adapter(&self) -> Option<String> {
+        None
+    }
+
+    fn take_warning(&mut self) -> Option<String> {
+        None
+    }
+}
+
+impl<R: SdlWindowRenderer> SdlWindowRenderer for RecordingRenderer<R> {
+    fn window_mut(&mut self) -> &mut Window {
+        self.inner_mut().window_mut()
+    }
+
+    fn set_frame_skip(&mut self, frame_skip: FrameSkip) {
+        self.inner_mut().set_frame_skip(frame_skip);
+        if let Some(mirror) = self.mirror_mut() {
+            mirror.set_frame_skip(frame_skip);
+        }
     }
 
     fn reload_config(&mut self, config: &JgnesDynamicConfig) -> Result<(), anyhow::Error> {
-        self.config.aspect_ratio = config.aspect_ratio;
-        self.config.overscan = config.overscan;
-        self.config.forced_integer_height_scaling = config.forced_integer_height_scaling;
-        // VSync mode is not configurable for the SDL2 renderer and filter mode is not applicable
+        self.set_max_frames(capture_frame_count(config.capture_len));
+        if let Some(mirror) = self.mirror_mut() {
+            let renderer_config = config.to_renderer_config(mirror.wgpu_backend());
+            mirror.update_renderer_config(mirror_renderer_config(renderer_config))?;
+        }
+        self.inner_mut().reload_config(config)
+    }
 
-        Ok(())
+    fn update_renderer_config(
+        &mut self,
+        renderer_config: RendererConfig,
+    ) -> Result<(), anyhow::Error> {
+        self.inner_mut().update_renderer_config(renderer_config)
+    }
+
+    fn wgpu_backend(&self) -> WgpuBackend {
+        self.inner().wgpu_backend()
+    }
+
+    fn gpu_adapter(&self) -> Option<String> {
+        self.inner().gpu_adapter()
+    }
+
+    fn take_warning(&mut self) -> Option<String> {
+        let mirror_warning = self.mirror_mut().and_then(SdlWindowRenderer::take_warning);
+        self.inner_mut()
+            .take_warning()
+            .or_else(|| mirror_warning.map(|warning| format!("Mirror window: {warning}")))
     }
 }
 
+// The primary window's VSync setting paces presentation, so the mirror window never waits for
+// vertical blank; otherwise every frame could block twice
+fn mirror_renderer_config(renderer_config: RendererConfig) -> RendererConfig {
+    RendererConfig { vsync_mode: VSyncMode::Adaptive, ..renderer_config }
+}
+
+// Captures are recorded at the NES's native frame rate; NTSC is the worst case for buffer size
+fn capture_frame_count(capture_len: Duration) -> usize {
+    (capture_len.as_secs_f64() * 60.0).round() as usize
+}
+
 impl SdlWindowRenderer for WgpuRenderer<Window> {
     fn window_mut(&mut self) -> &mut Window {
         self.window_mut()
@@ -304,16 +601,26 @@ impl SdlWindowRenderer for WgpuRenderer<Window> {
         self.update_frame_skip(frame_skip);
     }
 
-    fn handle_resize(&mut self) {
-        self.reconfigure_surface();
-    }
-
-    fn reload_config(&mut self, config: &JgnesDynamicConfig) -> Result<(), anyhow::Error> {
-        let renderer_config = config.to_renderer_config(self.wgpu_backend());
+    fn update_renderer_config(
+        &mut self,
+        renderer_config: RendererConfig,
+    ) -> Result<(), anyhow::Error> {
         self.update_render_config(renderer_config)?;
 
         Ok(())
     }
+
+    fn wgpu_backend(&self) -> WgpuBackend {
+        self.wgpu_backend()
+    }
+
+    fn gpu_adapter(&self) -> Option<String> {
+        Some(self.adapter_info().to_string())
+    }
+
+    fn take_warning(&mut self) -> Option<String> {
+        self.take_warning()
+    }
 }
 
 /// Run the emulator in a loop until it terminates.
@@ -345,33 +652,71 @@ pub fn run(config: &JgnesNativeConfig) -> anyhow::Result<()> {
         )));
     };
 
-    let rom_bytes = fs::read(Path::new(&config.nes_file_path))?;
+    let playlist = Playlist::from_config(config);
+    let rom_bytes =
+        read_rom_file(playlist.nes_file_path(), playlist.current().patch_file_path.as_deref())?;
+
+    if let Some(video_driver) = &config.sdl_video_driver {
+        sdl2::hint::set("SDL_VIDEODRIVER", video_driver);
+    }
 
     let sdl_ctx = sdl2::init().map_err(anyhow::Error::msg)?;
     let video_subsystem = sdl_ctx.video().map_err(anyhow::Error::msg)?;
+    log::info!("Using SDL video driver {}", video_subsystem.current_video_driver());
     let audio_subsystem = sdl_ctx.audio().map_err(anyhow::Error::msg)?;
     let joystick_subsystem = sdl_ctx.joystick().map_err(anyhow::Error::msg)?;
 
     sdl_ctx.mouse().show_cursor(false);
 
-    let mut window_builder = video_subsystem.window(
-        &format!("jgnes - {file_name}"),
-        config.window_width,
-        config.window_height,
-    );
+    let saved_window_geometry = geometry::display_configuration_key(&video_subsystem)
+        .and_then(|display_key| config.saved_window_geometry.get(&display_key));
+    let (window_width, window_height) = saved_window_geometry
+        .map_or((config.window_width, config.window_height), |geometry| {
+            (geometry.width, geometry.height)
+        });
+
+    let mut window_builder =
+        video_subsystem.window(&title::window_title(file_name), window_width, window_height);
+    if let Some(geometry) = saved_window_geometry {
+        window_builder.position(geometry.x, geometry.y);
+    }
     window_builder.resizable();
     window_builder.metal_view();
+    if config.hidpi_scaling == HiDpiScaling::Native {
+        window_builder.allow_highdpi();
+    }
 
     if config.launch_fullscreen {
         window_builder.fullscreen_desktop();
     }
+    if config.always_on_top {
+        window_builder.always_on_top();
+    }
+    if config.borderless {
+        window_builder.borderless();
+    }
 
     let window = init_window(window_builder.build()?)?;
+    let mirror_window = config
+        .mirror_window
+        .then(|| {
+            build_mirror_window(&video_subsystem, config, file_name, window_width, window_height)
+        })
+        .transpose()?;
 
-    let renderer_config = {
+    let (renderer_config, capture_frames) = {
         let dynamic_config = dynamic_config.lock().unwrap();
-        dynamic_config.to_renderer_config(config.wgpu_backend)
+        (
+            RendererConfig {
+                gpu_adapter: config.gpu_adapter.clone(),
+                ..dynamic_config.to_renderer_config(config.wgpu_backend)
+            },
+            capture_frame_count(dynamic_config.capture_len),
+        )
     };
+    if renderer_config.power_preference == PowerPreference::LowPower {
+        log::info!("Low power mode is active");
+    }
 
     let audio_queue = audio_subsystem
         .open_queue(None, &AudioSpecDesired {
@@ -382,14 +727,24 @@ pub fn run(config: &JgnesNativeConfig) -> anyhow::Result<()> {
         .map_err(anyhow::Error::msg)?;
     audio_queue.resume();
 
+    let audio_spec = audio_queue.spec();
+    log::info!(
+        target: AUDIO_TARGET,
+        "Opened audio device at {} Hz with {} channel(s) and a {}-sample buffer",
+        audio_spec.freq,
+        audio_spec.channels,
+        audio_spec.samples
+    );
+
     let audio_player = {
         let dynamic_config = dynamic_config.lock().unwrap();
         SdlAudioPlayer::new(
             audio_queue,
-            dynamic_config.sync_to_audio,
+            dynamic_config.audio_sync_enabled(),
             dynamic_config.internal_audio_buffer_size,
             dynamic_config.audio_sync_threshold,
-            dynamic_config.audio_refresh_rate_adjustment,
+            power::audio_sync_poll_interval(dynamic_config.low_power_mode.is_active()),
+            dynamic_config.audio_refresh_rate_adjustment_enabled(),
         )
     };
 
@@ -402,9 +757,9 @@ pub fn run(config: &JgnesNativeConfig) -> anyhow::Result<()> {
         Rc::clone(&input_poller.p2_joypad_state),
     );
 
-    let sav_path = Path::new(&config.nes_file_path).with_extension("sav");
+    let sav_path = playlist.current().sav_path(playlist.save_dir());
     let sav_bytes = load_sav_file(&sav_path);
-    let save_writer = FsSaveWriter { path: sav_path.clone() };
+    let save_writer = FsSaveWriter::new(sav_path.clone());
 
     if sav_bytes.is_some() {
         log::info!("Loaded SRAM from {}", sav_path.display());
@@ -413,49 +768,158 @@ pub fn run(config: &JgnesNativeConfig) -> anyhow::Result<()> {
     let mut event_pump = sdl_ctx.event_pump().map_err(anyhow::Error::msg)?;
     event_pump.disable_event(EventType::MouseMotion);
 
-    let save_state_path = Path::new(&config.nes_file_path).with_extension("ss0");
+    // Dropping the server closes the socket so that the next emulator session can reuse the port
+    let _remote_control_server = config
+        .remote_control_port
+        .map(|port| RemoteControlServer::start(port, config.shared_config.clone()))
+        .transpose()
+        .map_err(|err| {
+            anyhow::Error::msg(format!("unable to start remote control server: {err}"))
+        })?;
+
+    // SDL2 cannot provide window handles for KMSDRM windows, which wgpu requires
+    let native_renderer = if config.renderer == NativeRenderer::Wgpu
+        && video_subsystem.current_video_driver().eq_ignore_ascii_case("kmsdrm")
+    {
+        log::warn!("The wgpu renderer is not supported with the KMSDRM video driver; using SDL2");
+        NativeRenderer::Sdl2
+    } else {
+        config.renderer
+    };
 
-    match config.renderer {
+    let result = match native_renderer {
         NativeRenderer::Sdl2 => {
-            let mut canvas_builder = window.into_canvas();
-            if renderer_config.vsync_mode == VSyncMode::Enabled {
-                canvas_builder = canvas_builder.present_vsync();
-            }
-            let canvas = canvas_builder.build()?;
+            let canvas = build_canvas(window, renderer_config.vsync_mode)?;
             let texture_creator = canvas.texture_creator();
-            let renderer = SdlRenderer::new(canvas, &texture_creator, renderer_config)?;
+            let mirror_canvas = mirror_window
+                .map(|window| build_canvas(window, VSyncMode::Adaptive))
+                .transpose()?;
+            let mirror_texture_creator = mirror_canvas.as_ref().map(WindowCanvas::texture_creator);
+            let mirror = mirror_canvas
+                .zip(mirror_texture_creator.as_ref())
+                .map(|(canvas, texture_creator)| {
+                    SdlRenderer::new(
+                        canvas,
+                        texture_creator,
+                        mirror_renderer_config(renderer_config.clone()),
+                    )
+                })
+                .transpose()?;
+            let renderer = RecordingRenderer::new(
+                SdlRenderer::new(canvas, &texture_creator, renderer_config)?,
+                capture_frames,
+            )
+            .with_mirror(mirror);
 
             let emulator = Emulator::create(EmulatorCreateArgs {
                 rom_bytes,
                 sav_bytes,
                 forced_timing_mode: config.forced_timing_mode,
+                forced_mapper_number: config.forced_mapper_number,
+                ram_init: config.ram_init,
                 renderer,
                 audio_player,
                 input_poller,
                 save_writer,
-            })?;
-            run_emulator(emulator, config, event_pump, input_handler, &save_state_path)
+            })
+            .inspect_err(log_rom_diagnostics)?;
+            run_emulator(emulator, config, event_pump, input_handler, playlist)
         }
         NativeRenderer::Wgpu => {
+            let mirror = mirror_window
+                .map(|window| {
+                    pollster::block_on(WgpuRenderer::from_window(
+                        window,
+                        Window::drawable_size,
+                        mirror_renderer_config(renderer_config.clone()),
+                    ))
+                })
+                .transpose()?;
             let renderer = pollster::block_on(WgpuRenderer::from_window(
                 window,
-                Window::size,
+                Window::drawable_size,
                 renderer_config,
             ))?;
+            let renderer = RecordingRenderer::new(renderer, capture_frames).with_mirror(mirror);
             let emulator = Emulator::create(EmulatorCreateArgs {
                 rom_bytes,
                 sav_bytes,
                 forced_timing_mode: config.forced_timing_mode,
+                forced_mapper_number: config.forced_mapper_number,
+                ram_init: config.ram_init,
                 renderer,
                 audio_player,
                 input_poller,
                 save_writer,
-            })?;
-            run_emulator(emulator, config, event_pump, input_handler, &save_state_path)
+            })
+            .inspect_err(log_rom_diagnostics)?;
+            run_emulator(emulator, config, event_pump, input_handler, playlist)
         }
+    };
+
+    *config.shared_config.status.lock().unwrap() = EmulatorStatus::default();
+
+    result
+}
+
+/// Read a ROM file and soft patch it in memory, using either the given patch file or a `.bps` /
+/// `.ips` file next to the ROM file with the same name.
+fn read_rom_file(nes_file_path: &Path, patch_file_path: Option<&Path>) -> anyhow::Result<Vec<u8>> {
+    let rom_bytes = fs::read(nes_file_path)?;
+
+    let patch_file_path = patch_file_path.map(Path::to_path_buf).or_else(|| {
+        PatchFormat::extensions()
+            .iter()
+            .map(|extension| nes_file_path.with_extension(extension))
+            .find(|path| path.is_file())
+    });
+    let Some(patch_file_path) = patch_file_path else {
+        return Ok(rom_bytes);
+    };
+
+    let patch_bytes = fs::read(&patch_file_path)?;
+    let rom_bytes = patch::apply(&rom_bytes, &patch_bytes).map_err(|err| {
+        anyhow::Error::msg(format!("error applying patch '{}': {err}", patch_file_path.display()))
+    })?;
+    log::info!("Applied patch '{}'", patch_file_path.display());
+
+    Ok(rom_bytes)
+}
+
+// The cartridge load error alone is often not enough to tell what is wrong with a ROM file
+fn log_rom_diagnostics<E>(err: &InitializationError<E>) {
+    if let InitializationError::CartridgeLoad { source } = err {
+        log::error!("ROM diagnostics:\n{}", source.diagnostics);
     }
 }
 
+/// Create the second window for [`JgnesNativeConfig::mirror_window`].
+fn build_mirror_window(
+    video_subsystem: &VideoSubsystem,
+    config: &JgnesNativeConfig,
+    file_name: &str,
+    window_width: u32,
+    window_height: u32,
+) -> Result<Window, anyhow::Error> {
+    let title = format!("{} (mirror)", title::window_title(file_name));
+    let mut window_builder = video_subsystem.window(&title, window_width, window_height);
+    window_builder.resizable();
+    window_builder.metal_view();
+    if config.hidpi_scaling == HiDpiScaling::Native {
+        window_builder.allow_highdpi();
+    }
+
+    init_window(window_builder.build()?)
+}
+
+fn build_canvas(window: Window, vsync_mode: VSyncMode) -> Result<WindowCanvas, anyhow::Error> {
+    let mut canvas_builder = window.into_canvas();
+    if vsync_mode == VSyncMode::Enabled {
+        canvas_builder = canvas_builder.present_vsync();
+    }
+    Ok(canvas_builder.build()?)
+}
+
 fn init_window(window: Window) -> Result<Window, anyhow::Error> {
     let mut canvas = window.into_canvas().present_vsync().build()?;
 
@@ -466,69 +930,337 @@ fn init_window(window: Window) -> Result<Window, anyhow::Error> {
     Ok(canvas.into_window())
 }
 
+struct RewindEntry {
+    state: EmulationState,
+    // Approximate memory used by the snapshot
+    size: usize,
+    // Inputs from this snapshot up until the next one; only populated once the next snapshot is
+    // taken, and left empty if the emulation timeline was interrupted before then
+    inputs: Option<InputLog>,
+}
+
 struct RewindState {
-    previous_states: VecDeque<EmulationState>,
+    previous_states: VecDeque<RewindEntry>,
+    previous_states_size: usize,
     frame_count: u64,
+    record_interval: u64,
     rewind_buffer_len: usize,
+    memory_limit: usize,
+    memory_limit_reached: bool,
+    memory_warning_until: Option<Instant>,
     rewinding: bool,
+    rewind_held_since: Option<Instant>,
+    rewind_step_taken: bool,
+    rewound_snapshots: usize,
+    // The entry that was most recently loaded by rewinding, and entries that can be restored by
+    // rewinding forward again; both are discarded as soon as emulation continues
+    rewound_entry: Option<RewindEntry>,
+    redo_states: Vec<RewindEntry>,
+    current_inputs: (JoypadState, JoypadState),
+    current_input_log: Option<InputLog>,
+    ticks_since_snapshot: u32,
 }
 
-const REWIND_RECORD_INTERVAL: u64 = 3;
-// 3 * 16.6~ ms
-const THREE_FRAME_TIMES_NANOS: u64 = 50_000_000;
+// Durations are converted to snapshot counts assuming 60 FPS
+const FRAME_TIME_NANOS: u64 = 16_666_667;
+const REWIND_MEMORY_WARNING_DURATION: Duration = Duration::from_secs(3);
+const RENDERER_WARNING_DURATION: Duration = Duration::from_secs(3);
+// Tapping the rewind hotkey rewinds a single snapshot; holding it for longer than this rewinds
+// continuously
+const REWIND_HOLD_DELAY: Duration = Duration::from_millis(300);
 
-impl RewindState {
-    fn compute_rewind_buffer_len(rewind_buffer_len: Duration) -> usize {
-        (rewind_buffer_len.as_nanos() / u128::from(THREE_FRAME_TIMES_NANOS)) as usize
-    }
+fn rewind_record_interval(config: &JgnesDynamicConfig) -> u64 {
+    u64::from(config.rewind_interval_frames.max(1))
+}
 
-    fn new(rewind_buffer_len: Duration) -> Self {
-        let rewind_buffer_len = Self::compute_rewind_buffer_len(rewind_buffer_len);
-        Self {
+impl RewindState {
+    fn new(config: &JgnesDynamicConfig) -> Self {
+        let mut rewind_state = Self {
             previous_states: VecDeque::new(),
+            previous_states_size: 0,
             frame_count: 0,
-            rewind_buffer_len,
+            record_interval: rewind_record_interval(config),
+            rewind_buffer_len: 0,
+            memory_limit: 0,
+            memory_limit_reached: false,
+            memory_warning_until: None,
             rewinding: false,
+            rewind_held_since: None,
+            rewind_step_taken: false,
+            rewound_snapshots: 0,
+            rewound_entry: None,
+            redo_states: Vec::new(),
+            current_inputs: (JoypadState::default(), JoypadState::default()),
+            current_input_log: None,
+            ticks_since_snapshot: 0,
+        };
+        rewind_state.reload_config(config);
+        rewind_state
+    }
+
+    fn reload_config(&mut self, config: &JgnesDynamicConfig) {
+        let record_interval = rewind_record_interval(config);
+        if record_interval != self.record_interval {
+            // Clips assume that every snapshot is the same number of frames apart
+            self.previous_states.clear();
+            self.previous_states_size = 0;
+            self.record_interval = record_interval;
         }
+
+        self.rewind_buffer_len = self.snapshots_in(config.rewind_buffer_len);
+        self.memory_limit = config.rewind_memory_limit_mb as usize * 1024 * 1024;
+        self.memory_limit_reached = false;
+        self.trim();
+    }
+
+    fn snapshot_interval(&self) -> Duration {
+        Duration::from_nanos(FRAME_TIME_NANOS * self.record_interval)
+    }
+
+    fn snapshots_in(&self, duration: Duration) -> usize {
+        (duration.as_nanos() / self.snapshot_interval().as_nanos()) as usize
     }
 
-    fn reload_buffer_len(&mut self, rewind_buffer_len: Duration) {
-        self.rewind_buffer_len = Self::compute_rewind_buffer_len(rewind_buffer_len);
+    fn buffered_duration(&self) -> Duration {
+        self.snapshot_interval() * self.previous_states.len() as u32
     }
 
-    // Should be called once per frame; will internally store state every 3rd frame
-    fn record<R, A, I, S>(&mut self, emulator: &Emulator<R, A, I, S>) {
+    // Drop the oldest snapshots until the buffer is within its length and memory limits
+    fn trim(&mut self) {
+        while self.previous_states.len() > self.rewind_buffer_len {
+            self.pop_oldest();
+        }
+
+        if self.memory_limit == 0 || self.previous_states_size <= self.memory_limit {
+            return;
+        }
+
+        while self.previous_states_size > self.memory_limit {
+            self.pop_oldest();
+        }
+
+        if !self.memory_limit_reached {
+            self.memory_limit_reached = true;
+            self.memory_warning_until = Some(Instant::now() + REWIND_MEMORY_WARNING_DURATION);
+            log::warn!(
+                "Rewind buffer reached its memory limit of {} MB; only {:.1} seconds of rewind are available",
+                self.memory_limit / (1024 * 1024),
+                self.buffered_duration().as_secs_f64()
+            );
+        }
+    }
+
+    fn pop_oldest(&mut self) {
+        if let Some(entry) = self.previous_states.pop_front() {
+            self.previous_states_size -= entry.size;
+        }
+    }
+
+    // A warning shown for a few seconds after the buffer first reaches its memory limit
+    fn osd_lines(&self, now: Instant) -> Vec<OsdLine> {
+        if self.memory_warning_until.is_some_and(|until| now < until) {
+            vec![OsdLine { text: "REWIND MEMORY FULL".into(), color: OSD_TEXT_COLOR }]
+        } else {
+            Vec::new()
+        }
+    }
+
+    // Should be called once per emulator tick; will internally store state every few frames
+    // depending on the record interval
+    fn record<R, A, I, S>(&mut self, emulator: &Emulator<R, A, I, S>, tick_effect: TickEffect) {
+        self.ticks_since_snapshot += 1;
+
+        if tick_effect != TickEffect::FrameRendered {
+            return;
+        }
+
         self.frame_count += 1;
-        if self.frame_count % REWIND_RECORD_INTERVAL == 0 {
-            self.previous_states.push_back(emulator.snapshot_state());
 
-            while self.previous_states.len() > self.rewind_buffer_len {
-                self.previous_states.pop_front();
+        // Emulation has diverged from the states that were rewound past
+        self.rewound_entry = None;
+        self.redo_states.clear();
+        self.rewound_snapshots = 0;
+
+        if self.frame_count.is_multiple_of(self.record_interval) {
+            if let Some(last_entry) = self.previous_states.back_mut() {
+                if last_entry.inputs.is_none() {
+                    last_entry.inputs = self.current_input_log.take();
+                }
             }
+
+            let state = emulator.snapshot_state();
+            let size = state.approximate_size();
+            self.previous_states.push_back(RewindEntry { state, size, inputs: None });
+            self.previous_states_size += size;
+            self.current_input_log = Some(InputLog::new(self.current_inputs));
+            self.ticks_since_snapshot = 0;
+
+            self.trim();
+        }
+    }
+
+    // Should be called whenever joypad state may have changed
+    fn record_inputs(&mut self, inputs: (JoypadState, JoypadState)) {
+        self.current_inputs = inputs;
+        if let Some(input_log) = &mut self.current_input_log {
+            input_log.record(self.ticks_since_snapshot, inputs);
+        }
+    }
+
+    // Should be called after a soft reset so that it is repeated when replaying inputs
+    fn record_soft_reset(&mut self) {
+        if let Some(input_log) = &mut self.current_input_log {
+            input_log.record_soft_reset(self.ticks_since_snapshot);
+        }
+    }
+
+    // Should be called after a hard reset with the RAM init that the emulator powered on with
+    fn record_hard_reset(&mut self, ram_init: RamInit) {
+        if let Some(input_log) = &mut self.current_input_log {
+            input_log.record_hard_reset(self.ticks_since_snapshot, ram_init);
+        }
+    }
+
+    // Should be called whenever emulation state changes outside of normal emulation, e.g. loading
+    // a save state; the frames since the last snapshot can no longer be replayed
+    fn interrupt_input_log(&mut self) {
+        self.current_input_log = None;
+    }
+
+    // Collect replayable segments covering up to the given duration, oldest first
+    fn clip_segments(&self, clip_len: Duration) -> Vec<ClipSegment> {
+        let max_segments = self.snapshots_in(clip_len);
+        let mut segments: Vec<_> = self
+            .previous_states
+            .iter()
+            .rev()
+            .filter_map(|entry| {
+                entry.inputs.as_ref().map(|inputs| ClipSegment {
+                    state: entry.state.clone(),
+                    inputs: inputs.clone(),
+                })
+            })
+            .take(max_segments)
+            .collect();
+        segments.reverse();
+        segments
+    }
+
+    // Should be called when the rewind hotkey is pressed, including for key repeats
+    fn start_rewinding(&mut self) {
+        if self.rewind_held_since.is_none() {
+            self.rewinding = true;
+            self.rewind_held_since = Some(Instant::now());
+            self.rewind_step_taken = false;
+        }
+    }
+
+    fn stop_rewinding(&mut self) {
+        self.rewinding = false;
+        self.rewind_held_since = None;
+    }
+
+    // How much of the rewind buffer is still available behind the current state, from 0.0 to 1.0
+    fn scrub_position(&self) -> f64 {
+        let total = self.previous_states.len() + self.rewound_snapshots;
+        if total == 0 {
+            return 0.0;
         }
+        self.previous_states.len() as f64 / total as f64
+    }
+
+    // How far back the current rewind has gone
+    fn rewound_time(&self) -> Duration {
+        self.snapshot_interval() * self.rewound_snapshots as u32
     }
 
-    // Rewind to the most recent previous state, and then sleep for the appropriate amount of time.
-    // If the rewind buffer is empty then this method will do nothing and immediately return.
-    fn rewind_once<R: Renderer, A, I, S>(
+    // Rewind to the most recent previous state with the rewind position shown on the OSD, and then
+    // sleep for the appropriate amount of time. The first call after the rewind hotkey is pressed
+    // rewinds a single snapshot, and subsequent calls only rewind once the hotkey has been held
+    // long enough. If the rewind buffer is empty then this method will do nothing and immediately
+    // return.
+    fn rewind_once<R, A, I, S>(
         &mut self,
-        emulator: &mut Emulator<R, A, I, S>,
-    ) -> Result<(), R::Err> {
-        if let Some(state) = self.previous_states.pop_back() {
-            emulator.load_state_snapshot(state);
+        emulator: &mut Emulator<RecordingRenderer<R>, A, I, S>,
+        osd_lines: Vec<OsdLine>,
+    ) -> Result<(), R::Err>
+    where
+        R: Renderer,
+        R::Err: Display,
+    {
+        let held_for = self.rewind_held_since.map_or(Duration::ZERO, |since| since.elapsed());
+        if self.rewind_step_taken && held_for < REWIND_HOLD_DELAY {
+            sleep(Duration::from_millis(1));
+            return Ok(());
+        }
+        self.rewind_step_taken = true;
+
+        if let Some(entry) = self.previous_states.pop_back() {
+            emulator.load_state_snapshot(entry.state.clone());
+            self.previous_states_size -= entry.size;
+            self.interrupt_input_log();
+            self.rewound_snapshots += 1;
+            if let Some(rewound_entry) = self.rewound_entry.replace(entry) {
+                self.redo_states.push(rewound_entry);
+            }
 
             let start_time = SystemTime::now();
-            emulator.force_render()?;
+            self.render_rewind_position(emulator, osd_lines)?;
 
-            while SystemTime::now().duration_since(start_time).unwrap()
-                < Duration::from_nanos(THREE_FRAME_TIMES_NANOS)
-            {
+            while SystemTime::now().duration_since(start_time).unwrap() < self.snapshot_interval() {
                 sleep(Duration::from_micros(250));
             }
         }
 
         Ok(())
     }
+
+    // Step forward to the state that was most recently rewound past, if emulation has not continued
+    // since rewinding
+    fn rewind_forward_once<R, A, I, S>(
+        &mut self,
+        emulator: &mut Emulator<RecordingRenderer<R>, A, I, S>,
+        osd_lines: Vec<OsdLine>,
+    ) -> Result<(), R::Err>
+    where
+        R: Renderer,
+        R::Err: Display,
+    {
+        let Some(entry) = self.redo_states.pop() else {
+            return Ok(());
+        };
+
+        emulator.load_state_snapshot(entry.state.clone());
+        self.rewound_snapshots = self.rewound_snapshots.saturating_sub(1);
+        // The redone entry's inputs still lead to the next entry, so the previous entry keeps its
+        // inputs
+        if let Some(rewound_entry) = self.rewound_entry.replace(entry) {
+            self.previous_states_size += rewound_entry.size;
+            self.previous_states.push_back(rewound_entry);
+        }
+
+        self.render_rewind_position(emulator, osd_lines)
+    }
+
+    fn render_rewind_position<R, A, I, S>(
+        &self,
+        emulator: &mut Emulator<RecordingRenderer<R>, A, I, S>,
+        mut osd_lines: Vec<OsdLine>,
+    ) -> Result<(), R::Err>
+    where
+        R: Renderer,
+        R::Err: Display,
+    {
+        osd_lines.push(OsdLine {
+            text: format!("-{}", timer::format_time(self.rewound_time())),
+            color: OSD_TEXT_COLOR,
+        });
+        let renderer = emulator.get_renderer_mut();
+        renderer.set_osd_lines(osd_lines);
+        renderer.set_osd_scrub_bar(Some(self.scrub_position()));
+
+        emulator.force_render()
+    }
 }
 
 // Windows needs a special implementation of sleep because by default, std::thread::sleep will always
@@ -550,32 +1282,150 @@ fn sleep(duration: Duration) {
     thread::sleep(duration);
 }
 
-fn run_emulator<R, I, S, P>(
-    mut emulator: Emulator<R, SdlAudioPlayer, I, S>,
+// Keys that control the emulator window while emulation is paused because of an error
+const ERROR_PAUSE_RESUME_KEY: Keycode = Keycode::Space;
+const ERROR_PAUSE_STEP_KEY: Keycode = Keycode::N;
+
+// Emulation is paused after a recoverable error that the user should decide how to handle, until
+// it is resumed from the emulator window or the frontend
+#[derive(Debug)]
+struct ErrorPause {
+    kind: &'static str,
+    message: String,
+}
+
+impl ErrorPause {
+    fn osd_lines(&self) -> Vec<OsdLine> {
+        vec![
+            OsdLine { text: format!("{} error - paused", self.kind), color: OSD_TEXT_COLOR },
+            OsdLine { text: "Space: resume".into(), color: OSD_DIM_TEXT_COLOR },
+            OsdLine { text: "N: step one frame".into(), color: OSD_DIM_TEXT_COLOR },
+        ]
+    }
+}
+
+// Recoverable errors such as audio queue failures can repeat many times per second, so only
+// occasionally log them.
+//
+// Some recoverable errors also pause emulation: a failed save write means that progress is not
+// being saved, and renderer errors that persist for a full second mean that nothing is being
+// displayed. Save writes are retried every frame, so only the first failure pauses. Audio errors
+// only drop samples and never pause.
+#[derive(Debug, Default)]
+struct RecoverableErrorLog {
+    count: u64,
+    consecutive_render_errors: u32,
+    render_failed_this_frame: bool,
+    paused_for_save_error: bool,
+}
+
+impl RecoverableErrorLog {
+    const LOG_INTERVAL: u64 = 1000;
+    const PAUSE_AFTER_RENDER_ERRORS: u32 = 60;
+
+    fn record<R: Display, A: Display, S: Display>(
+        &mut self,
+        err: &EmulationError<R, A, S>,
+    ) -> Option<ErrorPause> {
+        if self.count.is_multiple_of(Self::LOG_INTERVAL) {
+            log::warn!("Recoverable emulation error (occurrence #{}): {err}", self.count + 1);
+        }
+        self.count += 1;
+
+        let kind = match err {
+            EmulationError::Render(_) => {
+                self.consecutive_render_errors += 1;
+                self.render_failed_this_frame = true;
+                if self.consecutive_render_errors != Self::PAUSE_AFTER_RENDER_ERRORS {
+                    return None;
+                }
+                "Render"
+            }
+            EmulationError::Save(_) if !self.paused_for_save_error => {
+                self.paused_for_save_error = true;
+                "Save"
+            }
+            EmulationError::Save(_) | EmulationError::Audio(_) => return None,
+        };
+
+        log::error!("Pausing emulation because of error: {err}");
+        Some(ErrorPause { kind, message: err.to_string() })
+    }
+
+    // Called for every completed frame, after any errors from rendering it have been recorded
+    fn frame_rendered(&mut self) {
+        if !mem::take(&mut self.render_failed_this_frame) {
+            self.consecutive_render_errors = 0;
+        }
+    }
+}
+
+// The on-screen display is normally only redrawn along with the next frame, which never comes
+// while paused
+fn redraw_osd<R, A, I, S>(
+    emulator: &mut Emulator<RecordingRenderer<R>, A, I, S>,
+    osd_lines: Vec<OsdLine>,
+) where
+    R: Renderer,
+    R::Err: Display,
+{
+    emulator.get_renderer_mut().set_osd_lines(osd_lines);
+    if let Err(err) = emulator.force_render() {
+        log::warn!("Unable to redraw the on-screen display: {err}");
+    }
+}
+
+// Show the last completed frame again, with render errors handled the same way as errors from
+// rendering frames during emulation
+fn render_last_frame<R, A, I, S>(
+    emulator: &mut Emulator<RecordingRenderer<R>, A, I, S>,
+    recoverable_errors: &mut RecoverableErrorLog,
+    error_pause: &mut Option<ErrorPause>,
+) -> Result<(), R::Err>
+where
+    R: Renderer,
+    R::Err: Display + ClassifyError,
+{
+    match emulator.get_renderer_mut().render_last_frame() {
+        Ok(()) => Ok(()),
+        Err(err) if err.severity() == ErrorSeverity::Recoverable => {
+            let err = EmulationError::<_, Infallible, Infallible>::Render(err);
+            if let Some(new_error_pause) = recoverable_errors.record(&err) {
+                redraw_osd(emulator, new_error_pause.osd_lines());
+                *error_pause = Some(new_error_pause);
+            }
+            Ok(())
+        }
+        Err(err) => Err(err),
+    }
+}
+
+fn run_emulator<R, I>(
+    mut emulator: Emulator<RecordingRenderer<R>, SdlAudioPlayer, I, FsSaveWriter>,
     native_config: &JgnesNativeConfig,
     mut event_pump: EventPump,
     mut input_handler: SdlInputHandler<'_>,
-    save_state_path: P,
+    mut playlist: Playlist,
 ) -> anyhow::Result<()>
 where
     R: Renderer + SdlWindowRenderer,
-    R::Err: std::error::Error + Send + Sync + 'static,
+    R::Err: std::error::Error + ClassifyError + Send + Sync + 'static,
     I: InputPoller,
-    S: SaveWriter<Err = anyhow::Error>,
-    P: AsRef<Path>,
 {
     let JgnesSharedConfig {
         dynamic_config,
-        config_reload_signal,
-        quit_signal,
+        command_receiver,
         input_reconfigure_sender,
-        input_reconfigure_signal,
+        status,
+        crash_state,
+        window_geometry,
+        ..
     } = &native_config.shared_config;
 
-    let save_state_path = save_state_path.as_ref();
-
     let mut emulator_config = EmulatorConfig::default();
     let mut fast_forward_multiplier;
+    let mut frame_pacing;
+    let mut clip_len;
     let mut rewind_state;
 
     {
@@ -583,91 +1433,442 @@ where
 
         dynamic_config.update_emulator_config(&mut emulator_config);
         fast_forward_multiplier = dynamic_config.fast_forward_multiplier;
-        rewind_state = RewindState::new(dynamic_config.rewind_buffer_len);
+        frame_pacing = dynamic_config.frame_pacing;
+        clip_len = dynamic_config.clip_len;
+        rewind_state = RewindState::new(&dynamic_config);
     };
 
+    let gpu_adapter = emulator.get_renderer().gpu_adapter();
+    let mut recoverable_errors = RecoverableErrorLog::default();
+    let mut error_pause: Option<ErrorPause> = None;
+    let mut stepping_frame = false;
     let mut ticks = 0_u64;
+    let mut paused = false;
+    let mut frontend_paused = false;
+    let mut frame_count = 0_u64;
+    let mut fps_window_start = Instant::now();
+    let mut fps_window_frames = 0_u32;
+    let mut fps = 0.0;
+    // Frames left to render before dumping recorded register writes; recording starts mid-frame,
+    // so the first frame rendered after enabling it is incomplete
+    let mut register_write_dump_frames: Option<u8> = None;
+    let save_state_worker = SaveStateWorker::new();
+    let mut undo_load_state: Option<UndoLoadState> = None;
+    let mut frame_timing_log =
+        native_config.frame_timing_log_path.clone().map(|path| FrameTimingLog::start(path, ticks));
+    let mut title_message = TitleMessage::default();
+    let mut confirm_prompt = ConfirmPrompt::default();
+    let mut renderer_warning_until: Option<Instant> = None;
+    let mut frame_pacer = FramePacer::new();
+    let mut rom_watcher = if native_config.watch_rom_file {
+        RomWatcher::new(playlist.nes_file_path())
+            .inspect_err(|err| {
+                log::error!(
+                    "Unable to watch '{}' for changes: {err}",
+                    playlist.nes_file_path().display()
+                );
+            })
+            .ok()
+    } else {
+        None
+    };
+    let mut speedrun_timer = SpeedrunTimer::new(
+        native_config.last_run_path.clone(),
+        native_config.speedrun_timer_auto_start,
+        native_config.livesplit_port.map(LiveSplitClient::start),
+    );
+    if let Some(wav_capture_path) = &native_config.wav_capture_path {
+        if let Err(err) = emulator.get_audio_player_mut().start_wav_capture(wav_capture_path) {
+            log::error!("Unable to capture audio to '{}': {err}", wav_capture_path.display());
+        }
+    }
     loop {
-        if !rewind_state.rewinding {
-            match emulator.tick(&emulator_config) {
-                Ok(TickEffect::None) => {}
-                Ok(TickEffect::FrameRendered) => {
-                    rewind_state.record(&emulator);
+        if (paused || frontend_paused || error_pause.is_some()) && !stepping_frame {
+            // Keep the window responsive without spinning a CPU core
+            sleep(Duration::from_millis(1));
+        } else if !rewind_state.rewinding {
+            let tick_effect = match emulator.tick(&emulator_config) {
+                Ok(tick_effect) => tick_effect,
+                Err(err) if err.is_recoverable() => {
+                    let tick_effect = err.effect;
+                    for err in err.into_errors() {
+                        // Errors that pause emulation only occur at the end of a frame, and the
+                        // OSD is redrawn with the pause message below
+                        if let Some(new_error_pause) = recoverable_errors.record(&err) {
+                            error_pause = Some(new_error_pause);
+                        }
+                    }
+                    tick_effect
                 }
                 Err(err) => {
+                    // Keep the state from when the error occurred for crash reports
+                    let mut state = Vec::new();
+                    match emulator.save_state(&mut state) {
+                        Ok(()) => *crash_state.lock().unwrap() = Some(state),
+                        Err(err) => log::error!("Unable to capture state for crash report: {err}"),
+                    }
+
+                    let err = err
+                        .into_errors()
+                        .find(|err| !err.is_recoverable())
+                        .expect("an unrecoverable tick error should contain a fatal error");
                     return match err {
                         EmulationError::Render(err) => Err(err.into()),
-                        EmulationError::Audio(err) | EmulationError::Save(err) => Err(err),
+                        EmulationError::Audio(err) => Err(err.into()),
+                        EmulationError::Save(err) => Err(err.into()),
                     };
                 }
+            };
+
+            rewind_state.record(&emulator, tick_effect);
+
+            if tick_effect == TickEffect::FrameRendered {
+                frame_count += 1;
+                fps_window_frames += 1;
+                recoverable_errors.frame_rendered();
+
+                input_handler.end_frame();
+
+                let now = Instant::now();
+                if let Some(frame_timing_log) = &mut frame_timing_log {
+                    frame_timing_log.record_frame(
+                        now,
+                        ticks,
+                        emulator.get_audio_player_mut().queued_audio_duration(),
+                    );
+                }
+                if let Some(frame_timing_log) =
+                    frame_timing_log.take_if(|frame_timing_log| frame_timing_log.is_complete())
+                {
+                    frame_timing_log.spawn_export();
+                }
+
+                // Shown starting with the next frame
+                let renderer = emulator.get_renderer_mut();
+                // The renderer logs the full warning; the OSD can only fit a short notice
+                if renderer.take_warning().is_some() {
+                    renderer_warning_until = Some(now + RENDERER_WARNING_DURATION);
+                }
+
+                let mut osd_lines = speedrun_timer.osd_lines(now);
+                osd_lines.extend(rewind_state.osd_lines(now));
+                osd_lines.extend(confirm_prompt.osd_lines(now));
+                if renderer_warning_until.is_some_and(|until| now < until) {
+                    osd_lines.push(OsdLine {
+                        text: "RENDERER WARNING - SEE LOG".into(),
+                        color: OSD_TEXT_COLOR,
+                    });
+                }
+                renderer.set_osd_lines(osd_lines);
+                renderer.set_osd_scrub_bar(None);
+
+                if frame_pacing == FramePacing::Vrr {
+                    let frame_skip = emulator.get_audio_player_mut().frame_skip;
+                    frame_pacer.wait_for_next_frame(emulator.timing_mode(), frame_skip);
+                }
+
+                if let Some(frames) = &mut register_write_dump_frames {
+                    *frames -= 1;
+                    if *frames == 0 {
+                        register_write_dump_frames = None;
+                        emulator_config.record_register_writes = false;
+                        dump_register_writes(
+                            playlist.nes_file_path(),
+                            emulator.rng_seed(),
+                            emulator.sprite_0_hit(),
+                            emulator.last_frame_register_writes(),
+                        );
+                    }
+                }
+
+                // A frame step ends at the frame boundary even if the frame failed to render or
+                // save, and the pause message replaces the OSD lines set above
+                stepping_frame = false;
+                if let Some(error_pause) = &error_pause {
+                    redraw_osd(&mut emulator, error_pause.osd_lines());
+                }
             }
 
             ticks += 1;
         }
 
         if rewind_state.rewinding {
-            rewind_state.rewind_once(&mut emulator)?;
+            rewind_state.rewind_once(&mut emulator, speedrun_timer.osd_lines(Instant::now()))?;
         }
 
-        if ticks % 15000 == 0 || rewind_state.rewinding {
-            if quit_signal.load(Ordering::Relaxed) {
-                return Ok(());
+        if ticks % 15000 == 0
+            || rewind_state.rewinding
+            || paused
+            || frontend_paused
+            || error_pause.is_some()
+        {
+            let fps_window_len = fps_window_start.elapsed();
+            if fps_window_len >= Duration::from_secs(1) {
+                fps = f64::from(fps_window_frames) / fps_window_len.as_secs_f64();
+                fps_window_start = Instant::now();
+                fps_window_frames = 0;
             }
 
-            if config_reload_signal.load(Ordering::Relaxed) {
-                config_reload_signal.store(false, Ordering::Relaxed);
-
-                let dynamic_config = &*dynamic_config.lock().unwrap();
+            for completion in save_state_worker.completions() {
+                handle_save_state_completion(
+                    completion,
+                    &mut emulator,
+                    &playlist,
+                    &mut rewind_state,
+                    &mut undo_load_state,
+                    &mut title_message,
+                );
+            }
+            if native_config.warn_on_timing_mode_mismatch {
+                warn_on_timing_mode_mismatch(&mut emulator, &mut playlist, &mut title_message);
+            }
+            title_message
+                .update(emulator.get_renderer_mut().window_mut(), &playlist.current().file_name());
+
+            let window = emulator.get_renderer_mut().window_mut();
+            if let (Some(display_key), Some(geometry)) = (
+                geometry::display_configuration_key(window.subsystem()),
+                geometry::window_geometry(window),
+            ) {
+                *window_geometry.lock().unwrap() = Some((display_key, geometry));
+            }
 
-                log::info!("Reloading dynamic config: {dynamic_config}");
+            *status.lock().unwrap() = EmulatorStatus {
+                running: true,
+                rom_file_name: Some(playlist.current().file_name()),
+                paused: paused || frontend_paused || error_pause.is_some(),
+                fps,
+                frame_count,
+                emulated_frame_count: emulator.frame_count(),
+                cpu_cycle_count: emulator.cpu_cycle_count(),
+                rewind_buffer_size: rewind_state.previous_states_size,
+                rewind_buffer_secs: rewind_state.buffered_duration().as_secs_f64(),
+                error_pause: error_pause.as_ref().map(|error_pause| error_pause.message.clone()),
+                cartridge_info: Some(*emulator.cartridge_info()),
+                gpu_adapter: gpu_adapter.clone(),
+            };
+
+            if let Some(rom_watcher) = &mut rom_watcher {
+                if rom_watcher.poll_changed(playlist.nes_file_path()) {
+                    log::info!("ROM file changed; reloading");
+
+                    // Reloading the current playlist entry starts the game over with the new ROM
+                    // contents while keeping the window and input state
+                    let current_index = playlist.current_index();
+                    if switch_game(
+                        &mut emulator,
+                        &mut playlist,
+                        current_index,
+                        native_config.forced_timing_mode,
+                        native_config.ram_init,
+                    )? {
+                        rewind_state = RewindState::new(&dynamic_config.lock().unwrap());
+                        frame_count = 0;
+                    }
+                }
+            }
 
-                dynamic_config.update_emulator_config(&mut emulator_config);
+            let commands: Vec<_> = command_receiver.lock().unwrap().try_iter().collect();
+            for command in commands {
+                match command {
+                    EmulatorCommand::Quit => {
+                        return Ok(());
+                    }
+                    EmulatorCommand::ReloadConfig => {
+                        let dynamic_config = &*dynamic_config.lock().unwrap();
 
-                let renderer = emulator.get_renderer_mut();
-                renderer.reload_config(dynamic_config)?;
+                        log::info!("Reloading dynamic config: {dynamic_config}");
 
-                let audio_player = emulator.get_audio_player_mut();
-                audio_player.sync_to_audio = dynamic_config.sync_to_audio;
-                audio_player.internal_buffer_size = dynamic_config.internal_audio_buffer_size;
-                audio_player.audio_sync_threshold = dynamic_config.audio_sync_threshold;
-                audio_player
-                    .downsample_counter
-                    .set_refresh_rate_adjustment(dynamic_config.audio_refresh_rate_adjustment);
+                        dynamic_config.update_emulator_config(&mut emulator_config);
 
-                input_handler.reload_input_config(&dynamic_config.input_config);
+                        let renderer = emulator.get_renderer_mut();
+                        renderer.reload_config(dynamic_config)?;
 
-                fast_forward_multiplier = dynamic_config.fast_forward_multiplier;
-                rewind_state.reload_buffer_len(dynamic_config.rewind_buffer_len);
-            }
+                        let audio_player = emulator.get_audio_player_mut();
+                        audio_player.sync_to_audio = dynamic_config.audio_sync_enabled();
+                        audio_player.internal_buffer_size =
+                            dynamic_config.internal_audio_buffer_size;
+                        audio_player.audio_sync_threshold = dynamic_config.audio_sync_threshold;
+                        audio_player.audio_sync_poll_interval = power::audio_sync_poll_interval(
+                            dynamic_config.low_power_mode.is_active(),
+                        );
+                        audio_player.downsample_counter.set_refresh_rate_adjustment(
+                            dynamic_config.audio_refresh_rate_adjustment_enabled(),
+                        );
 
-            if let Some(input_type) =
-                InputType::from_discriminant(input_reconfigure_signal.load(Ordering::Relaxed))
-            {
-                input_reconfigure_signal
-                    .store(JgnesSharedConfig::NO_INPUT_RECONFIGURE, Ordering::Relaxed);
+                        frame_pacing = dynamic_config.frame_pacing;
 
-                // Attempt to ensure that pressed inputs will go to the SDL2 window; does not appear
-                // to work on all platforms / window managers
-                emulator.get_renderer_mut().window_mut().raise();
+                        input_handler.reload_input_config(&dynamic_config.input_config);
 
-                match handle_input_reconfigure(input_type, &mut event_pump, &mut input_handler)? {
-                    InputReconfigureResult::Input(input_collect_result) => {
-                        log::info!("Sending input collect result {input_collect_result:?}");
-                        input_reconfigure_sender.send(Some(input_collect_result)).unwrap();
+                        fast_forward_multiplier = dynamic_config.fast_forward_multiplier;
+                        clip_len = dynamic_config.clip_len;
+                        rewind_state.reload_config(dynamic_config);
                     }
-                    InputReconfigureResult::Quit => {
-                        input_reconfigure_sender.send(None).unwrap();
-                        return Ok(());
+                    EmulatorCommand::ConfigureInput(input_type) => {
+                        // Attempt to ensure that pressed inputs will go to the SDL2 window; does
+                        // not appear to work on all platforms / window managers
+                        emulator.get_renderer_mut().window_mut().raise();
+
+                        match handle_input_reconfigure(
+                            input_type,
+                            &mut event_pump,
+                            &mut input_handler,
+                        )? {
+                            InputReconfigureResult::Input(input_collect_result) => {
+                                log::info!(target: INPUT_TARGET, "Sending input collect result {input_collect_result:?}");
+                                input_reconfigure_sender.send(Some(input_collect_result)).unwrap();
+                            }
+                            InputReconfigureResult::Quit => {
+                                input_reconfigure_sender.send(None).unwrap();
+                                return Ok(());
+                            }
+                        }
+                    }
+                    EmulatorCommand::SaveState { slot } => {
+                        save_state_to_slot(&emulator, &playlist, &save_state_worker, slot)?;
+                    }
+                    EmulatorCommand::LoadState { slot } => {
+                        load_state_from_slot(&playlist, &save_state_worker, slot);
+                    }
+                    EmulatorCommand::ExportState(path) => {
+                        let mut state = Vec::new();
+                        emulator.save_state(&mut state)?;
+                        save_state_worker.write(path, state);
+                    }
+                    EmulatorCommand::ImportState(path) => {
+                        save_state_worker.read(path, playlist.current_index());
+                    }
+                    EmulatorCommand::RecordFrameTiming(path) => {
+                        frame_timing_log = Some(FrameTimingLog::start(path, ticks));
+                        title_message.show(
+                            emulator.get_renderer_mut().window_mut(),
+                            &playlist.current().file_name(),
+                            "Recording frame timing",
+                        );
+                    }
+                    EmulatorCommand::SoftReset => {
+                        log::info!("Performing soft reset");
+                        emulator.soft_reset();
+                        rewind_state.record_soft_reset();
+                        speedrun_timer
+                            .console_reset(&playlist.current().file_name(), Instant::now());
+                    }
+                    EmulatorCommand::HardReset => {
+                        log::info!("Performing hard reset");
+                        emulator = emulator.hard_reset(None);
+                        rewind_state.record_hard_reset(emulator.ram_init());
+                        speedrun_timer
+                            .console_reset(&playlist.current().file_name(), Instant::now());
+                    }
+                    EmulatorCommand::LoadRom(rom_load_request) => {
+                        log::info!("Loading ROM {rom_load_request:?} into running emulator");
+
+                        let index = playlist.find_or_push(rom_load_request);
+                        if switch_game(
+                            &mut emulator,
+                            &mut playlist,
+                            index,
+                            native_config.forced_timing_mode,
+                            native_config.ram_init,
+                        )? {
+                            rewind_state = RewindState::new(&dynamic_config.lock().unwrap());
+                            frame_count = 0;
+                        }
+                    }
+                    EmulatorCommand::SetPaused(new_paused) => {
+                        log::info!("Setting paused to {new_paused}");
+                        paused = new_paused;
+                    }
+                    EmulatorCommand::SetFrontendPaused(new_paused) => {
+                        log::debug!("Setting frontend paused to {new_paused}");
+                        frontend_paused = new_paused;
+                    }
+                    EmulatorCommand::ResumeAfterError => {
+                        if error_pause.take().is_some() {
+                            log::info!("Resuming emulation after error");
+                        }
+                    }
+                    EmulatorCommand::StepFrame => {
+                        stepping_frame = true;
+                    }
+                    EmulatorCommand::Screenshot => {
+                        let dynamic_config = dynamic_config.lock().unwrap();
+                        emulator.get_renderer().spawn_screenshot_export(
+                            playlist.nes_file_path().to_path_buf(),
+                            &dynamic_config.palette,
+                            dynamic_config.overscan,
+                        );
+                    }
+                    EmulatorCommand::SetWindowFlags { always_on_top, borderless } => {
+                        let window = emulator.get_renderer_mut().window_mut();
+                        window.set_always_on_top(always_on_top);
+                        window.set_bordered(!borderless);
                     }
                 }
             }
 
             for event in event_pump.poll_iter() {
+                // The error pause keys are consumed so that they cannot also trigger hotkeys or
+                // joypad input that happen to be bound to the same keys
+                if let (Some(_), Event::KeyDown { keycode: Some(keycode), repeat, .. }) =
+                    (&error_pause, &event)
+                {
+                    let keycode = *keycode;
+                    if keycode == ERROR_PAUSE_RESUME_KEY || keycode == ERROR_PAUSE_STEP_KEY {
+                        if !repeat {
+                            if keycode == ERROR_PAUSE_RESUME_KEY {
+                                log::info!("Resuming emulation after error");
+                                error_pause = None;
+                            } else {
+                                stepping_frame = true;
+                            }
+                        }
+                        continue;
+                    }
+                }
+
                 input_handler.handle_event(&event)?;
 
                 match event {
                     Event::Quit { .. } => {
-                        return Ok(());
+                        let confirm_quit = dynamic_config.lock().unwrap().confirm_quit;
+                        if confirm_hotkey_action(
+                            &mut confirm_prompt,
+                            ConfirmAction::Quit,
+                            confirm_quit,
+                            false,
+                            &mut emulator,
+                            &playlist,
+                            &mut title_message,
+                        ) {
+                            return Ok(());
+                        }
+                    }
+                    Event::Window { win_event: WindowEvent::Close, window_id, .. } => {
+                        let renderer = emulator.get_renderer_mut();
+                        if renderer
+                            .mirror_mut()
+                            .is_some_and(|mirror| mirror.window_mut().id() == window_id)
+                        {
+                            log::info!("Mirror window closed");
+                            renderer.close_mirror();
+                        } else if renderer.mirror_mut().is_some() {
+                            // SDL only sends a quit event once every window has been closed, so
+                            // closing the main window has to be handled here while the mirror
+                            // window is open
+                            let confirm_quit = dynamic_config.lock().unwrap().confirm_quit;
+                            if confirm_hotkey_action(
+                                &mut confirm_prompt,
+                                ConfirmAction::Quit,
+                                confirm_quit,
+                                false,
+                                &mut emulator,
+                                &playlist,
+                                &mut title_message,
+                            ) {
+                                return Ok(());
+                            }
+                        }
                     }
                     Event::Window {
                         win_event:
@@ -684,13 +1885,31 @@ where
                             | WindowEvent::Shown,
                         ..
                     } => {
-                        emulator.get_renderer_mut().handle_resize();
+                        let renderer = emulator.get_renderer_mut();
+                        let (width, height) = renderer.window_mut().drawable_size();
+                        renderer.handle_window_resize(width, height);
+                        if let Some(mirror) = renderer.mirror_mut() {
+                            let (width, height) = mirror.window_mut().drawable_size();
+                            mirror.handle_window_resize(width, height);
+                        }
                     }
-                    Event::KeyDown { keycode: Some(keycode), .. } => {
-                        for hotkey in input_handler.check_for_hotkeys(keycode) {
+                    Event::KeyDown { keycode: Some(keycode), repeat, .. } => {
+                        // Cloned because macro hotkeys need to mutate the input handler
+                        for hotkey in input_handler.check_for_hotkeys(keycode).clone() {
                             match hotkey {
                                 Hotkey::Quit => {
-                                    return Ok(());
+                                    let confirm_quit = dynamic_config.lock().unwrap().confirm_quit;
+                                    if confirm_hotkey_action(
+                                        &mut confirm_prompt,
+                                        ConfirmAction::Quit,
+                                        confirm_quit,
+                                        repeat,
+                                        &mut emulator,
+                                        &playlist,
+                                        &mut title_message,
+                                    ) {
+                                        return Ok(());
+                                    }
                                 }
                                 Hotkey::ToggleFullscreen => {
                                     let window = emulator.get_renderer_mut().window_mut();
@@ -703,38 +1922,72 @@ where
                                         .map_err(anyhow::Error::msg)?;
                                 }
                                 Hotkey::SaveState => {
-                                    emulator.save_state(File::create(save_state_path)?)?;
-                                    log::info!("Saved state to '{}'", save_state_path.display());
-                                }
-                                Hotkey::LoadState => match File::open(save_state_path) {
-                                    Ok(file) => match emulator.load_state(file) {
-                                        Ok(..) => {
-                                            log::info!(
-                                                "Successfully loaded save state from '{}'",
-                                                save_state_path.display()
-                                            );
-                                        }
-                                        Err(err) => {
-                                            log::error!(
-                                                "Error loading state from '{}': {err}",
-                                                save_state_path.display()
-                                            );
-                                        }
-                                    },
-                                    Err(err) => {
-                                        log::error!(
-                                            "Cannot open file at '{}': {err}",
-                                            save_state_path.display()
-                                        );
+                                    let confirm_overwrite =
+                                        dynamic_config.lock().unwrap().confirm_overwrite_state
+                                            && playlist
+                                                .current()
+                                                .save_state_path(playlist.save_dir(), 0)
+                                                .is_file();
+                                    if confirm_hotkey_action(
+                                        &mut confirm_prompt,
+                                        ConfirmAction::OverwriteState,
+                                        confirm_overwrite,
+                                        repeat,
+                                        &mut emulator,
+                                        &playlist,
+                                        &mut title_message,
+                                    ) {
+                                        save_state_to_slot(
+                                            &emulator,
+                                            &playlist,
+                                            &save_state_worker,
+                                            0,
+                                        )?;
                                     }
-                                },
+                                }
+                                Hotkey::LoadState => {
+                                    load_state_from_slot(&playlist, &save_state_worker, 0);
+                                }
+                                Hotkey::UndoLoadState => {
+                                    undo_load_state_from_backup(
+                                        &mut emulator,
+                                        &playlist,
+                                        &mut undo_load_state,
+                                        &mut rewind_state,
+                                        &mut title_message,
+                                    );
+                                }
                                 Hotkey::SoftReset => {
                                     log::info!("Performing soft reset");
                                     emulator.soft_reset();
+                                    rewind_state.record_soft_reset();
+                                    speedrun_timer.console_reset(
+                                        &playlist.current().file_name(),
+                                        Instant::now(),
+                                    );
                                 }
                                 Hotkey::HardReset => {
+                                    let confirm_hard_reset =
+                                        dynamic_config.lock().unwrap().confirm_hard_reset;
+                                    if !confirm_hotkey_action(
+                                        &mut confirm_prompt,
+                                        ConfirmAction::HardReset,
+                                        confirm_hard_reset,
+                                        repeat,
+                                        &mut emulator,
+                                        &playlist,
+                                        &mut title_message,
+                                    ) {
+                                        continue;
+                                    }
+
                                     log::info!("Performing hard reset");
                                     emulator = emulator.hard_reset(None);
+                                    rewind_state.record_hard_reset(emulator.ram_init());
+                                    speedrun_timer.console_reset(
+                                        &playlist.current().file_name(),
+                                        Instant::now(),
+                                    );
                                 }
                                 Hotkey::FastForward => {
                                     let frame_skip =
@@ -744,7 +1997,126 @@ where
                                     emulator.get_audio_player_mut().frame_skip = frame_skip;
                                 }
                                 Hotkey::Rewind => {
-                                    rewind_state.rewinding = true;
+                                    rewind_state.start_rewinding();
+                                }
+                                Hotkey::RewindForward => {
+                                    rewind_state.rewind_forward_once(
+                                        &mut emulator,
+                                        speedrun_timer.osd_lines(Instant::now()),
+                                    )?;
+                                }
+                                Hotkey::SaveCapture => {
+                                    let dynamic_config = dynamic_config.lock().unwrap();
+                                    emulator.get_renderer().spawn_capture_export(
+                                        playlist.nes_file_path().to_path_buf(),
+                                        dynamic_config.capture_format,
+                                        &dynamic_config.palette,
+                                        dynamic_config.overscan,
+                                    );
+                                }
+                                Hotkey::SaveClip => {
+                                    let segments = rewind_state.clip_segments(clip_len);
+                                    if segments.is_empty() {
+                                        log::warn!("Rewind buffer is empty, not saving clip");
+                                        continue;
+                                    }
+
+                                    log::info!("Saving clip in the background");
+
+                                    let dynamic_config = dynamic_config.lock().unwrap();
+                                    clip::spawn_clip_export(ClipExportArgs {
+                                        nes_file_path: playlist.nes_file_path().to_path_buf(),
+                                        forced_timing_mode: native_config.forced_timing_mode,
+                                        forced_mapper_number: playlist
+                                            .current()
+                                            .forced_mapper_number,
+                                        patch_file_path: playlist.current().patch_file_path.clone(),
+                                        emulator_config: emulator_config.clone(),
+                                        palette: dynamic_config.palette.clone(),
+                                        overscan: dynamic_config.overscan,
+                                        frames_per_segment: rewind_state.record_interval as u32,
+                                        segments,
+                                    });
+                                }
+                                Hotkey::NextGame | Hotkey::PreviousGame => {
+                                    if playlist.len() < 2 {
+                                        log::info!("Playlist only contains one game");
+                                        continue;
+                                    }
+
+                                    let direction = if hotkey == Hotkey::NextGame {
+                                        PlaylistDirection::Next
+                                    } else {
+                                        PlaylistDirection::Previous
+                                    };
+                                    let next_index = playlist.neighbor_index(direction);
+                                    if switch_game(
+                                        &mut emulator,
+                                        &mut playlist,
+                                        next_index,
+                                        native_config.forced_timing_mode,
+                                        native_config.ram_init,
+                                    )? {
+                                        rewind_state =
+                                            RewindState::new(&dynamic_config.lock().unwrap());
+                                        frame_count = 0;
+                                    }
+                                }
+                                Hotkey::DumpRegisterWrites => {
+                                    log::info!("Recording register writes for the next frame");
+                                    emulator_config.record_register_writes = true;
+                                    register_write_dump_frames = Some(2);
+                                }
+                                Hotkey::ToggleWavCapture => {
+                                    toggle_wav_capture(
+                                        &mut emulator,
+                                        &playlist,
+                                        &mut title_message,
+                                    );
+                                }
+                                Hotkey::ToggleScrollOverlay => {
+                                    emulator_config.debug_scroll_overlay =
+                                        !emulator_config.debug_scroll_overlay;
+                                    log::info!(
+                                        "Scroll debug overlay {}",
+                                        if emulator_config.debug_scroll_overlay {
+                                            "enabled"
+                                        } else {
+                                            "disabled"
+                                        }
+                                    );
+                                }
+                                Hotkey::TimerStartSplit => {
+                                    speedrun_timer.start_or_split(
+                                        &playlist.current().file_name(),
+                                        Instant::now(),
+                                    );
+                                }
+                                Hotkey::TimerReset => {
+                                    speedrun_timer.reset(Instant::now());
+                                }
+                                Hotkey::RecordMacro | Hotkey::PlayMacro => {
+                                    let status = if hotkey == Hotkey::RecordMacro {
+                                        input_handler.toggle_macro_recording()
+                                    } else {
+                                        input_handler.play_macro()
+                                    };
+                                    title_message.show(
+                                        emulator.get_renderer_mut().window_mut(),
+                                        &playlist.current().file_name(),
+                                        &macro_status_message(status),
+                                    );
+                                }
+                                Hotkey::ToggleInputDisplay => {
+                                    emulator_config.input_display = !emulator_config.input_display;
+                                    log::info!(
+                                        "Input display {}",
+                                        if emulator_config.input_display {
+                                            "enabled"
+                                        } else {
+                                            "disabled"
+                                        }
+                                    );
                                 }
                             }
                         }
@@ -753,11 +2125,24 @@ where
                         for hotkey in input_handler.check_for_hotkeys(keycode) {
                             match hotkey {
                                 Hotkey::FastForward => {
+                                    let was_skipping = emulator.get_audio_player_mut().frame_skip
+                                        != FrameSkip::ZERO;
                                     emulator.get_renderer_mut().set_frame_skip(FrameSkip::ZERO);
                                     emulator.get_audio_player_mut().frame_skip = FrameSkip::ZERO;
+
+                                    // The last few frames may have been skipped; show the last
+                                    // completed frame now rather than when the next frame
+                                    // completes
+                                    if was_skipping {
+                                        render_last_frame(
+                                            &mut emulator,
+                                            &mut recoverable_errors,
+                                            &mut error_pause,
+                                        )?;
+                                    }
                                 }
                                 Hotkey::Rewind => {
-                                    rewind_state.rewinding = false;
+                                    rewind_state.stop_rewinding();
                                 }
                                 _ => {}
                             }
@@ -766,8 +2151,342 @@ where
                     _ => {}
                 }
             }
+
+            rewind_state.record_inputs(input_handler.joypad_states());
+        }
+    }
+}
+
+fn dump_register_writes(
+    nes_file_path: &Path,
+    rng_seed: u64,
+    sprite_0_hit: Option<Sprite0HitEvent>,
+    register_writes: &[RegisterWriteEvent],
+) {
+    let path =
+        encode::timestamped_output_path(nes_file_path, "register-writes").with_extension("txt");
+
+    let sprite_0_hit = sprite_0_hit.map_or_else(|| "None".into(), |hit| hit.to_string());
+
+    // The seed is needed to reproduce anything that depends on power-on RAM contents
+    let mut contents = format!("RNG seed: {rng_seed:016X}\nSprite 0 hit: {sprite_0_hit}\n");
+    contents.push_str("Scanline Dot  Address = Value  Register\n");
+    for event in register_writes {
+        contents.push_str(&event.to_string());
+        contents.push('\n');
+    }
+
+    match fs::write(&path, contents) {
+        Ok(()) => {
+            log::info!("Wrote {} register writes to '{}'", register_writes.len(), path.display());
+        }
+        Err(err) => log::error!("Error writing register writes to '{}': {err}", path.display()),
+    }
+}
+
+// Serializing is fast, so it happens immediately; the file is written in the background
+fn save_state_to_slot<R, A, I, S>(
+    emulator: &Emulator<R, A, I, S>,
+    playlist: &Playlist,
+    save_state_worker: &SaveStateWorker,
+    slot: u8,
+) -> anyhow::Result<()> {
+    let mut state = Vec::new();
+    emulator.save_state(&mut state)?;
+    save_state_worker.write(playlist.current().save_state_path(playlist.save_dir(), slot), state);
+
+    Ok(())
+}
+
+// Returns whether a hotkey action should happen now. If confirmation is required, the first press
+// only shows a prompt; it is also shown in the window title because the on-screen display is not
+// redrawn while paused. Holding the hotkey down does not confirm its own prompt.
+fn confirm_hotkey_action<R, A, I, S>(
+    confirm_prompt: &mut ConfirmPrompt,
+    action: ConfirmAction,
+    required: bool,
+    repeat: bool,
+    emulator: &mut Emulator<R, A, I, S>,
+    playlist: &Playlist,
+    title_message: &mut TitleMessage,
+) -> bool
+where
+    R: SdlWindowRenderer,
+{
+    if !required {
+        return true;
+    }
+    if repeat {
+        return false;
+    }
+
+    if confirm_prompt.confirm(action, Instant::now()) {
+        return true;
+    }
+
+    title_message.show(
+        emulator.get_renderer_mut().window_mut(),
+        &playlist.current().file_name(),
+        action.prompt(),
+    );
+    false
+}
+
+// The state is loaded once the file has been read in the background
+fn load_state_from_slot(playlist: &Playlist, save_state_worker: &SaveStateWorker, slot: u8) {
+    save_state_worker.read(
+        playlist.current().save_state_path(playlist.save_dir(), slot),
+        playlist.current_index(),
+    );
+}
+
+// Snapshot taken right before the most recent save state load so that an accidental load can be
+// undone, along with the playlist index of the game it belongs to
+#[derive(Debug)]
+struct UndoLoadState {
+    playlist_index: usize,
+    state: Vec<u8>,
+}
+
+fn snapshot_for_undo<R, A, I, S>(
+    emulator: &Emulator<R, A, I, S>,
+    playlist: &Playlist,
+) -> Option<UndoLoadState> {
+    let mut state = Vec::new();
+    match emulator.save_state(&mut state) {
+        Ok(()) => Some(UndoLoadState { playlist_index: playlist.current_index(), state }),
+        Err(err) => {
+            log::error!("Unable to snapshot state for undoing a save state load: {err}");
+            None
+        }
+    }
+}
+
+fn undo_load_state_from_backup<R, A, I, S>(
+    emulator: &mut Emulator<R, A, I, S>,
+    playlist: &Playlist,
+    undo_load_state: &mut Option<UndoLoadState>,
+    rewind_state: &mut RewindState,
+    title_message: &mut TitleMessage,
+) where
+    R: SdlWindowRenderer,
+{
+    let message = match undo_load_state.take() {
+        Some(undo) if undo.playlist_index == playlist.current_index() => {
+            // Keeping the state being replaced means that undoing again redoes the load
+            let redo = snapshot_for_undo(emulator, playlist);
+            match emulator.load_state(undo.state.as_slice()) {
+                Ok(()) => {
+                    log::info!("Restored state from before the last save state load");
+                    rewind_state.interrupt_input_log();
+                    *undo_load_state = redo;
+                    "Load state undone"
+                }
+                Err(err) => {
+                    log::error!("Error undoing save state load: {err}");
+                    "Error undoing load state"
+                }
+            }
+        }
+        _ => "No load state to undo",
+    };
+
+    title_message.show(
+        emulator.get_renderer_mut().window_mut(),
+        &playlist.current().file_name(),
+        message,
+    );
+}
+
+// Failing to save or load a save state is not fatal
+fn handle_save_state_completion<R, A, I, S>(
+    completion: SaveStateCompletion,
+    emulator: &mut Emulator<R, A, I, S>,
+    playlist: &Playlist,
+    rewind_state: &mut RewindState,
+    undo_load_state: &mut Option<UndoLoadState>,
+    title_message: &mut TitleMessage,
+) where
+    R: SdlWindowRenderer,
+{
+    let message = match completion {
+        SaveStateCompletion::Written { path, result: Ok(()) } => {
+            log::info!("Saved state to '{}'", path.display());
+            "State saved"
+        }
+        SaveStateCompletion::Written { path, result: Err(err) } => {
+            log::error!("Error writing save state to '{}': {err}", path.display());
+            "Error saving state"
+        }
+        SaveStateCompletion::Read { path, playlist_index, .. }
+            if playlist_index != playlist.current_index() =>
+        {
+            log::warn!(
+                "Not loading save state from '{}' because the game was switched",
+                path.display()
+            );
+            return;
+        }
+        SaveStateCompletion::Read { path, result: Ok(state), .. } => {
+            let backup = snapshot_for_undo(emulator, playlist);
+            match emulator.load_state(state.as_slice()) {
+                Ok(()) => {
+                    log::info!("Successfully loaded save state from '{}'", path.display());
+                    rewind_state.interrupt_input_log();
+                    if backup.is_some() {
+                        *undo_load_state = backup;
+                    }
+                    "State loaded"
+                }
+                Err(err) => {
+                    log::error!("Error loading state from '{}': {err}", path.display());
+                    "Error loading state"
+                }
+            }
+        }
+        SaveStateCompletion::Read { path, result: Err(err), .. } => {
+            log::error!("Cannot open file at '{}': {err}", path.display());
+            "Error loading state"
+        }
+    };
+
+    title_message.show(
+        emulator.get_renderer_mut().window_mut(),
+        &playlist.current().file_name(),
+        message,
+    );
+}
+
+fn toggle_wav_capture<R, I, S>(
+    emulator: &mut Emulator<R, SdlAudioPlayer, I, S>,
+    playlist: &Playlist,
+    title_message: &mut TitleMessage,
+) where
+    R: SdlWindowRenderer,
+{
+    let audio_player = emulator.get_audio_player_mut();
+    let message = if audio_player.stop_wav_capture().is_some() {
+        "Audio capture saved"
+    } else {
+        let path = encode::timestamped_output_path(playlist.nes_file_path(), "audio")
+            .with_extension("wav");
+        match audio_player.start_wav_capture(&path) {
+            Ok(()) => "Capturing audio",
+            Err(err) => {
+                log::error!("Unable to capture audio to '{}': {err}", path.display());
+                "Error starting audio capture"
+            }
+        }
+    };
+
+    title_message.show(
+        emulator.get_renderer_mut().window_mut(),
+        &playlist.current().file_name(),
+        message,
+    );
+}
+
+fn macro_status_message(status: MacroStatus) -> String {
+    match status {
+        MacroStatus::RecordingStarted => "Recording input macro".into(),
+        MacroStatus::Recorded { frames: 0 } => "Input macro recording cancelled".into(),
+        MacroStatus::Recorded { frames } => format!("Recorded {frames}-frame input macro"),
+        MacroStatus::PlaybackStarted => "Playing input macro".into(),
+        MacroStatus::NothingRecorded => "No input macro recorded".into(),
+        MacroStatus::Busy => "Input macro is already recording or playing".into(),
+    }
+}
+
+// Warns at most once per game per session
+fn warn_on_timing_mode_mismatch<R, A, I, S>(
+    emulator: &mut Emulator<R, A, I, S>,
+    playlist: &mut Playlist,
+    title_message: &mut TitleMessage,
+) where
+    R: SdlWindowRenderer,
+{
+    let current_index = playlist.current_index();
+    let entry = playlist.entry_mut(current_index);
+    if entry.timing_mode_checked {
+        return;
+    }
+    entry.timing_mode_checked = true;
+
+    let cartridge_info = emulator.cartridge_info();
+    let Some(header_timing_mode) = cartridge_info.timing_mode_mismatch() else {
+        return;
+    };
+
+    let message = format!("{header_timing_mode} game running as {}", cartridge_info.timing_mode);
+    log::warn!("{message}; the game will run at the wrong speed");
+    title_message.show(emulator.get_renderer_mut().window_mut(), &entry.file_name(), &message);
+}
+
+// Returns whether the game was switched; failing to load the next game is not fatal, the current
+// game simply keeps running
+fn switch_game<R, I>(
+    emulator: &mut Emulator<RecordingRenderer<R>, SdlAudioPlayer, I, FsSaveWriter>,
+    playlist: &mut Playlist,
+    next_index: usize,
+    forced_timing_mode: Option<TimingMode>,
+    ram_init: RamInit,
+) -> anyhow::Result<bool>
+where
+    R: Renderer + SdlWindowRenderer,
+    R::Err: std::error::Error + Send + Sync + 'static,
+    I: InputPoller,
+{
+    let prev_index = playlist.current_index();
+
+    let mut suspended_state = Vec::new();
+    emulator.save_state(&mut suspended_state)?;
+
+    let save_dir = playlist.save_dir().map(Path::to_path_buf);
+    let next = playlist.entry_mut(next_index);
+    let rom_bytes = match read_rom_file(&next.nes_file_path, next.patch_file_path.as_deref()) {
+        Ok(rom_bytes) => rom_bytes,
+        Err(err) => {
+            log::error!("Unable to read '{}': {err}", next.nes_file_path.display());
+            return Ok(false);
+        }
+    };
+
+    let sav_path = next.sav_path(save_dir.as_deref());
+    let sav_bytes = load_sav_file(&sav_path);
+    match emulator.swap_cartridge(CartridgeSwapArgs {
+        rom_bytes,
+        sav_bytes,
+        forced_timing_mode,
+        forced_mapper_number: next.forced_mapper_number,
+        ram_init,
+        save_writer: FsSaveWriter::new(sav_path),
+    }) {
+        Ok(_) => {}
+        Err(InitializationError::RendererInit { source }) => return Err(source.into()),
+        Err(err) => {
+            log_rom_diagnostics(&err);
+            log::error!("Unable to load '{}': {err}", next.nes_file_path.display());
+            return Ok(false);
         }
     }
+
+    if let Some(state) = next.suspended_state.take() {
+        if let Err(err) = emulator.load_state(state.as_slice()) {
+            log::error!("Unable to resume '{}': {err}", next.nes_file_path.display());
+        }
+    }
+    let file_name = next.file_name();
+
+    // Reloading the current game starts it over rather than resuming it
+    if prev_index != next_index {
+        playlist.entry_mut(prev_index).suspended_state = Some(suspended_state);
+    }
+    playlist.set_current(next_index);
+
+    log::info!("Switched to '{file_name}' ({}/{})", next_index + 1, playlist.len());
+    emulator.get_renderer_mut().window_mut().set_title(&title::window_title(&file_name))?;
+
+    Ok(true)
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -781,7 +2500,7 @@ fn handle_input_reconfigure(
     event_pump: &mut EventPump,
     input_handler: &mut SdlInputHandler<'_>,
 ) -> Result<InputReconfigureResult, anyhow::Error> {
-    log::info!("Input reconfigure requested for input type {input_type:?}");
+    log::info!(target: INPUT_TARGET, "Input reconfigure requested for input type {input_type:?}");
 
     let axis_deadzone = input_handler.axis_deadzone();
 
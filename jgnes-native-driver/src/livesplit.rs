@@ -0,0 +1,126 @@
+//! Optional client for the LiveSplit Server component, so that external split timers such as
+//! LiveSplit and LiveSplit One follow the built-in speedrun timer's start, split, and reset events.
+//!
+//! The LiveSplit Server protocol is plain text over TCP with one command per line. Commands are
+//! sent from a background thread so that a slow or missing server never stalls emulation; if the
+//! server is not reachable, the command is dropped and the next command tries to connect again.
+
+use std::io;
+use std::io::Write;
+use std::net::{Ipv4Addr, SocketAddr, TcpStream};
+use std::sync::mpsc;
+use std::sync::mpsc::{Receiver, Sender};
+use std::thread;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum LiveSplitCommand {
+    StartTimer,
+    Split,
+    Reset,
+}
+
+impl LiveSplitCommand {
+    fn protocol_str(self) -> &'static str {
+        match self {
+            Self::StartTimer => "starttimer",
+            Self::Split => "split",
+            Self::Reset => "reset",
+        }
+    }
+}
+
+/// LiveSplit Server client; the connection is closed when this is dropped.
+pub(crate) struct LiveSplitClient {
+    sender: Option<Sender<LiveSplitCommand>>,
+    send_thread: Option<JoinHandle<()>>,
+}
+
+impl LiveSplitClient {
+    pub(crate) fn start(port: u16) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let addr = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+        let send_thread = thread::spawn(move || send_commands(addr, &receiver));
+
+        log::info!("Sending speedrun timer events to LiveSplit Server at {addr}");
+
+        Self { sender: Some(sender), send_thread: Some(send_thread) }
+    }
+
+    pub(crate) fn send(&self, command: LiveSplitCommand) {
+        if let Some(sender) = &self.sender {
+            // The send thread only exits once the sender is dropped
+            let _ = sender.send(command);
+        }
+    }
+}
+
+impl Drop for LiveSplitClient {
+    fn drop(&mut self) {
+        self.sender = None;
+        if let Some(send_thread) = self.send_thread.take() {
+            let _ = send_thread.join();
+        }
+    }
+}
+
+fn send_commands(addr: SocketAddr, receiver: &Receiver<LiveSplitCommand>) {
+    let mut stream: Option<TcpStream> = None;
+    for command in receiver {
+        let mut result = send_command(&mut stream, addr, command);
+        // The server may have closed an existing connection, so retry once with a new connection
+        if result.is_err() && stream.take().is_some() {
+            result = send_command(&mut stream, addr, command);
+        }
+
+        if let Err(err) = result {
+            log::warn!(
+                "Unable to send '{}' to LiveSplit Server at {addr}: {err}",
+                command.protocol_str()
+            );
+            stream = None;
+        }
+    }
+}
+
+fn send_command(
+    stream: &mut Option<TcpStream>,
+    addr: SocketAddr,
+    command: LiveSplitCommand,
+) -> io::Result<()> {
+    let stream = match stream {
+        Some(stream) => stream,
+        None => stream.insert(TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT)?),
+    };
+
+    stream.write_all(command.protocol_str().as_bytes())?;
+    stream.write_all(b"\r\n")?;
+    stream.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use std::net::TcpListener;
+
+    #[test]
+    fn commands_are_sent_as_lines() {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let client = LiveSplitClient::start(port);
+        client.send(LiveSplitCommand::StartTimer);
+        client.send(LiveSplitCommand::Split);
+        client.send(LiveSplitCommand::Reset);
+        drop(client);
+
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut received = String::new();
+        stream.read_to_string(&mut received).unwrap();
+        assert_eq!(received, "starttimer\r\nsplit\r\nreset\r\n");
+    }
+}
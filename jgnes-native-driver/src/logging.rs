@@ -0,0 +1,164 @@
+//! Logger that wraps `env_logger` with two additions: the log level of each emulator subsystem can
+//! be changed at runtime, and recent log lines are kept in memory so that frontends can display
+//! them.
+//!
+//! Initial subsystem levels come from the usual `RUST_LOG` filter, e.g.
+//! `RUST_LOG=info,jgnes::ppu=debug`.
+
+use jgnes_core::logging::LogSubsystem;
+use log::{Level, LevelFilter, Log, Metadata, Record, SetLoggerError};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+const MAX_RECENT_LINES: usize = 1000;
+
+#[derive(Debug, Clone)]
+pub struct LogLine {
+    pub level: Level,
+    pub target: String,
+    pub message: String,
+}
+
+static LOGGER: OnceLock<JgnesLogger> = OnceLock::new();
+
+struct JgnesLogger {
+    // Only used to decide whether non-subsystem targets are enabled
+    env_filter: env_logger::Logger,
+    // Writes output; this allows every level for subsystem targets so that they can be filtered
+    // using the runtime levels instead
+    output: env_logger::Logger,
+    subsystem_levels: [AtomicUsize; LogSubsystem::ALL.len()],
+    recent_lines: Mutex<VecDeque<LogLine>>,
+}
+
+impl JgnesLogger {
+    fn subsystem_level(&self, subsystem: LogSubsystem) -> LevelFilter {
+        level_filter_from_usize(self.subsystem_levels[subsystem as usize].load(Ordering::Relaxed))
+    }
+
+    fn update_max_level(&self) {
+        let max_subsystem_level = LogSubsystem::ALL
+            .into_iter()
+            .map(|subsystem| self.subsystem_level(subsystem))
+            .max()
+            .unwrap_or(LevelFilter::Off);
+        log::set_max_level(self.env_filter.filter().max(max_subsystem_level));
+    }
+}
+
+impl Log for JgnesLogger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        match LogSubsystem::from_target(metadata.target()) {
+            Some(subsystem) => metadata.level() <= self.subsystem_level(subsystem),
+            None => self.env_filter.enabled(metadata),
+        }
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        self.output.log(record);
+
+        let mut recent_lines = self.recent_lines.lock().unwrap();
+        if recent_lines.len() == MAX_RECENT_LINES {
+            recent_lines.pop_front();
+        }
+        recent_lines.push_back(LogLine {
+            level: record.level(),
+            target: record.target().into(),
+            message: record.args().to_string(),
+        });
+    }
+
+    fn flush(&self) {
+        self.output.flush();
+    }
+}
+
+fn level_filter_from_usize(value: usize) -> LevelFilter {
+    LevelFilter::iter().find(|&level| level as usize == value).unwrap_or(LevelFilter::Off)
+}
+
+/// Install the jgnes logger as the global logger, using `default_filter` if the `RUST_LOG`
+/// environment variable is not set.
+///
+/// # Errors
+///
+/// This function will return an error if a global logger has already been set.
+pub fn init_logger(default_filter: &str) -> Result<(), SetLoggerError> {
+    let env = || env_logger::Env::default().default_filter_or(default_filter.to_owned());
+
+    let env_filter = env_logger::Builder::from_env(env()).build();
+
+    let mut output_builder = env_logger::Builder::from_env(env());
+    for subsystem in LogSubsystem::ALL {
+        output_builder.filter_module(subsystem.target(), LevelFilter::Trace);
+    }
+    let output = output_builder.build();
+
+    let subsystem_levels = LogSubsystem::ALL.map(|subsystem| {
+        // Most verbose level that the env filter enables for this subsystem
+        let level = Level::iter()
+            .filter(|&level| {
+                env_filter
+                    .enabled(&Metadata::builder().level(level).target(subsystem.target()).build())
+            })
+            .last()
+            .map_or(LevelFilter::Off, |level| level.to_level_filter());
+        AtomicUsize::new(level as usize)
+    });
+
+    let logger = LOGGER.get_or_init(|| JgnesLogger {
+        env_filter,
+        output,
+        subsystem_levels,
+        recent_lines: Mutex::new(VecDeque::with_capacity(MAX_RECENT_LINES)),
+    });
+    log::set_logger(logger)?;
+    logger.update_max_level();
+
+    Ok(())
+}
+
+/// The current log level for the given subsystem, or `Off` if the jgnes logger is not installed.
+#[must_use]
+pub fn subsystem_log_level(subsystem: LogSubsystem) -> LevelFilter {
+    LOGGER.get().map_or(LevelFilter::Off, |logger| logger.subsystem_level(subsystem))
+}
+
+/// Change the log level for the given subsystem. Does nothing if the jgnes logger is not installed.
+pub fn set_subsystem_log_level(subsystem: LogSubsystem, level: LevelFilter) {
+    if let Some(logger) = LOGGER.get() {
+        logger.subsystem_levels[subsystem as usize].store(level as usize, Ordering::Relaxed);
+        logger.update_max_level();
+    }
+}
+
+/// The most recent log lines, oldest first. Returns an empty list if the jgnes logger is not
+/// installed.
+///
+/// # Panics
+///
+/// This function will panic if the log line buffer's lock is poisoned.
+#[must_use]
+pub fn recent_log_lines() -> Vec<LogLine> {
+    LOGGER
+        .get()
+        .map(|logger| logger.recent_lines.lock().unwrap().iter().cloned().collect())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn level_filter_usize_round_trip() {
+        for level in LevelFilter::iter() {
+            assert_eq!(level_filter_from_usize(level as usize), level);
+        }
+    }
+}
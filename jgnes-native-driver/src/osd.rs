@@ -0,0 +1,131 @@
+//! Minimal on-screen display drawn directly into NES frame buffers before they are rendered.
+//!
+//! Only digits, letters, and the punctuation needed for timers are supported, using a 3x5 pixel font
+//! scaled up 2x. There is also a scrub bar for showing the position within the rewind buffer.
+
+use jgnes_core::{FrameBuffer, SCREEN_WIDTH};
+
+// NES colors; white and light gray text on a black background
+pub(crate) const OSD_TEXT_COLOR: u8 = 0x30;
+pub(crate) const OSD_DIM_TEXT_COLOR: u8 = 0x10;
+const OSD_BACKGROUND_COLOR: u8 = 0x0F;
+
+const GLYPH_WIDTH: usize = 3;
+const GLYPH_HEIGHT: usize = 5;
+const SCALE: usize = 2;
+const CHAR_ADVANCE: usize = (GLYPH_WIDTH + 1) * SCALE;
+const LINE_ADVANCE: usize = (GLYPH_HEIGHT + 1) * SCALE;
+const PADDING: usize = 2;
+const MARGIN: usize = 10;
+const SCRUB_BAR_WIDTH: usize = 96;
+const SCRUB_BAR_HEIGHT: usize = 4;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct OsdLine {
+    pub(crate) text: String,
+    pub(crate) color: u8,
+}
+
+// Each row is 3 bits with bit 2 as the leftmost pixel; letters are always drawn uppercase and
+// unsupported characters are blank
+fn glyph(c: char) -> [u8; GLYPH_HEIGHT] {
+    match c.to_ascii_uppercase() {
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b111, 0b001, 0b111, 0b100, 0b111],
+        '3' => [0b111, 0b001, 0b111, 0b001, 0b111],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b111, 0b001, 0b111],
+        '6' => [0b111, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b001, 0b001, 0b001],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+        ':' => [0b000, 0b010, 0b000, 0b010, 0b000],
+        '.' => [0b000, 0b000, 0b000, 0b000, 0b010],
+        '-' => [0b000, 0b000, 0b111, 0b000, 0b000],
+        'A' => [0b010, 0b101, 0b111, 0b101, 0b101],
+        'B' => [0b110, 0b101, 0b110, 0b101, 0b110],
+        'C' => [0b011, 0b100, 0b100, 0b100, 0b011],
+        'D' => [0b110, 0b101, 0b101, 0b101, 0b110],
+        'E' => [0b111, 0b100, 0b110, 0b100, 0b111],
+        'F' => [0b111, 0b100, 0b110, 0b100, 0b100],
+        'G' => [0b011, 0b100, 0b101, 0b101, 0b011],
+        'H' => [0b101, 0b101, 0b111, 0b101, 0b101],
+        'I' => [0b111, 0b010, 0b010, 0b010, 0b111],
+        'J' => [0b001, 0b001, 0b001, 0b101, 0b010],
+        'K' => [0b101, 0b101, 0b110, 0b101, 0b101],
+        'L' => [0b100, 0b100, 0b100, 0b100, 0b111],
+        'M' => [0b101, 0b111, 0b111, 0b101, 0b101],
+        'N' => [0b110, 0b101, 0b101, 0b101, 0b101],
+        'O' => [0b010, 0b101, 0b101, 0b101, 0b010],
+        'P' => [0b110, 0b101, 0b110, 0b100, 0b100],
+        'Q' => [0b010, 0b101, 0b101, 0b110, 0b011],
+        'R' => [0b110, 0b101, 0b110, 0b101, 0b101],
+        'S' => [0b011, 0b100, 0b010, 0b001, 0b110],
+        'T' => [0b111, 0b010, 0b010, 0b010, 0b010],
+        'U' => [0b101, 0b101, 0b101, 0b101, 0b111],
+        'V' => [0b101, 0b101, 0b101, 0b101, 0b010],
+        'W' => [0b101, 0b101, 0b111, 0b111, 0b101],
+        'X' => [0b101, 0b101, 0b010, 0b101, 0b101],
+        'Y' => [0b101, 0b101, 0b010, 0b010, 0b010],
+        'Z' => [0b111, 0b001, 0b010, 0b100, 0b111],
+        _ => [0b000; GLYPH_HEIGHT],
+    }
+}
+
+/// Draw lines of text right-aligned in the top-right corner of the frame, below the rows that are
+/// cropped in NTSC. Text that does not fit on screen is truncated.
+pub(crate) fn draw_lines(frame_buffer: &mut FrameBuffer, lines: &[OsdLine]) {
+    let max_chars = (usize::from(SCREEN_WIDTH) - 2 * MARGIN - 2 * PADDING) / CHAR_ADVANCE;
+    let line_len = |line: &OsdLine| line.text.chars().count().min(max_chars);
+
+    let max_len = lines.iter().map(line_len).max().unwrap_or(0);
+    if max_len == 0 {
+        return;
+    }
+
+    let box_right = usize::from(SCREEN_WIDTH) - MARGIN;
+    let box_left = box_right - (max_len * CHAR_ADVANCE - SCALE + 2 * PADDING);
+    let box_height = lines.len() * LINE_ADVANCE - SCALE + 2 * PADDING;
+    for row in &mut frame_buffer[MARGIN..MARGIN + box_height] {
+        row[box_left..box_right].fill(OSD_BACKGROUND_COLOR);
+    }
+
+    for (i, line) in lines.iter().enumerate() {
+        if line_len(line) == 0 {
+            continue;
+        }
+
+        let top = MARGIN + PADDING + i * LINE_ADVANCE;
+        let left = box_right - PADDING - (line_len(line) * CHAR_ADVANCE - SCALE);
+
+        for (j, c) in line.text.chars().take(max_chars).enumerate() {
+            for (glyph_row, bits) in glyph(c).into_iter().enumerate() {
+                for glyph_col in (0..GLYPH_WIDTH).filter(|&col| bits & (0b100 >> col) != 0) {
+                    let row = top + glyph_row * SCALE;
+                    let col = left + j * CHAR_ADVANCE + glyph_col * SCALE;
+                    for frame_row in &mut frame_buffer[row..row + SCALE] {
+                        frame_row[col..col + SCALE].fill(line.color);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Draw a horizontal bar in the top-left corner of the frame, filled from the left up to the given
+/// position between 0.0 and 1.0.
+pub(crate) fn draw_scrub_bar(frame_buffer: &mut FrameBuffer, position: f64) {
+    let filled = (position.clamp(0.0, 1.0) * SCRUB_BAR_WIDTH as f64).round() as usize;
+
+    for row in &mut frame_buffer[MARGIN..MARGIN + SCRUB_BAR_HEIGHT + 2 * PADDING] {
+        row[MARGIN..MARGIN + SCRUB_BAR_WIDTH + 2 * PADDING].fill(OSD_BACKGROUND_COLOR);
+    }
+
+    let top = MARGIN + PADDING;
+    let left = MARGIN + PADDING;
+    for row in &mut frame_buffer[top..top + SCRUB_BAR_HEIGHT] {
+        row[left..left + filled].fill(OSD_TEXT_COLOR);
+        row[left + filled..left + SCRUB_BAR_WIDTH].fill(OSD_DIM_TEXT_COLOR);
+    }
+}
@@ -0,0 +1,42 @@
+//! Timer-based frame pacing for variable refresh rate displays.
+//!
+//! With audio sync, emulation speed is set by how quickly the audio device drains its queue, and
+//! frames are shown on the display's fixed refresh cycle. On a VRR display it is smoother to
+//! present every frame as soon as it is emulated and let the display refresh at the NES's own rate,
+//! which requires pacing emulation against a clock instead.
+
+use jgnes_core::TimingMode;
+use jgnes_renderer::config::FrameSkip;
+use std::time::{Duration, Instant};
+
+#[derive(Debug)]
+pub(crate) struct FramePacer {
+    next_frame_time: Option<Instant>,
+}
+
+impl FramePacer {
+    pub(crate) fn new() -> Self {
+        Self { next_frame_time: None }
+    }
+
+    /// Sleep until it is time to start emulating the next frame. While fast-forwarding, every
+    /// emulated frame is paced proportionally faster.
+    pub(crate) fn wait_for_next_frame(&mut self, timing_mode: TimingMode, frame_skip: FrameSkip) {
+        let frame_duration = Duration::from_secs_f64(
+            1.0 / (timing_mode.nes_native_display_rate() * (f64::from(frame_skip.0) + 1.0)),
+        );
+
+        let now = Instant::now();
+        match self.next_frame_time {
+            Some(next_frame_time) if now < next_frame_time => {
+                crate::sleep(next_frame_time - now);
+                self.next_frame_time = Some(next_frame_time + frame_duration);
+            }
+            _ => {
+                // First frame, or running behind (e.g. after a pause or a slow frame); don't try to
+                // catch up by running frames back-to-back
+                self.next_frame_time = Some(now + frame_duration);
+            }
+        }
+    }
+}
@@ -0,0 +1,160 @@
+//! Resolution of where save files, save states, and frontend config files are stored.
+//!
+//! By default, save files and save states are stored next to each ROM and frontends keep their
+//! config files in the working directory. In portable mode, e.g. for running from a USB stick, all
+//! of these are instead stored in a `jgnes` directory next to the executable. Portable mode is
+//! enabled by a frontend's `--portable` flag or by a `portable.txt` file next to the executable.
+//!
+//! Lock files and access tokens that other processes run by the same user need to find are kept in
+//! a per-user directory instead, since the working directory differs between launches.
+
+use std::path::{Path, PathBuf};
+use std::{env, fs, io};
+
+/// Marker file that enables portable mode when placed next to the executable.
+pub const PORTABLE_MARKER_FILE_NAME: &str = "portable.txt";
+
+const PORTABLE_DIR_NAME: &str = "jgnes";
+const SAVES_DIR_NAME: &str = "saves";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DataPaths {
+    portable_dir: Option<PathBuf>,
+}
+
+impl DataPaths {
+    /// Resolve paths for the running executable, enabling portable mode if `portable` is set or
+    /// if the marker file exists. The portable directory is created if it does not exist.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if portable mode is enabled and the portable directory
+    /// cannot be created.
+    pub fn resolve(portable: bool) -> io::Result<Self> {
+        let current_exe = env::current_exe()?;
+        let Some(exe_dir) = current_exe.parent() else {
+            return Ok(Self::default());
+        };
+
+        if !portable && !exe_dir.join(PORTABLE_MARKER_FILE_NAME).is_file() {
+            return Ok(Self::default());
+        }
+
+        let portable_dir = exe_dir.join(PORTABLE_DIR_NAME);
+        fs::create_dir_all(portable_dir.join(SAVES_DIR_NAME))?;
+        log::info!(
+            "Portable mode enabled; storing config and saves in '{}'",
+            portable_dir.display()
+        );
+
+        Ok(Self { portable_dir: Some(portable_dir) })
+    }
+
+    #[must_use]
+    pub fn is_portable(&self) -> bool {
+        self.portable_dir.is_some()
+    }
+
+    /// Path of the frontend config file with the given file name.
+    #[must_use]
+    pub fn config_file(&self, file_name: &str) -> PathBuf {
+        match &self.portable_dir {
+            Some(portable_dir) => portable_dir.join(file_name),
+            None => PathBuf::from(file_name),
+        }
+    }
+
+    /// Directory to store save files and save states in, or `None` to store them next to each ROM.
+    /// This is meant for [`JgnesNativeConfig::save_dir`](crate::JgnesNativeConfig::save_dir).
+    #[must_use]
+    pub fn save_dir(&self) -> Option<PathBuf> {
+        self.portable_dir.as_ref().map(|portable_dir| portable_dir.join(SAVES_DIR_NAME))
+    }
+}
+
+/// Path of a file in a per-user directory (`XDG_RUNTIME_DIR` or the home directory on Unix,
+/// `LOCALAPPDATA` on Windows), or `None` if no such directory can be determined.
+#[must_use]
+pub fn user_runtime_file(file_name: &str) -> Option<PathBuf> {
+    if cfg!(windows) {
+        env::var_os("LOCALAPPDATA").map(|dir| PathBuf::from(dir).join(file_name))
+    } else if let Some(runtime_dir) = env::var_os("XDG_RUNTIME_DIR") {
+        Some(PathBuf::from(runtime_dir).join(file_name))
+    } else {
+        env::var_os("HOME").map(|dir| PathBuf::from(dir).join(format!(".{file_name}")))
+    }
+}
+
+/// Write a file that only the current user can read, e.g. one that contains an access token.
+///
+/// # Errors
+///
+/// This function will return an error if the file cannot be written.
+#[cfg(unix)]
+pub fn write_private_file(path: &Path, contents: &str) -> io::Result<()> {
+    use std::io::Write;
+    use std::os::unix::fs::OpenOptionsExt;
+
+    let mut file =
+        fs::OpenOptions::new().write(true).create(true).truncate(true).mode(0o600).open(path)?;
+    file.write_all(contents.as_bytes())
+}
+
+/// Write a file that only the current user can read, e.g. one that contains an access token.
+///
+/// # Errors
+///
+/// This function will return an error if the file cannot be written.
+#[cfg(not(unix))]
+pub fn write_private_file(path: &Path, contents: &str) -> io::Result<()> {
+    fs::write(path, contents)
+}
+
+/// Path of a ROM's save file or save state with the given extension, either next to the ROM or
+/// in the save directory if one is set.
+pub(crate) fn save_file_path(
+    nes_file_path: &Path,
+    save_dir: Option<&Path>,
+    extension: &str,
+) -> PathBuf {
+    let path = nes_file_path.with_extension(extension);
+    match (save_dir, path.file_name()) {
+        (Some(save_dir), Some(file_name)) => save_dir.join(file_name),
+        _ => path,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn portable_paths_are_next_to_executable() {
+        let exe_dir = Path::new("usb").join("emulators");
+        let data_paths = DataPaths { portable_dir: Some(exe_dir.join("jgnes")) };
+
+        assert_eq!(
+            data_paths.config_file("jgnes-config.toml"),
+            exe_dir.join("jgnes").join("jgnes-config.toml")
+        );
+
+        let save_dir = data_paths.save_dir().unwrap();
+        assert_eq!(save_dir, exe_dir.join("jgnes").join("saves"));
+        assert_eq!(
+            save_file_path(&Path::new("roms").join("game.nes"), Some(&save_dir), "sav"),
+            save_dir.join("game.sav")
+        );
+    }
+
+    #[test]
+    fn default_paths_are_unchanged() {
+        let data_paths = DataPaths::default();
+
+        assert_eq!(data_paths.config_file("jgnes-config.toml"), Path::new("jgnes-config.toml"));
+        assert_eq!(data_paths.save_dir(), None);
+        assert_eq!(
+            save_file_path(&Path::new("roms").join("game.nes"), None, "ss1"),
+            Path::new("roms").join("game.ss1")
+        );
+    }
+}
@@ -0,0 +1,146 @@
+//! Multi-ROM sessions where the user can switch between games at runtime.
+//!
+//! Switching games swaps the cartridge in the running emulator rather than creating a new window.
+//! Each game's SRAM is persisted to its own `.sav` file as usual, and the state of the game being
+//! switched away from is kept in memory so that switching back resumes where it left off.
+
+use crate::paths;
+use crate::{JgnesNativeConfig, RomLoadRequest};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PlaylistDirection {
+    Next,
+    Previous,
+}
+
+#[derive(Debug)]
+pub(crate) struct PlaylistEntry {
+    pub(crate) nes_file_path: PathBuf,
+    pub(crate) patch_file_path: Option<PathBuf>,
+    pub(crate) forced_mapper_number: Option<u16>,
+    // Serialized save state from when the user last switched away from this game
+    pub(crate) suspended_state: Option<Vec<u8>>,
+    // Whether the user has already been warned if this game's timing mode does not match its header
+    pub(crate) timing_mode_checked: bool,
+}
+
+impl PlaylistEntry {
+    pub(crate) fn sav_path(&self, save_dir: Option<&Path>) -> PathBuf {
+        paths::save_file_path(&self.nes_file_path, save_dir, "sav")
+    }
+
+    pub(crate) fn save_state_path(&self, save_dir: Option<&Path>, slot: u8) -> PathBuf {
+        paths::save_file_path(&self.nes_file_path, save_dir, &format!("ss{slot}"))
+    }
+
+    pub(crate) fn file_name(&self) -> String {
+        self.nes_file_path.file_name().map_or_else(
+            || self.nes_file_path.to_string_lossy().to_string(),
+            |file_name| file_name.to_string_lossy().to_string(),
+        )
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct Playlist {
+    entries: Vec<PlaylistEntry>,
+    current: usize,
+    save_dir: Option<PathBuf>,
+}
+
+impl Playlist {
+    pub(crate) fn from_config(config: &JgnesNativeConfig) -> Self {
+        // The explicit patch and forced mapper only apply to the initially loaded ROM
+        let first = PlaylistEntry {
+            nes_file_path: PathBuf::from(&config.nes_file_path),
+            patch_file_path: config.patch_file_path.as_ref().map(PathBuf::from),
+            forced_mapper_number: config.forced_mapper_number,
+            suspended_state: None,
+            timing_mode_checked: false,
+        };
+        let rest = config.playlist.iter().map(|nes_file_path| PlaylistEntry {
+            nes_file_path: PathBuf::from(nes_file_path),
+            patch_file_path: None,
+            forced_mapper_number: None,
+            suspended_state: None,
+            timing_mode_checked: false,
+        });
+
+        Self {
+            entries: [first].into_iter().chain(rest).collect(),
+            current: 0,
+            save_dir: config.save_dir.clone(),
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub(crate) fn current(&self) -> &PlaylistEntry {
+        &self.entries[self.current]
+    }
+
+    pub(crate) fn current_index(&self) -> usize {
+        self.current
+    }
+
+    pub(crate) fn entry_mut(&mut self, index: usize) -> &mut PlaylistEntry {
+        &mut self.entries[index]
+    }
+
+    /// Index of the entry in the given direction from the current entry, wrapping around at
+    /// either end of the playlist.
+    pub(crate) fn neighbor_index(&self, direction: PlaylistDirection) -> usize {
+        match direction {
+            PlaylistDirection::Next => (self.current + 1) % self.entries.len(),
+            PlaylistDirection::Previous => {
+                (self.current + self.entries.len() - 1) % self.entries.len()
+            }
+        }
+    }
+
+    /// Index of the entry for the requested ROM, adding it to the end of the playlist if it is not
+    /// already present.
+    pub(crate) fn find_or_push(&mut self, request: RomLoadRequest) -> usize {
+        let nes_file_path = PathBuf::from(request.nes_file_path);
+        let patch_file_path = request.patch_file_path.map(PathBuf::from);
+
+        if let Some(index) =
+            self.entries.iter().position(|entry| entry.nes_file_path == nes_file_path)
+        {
+            let entry = &mut self.entries[index];
+            if entry.patch_file_path != patch_file_path
+                || entry.forced_mapper_number != request.forced_mapper_number
+            {
+                // A suspended state for a differently patched ROM cannot be resumed
+                entry.patch_file_path = patch_file_path;
+                entry.forced_mapper_number = request.forced_mapper_number;
+                entry.suspended_state = None;
+            }
+            return index;
+        }
+
+        self.entries.push(PlaylistEntry {
+            nes_file_path,
+            patch_file_path,
+            forced_mapper_number: request.forced_mapper_number,
+            suspended_state: None,
+            timing_mode_checked: false,
+        });
+        self.entries.len() - 1
+    }
+
+    pub(crate) fn set_current(&mut self, index: usize) {
+        self.current = index;
+    }
+
+    pub(crate) fn nes_file_path(&self) -> &Path {
+        &self.current().nes_file_path
+    }
+
+    pub(crate) fn save_dir(&self) -> Option<&Path> {
+        self.save_dir.as_deref()
+    }
+}
@@ -0,0 +1,47 @@
+//! Low power mode for playing on laptops without spinning up the fans.
+//!
+//! While low power mode is active, the wgpu renderer requests the low-power GPU, VSync is used to
+//! pace frames whenever emulation is not paced by a timer, and audio sync polls the audio queue
+//! less often. Battery status is checked when the emulator starts and whenever the config is
+//! reloaded.
+
+use jgnes_proc_macros::{EnumDisplay, EnumFromStr};
+use sdl2::sys::{SDL_GetPowerInfo, SDL_PowerState};
+use serde::{Deserialize, Serialize};
+use std::ptr;
+use std::time::Duration;
+
+const AUDIO_SYNC_POLL_INTERVAL: Duration = Duration::from_micros(250);
+const LOW_POWER_AUDIO_SYNC_POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, EnumDisplay, EnumFromStr,
+)]
+pub enum LowPowerMode {
+    #[default]
+    Off,
+    /// Only while the system is running on battery power
+    OnBattery,
+    Always,
+}
+
+impl LowPowerMode {
+    pub(crate) fn is_active(self) -> bool {
+        match self {
+            Self::Off => false,
+            Self::OnBattery => on_battery_power(),
+            Self::Always => true,
+        }
+    }
+}
+
+fn on_battery_power() -> bool {
+    // SAFETY: SDL_GetPowerInfo accepts null pointers for the outputs that are not needed, and it
+    // can be called without initializing any SDL subsystems
+    let state = unsafe { SDL_GetPowerInfo(ptr::null_mut(), ptr::null_mut()) };
+    state == SDL_PowerState::SDL_POWERSTATE_ON_BATTERY
+}
+
+pub(crate) fn audio_sync_poll_interval(low_power: bool) -> Duration {
+    if low_power { LOW_POWER_AUDIO_SYNC_POLL_INTERVAL } else { AUDIO_SYNC_POLL_INTERVAL }
+}
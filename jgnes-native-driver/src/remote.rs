@@ -0,0 +1,348 @@
+//! Optional localhost TCP server for controlling a running emulator from scripts and external
+//! tools.
+//!
+//! When the server starts, it writes a random token to `jgnes-remote-control-<port>.token` in a
+//! per-user directory (`$XDG_RUNTIME_DIR`, `~/.jgnes-remote-control-<port>.token` if that is not
+//! set, or `%LOCALAPPDATA%` on Windows) that only the current user can read. The first line sent on
+//! every connection must be that token; connections that send anything else are closed without a
+//! response. The token file is deleted when the server shuts down.
+//!
+//! After the token, the protocol is newline-delimited JSON. Each request is an object with a
+//! `command` field, and every request line gets exactly one response line:
+//!
+//! ```text
+//! {"command": "pause"}                  -> {"ok":true}
+//! {"command": "save_state", "slot": 1}  -> {"ok":true}
+//! {"command": "load_rom", "path": "..."} -> {"ok":true}
+//! {"command": "status"}                 -> {"ok":true,"status":{"running":true,...}}
+//! {"command": "set_log_level", "subsystem": "ppu", "level": "debug"} -> {"ok":true}
+//! {"command": "bogus"}                  -> {"ok":false,"error":"..."}
+//! ```
+//!
+//! Commands other than `status` and `set_log_level` are queued for the emulator's run loop, so a
+//! successful response means that the command was accepted, not that it has finished.
+//!
+//! A line that is not a valid request gets an error response and then the connection is closed, and
+//! connections that start like an HTTP request are closed without a response. Lines longer than
+//! 64KB also close the connection.
+
+use crate::config::{EmulatorCommand, EmulatorStatus, JgnesSharedConfig, RomLoadRequest};
+use crate::logging;
+use crate::paths::{user_runtime_file, write_private_file};
+use jgnes_core::logging::LogSubsystem;
+use log::LevelFilter;
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::thread::JoinHandle;
+use std::time::Duration;
+use std::{fs, io};
+
+// How often blocked threads check whether the server has been shut down
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+// Generous limit on a single request line; without one, a client that never sends a newline could
+// make the server buffer an unbounded amount of data
+const MAX_LINE_LEN: usize = 64 * 1024;
+
+const HTTP_METHODS: &[&str] =
+    &["GET", "HEAD", "POST", "PUT", "DELETE", "CONNECT", "OPTIONS", "TRACE", "PATCH"];
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+enum RemoteRequest {
+    Status,
+    Pause,
+    Resume,
+    SaveState {
+        #[serde(default)]
+        slot: u8,
+    },
+    LoadState {
+        #[serde(default)]
+        slot: u8,
+    },
+    Screenshot,
+    LoadRom {
+        path: String,
+        #[serde(default)]
+        patch_path: Option<String>,
+    },
+    SoftReset,
+    HardReset,
+    Quit,
+    SetLogLevel {
+        subsystem: String,
+        level: String,
+    },
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct RemoteResponse {
+    ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    status: Option<EmulatorStatus>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl RemoteResponse {
+    fn ok() -> Self {
+        Self { ok: true, status: None, error: None }
+    }
+
+    fn status(status: EmulatorStatus) -> Self {
+        Self { ok: true, status: Some(status), error: None }
+    }
+
+    fn error(error: String) -> Self {
+        Self { ok: false, status: None, error: Some(error) }
+    }
+}
+
+/// Remote control server; the listening socket is closed when this is dropped.
+pub(crate) struct RemoteControlServer {
+    shutdown: Arc<AtomicBool>,
+    accept_thread: Option<JoinHandle<()>>,
+    token_path: PathBuf,
+}
+
+impl RemoteControlServer {
+    pub(crate) fn start(port: u16, shared_config: JgnesSharedConfig) -> io::Result<Self> {
+        let token_path = user_runtime_file(&format!("jgnes-remote-control-{port}.token"))
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    "unable to determine where to write the remote control token",
+                )
+            })?;
+
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))?;
+        listener.set_nonblocking(true)?;
+
+        let token: Arc<str> = format!("{:032x}", rand::random::<u128>()).into();
+        write_private_file(&token_path, &format!("{token}\n"))?;
+
+        log::info!(
+            "Listening for remote control connections on {}, token written to '{}'",
+            listener.local_addr()?,
+            token_path.display()
+        );
+
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let accept_thread = thread::spawn({
+            let shutdown = Arc::clone(&shutdown);
+            move || accept_connections(&listener, &shared_config, &token, &shutdown)
+        });
+
+        Ok(Self { shutdown, accept_thread: Some(accept_thread), token_path })
+    }
+}
+
+impl Drop for RemoteControlServer {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::Relaxed);
+        if let Some(accept_thread) = self.accept_thread.take() {
+            let _ = accept_thread.join();
+        }
+
+        if let Err(err) = fs::remove_file(&self.token_path) {
+            log::warn!(
+                "Unable to remove remote control token file '{}': {err}",
+                self.token_path.display()
+            );
+        }
+    }
+}
+
+fn accept_connections(
+    listener: &TcpListener,
+    shared_config: &JgnesSharedConfig,
+    token: &Arc<str>,
+    shutdown: &Arc<AtomicBool>,
+) {
+    while !shutdown.load(Ordering::Relaxed) {
+        match listener.accept() {
+            Ok((stream, addr)) => {
+                log::info!("Accepted remote control connection from {addr}");
+
+                let shared_config = shared_config.clone();
+                let token = Arc::clone(token);
+                let shutdown = Arc::clone(shutdown);
+                thread::spawn(move || {
+                    if let Err(err) = handle_connection(stream, &shared_config, &token, &shutdown) {
+                        log::warn!("Remote control connection from {addr} failed: {err}");
+                    }
+                });
+            }
+            Err(err) if err.kind() == io::ErrorKind::WouldBlock => {
+                thread::sleep(POLL_INTERVAL);
+            }
+            Err(err) => {
+                log::error!("Error accepting remote control connection: {err}");
+                return;
+            }
+        }
+    }
+}
+
+fn handle_connection(
+    stream: TcpStream,
+    shared_config: &JgnesSharedConfig,
+    token: &str,
+    shutdown: &AtomicBool,
+) -> io::Result<()> {
+    // Accepted sockets inherit non-blocking mode from the listener on some platforms
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(POLL_INTERVAL))?;
+
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut writer = stream;
+    let mut line = String::new();
+    let mut authenticated = false;
+    while !shutdown.load(Ordering::Relaxed) {
+        match read_line_bounded(&mut reader, &mut line) {
+            Ok(0) => return Ok(()),
+            Ok(_) => {
+                let request_line = line.trim();
+                if is_http_request_line(request_line) {
+                    log::warn!("Closing remote control connection that sent an HTTP request");
+                    return Ok(());
+                }
+
+                if !authenticated {
+                    if request_line != token {
+                        log::warn!("Closing remote control connection that sent the wrong token");
+                        return Ok(());
+                    }
+                    authenticated = true;
+                } else if !request_line.is_empty() {
+                    // Nothing after a line that is not a request is trusted to be a request either
+                    let request = match serde_json::from_str::<RemoteRequest>(request_line) {
+                        Ok(request) => request,
+                        Err(err) => {
+                            let response = RemoteResponse::error(format!("invalid request: {err}"));
+                            write_response(&mut writer, &response)?;
+                            return Ok(());
+                        }
+                    };
+
+                    let response = handle_request(request, shared_config);
+                    write_response(&mut writer, &response)?;
+                }
+                line.clear();
+            }
+            // A partially read line stays in the buffer until the rest of it arrives
+            Err(err)
+                if matches!(err.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) => {}
+            Err(err) => return Err(err),
+        }
+    }
+
+    Ok(())
+}
+
+// Like read_line, but fails instead of buffering more than MAX_LINE_LEN bytes of a single line.
+// `line` may already hold the start of a line that was interrupted by a read timeout
+fn read_line_bounded<R: BufRead>(reader: &mut R, line: &mut String) -> io::Result<usize> {
+    let remaining = MAX_LINE_LEN.saturating_sub(line.len());
+    let bytes_read = reader.by_ref().take(remaining as u64).read_line(line)?;
+    if line.len() >= MAX_LINE_LEN && !line.ends_with('\n') {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "request line is too long"));
+    }
+
+    Ok(bytes_read)
+}
+
+fn is_http_request_line(line: &str) -> bool {
+    line.split_once(' ').is_some_and(|(method, _)| HTTP_METHODS.contains(&method))
+}
+
+fn write_response(writer: &mut TcpStream, response: &RemoteResponse) -> io::Result<()> {
+    serde_json::to_writer(&mut *writer, response)?;
+    writer.write_all(b"\n")
+}
+
+fn handle_request(request: RemoteRequest, shared_config: &JgnesSharedConfig) -> RemoteResponse {
+    let command = match request {
+        RemoteRequest::Status => return RemoteResponse::status(shared_config.status()),
+        RemoteRequest::SetLogLevel { subsystem, level } => {
+            return match (LogSubsystem::from_str(&subsystem), LevelFilter::from_str(&level)) {
+                (Ok(subsystem), Ok(level)) => {
+                    logging::set_subsystem_log_level(subsystem, level);
+                    RemoteResponse::ok()
+                }
+                (Err(_), _) => RemoteResponse::error(format!("invalid subsystem: {subsystem}")),
+                (_, Err(_)) => RemoteResponse::error(format!("invalid log level: {level}")),
+            };
+        }
+        RemoteRequest::Pause => EmulatorCommand::SetPaused(true),
+        RemoteRequest::Resume => EmulatorCommand::SetPaused(false),
+        RemoteRequest::SaveState { slot } => EmulatorCommand::SaveState { slot },
+        RemoteRequest::LoadState { slot } => EmulatorCommand::LoadState { slot },
+        RemoteRequest::Screenshot => EmulatorCommand::Screenshot,
+        RemoteRequest::LoadRom { path, patch_path } => EmulatorCommand::LoadRom(RomLoadRequest {
+            nes_file_path: path,
+            patch_file_path: patch_path,
+            forced_mapper_number: None,
+        }),
+        RemoteRequest::SoftReset => EmulatorCommand::SoftReset,
+        RemoteRequest::HardReset => EmulatorCommand::HardReset,
+        RemoteRequest::Quit => EmulatorCommand::Quit,
+    };
+    shared_config.send_command(command);
+
+    RemoteResponse::ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_requests() {
+        assert_eq!(
+            serde_json::from_str::<RemoteRequest>(r#"{"command": "save_state"}"#).unwrap(),
+            RemoteRequest::SaveState { slot: 0 }
+        );
+        assert_eq!(
+            serde_json::from_str::<RemoteRequest>(r#"{"command": "load_rom", "path": "a.nes"}"#)
+                .unwrap(),
+            RemoteRequest::LoadRom { path: "a.nes".into(), patch_path: None }
+        );
+        assert_eq!(
+            serde_json::from_str::<RemoteRequest>(
+                r#"{"command": "set_log_level", "subsystem": "ppu", "level": "debug"}"#
+            )
+            .unwrap(),
+            RemoteRequest::SetLogLevel { subsystem: "ppu".into(), level: "debug".into() }
+        );
+        assert!(serde_json::from_str::<RemoteRequest>(r#"{"command": "bogus"}"#).is_err());
+    }
+
+    #[test]
+    fn detect_http_requests() {
+        assert!(is_http_request_line("POST / HTTP/1.1"));
+        assert!(is_http_request_line("GET /status HTTP/1.0"));
+        assert!(!is_http_request_line(r#"{"command": "pause"}"#));
+        assert!(!is_http_request_line("POST"));
+    }
+
+    #[test]
+    fn line_length_is_bounded() {
+        let mut line = String::new();
+        let mut reader = io::Cursor::new(format!("token\n{}\n", "a".repeat(MAX_LINE_LEN)));
+        assert_eq!(read_line_bounded(&mut reader, &mut line).unwrap(), 6);
+        assert_eq!(line, "token\n");
+
+        line.clear();
+        let err = read_line_bounded(&mut reader, &mut line).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(line.len(), MAX_LINE_LEN);
+    }
+}
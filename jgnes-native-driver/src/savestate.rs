@@ -0,0 +1,100 @@
+//! Save state file I/O on a worker thread.
+//!
+//! States are serialized to and deserialized from memory on the emulation thread, which is fast,
+//! while the comparatively slow file reads and writes happen in the background so that they do not
+//! cause a visible hitch. Results are picked up by polling for completions.
+
+use crate::savewriter;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::sync::mpsc::{Receiver, Sender};
+use std::thread;
+use std::thread::JoinHandle;
+
+enum SaveStateRequest {
+    Write { path: PathBuf, state: Vec<u8> },
+    Read { path: PathBuf, playlist_index: usize },
+}
+
+pub(crate) enum SaveStateCompletion {
+    Written {
+        path: PathBuf,
+        result: io::Result<()>,
+    },
+    Read {
+        path: PathBuf,
+        // The game that the state was requested for, in case the game was switched in the meantime
+        playlist_index: usize,
+        result: io::Result<Vec<u8>>,
+    },
+}
+
+/// Background save state reader/writer. Dropping the worker blocks until all requested writes
+/// have finished.
+pub(crate) struct SaveStateWorker {
+    request_sender: Option<Sender<SaveStateRequest>>,
+    completion_receiver: Receiver<SaveStateCompletion>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl SaveStateWorker {
+    pub(crate) fn new() -> Self {
+        let (request_sender, request_receiver) = mpsc::channel();
+        let (completion_sender, completion_receiver) = mpsc::channel();
+        let thread = thread::spawn(move || process_requests(&request_receiver, &completion_sender));
+
+        Self { request_sender: Some(request_sender), completion_receiver, thread: Some(thread) }
+    }
+
+    pub(crate) fn write(&self, path: PathBuf, state: Vec<u8>) {
+        self.send(SaveStateRequest::Write { path, state });
+    }
+
+    pub(crate) fn read(&self, path: PathBuf, playlist_index: usize) {
+        self.send(SaveStateRequest::Read { path, playlist_index });
+    }
+
+    fn send(&self, request: SaveStateRequest) {
+        if let Some(request_sender) = &self.request_sender {
+            // The worker thread only exits after the sender is dropped
+            let _ = request_sender.send(request);
+        }
+    }
+
+    pub(crate) fn completions(&self) -> impl Iterator<Item = SaveStateCompletion> + '_ {
+        self.completion_receiver.try_iter()
+    }
+}
+
+impl Drop for SaveStateWorker {
+    fn drop(&mut self) {
+        // Closing the channel stops the worker thread once it has processed every request
+        self.request_sender = None;
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn process_requests(
+    request_receiver: &Receiver<SaveStateRequest>,
+    completion_sender: &Sender<SaveStateCompletion>,
+) {
+    for request in request_receiver {
+        let completion = match request {
+            SaveStateRequest::Write { path, state } => {
+                let result = savewriter::write_atomic(&path, &state);
+                SaveStateCompletion::Written { path, result }
+            }
+            SaveStateRequest::Read { path, playlist_index } => {
+                let result = fs::read(&path);
+                SaveStateCompletion::Read { path, playlist_index, result }
+            }
+        };
+
+        // Completions are dropped if the emulator has already stopped
+        let _ = completion_sender.send(completion);
+    }
+}
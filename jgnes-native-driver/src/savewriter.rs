@@ -0,0 +1,231 @@
+//! Battery save persistence on a background thread, so that slow disks or network shares do not
+//! cause frame hitches in the emulation loop.
+//!
+//! Writes are coalesced: if the emulator persists SRAM again before the previous write has
+//! started, only the latest image is written.
+//!
+//! A failed write keeps its image and is retried periodically until it succeeds or a newer image
+//! replaces it. Failures are reported on the emulator's next frame, and dropping the writer makes a
+//! few final attempts before giving up.
+
+use jgnes_core::{ClassifyError, ErrorSeverity, SaveWriter};
+use std::fs;
+use std::io;
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::thread::JoinHandle;
+use std::time::Duration;
+use thiserror::Error;
+
+const RETRY_INTERVAL: Duration = Duration::from_secs(1);
+const SHUTDOWN_WRITE_ATTEMPTS: u32 = 3;
+const SHUTDOWN_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Error)]
+#[error("Error writing save file: {source}")]
+pub(crate) struct SaveWriteError {
+    #[from]
+    source: io::Error,
+}
+
+impl ClassifyError for SaveWriteError {
+    fn severity(&self) -> ErrorSeverity {
+        // The writer thread keeps the image and retries the write
+        ErrorSeverity::Recoverable
+    }
+}
+
+#[derive(Debug, Default)]
+struct WriterState {
+    pending: Option<Vec<u8>>,
+    // Error from the most recent background write, reported on the emulator's next frame
+    error: Option<io::Error>,
+    shutdown: bool,
+}
+
+#[derive(Debug, Default)]
+struct SharedState {
+    state: Mutex<WriterState>,
+    condvar: Condvar,
+}
+
+/// Writes SRAM to a `.sav` file. Dropping the writer blocks until any pending or failed write has
+/// been written or has failed its final attempts.
+#[derive(Debug)]
+pub(crate) struct FsSaveWriter {
+    shared: Arc<SharedState>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl FsSaveWriter {
+    pub(crate) fn new(path: PathBuf) -> Self {
+        let shared = Arc::new(SharedState::default());
+        let thread = thread::spawn({
+            let shared = Arc::clone(&shared);
+            move || write_loop(&path, &shared)
+        });
+
+        Self { shared, thread: Some(thread) }
+    }
+}
+
+impl SaveWriter for FsSaveWriter {
+    type Err = SaveWriteError;
+
+    #[inline]
+    fn persist_sram(&mut self, sram: &[u8]) -> Result<(), Self::Err> {
+        let mut state = self.shared.state.lock().unwrap();
+        state.pending = Some(sram.to_vec());
+        let error = state.error.take();
+        drop(state);
+
+        self.shared.condvar.notify_one();
+
+        match error {
+            Some(err) => Err(err.into()),
+            None => Ok(()),
+        }
+    }
+
+    #[inline]
+    fn poll_error(&mut self) -> Result<(), Self::Err> {
+        match self.shared.state.lock().unwrap().error.take() {
+            Some(err) => Err(err.into()),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for FsSaveWriter {
+    fn drop(&mut self) {
+        self.shared.state.lock().unwrap().shutdown = true;
+        self.shared.condvar.notify_one();
+
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn write_loop(path: &Path, shared: &SharedState) {
+    // Image whose write failed, retried until it is written or a newer image replaces it
+    let mut failed_sram: Option<Vec<u8>> = None;
+    let mut failing = false;
+
+    loop {
+        let (sram, shutdown) = {
+            let state = shared.state.lock().unwrap();
+            let idle = |state: &mut WriterState| state.pending.is_none() && !state.shutdown;
+            let mut state = if failed_sram.is_some() {
+                shared.condvar.wait_timeout_while(state, RETRY_INTERVAL, idle).unwrap().0
+            } else {
+                shared.condvar.wait_while(state, idle).unwrap()
+            };
+
+            // A newer image supersedes one that failed to write, and pending and failed writes are
+            // always attempted again before shutting down
+            let failed_sram = failed_sram.take();
+            match state.pending.take().or(failed_sram) {
+                Some(sram) => (sram, state.shutdown),
+                None => return,
+            }
+        };
+
+        let mut result = write_atomic(path, &sram);
+        if shutdown {
+            // Last chance to write this image
+            for _ in 1..SHUTDOWN_WRITE_ATTEMPTS {
+                if result.is_ok() {
+                    break;
+                }
+                thread::sleep(SHUTDOWN_RETRY_DELAY);
+                result = write_atomic(path, &sram);
+            }
+        }
+
+        match result {
+            Ok(()) => {
+                if mem::take(&mut failing) {
+                    log::info!("Save file '{}' written after an earlier failure", path.display());
+                }
+            }
+            Err(err) if shutdown => {
+                log::error!(
+                    "Error writing save file '{}'; the latest save data was lost: {err}",
+                    path.display()
+                );
+                return;
+            }
+            Err(err) => {
+                // Retries fail once per interval, so only log the first failure in a row
+                if !mem::replace(&mut failing, true) {
+                    log::error!("Error writing save file '{}', will retry: {err}", path.display());
+                }
+                failed_sram = Some(sram);
+                shared.state.lock().unwrap().error = Some(err);
+            }
+        }
+    }
+}
+
+// Write to a temp file and then rename so that a crash mid-write cannot corrupt the existing file.
+// The temp file name keeps the original extension so that writes to a game's .sav file and its
+// save state files cannot collide.
+pub(crate) fn write_atomic(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    let mut tmp_path = path.as_os_str().to_owned();
+    tmp_path.push(".tmp");
+    fs::write(&tmp_path, bytes)?;
+    fs::rename(tmp_path, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    #[test]
+    fn latest_write_is_persisted_on_drop() {
+        let path =
+            std::env::temp_dir().join(format!("jgnes-savewriter-{}.sav", std::process::id()));
+
+        let mut writer = FsSaveWriter::new(path.clone());
+        writer.persist_sram(&[1, 2, 3]).unwrap();
+        writer.persist_sram(&[4, 5, 6]).unwrap();
+        drop(writer);
+
+        assert_eq!(fs::read(&path).unwrap(), [4, 5, 6]);
+        assert!(!path.with_extension("sav.tmp").exists());
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn failed_write_is_reported_and_retried() {
+        let dir =
+            std::env::temp_dir().join(format!("jgnes-savewriter-retry-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let path = dir.join("game.sav");
+        let deadline = Instant::now() + Duration::from_secs(10);
+
+        // The directory does not exist yet, so the write fails, and the failure is reported
+        // without another persist call
+        let mut writer = FsSaveWriter::new(path.clone());
+        writer.persist_sram(&[1, 2, 3]).unwrap();
+        while writer.poll_error().is_ok() {
+            assert!(Instant::now() < deadline, "write failure was never reported");
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        fs::create_dir(&dir).unwrap();
+        while !path.exists() {
+            assert!(Instant::now() < deadline, "failed write was never retried");
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(fs::read(&path).unwrap(), [1, 2, 3]);
+
+        drop(writer);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,170 @@
+//! Built-in real time speedrun timer, controlled by start/split and reset hotkeys and shown on the
+//! on-screen display.
+//!
+//! The timer does not know how many segments a run has, so a run ends when the timer is reset; the
+//! final split marks the end of the run. Each run is saved to a JSON file when it ends so that the
+//! last run's times are not lost. Timer events can also be forwarded to LiveSplit Server.
+
+use crate::livesplit::{LiveSplitClient, LiveSplitCommand};
+use crate::osd::{OSD_DIM_TEXT_COLOR, OSD_TEXT_COLOR, OsdLine};
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Serialize)]
+struct SavedRun {
+    rom_file_name: String,
+    splits_ms: Vec<u64>,
+    elapsed_ms: u64,
+}
+
+pub(crate) struct SpeedrunTimer {
+    start: Option<Instant>,
+    rom_file_name: String,
+    splits: Vec<Duration>,
+    last_run_path: Option<PathBuf>,
+    auto_start: bool,
+    livesplit_client: Option<LiveSplitClient>,
+}
+
+impl SpeedrunTimer {
+    pub(crate) fn new(
+        last_run_path: Option<PathBuf>,
+        auto_start: bool,
+        livesplit_client: Option<LiveSplitClient>,
+    ) -> Self {
+        Self {
+            start: None,
+            rom_file_name: String::new(),
+            splits: Vec::new(),
+            last_run_path,
+            auto_start,
+            livesplit_client,
+        }
+    }
+
+    /// Start the timer if it is stopped, otherwise record a split.
+    pub(crate) fn start_or_split(&mut self, rom_file_name: &str, now: Instant) {
+        match self.start {
+            Some(start) => {
+                let split = now - start;
+                log::info!(
+                    "Speedrun timer split {}: {}",
+                    self.splits.len() + 1,
+                    format_time(split)
+                );
+                self.splits.push(split);
+                self.send_to_livesplit(LiveSplitCommand::Split);
+            }
+            None => {
+                log::info!("Speedrun timer started");
+                self.start = Some(now);
+                rom_file_name.clone_into(&mut self.rom_file_name);
+                self.send_to_livesplit(LiveSplitCommand::StartTimer);
+            }
+        }
+    }
+
+    /// Stop the timer and save the run if the timer was running.
+    pub(crate) fn reset(&mut self, now: Instant) {
+        let Some(start) = self.start.take() else {
+            return;
+        };
+
+        let elapsed = now - start;
+        log::info!("Speedrun timer reset at {}", format_time(elapsed));
+        self.send_to_livesplit(LiveSplitCommand::Reset);
+
+        let run = SavedRun {
+            rom_file_name: std::mem::take(&mut self.rom_file_name),
+            splits_ms: self.splits.drain(..).map(|split| split.as_millis() as u64).collect(),
+            elapsed_ms: elapsed.as_millis() as u64,
+        };
+        if let Some(last_run_path) = &self.last_run_path {
+            if let Err(err) = save_run(&run, last_run_path) {
+                log::error!("Unable to save speedrun to '{}': {err}", last_run_path.display());
+            }
+        }
+    }
+
+    /// Restart the timer if it is configured to start automatically when the console is reset.
+    pub(crate) fn console_reset(&mut self, rom_file_name: &str, now: Instant) {
+        if self.auto_start {
+            self.reset(now);
+            self.start_or_split(rom_file_name, now);
+        }
+    }
+
+    fn send_to_livesplit(&self, command: LiveSplitCommand) {
+        if let Some(livesplit_client) = &self.livesplit_client {
+            livesplit_client.send(command);
+        }
+    }
+
+    /// The current time and the most recent split, or nothing if the timer is stopped.
+    pub(crate) fn osd_lines(&self, now: Instant) -> Vec<OsdLine> {
+        let Some(start) = self.start else {
+            return Vec::new();
+        };
+
+        let mut lines = vec![OsdLine { text: format_time(now - start), color: OSD_TEXT_COLOR }];
+        if let Some(&split) = self.splits.last() {
+            lines.push(OsdLine { text: format_time(split), color: OSD_DIM_TEXT_COLOR });
+        }
+        lines
+    }
+}
+
+// Save a run in progress if the emulator exits
+impl Drop for SpeedrunTimer {
+    fn drop(&mut self) {
+        self.reset(Instant::now());
+    }
+}
+
+fn save_run(run: &SavedRun, path: &Path) -> anyhow::Result<()> {
+    fs::write(path, serde_json::to_string_pretty(run)?)?;
+    Ok(())
+}
+
+pub(crate) fn format_time(duration: Duration) -> String {
+    let centis = duration.as_millis() / 10;
+    let (hours, minutes, seconds, centis) =
+        (centis / 360_000, centis / 6000 % 60, centis / 100 % 60, centis % 100);
+
+    if hours > 0 {
+        format!("{hours}:{minutes:02}:{seconds:02}.{centis:02}")
+    } else {
+        format!("{minutes}:{seconds:02}.{centis:02}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn time_formatting() {
+        assert_eq!(format_time(Duration::ZERO), "0:00.00");
+        assert_eq!(format_time(Duration::from_millis(61_239)), "1:01.23");
+        assert_eq!(format_time(Duration::from_secs(3 * 3600 + 5)), "3:00:05.00");
+    }
+
+    #[test]
+    fn splits_are_shown_until_reset() {
+        let start = Instant::now();
+        let mut timer = SpeedrunTimer::new(None, false, None);
+        assert!(timer.osd_lines(start).is_empty());
+
+        timer.start_or_split("game.nes", start);
+        timer.start_or_split("game.nes", start + Duration::from_secs(10));
+
+        let lines = timer.osd_lines(start + Duration::from_secs(15));
+        let texts: Vec<_> = lines.iter().map(|line| line.text.as_str()).collect();
+        assert_eq!(texts, ["0:15.00", "0:10.00"]);
+
+        timer.reset(start + Duration::from_secs(20));
+        assert!(timer.osd_lines(start + Duration::from_secs(20)).is_empty());
+    }
+}
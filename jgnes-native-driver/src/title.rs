@@ -0,0 +1,38 @@
+//! Temporary status messages in the window title. The on-screen display is reserved for overlays
+//! such as the speedrun timer, so this is where the results of background operations such as save
+//! state writes are shown.
+
+use sdl2::video::Window;
+use std::time::{Duration, Instant};
+
+const MESSAGE_DURATION: Duration = Duration::from_secs(3);
+
+pub(crate) fn window_title(file_name: &str) -> String {
+    format!("jgnes - {file_name}")
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct TitleMessage {
+    expiration: Option<Instant>,
+}
+
+impl TitleMessage {
+    pub(crate) fn show(&mut self, window: &mut Window, file_name: &str, message: &str) {
+        set_title(window, &format!("{} - {message}", window_title(file_name)));
+        self.expiration = Some(Instant::now() + MESSAGE_DURATION);
+    }
+
+    /// Restore the normal window title if the current message has expired.
+    pub(crate) fn update(&mut self, window: &mut Window, file_name: &str) {
+        if self.expiration.is_some_and(|expiration| Instant::now() >= expiration) {
+            self.expiration = None;
+            set_title(window, &window_title(file_name));
+        }
+    }
+}
+
+fn set_title(window: &mut Window, title: &str) {
+    if let Err(err) = window.set_title(title) {
+        log::warn!("Unable to set window title: {err}");
+    }
+}
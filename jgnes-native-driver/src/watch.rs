@@ -0,0 +1,108 @@
+//! ROM file watching for homebrew development.
+//!
+//! When enabled, the running game is reloaded from disk whenever its ROM file changes, so that a
+//! freshly assembled ROM can be tested without restarting the emulator. The directory containing
+//! the ROM is watched rather than the file itself because many assemblers and editors replace files
+//! by renaming a temporary file over them, which ends a watch on the original file.
+
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::sync::mpsc::Receiver;
+use std::time::{Duration, Instant};
+
+// Builds usually write the ROM file in several chunks; wait for writes to stop before reloading so
+// that a partially written ROM is never loaded
+const SETTLE_TIME: Duration = Duration::from_millis(250);
+
+pub(crate) struct RomWatcher {
+    watcher: RecommendedWatcher,
+    events: Receiver<notify::Result<notify::Event>>,
+    nes_file_path: PathBuf,
+    watched_dir: PathBuf,
+    last_change: Option<Instant>,
+}
+
+impl RomWatcher {
+    pub(crate) fn new(nes_file_path: &Path) -> notify::Result<Self> {
+        let (sender, events) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(sender)?;
+
+        let watched_dir = parent_dir(nes_file_path);
+        watcher.watch(&watched_dir, RecursiveMode::NonRecursive)?;
+
+        log::info!("Watching '{}' for changes", nes_file_path.display());
+
+        Ok(Self {
+            watcher,
+            events,
+            nes_file_path: nes_file_path.to_path_buf(),
+            watched_dir,
+            last_change: None,
+        })
+    }
+
+    /// Return whether the ROM file at `nes_file_path` has changed and has since stopped changing.
+    ///
+    /// If `nes_file_path` is not the file that was being watched (e.g. because the user switched
+    /// games), the watch moves to the new file.
+    pub(crate) fn poll_changed(&mut self, nes_file_path: &Path) -> bool {
+        if nes_file_path != self.nes_file_path {
+            self.retarget(nes_file_path);
+        }
+
+        let file_name = self.nes_file_path.file_name();
+        for event in self.events.try_iter() {
+            match event {
+                Ok(event) => {
+                    if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_))
+                        && event.paths.iter().any(|path| path.file_name() == file_name)
+                    {
+                        self.last_change = Some(Instant::now());
+                    }
+                }
+                Err(err) => {
+                    log::error!("Error watching '{}': {err}", self.nes_file_path.display());
+                }
+            }
+        }
+
+        match self.last_change {
+            Some(last_change) if last_change.elapsed() >= SETTLE_TIME => {
+                self.last_change = None;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn retarget(&mut self, nes_file_path: &Path) {
+        self.nes_file_path = nes_file_path.to_path_buf();
+        self.last_change = None;
+
+        let watched_dir = parent_dir(nes_file_path);
+        if watched_dir == self.watched_dir {
+            return;
+        }
+
+        if let Err(err) = self.watcher.unwatch(&self.watched_dir) {
+            log::error!("Error unwatching '{}': {err}", self.watched_dir.display());
+        }
+        if let Err(err) = self.watcher.watch(&watched_dir, RecursiveMode::NonRecursive) {
+            log::error!("Unable to watch '{}' for changes: {err}", nes_file_path.display());
+        }
+        self.watched_dir = watched_dir;
+
+        // Drop any events from the previous directory
+        while self.events.try_recv().is_ok() {}
+
+        log::info!("Watching '{}' for changes", nes_file_path.display());
+    }
+}
+
+fn parent_dir(path: &Path) -> PathBuf {
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => PathBuf::from("."),
+    }
+}
@@ -0,0 +1,169 @@
+//! GPU adapter enumeration and selection.
+//!
+//! By default wgpu picks an adapter by power preference, which is not always the right one on
+//! systems with more than one GPU. An adapter can instead be selected by its index in the
+//! enumeration order or by (part of) its name.
+
+use crate::config::WgpuBackend;
+#[cfg(not(target_arch = "wasm32"))]
+use jgnes_core::logging::RENDERER_TARGET;
+use serde::{Deserialize, Serialize};
+use std::convert::Infallible;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GpuAdapterSelector {
+    /// Index in the list returned by [`enumerate_adapters`] for the same backend.
+    Index(usize),
+    /// Case-insensitive substring of the adapter name.
+    Name(String),
+}
+
+impl GpuAdapterSelector {
+    #[cfg(not(target_arch = "wasm32"))]
+    fn matches(&self, index: usize, info: &wgpu::AdapterInfo) -> bool {
+        match self {
+            Self::Index(selected) => *selected == index,
+            Self::Name(name) => info.name.to_lowercase().contains(&name.to_lowercase()),
+        }
+    }
+}
+
+impl FromStr for GpuAdapterSelector {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.parse() {
+            Ok(index) => Self::Index(index),
+            Err(_) => Self::Name(s.into()),
+        })
+    }
+}
+
+impl Display for GpuAdapterSelector {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Index(index) => write!(f, "{index}"),
+            Self::Name(name) => write!(f, "{name}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GpuAdapterInfo {
+    pub name: String,
+    pub backend: String,
+    pub device_type: String,
+    pub driver: String,
+}
+
+impl From<wgpu::AdapterInfo> for GpuAdapterInfo {
+    fn from(info: wgpu::AdapterInfo) -> Self {
+        let driver = match (info.driver.is_empty(), info.driver_info.is_empty()) {
+            (true, true) => "unknown".into(),
+            (false, true) => info.driver,
+            (true, false) => info.driver_info,
+            (false, false) => format!("{} {}", info.driver, info.driver_info),
+        };
+
+        Self {
+            name: info.name,
+            backend: info.backend.to_string(),
+            device_type: format!("{:?}", info.device_type),
+            driver,
+        }
+    }
+}
+
+impl Display for GpuAdapterInfo {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({}, {}, driver: {})", self.name, self.backend, self.device_type, self.driver)
+    }
+}
+
+/// List the GPU adapters available with the given backend, in the order that
+/// [`GpuAdapterSelector::Index`] refers to. Adapters listed here are not necessarily able to
+/// render to a given window.
+#[cfg(not(target_arch = "wasm32"))]
+#[must_use]
+pub fn enumerate_adapters(backend: WgpuBackend) -> Vec<GpuAdapterInfo> {
+    wgpu::Instance::new(instance_descriptor(backend))
+        .enumerate_adapters(backend.to_wgpu_backends())
+        .into_iter()
+        .map(|adapter| adapter.get_info().into())
+        .collect()
+}
+
+/// Create a wgpu instance for the given backend. If the backend includes both WebGPU and WebGL2,
+/// WebGPU is only used if the browser can actually provide a WebGPU adapter, so that a single web
+/// build can fall back to WebGL2.
+pub(crate) async fn new_instance(backend: WgpuBackend) -> wgpu::Instance {
+    wgpu::util::new_instance_with_webgpu_detection(instance_descriptor(backend)).await
+}
+
+fn instance_descriptor(backend: WgpuBackend) -> wgpu::InstanceDescriptor {
+    wgpu::InstanceDescriptor {
+        backends: backend.to_wgpu_backends(),
+        flags: wgpu::InstanceFlags::default(),
+        dx12_shader_compiler: wgpu::Dx12Compiler::default(),
+        gles_minor_version: wgpu::Gles3MinorVersion::default(),
+    }
+}
+
+/// Find the adapter matching the selector that can render to the given surface. Adapters can only
+/// be enumerated on native platforms.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn select_adapter(
+    instance: &wgpu::Instance,
+    backend: WgpuBackend,
+    surface: &wgpu::Surface<'_>,
+    selector: &GpuAdapterSelector,
+) -> Option<wgpu::Adapter> {
+    let mut adapters = instance.enumerate_adapters(backend.to_wgpu_backends());
+    let Some(index) = adapters
+        .iter()
+        .enumerate()
+        .position(|(i, adapter)| selector.matches(i, &adapter.get_info()))
+    else {
+        let available: Vec<_> = adapters
+            .iter()
+            .enumerate()
+            .map(|(i, adapter)| format!("{i}: {}", adapter.get_info().name))
+            .collect();
+        log::warn!(target: RENDERER_TARGET, "No GPU adapter matches '{selector}'; using the default adapter (available adapters: {})", available.join(", "));
+        return None;
+    };
+    let adapter = adapters.swap_remove(index);
+
+    if !adapter.is_surface_supported(surface) {
+        log::warn!(target: RENDERER_TARGET, "GPU adapter '{}' cannot render to this window; using the default adapter", adapter.get_info().name);
+        return None;
+    }
+
+    Some(adapter)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn selector_parsing() {
+        assert_eq!("1".parse(), Ok(GpuAdapterSelector::Index(1)));
+        assert_eq!("NVIDIA".parse(), Ok(GpuAdapterSelector::Name("NVIDIA".into())));
+
+        let info = wgpu::AdapterInfo {
+            name: "NVIDIA GeForce RTX 3060".into(),
+            vendor: 0,
+            device: 0,
+            device_type: wgpu::DeviceType::DiscreteGpu,
+            driver: String::new(),
+            driver_info: String::new(),
+            backend: wgpu::Backend::Vulkan,
+        };
+        assert!(GpuAdapterSelector::Name("geforce".into()).matches(0, &info));
+        assert!(!GpuAdapterSelector::Name("intel".into()).matches(0, &info));
+        assert!(!GpuAdapterSelector::Index(1).matches(0, &info));
+    }
+}
@@ -1,9 +1,118 @@
 use crate::config::Overscan;
 use jgnes_core::{ColorEmphasis, FrameBuffer, TimingMode};
+use std::array;
 use std::ops::Range;
+use thiserror::Error;
 
-// TODO support color customization
-const COLOR_MAPPING: &[u8; 8 * 64 * 3] = include_bytes!("nespalette.pal");
+const DEFAULT_PALETTE: &[u8; FULL_PALETTE_LEN] = include_bytes!("nespalette.pal");
+
+pub const NUM_BASE_COLORS: usize = 64;
+
+// .pal files contain either only the 64 base colors, or all 8 color emphasis variants of each color
+const BASE_PALETTE_LEN: usize = 3 * NUM_BASE_COLORS;
+const FULL_PALETTE_LEN: usize = 8 * BASE_PALETTE_LEN;
+
+// Approximate attenuation applied to non-emphasized channels when generating color emphasis
+// variants for palettes that do not include them
+const EMPHASIS_ATTENUATION: f64 = 0.816;
+
+#[derive(Debug, Error)]
+pub enum PaletteError {
+    #[error("Invalid palette length, expected {BASE_PALETTE_LEN} or {FULL_PALETTE_LEN} bytes: {0}")]
+    InvalidLength(usize),
+}
+
+/// A mapping from NES colors to RGB, including all 8 color emphasis variants of each color.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Palette {
+    colors: Box<[u8; FULL_PALETTE_LEN]>,
+}
+
+impl Palette {
+    /// Parse a palette from the contents of a .pal file.
+    ///
+    /// If the file only contains the 64 base colors, the color emphasis variants will be generated
+    /// using an approximation of the NES's color emphasis behavior.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the file is not a supported length.
+    pub fn from_pal_bytes(bytes: &[u8]) -> Result<Self, PaletteError> {
+        match bytes.len() {
+            BASE_PALETTE_LEN => {
+                let mut palette = Self::default();
+                for (i, rgb) in bytes.chunks_exact(3).enumerate() {
+                    palette.set_color(i, [rgb[0], rgb[1], rgb[2]]);
+                }
+                Ok(palette)
+            }
+            FULL_PALETTE_LEN => {
+                let mut colors = Box::new([0; FULL_PALETTE_LEN]);
+                colors.copy_from_slice(bytes);
+                Ok(Self { colors })
+            }
+            len => Err(PaletteError::InvalidLength(len)),
+        }
+    }
+
+    /// Serialize this palette in .pal format, including all color emphasis variants.
+    #[must_use]
+    pub fn to_pal_bytes(&self) -> Vec<u8> {
+        self.colors.to_vec()
+    }
+
+    /// Retrieve the RGB value of a base color (no color emphasis).
+    ///
+    /// # Panics
+    ///
+    /// This method will panic if `index` is not a valid NES color (0-63).
+    #[must_use]
+    pub fn color(&self, index: usize) -> [u8; 3] {
+        assert!(index < NUM_BASE_COLORS, "invalid NES color index: {index}");
+
+        let start = 3 * index;
+        [self.colors[start], self.colors[start + 1], self.colors[start + 2]]
+    }
+
+    /// Set the RGB value of a base color. The color emphasis variants of the color will be
+    /// regenerated from the new value.
+    ///
+    /// # Panics
+    ///
+    /// This method will panic if `index` is not a valid NES color (0-63).
+    pub fn set_color(&mut self, index: usize, rgb: [u8; 3]) {
+        assert!(index < NUM_BASE_COLORS, "invalid NES color index: {index}");
+
+        for emphasis in 0..8 {
+            // Emphasis bits are ordered red, green, blue; emphasizing a channel darkens the others
+            let emphasized_rgb: [u8; 3] = std::array::from_fn(|channel| {
+                let attenuated = emphasis != 0 && emphasis & (1 << channel) == 0;
+                if attenuated {
+                    (f64::from(rgb[channel]) * EMPHASIS_ATTENUATION).round() as u8
+                } else {
+                    rgb[channel]
+                }
+            });
+
+            let start = emphasis * BASE_PALETTE_LEN + 3 * index;
+            self.colors[start..start + 3].copy_from_slice(&emphasized_rgb);
+        }
+    }
+
+    fn rgb(&self, color_map_index: usize) -> [u8; 3] {
+        [
+            self.colors[color_map_index],
+            self.colors[color_map_index + 1],
+            self.colors[color_map_index + 2],
+        ]
+    }
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self { colors: Box::new(*DEFAULT_PALETTE) }
+    }
+}
 
 fn get_color_emphasis_offset(color_emphasis: ColorEmphasis) -> u16 {
     3 * 64 * u16::from(color_emphasis.red)
@@ -59,12 +168,13 @@ fn row_offset_for(timing_mode: TimingMode) -> usize {
     }
 }
 
-pub fn sdl_texture_updater(
-    frame_buffer: &FrameBuffer,
+pub fn sdl_texture_updater<'a>(
+    frame_buffer: &'a FrameBuffer,
     color_emphasis: ColorEmphasis,
+    palette: &'a Palette,
     overscan: Overscan,
     timing_mode: TimingMode,
-) -> impl FnOnce(&mut [u8], usize) + '_ {
+) -> impl FnOnce(&mut [u8], usize) + 'a {
     let screen_height = jgnes_core::SCREEN_HEIGHT as usize;
     let visible_screen_height = timing_mode.visible_screen_height();
 
@@ -99,8 +209,7 @@ pub fn sdl_texture_updater(
             {
                 let color_map_index = color_emphasis_offset + (3 * nes_color) as usize;
                 let start = (i - row_offset) * pitch + 3 * j;
-                pixels[start..start + 3]
-                    .copy_from_slice(&COLOR_MAPPING[color_map_index..color_map_index + 3]);
+                pixels[start..start + 3].copy_from_slice(&palette.rgb(color_map_index));
             }
         }
     }
@@ -109,6 +218,7 @@ pub fn sdl_texture_updater(
 pub fn to_rgba(
     frame_buffer: &FrameBuffer,
     color_emphasis: ColorEmphasis,
+    palette: &Palette,
     overscan: Overscan,
     timing_mode: TimingMode,
     out: &mut [u8],
@@ -134,24 +244,30 @@ pub fn to_rgba(
     clear_cols_rgba(0..left, out, pitch, visible_screen_height);
     clear_cols_rgba(right..screen_width, out, pitch, visible_screen_height);
 
-    let color_emphasis_offset = get_color_emphasis_offset(color_emphasis) as usize;
-    for (scanline_idx, scanline) in
-        frame_buffer.iter().enumerate().filter(|(i, _)| (top..bottom).contains(i))
-    {
-        for (color_idx, nes_color) in
-            scanline.iter().copied().enumerate().filter(|(j, _)| (left..right).contains(j))
-        {
-            let color_map_index = color_emphasis_offset + (3 * nes_color) as usize;
-            let [r, g, b] = COLOR_MAPPING[color_map_index..color_map_index + 3] else {
-                unreachable!("destructuring a slice of size 3 into [a, b, c]")
-            };
-
-            let out_index = (scanline_idx - row_offset) * 4 * screen_width + color_idx * 4;
-            out[out_index..out_index + 4].copy_from_slice(&[r, g, b, 255]);
+    let lookup_table = rgba_lookup_table(palette, color_emphasis);
+    for (scanline_idx, scanline) in frame_buffer.iter().enumerate().take(bottom).skip(top) {
+        let out_row_start = (scanline_idx - row_offset) * pitch;
+        let out_row = &mut out[out_row_start + 4 * left..out_row_start + 4 * right];
+        for (out_pixel, &nes_color) in out_row.chunks_exact_mut(4).zip(&scanline[left..right]) {
+            out_pixel.copy_from_slice(&lookup_table[nes_color as usize]);
         }
     }
 }
 
+// Resolve the color emphasis offset once per frame rather than once per pixel. Keeping the inner
+// loop to a table lookup and a fixed-size copy also lets the compiler vectorize the row stores,
+// which matters most for the web build when compiled with simd128 enabled.
+fn rgba_lookup_table(
+    palette: &Palette,
+    color_emphasis: ColorEmphasis,
+) -> [[u8; 4]; NUM_BASE_COLORS] {
+    let color_emphasis_offset = get_color_emphasis_offset(color_emphasis) as usize;
+    array::from_fn(|nes_color| {
+        let [r, g, b] = palette.rgb(color_emphasis_offset + 3 * nes_color);
+        [r, g, b, 255]
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -171,6 +287,7 @@ mod tests {
             to_rgba(
                 &frame_buffer,
                 ColorEmphasis::default(),
+                &Palette::default(),
                 Overscan::default(),
                 timing_mode,
                 &mut output_buffer,
@@ -178,6 +295,7 @@ mod tests {
             to_rgba(
                 &frame_buffer,
                 ColorEmphasis::default(),
+                &Palette::default(),
                 Overscan { top: 8, bottom: 8, left: 8, right: 8 },
                 timing_mode,
                 &mut output_buffer,
@@ -185,10 +303,42 @@ mod tests {
         }
     }
 
+    #[test]
+    fn to_rgba_uses_emphasis_variant() {
+        let frame_buffer: Box<FrameBuffer> =
+            Box::new(array::from_fn(|i| array::from_fn(|j| ((i + j) % NUM_BASE_COLORS) as u8)));
+
+        let palette = Palette::default();
+        let color_emphasis = ColorEmphasis { red: false, green: true, blue: true };
+        let timing_mode = TimingMode::Pal;
+        let mut output_buffer = vec![
+            0;
+            4 * jgnes_core::SCREEN_WIDTH as usize
+                * timing_mode.visible_screen_height() as usize
+        ];
+        to_rgba(
+            &frame_buffer,
+            color_emphasis,
+            &palette,
+            Overscan::default(),
+            timing_mode,
+            &mut output_buffer,
+        );
+
+        for (i, row) in frame_buffer.iter().enumerate() {
+            for (j, &nes_color) in row.iter().enumerate() {
+                let [r, g, b] = palette.rgb(3 * 64 * 6 + 3 * nes_color as usize);
+                let out_index = 4 * (i * jgnes_core::SCREEN_WIDTH as usize + j);
+                assert_eq!([r, g, b, 255], output_buffer[out_index..out_index + 4]);
+            }
+        }
+    }
+
     #[test]
     fn sdl_texture_updater_does_not_panic() {
         let frame_buffer =
             [[0; jgnes_core::SCREEN_WIDTH as usize]; jgnes_core::SCREEN_HEIGHT as usize];
+        let palette = Palette::default();
 
         for &timing_mode in TimingMode::all() {
             let mut pixels = vec![
@@ -201,6 +351,7 @@ mod tests {
             let updater = sdl_texture_updater(
                 &frame_buffer,
                 ColorEmphasis::default(),
+                &palette,
                 Overscan::default(),
                 timing_mode,
             );
@@ -209,10 +360,25 @@ mod tests {
             let updater = sdl_texture_updater(
                 &frame_buffer,
                 ColorEmphasis::default(),
+                &palette,
                 Overscan { top: 8, bottom: 8, left: 8, right: 8 },
                 timing_mode,
             );
             updater(&mut pixels, pitch);
         }
     }
+
+    #[test]
+    fn palette_pal_round_trip() {
+        let palette = Palette::default();
+        assert_eq!(Palette::from_pal_bytes(&palette.to_pal_bytes()).unwrap(), palette);
+
+        let base_colors: Vec<_> = (0..NUM_BASE_COLORS).flat_map(|i| palette.color(i)).collect();
+        let from_base = Palette::from_pal_bytes(&base_colors).unwrap();
+        for i in 0..NUM_BASE_COLORS {
+            assert_eq!(from_base.color(i), palette.color(i));
+        }
+
+        assert!(Palette::from_pal_bytes(&[0; 100]).is_err());
+    }
 }
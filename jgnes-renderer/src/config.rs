@@ -1,3 +1,5 @@
+use crate::GpuAdapterSelector;
+use crate::colors::Palette;
 use jgnes_proc_macros::{EnumDisplay, EnumFromStr};
 use serde::{Deserialize, Serialize};
 use std::fmt::{Display, Formatter};
@@ -48,6 +50,42 @@ pub struct Overscan {
     pub bottom: u8,
 }
 
+impl Overscan {
+    /// Return the named preset that exactly matches this overscan, if there is one.
+    #[must_use]
+    pub fn matching_preset(self) -> Option<OverscanPreset> {
+        OverscanPreset::ALL.into_iter().find(|preset| preset.to_overscan() == self)
+    }
+}
+
+/// Named overscan settings for common use cases.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, EnumDisplay, EnumFromStr,
+)]
+pub enum OverscanPreset {
+    /// Display the full frame.
+    #[default]
+    None,
+    /// Crop 8 pixels from every side, which hides most graphical garbage at the edges of the
+    /// screen.
+    TvSafe8px,
+    /// Approximate the area that was visible on a typical NTSC CRT.
+    FullNtscCrop,
+}
+
+impl OverscanPreset {
+    pub const ALL: [Self; 3] = [Self::None, Self::TvSafe8px, Self::FullNtscCrop];
+
+    #[must_use]
+    pub fn to_overscan(self) -> Overscan {
+        match self {
+            Self::None => Overscan::default(),
+            Self::TvSafe8px => Overscan { top: 8, left: 8, right: 8, bottom: 8 },
+            Self::FullNtscCrop => Overscan { top: 8, left: 12, right: 12, bottom: 16 },
+        }
+    }
+}
+
 impl Display for Overscan {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(
@@ -66,6 +104,10 @@ pub enum VSyncMode {
     Enabled,
     Disabled,
     Fast,
+    /// Present each frame as soon as it is rendered, for variable refresh rate displays that
+    /// refresh whenever a frame is presented. Falls back to mailbox presentation if immediate
+    /// presentation is not supported.
+    Adaptive,
 }
 
 impl VSyncMode {
@@ -74,6 +116,27 @@ impl VSyncMode {
             Self::Enabled => wgpu::PresentMode::Fifo,
             Self::Disabled => wgpu::PresentMode::Immediate,
             Self::Fast => wgpu::PresentMode::Mailbox,
+            Self::Adaptive => wgpu::PresentMode::AutoNoVsync,
+        }
+    }
+}
+
+/// Which GPU wgpu should prefer when no specific adapter is selected.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, EnumDisplay, EnumFromStr,
+)]
+pub enum PowerPreference {
+    #[default]
+    HighPerformance,
+    /// Prefer an integrated GPU, which usually draws less power
+    LowPower,
+}
+
+impl PowerPreference {
+    pub(crate) fn to_wgpu_power_preference(self) -> wgpu::PowerPreference {
+        match self {
+            Self::HighPerformance => wgpu::PowerPreference::HighPerformance,
+            Self::LowPower => wgpu::PowerPreference::LowPower,
         }
     }
 }
@@ -161,6 +224,133 @@ impl Display for Shader {
     }
 }
 
+impl Shader {
+    pub(crate) fn to_passes(self) -> Vec<ShaderPass> {
+        match self {
+            Self::Prescale(render_scale) if render_scale.get() > 1 => {
+                vec![ShaderPass { scale: render_scale, ..ShaderPass::default() }]
+            }
+            Self::None | Self::Prescale(_) => vec![],
+            Self::GaussianBlur { prescale_factor, stdev, radius } => vec![ShaderPass {
+                effect: ShaderEffect::GaussianBlur { stdev, radius },
+                scale: prescale_factor,
+                filter: GpuFilterMode::NearestNeighbor,
+            }],
+        }
+    }
+}
+
+/// The effect applied by a single shader pass, after its input has been scaled.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(tag = "effect")]
+pub enum ShaderEffect {
+    /// Only scale the input, with no additional effect.
+    #[default]
+    Stock,
+    /// Apply a two-dimensional Gaussian blur.
+    GaussianBlur { stdev: f64, radius: u32 },
+}
+
+/// A single pass in a multi-pass shader preset.
+///
+/// Each pass integer scales its input by `scale`, sampling with the given filter mode, and then
+/// applies its effect to the scaled image.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ShaderPass {
+    #[serde(flatten)]
+    pub effect: ShaderEffect,
+    #[serde(default = "default_pass_scale")]
+    pub scale: RenderScale,
+    #[serde(default)]
+    pub filter: GpuFilterMode,
+}
+
+fn default_pass_scale() -> RenderScale {
+    RenderScale::ONE
+}
+
+impl Default for ShaderPass {
+    fn default() -> Self {
+        Self {
+            effect: ShaderEffect::default(),
+            scale: default_pass_scale(),
+            filter: GpuFilterMode::default(),
+        }
+    }
+}
+
+// Product of all pass scale factors cannot exceed the maximum single render scale, to avoid
+// allocating enormous intermediate textures
+const MAX_PRESET_TOTAL_SCALE: u32 = 16;
+const MAX_PRESET_PASSES: usize = 16;
+
+/// A chain of shader passes, similar in spirit to `.slangp` shader presets. When a preset is set,
+/// it replaces the single-shader configuration in [`RendererConfig::shader`].
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct ShaderPreset {
+    pub passes: Vec<ShaderPass>,
+}
+
+impl ShaderPreset {
+    /// Validate that the preset can be rendered.
+    ///
+    /// # Errors
+    ///
+    /// This method will return an error describing the first invalid pass, or if the preset's
+    /// total scale factor is too large.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.passes.len() > MAX_PRESET_PASSES {
+            return Err(format!(
+                "Shader preset has too many passes, maximum is {MAX_PRESET_PASSES}: {}",
+                self.passes.len()
+            ));
+        }
+
+        let mut total_scale = 1_u32;
+        for (i, pass) in self.passes.iter().enumerate() {
+            RenderScale::try_from(pass.scale.get())
+                .map_err(|err| format!("Invalid scale in shader pass {i}: {err}"))?;
+            total_scale = total_scale.saturating_mul(pass.scale.get());
+
+            if let ShaderEffect::GaussianBlur { stdev, radius } = pass.effect {
+                if stdev.is_nan() || stdev.is_sign_negative() {
+                    return Err(format!("Invalid blur stdev in shader pass {i}: {stdev}"));
+                }
+                if radius == 0 {
+                    return Err(format!("Blur radius in shader pass {i} must be positive"));
+                }
+            }
+        }
+
+        if total_scale > MAX_PRESET_TOTAL_SCALE {
+            return Err(format!(
+                "Shader preset total scale factor must be at most {MAX_PRESET_TOTAL_SCALE}: {total_scale}"
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl Display for ShaderPreset {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "ShaderPreset[")?;
+        for (i, pass) in self.passes.iter().enumerate() {
+            if i != 0 {
+                write!(f, ", ")?;
+            }
+            match pass.effect {
+                ShaderEffect::Stock => write!(f, "Stock")?,
+                ShaderEffect::GaussianBlur { stdev, radius } => {
+                    write!(f, "GaussianBlur[stdev={stdev}, radius={radius}]")?;
+                }
+            }
+            write!(f, " {} {}", pass.scale, pass.filter)?;
+        }
+        write!(f, "]")
+    }
+}
+
 #[derive(
     Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, EnumDisplay, EnumFromStr,
 )]
@@ -171,6 +361,59 @@ pub enum Scanlines {
     Dim,
 }
 
+/// Color-blindness assist filter applied in the final render pass.
+///
+/// Each mode simulates how the image appears with the given color vision deficiency and shifts the
+/// lost color information into channels that remain distinguishable (daltonization).
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, EnumDisplay, EnumFromStr,
+)]
+pub enum ColorBlindFilter {
+    #[default]
+    None,
+    Protanopia,
+    Deuteranopia,
+    Tritanopia,
+}
+
+/// Screen-space CRT geometry effects applied in the final render pass. All strengths are in the
+/// range \[0.0, 1.0\], and 0.0 disables the corresponding effect.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct CrtGeometry {
+    /// Strength of the barrel distortion applied to the image.
+    #[serde(default)]
+    pub curvature: f64,
+    /// Corner radius, relative to the shorter side of the display area.
+    #[serde(default)]
+    pub corner_rounding: f64,
+    /// Strength of the darkening applied towards the edges of the image.
+    #[serde(default)]
+    pub vignette: f64,
+}
+
+impl CrtGeometry {
+    /// Return a copy with all strengths clamped to the valid range. NaN values are treated as 0.
+    #[must_use]
+    pub fn clamped(self) -> Self {
+        let clamp = |value: f64| if value.is_nan() { 0.0 } else { value.clamp(0.0, 1.0) };
+        Self {
+            curvature: clamp(self.curvature),
+            corner_rounding: clamp(self.corner_rounding),
+            vignette: clamp(self.vignette),
+        }
+    }
+}
+
+impl Display for CrtGeometry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "CrtGeometry[curvature={}, corner_rounding={}, vignette={}]",
+            self.curvature, self.corner_rounding, self.vignette
+        )
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct FrameSkip(pub u8);
 
@@ -187,11 +430,83 @@ impl FrameSkip {
 pub struct RendererConfig {
     pub vsync_mode: VSyncMode,
     pub wgpu_backend: WgpuBackend,
+    /// GPU adapter to render with, or `None` to let wgpu choose. Ignored on the web.
+    pub gpu_adapter: Option<GpuAdapterSelector>,
+    /// Only used when `gpu_adapter` is not set or does not match an adapter
+    pub power_preference: PowerPreference,
     pub gpu_filter_mode: GpuFilterMode,
     pub shader: Shader,
+    pub shader_preset: Option<ShaderPreset>,
     pub scanlines: Scanlines,
+    /// How much of the selected scanline darkening to apply, from 0.0 (none) to 1.0 (full). Values
+    /// outside of this range are clamped.
+    pub scanline_intensity: f64,
+    pub crt_geometry: CrtGeometry,
+    pub color_blind_filter: ColorBlindFilter,
+    /// How strongly to blend each frame with the previous frame, from 0.0 (disabled) to 1.0 (an
+    /// even average of the two). Smooths out flicker-based transparency effects. Values outside of
+    /// this range are clamped.
+    pub frame_blend_strength: f64,
+    pub palette: Palette,
     pub aspect_ratio: AspectRatio,
     pub overscan: Overscan,
     pub forced_integer_height_scaling: bool,
+    /// Request WebGL2-compatible device limits if the adapter turns out to use the OpenGL backend,
+    /// e.g. when a browser without WebGPU support falls back to WebGL2. If not set, OpenGL adapters
+    /// use OpenGL ES 3.0-compatible limits
     pub use_webgl2_limits: bool,
 }
+
+impl RendererConfig {
+    pub(crate) fn shader_passes(&self) -> Vec<ShaderPass> {
+        match &self.shader_preset {
+            Some(preset) => preset.passes.clone(),
+            None => self.shader.to_passes(),
+        }
+    }
+
+    pub(crate) fn clamped_frame_blend_strength(&self) -> f64 {
+        if self.frame_blend_strength.is_nan() {
+            0.0
+        } else {
+            self.frame_blend_strength.clamp(0.0, 1.0)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shader_preset_validation() {
+        let pass = |scale| ShaderPass { scale: RenderScale(scale), ..ShaderPass::default() };
+
+        assert!(ShaderPreset { passes: vec![pass(4), pass(4)] }.validate().is_ok());
+        assert!(ShaderPreset { passes: vec![pass(4), pass(8)] }.validate().is_err());
+        assert!(ShaderPreset { passes: vec![pass(0)] }.validate().is_err());
+    }
+
+    #[test]
+    fn example_shader_preset() {
+        let preset: ShaderPreset =
+            toml::from_str(include_str!("../presets/soft-blur.toml")).unwrap();
+        preset.validate().unwrap();
+
+        assert_eq!(
+            preset.passes,
+            vec![
+                ShaderPass {
+                    effect: ShaderEffect::Stock,
+                    scale: RenderScale(3),
+                    filter: GpuFilterMode::NearestNeighbor,
+                },
+                ShaderPass {
+                    effect: ShaderEffect::GaussianBlur { stdev: 1.5, radius: 4 },
+                    scale: RenderScale::ONE,
+                    filter: GpuFilterMode::NearestNeighbor,
+                },
+            ]
+        );
+    }
+}
@@ -1,10 +1,14 @@
+mod adapter;
 pub mod colors;
 pub mod config;
 mod renderer;
 
 use crate::config::AspectRatio;
+#[cfg(not(target_arch = "wasm32"))]
+pub use adapter::enumerate_adapters;
+pub use adapter::{GpuAdapterInfo, GpuAdapterSelector};
 use jgnes_core::TimingMode;
-pub use renderer::WgpuRenderer;
+pub use renderer::{OverlayContext, WgpuRenderer};
 use std::cmp;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -16,6 +20,10 @@ pub struct DisplayArea {
 }
 
 /// Determine the display area given the specified window size, aspect ratio, and forced scaling.
+///
+/// The window size should be the size of the drawable area in physical pixels, which on high-DPI
+/// displays can be larger than the window's size in logical units. The returned area is in the same
+/// units, so integer scaling stays pixel-exact under fractional display scaling.
 #[must_use]
 pub fn determine_display_area(
     window_width: u32,
@@ -5,8 +5,9 @@ mod shaders;
 
 use crate::config::{FrameSkip, GpuFilterMode, RendererConfig, Scanlines, VSyncMode, WgpuBackend};
 use crate::renderer::shaders::{FragmentGlobals, RenderPipelineState};
-use crate::{DisplayArea, colors};
-use jgnes_core::{ColorEmphasis, FrameBuffer, Renderer, TimingMode};
+use crate::{DisplayArea, GpuAdapterInfo, adapter, colors};
+use jgnes_core::logging::RENDERER_TARGET;
+use jgnes_core::{ClassifyError, ColorEmphasis, ErrorSeverity, FrameBuffer, Renderer, TimingMode};
 use raw_window_handle::{HasDisplayHandle, HasWindowHandle};
 use std::iter;
 use thiserror::Error;
@@ -60,6 +61,10 @@ pub enum WgpuRendererError {
         #[from]
         source: wgpu::SurfaceError,
     },
+    #[error(
+        "GPU ran out of memory while acquiring the output surface; try lowering the prescale factor or disabling shaders"
+    )]
+    OutOfMemory,
     #[error("Error in wgpu renderer: {msg}")]
     Other { msg: String },
 }
@@ -70,10 +75,51 @@ impl WgpuRendererError {
     }
 }
 
+impl ClassifyError for WgpuRendererError {
+    fn severity(&self) -> ErrorSeverity {
+        match self {
+            // Lost and outdated surfaces are reconfigured on the next frame
+            Self::OutputSurface {
+                source:
+                    wgpu::SurfaceError::Lost
+                    | wgpu::SurfaceError::Outdated
+                    | wgpu::SurfaceError::Timeout,
+            } => ErrorSeverity::Recoverable,
+            Self::CreateSurface { .. }
+            | Self::RequestDevice { .. }
+            | Self::Handle(..)
+            | Self::OutputSurface { .. }
+            | Self::OutOfMemory
+            | Self::Other { .. } => ErrorSeverity::Fatal,
+        }
+    }
+}
+
+/// Returns the size of the window's drawable area in physical pixels. On high-DPI displays this can
+/// differ from the window's size in logical units, and the surface must match the physical size.
 pub type WindowSizeFn<W> = fn(&W) -> (u32, u32);
 
+/// State passed to an overlay callback; see [`WgpuRenderer::set_overlay_callback`].
+#[non_exhaustive]
+pub struct OverlayContext<'a> {
+    pub device: &'a wgpu::Device,
+    pub queue: &'a wgpu::Queue,
+    /// Encoder that the NES frame has already been drawn with. Passes recorded here are submitted
+    /// together with the frame
+    pub encoder: &'a mut wgpu::CommandEncoder,
+    /// View of the surface texture that is about to be presented. This covers the entire window,
+    /// not only the area that the NES frame was drawn to
+    pub surface_view: &'a wgpu::TextureView,
+    pub surface_format: wgpu::TextureFormat,
+    /// Surface size in physical pixels
+    pub surface_size: (u32, u32),
+}
+
+type OverlayCallback = Box<dyn FnMut(OverlayContext<'_>)>;
+
 pub struct WgpuRenderer<W> {
     render_config: RendererConfig,
+    adapter_info: GpuAdapterInfo,
     timing_mode: TimingMode,
     output_buffer: Vec<u8>,
     device: wgpu::Device,
@@ -90,6 +136,8 @@ pub struct WgpuRenderer<W> {
     fs_globals_buffer: wgpu::Buffer,
     frame_skip: FrameSkip,
     total_frames: u64,
+    pending_warning: Option<String>,
+    overlay_callback: Option<OverlayCallback>,
     // SAFETY: The window must be declared after the surface so that it is not dropped before the
     // surface is dropped
     window: W,
@@ -115,12 +163,7 @@ where
 
         let output_buffer = vec![0; output_buffer_len(timing_mode)];
 
-        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
-            backends: render_config.wgpu_backend.to_wgpu_backends(),
-            flags: wgpu::InstanceFlags::default(),
-            dx12_shader_compiler: wgpu::Dx12Compiler::default(),
-            gles_minor_version: wgpu::Gles3MinorVersion::default(),
-        });
+        let instance = adapter::new_instance(render_config.wgpu_backend).await;
 
         // SAFETY: The surface must not outlive the window it was created from.
         // The surface and window are both owned by WgpuRenderer, and the window field is declared
@@ -130,27 +173,47 @@ where
             instance.create_surface_unsafe(wgpu::SurfaceTargetUnsafe::from_window(&window)?)
         }?;
 
-        let adapter = instance
-            .request_adapter(&wgpu::RequestAdapterOptions {
-                power_preference: wgpu::PowerPreference::HighPerformance,
-                compatible_surface: Some(&surface),
-                force_fallback_adapter: false,
-            })
-            .await
-            .ok_or_else(|| WgpuRendererError::msg("Unable to obtain wgpu adapter"))?;
+        #[cfg(not(target_arch = "wasm32"))]
+        let selected_adapter = render_config.gpu_adapter.as_ref().and_then(|selector| {
+            adapter::select_adapter(&instance, render_config.wgpu_backend, &surface, selector)
+        });
+        #[cfg(target_arch = "wasm32")]
+        let selected_adapter = None;
+
+        let adapter = match selected_adapter {
+            Some(adapter) => adapter,
+            None => instance
+                .request_adapter(&wgpu::RequestAdapterOptions {
+                    power_preference: render_config.power_preference.to_wgpu_power_preference(),
+                    compatible_surface: Some(&surface),
+                    force_fallback_adapter: false,
+                })
+                .await
+                .ok_or_else(|| WgpuRendererError::msg("Unable to obtain wgpu adapter"))?,
+        };
 
-        log::info!("Using GPU adapter with backend {:?}", adapter.get_info().backend);
+        let required_limits = if adapter.get_info().backend == wgpu::Backend::Gl {
+            // OpenGL ES devices such as the Raspberry Pi's GPU do not support the default limits.
+            // The downlevel limits only allow 2048x2048 textures, which is too small for the
+            // surface on large displays, so take the texture size limits from the adapter
+            let downlevel_limits = if render_config.use_webgl2_limits {
+                wgpu::Limits::downlevel_webgl2_defaults()
+            } else {
+                wgpu::Limits::downlevel_defaults()
+            };
+            downlevel_limits.using_resolution(adapter.limits())
+        } else {
+            wgpu::Limits::default()
+        };
+        let adapter_info = GpuAdapterInfo::from(adapter.get_info());
+        log::info!(target: RENDERER_TARGET, "Using GPU adapter {adapter_info}");
 
         let (device, queue) = adapter
             .request_device(
                 &wgpu::DeviceDescriptor {
                     label: Some("device"),
                     required_features: wgpu::Features::empty(),
-                    required_limits: if render_config.use_webgl2_limits {
-                        wgpu::Limits::downlevel_webgl2_defaults()
-                    } else {
-                        wgpu::Limits::default()
-                    },
+                    required_limits,
                     memory_hints: wgpu::MemoryHints::default(),
                 },
                 None,
@@ -160,19 +223,24 @@ where
         let (window_width, window_height) = window_size_fn(&window);
 
         let surface_capabilities = surface.get_capabilities(&adapter);
+        if surface_capabilities.formats.is_empty() {
+            return Err(WgpuRendererError::msg(format!(
+                "GPU adapter {adapter_info} is unable to present to this window"
+            )));
+        }
         let surface_format = surface_capabilities
             .formats
             .iter()
             .copied()
             .find(wgpu::TextureFormat::is_srgb)
             .unwrap_or_else(|| {
-                log::warn!("wgpu adapter does not support any sRGB texture formats; defaulting to first format in this list: {:?}", surface_capabilities.formats);
+                log::warn!(target: RENDERER_TARGET, "wgpu adapter does not support any sRGB texture formats; defaulting to first format in this list: {:?}", surface_capabilities.formats);
                 surface_capabilities.formats[0]
             });
 
         let desired_present_mode = render_config.vsync_mode.to_present_mode();
 
-        if !surface_capabilities.present_modes.contains(&desired_present_mode) {
+        if !is_present_mode_supported(desired_present_mode, &surface_capabilities.present_modes) {
             return Err(WgpuRendererError::msg(unsupported_vsync_mode_error(
                 render_config.vsync_mode,
                 &surface_capabilities.present_modes,
@@ -215,7 +283,13 @@ where
             usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
         });
 
-        let fs_globals = FragmentGlobals::new(display_area, timing_mode);
+        let fs_globals = FragmentGlobals::new(
+            display_area,
+            timing_mode,
+            render_config.crt_geometry,
+            render_config.color_blind_filter,
+            render_config.scanline_intensity,
+        );
         let fs_globals_buffer = device.create_buffer(&wgpu::BufferDescriptor {
             label: Some("fs_globals_buffer"),
             size: FragmentGlobals::SIZE as u64,
@@ -229,12 +303,12 @@ where
             &sampler,
             &fs_globals_buffer,
             surface_format,
-            render_config.shader,
-            render_config.scanlines,
+            &render_config,
         );
 
         Ok(Self {
             render_config,
+            adapter_info,
             timing_mode,
             output_buffer,
             device,
@@ -251,6 +325,8 @@ where
             fs_globals_buffer,
             frame_skip: FrameSkip::ZERO,
             total_frames: 0,
+            pending_warning: None,
+            overlay_callback: None,
             window,
             window_size_fn,
         })
@@ -268,8 +344,45 @@ where
         self.render_config.wgpu_backend
     }
 
+    pub fn adapter_info(&self) -> &GpuAdapterInfo {
+        &self.adapter_info
+    }
+
+    /// Take the most recent non-fatal rendering warning, if any have occurred since the last call.
+    ///
+    /// Warnings are issued when the renderer recovers from a surface error (e.g. after a GPU reset
+    /// or a display change) or when it has to drop a frame because the surface was unavailable.
+    pub fn take_warning(&mut self) -> Option<String> {
+        self.pending_warning.take()
+    }
+
+    /// Set a callback that is invoked after each NES frame is drawn and before it is presented,
+    /// which can record additional render passes (e.g. an egui overlay) on top of the frame.
+    ///
+    /// The callback is only invoked for frames that are actually presented, so it will not be
+    /// called for skipped frames or when the surface could not be acquired. Any render passes it
+    /// records should load rather than clear the surface contents.
+    pub fn set_overlay_callback(&mut self, callback: impl FnMut(OverlayContext<'_>) + 'static) {
+        self.overlay_callback = Some(Box::new(callback));
+    }
+
+    /// Remove the overlay callback, if one is set.
+    pub fn clear_overlay_callback(&mut self) {
+        self.overlay_callback = None;
+    }
+
     pub fn reconfigure_surface(&mut self) {
         let (window_width, window_height) = (self.window_size_fn)(&self.window);
+        self.resize_surface(window_width, window_height);
+    }
+
+    fn resize_surface(&mut self, window_width: u32, window_height: u32) {
+        if window_width == 0 || window_height == 0 {
+            // Window is minimized or otherwise has no area; surfaces cannot be configured with a
+            // size of 0, so wait until the window is resized again
+            return;
+        }
+
         self.surface_config.width = window_width;
         self.surface_config.height = window_height;
 
@@ -284,11 +397,18 @@ where
         );
 
         self.vertices = compute_vertices(window_width, window_height, display_area);
-        self.fs_globals = FragmentGlobals::new(display_area, self.timing_mode);
+        self.fs_globals = FragmentGlobals::new(
+            display_area,
+            self.timing_mode,
+            self.render_config.crt_geometry,
+            self.render_config.color_blind_filter,
+            self.render_config.scanline_intensity,
+        );
     }
 
-    /// Update the rendering config. The `wgpu_backend` and `use_webgl2_limits` fields in the input
-    /// config will be ignored, but all other fields will be updated and immediately applied.
+    /// Update the rendering config. The `wgpu_backend`, `gpu_adapter`, `power_preference`, and
+    /// `use_webgl2_limits` fields in the input config will be ignored, but all other fields will be
+    /// updated and immediately applied.
     ///
     /// # Errors
     ///
@@ -300,6 +420,8 @@ where
     ) -> Result<(), WgpuRendererError> {
         let new_config = RendererConfig {
             wgpu_backend: self.render_config.wgpu_backend,
+            gpu_adapter: self.render_config.gpu_adapter.clone(),
+            power_preference: self.render_config.power_preference,
             use_webgl2_limits: self.render_config.use_webgl2_limits,
             ..render_config
         };
@@ -347,7 +469,9 @@ where
             sample_count: 1,
             dimension: wgpu::TextureDimension::D2,
             format: self.texture_format,
-            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_DST
+                | wgpu::TextureUsages::COPY_SRC,
             view_formats: &[],
         });
 
@@ -357,18 +481,57 @@ where
             &sampler,
             &self.fs_globals_buffer,
             self.surface_config.format,
-            self.render_config.shader,
-            self.render_config.scanlines,
+            &self.render_config,
         );
     }
 
+    fn warn(&mut self, warning: String) {
+        log::warn!(target: RENDERER_TARGET, "{warning}");
+        self.pending_warning = Some(warning);
+    }
+
+    // Returns None if the surface is temporarily unavailable and this frame should be dropped
+    fn acquire_surface_texture(
+        &mut self,
+    ) -> Result<Option<wgpu::SurfaceTexture>, WgpuRendererError> {
+        match self.surface.get_current_texture() {
+            Ok(output) => return Ok(Some(output)),
+            Err(err @ (wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated)) => {
+                self.warn(format!("wgpu surface error ({err}), reconfiguring surface"));
+                self.reconfigure_surface();
+            }
+            Err(wgpu::SurfaceError::Timeout) => {
+                self.warn("Timed out acquiring wgpu surface texture, dropping frame".into());
+                return Ok(None);
+            }
+            Err(wgpu::SurfaceError::OutOfMemory) => return Err(WgpuRendererError::OutOfMemory),
+        }
+
+        // Retry once after reconfiguring; if the surface is still unusable, drop this frame and
+        // try again next frame rather than terminating
+        match self.surface.get_current_texture() {
+            Ok(output) => Ok(Some(output)),
+            Err(
+                err @ (wgpu::SurfaceError::Lost
+                | wgpu::SurfaceError::Outdated
+                | wgpu::SurfaceError::Timeout),
+            ) => {
+                self.warn(format!(
+                    "wgpu surface error ({err}) persisted after reconfiguring, dropping frame"
+                ));
+                Ok(None)
+            }
+            Err(wgpu::SurfaceError::OutOfMemory) => Err(WgpuRendererError::OutOfMemory),
+        }
+    }
+
     fn update_vsync_mode(&mut self, vsync_mode: VSyncMode) -> Result<(), WgpuRendererError> {
         if vsync_mode == self.render_config.vsync_mode {
             return Ok(());
         }
 
         let present_mode = vsync_mode.to_present_mode();
-        if !self.surface_capabilities.present_modes.contains(&present_mode) {
+        if !is_present_mode_supported(present_mode, &self.surface_capabilities.present_modes) {
             return Err(WgpuRendererError::msg(unsupported_vsync_mode_error(
                 vsync_mode,
                 &self.surface_capabilities.present_modes,
@@ -386,6 +549,16 @@ const fn output_buffer_len(timing_mode: TimingMode) -> usize {
     4 * jgnes_core::SCREEN_WIDTH as usize * timing_mode.visible_screen_height() as usize
 }
 
+// The auto modes fall back to a supported mode when the surface is configured, so they are never
+// listed in the surface capabilities
+fn is_present_mode_supported(
+    present_mode: wgpu::PresentMode,
+    supported_modes: &[wgpu::PresentMode],
+) -> bool {
+    matches!(present_mode, wgpu::PresentMode::AutoVsync | wgpu::PresentMode::AutoNoVsync)
+        || supported_modes.contains(&present_mode)
+}
+
 fn unsupported_vsync_mode_error(
     vsync_mode: VSyncMode,
     supported_modes: &[wgpu::PresentMode],
@@ -413,7 +586,9 @@ fn create_texture(
         sample_count: 1,
         dimension: wgpu::TextureDimension::D2,
         format: texture_format,
-        usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+        usage: wgpu::TextureUsages::TEXTURE_BINDING
+            | wgpu::TextureUsages::COPY_DST
+            | wgpu::TextureUsages::COPY_SRC,
         view_formats: &[],
     })
 }
@@ -476,9 +651,36 @@ fn compute_vertices(
         .collect()
 }
 
+// Let the overlay callback record its passes on top of the frame that was drawn with the encoder,
+// then submit everything before presenting
+fn submit_and_present(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    mut encoder: wgpu::CommandEncoder,
+    surface_view: &wgpu::TextureView,
+    surface_config: &wgpu::SurfaceConfiguration,
+    overlay_callback: Option<&mut OverlayCallback>,
+    present: impl FnOnce(),
+) {
+    if let Some(overlay_callback) = overlay_callback {
+        overlay_callback(OverlayContext {
+            device,
+            queue,
+            encoder: &mut encoder,
+            surface_view,
+            surface_format: surface_config.format,
+            surface_size: (surface_config.width, surface_config.height),
+        });
+    }
+
+    queue.submit(iter::once(encoder.finish()));
+    present();
+}
+
 impl<W: HasDisplayHandle + HasWindowHandle> Renderer for WgpuRenderer<W> {
     type Err = WgpuRendererError;
 
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
     fn render_frame(
         &mut self,
         frame_buffer: &FrameBuffer,
@@ -497,6 +699,7 @@ impl<W: HasDisplayHandle + HasWindowHandle> Renderer for WgpuRenderer<W> {
         colors::to_rgba(
             frame_buffer,
             color_emphasis,
+            &self.render_config.palette,
             self.render_config.overscan,
             self.timing_mode,
             &mut self.output_buffer,
@@ -518,7 +721,9 @@ impl<W: HasDisplayHandle + HasWindowHandle> Renderer for WgpuRenderer<W> {
             self.texture.size(),
         );
 
-        let output = self.surface.get_current_texture()?;
+        let Some(output) = self.acquire_surface_texture()? else {
+            return Ok(());
+        };
         let surface_view = output.texture.create_view(&wgpu::TextureViewDescriptor::default());
 
         let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
@@ -527,13 +732,21 @@ impl<W: HasDisplayHandle + HasWindowHandle> Renderer for WgpuRenderer<W> {
 
         self.render_pipeline_state.draw(
             &mut encoder,
+            &self.texture,
             &self.vertex_buffer,
             VERTICES.len() as u32,
             &surface_view,
         );
 
-        self.queue.submit(iter::once(encoder.finish()));
-        output.present();
+        submit_and_present(
+            &self.device,
+            &self.queue,
+            encoder,
+            &surface_view,
+            &self.surface_config,
+            self.overlay_callback.as_mut(),
+            || output.present(),
+        );
 
         Ok(())
     }
@@ -548,4 +761,141 @@ impl<W: HasDisplayHandle + HasWindowHandle> Renderer for WgpuRenderer<W> {
 
         Ok(())
     }
+
+    fn handle_window_resize(&mut self, width: u32, height: u32) {
+        self.resize_surface(width, height);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    // 64 RGBA pixels fill exactly one 256-byte row, which is the required alignment for copying a
+    // texture to a buffer
+    const SURFACE_WIDTH: u32 = 64;
+    const SURFACE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8Unorm;
+
+    fn clear(encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView, color: wgpu::Color) {
+        encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("test_clear_render_pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(color),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+    }
+
+    fn read_first_pixel(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        texture: &wgpu::Texture,
+    ) -> [u8; 4] {
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("test_readback_buffer"),
+            size: 4 * u64::from(SURFACE_WIDTH),
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(4 * SURFACE_WIDTH),
+                    rows_per_image: None,
+                },
+            },
+            texture.size(),
+        );
+        queue.submit(iter::once(encoder.finish()));
+
+        let slice = buffer.slice(..);
+        slice.map_async(wgpu::MapMode::Read, |result| result.expect("buffer should map"));
+        device.poll(wgpu::Maintain::Wait);
+        let bytes = slice.get_mapped_range();
+        [bytes[0], bytes[1], bytes[2], bytes[3]]
+    }
+
+    #[test]
+    fn overlay_callback_runs_before_present() {
+        let instance = wgpu::Instance::default();
+        let Some(adapter) =
+            pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default()))
+        else {
+            eprintln!("No wgpu adapter available; skipping overlay callback test");
+            return;
+        };
+        let (device, queue) = pollster::block_on(adapter.request_device(
+            &wgpu::DeviceDescriptor { required_limits: adapter.limits(), ..Default::default() },
+            None,
+        ))
+        .unwrap();
+
+        // Stands in for the surface texture
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("test_surface_texture"),
+            size: wgpu::Extent3d { width: SURFACE_WIDTH, height: 1, depth_or_array_layers: 1 },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: SURFACE_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let surface_view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let surface_config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            format: SURFACE_FORMAT,
+            width: SURFACE_WIDTH,
+            height: 1,
+            present_mode: wgpu::PresentMode::Fifo,
+            desired_maximum_frame_latency: 2,
+            alpha_mode: wgpu::CompositeAlphaMode::Opaque,
+            view_formats: vec![],
+        };
+
+        // Stands in for drawing the NES frame
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        clear(&mut encoder, &surface_view, wgpu::Color::BLACK);
+
+        let overlay_ran = Rc::new(Cell::new(false));
+        let mut overlay_callback: OverlayCallback = Box::new({
+            let overlay_ran = Rc::clone(&overlay_ran);
+            move |context: OverlayContext<'_>| {
+                assert_eq!(context.surface_format, SURFACE_FORMAT);
+                assert_eq!(context.surface_size, (SURFACE_WIDTH, 1));
+                clear(context.encoder, context.surface_view, wgpu::Color::GREEN);
+                overlay_ran.set(true);
+            }
+        });
+
+        let mut presented = false;
+        submit_and_present(
+            &device,
+            &queue,
+            encoder,
+            &surface_view,
+            &surface_config,
+            Some(&mut overlay_callback),
+            || {
+                assert!(overlay_ran.get(), "overlay callback should run before presenting");
+                assert_eq!(read_first_pixel(&device, &queue, &texture), [0, 255, 0, 255]);
+                presented = true;
+            },
+        );
+        assert!(presented);
+    }
 }
@@ -0,0 +1,24 @@
+struct PaddedBlendStrength {
+    value: f32,
+    // Padding for WebGL
+    _padding0: u32,
+    _padding1: u32,
+    _padding2: u32,
+}
+
+@group(0) @binding(0)
+var current_frame: texture_2d<f32>;
+@group(0) @binding(1)
+var previous_frame: texture_2d<f32>;
+@group(0) @binding(2)
+var<uniform> blend_strength: PaddedBlendStrength;
+
+@fragment
+fn fs_main(@builtin(position) position: vec4f) -> @location(0) vec4f {
+    let texel = vec2u(u32(round(position.x - 0.5)), u32(round(position.y - 0.5)));
+    let current = textureLoad(current_frame, texel, 0);
+    let previous = textureLoad(previous_frame, texel, 0);
+
+    // At full strength the output is an even average of the two frames
+    return mix(current, previous, 0.5 * blend_strength.value);
+}
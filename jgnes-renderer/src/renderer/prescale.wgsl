@@ -10,10 +10,18 @@ struct PaddedRenderScale {
 var texture_in: texture_2d<f32>;
 @group(0) @binding(1)
 var<uniform> render_scale: PaddedRenderScale;
+@group(0) @binding(2)
+var texture_sampler: sampler;
 
 @fragment
 fn fs_main(@builtin(position) position: vec4f) -> @location(0) vec4f {
     let top_left = vec2u(u32(round(position.x - 0.5)), u32(round(position.y - 0.5)));
     let input_position = vec2u(top_left.x / render_scale.value, top_left.y / render_scale.value);
     return textureLoad(texture_in, input_position, 0);
+}
+
+@fragment
+fn fs_linear(@builtin(position) position: vec4f) -> @location(0) vec4f {
+    let output_size = vec2f(textureDimensions(texture_in)) * f32(render_scale.value);
+    return textureSample(texture_in, texture_sampler, position.xy / output_size);
 }
\ No newline at end of file
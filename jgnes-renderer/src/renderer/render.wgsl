@@ -28,10 +28,16 @@ struct FragmentGlobals {
     viewport_width: u32,
     viewport_height: u32,
     nes_visible_height: u32,
-    // Padding required for WebGL, which requires structs to be aligned to 16-byte boundaries
-    _padding0: u32,
-    _padding1: u32,
-    _padding2: u32,
+    // CRT geometry parameters; a value of 0 disables the corresponding effect
+    curvature: f32,
+    corner_radius: f32,
+    vignette: f32,
+    // 0 = none, 1 = protanopia, 2 = deuteranopia, 3 = tritanopia
+    color_blind_filter: u32,
+    // Fraction of the scanline darkening to apply, in [0, 1]
+    scanline_intensity: f32,
+    padding0: u32,
+    padding1: u32,
 }
 
 @group(0) @binding(0)
@@ -41,24 +47,108 @@ var s_diffuse: sampler;
 @group(0) @binding(2)
 var<uniform> fs_globals: FragmentGlobals;
 
+// Apply barrel distortion to texture coordinates in the range [0, 1]
+fn curve_coords(coords: vec2f) -> vec2f {
+    let centered = 2.0 * coords - 1.0;
+    let distorted = centered * (1.0 + fs_globals.curvature * centered.yx * centered.yx);
+    return 0.5 * distorted + 0.5;
+}
+
+// Returns a multiplier in [0, 1] that applies corner rounding and vignette to the given (already
+// curved) texture coordinates. Areas outside of the image are fully masked.
+fn crt_mask(coords: vec2f) -> f32 {
+    let viewport_size = vec2f(f32(fs_globals.viewport_width), f32(fs_globals.viewport_height));
+    let half_size = 0.5 * viewport_size;
+    let radius = fs_globals.corner_radius * min(half_size.x, half_size.y);
+
+    // Signed distance from a rounded rectangle covering the viewport, in pixels
+    let q = abs(coords * viewport_size - half_size) - (half_size - radius);
+    let distance = length(max(q, vec2f(0.0))) + min(max(q.x, q.y), 0.0) - radius;
+    let shape_mask = clamp(0.5 - distance, 0.0, 1.0);
+
+    let edge = clamp(coords * (1.0 - coords), vec2f(0.0), vec2f(0.25));
+    let vignette_base = 16.0 * edge.x * edge.y;
+    let vignette_mask = select(pow(vignette_base, fs_globals.vignette), 1.0, fs_globals.vignette == 0.0);
+
+    return shape_mask * vignette_mask;
+}
+
+fn sample_crt(input: VertexOutput) -> vec3f {
+    let coords = curve_coords(input.texture_coords);
+    let color = textureSample(t_diffuse, s_diffuse, clamp(coords, vec2f(0.0), vec2f(1.0))).rgb;
+    return crt_mask(coords) * color;
+}
+
+fn rgb_to_lms(c: vec3f) -> vec3f {
+    return vec3f(
+        dot(vec3f(17.8824, 43.5161, 4.11935), c),
+        dot(vec3f(3.45565, 27.1554, 3.86714), c),
+        dot(vec3f(0.0299566, 0.184309, 1.46709), c),
+    );
+}
+
+fn lms_to_rgb(c: vec3f) -> vec3f {
+    return vec3f(
+        dot(vec3f(0.0809444479, -0.130504409, 0.116721066), c),
+        dot(vec3f(-0.0102485335, 0.0540193266, -0.113614708), c),
+        dot(vec3f(-0.000365296938, -0.00412161469, 0.693511405), c),
+    );
+}
+
+// Daltonization: simulate the color vision deficiency in LMS space, then redistribute the color
+// information that would be lost into the channels that can still be distinguished
+fn apply_color_blind_filter(color: vec3f) -> vec3f {
+    let mode = fs_globals.color_blind_filter;
+    if mode == 0u {
+        return color;
+    }
+
+    let lms = rgb_to_lms(color);
+    var simulated_lms = lms;
+    if mode == 1u {
+        simulated_lms.x = 2.02344 * lms.y - 2.52581 * lms.z;
+    } else if mode == 2u {
+        simulated_lms.y = 0.494207 * lms.x + 1.24827 * lms.z;
+    } else {
+        simulated_lms.z = -0.395913 * lms.x + 0.801109 * lms.y;
+    }
+
+    let error = color - lms_to_rgb(simulated_lms);
+    let correction = vec3f(0.0, 0.7 * error.x + error.y, 0.7 * error.x + error.z);
+    return clamp(color + correction, vec3f(0.0), vec3f(1.0));
+}
+
 @fragment
 fn basic_fs(input: VertexOutput) -> @location(0) vec4f {
-    return textureSample(t_diffuse, s_diffuse, input.texture_coords);
+    return vec4f(apply_color_blind_filter(sample_crt(input)), 1.0);
+}
+
+// Integral from 0 to y of the scanline gap pattern, where y is in NES rows and the bottom half of
+// every row is a gap
+fn scanline_gap_integral(y: f32) -> f32 {
+    return 0.5 * floor(y) + max(fract(y) - 0.5, 0.0);
 }
 
-fn scanlines_fs(input: VertexOutput, color_multiplier: f32) -> vec4f {
-    let vp_line = u32(round(input.position.y - 0.5)) - fs_globals.viewport_y;
-    let crt_line = 2u * fs_globals.nes_visible_height * vp_line / fs_globals.viewport_height;
+fn scanlines_fs(input: VertexOutput, gap_multiplier: f32) -> vec4f {
+    // Box filter the gap pattern over the range of NES rows covered by this pixel, rather than
+    // assigning each output row to a single NES row, so that every line has the same thickness and
+    // brightness at non-integer scales. Rows follow the curved coordinates if curvature is enabled
+    let nes_height = f32(fs_globals.nes_visible_height);
+    let rows_per_pixel = nes_height / f32(fs_globals.viewport_height);
+    let row = curve_coords(input.texture_coords).y * nes_height;
+    let gap_coverage = (scanline_gap_integral(row + 0.5 * rows_per_pixel)
+        - scanline_gap_integral(row - 0.5 * rows_per_pixel)) / rows_per_pixel;
 
-    let is_odd_line = crt_line % 2u == 0u;
+    let darkening = fs_globals.scanline_intensity * (1.0 - gap_multiplier) * gap_coverage;
 
-    let color = textureSample(t_diffuse, s_diffuse, input.texture_coords).rgb;
-    let filtered_color = select(color_multiplier * color, color, is_odd_line);
+    let color = sample_crt(input);
+    let filtered_color = (1.0 - darkening) * color;
 
-    // Ignore result of scanline filtering if viewport height is less than 2x native frame height
+    // Ignore result of scanline filtering if viewport height is less than 2x native frame height;
+    // gaps would be less than a pixel tall and would only darken the whole image
     let vp_too_small = fs_globals.viewport_height < 2u * fs_globals.nes_visible_height;
     let result = select(filtered_color, color, vp_too_small);
-    return vec4f(result, 1.0);
+    return vec4f(apply_color_blind_filter(result), 1.0);
 }
 
 @fragment
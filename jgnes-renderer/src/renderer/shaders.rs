@@ -1,5 +1,8 @@
 use crate::DisplayArea;
-use crate::config::{RenderScale, Scanlines, Shader};
+use crate::config::{
+    ColorBlindFilter, CrtGeometry, GpuFilterMode, RenderScale, RendererConfig, Scanlines,
+    ShaderEffect, ShaderPass,
+};
 use crate::renderer::Vertex2d;
 use jgnes_core::TimingMode;
 use wgpu::util::DeviceExt;
@@ -34,7 +37,10 @@ impl BlurGlobals {
     }
 }
 
-const FS_GLOBALS_PADDING: usize = 12;
+// Maximum values that the CRT geometry strengths map to in the shader
+const MAX_CURVATURE: f64 = 0.25;
+const MAX_CORNER_RADIUS: f64 = 0.25;
+const MAX_VIGNETTE_EXPONENT: f64 = 0.5;
 
 #[repr(C)]
 #[derive(Debug, Clone, Copy, Default, bytemuck::Pod, bytemuck::Zeroable)]
@@ -44,21 +50,40 @@ pub struct FragmentGlobals {
     viewport_width: u32,
     viewport_height: u32,
     nes_visible_height: u32,
+    curvature: f32,
+    corner_radius: f32,
+    vignette: f32,
+    color_blind_filter: u32,
+    scanline_intensity: f32,
     // WebGL requires types to be a multiple of 16 bytes
-    padding: [u8; FS_GLOBALS_PADDING],
+    padding: [u32; 2],
 }
 
 impl FragmentGlobals {
-    pub const SIZE: usize = 32;
-
-    pub fn new(display_area: DisplayArea, timing_mode: TimingMode) -> Self {
+    pub const SIZE: usize = 48;
+
+    pub fn new(
+        display_area: DisplayArea,
+        timing_mode: TimingMode,
+        crt_geometry: CrtGeometry,
+        color_blind_filter: ColorBlindFilter,
+        scanline_intensity: f64,
+    ) -> Self {
+        let crt_geometry = crt_geometry.clamped();
+        let scanline_intensity =
+            if scanline_intensity.is_nan() { 0.0 } else { scanline_intensity.clamp(0.0, 1.0) };
         Self {
             viewport_x: display_area.x,
             viewport_y: display_area.y,
             viewport_width: display_area.width,
             viewport_height: display_area.height,
             nes_visible_height: timing_mode.visible_screen_height().into(),
-            padding: [0; FS_GLOBALS_PADDING],
+            curvature: (MAX_CURVATURE * crt_geometry.curvature) as f32,
+            corner_radius: (MAX_CORNER_RADIUS * crt_geometry.corner_rounding) as f32,
+            vignette: (MAX_VIGNETTE_EXPONENT * crt_geometry.vignette) as f32,
+            color_blind_filter: color_blind_filter as u32,
+            scanline_intensity: scanline_intensity as f32,
+            padding: [0; 2],
         }
     }
 
@@ -96,7 +121,12 @@ struct TextureScalePipeline {
 }
 
 impl TextureScalePipeline {
-    fn create(device: &wgpu::Device, render_scale: RenderScale, input: &wgpu::Texture) -> Self {
+    fn create(
+        device: &wgpu::Device,
+        render_scale: RenderScale,
+        filter_mode: GpuFilterMode,
+        input: &wgpu::Texture,
+    ) -> Self {
         let render_scale = render_scale.get();
 
         let scaled_texture_size = wgpu::Extent3d {
@@ -122,7 +152,7 @@ impl TextureScalePipeline {
                     binding: 0,
                     visibility: wgpu::ShaderStages::FRAGMENT,
                     ty: wgpu::BindingType::Texture {
-                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
                         view_dimension: wgpu::TextureViewDimension::D2,
                         multisampled: false,
                     },
@@ -138,9 +168,25 @@ impl TextureScalePipeline {
                     },
                     count: None,
                 },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
             ],
         });
 
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("texture_scale_sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: filter_mode.to_wgpu_filter_mode(),
+            min_filter: filter_mode.to_wgpu_filter_mode(),
+            ..wgpu::SamplerDescriptor::default()
+        });
+
         let input_view = input.create_view(&wgpu::TextureViewDescriptor::default());
 
         let render_scale_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
@@ -166,6 +212,10 @@ impl TextureScalePipeline {
                         size: None,
                     }),
                 },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
             ],
         });
 
@@ -177,6 +227,10 @@ impl TextureScalePipeline {
 
         let vertex_shader = device.create_shader_module(wgpu::include_wgsl!("vertex.wgsl"));
         let prescale_shader = device.create_shader_module(wgpu::include_wgsl!("prescale.wgsl"));
+        let fs_main = match filter_mode {
+            GpuFilterMode::NearestNeighbor => "fs_main",
+            GpuFilterMode::LinearInterpolation => "fs_linear",
+        };
         let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
             label: Some("texture_scale_pipeline"),
             layout: Some(&pipeline_layout),
@@ -203,7 +257,7 @@ impl TextureScalePipeline {
             },
             fragment: Some(wgpu::FragmentState {
                 module: &prescale_shader,
-                entry_point: "fs_main".into(),
+                entry_point: fs_main.into(),
                 compilation_options: wgpu::PipelineCompilationOptions::default(),
                 targets: &[Some(wgpu::ColorTargetState {
                     format: scaled_texture.format(),
@@ -553,13 +607,231 @@ impl RenderPipeline {
     }
 }
 
-enum ShaderPipeline {
-    Prescale(TextureScalePipeline),
-    Blur(TextureScalePipeline, BlurPipeline),
+// Blends each frame with the previous frame before any other processing, similar to phosphor
+// persistence on a CRT. This smooths out the flicker that many games use for transparency effects
+struct FrameBlendPipeline {
+    previous_texture: wgpu::Texture,
+    previous_texture_valid: bool,
+    blended_texture: wgpu::Texture,
+    bind_group: wgpu::BindGroup,
+    pipeline: wgpu::RenderPipeline,
+}
+
+impl FrameBlendPipeline {
+    fn create(device: &wgpu::Device, input: &wgpu::Texture, strength: f64) -> Self {
+        let create_texture = |label, usage| {
+            device.create_texture(&wgpu::TextureDescriptor {
+                label: Some(label),
+                size: input.size(),
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: input.format(),
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | usage,
+                view_formats: &[],
+            })
+        };
+        let previous_texture =
+            create_texture("previous_frame_texture", wgpu::TextureUsages::COPY_DST);
+        let blended_texture =
+            create_texture("blended_frame_texture", wgpu::TextureUsages::RENDER_ATTACHMENT);
+
+        let strength_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("frame_blend_strength_buffer"),
+            // Must be padded to 16 bytes for WebGL
+            contents: bytemuck::cast_slice(&[strength as f32, 0.0, 0.0, 0.0]),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+
+        let texture_layout_entry = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        };
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("frame_blend_bind_group_layout"),
+            entries: &[
+                texture_layout_entry(0),
+                texture_layout_entry(1),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let input_view = input.create_view(&wgpu::TextureViewDescriptor::default());
+        let previous_view = previous_texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("frame_blend_bind_group"),
+            layout: &bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&input_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&previous_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                        buffer: &strength_buffer,
+                        offset: 0,
+                        size: None,
+                    }),
+                },
+            ],
+        });
+
+        let vertex_shader = device.create_shader_module(wgpu::include_wgsl!("vertex.wgsl"));
+        let frame_blend_shader =
+            device.create_shader_module(wgpu::include_wgsl!("frameblend.wgsl"));
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("frame_blend_pipeline_layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("frame_blend_pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &vertex_shader,
+                entry_point: "vs_main".into(),
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                buffers: &[],
+            },
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: None,
+                unclipped_depth: false,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                conservative: false,
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &frame_blend_shader,
+                entry_point: "fs_main".into(),
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: blended_texture.format(),
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            multiview: None,
+            cache: None,
+        });
+
+        Self {
+            previous_texture,
+            previous_texture_valid: false,
+            blended_texture,
+            bind_group,
+            pipeline,
+        }
+    }
+
+    fn output(&self) -> &wgpu::Texture {
+        &self.blended_texture
+    }
+
+    fn draw(&mut self, encoder: &mut wgpu::CommandEncoder, input: &wgpu::Texture) {
+        // Don't blend the first frame after (re)creating the pipeline with an all-black frame
+        if !self.previous_texture_valid {
+            encoder.copy_texture_to_texture(
+                input.as_image_copy(),
+                self.previous_texture.as_image_copy(),
+                input.size(),
+            );
+            self.previous_texture_valid = true;
+        }
+
+        {
+            let blended_texture_view =
+                self.blended_texture.create_view(&wgpu::TextureViewDescriptor::default());
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("frame_blend_render_pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &blended_texture_view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+
+            render_pass.set_bind_group(0, &self.bind_group, &[]);
+            render_pass.set_pipeline(&self.pipeline);
+
+            render_pass.draw(0..6, 0..1);
+        }
+
+        encoder.copy_texture_to_texture(
+            input.as_image_copy(),
+            self.previous_texture.as_image_copy(),
+            input.size(),
+        );
+    }
+}
+
+struct ShaderPassPipeline {
+    texture_scale: TextureScalePipeline,
+    blur: Option<BlurPipeline>,
+}
+
+impl ShaderPassPipeline {
+    fn create(device: &wgpu::Device, pass: ShaderPass, input: &wgpu::Texture) -> Self {
+        let texture_scale = TextureScalePipeline::create(device, pass.scale, pass.filter, input);
+        let blur = match pass.effect {
+            ShaderEffect::Stock => None,
+            ShaderEffect::GaussianBlur { stdev, radius } => {
+                Some(BlurPipeline::create(device, &texture_scale.scaled_texture, stdev, radius))
+            }
+        };
+
+        Self { texture_scale, blur }
+    }
+
+    fn output(&self) -> &wgpu::Texture {
+        &self.texture_scale.scaled_texture
+    }
+
+    fn draw(&self, encoder: &mut wgpu::CommandEncoder) {
+        self.texture_scale.draw(encoder);
+        if let Some(blur) = &self.blur {
+            blur.draw(encoder, &self.texture_scale.scaled_texture);
+        }
+    }
 }
 
 pub struct RenderPipelineState {
-    shader_pipeline: Option<ShaderPipeline>,
+    frame_blend: Option<FrameBlendPipeline>,
+    shader_passes: Vec<ShaderPassPipeline>,
     render: RenderPipeline,
 }
 
@@ -570,38 +842,33 @@ impl RenderPipelineState {
         sampler: &wgpu::Sampler,
         fs_globals_buffer: &wgpu::Buffer,
         output_format: wgpu::TextureFormat,
-        shader: Shader,
-        scanlines: Scanlines,
+        render_config: &RendererConfig,
     ) -> Self {
-        let shader_pipeline = match shader {
-            Shader::Prescale(render_scale) if render_scale.get() > 1 => Some(
-                ShaderPipeline::Prescale(TextureScalePipeline::create(device, render_scale, input)),
-            ),
-            Shader::GaussianBlur { prescale_factor, stdev, radius } => {
-                let texture_scale = TextureScalePipeline::create(device, prescale_factor, input);
-                let blur =
-                    BlurPipeline::create(device, &texture_scale.scaled_texture, stdev, radius);
-                Some(ShaderPipeline::Blur(texture_scale, blur))
-            }
-            _ => None,
-        };
+        let frame_blend_strength = render_config.clamped_frame_blend_strength();
+        let frame_blend = (frame_blend_strength > 0.0)
+            .then(|| FrameBlendPipeline::create(device, input, frame_blend_strength));
+        let passes_input = frame_blend.as_ref().map_or(input, FrameBlendPipeline::output);
+
+        // Each pass reads from the output of the previous pass
+        let passes = render_config.shader_passes();
+        let mut shader_passes: Vec<ShaderPassPipeline> = Vec::with_capacity(passes.len());
+        for pass in passes {
+            let pass_input = shader_passes.last().map_or(passes_input, ShaderPassPipeline::output);
+            let pass_pipeline = ShaderPassPipeline::create(device, pass, pass_input);
+            shader_passes.push(pass_pipeline);
+        }
 
-        let render_input = match &shader_pipeline {
-            Some(
-                ShaderPipeline::Prescale(texture_scale) | ShaderPipeline::Blur(texture_scale, _),
-            ) => &texture_scale.scaled_texture,
-            None => input,
-        };
+        let render_input = shader_passes.last().map_or(passes_input, ShaderPassPipeline::output);
         let render = RenderPipeline::create(
             device,
             render_input,
             sampler,
             fs_globals_buffer,
             output_format,
-            scanlines,
+            render_config.scanlines,
         );
 
-        Self { shader_pipeline, render }
+        Self { frame_blend, shader_passes, render }
     }
 
     pub fn recreate_render_pipeline(
@@ -614,22 +881,21 @@ impl RenderPipelineState {
             create_render_pipeline(scanlines, device, &self.render.pipeline_layout, output_format);
     }
 
+    /// Draw a frame; `input` must be the same texture that the pipeline state was created with.
     pub fn draw(
-        &self,
+        &mut self,
         encoder: &mut wgpu::CommandEncoder,
+        input: &wgpu::Texture,
         vertex_buffer: &wgpu::Buffer,
         num_vertices: u32,
         output_view: &wgpu::TextureView,
     ) {
-        match &self.shader_pipeline {
-            Some(ShaderPipeline::Prescale(texture_scale)) => {
-                texture_scale.draw(encoder);
-            }
-            Some(ShaderPipeline::Blur(texture_scale, blur)) => {
-                texture_scale.draw(encoder);
-                blur.draw(encoder, &texture_scale.scaled_texture);
-            }
-            None => {}
+        if let Some(frame_blend) = &mut self.frame_blend {
+            frame_blend.draw(encoder, input);
+        }
+
+        for shader_pass in &self.shader_passes {
+            shader_pass.draw(encoder);
         }
 
         self.render.draw(encoder, vertex_buffer, num_vertices, output_view);
@@ -9,14 +9,20 @@ echo "Building using toolchain '$toolchain'"
 RUSTFLAGS=""
 cargo_args=""
 if [[ -n "${JGNES_WEBGPU:-}" ]]; then
-    echo "Compiling for WebGPU backend"
+    echo "Compiling for WebGPU backend only"
 
-    RUSTFLAGS="--cfg=web_sys_unstable_apis"
     cargo_args="--no-default-features"
 else
-    echo "Compiling for WebGL2 backend"
+    echo "Compiling for WebGPU backend with WebGL2 fallback"
 fi
 
-RUSTFLAGS="$RUSTFLAGS --cfg getrandom_backend=\"wasm_js\" -C target-feature=+atomics,+bulk-memory,+mutable-globals" \
+target_features="+atomics,+bulk-memory,+mutable-globals"
+if [[ -n "${JGNES_WASM_SIMD:-}" ]]; then
+    echo "Enabling WASM SIMD"
+
+    target_features="$target_features,+simd128"
+fi
+
+RUSTFLAGS="$RUSTFLAGS --cfg getrandom_backend=\"wasm_js\" -C target-feature=$target_features" \
 rustup run $toolchain \
 wasm-pack build --target web . "$@" -- $cargo_args -Z build-std=panic_abort,std
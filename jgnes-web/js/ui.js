@@ -16,12 +16,155 @@ export function saveToLocalStorage(key, value) {
     localStorage.setItem(key, value);
 }
 
+const INDEXED_DB_NAME = "jgnes";
+const INDEXED_DB_STORE = "persisted";
+const INDEXED_DB_LIBRARY_STORE = "library";
+
+/** @type {Promise<IDBDatabase> | null} */
+let indexedDbPromise = null;
+
+// The database connection is opened once and cached so that writes issued while the page is being
+// hidden or unloaded don't need to wait on an open request
+/**
+ * @returns {Promise<IDBDatabase>}
+ */
+function getIndexedDb() {
+    if (indexedDbPromise === null) {
+        indexedDbPromise = new Promise((resolve, reject) => {
+            // Version 2 added the ROM library store
+            let request = indexedDB.open(INDEXED_DB_NAME, 2);
+            request.onupgradeneeded = () => {
+                let db = request.result;
+                if (!db.objectStoreNames.contains(INDEXED_DB_STORE)) {
+                    db.createObjectStore(INDEXED_DB_STORE);
+                }
+                if (!db.objectStoreNames.contains(INDEXED_DB_LIBRARY_STORE)) {
+                    db.createObjectStore(INDEXED_DB_LIBRARY_STORE);
+                }
+            };
+            request.onsuccess = () => resolve(request.result);
+            request.onerror = () => reject(request.error);
+        });
+    }
+    return indexedDbPromise;
+}
+
+/**
+ * @param key {string}
+ * @param value {Uint8Array}
+ */
+export function saveToIndexedDb(key, value) {
+    getIndexedDb().then((db) => {
+        db.transaction(INDEXED_DB_STORE, "readwrite").objectStore(INDEXED_DB_STORE).put(value, key);
+    }).catch((err) => {
+        console.error(`Error writing '${key}' to IndexedDB: ${err}`);
+    });
+}
+
+/**
+ * @param key {string}
+ * @returns {Promise<Uint8Array | null>}
+ */
+export async function loadFromIndexedDb(key) {
+    let db = await getIndexedDb();
+    return new Promise((resolve, reject) => {
+        let request = db.transaction(INDEXED_DB_STORE, "readonly").objectStore(INDEXED_DB_STORE).get(key);
+        request.onsuccess = () => resolve(request.result ?? null);
+        request.onerror = () => reject(request.error);
+    });
+}
+
+/**
+ * @param fileName {string}
+ * @param bytes {Uint8Array}
+ */
+export function saveRomToLibrary(fileName, bytes) {
+    getIndexedDb().then((db) => {
+        let transaction = db.transaction(INDEXED_DB_LIBRARY_STORE, "readwrite");
+        transaction.objectStore(INDEXED_DB_LIBRARY_STORE).put(bytes, fileName);
+        transaction.oncomplete = () => refreshRomLibrary();
+    }).catch((err) => {
+        console.error(`Error adding '${fileName}' to the ROM library: ${err}`);
+    });
+}
+
+/**
+ * @param fileName {string}
+ */
+export function removeRomFromLibrary(fileName) {
+    getIndexedDb().then((db) => {
+        let transaction = db.transaction(INDEXED_DB_LIBRARY_STORE, "readwrite");
+        transaction.objectStore(INDEXED_DB_LIBRARY_STORE).delete(fileName);
+        transaction.oncomplete = () => refreshRomLibrary();
+    }).catch((err) => {
+        console.error(`Error removing '${fileName}' from the ROM library: ${err}`);
+    });
+}
+
+/**
+ * @param fileName {string}
+ * @returns {Promise<Uint8Array | null>}
+ */
+export async function loadRomFromLibrary(fileName) {
+    let db = await getIndexedDb();
+    return new Promise((resolve, reject) => {
+        let request = db.transaction(INDEXED_DB_LIBRARY_STORE, "readonly").objectStore(INDEXED_DB_LIBRARY_STORE).get(fileName);
+        request.onsuccess = () => resolve(request.result ?? null);
+        request.onerror = () => reject(request.error);
+    });
+}
+
+// IndexedDB returns keys in sorted order, so the library list is always sorted by file name
+export function refreshRomLibrary() {
+    getIndexedDb().then((db) => new Promise((resolve, reject) => {
+        let request = db.transaction(INDEXED_DB_LIBRARY_STORE, "readonly").objectStore(INDEXED_DB_LIBRARY_STORE).getAllKeys();
+        request.onsuccess = () => resolve(request.result);
+        request.onerror = () => reject(request.error);
+    })).then((fileNames) => {
+        let entries = fileNames.map((fileName) => {
+            let playButton = document.createElement("input");
+            playButton.type = "button";
+            playButton.className = "rom-library-play";
+            playButton.value = fileName;
+            playButton.dataset.romName = fileName;
+
+            let removeButton = document.createElement("input");
+            removeButton.type = "button";
+            removeButton.className = "rom-library-remove";
+            removeButton.value = "Remove";
+            removeButton.dataset.romName = fileName;
+
+            let entry = document.createElement("li");
+            entry.append(playButton, removeButton);
+            return entry;
+        });
+
+        document.getElementById("rom-library-list").replaceChildren(...entries);
+        document.getElementById("rom-library-empty").style.display = entries.length === 0 ? "" : "none";
+    }).catch((err) => {
+        console.error(`Unable to list the ROM library: ${err}`);
+    });
+}
+
 export function initComplete() {
+    getIndexedDb().catch((err) => {
+        console.error(`Unable to open IndexedDB: ${err}`);
+    });
+
     document.getElementById("jgnes").classList.remove("hidden");
     document.getElementById("info-text").classList.remove("hidden");
     document.getElementById("loading-text").remove();
 }
 
+/**
+ * @param message {string}
+ */
+export function showStartupError(message) {
+    let element = document.getElementById("loading-text");
+    element.innerText = message;
+    element.classList.add("startup-error");
+}
+
 /**
  * @param buttonName {string}
  * @param buttonText {string}
@@ -40,6 +183,21 @@ function setInputButtonText(buttonName, buttonText) {
     document.getElementById(buttonId).value = buttonText;
 }
 
+/**
+ * @param hotkeyName {string}
+ * @param buttonText {string}
+ */
+function setHotkeyButtonText(hotkeyName, buttonText) {
+    let buttonId = {
+        "ToggleFullscreen": "toggle-fullscreen-key",
+        "Reset": "reset-key",
+        "SaveState": "save-state-key",
+        "LoadState": "load-state-key",
+        "FastForward": "fast-forward-key",
+    }[hotkeyName];
+    document.getElementById(buttonId).value = buttonText;
+}
+
 /**
  * @param jgnesWebConfig {JgnesWebConfig}
  */
@@ -60,6 +218,23 @@ export function setConfigDisplayValues(jgnesWebConfig) {
         element.checked = element.value === jgnesWebConfig.scanlines();
     });
 
+    let shaderType = jgnesWebConfig.shader_type();
+    document.querySelectorAll("input[name='shader-type']").forEach((element) => {
+        element.checked = element.value === shaderType;
+    });
+
+    document.querySelectorAll("input[name='image-prescale']").forEach((element) => {
+        element.disabled = shaderType === "None";
+    });
+
+    let blurStdev = document.getElementById("blur-stdev");
+    blurStdev.value = jgnesWebConfig.blur_stdev();
+    blurStdev.disabled = shaderType !== "GaussianBlur";
+
+    let blurRadius = document.getElementById("blur-radius");
+    blurRadius.value = jgnesWebConfig.blur_radius();
+    blurRadius.disabled = shaderType !== "GaussianBlur";
+
     document.getElementById("overscan-left").checked = jgnesWebConfig.overscan_left();
     document.getElementById("overscan-right").checked = jgnesWebConfig.overscan_right();
     document.getElementById("overscan-top").checked = jgnesWebConfig.overscan_top();
@@ -68,10 +243,19 @@ export function setConfigDisplayValues(jgnesWebConfig) {
     document.getElementById("audio-enabled").checked = jgnesWebConfig.audio_enabled();
     document.getElementById("audio-sync-enabled").checked = jgnesWebConfig.audio_sync_enabled();
     document.getElementById("silence-triangle-ultrasonic").checked = jgnesWebConfig.silence_ultrasonic_triangle_output();
+    document.getElementById("linear-apu-mixing").checked = jgnesWebConfig.linear_apu_mixing();
+    document.getElementById("ramp-triangle-silencing").checked = jgnesWebConfig.ramp_triangle_silencing();
+    document.getElementById("dmc-declick-filter").checked = jgnesWebConfig.dmc_declick_filter();
+
+    document.querySelectorAll("input[name='audio-latency']").forEach((element) => {
+        element.checked = element.value === jgnesWebConfig.audio_latency();
+    });
 
     document.getElementById("force-integer-scaling").checked = jgnesWebConfig.get_force_integer_scaling();
     document.getElementById("sprite-limit-disabled").checked = jgnesWebConfig.get_remove_sprite_limit();
     document.getElementById("frame-time-sync").checked = jgnesWebConfig.frame_time_sync();
+    document.getElementById("persist-state-on-unload").checked = jgnesWebConfig.persist_state_on_unload();
+    document.getElementById("add-roms-to-library").checked = jgnesWebConfig.add_roms_to_library();
 
     let inputConfig = jgnesWebConfig.inputs();
     setInputButtonText("Up", inputConfig.up());
@@ -82,6 +266,13 @@ export function setConfigDisplayValues(jgnesWebConfig) {
     setInputButtonText("B", inputConfig.b());
     setInputButtonText("Start", inputConfig.start());
     setInputButtonText("Select", inputConfig.select());
+
+    let hotkeyConfig = jgnesWebConfig.hotkeys();
+    setHotkeyButtonText("ToggleFullscreen", hotkeyConfig.toggle_fullscreen());
+    setHotkeyButtonText("Reset", hotkeyConfig.reset());
+    setHotkeyButtonText("SaveState", hotkeyConfig.save_state());
+    setHotkeyButtonText("LoadState", hotkeyConfig.load_state());
+    setHotkeyButtonText("FastForward", hotkeyConfig.fast_forward());
 }
 
 /**
@@ -99,10 +290,40 @@ export function afterInputReconfigure(buttonName, buttonText) {
     setInputButtonText(buttonName, buttonText);
 }
 
+/**
+ * @param hotkeyName {string}
+ * @param buttonText {string}
+ */
+export function afterHotkeyReconfigure(hotkeyName, buttonText) {
+    document.querySelectorAll("input.input-config").forEach((element) => {
+        element.disabled = false;
+    });
+
+    document.querySelector("canvas").classList.remove("grayed-out");
+    document.getElementById("jgnes-wasm").classList.remove("grayed-out");
+
+    setHotkeyButtonText(hotkeyName, buttonText);
+}
+
 export function focusCanvas() {
     document.querySelector("canvas").focus();
 }
 
+/**
+ * @param fileName {string}
+ * @param bytes {Uint8Array}
+ */
+export function downloadFile(fileName, bytes) {
+    let a = document.createElement("a");
+    a.href = window.URL.createObjectURL(new Blob([bytes], {type: "application/octet-stream"}));
+    a.download = fileName;
+
+    document.body.appendChild(a);
+    a.click();
+    document.body.removeChild(a);
+    window.URL.revokeObjectURL(a.href);
+}
+
 /**
  * @param enabled {boolean}
  */
@@ -112,6 +333,15 @@ export function setSaveButtonsEnabled(enabled) {
     });
 }
 
+/**
+ * @param warning {string}
+ */
+export function showRendererWarning(warning) {
+    let element = document.getElementById("renderer-warning");
+    element.innerText = warning;
+    element.classList.remove("hidden");
+}
+
 /**
  * @param visible {boolean}
  */
@@ -1,13 +1,16 @@
+use crate::config::AudioLatency;
 use js_sys::{Array, Atomics, SharedArrayBuffer, Uint32Array};
 use std::cmp;
 use wasm_bindgen::prelude::*;
 use wasm_bindgen_futures::JsFuture;
 use web_sys::{AudioContext, AudioWorkletNode, AudioWorkletNodeOptions, ChannelCountMode};
 
-const HEADER_LEN: u32 = 2;
+const HEADER_LEN: u32 = 4;
 const HEADER_LEN_BYTES: u32 = HEADER_LEN * 4;
 const START_INDEX: u32 = 0;
 const END_INDEX: u32 = 1;
+const UNDERRUN_COUNT_INDEX: u32 = 2;
+const LOW_WATER_INDEX: u32 = 3;
 
 const BUFFER_LEN: u32 = 4096;
 const BUFFER_LEN_BYTES: u32 = BUFFER_LEN * 4;
@@ -19,8 +22,16 @@ pub enum EnqueueResult {
     BufferFull,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueueStats {
+    pub underruns: u32,
+    pub low_water: u32,
+}
+
 // A very simple lock-free queue implemented using a circular buffer.
-// The header contains two 32-bit integers containing the current start and exclusive end indices.
+// The header contains two 32-bit integers containing the current start and exclusive end indices,
+// followed by two 32-bit integers that the audio worklet uses to report how many times it ran out
+// of samples and the lowest queue length it has observed since the stats were last taken.
 #[wasm_bindgen]
 pub struct AudioQueue {
     header: SharedArrayBuffer,
@@ -50,7 +61,9 @@ impl AudioQueue {
     pub fn new() -> Self {
         let header = SharedArrayBuffer::new(HEADER_LEN_BYTES);
         let buffer = SharedArrayBuffer::new(BUFFER_LEN_BYTES);
-        Self::from_buffers(header, buffer)
+        let queue = Self::from_buffers(header, buffer);
+        queue.header_typed.set_index(LOW_WATER_INDEX, u32::MAX);
+        queue
     }
 
     pub fn from_buffers(header: SharedArrayBuffer, buffer: SharedArrayBuffer) -> Self {
@@ -82,6 +95,14 @@ impl AudioQueue {
             if loaded_start <= end { end - loaded_start } else { end + BUFFER_LEN - loaded_start };
         let drain_len = cmp::min(queue_len as usize, out.len());
 
+        if queue_len < Atomics::load(&self.header_typed, LOW_WATER_INDEX)? as u32 {
+            Atomics::store(&self.header_typed, LOW_WATER_INDEX, queue_len as i32)?;
+        }
+
+        if drain_len < out.len() {
+            Atomics::add(&self.header_typed, UNDERRUN_COUNT_INDEX, 1)?;
+        }
+
         let mut start = loaded_start;
         for out_value in out.iter_mut().take(drain_len) {
             let value = Atomics::load(&self.buffer_typed, start)?;
@@ -105,11 +126,123 @@ impl AudioQueue {
         if start <= end { Ok(end - start) } else { Ok(end + BUFFER_LEN - start) }
     }
 
+    /// Discard all queued samples and reset the stats. This must only be called while the audio
+    /// worklet is not consuming samples, e.g. while the audio context is suspended.
+    pub fn clear(&self) -> Result<(), JsValue> {
+        let start = Atomics::load(&self.header_typed, START_INDEX)?;
+        Atomics::store(&self.header_typed, END_INDEX, start)?;
+        self.take_stats()?;
+        Ok(())
+    }
+
+    /// Return the underrun count and low water mark reported by the audio worklet, and reset both.
+    pub fn take_stats(&self) -> Result<QueueStats, JsValue> {
+        let underruns = Atomics::exchange(&self.header_typed, UNDERRUN_COUNT_INDEX, 0)? as u32;
+        let low_water = Atomics::exchange(&self.header_typed, LOW_WATER_INDEX, u32::MAX as i32)?;
+        Ok(QueueStats { underruns, low_water: low_water as u32 })
+    }
+
     fn to_js_value(&self) -> JsValue {
         Array::of2(&self.header, &self.buffer).into()
     }
 }
 
+// Upper bound on the queue threshold, leaving room in the buffer for a full frame of samples
+// (800 at 60Hz / 960 at 50Hz) on top of the threshold
+const MAX_QUEUE_THRESHOLD: u32 = 3072;
+const THRESHOLD_GROW_STEP: u32 = 256;
+const THRESHOLD_SHRINK_STEP: u32 = 64;
+// Roughly 5 seconds of emulated frames
+const TUNING_WINDOW_FRAMES: u32 = 300;
+// Don't shrink the threshold unless the queue never dropped below this many samples during the
+// last window; the worklet consumes 128 samples per callback
+const LOW_WATER_MARGIN: u32 = 256;
+
+/// Dynamically adjusts the audio queue threshold that audio sync waits on.
+///
+/// The threshold grows immediately whenever the audio worklet runs out of samples, and shrinks
+/// slowly while the queue has stayed comfortably above empty, so devices with steady audio
+/// callbacks converge towards lower latency and devices with jittery callbacks converge towards
+/// fewer underruns.
+#[derive(Debug, Clone)]
+pub struct AudioBufferTuner {
+    latency: AudioLatency,
+    threshold: u32,
+    window_frames: u32,
+    window_low_water: u32,
+    window_dropped_samples: u32,
+}
+
+impl AudioBufferTuner {
+    pub fn new(latency: AudioLatency) -> Self {
+        Self {
+            latency,
+            threshold: latency.initial_queue_threshold(),
+            window_frames: 0,
+            window_low_water: u32::MAX,
+            window_dropped_samples: 0,
+        }
+    }
+
+    pub fn threshold(&self) -> u32 {
+        self.threshold
+    }
+
+    pub fn latency(&self) -> AudioLatency {
+        self.latency
+    }
+
+    pub fn record_dropped_sample(&mut self) {
+        self.window_dropped_samples += 1;
+    }
+
+    /// Update the threshold using the stats collected over the last frame.
+    pub fn update(&mut self, stats: QueueStats) {
+        self.window_low_water = cmp::min(self.window_low_water, stats.low_water);
+        self.window_frames += 1;
+
+        if stats.underruns != 0 {
+            self.threshold = cmp::min(self.threshold + THRESHOLD_GROW_STEP, MAX_QUEUE_THRESHOLD);
+            log::debug!(
+                "Audio worklet underran {} times, increased queue threshold to {}",
+                stats.underruns,
+                self.threshold
+            );
+            self.reset_window();
+            return;
+        }
+
+        if self.window_frames < TUNING_WINDOW_FRAMES {
+            return;
+        }
+
+        if self.window_dropped_samples != 0 {
+            log::warn!(
+                "Audio queue was full, dropped {} samples in the last {} frames",
+                self.window_dropped_samples,
+                self.window_frames
+            );
+        }
+
+        let min_threshold = self.latency.min_queue_threshold();
+        if self.window_low_water >= LOW_WATER_MARGIN + THRESHOLD_SHRINK_STEP
+            && self.threshold > min_threshold
+        {
+            self.threshold =
+                cmp::max(self.threshold.saturating_sub(THRESHOLD_SHRINK_STEP), min_threshold);
+            log::debug!("Decreased audio queue threshold to {}", self.threshold);
+        }
+
+        self.reset_window();
+    }
+
+    fn reset_window(&mut self) {
+        self.window_frames = 0;
+        self.window_low_water = u32::MAX;
+        self.window_dropped_samples = 0;
+    }
+}
+
 #[wasm_bindgen]
 pub struct AudioProcessor {
     audio_queue: AudioQueue,
@@ -1,5 +1,9 @@
-use crate::{NesButton, js};
-use jgnes_renderer::config::{AspectRatio, GpuFilterMode, Overscan, RenderScale, Scanlines};
+use crate::{Hotkey, NesButton, js};
+use jgnes_proc_macros::{EnumDisplay, EnumFromStr};
+use jgnes_renderer::config::{
+    AspectRatio, GpuFilterMode, Overscan, RenderScale, Scanlines, Shader,
+};
+use js_sys::Uint8Array;
 use serde::{Deserialize, Serialize};
 use std::cell::{Cell, RefCell};
 use std::rc::Rc;
@@ -10,18 +14,72 @@ fn default_render_scale() -> RenderScale {
     RenderScale::ONE
 }
 
+fn default_blur_stdev() -> f64 {
+    1.5
+}
+
+fn default_blur_radius() -> u32 {
+    16
+}
+
 fn true_fn() -> bool {
     true
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, EnumDisplay, EnumFromStr,
+)]
+pub enum ShaderType {
+    None,
+    #[default]
+    Prescale,
+    GaussianBlur,
+}
+
+/// How much audio latency to trade for protection against audio skips. This sets the starting
+/// point and lower bound of the adaptive audio queue threshold.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, EnumDisplay, EnumFromStr,
+)]
+pub enum AudioLatency {
+    Low,
+    #[default]
+    Normal,
+    Safe,
+}
+
+impl AudioLatency {
+    pub(crate) fn initial_queue_threshold(self) -> u32 {
+        match self {
+            Self::Low => 800,
+            Self::Normal => 1200,
+            Self::Safe => 2048,
+        }
+    }
+
+    pub(crate) fn min_queue_threshold(self) -> u32 {
+        match self {
+            Self::Low => 512,
+            Self::Normal => 800,
+            Self::Safe => 1600,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub(crate) struct ConfigFields {
     #[serde(default)]
     pub(crate) aspect_ratio: AspectRatio,
     #[serde(default)]
     pub(crate) gpu_filter_mode: GpuFilterMode,
+    #[serde(default)]
+    pub(crate) shader_type: ShaderType,
     #[serde(default = "default_render_scale")]
     pub(crate) render_scale: RenderScale,
+    #[serde(default = "default_blur_stdev")]
+    pub(crate) blur_stdev: f64,
+    #[serde(default = "default_blur_radius")]
+    pub(crate) blur_radius: u32,
     #[serde(default)]
     pub(crate) scanlines: Scanlines,
     #[serde(default)]
@@ -35,9 +93,21 @@ pub(crate) struct ConfigFields {
     #[serde(default = "true_fn")]
     pub(crate) audio_sync_enabled: bool,
     #[serde(default)]
+    pub(crate) audio_latency: AudioLatency,
+    #[serde(default)]
     pub(crate) silence_ultrasonic_triangle_output: bool,
     #[serde(default)]
+    pub(crate) linear_apu_mixing: bool,
+    #[serde(default)]
+    pub(crate) ramp_triangle_silencing: bool,
+    #[serde(default)]
+    pub(crate) dmc_declick_filter: bool,
+    #[serde(default)]
     pub(crate) frame_time_sync: bool,
+    #[serde(default)]
+    pub(crate) persist_state_on_unload: bool,
+    #[serde(default = "true_fn")]
+    pub(crate) add_roms_to_library: bool,
 }
 
 impl ConfigFields {
@@ -46,6 +116,18 @@ impl ConfigFields {
     fn save(&self) {
         save_to_local_storage(Self::LOCAL_STORAGE_KEY, self);
     }
+
+    pub(crate) fn shader(&self) -> Shader {
+        match self.shader_type {
+            ShaderType::None => Shader::None,
+            ShaderType::Prescale => Shader::Prescale(self.render_scale),
+            ShaderType::GaussianBlur => Shader::GaussianBlur {
+                prescale_factor: self.render_scale,
+                stdev: self.blur_stdev,
+                radius: self.blur_radius,
+            },
+        }
+    }
 }
 
 impl Default for ConfigFields {
@@ -152,17 +234,114 @@ impl Default for InputConfig {
     }
 }
 
+// Allow unsafe_derive_deserialize for the same reason as InputConfig
+#[allow(clippy::unsafe_derive_deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+#[wasm_bindgen]
+pub struct HotkeyConfig {
+    pub(crate) toggle_fullscreen: Option<KeyCode>,
+    pub(crate) reset: Option<KeyCode>,
+    pub(crate) save_state: Option<KeyCode>,
+    pub(crate) load_state: Option<KeyCode>,
+    pub(crate) fast_forward: Option<KeyCode>,
+}
+
+impl HotkeyConfig {
+    const LOCAL_STORAGE_KEY: &'static str = "__hotkeys";
+
+    pub fn set_key(&mut self, hotkey: Hotkey, keycode: Option<KeyCode>) {
+        let field = match hotkey {
+            Hotkey::ToggleFullscreen => &mut self.toggle_fullscreen,
+            Hotkey::Reset => &mut self.reset,
+            Hotkey::SaveState => &mut self.save_state,
+            Hotkey::LoadState => &mut self.load_state,
+            Hotkey::FastForward => &mut self.fast_forward,
+        };
+        *field = keycode;
+
+        save_to_local_storage(Self::LOCAL_STORAGE_KEY, self);
+    }
+}
+
+fn fmt_hotkey(keycode: Option<KeyCode>) -> String {
+    keycode.map_or("<None>".into(), |keycode| format!("{keycode:?}"))
+}
+
+#[wasm_bindgen]
+impl HotkeyConfig {
+    pub fn toggle_fullscreen(&self) -> String {
+        fmt_hotkey(self.toggle_fullscreen)
+    }
+
+    pub fn reset(&self) -> String {
+        fmt_hotkey(self.reset)
+    }
+
+    pub fn save_state(&self) -> String {
+        fmt_hotkey(self.save_state)
+    }
+
+    pub fn load_state(&self) -> String {
+        fmt_hotkey(self.load_state)
+    }
+
+    pub fn fast_forward(&self) -> String {
+        fmt_hotkey(self.fast_forward)
+    }
+}
+
+impl Default for HotkeyConfig {
+    fn default() -> Self {
+        Self {
+            toggle_fullscreen: Some(KeyCode::F8),
+            reset: Some(KeyCode::F3),
+            save_state: Some(KeyCode::F5),
+            load_state: Some(KeyCode::F6),
+            fast_forward: Some(KeyCode::Tab),
+        }
+    }
+}
+
+/// The most recent SRAM contents and (optionally) emulator state for the currently running ROM,
+/// written to `IndexedDB` when the page is hidden or about to unload.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct UnloadSnapshot {
+    pub(crate) file_name: String,
+    pub(crate) sram: Option<Vec<u8>>,
+    pub(crate) state: Option<Vec<u8>>,
+}
+
+impl UnloadSnapshot {
+    pub(crate) fn sram_key(file_name: &str) -> String {
+        format!("{file_name}.sav")
+    }
+
+    pub(crate) fn state_key(file_name: &str) -> String {
+        format!("{file_name}.state")
+    }
+}
+
 #[derive(Debug, Clone)]
 #[wasm_bindgen]
 pub struct JgnesWebConfig {
     pub(crate) inputs: Rc<RefCell<InputConfig>>,
+    pub(crate) hotkeys: Rc<RefCell<HotkeyConfig>>,
     pub(crate) fields: Rc<RefCell<ConfigFields>>,
     pub(crate) reconfig_input_request: Rc<Cell<Option<NesButton>>>,
+    pub(crate) reconfig_hotkey_request: Rc<Cell<Option<Hotkey>>>,
+    pub(crate) clear_hotkey_request: Rc<Cell<Option<Hotkey>>>,
     pub(crate) open_file_requested: Rc<Cell<bool>>,
+    pub(crate) open_patched_file_requested: Rc<Cell<bool>>,
+    pub(crate) open_library_rom_request: Rc<RefCell<Option<String>>>,
     pub(crate) reset_requested: Rc<Cell<bool>>,
+    pub(crate) download_save_file_requested: Rc<Cell<bool>>,
     pub(crate) upload_save_file_requested: Rc<Cell<bool>>,
+    pub(crate) export_state_requested: Rc<Cell<bool>>,
+    pub(crate) import_state_requested: Rc<Cell<bool>>,
     pub(crate) restore_defaults_requested: Rc<Cell<bool>>,
     pub(crate) current_filename: Rc<RefCell<String>>,
+    pub(crate) unload_snapshot: Rc<RefCell<Option<UnloadSnapshot>>>,
 }
 
 const NTSC: &str = "Ntsc";
@@ -181,12 +360,17 @@ impl JgnesWebConfig {
             .and_then(|config_str| serde_json::from_str::<InputConfig>(&config_str).ok())
             .unwrap_or_default();
 
+        let hotkeys = js::loadFromLocalStorage(HotkeyConfig::LOCAL_STORAGE_KEY)
+            .and_then(|config_str| serde_json::from_str::<HotkeyConfig>(&config_str).ok())
+            .unwrap_or_default();
+
         let fields = js::loadFromLocalStorage(ConfigFields::LOCAL_STORAGE_KEY)
             .and_then(|config_str| serde_json::from_str::<ConfigFields>(&config_str).ok())
             .unwrap_or_default();
 
         Self {
             inputs: Rc::new(RefCell::new(inputs)),
+            hotkeys: Rc::new(RefCell::new(hotkeys)),
             fields: Rc::new(RefCell::new(fields)),
             ..Self::default()
         }
@@ -247,6 +431,50 @@ impl JgnesWebConfig {
         fields.save();
     }
 
+    pub fn shader_type(&self) -> String {
+        format!("{}", self.fields.borrow().shader_type)
+    }
+
+    pub fn set_shader_type(&self, shader_type: &str) {
+        let Ok(shader_type) = shader_type.parse() else {
+            return;
+        };
+        let mut fields = self.fields.borrow_mut();
+        fields.shader_type = shader_type;
+        fields.save();
+    }
+
+    pub fn blur_stdev(&self) -> f64 {
+        self.fields.borrow().blur_stdev
+    }
+
+    /// Returns false if the value is invalid (negative or NaN), in which case the config is not
+    /// updated.
+    pub fn set_blur_stdev(&self, value: f64) -> bool {
+        if value.is_nan() || value.is_sign_negative() {
+            return false;
+        }
+        let mut fields = self.fields.borrow_mut();
+        fields.blur_stdev = value;
+        fields.save();
+        true
+    }
+
+    pub fn blur_radius(&self) -> u32 {
+        self.fields.borrow().blur_radius
+    }
+
+    /// Returns false if the value is invalid (0), in which case the config is not updated.
+    pub fn set_blur_radius(&self, value: u32) -> bool {
+        if value == 0 {
+            return false;
+        }
+        let mut fields = self.fields.borrow_mut();
+        fields.blur_radius = value;
+        fields.save();
+        true
+    }
+
     pub fn scanlines(&self) -> String {
         format!("{}", self.fields.borrow().scanlines)
     }
@@ -336,6 +564,19 @@ impl JgnesWebConfig {
         fields.save();
     }
 
+    pub fn audio_latency(&self) -> String {
+        format!("{}", self.fields.borrow().audio_latency)
+    }
+
+    pub fn set_audio_latency(&self, audio_latency: &str) {
+        let Ok(audio_latency) = audio_latency.parse() else {
+            return;
+        };
+        let mut fields = self.fields.borrow_mut();
+        fields.audio_latency = audio_latency;
+        fields.save();
+    }
+
     pub fn silence_ultrasonic_triangle_output(&self) -> bool {
         self.fields.borrow().silence_ultrasonic_triangle_output
     }
@@ -346,6 +587,36 @@ impl JgnesWebConfig {
         fields.save();
     }
 
+    pub fn linear_apu_mixing(&self) -> bool {
+        self.fields.borrow().linear_apu_mixing
+    }
+
+    pub fn set_linear_apu_mixing(&self, value: bool) {
+        let mut fields = self.fields.borrow_mut();
+        fields.linear_apu_mixing = value;
+        fields.save();
+    }
+
+    pub fn ramp_triangle_silencing(&self) -> bool {
+        self.fields.borrow().ramp_triangle_silencing
+    }
+
+    pub fn set_ramp_triangle_silencing(&self, value: bool) {
+        let mut fields = self.fields.borrow_mut();
+        fields.ramp_triangle_silencing = value;
+        fields.save();
+    }
+
+    pub fn dmc_declick_filter(&self) -> bool {
+        self.fields.borrow().dmc_declick_filter
+    }
+
+    pub fn set_dmc_declick_filter(&self, value: bool) {
+        let mut fields = self.fields.borrow_mut();
+        fields.dmc_declick_filter = value;
+        fields.save();
+    }
+
     pub fn frame_time_sync(&self) -> bool {
         self.fields.borrow().frame_time_sync
     }
@@ -356,17 +627,66 @@ impl JgnesWebConfig {
         fields.save();
     }
 
+    pub fn persist_state_on_unload(&self) -> bool {
+        self.fields.borrow().persist_state_on_unload
+    }
+
+    pub fn set_persist_state_on_unload(&self, value: bool) {
+        let mut fields = self.fields.borrow_mut();
+        fields.persist_state_on_unload = value;
+        fields.save();
+    }
+
+    pub fn add_roms_to_library(&self) -> bool {
+        self.fields.borrow().add_roms_to_library
+    }
+
+    pub fn set_add_roms_to_library(&self, value: bool) {
+        let mut fields = self.fields.borrow_mut();
+        fields.add_roms_to_library = value;
+        fields.save();
+    }
+
+    /// Write the current SRAM and emulator state snapshot to `IndexedDB`. Called when the page is
+    /// hidden or about to unload. The writes are asynchronous and may not complete if the page is
+    /// being closed, so the most recent snapshot is not guaranteed to be persisted.
+    pub fn persist_on_unload(&self) {
+        let Some(snapshot) = &*self.unload_snapshot.borrow() else {
+            return;
+        };
+
+        if let Some(sram) = &snapshot.sram {
+            js::saveToIndexedDb(
+                &UnloadSnapshot::sram_key(&snapshot.file_name),
+                Uint8Array::from(sram.as_slice()),
+            );
+        }
+
+        if let Some(state) = &snapshot.state {
+            js::saveToIndexedDb(
+                &UnloadSnapshot::state_key(&snapshot.file_name),
+                Uint8Array::from(state.as_slice()),
+            );
+        }
+    }
+
     pub fn inputs(&self) -> InputConfig {
         self.inputs.borrow().clone()
     }
 
+    pub fn hotkeys(&self) -> HotkeyConfig {
+        self.hotkeys.borrow().clone()
+    }
+
     pub fn restore_defaults(&self) {
         self.restore_defaults_requested.set(true);
 
         *self.inputs.borrow_mut() = InputConfig::default();
+        *self.hotkeys.borrow_mut() = HotkeyConfig::default();
         *self.fields.borrow_mut() = ConfigFields::default();
 
         save_to_local_storage(InputConfig::LOCAL_STORAGE_KEY, &InputConfig::default());
+        save_to_local_storage(HotkeyConfig::LOCAL_STORAGE_KEY, &HotkeyConfig::default());
         save_to_local_storage(ConfigFields::LOCAL_STORAGE_KEY, &ConfigFields::default());
 
         js::setConfigDisplayValues(self.clone());
@@ -376,18 +696,49 @@ impl JgnesWebConfig {
         self.reconfig_input_request.set(Some(button));
     }
 
+    pub fn reconfigure_hotkey(&self, hotkey: Hotkey) {
+        self.reconfig_hotkey_request.set(Some(hotkey));
+    }
+
+    pub fn clear_hotkey(&self, hotkey: Hotkey) {
+        self.hotkeys.borrow_mut().set_key(hotkey, None);
+        self.clear_hotkey_request.set(Some(hotkey));
+
+        js::setConfigDisplayValues(self.clone());
+    }
+
     pub fn open_new_file(&self) {
         self.open_file_requested.set(true);
     }
 
+    pub fn open_new_patched_file(&self) {
+        self.open_patched_file_requested.set(true);
+    }
+
+    pub fn open_library_rom(&self, file_name: String) {
+        *self.open_library_rom_request.borrow_mut() = Some(file_name);
+    }
+
     pub fn reset_emulator(&self) {
         self.reset_requested.set(true);
     }
 
+    pub fn download_save_file(&self) {
+        self.download_save_file_requested.set(true);
+    }
+
     pub fn upload_save_file(&self) {
         self.upload_save_file_requested.set(true);
     }
 
+    pub fn export_state(&self) {
+        self.export_state_requested.set(true);
+    }
+
+    pub fn import_state(&self) {
+        self.import_state_requested.set(true);
+    }
+
     #[must_use]
     pub fn current_filename(&self) -> String {
         self.current_filename.borrow().clone()
@@ -413,13 +764,22 @@ impl Default for JgnesWebConfig {
     fn default() -> Self {
         JgnesWebConfig {
             inputs: Rc::default(),
+            hotkeys: Rc::default(),
             fields: Rc::default(),
             reconfig_input_request: Rc::new(Cell::new(None)),
+            reconfig_hotkey_request: Rc::new(Cell::new(None)),
+            clear_hotkey_request: Rc::new(Cell::new(None)),
             open_file_requested: Rc::new(Cell::new(false)),
+            open_patched_file_requested: Rc::new(Cell::new(false)),
+            open_library_rom_request: Rc::new(RefCell::new(None)),
             reset_requested: Rc::new(Cell::new(false)),
+            download_save_file_requested: Rc::new(Cell::new(false)),
             upload_save_file_requested: Rc::new(Cell::new(false)),
+            export_state_requested: Rc::new(Cell::new(false)),
+            import_state_requested: Rc::new(Cell::new(false)),
             restore_defaults_requested: Rc::new(Cell::new(false)),
             current_filename: Rc::new(RefCell::new(String::new())),
+            unload_snapshot: Rc::new(RefCell::new(None)),
         }
     }
 }
@@ -1,6 +1,4 @@
 use crate::config::JgnesWebConfig;
-use base64::Engine;
-use base64::engine::general_purpose;
 use jgnes_proc_macros::build_time_pretty_str;
 use js_sys::Uint8Array;
 use wasm_bindgen::prelude::*;
@@ -18,9 +16,28 @@ extern "C" {
     /// Set an item in local storage.
     pub fn saveToLocalStorage(key: &str, value: &str);
 
+    /// Write an item to `IndexedDB`. The write completes asynchronously, so it may not finish if the
+    /// page is closed immediately afterward.
+    pub fn saveToIndexedDb(key: &str, value: Uint8Array);
+
+    /// Retrieve an item from `IndexedDB`. Resolves to null if no item exists for this key.
+    #[wasm_bindgen(catch)]
+    pub async fn loadFromIndexedDb(key: &str) -> Result<JsValue, JsValue>;
+
+    /// Add a ROM to the library in `IndexedDB`, replacing any existing ROM with the same file name.
+    /// The library list in the page is refreshed once the write completes.
+    pub fn saveRomToLibrary(fileName: &str, bytes: Uint8Array);
+
+    /// Retrieve a ROM from the library. Resolves to null if the library has no ROM with this name.
+    #[wasm_bindgen(catch)]
+    pub async fn loadRomFromLibrary(fileName: &str) -> Result<JsValue, JsValue>;
+
     /// Remove the 'Loading...' text and unhide the UI.
     pub fn initComplete();
 
+    /// Replace the 'Loading...' text with an error explaining why the emulator could not start.
+    pub fn showStartupError(message: &str);
+
     /// Set all config displays to the values in the given config.
     pub fn setConfigDisplayValues(jgnesWebConfig: JgnesWebConfig);
 
@@ -28,30 +45,24 @@ extern "C" {
     /// configured key.
     pub fn afterInputReconfigure(buttonName: &str, buttonText: &str);
 
+    /// Same as `afterInputReconfigure` but for hotkey buttons.
+    pub fn afterHotkeyReconfigure(hotkeyName: &str, buttonText: &str);
+
     /// Focus the canvas element. Useful because the emulator can only receive inputs while the
     /// canvas has focus, and clicking on any UI element will take focus away.
     pub fn focusCanvas();
 
+    /// Prompt the browser to download the given bytes as a file.
+    pub fn downloadFile(fileName: &str, bytes: Uint8Array);
+
     /// Enable or disable the Download Save / Upload Save buttons.
     pub fn setSaveButtonsEnabled(enabled: bool);
 
     /// Set whether the cursor is visible when over the canvas element.
     pub fn setCursorVisible(visible: bool);
-}
 
-#[must_use]
-#[wasm_bindgen]
-pub fn b64_to_bytes(s: &str) -> Option<Uint8Array> {
-    match general_purpose::STANDARD.decode(s) {
-        Ok(bytes) => {
-            let array = Uint8Array::new_with_length(bytes.len() as u32);
-            for (i, byte) in bytes.iter().copied().enumerate() {
-                array.set_index(i as u32, byte);
-            }
-            Some(array)
-        }
-        Err(_) => None,
-    }
+    /// Display a non-fatal rendering warning below the canvas.
+    pub fn showRendererWarning(warning: &str);
 }
 
 #[must_use]
@@ -4,28 +4,38 @@ mod audio;
 mod config;
 mod js;
 
-use crate::audio::{AudioQueue, EnqueueResult};
-use crate::config::{ConfigFields, InputConfig};
+use crate::audio::{AudioBufferTuner, AudioQueue, EnqueueResult};
+use crate::config::{AudioLatency, ConfigFields, HotkeyConfig, InputConfig, UnloadSnapshot};
 use base64::Engine;
 use base64::engine::GeneralPurpose;
 use config::JgnesWebConfig;
 use jgnes_core::audio::{DownsampleAction, DownsampleCounter, LowPassFilter};
+use jgnes_core::patch::PatchFormat;
 use jgnes_core::{
-    AudioPlayer, ColorEmphasis, Emulator, EmulatorConfig, EmulatorCreateArgs, InputPoller,
-    JoypadState, Renderer, SaveWriter, TickEffect, TimingMode,
+    AudioPlayer, ClassifyError, ColorEmphasis, Emulator, EmulatorConfig, EmulatorCreateArgs,
+    ErrorSeverity, InitializationError, InputPoller, JoypadState, RamInit, Renderer, SaveWriter,
+    TickEffect, TimingMode,
 };
 use jgnes_proc_macros::EnumDisplay;
 use jgnes_renderer::WgpuRenderer;
-use jgnes_renderer::config::{RendererConfig, Shader, VSyncMode, WgpuBackend};
-use js_sys::Promise;
+use jgnes_renderer::colors::Palette;
+use jgnes_renderer::config::{
+    AspectRatio, ColorBlindFilter, CrtGeometry, PowerPreference, RendererConfig, VSyncMode,
+    WgpuBackend,
+};
+use js_sys::{Promise, Uint8Array};
 use rfd::AsyncFileDialog;
 use std::array;
 use std::cell::{Cell, RefCell};
 use std::collections::HashMap;
+use std::convert::Infallible;
+use std::fmt;
+use std::fmt::{Display, Formatter};
 use std::rc::Rc;
 use std::time::Duration;
 use wasm_bindgen::prelude::*;
-use web_sys::{AudioContext, AudioContextOptions};
+use web_sys::{AudioContext, AudioContextOptions, Performance};
+use winit::dpi::PhysicalSize;
 use winit::event::{ElementState, Event, KeyEvent, WindowEvent};
 use winit::event_loop::{ControlFlow, EventLoop, EventLoopProxy};
 use winit::keyboard::{KeyCode, PhysicalKey};
@@ -34,7 +44,8 @@ use winit::window::{Fullscreen, Window, WindowAttributes, WindowId};
 
 const BASE64_ENGINE: GeneralPurpose = base64::engine::general_purpose::STANDARD;
 
-const FULLSCREEN_KEY: KeyCode = KeyCode::F8;
+// Same extension as states exported from the native GUI
+const SAVE_STATE_EXTENSION: &str = "state";
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, EnumDisplay)]
 #[wasm_bindgen]
@@ -49,22 +60,32 @@ pub enum NesButton {
     Select,
 }
 
-fn alert_and_panic(s: &str) -> ! {
-    js::alert(s);
-    panic!("{s}")
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, EnumDisplay)]
+#[wasm_bindgen]
+pub enum Hotkey {
+    ToggleFullscreen,
+    Reset,
+    SaveState,
+    LoadState,
+    FastForward,
 }
 
 struct WebSaveWriter {
     file_name: String,
+    unload_snapshot: Rc<RefCell<Option<UnloadSnapshot>>>,
 }
 
 impl SaveWriter for WebSaveWriter {
-    type Err = ();
+    type Err = Infallible;
 
     #[inline]
     fn persist_sram(&mut self, sram: &[u8]) -> Result<(), Self::Err> {
         let sram_b64 = BASE64_ENGINE.encode(sram);
         js::saveToLocalStorage(&self.file_name, &sram_b64);
+
+        if let Some(snapshot) = &mut *self.unload_snapshot.borrow_mut() {
+            snapshot.sram = Some(sram.to_vec());
+        }
         Ok(())
     }
 }
@@ -73,10 +94,12 @@ impl SaveWriter for WebSaveWriter {
 enum InputHandlerState {
     RunningEmulator,
     WaitingForInput(NesButton),
+    WaitingForHotkey(Hotkey),
 }
 
 struct InputHandler {
     button_mapping: HashMap<KeyCode, Vec<NesButton>>,
+    hotkey_mapping: HashMap<KeyCode, Vec<Hotkey>>,
     p1_joypad_state: Rc<Cell<JoypadState>>,
     handler_state: InputHandlerState,
 }
@@ -100,11 +123,29 @@ impl InputHandler {
         mapping
     }
 
-    fn new(config: &InputConfig) -> Self {
+    fn hotkey_mapping_for(config: &HotkeyConfig) -> HashMap<KeyCode, Vec<Hotkey>> {
+        let mut mapping: HashMap<KeyCode, Vec<Hotkey>> = HashMap::new();
+        for (hotkey, keycode) in [
+            (Hotkey::ToggleFullscreen, config.toggle_fullscreen),
+            (Hotkey::Reset, config.reset),
+            (Hotkey::SaveState, config.save_state),
+            (Hotkey::LoadState, config.load_state),
+            (Hotkey::FastForward, config.fast_forward),
+        ] {
+            if let Some(keycode) = keycode {
+                mapping.entry(keycode).or_default().push(hotkey);
+            }
+        }
+
+        mapping
+    }
+
+    fn new(config: &InputConfig, hotkey_config: &HotkeyConfig) -> Self {
         let initial_mapping = Self::input_mapping_for(config);
 
         Self {
             button_mapping: initial_mapping,
+            hotkey_mapping: Self::hotkey_mapping_for(hotkey_config),
             p1_joypad_state: Rc::default(),
             handler_state: InputHandlerState::RunningEmulator,
         }
@@ -123,8 +164,9 @@ impl InputHandler {
         }
     }
 
-    fn update_all_mappings(&mut self, config: &InputConfig) {
+    fn update_all_mappings(&mut self, config: &InputConfig, hotkey_config: &HotkeyConfig) {
         self.button_mapping = Self::input_mapping_for(config);
+        self.hotkey_mapping = Self::hotkey_mapping_for(hotkey_config);
         self.p1_joypad_state.set(JoypadState::default());
     }
 
@@ -135,14 +177,34 @@ impl InputHandler {
         self.button_mapping.retain(|_, buttons| !buttons.is_empty());
     }
 
-    fn handle_window_event(&mut self, event: &WindowEvent, config: &JgnesWebConfig) {
+    fn remove_mapping_for_hotkey(&mut self, hotkey: Hotkey) {
+        for hotkeys in self.hotkey_mapping.values_mut() {
+            hotkeys.retain(|other_hotkey| *other_hotkey != hotkey);
+        }
+        self.hotkey_mapping.retain(|_, hotkeys| !hotkeys.is_empty());
+    }
+
+    // Returns any hotkeys that were pressed or released by this event
+    fn handle_window_event(
+        &mut self,
+        event: &WindowEvent,
+        config: &JgnesWebConfig,
+    ) -> Vec<(Hotkey, ElementState)> {
+        let mut hotkey_events = Vec::new();
+
         if let WindowEvent::KeyboardInput {
-            event: KeyEvent { physical_key: PhysicalKey::Code(keycode), state, .. },
+            event: KeyEvent { physical_key: PhysicalKey::Code(keycode), state, repeat, .. },
             ..
         } = event
         {
             match self.handler_state {
                 InputHandlerState::RunningEmulator => {
+                    if !*repeat {
+                        for &hotkey in self.hotkey_mapping.get(keycode).unwrap_or(&vec![]) {
+                            hotkey_events.push((hotkey, *state));
+                        }
+                    }
+
                     for &button in self.button_mapping.get(keycode).unwrap_or(&vec![]) {
                         let mut joypad_state = self.p1_joypad_state.get();
                         let field = Self::get_field_mut(&mut joypad_state, button);
@@ -164,8 +226,20 @@ impl InputHandler {
                         js::afterInputReconfigure(&format!("{button}"), &format!("{keycode:?}"));
                     }
                 }
+                InputHandlerState::WaitingForHotkey(hotkey) => {
+                    if *state == ElementState::Pressed {
+                        self.hotkey_mapping.entry(*keycode).or_default().push(hotkey);
+                        self.handler_state = InputHandlerState::RunningEmulator;
+
+                        config.hotkeys.borrow_mut().set_key(hotkey, Some(*keycode));
+
+                        js::afterHotkeyReconfigure(&format!("{hotkey}"), &format!("{keycode:?}"));
+                    }
+                }
             }
         }
+
+        hotkey_events
     }
 }
 
@@ -190,31 +264,69 @@ struct WebAudioPlayer {
     low_pass_filter: LowPassFilter,
     downsample_counter: DownsampleCounter,
     audio_enabled: bool,
+    fast_forwarding: bool,
+    buffer_tuner: AudioBufferTuner,
 }
 
 impl WebAudioPlayer {
-    fn new(audio_queue: AudioQueue, audio_enabled: bool) -> Self {
+    fn new(audio_queue: AudioQueue, audio_enabled: bool, audio_latency: AudioLatency) -> Self {
         Self {
             audio_queue,
             low_pass_filter: LowPassFilter::new(),
             downsample_counter: DownsampleCounter::new(AUDIO_OUTPUT_FREQUENCY, DISPLAY_RATE, true),
             audio_enabled,
+            fast_forwarding: false,
+            buffer_tuner: AudioBufferTuner::new(audio_latency),
         }
     }
+
+    // Stats are always taken so that underruns from while tuning was disabled (e.g. no ROM loaded)
+    // don't count against the threshold once tuning resumes
+    fn update_buffer_tuning(&mut self, tuning_enabled: bool) -> Result<(), JsValue> {
+        let stats = self.audio_queue.take_stats()?;
+        if tuning_enabled {
+            self.buffer_tuner.update(stats);
+        }
+        Ok(())
+    }
 }
 
 const AUDIO_OUTPUT_FREQUENCY: f64 = 48000.0;
 const DISPLAY_RATE: f64 = 60.0;
 
-// This can cause audio lag in some configurations, but setting the threshold much lower than this
-// is likely to cause audio skips
-const AUDIO_QUEUE_THRESHOLD: u32 = 1200;
+const FAST_FORWARD_MULTIPLIER: u32 = 3;
+
+// The state snapshot that gets persisted when the page is hidden or unloaded is refreshed about
+// once per second
+const UNLOAD_SNAPSHOT_INTERVAL_FRAMES: u32 = 60;
+
+const RECOVERABLE_ERROR_LOG_INTERVAL: u64 = 1000;
+
+// Browsers throttle timers in hidden tabs anyway, so there is no point in checking more often
+const HIDDEN_PAGE_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+#[derive(Debug)]
+struct WebAudioError(JsValue);
+
+impl Display for WebAudioError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Error enqueueing audio sample: {:?}", self.0)
+    }
+}
+
+impl ClassifyError for WebAudioError {
+    fn severity(&self) -> ErrorSeverity {
+        // The sample that failed to enqueue is dropped
+        ErrorSeverity::Recoverable
+    }
+}
 
 impl AudioPlayer for WebAudioPlayer {
-    type Err = JsValue;
+    type Err = WebAudioError;
 
     fn push_sample(&mut self, sample: f64) -> Result<(), Self::Err> {
-        if !self.audio_enabled {
+        // Audio would be played back faster than real time while fast forwarding, so drop it
+        if !self.audio_enabled || self.fast_forwarding {
             return Ok(());
         }
 
@@ -222,8 +334,10 @@ impl AudioPlayer for WebAudioPlayer {
 
         if self.downsample_counter.increment() == DownsampleAction::OutputSample {
             let output_sample = self.low_pass_filter.output_sample();
-            if self.audio_queue.push_if_space(output_sample as f32)? == EnqueueResult::BufferFull {
-                log::warn!("Audio queue is full, dropping sample");
+            if self.audio_queue.push_if_space(output_sample as f32).map_err(WebAudioError)?
+                == EnqueueResult::BufferFull
+            {
+                self.buffer_tuner.record_dropped_sample();
             }
         }
 
@@ -248,12 +362,157 @@ struct State {
     input_handler: InputHandler,
     current_config: ConfigFields,
     user_interacted: bool,
+    save_state_bytes: Option<Vec<u8>>,
+    fast_forwarding: bool,
+    frames_since_snapshot: u32,
+    recoverable_error_count: u64,
+    canvas_fit: Option<CanvasFit>,
+    page_hidden: bool,
+}
+
+// Everything that determines the canvas size, so that the canvas is only resized when one of
+// these changes
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct CanvasFit {
+    container_width: i32,
+    container_height: i32,
+    scale_factor: f64,
+    aspect_ratio: AspectRatio,
+    force_integer_scaling: bool,
+    timing_mode: TimingMode,
 }
 
 impl State {
     fn window_id(&self) -> WindowId {
         self.renderer.borrow().window().id()
     }
+
+    // Pause emulation and audio while the page is hidden, since browsers throttle hidden pages and
+    // emulation would fall behind anyway. Returns whether the page is currently hidden.
+    fn update_page_visibility(
+        &mut self,
+        performance: &Performance,
+        next_frame_time: &mut f64,
+    ) -> bool {
+        let hidden =
+            web_sys::window().and_then(|win| win.document()).is_some_and(|doc| doc.hidden());
+        if hidden == self.page_hidden {
+            return hidden;
+        }
+        self.page_hidden = hidden;
+
+        if hidden {
+            log::info!("Page hidden, pausing emulation");
+            let _: Promise = self.audio_ctx.suspend().unwrap();
+
+            // Key releases are not delivered while the page is hidden
+            self.handle_hotkey(Hotkey::FastForward, ElementState::Released);
+        } else {
+            log::info!("Page visible, resuming emulation");
+
+            // Start over with an empty audio queue and the next frame due now, rather than playing
+            // stale samples or running frames back-to-back to catch up. The audio context is still
+            // suspended, so the audio worklet is not reading from the queue.
+            self.audio_player.borrow().audio_queue.clear().unwrap();
+            *next_frame_time = performance.now();
+            if self.user_interacted {
+                let _: Promise = self.audio_ctx.resume().unwrap();
+            }
+        }
+
+        hidden
+    }
+
+    // Size the canvas to the largest display area that fits in its container, so that the canvas
+    // follows the browser window while keeping the configured aspect ratio and integer scaling
+    fn fit_canvas_to_container(&mut self) {
+        let renderer = self.renderer.borrow();
+        let window = renderer.window();
+        if window.fullscreen().is_some() {
+            // Fit again after leaving fullscreen
+            self.canvas_fit = None;
+            return;
+        }
+
+        let Some(container) = web_sys::window()
+            .and_then(|win| win.document())
+            .and_then(|doc| doc.get_element_by_id("jgnes-wasm"))
+        else {
+            return;
+        };
+
+        let fit = CanvasFit {
+            container_width: container.client_width(),
+            container_height: container.client_height(),
+            scale_factor: window.scale_factor(),
+            aspect_ratio: self.current_config.aspect_ratio,
+            force_integer_scaling: self.current_config.force_integer_scaling,
+            timing_mode: self.emulator.as_ref().map_or(TimingMode::Ntsc, Emulator::timing_mode),
+        };
+        if self.canvas_fit == Some(fit) {
+            return;
+        }
+        self.canvas_fit = Some(fit);
+
+        let display_area = jgnes_renderer::determine_display_area(
+            (f64::from(fit.container_width) * fit.scale_factor).round() as u32,
+            (f64::from(fit.container_height) * fit.scale_factor).round() as u32,
+            fit.aspect_ratio,
+            fit.force_integer_scaling,
+            fit.timing_mode,
+        );
+        let _ = window.request_inner_size(PhysicalSize::new(
+            display_area.width.max(1),
+            display_area.height.max(1),
+        ));
+    }
+
+    fn handle_hotkey(&mut self, hotkey: Hotkey, element_state: ElementState) {
+        match (hotkey, element_state) {
+            (Hotkey::ToggleFullscreen, ElementState::Pressed) => {
+                let mut renderer = self.renderer.borrow_mut();
+                let window = renderer.window_mut();
+
+                let new_fullscreen = match window.fullscreen() {
+                    None => Some(Fullscreen::Borderless(None)),
+                    Some(_) => None,
+                };
+                window.set_fullscreen(new_fullscreen);
+            }
+            (Hotkey::Reset, ElementState::Pressed) => {
+                if let Some(emulator) = &mut self.emulator {
+                    emulator.soft_reset();
+                }
+            }
+            (Hotkey::SaveState, ElementState::Pressed) => {
+                if let Some(emulator) = &self.emulator {
+                    let mut state_bytes = Vec::new();
+                    match emulator.save_state(&mut state_bytes) {
+                        Ok(()) => {
+                            self.save_state_bytes = Some(state_bytes);
+                        }
+                        Err(err) => {
+                            log::error!("Error saving state: {err}");
+                        }
+                    }
+                }
+            }
+            (Hotkey::LoadState, ElementState::Pressed) => {
+                if let (Some(emulator), Some(state_bytes)) =
+                    (&mut self.emulator, &self.save_state_bytes)
+                {
+                    if let Err(err) = emulator.load_state(state_bytes.as_slice()) {
+                        log::error!("Error loading state: {err}");
+                    }
+                }
+            }
+            (Hotkey::FastForward, _) => {
+                self.fast_forwarding = element_state == ElementState::Pressed;
+                self.audio_player.borrow_mut().fast_forwarding = self.fast_forwarding;
+            }
+            (_, ElementState::Released) => {}
+        }
+    }
 }
 
 /// Initialize `console_error_panic_hook` and `console_log`.
@@ -282,14 +541,104 @@ fn set_rom_file_name_text(file_name: &str) {
         .expect("Unable to write file name into the DOM");
 }
 
-async fn open_file_in_event_loop(event_loop_proxy: EventLoopProxy<JgnesUserEvent>) {
+async fn open_file_in_event_loop(
+    event_loop_proxy: EventLoopProxy<JgnesUserEvent>,
+    patch: bool,
+    add_to_library: bool,
+) {
     let Some(file) = AsyncFileDialog::new().add_filter("nes", &["nes"]).pick_file().await else {
         return;
     };
 
-    let file_bytes = file.read().await;
+    let mut file_bytes = file.read().await;
     let file_name = file.file_name();
-    event_loop_proxy.send_event(JgnesUserEvent::RomFileLoaded { file_bytes, file_name }).unwrap();
+
+    // Patched ROMs are not added to the library because the library is keyed by the file name of
+    // the unpatched ROM
+    if add_to_library && !patch {
+        js::saveRomToLibrary(&file_name, Uint8Array::from(file_bytes.as_slice()));
+    }
+
+    if patch {
+        let Some(patch_file) =
+            AsyncFileDialog::new().add_filter("patch", PatchFormat::extensions()).pick_file().await
+        else {
+            return;
+        };
+
+        let patch_file_name = patch_file.file_name();
+        match jgnes_core::patch::apply(&file_bytes, &patch_file.read().await) {
+            Ok(patched_bytes) => {
+                log::info!("Applied patch '{patch_file_name}' to '{file_name}'");
+                file_bytes = patched_bytes;
+            }
+            Err(err) => {
+                js::alert(&format!("Error applying patch '{patch_file_name}': {err}"));
+                return;
+            }
+        }
+    }
+
+    send_rom_file_loaded(&event_loop_proxy, file_bytes, file_name).await;
+}
+
+async fn open_library_rom_in_event_loop(
+    event_loop_proxy: EventLoopProxy<JgnesUserEvent>,
+    file_name: String,
+) {
+    let file_bytes = match js::loadRomFromLibrary(&file_name).await {
+        Ok(value) => value.dyn_into::<Uint8Array>().ok().map(|array| array.to_vec()),
+        Err(err) => {
+            log::error!("Error reading '{file_name}' from the ROM library: {err:?}");
+            None
+        }
+    };
+    let Some(file_bytes) = file_bytes else {
+        js::alert(&format!("'{file_name}' is no longer in the ROM library"));
+        return;
+    };
+
+    send_rom_file_loaded(&event_loop_proxy, file_bytes, file_name).await;
+}
+
+async fn send_rom_file_loaded(
+    event_loop_proxy: &EventLoopProxy<JgnesUserEvent>,
+    file_bytes: Vec<u8>,
+    file_name: String,
+) {
+    let persisted_sram = load_from_indexed_db(&UnloadSnapshot::sram_key(&file_name)).await;
+    let persisted_state = load_from_indexed_db(&UnloadSnapshot::state_key(&file_name)).await;
+
+    event_loop_proxy
+        .send_event(JgnesUserEvent::RomFileLoaded {
+            file_bytes,
+            file_name,
+            persisted_sram,
+            persisted_state,
+        })
+        .unwrap();
+}
+
+async fn load_from_indexed_db(key: &str) -> Option<Vec<u8>> {
+    match js::loadFromIndexedDb(key).await {
+        Ok(value) => value.dyn_into::<Uint8Array>().ok().map(|array| array.to_vec()),
+        Err(err) => {
+            log::error!("Error reading '{key}' from IndexedDB: {err:?}");
+            None
+        }
+    }
+}
+
+// Reads SRAM from the running emulator rather than from local storage, since local storage is only
+// updated when the game writes to SRAM
+fn download_save_file(emulator: Option<&WebEmulator>, file_name: &str) {
+    let Some(sram) = emulator.and_then(WebEmulator::persistent_ram) else {
+        js::alert(&format!("'{file_name}' does not have persistent saves"));
+        return;
+    };
+
+    let sav_file_name = format!("{}.sav", file_name.strip_suffix(".nes").unwrap_or(file_name));
+    js::downloadFile(&sav_file_name, Uint8Array::from(sram.as_ref()));
 }
 
 async fn upload_save_file(event_loop_proxy: EventLoopProxy<JgnesUserEvent>, file_name: String) {
@@ -303,15 +652,56 @@ async fn upload_save_file(event_loop_proxy: EventLoopProxy<JgnesUserEvent>, file
     event_loop_proxy.send_event(JgnesUserEvent::SaveFileLoaded { save_bytes, file_name }).unwrap();
 }
 
+// Save states are portable, so exported states can also be imported into the native GUI
+fn export_state(emulator: Option<&WebEmulator>, file_name: &str) {
+    let Some(emulator) = emulator else {
+        return;
+    };
+
+    let mut state_bytes = Vec::new();
+    if let Err(err) = emulator.save_state(&mut state_bytes) {
+        js::alert(&format!("Error saving state: {err}"));
+        return;
+    }
+
+    let state_file_name =
+        format!("{}.{SAVE_STATE_EXTENSION}", file_name.strip_suffix(".nes").unwrap_or(file_name));
+    js::downloadFile(&state_file_name, Uint8Array::from(state_bytes.as_slice()));
+}
+
+async fn import_state_file(event_loop_proxy: EventLoopProxy<JgnesUserEvent>) {
+    let Some(state_file) =
+        AsyncFileDialog::new().add_filter("save state", &[SAVE_STATE_EXTENSION]).pick_file().await
+    else {
+        return;
+    };
+
+    let state_bytes = state_file.read().await;
+
+    event_loop_proxy.send_event(JgnesUserEvent::StateImported { state_bytes }).unwrap();
+}
+
 #[derive(Debug, Clone)]
 enum JgnesUserEvent {
-    RomFileLoaded { file_bytes: Vec<u8>, file_name: String },
-    SaveFileLoaded { save_bytes: Vec<u8>, file_name: String },
+    RomFileLoaded {
+        file_bytes: Vec<u8>,
+        file_name: String,
+        persisted_sram: Option<Vec<u8>>,
+        persisted_state: Option<Vec<u8>>,
+    },
+    SaveFileLoaded {
+        save_bytes: Vec<u8>,
+        file_name: String,
+    },
+    StateImported {
+        state_bytes: Vec<u8>,
+    },
 }
 
+// WebGPU is used if the browser supports it, with WebGL2 as a fallback
 #[cfg(feature = "webgl")]
 const fn get_wgpu_backend() -> WgpuBackend {
-    WgpuBackend::OpenGl
+    WgpuBackend::BrowserAuto
 }
 
 #[cfg(not(feature = "webgl"))]
@@ -319,24 +709,47 @@ const fn get_wgpu_backend() -> WgpuBackend {
     WgpuBackend::WebGpu
 }
 
+#[cfg(feature = "webgl")]
+const SUPPORTED_GRAPHICS_APIS: &str = "WebGPU or WebGL2";
+
+#[cfg(not(feature = "webgl"))]
+const SUPPORTED_GRAPHICS_APIS: &str = "WebGPU";
+
+fn renderer_init_error_message(err: &impl Display) -> String {
+    format!(
+        "jgnes could not start because graphics could not be initialized. This browser may not \
+         support {SUPPORTED_GRAPHICS_APIS}, or hardware acceleration may be disabled. Try enabling \
+         hardware acceleration in the browser settings, updating the browser and graphics drivers, \
+         or using a recent version of Chrome, Edge, Firefox, or Safari.\n\nError: {err}"
+    )
+}
+
 fn new_renderer_config(fields: &ConfigFields) -> RendererConfig {
     let wgpu_backend = get_wgpu_backend();
     RendererConfig {
         vsync_mode: VSyncMode::Enabled,
         wgpu_backend,
+        gpu_adapter: None,
+        power_preference: PowerPreference::default(),
         gpu_filter_mode: fields.gpu_filter_mode,
-        shader: Shader::Prescale(fields.render_scale),
+        shader: fields.shader(),
+        // Shader presets are TOML files selected by path, which only the CLI and the desktop GUI
+        // support
+        shader_preset: None,
+        crt_geometry: CrtGeometry::default(),
+        color_blind_filter: ColorBlindFilter::default(),
+        frame_blend_strength: 0.0,
+        palette: Palette::default(),
         scanlines: fields.scanlines,
+        scanline_intensity: 1.0,
         aspect_ratio: fields.aspect_ratio,
         overscan: fields.overscan,
         forced_integer_height_scaling: fields.force_integer_scaling,
-        use_webgl2_limits: wgpu_backend == WgpuBackend::OpenGl,
+        // Only applied if the browser falls back to WebGL2
+        use_webgl2_limits: true,
     }
 }
 
-const CANVAS_WIDTH: u32 = 878;
-const CANVAS_HEIGHT: u32 = 672;
-
 #[allow(clippy::missing_panics_doc)]
 #[wasm_bindgen]
 pub async fn run_emulator(config: JgnesWebConfig) {
@@ -359,11 +772,21 @@ pub async fn run_emulator(config: JgnesWebConfig) {
         .expect("Couldn't append canvas to document body");
 
     let renderer_config = new_renderer_config(&config.fields.borrow());
-    let renderer =
-        WgpuRenderer::from_window(window, |_| (CANVAS_WIDTH, CANVAS_HEIGHT), renderer_config)
-            .await
-            .map_err(|err| alert_and_panic(&err.to_string()))
-            .unwrap();
+    let renderer = match WgpuRenderer::from_window(
+        window,
+        |window| window.inner_size().into(),
+        renderer_config,
+    )
+    .await
+    {
+        Ok(renderer) => renderer,
+        Err(err) => {
+            log::error!("Error initializing renderer: {err}");
+            js::showStartupError(&renderer_init_error_message(&err));
+            return;
+        }
+    };
+    log::info!("Rendering with {}", renderer.adapter_info());
     let renderer = Rc::new(RefCell::new(renderer));
 
     let audio_ctx_options = AudioContextOptions::new();
@@ -372,10 +795,14 @@ pub async fn run_emulator(config: JgnesWebConfig) {
     let audio_queue = AudioQueue::new();
     let _audio_worklet = audio::initialize_audio_worklet(&audio_ctx, &audio_queue).await.unwrap();
 
-    let audio_player = WebAudioPlayer::new(audio_queue, config.fields.borrow().audio_enabled);
+    let audio_player = WebAudioPlayer::new(
+        audio_queue,
+        config.fields.borrow().audio_enabled,
+        config.fields.borrow().audio_latency,
+    );
     let audio_player = Rc::new(RefCell::new(audio_player));
 
-    let input_handler = InputHandler::new(&config.inputs.borrow());
+    let input_handler = InputHandler::new(&config.inputs.borrow(), &config.hotkeys.borrow());
 
     let state = State {
         emulator: None,
@@ -385,6 +812,12 @@ pub async fn run_emulator(config: JgnesWebConfig) {
         input_handler,
         current_config: config.fields.borrow().clone(),
         user_interacted: false,
+        save_state_bytes: None,
+        fast_forwarding: false,
+        frames_since_snapshot: 0,
+        recoverable_error_count: 0,
+        canvas_fit: None,
+        page_hidden: false,
     };
 
     js::initComplete();
@@ -407,24 +840,57 @@ fn run_event_loop(event_loop: EventLoop<JgnesUserEvent>, config: JgnesWebConfig,
     event_loop
         .run(move |event, elwt| {
             match event {
-                Event::UserEvent(JgnesUserEvent::RomFileLoaded { file_bytes, file_name }) => {
-                    let sav_bytes = load_sav_bytes(&file_name);
+                Event::UserEvent(JgnesUserEvent::RomFileLoaded {
+                    file_bytes,
+                    file_name,
+                    persisted_sram,
+                    persisted_state,
+                }) => {
+                    // Local storage is written every time SRAM changes, so it should only be
+                    // missing SRAM if the user cleared it
+                    let sav_bytes = load_sav_bytes(&file_name).or(persisted_sram);
+
+                    *config.unload_snapshot.borrow_mut() = Some(UnloadSnapshot {
+                        file_name: file_name.clone(),
+                        sram: sav_bytes.clone(),
+                        state: None,
+                    });
 
                     let input_poller = WebInputPoller {
                         p1_joypad_state: Rc::clone(&state.input_handler.p1_joypad_state),
                     };
-                    let save_writer = WebSaveWriter { file_name: file_name.clone() };
+                    let save_writer = WebSaveWriter {
+                        file_name: file_name.clone(),
+                        unload_snapshot: Rc::clone(&config.unload_snapshot),
+                    };
 
                     match Emulator::create(EmulatorCreateArgs {
                         rom_bytes: file_bytes,
                         sav_bytes,
                         forced_timing_mode: None,
+                        forced_mapper_number: None,
+                        ram_init: RamInit::default(),
                         renderer: Rc::clone(&state.renderer),
                         audio_player: Rc::clone(&state.audio_player),
                         input_poller,
                         save_writer,
                     }) {
-                        Ok(emulator) => {
+                        Ok(mut emulator) => {
+                            if let Some(persisted_state) = persisted_state
+                                .filter(|_| state.current_config.persist_state_on_unload)
+                            {
+                                match emulator.load_state(persisted_state.as_slice()) {
+                                    Ok(()) => log::info!("Restored state for '{file_name}'"),
+                                    Err(err) => {
+                                        log::error!(
+                                            "Unable to restore state for '{file_name}': {err}"
+                                        );
+                                    }
+                                }
+                            }
+
+                            state.frames_since_snapshot = 0;
+
                             if !state.user_interacted {
                                 state.user_interacted = true;
 
@@ -437,6 +903,13 @@ fn run_event_loop(event_loop: EventLoop<JgnesUserEvent>, config: JgnesWebConfig,
                             js::focusCanvas();
                             state.emulator = Some(emulator);
                         }
+                        Err(InitializationError::CartridgeLoad { source }) => {
+                            js::alert(&format!(
+                                "Error initializing emulator: {source}\n\n{}",
+                                source.diagnostics
+                            ));
+                            log::error!("Error initializing emulator: {source}");
+                        }
                         Err(err) => {
                             js::alert(&format!("Error initializing emulator: {err}"));
                             log::error!("Error initializing emulator: {err}");
@@ -453,30 +926,25 @@ fn run_event_loop(event_loop: EventLoop<JgnesUserEvent>, config: JgnesWebConfig,
 
                     js::focusCanvas();
                 }
+                Event::UserEvent(JgnesUserEvent::StateImported { state_bytes }) => {
+                    if let Some(emulator) = &mut state.emulator {
+                        if let Err(err) = emulator.load_state(state_bytes.as_slice()) {
+                            js::alert(&format!("Error loading state: {err}"));
+                        }
+                    }
+
+                    js::focusCanvas();
+                }
                 Event::WindowEvent { event: win_event, window_id }
                     if window_id == state.window_id() =>
                 {
-                    state.input_handler.handle_window_event(&win_event, &config);
+                    let hotkey_events =
+                        state.input_handler.handle_window_event(&win_event, &config);
+                    for (hotkey, element_state) in hotkey_events {
+                        state.handle_hotkey(hotkey, element_state);
+                    }
 
                     match win_event {
-                        WindowEvent::KeyboardInput {
-                            event:
-                                KeyEvent {
-                                    physical_key: PhysicalKey::Code(FULLSCREEN_KEY),
-                                    state: ElementState::Pressed,
-                                    ..
-                                },
-                            ..
-                        } => {
-                            let mut renderer = state.renderer.borrow_mut();
-                            let window = renderer.window_mut();
-
-                            let new_fullscreen = match window.fullscreen() {
-                                None => Some(Fullscreen::Borderless(None)),
-                                Some(_) => None,
-                            };
-                            window.set_fullscreen(new_fullscreen);
-                        }
                         WindowEvent::Resized(_) => {
                             let mut renderer = state.renderer.borrow_mut();
                             renderer.reconfigure_surface();
@@ -491,6 +959,12 @@ fn run_event_loop(event_loop: EventLoop<JgnesUserEvent>, config: JgnesWebConfig,
                     }
                 }
                 Event::AboutToWait => {
+                    state.fit_canvas_to_container();
+
+                    if let Some(warning) = state.renderer.borrow_mut().take_warning() {
+                        js::showRendererWarning(&warning);
+                    }
+
                     if state.current_config != *config.fields.borrow() {
                         state.current_config = config.fields.borrow().clone();
 
@@ -499,13 +973,35 @@ fn run_event_loop(event_loop: EventLoop<JgnesUserEvent>, config: JgnesWebConfig,
                             .borrow_mut()
                             .update_render_config(new_renderer_config(&state.current_config))
                             .expect("Failed to update wgpu renderer config");
-                        state.audio_player.borrow_mut().audio_enabled =
-                            state.current_config.audio_enabled;
+                        let mut audio_player = state.audio_player.borrow_mut();
+                        audio_player.audio_enabled = state.current_config.audio_enabled;
+                        if audio_player.buffer_tuner.latency() != state.current_config.audio_latency
+                        {
+                            audio_player.buffer_tuner =
+                                AudioBufferTuner::new(state.current_config.audio_latency);
+                        }
                     }
 
                     if config.open_file_requested.replace(false) {
                         wasm_bindgen_futures::spawn_local(open_file_in_event_loop(
                             event_loop_proxy.clone(),
+                            false,
+                            state.current_config.add_roms_to_library,
+                        ));
+                    }
+
+                    if config.open_patched_file_requested.replace(false) {
+                        wasm_bindgen_futures::spawn_local(open_file_in_event_loop(
+                            event_loop_proxy.clone(),
+                            true,
+                            state.current_config.add_roms_to_library,
+                        ));
+                    }
+
+                    if let Some(file_name) = config.open_library_rom_request.borrow_mut().take() {
+                        wasm_bindgen_futures::spawn_local(open_library_rom_in_event_loop(
+                            event_loop_proxy.clone(),
+                            file_name,
                         ));
                     }
 
@@ -515,6 +1011,10 @@ fn run_event_loop(event_loop: EventLoop<JgnesUserEvent>, config: JgnesWebConfig,
                         }
                     }
 
+                    if config.download_save_file_requested.replace(false) {
+                        download_save_file(state.emulator.as_ref(), &config.current_filename());
+                    }
+
                     if config.upload_save_file_requested.replace(false) {
                         wasm_bindgen_futures::spawn_local(upload_save_file(
                             event_loop_proxy.clone(),
@@ -522,11 +1022,23 @@ fn run_event_loop(event_loop: EventLoop<JgnesUserEvent>, config: JgnesWebConfig,
                         ));
                     }
 
+                    if config.export_state_requested.replace(false) {
+                        export_state(state.emulator.as_ref(), &config.current_filename());
+                    }
+
+                    if config.import_state_requested.replace(false) {
+                        wasm_bindgen_futures::spawn_local(import_state_file(
+                            event_loop_proxy.clone(),
+                        ));
+                    }
+
                     if config.restore_defaults_requested.replace(false) {
                         // JgnesWebConfig::restore_defaults updates the actual config values, but
                         // updating the InputConfig does not automatically update the input mappings in
                         // the InputHandler
-                        state.input_handler.update_all_mappings(&config.inputs.borrow());
+                        state
+                            .input_handler
+                            .update_all_mappings(&config.inputs.borrow(), &config.hotkeys.borrow());
                     }
 
                     if let Some(button) = config.reconfig_input_request.replace(None) {
@@ -535,21 +1047,41 @@ fn run_event_loop(event_loop: EventLoop<JgnesUserEvent>, config: JgnesWebConfig,
                             InputHandlerState::WaitingForInput(button);
                     }
 
-                    // Don't tick the emulator while waiting for input configuration
-                    if !matches!(
-                        state.input_handler.handler_state,
-                        InputHandlerState::WaitingForInput(_)
-                    ) {
+                    if let Some(hotkey) = config.reconfig_hotkey_request.replace(None) {
+                        state.input_handler.remove_mapping_for_hotkey(hotkey);
+                        state.input_handler.handler_state =
+                            InputHandlerState::WaitingForHotkey(hotkey);
+                    }
+
+                    if let Some(hotkey) = config.clear_hotkey_request.replace(None) {
+                        state.input_handler.remove_mapping_for_hotkey(hotkey);
+                        if hotkey == Hotkey::FastForward {
+                            state.handle_hotkey(hotkey, ElementState::Released);
+                        }
+                    }
+
+                    if state.update_page_visibility(&performance, &mut next_frame_time) {
+                        elwt.set_control_flow(ControlFlow::WaitUntil(
+                            web_time::Instant::now() + HIDDEN_PAGE_POLL_INTERVAL,
+                        ));
+                    } else if state.input_handler.handler_state
+                        == InputHandlerState::RunningEmulator
+                    {
+                        // Don't tick the emulator while waiting for input configuration.
                         // If audio sync is enabled, only run the emulator if the audio queue isn't filling up
                         let audio_sync_enabled = state.current_config.audio_sync_enabled;
                         let audio_queue_len =
                             state.audio_player.borrow().audio_queue.len().unwrap();
-                        let should_wait_for_audio =
-                            audio_sync_enabled && audio_queue_len > AUDIO_QUEUE_THRESHOLD;
+                        let audio_queue_threshold =
+                            state.audio_player.borrow().buffer_tuner.threshold();
+                        let should_wait_for_audio = audio_sync_enabled
+                            && !state.fast_forwarding
+                            && audio_queue_len > audio_queue_threshold;
 
                         let frame_time_sync = state.current_config.frame_time_sync;
                         let now = performance.now();
-                        let should_wait_for_frame_time = frame_time_sync && now < next_frame_time;
+                        let should_wait_for_frame_time =
+                            frame_time_sync && !state.fast_forwarding && now < next_frame_time;
 
                         if !should_wait_for_audio && !should_wait_for_frame_time {
                             elwt.set_control_flow(ControlFlow::Poll);
@@ -576,18 +1108,39 @@ fn run_event_loop(event_loop: EventLoop<JgnesUserEvent>, config: JgnesWebConfig,
                                         silence_ultrasonic_triangle_output: state
                                             .current_config
                                             .silence_ultrasonic_triangle_output,
+                                        linear_apu_mixing: state.current_config.linear_apu_mixing,
+                                        ramp_triangle_silencing: state
+                                            .current_config
+                                            .ramp_triangle_silencing,
+                                        dmc_declick_filter: state.current_config.dmc_declick_filter,
+                                        ..EmulatorConfig::default()
                                     };
 
-                                    // Tick the emulator until it renders the next frame
-                                    loop {
-                                        match emulator.tick(&emulator_config) {
-                                            Ok(TickEffect::None) => {}
-                                            Ok(TickEffect::FrameRendered) => {
-                                                elwt.set_control_flow(ControlFlow::WaitUntil(
-                                                    web_time::Instant::now()
-                                                        + Duration::from_millis(1),
-                                                ));
-                                                break;
+                                    let frames = if state.fast_forwarding {
+                                        FAST_FORWARD_MULTIPLIER
+                                    } else {
+                                        1
+                                    };
+
+                                    // Tick the emulator until it renders the next frame (or the
+                                    // next several frames if fast forwarding)
+                                    let mut frames_rendered = 0;
+                                    while frames_rendered < frames {
+                                        let tick_effect = match emulator.tick(&emulator_config) {
+                                            Ok(tick_effect) => tick_effect,
+                                            Err(err) if err.is_recoverable() => {
+                                                // Recoverable errors can repeat many times per
+                                                // second, so only occasionally log them
+                                                if state
+                                                    .recoverable_error_count
+                                                    .is_multiple_of(RECOVERABLE_ERROR_LOG_INTERVAL)
+                                                {
+                                                    log::warn!(
+                                                        "Recoverable emulation error: {err}"
+                                                    );
+                                                }
+                                                state.recoverable_error_count += 1;
+                                                err.effect
                                             }
                                             Err(err) => {
                                                 // Assume emulator is now invalid
@@ -597,10 +1150,49 @@ fn run_event_loop(event_loop: EventLoop<JgnesUserEvent>, config: JgnesWebConfig,
                                                 ));
                                                 break;
                                             }
+                                        };
+
+                                        if tick_effect == TickEffect::FrameRendered {
+                                            frames_rendered += 1;
+                                            state.frames_since_snapshot += 1;
+                                        }
+                                    }
+
+                                    if frames_rendered == frames {
+                                        elwt.set_control_flow(ControlFlow::WaitUntil(
+                                            web_time::Instant::now() + Duration::from_millis(1),
+                                        ));
+
+                                        // The queue threshold only matters when audio sync is
+                                        // pacing the emulator
+                                        let tuning_enabled = audio_sync_enabled
+                                            && state.current_config.audio_enabled
+                                            && !state.fast_forwarding;
+                                        state
+                                            .audio_player
+                                            .borrow_mut()
+                                            .update_buffer_tuning(tuning_enabled)
+                                            .unwrap();
+
+                                        if state.frames_since_snapshot
+                                            >= UNLOAD_SNAPSHOT_INTERVAL_FRAMES
+                                        {
+                                            state.frames_since_snapshot = 0;
+                                            update_unload_snapshot(
+                                                state.emulator.as_ref(),
+                                                &state.current_config,
+                                                &config,
+                                            );
                                         }
                                     }
                                 }
                                 None => {
+                                    state
+                                        .audio_player
+                                        .borrow_mut()
+                                        .update_buffer_tuning(false)
+                                        .unwrap();
+
                                     odd_frame = !odd_frame;
                                     if odd_frame {
                                         render_white_noise(&mut *state.renderer.borrow_mut())
@@ -624,6 +1216,32 @@ fn run_event_loop(event_loop: EventLoop<JgnesUserEvent>, config: JgnesWebConfig,
         .unwrap();
 }
 
+fn update_unload_snapshot(
+    emulator: Option<&WebEmulator>,
+    fields: &ConfigFields,
+    config: &JgnesWebConfig,
+) {
+    let (Some(emulator), Some(snapshot)) = (emulator, &mut *config.unload_snapshot.borrow_mut())
+    else {
+        return;
+    };
+
+    if !fields.persist_state_on_unload {
+        snapshot.state = None;
+        return;
+    }
+
+    let mut state_bytes = Vec::new();
+    match emulator.save_state(&mut state_bytes) {
+        Ok(()) => {
+            snapshot.state = Some(state_bytes);
+        }
+        Err(err) => {
+            log::error!("Error snapshotting emulator state: {err}");
+        }
+    }
+}
+
 fn render_white_noise<R: Renderer>(renderer: &mut R) -> Result<(), R::Err> {
     let frame_buffer = array::from_fn(|_| array::from_fn(|_| rand::random::<u8>() % 64));
     renderer.render_frame(&frame_buffer, ColorEmphasis::default())